@@ -0,0 +1,406 @@
+//! Workspace switch and window open and close animations
+//!
+//! Animations are sampled against the compositor clock right before each
+//! frame is drawn ([`StilchState::advance_animations`] runs from
+//! `pre_repaint`). A workspace switch slides the outgoing windows out and the
+//! incoming ones in by moving them in the space, clipped to their output so
+//! they don't show up on the next one. A new window fades and scales in
+//! through a [`WindowTransform`] its render elements pick up. A closing
+//! window is gone from the space, so a snapshot of its last buffer fades and
+//! shrinks out in its place, drawn from [`ClosingFrame`]s. All of it shows
+//! up as ordinary element movement and geometry changes to the damage
+//! tracker. With animations disabled nothing is ever started, so every
+//! change stays instant.
+
+use std::time::Duration;
+
+use smithay::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{element::memory::MemoryRenderBuffer, utils::with_renderer_surface_state},
+    },
+    desktop::space::SpaceElement,
+    output::Output,
+    reexports::wayland_server::protocol::{wl_shm, wl_surface::WlSurface},
+    utils::{Logical, Point, Rectangle, Size, Transform},
+    wayland::{shm::with_buffer_contents, single_pixel_buffer::get_single_pixel_buffer},
+};
+
+use crate::{
+    config::Easing,
+    shell::WindowElement,
+    state::{Backend, StilchState},
+    workspace::WorkspaceId,
+};
+
+/// Scale a new window starts from before growing to its full size, and a
+/// closing one shrinks to
+const OPEN_START_SCALE: f64 = 0.9;
+
+impl Easing {
+    /// Map linear progress in 0..=1 onto the curve
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+        }
+    }
+}
+
+/// A single timed transition
+#[derive(Debug, Clone, Copy)]
+pub struct Animation {
+    start: Duration,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl Animation {
+    pub fn new(start: Duration, duration_ms: u32, easing: Easing) -> Self {
+        Self {
+            start,
+            duration: Duration::from_millis(duration_ms as u64),
+            easing,
+        }
+    }
+
+    /// Eased progress from 0.0 to 1.0
+    pub fn progress(&self, now: Duration) -> f64 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        let elapsed = now.saturating_sub(self.start).as_secs_f64();
+        self.easing
+            .apply((elapsed / self.duration.as_secs_f64()).clamp(0.0, 1.0))
+    }
+
+    pub fn is_done(&self, now: Duration) -> bool {
+        now >= self.start + self.duration
+    }
+}
+
+/// Opacity and scale a window is drawn with while it animates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowTransform {
+    pub alpha: f32,
+    /// Scale around the window's center
+    pub scale: f64,
+}
+
+/// Windows of two workspaces sliding past each other
+#[derive(Debug)]
+struct WorkspaceSlide {
+    animation: Animation,
+    /// Workspace sliding in
+    workspace: WorkspaceId,
+    /// Horizontal distance the windows travel; negative moves them left
+    distance: i32,
+    /// The output the workspaces slide on, where the windows are cut off
+    region: Rectangle<i32, Logical>,
+    outgoing: Vec<(WindowElement, Point<i32, Logical>)>,
+    incoming: Vec<(WindowElement, Point<i32, Logical>)>,
+}
+
+/// Last buffer of a closed window and where it was shown
+#[derive(Debug)]
+struct ClosingWindow {
+    animation: Animation,
+    buffer: MemoryRenderBuffer,
+    geometry: Rectangle<i32, Logical>,
+}
+
+/// A closing window's snapshot as drawn in one frame on one output
+#[derive(Debug)]
+pub struct ClosingFrame {
+    pub buffer: MemoryRenderBuffer,
+    /// Relative to the output
+    pub location: Point<i32, Logical>,
+    pub size: Size<i32, Logical>,
+    pub alpha: f32,
+}
+
+/// Running animations
+#[derive(Debug, Default)]
+pub struct Animations {
+    slides: Vec<WorkspaceSlide>,
+    window_open: Vec<(WindowElement, Animation)>,
+    window_close: Vec<ClosingWindow>,
+}
+
+impl Animations {
+    pub fn is_running(&self) -> bool {
+        !self.slides.is_empty() || !self.window_open.is_empty() || !self.window_close.is_empty()
+    }
+}
+
+/// Copy of the buffer `surface` shows, so it can still be drawn once the
+/// client destroyed it. Only single-pixel and shared memory buffers can be
+/// read here; a window drawn on the GPU closes without a snapshot.
+fn snapshot_surface(surface: &WlSurface) -> Option<(MemoryRenderBuffer, Size<i32, Logical>)> {
+    with_renderer_surface_state(surface, |state| {
+        let surface_size = state.surface_size()?;
+        let buffer = state.buffer()?;
+        if let Ok(pixel) = get_single_pixel_buffer(buffer) {
+            let snapshot = MemoryRenderBuffer::from_slice(
+                &pixel.rgba8888(),
+                Fourcc::Abgr8888,
+                (1, 1),
+                1,
+                Transform::Normal,
+                None,
+            );
+            return Some((snapshot, surface_size));
+        }
+        with_buffer_contents(buffer, |ptr, len, data| {
+            let format = match data.format {
+                wl_shm::Format::Argb8888 => Fourcc::Argb8888,
+                wl_shm::Format::Xrgb8888 => Fourcc::Xrgb8888,
+                _ => return None,
+            };
+            let (width, height, stride) = (
+                data.width as usize,
+                data.height as usize,
+                data.stride as usize,
+            );
+            let start = data.offset as usize;
+            if width * 4 > stride || start + stride * height > len {
+                return None;
+            }
+            // SAFETY: smithay maps the pool for the duration of the closure,
+            // and the range was checked against its length
+            let pool = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let pixels: Vec<u8> = pool[start..start + stride * height]
+                .chunks(stride)
+                .flat_map(|row| &row[..width * 4])
+                .copied()
+                .collect();
+            let snapshot = MemoryRenderBuffer::from_slice(
+                &pixels,
+                format,
+                (data.width, data.height),
+                1,
+                Transform::Normal,
+                None,
+            );
+            Some((snapshot, surface_size))
+        })
+        .ok()
+        .flatten()
+    })
+    .flatten()
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    fn animation_now(&self) -> Duration {
+        self.clock.now().into()
+    }
+
+    /// Slide `outgoing` (already unmapped, with their last locations) out and
+    /// the now shown `workspace` in. `forward` brings it in from the right.
+    pub fn start_workspace_slide(
+        &mut self,
+        workspace: WorkspaceId,
+        outgoing: Vec<(WindowElement, Point<i32, Logical>)>,
+        forward: bool,
+    ) {
+        let config = &self.config.animations;
+        if !config.enabled || config.workspace_duration == 0 {
+            return;
+        }
+        let Some(ws) = self.workspace_manager.get(workspace) else {
+            return;
+        };
+        let width = ws.area.size.w;
+        let region = self
+            .workspace_manager
+            .find_workspace_location(workspace)
+            .and_then(|vo_id| self.virtual_output_manager.get(vo_id))
+            .map_or(ws.area, |vo| vo.logical_region());
+        let animation = Animation::new(
+            self.animation_now(),
+            config.workspace_duration,
+            config.easing,
+        );
+
+        let space = self.window_manager.space();
+        let incoming = ws
+            .windows
+            .iter()
+            .filter_map(|&id| self.window_registry().get(id))
+            .filter_map(|managed| {
+                let loc = space.element_location(&managed.element)?;
+                Some((managed.element.clone(), loc))
+            })
+            .collect();
+
+        self.animations.slides.push(WorkspaceSlide {
+            animation,
+            workspace,
+            distance: if forward { -width } else { width },
+            region,
+            outgoing,
+            incoming,
+        });
+        self.backend_data.request_render();
+    }
+
+    /// Fade and scale a newly mapped window in
+    pub fn start_window_open_animation(&mut self, element: &WindowElement) {
+        let config = &self.config.animations;
+        if !config.enabled || config.window_open_duration == 0 {
+            return;
+        }
+        let animation = Animation::new(
+            self.animation_now(),
+            config.window_open_duration,
+            config.easing,
+        );
+        element.set_render_transform(Some(WindowTransform {
+            alpha: 0.0,
+            scale: OPEN_START_SCALE,
+        }));
+        self.animations
+            .window_open
+            .push((element.clone(), animation));
+        self.backend_data.request_render();
+    }
+
+    /// Fade and shrink a window that is closing out from where it was,
+    /// before it leaves the space
+    pub fn start_window_close_animation(&mut self, element: &WindowElement) {
+        let config = &self.config.animations;
+        if !config.enabled || config.window_close_duration == 0 {
+            return;
+        }
+        let Some(location) = self.window_manager.space().element_location(element) else {
+            return;
+        };
+        let Some((buffer, size)) = element
+            .wl_surface()
+            .and_then(|surface| snapshot_surface(&surface))
+        else {
+            return;
+        };
+        // The surface starts where the bounding box does, not the geometry
+        let origin = location - SpaceElement::geometry(element).loc;
+        let animation = Animation::new(
+            self.animation_now(),
+            config.window_close_duration,
+            config.easing,
+        );
+        self.animations.window_close.push(ClosingWindow {
+            animation,
+            buffer,
+            geometry: Rectangle::new(origin, size),
+        });
+        self.backend_data.request_render();
+    }
+
+    /// Snapshots of closing windows on `output`, as they are to be drawn now
+    pub fn closing_window_frames(&self, output: &Output) -> Vec<ClosingFrame> {
+        let Some(output_geometry) = self.window_manager.space().output_geometry(output) else {
+            return Vec::new();
+        };
+        let now = self.animation_now();
+        self.animations
+            .window_close
+            .iter()
+            .filter(|closing| closing.geometry.overlaps(output_geometry))
+            .map(|closing| {
+                let progress = closing.animation.progress(now);
+                let scale = 1.0 - (1.0 - OPEN_START_SCALE) * progress;
+                let size = closing.geometry.size.to_f64().upscale(scale).to_i32_round();
+                let inset = (closing.geometry.size - size).downscale(2);
+                ClosingFrame {
+                    buffer: closing.buffer.clone(),
+                    location: closing.geometry.loc + Point::from((inset.w, inset.h))
+                        - output_geometry.loc,
+                    size,
+                    alpha: (1.0 - progress) as f32,
+                }
+            })
+            .collect()
+    }
+
+    /// Jump running workspace slides to their end, e.g. before another switch
+    pub fn finish_workspace_slides(&mut self) {
+        for slide in std::mem::take(&mut self.animations.slides) {
+            for (element, _) in &slide.outgoing {
+                element.set_render_clip(None);
+                self.window_manager.space_mut().unmap_elem(element);
+            }
+            for (element, _) in &slide.incoming {
+                element.set_render_clip(None);
+            }
+            self.apply_workspace_layout(slide.workspace);
+        }
+    }
+
+    /// Bring every running animation to the current time
+    pub fn advance_animations(&mut self) {
+        if !self.animations.is_running() {
+            return;
+        }
+        let now = self.animation_now();
+
+        self.animations.window_open.retain(|(element, animation)| {
+            if animation.is_done(now) {
+                element.set_render_transform(None);
+                return false;
+            }
+            let progress = animation.progress(now);
+            element.set_render_transform(Some(WindowTransform {
+                alpha: progress as f32,
+                scale: OPEN_START_SCALE + (1.0 - OPEN_START_SCALE) * progress,
+            }));
+            true
+        });
+
+        self.animations
+            .window_close
+            .retain(|closing| !closing.animation.is_done(now));
+
+        let (done, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.animations.slides)
+            .into_iter()
+            .partition(|slide| slide.animation.is_done(now));
+        for slide in &running {
+            let progress = slide.animation.progress(now);
+            let outgoing_offset = (slide.distance as f64 * progress).round() as i32;
+            let incoming_offset = outgoing_offset - slide.distance;
+
+            let windows = slide
+                .outgoing
+                .iter()
+                .map(|window| (window, outgoing_offset))
+                .chain(
+                    slide
+                        .incoming
+                        .iter()
+                        .map(|window| (window, incoming_offset)),
+                );
+            for ((element, loc), offset) in windows {
+                // Windows closed mid-slide must not come back
+                if self.window_registry().find_by_element(element).is_none() {
+                    continue;
+                }
+                let loc = Point::from((loc.x + offset, loc.y));
+                element.set_render_clip(Some(Rectangle::new(
+                    slide.region.loc - loc,
+                    slide.region.size,
+                )));
+                self.window_manager
+                    .space_mut()
+                    .map_element(element.clone(), loc, false);
+            }
+        }
+        self.animations.slides = done;
+        self.finish_workspace_slides();
+        self.animations.slides = running;
+
+        if self.animations.is_running() {
+            self.backend_data.request_render();
+        }
+    }
+}
@@ -4,6 +4,7 @@
 //! a command interface for programmatic testing.
 
 use crate::window::WindowId;
+use crate::workspace::layout::{ContainerLayout, LayoutNode, LayoutTree};
 use smithay::utils::{Logical, Point, Rectangle, Size};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -78,6 +79,74 @@ pub struct TabInfo {
     pub is_active: bool,
 }
 
+/// A layout tree node, for container markers and the snapshot legend
+#[derive(Debug, Clone)]
+pub enum AsciiNode {
+    Window {
+        id: WindowId,
+        focused: bool,
+    },
+    Container {
+        layout: ContainerLayout,
+        bounds: Rectangle<i32, Logical>,
+        children: Vec<AsciiNode>,
+    },
+}
+
+impl AsciiNode {
+    /// Mirror a workspace's layout tree without its placeholders, marking
+    /// `focused`
+    pub fn from_layout(tree: &LayoutTree, focused: Option<WindowId>) -> Option<Self> {
+        tree.root()
+            .and_then(|root| Self::from_node(root, tree, focused))
+    }
+
+    fn from_node(node: &LayoutNode, tree: &LayoutTree, focused: Option<WindowId>) -> Option<Self> {
+        match node {
+            LayoutNode::Window { id, .. } => {
+                (!tree.is_placeholder(*id)).then(|| AsciiNode::Window {
+                    id: *id,
+                    focused: focused == Some(*id),
+                })
+            }
+            LayoutNode::Container {
+                layout,
+                children,
+                geometry,
+                ..
+            } => {
+                let children: Vec<_> = children
+                    .iter()
+                    .filter_map(|child| Self::from_node(child, tree, focused))
+                    .collect();
+                (!children.is_empty()).then(|| AsciiNode::Container {
+                    layout: *layout,
+                    bounds: *geometry,
+                    children,
+                })
+            }
+        }
+    }
+}
+
+/// A workspace's layout tree as shown in a snapshot
+#[derive(Debug, Clone)]
+pub struct AsciiLayout {
+    pub workspace: String,
+    pub gap: i32,
+    pub root: AsciiNode,
+}
+
+/// Marker for a container type: H, V, T (tabbed) or S (stacked)
+fn layout_marker(layout: ContainerLayout) -> char {
+    match layout {
+        ContainerLayout::Horizontal => 'H',
+        ContainerLayout::Vertical => 'V',
+        ContainerLayout::Tabbed => 'T',
+        ContainerLayout::Stacked => 'S',
+    }
+}
+
 /// ASCII renderer backend
 pub struct AsciiBackend {
     /// Grid dimensions
@@ -372,6 +441,97 @@ impl AsciiBackend {
 
     /// Render the current state to ASCII
     pub fn render(&mut self) -> String {
+        self.draw_windows();
+        self.grid_string()
+    }
+
+    /// Render with each container's type marked on the top edge of its
+    /// area, nested containers left to right, and a legend below listing
+    /// the layout trees with their bounds in grid cells.
+    ///
+    /// Windows are drawn at their own geometry, so gaps wide enough to span
+    /// a cell stay blank between them.
+    pub fn render_with_layouts(&mut self, layouts: &[AsciiLayout]) -> String {
+        self.draw_windows();
+        for layout in layouts {
+            let mut markers = HashMap::new();
+            self.collect_markers(&layout.root, &mut markers);
+            for ((x, y), (x2, marker)) in markers {
+                for (i, ch) in marker.chars().enumerate() {
+                    if x + 1 + i < x2 {
+                        self.grid[y][x + 1 + i] = ch;
+                    }
+                }
+            }
+        }
+
+        let mut output = self.grid_string();
+        if !layouts.is_empty() {
+            output.push_str("Layout:\n");
+        }
+        for layout in layouts {
+            output.push_str(&format!(
+                "workspace {}: gap {}\n",
+                layout.workspace, layout.gap
+            ));
+            self.write_legend(&layout.root, 0, &mut output);
+        }
+        output
+    }
+
+    /// Markers of the containers starting at each grid cell, outermost
+    /// first, with how far right they may run
+    fn collect_markers(
+        &self,
+        node: &AsciiNode,
+        markers: &mut HashMap<(usize, usize), (usize, String)>,
+    ) {
+        if let AsciiNode::Container {
+            layout,
+            bounds,
+            children,
+        } = node
+        {
+            let (x1, y1, x2, _) = self.to_grid_rect(*bounds);
+            markers
+                .entry((x1, y1))
+                .or_insert_with(|| (x2, String::new()))
+                .1
+                .push(layout_marker(*layout));
+            for child in children {
+                self.collect_markers(child, markers);
+            }
+        }
+    }
+
+    fn write_legend(&self, node: &AsciiNode, depth: usize, output: &mut String) {
+        let indent = "  ".repeat(depth);
+        match node {
+            AsciiNode::Window { id, focused } => {
+                let focus = if *focused { " [F]" } else { "" };
+                output.push_str(&format!("{indent}#{}{focus}\n", id.get()));
+            }
+            AsciiNode::Container {
+                layout,
+                bounds,
+                children,
+            } => {
+                let (x1, y1, x2, y2) = self.to_grid_rect(*bounds);
+                output.push_str(&format!(
+                    "{indent}{} [{x1},{y1} {}x{}]\n",
+                    layout_marker(*layout),
+                    x2 - x1,
+                    y2 - y1
+                ));
+                for child in children {
+                    self.write_legend(child, depth + 1, output);
+                }
+            }
+        }
+    }
+
+    /// Clear the grid and draw every window on it
+    fn draw_windows(&mut self) {
         self.clear_grid();
 
         // Sort windows by z-order (floating windows last)
@@ -388,8 +548,10 @@ impl AsciiBackend {
         for window in &windows {
             self.draw_window(window);
         }
+    }
 
-        // Convert grid to string
+    /// The grid as text, one line per row
+    fn grid_string(&self) -> String {
         let mut output = String::new();
         for row in &self.grid {
             for &ch in row {
@@ -0,0 +1,231 @@
+//! Minimal wlr-gamma-control client for testing
+//!
+//! `gamma_control <output>` sets an inverted gamma ramp on the output, prints
+//! `SET` once the compositor has read it, and then keeps the control until
+//! killed. A control that fails prints `FAILED` and exits; with `--short`
+//! the ramps sent are one entry short, which the compositor rejects with a
+//! protocol error, printed as `ERROR`. With `--pipe` the ramps are sent as a
+//! socket the client never writes to, which must fail the control rather than
+//! stall the compositor.
+
+use std::{
+    fs::File,
+    io::{Seek, Write},
+    os::{fd::AsFd, unix::net::UnixStream},
+};
+
+use smithay_client_toolkit::reexports::{
+    client::{
+        globals::{registry_queue_init, GlobalListContents},
+        protocol::{
+            wl_output::{self, WlOutput},
+            wl_registry::WlRegistry,
+        },
+        Connection, Dispatch, Proxy, QueueHandle,
+    },
+    protocols_wlr::gamma_control::v1::client::{
+        zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1,
+        zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+    },
+};
+
+struct GammaControl {
+    /// Output names, as wl_output sends them
+    outputs: Vec<(WlOutput, Option<String>)>,
+    gamma_size: Option<u32>,
+    failed: bool,
+}
+
+fn main() {
+    let mut output_name = None;
+    let mut short = false;
+    let mut pipe = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--short" => short = true,
+            "--pipe" => pipe = true,
+            _ if output_name.is_none() => output_name = Some(arg),
+            _ => {
+                eprintln!("Unknown argument {arg}");
+                std::process::exit(1);
+            }
+        }
+    }
+    let Some(output_name) = output_name else {
+        eprintln!("Usage: gamma_control <output> [--short] [--pipe]");
+        std::process::exit(1);
+    };
+
+    let conn = match Connection::connect_to_env() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Failed to connect to Wayland: {e:?}");
+            std::process::exit(1);
+        }
+    };
+    let (globals, mut event_queue) = registry_queue_init(&conn).unwrap();
+    let qh = event_queue.handle();
+
+    let mut state = GammaControl {
+        outputs: Vec::new(),
+        gamma_size: None,
+        failed: false,
+    };
+    for global in globals.contents().clone_list() {
+        if global.interface == "wl_output" && global.version >= 4 {
+            let output = globals
+                .registry()
+                .bind::<WlOutput, _, _>(global.name, 4, &qh, ());
+            state.outputs.push((output, None));
+        }
+    }
+    let manager = match globals.bind::<ZwlrGammaControlManagerV1, _, _>(&qh, 1..=1, ()) {
+        Ok(manager) => manager,
+        Err(e) => {
+            eprintln!("No gamma control manager: {e}");
+            std::process::exit(1);
+        }
+    };
+    event_queue.roundtrip(&mut state).unwrap();
+
+    let Some(output) = state
+        .outputs
+        .iter()
+        .find(|(_, name)| name.as_deref() == Some(output_name.as_str()))
+        .map(|(output, _)| output.clone())
+    else {
+        eprintln!("No output named {output_name}");
+        std::process::exit(1);
+    };
+    let control = manager.get_gamma_control(&output, &qh, ());
+    while state.gamma_size.is_none() && !state.failed {
+        dispatch(&mut event_queue, &mut state);
+    }
+    if state.failed {
+        report("FAILED");
+        return;
+    }
+
+    // Kept open, so reading the other end would block for good
+    let _unwritten = if pipe {
+        let (sent, unwritten) = UnixStream::pair().unwrap();
+        control.set_gamma(sent.as_fd());
+        Some(unwritten)
+    } else {
+        let size = state.gamma_size.unwrap() as usize;
+        let mut entries = size * 3;
+        if short {
+            entries -= 1;
+        }
+        let ramps: Vec<u8> = (0..entries)
+            .flat_map(|i| {
+                let i = i % size;
+                let value = ((size - 1 - i) * u16::MAX as usize / (size - 1).max(1)) as u16;
+                value.to_ne_bytes()
+            })
+            .collect();
+        let path = std::env::temp_dir().join(format!("stilch-gamma-{}", std::process::id()));
+        let mut file = File::options()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+        file.write_all(&ramps).unwrap();
+        file.rewind().unwrap();
+        control.set_gamma(file.as_fd());
+        None
+    };
+
+    if let Err(e) = event_queue.roundtrip(&mut state) {
+        eprintln!("Gamma control error: {e}");
+        report("ERROR");
+        return;
+    }
+    if state.failed {
+        report("FAILED");
+        return;
+    }
+    report("SET");
+
+    while !state.failed {
+        dispatch(&mut event_queue, &mut state);
+    }
+    report("FAILED");
+}
+
+fn dispatch(
+    event_queue: &mut smithay_client_toolkit::reexports::client::EventQueue<GammaControl>,
+    state: &mut GammaControl,
+) {
+    if let Err(e) = event_queue.blocking_dispatch(state) {
+        eprintln!("Event dispatch failed: {e}");
+        report("ERROR");
+        std::process::exit(0);
+    }
+}
+
+fn report(outcome: &str) {
+    println!("{outcome}");
+    let _ = std::io::stdout().flush();
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for GammaControl {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlRegistry,
+        _event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlOutput, ()> for GammaControl {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            if let Some((_, output_name)) = state.outputs.iter_mut().find(|(o, _)| o == output) {
+                *output_name = Some(name);
+            }
+        }
+    }
+}
+
+impl Dispatch<ZwlrGammaControlManagerV1, ()> for GammaControl {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwlrGammaControlManagerV1,
+        _event: <ZwlrGammaControlManagerV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrGammaControlV1, ()> for GammaControl {
+    fn event(
+        state: &mut Self,
+        _control: &ZwlrGammaControlV1,
+        event: zwlr_gamma_control_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_gamma_control_v1::Event::GammaSize { size } => state.gamma_size = Some(size),
+            zwlr_gamma_control_v1::Event::Failed => state.failed = true,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Minimal wlr-layer-shell client for testing popups of layer surfaces
+//!
+//! Maps a 200x30 panel in the bottom right corner of its output and, once
+//! it is configured, opens a 300x200 popup below and right of it, where it
+//! can't fit, allowing the compositor to slide it. Prints every configure
+//! of the popup, relative to the panel, as `POPUP <x> <y> <width> <height>`
+//! and stays until killed.
+
+use std::io::Write;
+
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState, Surface},
+    output::{OutputHandler, OutputState},
+    reexports::{
+        client::{
+            delegate_noop,
+            globals::registry_queue_init,
+            protocol::{wl_buffer, wl_output, wl_surface},
+            Connection, QueueHandle,
+        },
+        protocols::{
+            wp::{
+                single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1,
+                viewporter::client::{wp_viewport::WpViewport, wp_viewporter::WpViewporter},
+            },
+            xdg::shell::client::xdg_positioner::{Anchor, ConstraintAdjustment, Gravity},
+        },
+    },
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    shell::{
+        wlr_layer::{
+            Anchor as LayerAnchor, Layer, LayerShell, LayerShellHandler, LayerSurface,
+            LayerSurfaceConfigure,
+        },
+        xdg::{
+            popup::{Popup, PopupConfigure, PopupHandler},
+            XdgPositioner, XdgShell,
+        },
+        WaylandSurface,
+    },
+};
+
+const PANEL_SIZE: (i32, i32) = (200, 30);
+const POPUP_SIZE: (i32, i32) = (300, 200);
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut event_queue) = registry_queue_init(&conn)?;
+    let qh = event_queue.handle();
+
+    let compositor = CompositorState::bind(&globals, &qh)?;
+    let layer_shell = LayerShell::bind(&globals, &qh)?;
+    let xdg_shell = XdgShell::bind(&globals, &qh)?;
+    let single_pixel: WpSinglePixelBufferManagerV1 = globals.bind(&qh, 1..=1, ())?;
+    let viewporter: WpViewporter = globals.bind(&qh, 1..=1, ())?;
+
+    let surface = compositor.create_surface(&qh);
+    let viewport = viewporter.get_viewport(&surface, &qh, ());
+    let layer =
+        layer_shell.create_layer_surface(&qh, surface, Layer::Top, Some("layer-popup"), None);
+    layer.set_anchor(LayerAnchor::BOTTOM | LayerAnchor::RIGHT);
+    layer.set_size(PANEL_SIZE.0 as u32, PANEL_SIZE.1 as u32);
+    layer.commit();
+
+    let mut client = LayerPopup {
+        registry_state: RegistryState::new(&globals),
+        output_state: OutputState::new(&globals, &qh),
+        compositor,
+        xdg_shell,
+        layer,
+        viewport,
+        single_pixel,
+        buffer: None,
+        popup: None,
+    };
+
+    loop {
+        event_queue.blocking_dispatch(&mut client)?;
+    }
+}
+
+struct LayerPopup {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    compositor: CompositorState,
+    xdg_shell: XdgShell,
+    layer: LayerSurface,
+    viewport: WpViewport,
+    single_pixel: WpSinglePixelBufferManagerV1,
+    buffer: Option<wl_buffer::WlBuffer>,
+    popup: Option<Popup>,
+}
+
+impl LayerPopup {
+    /// Show the panel as a single gray pixel stretched to its size
+    fn draw(&mut self, qh: &QueueHandle<Self>) {
+        let gray = 0x8080_8080;
+        let buffer = self
+            .single_pixel
+            .create_u32_rgba_buffer(gray, gray, gray, u32::MAX, qh, ());
+        let surface = self.layer.wl_surface();
+        self.viewport.set_destination(PANEL_SIZE.0, PANEL_SIZE.1);
+        surface.attach(Some(&buffer), 0, 0);
+        surface.damage_buffer(0, 0, 1, 1);
+        surface.commit();
+        if let Some(old) = self.buffer.replace(buffer) {
+            old.destroy();
+        }
+    }
+
+    /// Open the popup off the panel's bottom right corner, made a child of
+    /// the panel through the layer surface rather than an xdg parent
+    fn open_popup(&mut self, qh: &QueueHandle<Self>) -> Result<(), Box<dyn std::error::Error>> {
+        let positioner = XdgPositioner::new(&self.xdg_shell)?;
+        positioner.set_size(POPUP_SIZE.0, POPUP_SIZE.1);
+        positioner.set_anchor_rect(0, 0, PANEL_SIZE.0, PANEL_SIZE.1);
+        positioner.set_anchor(Anchor::BottomRight);
+        positioner.set_gravity(Gravity::BottomRight);
+        positioner
+            .set_constraint_adjustment(ConstraintAdjustment::SlideX | ConstraintAdjustment::SlideY);
+
+        let surface = Surface::new(&self.compositor, qh)?;
+        let popup = Popup::from_surface(None, &positioner, qh, surface, &self.xdg_shell)?;
+        self.layer.get_popup(popup.xdg_popup());
+        popup.wl_surface().commit();
+        self.popup = Some(popup);
+        Ok(())
+    }
+}
+
+impl LayerShellHandler for LayerPopup {
+    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface) {
+        std::process::exit(0);
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        _layer: &LayerSurface,
+        _configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        self.draw(qh);
+        if self.popup.is_none() {
+            if let Err(err) = self.open_popup(qh) {
+                eprintln!("Failed to open the popup: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+impl PopupHandler for LayerPopup {
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _popup: &Popup,
+        config: PopupConfigure,
+    ) {
+        println!(
+            "POPUP {} {} {} {}",
+            config.position.0, config.position.1, config.width, config.height
+        );
+        let _ = std::io::stdout().flush();
+    }
+
+    fn done(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _popup: &Popup) {
+        self.popup = None;
+    }
+}
+
+impl CompositorHandler for LayerPopup {
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_factor: i32,
+    ) {
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+    }
+
+    fn surface_enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+
+    fn surface_leave(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl OutputHandler for LayerPopup {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn update_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl ProvidesRegistryState for LayerPopup {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    registry_handlers!(OutputState);
+}
+
+smithay_client_toolkit::delegate_compositor!(LayerPopup);
+smithay_client_toolkit::delegate_output!(LayerPopup);
+smithay_client_toolkit::delegate_layer!(LayerPopup);
+smithay_client_toolkit::delegate_xdg_shell!(LayerPopup);
+smithay_client_toolkit::delegate_xdg_popup!(LayerPopup);
+smithay_client_toolkit::delegate_registry!(LayerPopup);
+delegate_noop!(LayerPopup: WpSinglePixelBufferManagerV1);
+delegate_noop!(LayerPopup: WpViewporter);
+delegate_noop!(LayerPopup: WpViewport);
+delegate_noop!(LayerPopup: ignore wl_buffer::WlBuffer);
@@ -0,0 +1,290 @@
+//! Minimal wlr-output-management client for testing
+//!
+//! Prints every head once the manager is done advertising them as
+//! `HEAD <name> enabled=<0|1>`. When asked to change anything, it then sends
+//! one configuration covering every head, prints its outcome as `SUCCEEDED`,
+//! `FAILED` or `CANCELLED` and exits.
+//!
+//! Options:
+//! - `--disable <name>`, `--enable <name>`: turn an output off or on
+//! - `--scale <name> <scale>`: set an output's scale
+//! - `--test`: only test the configuration instead of applying it
+
+use std::{collections::HashMap, io::Write};
+
+use smithay_client_toolkit::reexports::{
+    client::{
+        event_created_child,
+        globals::{registry_queue_init, GlobalListContents},
+        protocol::wl_registry::WlRegistry,
+        Connection, Dispatch, Proxy, QueueHandle,
+    },
+    protocols_wlr::output_management::v1::client::{
+        zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1,
+        zwlr_output_configuration_v1::{self, ZwlrOutputConfigurationV1},
+        zwlr_output_head_v1::{self, ZwlrOutputHeadV1},
+        zwlr_output_manager_v1::{self, ZwlrOutputManagerV1},
+        zwlr_output_mode_v1::ZwlrOutputModeV1,
+    },
+};
+
+#[derive(Debug, Default)]
+struct Head {
+    name: String,
+    enabled: bool,
+}
+
+#[derive(Debug, Default)]
+struct Changes {
+    enable: Vec<String>,
+    disable: Vec<String>,
+    scale: HashMap<String, f64>,
+    test_only: bool,
+}
+
+impl Changes {
+    fn is_empty(&self) -> bool {
+        self.enable.is_empty() && self.disable.is_empty() && self.scale.is_empty()
+    }
+}
+
+struct OutputConfig {
+    heads: HashMap<ZwlrOutputHeadV1, Head>,
+    changes: Changes,
+    /// Set once the configuration has an outcome, or there is nothing to change
+    finished: bool,
+    configuration_sent: bool,
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut changes = Changes::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--test" => changes.test_only = true,
+            "--enable" | "--disable" => {
+                let Some(name) = args.next() else {
+                    eprintln!("{arg} needs an output name");
+                    std::process::exit(1);
+                };
+                if arg == "--enable" {
+                    changes.enable.push(name);
+                } else {
+                    changes.disable.push(name);
+                }
+            }
+            "--scale" => {
+                let (Some(name), Some(scale)) = (args.next(), args.next()) else {
+                    eprintln!("--scale needs an output name and a scale");
+                    std::process::exit(1);
+                };
+                let Ok(scale) = scale.parse() else {
+                    eprintln!("Invalid scale {scale}");
+                    std::process::exit(1);
+                };
+                changes.scale.insert(name, scale);
+            }
+            _ => {
+                eprintln!("Unknown argument {arg}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let conn = match Connection::connect_to_env() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Failed to connect to Wayland: {e:?}");
+            std::process::exit(1);
+        }
+    };
+    let (globals, mut event_queue) = registry_queue_init(&conn).unwrap();
+    let qh = event_queue.handle();
+
+    let _manager = match globals.bind::<ZwlrOutputManagerV1, _, _>(&qh, 1..=3, ()) {
+        Ok(manager) => manager,
+        Err(e) => {
+            eprintln!("No output manager: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut config = OutputConfig {
+        heads: HashMap::new(),
+        changes,
+        finished: false,
+        configuration_sent: false,
+    };
+    while !config.finished {
+        if let Err(e) = event_queue.blocking_dispatch(&mut config) {
+            eprintln!("Event dispatch failed: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+impl OutputConfig {
+    fn print_heads(&self) {
+        let mut heads: Vec<&Head> = self.heads.values().collect();
+        heads.sort_by(|a, b| a.name.cmp(&b.name));
+        for head in heads {
+            println!("HEAD {} enabled={}", head.name, head.enabled as u8);
+        }
+        let _ = std::io::stdout().flush();
+    }
+
+    /// Configure every head: the ones asked for as asked, the rest as they are
+    fn send_configuration(
+        &mut self,
+        manager: &ZwlrOutputManagerV1,
+        serial: u32,
+        qh: &QueueHandle<Self>,
+    ) {
+        let configuration = manager.create_configuration(serial, qh, ());
+        for (head, info) in &self.heads {
+            let enable = if self.changes.disable.contains(&info.name) {
+                false
+            } else {
+                info.enabled || self.changes.enable.contains(&info.name)
+            };
+            if !enable {
+                configuration.disable_head(head);
+                continue;
+            }
+            let configuration_head = configuration.enable_head(head, qh, ());
+            if let Some(&scale) = self.changes.scale.get(&info.name) {
+                configuration_head.set_scale(scale);
+            }
+        }
+        if self.changes.test_only {
+            configuration.test();
+        } else {
+            configuration.apply();
+        }
+        self.configuration_sent = true;
+    }
+
+    fn report(&mut self, outcome: &str) {
+        println!("{outcome}");
+        let _ = std::io::stdout().flush();
+        self.finished = true;
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for OutputConfig {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlRegistry,
+        _event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrOutputManagerV1, ()> for OutputConfig {
+    fn event(
+        state: &mut Self,
+        manager: &ZwlrOutputManagerV1,
+        event: zwlr_output_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_manager_v1::Event::Head { head } => {
+                state.heads.insert(head, Head::default());
+            }
+            zwlr_output_manager_v1::Event::Done { serial } => {
+                if state.configuration_sent {
+                    return;
+                }
+                state.print_heads();
+                if state.changes.is_empty() {
+                    state.finished = true;
+                } else {
+                    state.send_configuration(manager, serial, qh);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(OutputConfig, ZwlrOutputManagerV1, [
+        zwlr_output_manager_v1::EVT_HEAD_OPCODE => (ZwlrOutputHeadV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrOutputHeadV1, ()> for OutputConfig {
+    fn event(
+        state: &mut Self,
+        head: &ZwlrOutputHeadV1,
+        event: zwlr_output_head_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_head_v1::Event::Name { name } => {
+                state.heads.entry(head.clone()).or_default().name = name;
+            }
+            zwlr_output_head_v1::Event::Enabled { enabled } => {
+                state.heads.entry(head.clone()).or_default().enabled = enabled != 0;
+            }
+            zwlr_output_head_v1::Event::Finished => {
+                state.heads.remove(head);
+                head.release();
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(OutputConfig, ZwlrOutputHeadV1, [
+        zwlr_output_head_v1::EVT_MODE_OPCODE => (ZwlrOutputModeV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrOutputModeV1, ()> for OutputConfig {
+    fn event(
+        _state: &mut Self,
+        _mode: &ZwlrOutputModeV1,
+        _event: <ZwlrOutputModeV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrOutputConfigurationV1, ()> for OutputConfig {
+    fn event(
+        state: &mut Self,
+        configuration: &ZwlrOutputConfigurationV1,
+        event: zwlr_output_configuration_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let outcome = match event {
+            zwlr_output_configuration_v1::Event::Succeeded => "SUCCEEDED",
+            zwlr_output_configuration_v1::Event::Failed => "FAILED",
+            zwlr_output_configuration_v1::Event::Cancelled => "CANCELLED",
+            _ => return,
+        };
+        configuration.destroy();
+        state.report(outcome);
+    }
+}
+
+impl Dispatch<ZwlrOutputConfigurationHeadV1, ()> for OutputConfig {
+    fn event(
+        _state: &mut Self,
+        _head: &ZwlrOutputConfigurationHeadV1,
+        _event: <ZwlrOutputConfigurationHeadV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
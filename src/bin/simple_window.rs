@@ -2,17 +2,49 @@
 //!
 //! This creates a window with a solid color background and keeps it alive
 //! until killed. Perfect for integration testing.
+//!
+//! Options for selection tests:
+//! - `--set-primary <text>`: offer `text` as the primary selection once focused
+//! - `--paste-primary`: print every received primary selection to stdout as
+//!   `PRIMARY_SELECTION: <text>`
+//!
+//! Other options:
+//! - `--fullscreen-on <output>`: once mapped, ask to go fullscreen on the
+//!   output with that name
+//! - `--fullscreen-at-start <output>`: the same, but before the initial
+//!   commit
+//! - `--fixed-size`: set equal minimum and maximum sizes
+//! - `--dialog <title>`: once mapped, open a second window with that title
+//!   whose parent is the first one
+//! - `--commit-storm <n>`: once mapped, commit damage `n` times without
+//!   waiting for frames, then print `COMMIT_STORM_DONE` once the compositor
+//!   has handled them
+
+use std::io::{Read, Write};
 
 use smithay_client_toolkit::{
     compositor::{CompositorHandler, CompositorState},
+    data_device_manager::WritePipe,
     output::{OutputHandler, OutputState},
-    reexports::client::{
-        globals::registry_queue_init,
-        protocol::{wl_output, wl_shm, wl_surface},
-        Connection, QueueHandle,
+    primary_selection::{
+        device::{PrimarySelectionDevice, PrimarySelectionDeviceHandler},
+        selection::{PrimarySelectionSource, PrimarySelectionSourceHandler},
+        PrimarySelectionManagerState,
+    },
+    reexports::{
+        client::{
+            globals::registry_queue_init,
+            protocol::{wl_output, wl_seat, wl_shm, wl_surface},
+            Connection, QueueHandle,
+        },
+        protocols::wp::primary_selection::zv1::client::{
+            zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+            zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1,
+        },
     },
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
+    seat::{Capability, SeatHandler, SeatState},
     shell::{
         xdg::{
             window::{Window, WindowConfigure, WindowDecorations, WindowHandler},
@@ -23,12 +55,41 @@ use smithay_client_toolkit::{
     shm::{slot::SlotPool, Shm, ShmHandler},
 };
 
+const TEXT_MIME_TYPE: &str = "text/plain;charset=utf-8";
+
 fn main() {
+    // Split selection options from the positional title and color
+    let mut args = std::env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut set_primary = None;
+    let mut paste_primary = false;
+    let mut retitle = None;
+    let mut fullscreen_on = None;
+    let mut fullscreen_at_start = None;
+    let mut fixed_size = false;
+    let mut dialog_title = None;
+    let mut commit_storm = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--set-primary" => set_primary = args.next(),
+            "--paste-primary" => paste_primary = true,
+            "--retitle" => retitle = args.next(),
+            "--fullscreen-on" => fullscreen_on = args.next(),
+            "--fullscreen-at-start" => fullscreen_at_start = args.next(),
+            "--fixed-size" => fixed_size = true,
+            "--dialog" => dialog_title = args.next(),
+            "--commit-storm" => commit_storm = args.next().and_then(|n| n.parse().ok()),
+            _ => positional.push(arg),
+        }
+    }
+
     // Get window title from args or use default
-    let args: Vec<String> = std::env::args().collect();
-    let title = args.get(1).unwrap_or(&"Test Window".to_string()).clone();
-    let color = args
-        .get(2)
+    let title = positional
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "Test Window".to_string());
+    let color = positional
+        .get(1)
         .and_then(|s| match s.as_str() {
             "red" => Some(0xFFFF0000),
             "green" => Some(0xFF00FF00),
@@ -58,18 +119,45 @@ fn main() {
     window.set_app_id("simple-window".to_string());
 
     // Configure window size
-    window.set_min_size(Some((256, 256)));
-
-    // Request initial configure
-    window.commit();
+    if fixed_size {
+        window.set_min_size(Some((400, 300)));
+        window.set_max_size(Some((400, 300)));
+    } else {
+        window.set_min_size(Some((256, 256)));
+    }
 
     let pool = SlotPool::new(800 * 600 * 4, &shm).unwrap();
 
+    let seat_state = SeatState::new(&globals, &qh);
+    let primary_selection_manager = if set_primary.is_some() || paste_primary {
+        PrimarySelectionManagerState::bind(&globals, &qh).ok()
+    } else {
+        None
+    };
+    let primary_selection_device = primary_selection_manager
+        .as_ref()
+        .zip(seat_state.seats().next())
+        .map(|(manager, seat)| manager.get_selection_device(&qh, &seat));
+
     let mut simple_window = SimpleWindow {
         registry_state: RegistryState::new(&globals),
         output_state: OutputState::new(&globals, &qh),
+        seat_state,
         shm,
 
+        primary_selection_manager,
+        primary_selection_device,
+        primary_source: None,
+        set_primary,
+        paste_primary,
+        retitle,
+        fullscreen_on,
+        xdg_shell,
+        compositor,
+        dialog_title,
+        dialog: None,
+        commit_storm,
+
         window,
         pool,
         color,
@@ -78,12 +166,37 @@ fn main() {
         height: 600,
     };
 
+    // Output names are only known once their events are in
+    if let Some(name) = fullscreen_at_start {
+        event_queue.roundtrip(&mut simple_window).unwrap();
+        match simple_window.output_named(&name) {
+            Some(output) => simple_window.window.set_fullscreen(Some(&output)),
+            None => eprintln!("No output named {name}"),
+        }
+    }
+
+    // Request initial configure
+    simple_window.window.commit();
+
     // Initial draw
     simple_window.draw(&qh);
 
     loop {
         match event_queue.blocking_dispatch(&mut simple_window) {
-            Ok(_) => {}
+            Ok(_) => {
+                if simple_window.configured {
+                    if let Some(commits) = simple_window.commit_storm.take() {
+                        let surface = simple_window.window.wl_surface();
+                        for _ in 0..commits {
+                            surface.damage_buffer(0, 0, 1, 1);
+                            surface.commit();
+                        }
+                        event_queue.roundtrip(&mut simple_window).unwrap();
+                        println!("COMMIT_STORM_DONE");
+                        let _ = std::io::stdout().flush();
+                    }
+                }
+            }
             Err(e) => {
                 // Check if it's a broken pipe error (compositor gone)
                 let err_str = format!("{e:?}");
@@ -101,8 +214,27 @@ fn main() {
 struct SimpleWindow {
     registry_state: RegistryState,
     output_state: OutputState,
+    seat_state: SeatState,
     shm: Shm,
 
+    primary_selection_manager: Option<PrimarySelectionManagerState>,
+    primary_selection_device: Option<PrimarySelectionDevice>,
+    primary_source: Option<PrimarySelectionSource>,
+    /// Text to offer as primary selection
+    set_primary: Option<String>,
+    paste_primary: bool,
+    /// Title to switch to once the window is first activated
+    retitle: Option<String>,
+    /// Output to ask to go fullscreen on once mapped
+    fullscreen_on: Option<String>,
+    xdg_shell: XdgShell,
+    compositor: CompositorState,
+    /// Title of the dialog to open once mapped
+    dialog_title: Option<String>,
+    dialog: Option<Window>,
+    /// Number of commits to send at once after mapping
+    commit_storm: Option<u32>,
+
     window: Window,
     pool: SlotPool,
     color: u32,
@@ -112,13 +244,28 @@ struct SimpleWindow {
 }
 
 impl SimpleWindow {
+    /// The output the compositor named `name`, if there is one
+    fn output_named(&self, name: &str) -> Option<wl_output::WlOutput> {
+        self.output_state.outputs().find(|output| {
+            self.output_state
+                .info(output)
+                .is_some_and(|info| info.name.as_deref() == Some(name))
+        })
+    }
+
     fn draw(&mut self, _qh: &QueueHandle<Self>) {
+        let surface = self.window.wl_surface().clone();
+        self.fill(&surface, self.width, self.height);
+    }
+
+    /// Attach a buffer of the window color to `surface` and commit it
+    fn fill(&mut self, surface: &wl_surface::WlSurface, width: u32, height: u32) {
         let (buffer, canvas) = self
             .pool
             .create_buffer(
-                self.width as i32,
-                self.height as i32,
-                (self.width * 4) as i32,
+                width as i32,
+                height as i32,
+                (width * 4) as i32,
                 wl_shm::Format::Argb8888,
             )
             .expect("create buffer");
@@ -131,18 +278,61 @@ impl SimpleWindow {
             pixel[3] = ((self.color >> 24) & 0xFF) as u8; // A
         }
 
-        self.window
-            .wl_surface()
-            .attach(Some(buffer.wl_buffer()), 0, 0);
-        self.window
-            .wl_surface()
-            .damage_buffer(0, 0, self.width as i32, self.height as i32);
-        self.window.wl_surface().commit();
+        surface.attach(Some(buffer.wl_buffer()), 0, 0);
+        surface.damage_buffer(0, 0, width as i32, height as i32);
+        surface.commit();
 
         // Buffer will be released when it goes out of scope
     }
 }
 
+impl SimpleWindow {
+    /// Offer the configured primary selection text.
+    ///
+    /// The compositor only accepts selections from the focused client, and
+    /// ignores the serial, so this waits for the first selection event sent
+    /// on keyboard focus.
+    fn offer_primary(&mut self, qh: &QueueHandle<Self>) {
+        let (Some(manager), Some(device)) = (
+            self.primary_selection_manager.as_ref(),
+            self.primary_selection_device.as_ref(),
+        ) else {
+            return;
+        };
+        if self.set_primary.is_none() || self.primary_source.is_some() {
+            return;
+        }
+
+        let source = manager.create_selection_source(qh, [TEXT_MIME_TYPE]);
+        source.set_selection(device, 0);
+        self.primary_source = Some(source);
+    }
+
+    /// Print the current primary selection if it is offered as text
+    fn print_primary(&mut self, conn: &Connection) {
+        let Some(offer) = self
+            .primary_selection_device
+            .as_ref()
+            .and_then(|device| device.data().selection_offer())
+        else {
+            return;
+        };
+        if !offer.with_mime_types(|mime_types| mime_types.iter().any(|m| m == TEXT_MIME_TYPE)) {
+            return;
+        }
+
+        let Ok(mut pipe) = offer.receive(TEXT_MIME_TYPE.to_string()) else {
+            return;
+        };
+        let _ = conn.flush();
+        let mut text = String::new();
+        if pipe.read_to_string(&mut text).is_ok() {
+            println!("PRIMARY_SELECTION: {text}");
+            let _ = std::io::stdout().flush();
+        }
+    }
+}
+
 impl CompositorHandler for SimpleWindow {
     fn scale_factor_changed(
         &mut self,
@@ -230,10 +420,19 @@ impl WindowHandler for SimpleWindow {
         &mut self,
         _conn: &Connection,
         qh: &QueueHandle<Self>,
-        _window: &Window,
+        window: &Window,
         configure: WindowConfigure,
         _serial: u32,
     ) {
+        if self.dialog.as_ref() == Some(window) {
+            let (width, height) = match configure.new_size {
+                (Some(w), Some(h)) => (w.get(), h.get()),
+                _ => (300, 200),
+            };
+            self.fill(window.wl_surface(), width, height);
+            return;
+        }
+
         if let (Some(w), Some(h)) = configure.new_size {
             self.width = w.get();
             self.height = h.get();
@@ -241,6 +440,106 @@ impl WindowHandler for SimpleWindow {
 
         self.configured = true;
         self.draw(qh);
+
+        if let Some(name) = self.fullscreen_on.take() {
+            match self.output_named(&name) {
+                Some(output) => self.window.set_fullscreen(Some(&output)),
+                None => eprintln!("No output named {name}"),
+            }
+        }
+
+        if let Some(title) = self.dialog_title.take() {
+            let surface = self.compositor.create_surface(qh);
+            let dialog = self
+                .xdg_shell
+                .create_window(surface, WindowDecorations::None, qh);
+            dialog.set_title(title);
+            dialog.set_app_id("simple-window".to_string());
+            dialog.set_parent(Some(&self.window));
+            dialog.commit();
+            self.dialog = Some(dialog);
+        }
+
+        if configure.is_activated() {
+            if let Some(title) = self.retitle.take() {
+                self.window.set_title(title);
+                self.window.commit();
+            }
+        }
+    }
+}
+
+impl SeatHandler for SimpleWindow {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+
+    fn new_seat(&mut self, _conn: &Connection, qh: &QueueHandle<Self>, seat: wl_seat::WlSeat) {
+        if self.primary_selection_device.is_none() {
+            if let Some(manager) = self.primary_selection_manager.as_ref() {
+                self.primary_selection_device = Some(manager.get_selection_device(qh, &seat));
+            }
+        }
+    }
+
+    fn new_capability(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _seat: wl_seat::WlSeat,
+        _capability: Capability,
+    ) {
+    }
+
+    fn remove_capability(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _seat: wl_seat::WlSeat,
+        _capability: Capability,
+    ) {
+    }
+
+    fn remove_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat) {
+    }
+}
+
+impl PrimarySelectionDeviceHandler for SimpleWindow {
+    fn selection(
+        &mut self,
+        conn: &Connection,
+        qh: &QueueHandle<Self>,
+        _primary_selection_device: &ZwpPrimarySelectionDeviceV1,
+    ) {
+        self.offer_primary(qh);
+        if self.paste_primary {
+            self.print_primary(conn);
+        }
+    }
+}
+
+impl PrimarySelectionSourceHandler for SimpleWindow {
+    fn send_request(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _source: &ZwpPrimarySelectionSourceV1,
+        mime: String,
+        mut write_pipe: WritePipe,
+    ) {
+        if mime == TEXT_MIME_TYPE {
+            if let Some(text) = self.set_primary.as_ref() {
+                let _ = write_pipe.write_all(text.as_bytes());
+            }
+        }
+    }
+
+    fn cancelled(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _source: &ZwpPrimarySelectionSourceV1,
+    ) {
     }
 }
 
@@ -255,7 +554,7 @@ impl ProvidesRegistryState for SimpleWindow {
         &mut self.registry_state
     }
 
-    registry_handlers!(OutputState);
+    registry_handlers!(OutputState, SeatState);
 }
 
 smithay_client_toolkit::delegate_compositor!(SimpleWindow);
@@ -264,3 +563,5 @@ smithay_client_toolkit::delegate_shm!(SimpleWindow);
 smithay_client_toolkit::delegate_xdg_shell!(SimpleWindow);
 smithay_client_toolkit::delegate_xdg_window!(SimpleWindow);
 smithay_client_toolkit::delegate_registry!(SimpleWindow);
+smithay_client_toolkit::delegate_seat!(SimpleWindow);
+smithay_client_toolkit::delegate_primary_selection!(SimpleWindow);
@@ -0,0 +1,272 @@
+//! Minimal wlr-foreign-toplevel-management client for testing
+//!
+//! Prints every toplevel handle update to stdout as
+//! `TOPLEVEL <title> app_id=<app_id> states=<state,...>` and closed handles
+//! as `CLOSED <title>`, until killed.
+//!
+//! Options send one request to the handle titled `<title>` once it is
+//! advertised:
+//! - `--minimize <title>`, `--unminimize <title>`
+//! - `--fullscreen <title>`, with `--on <output>` to ask for that output
+//! - `--activate <title>`
+
+use std::{collections::HashMap, io::Write};
+
+use smithay_client_toolkit::reexports::{
+    client::{
+        event_created_child,
+        globals::{registry_queue_init, GlobalListContents},
+        protocol::{
+            wl_output::{self, WlOutput},
+            wl_registry::WlRegistry,
+            wl_seat::WlSeat,
+        },
+        Connection, Dispatch, Proxy, QueueHandle,
+    },
+    protocols_wlr::foreign_toplevel::v1::client::{
+        zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+        zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Request {
+    Minimize,
+    Unminimize,
+    Fullscreen,
+    Activate,
+}
+
+#[derive(Debug, Default)]
+struct Toplevel {
+    title: String,
+    app_id: String,
+    states: Vec<&'static str>,
+}
+
+struct ToplevelList {
+    toplevels: HashMap<ZwlrForeignToplevelHandleV1, Toplevel>,
+    outputs: HashMap<WlOutput, String>,
+    seat: Option<WlSeat>,
+    /// Request still to send, with the title of its handle
+    request: Option<(Request, String)>,
+    /// Output to ask for with `--fullscreen`
+    fullscreen_on: Option<String>,
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut request = None;
+    let mut fullscreen_on = None;
+    while let Some(arg) = args.next() {
+        let kind = match arg.as_str() {
+            "--minimize" => Request::Minimize,
+            "--unminimize" => Request::Unminimize,
+            "--fullscreen" => Request::Fullscreen,
+            "--activate" => Request::Activate,
+            "--on" => {
+                fullscreen_on = args.next();
+                continue;
+            }
+            _ => {
+                eprintln!("Unknown argument {arg}");
+                std::process::exit(1);
+            }
+        };
+        match args.next() {
+            Some(title) => request = Some((kind, title)),
+            None => {
+                eprintln!("{arg} needs a window title");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let conn = match Connection::connect_to_env() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Failed to connect to Wayland: {e:?}");
+            std::process::exit(1);
+        }
+    };
+    let (globals, mut event_queue) = registry_queue_init(&conn).unwrap();
+    let qh = event_queue.handle();
+
+    let mut list = ToplevelList {
+        toplevels: HashMap::new(),
+        outputs: HashMap::new(),
+        seat: None,
+        request,
+        fullscreen_on,
+    };
+
+    // Output names come with wl_output version 4
+    for global in globals.contents().clone_list() {
+        match global.interface.as_str() {
+            "wl_output" if global.version >= 4 => {
+                globals
+                    .registry()
+                    .bind::<WlOutput, _, _>(global.name, 4, &qh, ());
+            }
+            "wl_seat" if list.seat.is_none() => {
+                list.seat = Some(globals.registry().bind::<WlSeat, _, _>(
+                    global.name,
+                    global.version.min(7),
+                    &qh,
+                    (),
+                ));
+            }
+            _ => {}
+        }
+    }
+    let _manager = match globals.bind::<ZwlrForeignToplevelManagerV1, _, _>(&qh, 1..=3, ()) {
+        Ok(manager) => manager,
+        Err(e) => {
+            eprintln!("No foreign toplevel manager: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    loop {
+        if let Err(e) = event_queue.blocking_dispatch(&mut list) {
+            eprintln!("Event dispatch failed: {e}");
+            break;
+        }
+    }
+}
+
+impl ToplevelList {
+    /// Send the pending request if it is for this handle
+    fn send_request(&mut self, handle: &ZwlrForeignToplevelHandleV1) {
+        let Some((request, title)) = self.request.as_ref() else {
+            return;
+        };
+        if self.toplevels.get(handle).map(|t| &t.title) != Some(title) {
+            return;
+        }
+
+        match request {
+            Request::Minimize => handle.set_minimized(),
+            Request::Unminimize => handle.unset_minimized(),
+            Request::Fullscreen => {
+                let output = self.fullscreen_on.as_ref().and_then(|name| {
+                    self.outputs
+                        .iter()
+                        .find(|(_, output_name)| *output_name == name)
+                        .map(|(output, _)| output)
+                });
+                handle.set_fullscreen(output);
+            }
+            Request::Activate => match self.seat.as_ref() {
+                Some(seat) => handle.activate(seat),
+                None => eprintln!("No seat to activate with"),
+            },
+        }
+        self.request = None;
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for ToplevelList {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlRegistry,
+        _event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlOutput, ()> for ToplevelList {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            state.outputs.insert(output.clone(), name);
+        }
+    }
+}
+
+impl Dispatch<WlSeat, ()> for ToplevelList {
+    fn event(
+        _state: &mut Self,
+        _seat: &WlSeat,
+        _event: <WlSeat as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for ToplevelList {
+    fn event(
+        state: &mut Self,
+        _manager: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } = event {
+            state.toplevels.insert(toplevel, Toplevel::default());
+        }
+    }
+
+    event_created_child!(ToplevelList, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for ToplevelList {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let toplevel = state.toplevels.entry(handle.clone()).or_default();
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => toplevel.title = title,
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => toplevel.app_id = app_id,
+            zwlr_foreign_toplevel_handle_v1::Event::State { state: states } => {
+                toplevel.states = states
+                    .chunks_exact(4)
+                    .filter_map(|bytes| bytes.try_into().ok().map(u32::from_ne_bytes))
+                    .map(|state| match state {
+                        0 => "maximized",
+                        1 => "minimized",
+                        2 => "activated",
+                        3 => "fullscreen",
+                        _ => "unknown",
+                    })
+                    .collect();
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Done => {
+                println!(
+                    "TOPLEVEL {} app_id={} states={}",
+                    toplevel.title,
+                    toplevel.app_id,
+                    toplevel.states.join(",")
+                );
+                let _ = std::io::stdout().flush();
+                state.send_request(handle);
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                println!("CLOSED {}", toplevel.title);
+                let _ = std::io::stdout().flush();
+                state.toplevels.remove(handle);
+                handle.destroy();
+            }
+            _ => {}
+        }
+    }
+}
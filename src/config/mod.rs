@@ -1,6 +1,10 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use smithay::input::keyboard::{Keysym, ModifiersState};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::window::WindowCriteria;
 
 pub mod parser;
 #[cfg(test)]
@@ -12,6 +16,8 @@ pub struct Config {
     pub variables: HashMap<String, String>,
     /// Keybindings
     pub keybindings: Vec<Keybinding>,
+    /// `bindcode` keybindings by raw keycode, matched before `keybindings`
+    pub keycode_bindings: HashMap<u32, Vec<Keybinding>>,
     /// Output configurations
     pub outputs: Vec<OutputConfig>,
     /// Virtual output configurations
@@ -24,17 +30,130 @@ pub struct Config {
     pub border: BorderConfig,
     /// Font settings
     pub font: String,
+    /// Text shown in tabs and title bars, with %title, %app_id, %class and
+    /// %workspace placeholders
+    pub title_format: String,
     /// Startup commands (exec without keybinding)
     pub startup_commands: Vec<String>,
     /// Input device configurations
     pub input_configs: Vec<InputConfig>,
+    /// Per-window rules (`for_window [criteria] command`)
+    pub window_rules: Vec<WindowRule>,
+    /// Workspaces new windows open on (`assign [criteria] workspace N`)
+    pub assignments: Vec<Assignment>,
+    /// New windows that don't take focus (`no_focus [criteria]`)
+    pub no_focus_rules: Vec<NoFocusRule>,
+    /// Clipboard persistence settings
+    pub clipboard: ClipboardConfig,
+    /// Touchpad gesture bindings and thresholds
+    pub gestures: GestureConfig,
+    /// Modifier for dragging and resizing floating windows, None if disabled
+    pub floating_modifier: Option<FloatingModifier>,
+    /// Workspace switch and window open animations
+    pub animations: AnimationConfig,
+    /// Targeting the current workspace goes to the previous one instead
+    pub workspace_auto_back_and_forth: bool,
+    /// Where the pointer goes after switching workspaces
+    pub workspace_switch_warp: WorkspaceSwitchWarp,
+    /// What switching to a workspace that belongs to another output does
+    pub workspace_switch_mode: WorkspaceSwitchMode,
+    /// What happens to a window fullscreen on a physical output that is
+    /// unplugged
+    pub fullscreen_output_removed: FullscreenOutputRemoved,
+    /// Compositor-drawn status strip on every virtual output, None if disabled
+    pub status_bar: Option<StatusBarConfig>,
+    /// Size and colors of the tab and title bars of tabbed and stacked containers
+    pub tab_bar: TabBarConfig,
+    /// Hide the cursor on key presses until the pointer moves
+    pub hide_cursor_when_typing: bool,
+    /// Hide the cursor after this many milliseconds without pointer activity
+    pub hide_cursor_timeout: Option<u32>,
+    /// Longest gap in milliseconds between presses of a double or triple click
+    pub double_click_time: u32,
+    /// Chord that toggles a client's keyboard shortcuts inhibitor, None if disabled
+    pub shortcuts_inhibit_escape: Option<KeyChord>,
+    /// DRM device to render and import client buffers with, overriding the
+    /// seat's boot GPU (udev backend only)
+    pub render_node: Option<String>,
+    /// Most frames per second drawn on an output however often clients
+    /// commit, None to follow the refresh rate alone
+    pub max_render_fps: Option<u32>,
+    /// Where new tiled windows go among the children of the container
+    /// they join
+    pub new_window_position: NewWindowPosition,
+    /// File remembering which output each workspace was on across
+    /// restarts, None if not kept
+    pub workspace_state_file: Option<String>,
+    /// Open floating windows where the last window of the same app was
+    /// moved or resized to
+    pub remember_window_geometry: bool,
+    /// What an xdg-activation request from a window without focus does
+    pub focus_on_window_activation: FocusOnWindowActivation,
+    /// How long in milliseconds the pointer must rest on a window before
+    /// focus_follows_mouse focuses it, 0 for right away
+    pub focus_follows_mouse_delay_ms: u32,
+    /// File the config was loaded from, for reloading it
+    pub path: Option<PathBuf>,
+    /// Scale of X11 windows, which all share one XWayland client scale
+    pub xwayland_scale: XWaylandScale,
+    /// Whether XWayland is started, `xwayland enable|disable`. `lazy` is
+    /// taken as `enable`, XWayland can't be started on demand.
+    pub xwayland: bool,
+}
+
+/// The keybinding tables of a config, which `reload bindings` re-reads alone
+#[derive(Debug, Clone, Default)]
+pub struct Bindings {
+    pub keybindings: Vec<Keybinding>,
+    pub keycode_bindings: HashMap<u32, Vec<Keybinding>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Keybinding {
     pub modifiers: ModifiersState,
-    pub key: Keysym,
+    pub key: BindingKey,
+    /// Other keys of a chord, which must already be held when `key` is pressed
+    pub held: Vec<BindingKey>,
     pub command: Command,
+    /// Fire on key release instead of press (`--release`)
+    pub release: bool,
+    /// Fire again while the key is held, unless bound with `--no-repeat`
+    pub repeat: bool,
+    /// Also fire while the session is locked (`--locked`). Kept for when
+    /// stilch gets a session lock; until then it changes nothing.
+    pub locked: bool,
+    /// Mouse bindings also fire over window contents, not just decorations (`--whole-window`)
+    pub whole_window: bool,
+    /// The `mode "<name>" { ... }` block the binding is in, None at the top
+    /// level
+    pub mode: Option<String>,
+}
+
+/// A key with the modifiers that must be held for it
+#[derive(Debug, Clone, Copy)]
+pub struct KeyChord {
+    pub modifiers: ModifiersState,
+    pub key: Keysym,
+}
+
+/// `floating_modifier <mod> [normal|inverse]`
+#[derive(Debug, Clone, Copy)]
+pub struct FloatingModifier {
+    pub modifiers: ModifiersState,
+    /// Swap the buttons: left drag resizes, right drag moves
+    pub inverse: bool,
+}
+
+/// What a binding is triggered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKey {
+    Key(Keysym),
+    /// Raw keycode from `bindcode`, whatever the keyboard layout
+    Code(u32),
+    /// Mouse button, as a Linux input event code
+    Button(u32),
+    /// One notch of the scroll wheel
+    Wheel(Direction),
 }
 
 #[derive(Debug, Clone)]
@@ -45,10 +164,14 @@ pub enum Command {
     Kill,
     /// Reload configuration
     Reload,
+    /// Re-read only the keybindings (`reload bindings`)
+    ReloadBindings,
     /// Exit compositor
     Exit,
     /// Focus window in direction
     Focus(Direction),
+    /// Focus an output (`focus output <direction|primary|name>`)
+    FocusOutput(OutputTarget),
     /// Move window in direction
     Move(Direction),
     /// Switch to workspace
@@ -65,20 +188,96 @@ pub enum Command {
     FullscreenVirtualOutput,
     /// Physical output fullscreen toggle
     FullscreenPhysicalOutput,
+    /// Fullscreen on or off regardless of the current state
+    /// (`fullscreen enable|disable [container|virtual|physical]`)
+    SetFullscreen {
+        enable: bool,
+        mode: crate::window::FullscreenMode,
+    },
     /// Floating toggle
     FloatingToggle,
-    /// Focus mode toggle (tiling/floating)
-    FocusModeToggle,
-    /// Resize mode
-    ResizeMode,
+    /// Make the window floating
+    FloatingEnable,
+    /// Make the window tiled
+    FloatingDisable,
+    /// `focus_follows_mouse enable|disable`: as a `for_window` command,
+    /// override focus_follows_mouse for matching windows; bound to a key,
+    /// switch it everywhere until changed again
+    FocusFollowsMouse(bool),
+    /// `focus_follows_mouse toggle`: switch it everywhere until a reload
+    ToggleFocusFollowsMouse,
+    /// Move the focused floating window (`move position ...`)
+    MoveFloating(FloatingPosition),
+    /// Resize the focused floating window (`resize set <width> <height>`)
+    ResizeFloating { width: i32, height: i32 },
+    /// Grow the focused window by `amount` pixels along `direction`'s axis,
+    /// or shrink it for a negative one
+    /// (`resize grow|shrink width|height <n> [px]`)
+    Resize { direction: Direction, amount: i32 },
+    /// Move focus between tiled and floating windows
+    /// (`focus tiling|floating|mode_toggle`)
+    FocusMode(FocusMode),
+    /// Focus the most recently urgent window, wherever it is (`focus urgent`)
+    FocusUrgent,
+    /// Step back through the windows focused before (`focus back`)
+    FocusBack,
+    /// Step forward again after `focus back` (`focus forward`)
+    FocusForward,
+    /// Select the container holding the focused window or container
+    /// (`focus parent`)
+    FocusParent,
+    /// Go back down from a selected container (`focus child`)
+    FocusChild,
+    /// Step to the next or previous child of the same container, wrapping
+    /// around (`focus next|prev [sibling]`)
+    FocusSibling(SiblingDirection),
+    /// Switch to the bindings of a `mode "<name>" { ... }` block, or back
+    /// to the top-level ones for "default" (`mode <name>`)
+    Mode(String),
     /// Split orientation
     Split(Orientation),
     /// Move workspace to output
-    MoveWorkspaceToOutput(Direction),
+    /// (`move workspace to output <direction|primary|name>`)
+    MoveWorkspaceToOutput(OutputTarget),
+    /// Exchange the workspaces of the focused output and another one
+    /// (`swap workspace with output <direction|name>`)
+    SwapOutputWorkspaces(OutputTarget),
+    /// Move the focused window to the workspace shown on the virtual output
+    /// under the pointer (`move container to output pointer`)
+    MoveWindowToPointerOutput,
+    /// Change an output's fractional scale (`output <name> scale <factor>`)
+    SetScale { output: String, scale: f64 },
+    /// Switch an output's mode (`output <name> mode <width>x<height>[@<rate>]`),
+    /// with the refresh rate in millihertz
+    OutputMode {
+        name: String,
+        width: i32,
+        height: i32,
+        refresh: Option<u32>,
+    },
     /// Scratchpad commands
     Scratchpad(ScratchpadCommand),
     /// Custom/unimplemented command
     Raw(String),
+    /// Drag the window under the pointer (`move` without arguments, for mouse bindings)
+    InteractiveMove,
+    /// Resize the window under the pointer from its nearest corner (`resize` without arguments)
+    InteractiveResize,
+    /// Do nothing, optionally carrying a comment (`nop [text]`)
+    Nop(Option<String>),
+    /// Commands chained with `;`, run in order
+    Sequence(Vec<Command>),
+    /// Show thumbnails of all windows on the focused output to pick one from
+    Overview(Toggle),
+    /// Show frame rate, frame time and window count on the focused output
+    DebugOverlay(Toggle),
+    /// Put the cursor on a hardware cursor plane or draw it into frames
+    /// (`cursor_mode hardware|software`)
+    CursorMode(CursorMode),
+    /// Write the focused workspace's tiling layout to a JSON file
+    SaveLayout(String),
+    /// Add placeholders from a saved layout to the focused workspace
+    AppendLayout(String),
     /// Debug command to swap first two windows
     DebugSwapWindows,
     /// Set horizontal split
@@ -91,9 +290,18 @@ pub enum Command {
     MoveTabLeft,
     /// Move tab right in tabbed/stacked container
     MoveTabRight,
+    /// Shift every window in the focused container by one slot
+    /// (`rotate container clockwise|counterclockwise`)
+    RotateContainer(RotateDirection),
+    /// Even out the splits of the focused workspace or container
+    /// (`balance [workspace|container]`)
+    BalanceSplits { scope: BalanceScope },
+    /// Change a gap at runtime
+    /// (`gaps <inner|outer|top|bottom|left|right> [all] [set|plus|minus] <px>`)
+    Gaps { scope: GapScope, amount: GapAmount },
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Left,
     Right,
@@ -107,10 +315,106 @@ pub enum WorkspaceTarget {
     Number(u8),
     /// Workspace by name
     Name(String),
-    /// Previous workspace
+    /// Previous workspace in the workspace order, on whichever output it is
     Previous,
-    /// Next workspace
+    /// Next workspace in the workspace order, on whichever output it is
+    Next,
+    /// Previous of the workspaces associated with the focused output
+    PreviousOnOutput,
+    /// Next of the workspaces associated with the focused output
+    NextOnOutput,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputTarget {
+    /// The nearest output in a direction from the focused one
+    Direction(Direction),
+    /// The primary output
+    Primary,
+    /// An output by name, virtual or physical
+    Name(String),
+}
+
+/// Where `new_window_position` puts new tiled windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NewWindowPosition {
+    /// Right after the focused window, splitting it
+    AfterFocused,
+    /// After all other windows
+    #[default]
+    End,
+    /// Before all other windows
+    Beginning,
+}
+
+/// Which way `rotate container` shifts windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotateDirection {
+    /// Each window takes the next slot, the last one the first
+    Clockwise,
+    /// Each window takes the previous slot, the first one the last
+    CounterClockwise,
+}
+
+/// Which windows `focus tiling|floating|mode_toggle` moves focus to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusMode {
+    /// The last focused tiled window
+    Tiling,
+    /// The topmost floating window
+    Floating,
+    /// Whichever of the two the focused window isn't
+    ModeToggle,
+}
+
+/// Which way `focus next|prev` steps through a container's children
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiblingDirection {
     Next,
+    Prev,
+}
+
+/// What `balance` evens out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BalanceScope {
+    /// Every split on the focused workspace
+    #[default]
+    Workspace,
+    /// The container holding the focused window
+    Container,
+}
+
+/// Which gap a `gaps` command changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapScope {
+    /// Between neighbouring tiled windows
+    Inner,
+    /// Along all four edges of the workspace
+    Outer,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// How a `gaps` command changes a gap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapAmount {
+    Set(i32),
+    Plus(i32),
+    Minus(i32),
+}
+
+impl GapAmount {
+    /// The gap after changing `current`, never below zero
+    pub fn apply(self, current: i32) -> i32 {
+        match self {
+            GapAmount::Set(amount) => amount,
+            GapAmount::Plus(amount) => current.saturating_add(amount),
+            GapAmount::Minus(amount) => current.saturating_sub(amount),
+        }
+        .max(0)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -128,12 +432,185 @@ pub enum LayoutCommand {
     SplitV,
 }
 
+/// Where `move position` puts a floating window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatingPosition {
+    /// Centered in the usable area of its virtual output
+    Center,
+    /// Centered on the pointer
+    Cursor,
+    /// Top-left corner at `x`, `y`, relative to the virtual output, or in
+    /// global coordinates if `absolute`
+    Position { x: i32, y: i32, absolute: bool },
+}
+
 #[derive(Debug, Clone)]
 pub enum ScratchpadCommand {
     Show,
     Move,
 }
 
+/// Argument of commands that switch something on or off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Toggle {
+    Enable,
+    Disable,
+    Toggle,
+}
+
+/// How the udev backend shows the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorMode {
+    /// On a cursor plane where the driver offers one, for the lowest latency
+    #[default]
+    Hardware,
+    /// Composited into every frame like any other element, which works
+    /// around buggy cursor planes
+    Software,
+}
+
+/// Criteria used to select windows, e.g. `[app_id="pavucontrol"]`
+///
+/// Every property that is set must match for the criteria to match. Values
+/// are regular expressions matched against the whole property, so plain
+/// text still has to match exactly; `window_type` is compared as is.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Criteria {
+    /// Wayland app_id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<Pattern>,
+    /// X11 WM_CLASS class
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<Pattern>,
+    /// X11 WM_CLASS instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<Pattern>,
+    /// Window title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<Pattern>,
+    /// X11 window type, e.g. `dialog` or `utility`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_type: Option<String>,
+}
+
+impl Criteria {
+    /// Check whether a window with the given properties matches
+    pub fn matches(&self, window: &WindowCriteria) -> bool {
+        fn check(pattern: &Option<Pattern>, actual: Option<&str>) -> bool {
+            pattern.as_ref().map_or(true, |pattern| {
+                actual.is_some_and(|actual| pattern.matches(actual))
+            })
+        }
+
+        check(&self.app_id, window.app_id.as_deref())
+            && check(&self.class, window.class.as_deref())
+            && check(&self.instance, window.instance.as_deref())
+            && check(&self.title, Some(&window.title))
+            && self
+                .window_type
+                .as_deref()
+                .map_or(true, |expected| window.window_type == Some(expected))
+    }
+
+    /// Whether no property is set, so that every window would match
+    pub fn is_empty(&self) -> bool {
+        self.app_id.is_none()
+            && self.class.is_none()
+            && self.instance.is_none()
+            && self.title.is_none()
+            && self.window_type.is_none()
+    }
+}
+
+/// A criteria value, compiled once when the criteria is read so that it
+/// has to match a whole property. Saved as the text it was written as.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    /// None for a pattern that doesn't compile, e.g. from a hand-edited
+    /// saved layout, which then only matches itself
+    regex: Option<Regex>,
+}
+
+impl Pattern {
+    /// Compile a pattern, failing if it is not a valid regular expression
+    pub fn new(source: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            source: source.to_string(),
+            regex: Some(Self::compile(source)?),
+        })
+    }
+
+    /// A pattern matching `value` and nothing else
+    pub fn literal(value: &str) -> Self {
+        let source = regex::escape(value);
+        Self {
+            regex: Self::compile(&source).ok(),
+            source,
+        }
+    }
+
+    fn compile(source: &str) -> Result<Regex, regex::Error> {
+        Regex::new(&format!("^(?:{source})$"))
+    }
+
+    /// The pattern as written
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the pattern matches all of `value`
+    pub fn matches(&self, value: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(value),
+            None => self.source == value,
+        }
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Ok(Self {
+            regex: Self::compile(&source).ok(),
+            source,
+        })
+    }
+}
+
+/// A `for_window` rule: run a command for every new window matching the criteria
+#[derive(Debug, Clone)]
+pub struct WindowRule {
+    pub criteria: Criteria,
+    pub command: Command,
+}
+
+/// An `assign` rule: open windows matching the criteria on a workspace
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    pub criteria: Criteria,
+    /// Workspace number (1-10)
+    pub workspace: u8,
+}
+
+/// A `no_focus` rule: matching windows don't take focus when they open
+#[derive(Debug, Clone)]
+pub struct NoFocusRule {
+    pub criteria: Criteria,
+}
+
 #[derive(Debug, Clone)]
 pub struct OutputConfig {
     pub name: String,
@@ -147,6 +624,15 @@ pub struct OutputConfig {
     pub physical_size_mm: Option<(f64, f64)>,
     /// Physical position in millimeters (x, y) from top-left origin
     pub physical_position_mm: Option<(f64, f64)>,
+    /// Where workspaces and layer surfaces without an output of their own go
+    pub primary: bool,
+    /// Gap between tiled windows on this output, instead of `gaps inner`
+    pub gaps_inner: Option<i32>,
+    /// Border width of tiled windows on this output, instead of `default_border`
+    pub border_width: Option<i32>,
+    /// XWayland scale while the focused X11 window is on this output, with
+    /// `xwayland_scale auto`, instead of the output's scale
+    pub xwayland_scale: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -174,12 +660,19 @@ pub struct BackgroundConfig {
     pub mode: String, // fill, stretch, fit, center, tile
 }
 
+/// A `workspace N ...` directive
 #[derive(Debug, Clone)]
 pub struct WorkspaceConfig {
     pub number: u8,
     pub output: Option<String>,
+    /// Position from `workspace N order M`; may be negative to sort first
+    pub order: Option<i32>,
+    /// `workspace N persistent`: exists even while empty and hidden
+    pub persistent: bool,
 }
 
+/// Gaps between tiled windows and along the workspace edges. A set edge
+/// (`gaps top 20`) overrides `gaps outer` on that edge.
 #[derive(Debug, Clone)]
 pub struct GapConfig {
     pub inner: Option<i32>,
@@ -188,13 +681,273 @@ pub struct GapConfig {
     pub bottom: Option<i32>,
     pub left: Option<i32>,
     pub right: Option<i32>,
+    /// `smart_gaps on`: no gaps on a workspace with a single tiled window
     pub smart: bool,
 }
 
+impl GapConfig {
+    /// Gap between tiled windows when neither config nor output sets one
+    pub const DEFAULT_INNER: i32 = 10;
+
+    /// The gaps along the top, right, bottom and left workspace edges
+    pub fn outer_edges(&self) -> [i32; 4] {
+        [self.top, self.right, self.bottom, self.left].map(|edge| edge.or(self.outer).unwrap_or(0))
+    }
+
+    /// Change a gap the way a `gaps` command does. Changing the outer gap
+    /// changes each edge from where it is, overrides included.
+    pub fn apply(&mut self, scope: GapScope, amount: GapAmount) {
+        let outer = self.outer;
+        let change =
+            |edge: &mut Option<i32>| *edge = Some(amount.apply(edge.or(outer).unwrap_or(0)));
+        match scope {
+            GapScope::Inner => {
+                self.inner = Some(amount.apply(self.inner.unwrap_or(Self::DEFAULT_INNER)));
+            }
+            GapScope::Outer => {
+                for edge in [
+                    &mut self.top,
+                    &mut self.bottom,
+                    &mut self.left,
+                    &mut self.right,
+                    &mut self.outer,
+                ] {
+                    change(edge);
+                }
+            }
+            GapScope::Top => change(&mut self.top),
+            GapScope::Bottom => change(&mut self.bottom),
+            GapScope::Left => change(&mut self.left),
+            GapScope::Right => change(&mut self.right),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClipboardConfig {
+    /// Keep text selections available after the client that set them exits
+    pub persist: bool,
+    /// Maximum number of bytes cached per selection, across all MIME types
+    pub max_size: usize,
+}
+
+/// Rate of change over an animation's duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseOut,
+    EaseInOut,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationConfig {
+    /// Off keeps every change instant
+    pub enabled: bool,
+    /// Workspace slide length in milliseconds
+    pub workspace_duration: u32,
+    /// New window fade and scale-in length in milliseconds
+    pub window_open_duration: u32,
+    /// Closing window fade and shrink-out length in milliseconds
+    pub window_close_duration: u32,
+    pub easing: Easing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureKind {
+    Swipe,
+    Pinch,
+    /// Fingers resting on the touchpad without moving
+    Hold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// Fingers pinched together
+    Inward,
+    /// Fingers spread apart
+    Outward,
+    /// Holds have no direction
+    Still,
+}
+
+/// `bindgesture <kind>:<fingers>:<direction> <command>`, or
+/// `bindgesture hold[:<fingers>] <command>`
+#[derive(Debug, Clone)]
+pub struct GestureBinding {
+    pub kind: GestureKind,
+    pub fingers: u32,
+    pub direction: GestureDirection,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone)]
+pub struct GestureConfig {
+    pub bindings: Vec<GestureBinding>,
+    /// Distance a swipe must travel, in logical pixels, to trigger its binding
+    pub swipe_threshold: f64,
+    /// Scale change a pinch must reach (e.g. 0.25 means below 0.75 or above 1.25)
+    pub pinch_threshold: f64,
+}
+
+impl GestureConfig {
+    /// Whether any binding uses this gesture with this many fingers
+    pub fn binds(&self, kind: GestureKind, fingers: u32) -> bool {
+        self.bindings
+            .iter()
+            .any(|b| b.kind == kind && b.fingers == fingers)
+    }
+
+    pub fn find(
+        &self,
+        kind: GestureKind,
+        fingers: u32,
+        direction: GestureDirection,
+    ) -> Option<&GestureBinding> {
+        self.bindings
+            .iter()
+            .find(|b| b.kind == kind && b.fingers == fingers && b.direction == direction)
+    }
+}
+
+/// `status_bar { position top; height 24; show_workspaces true; show_clock true }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusBarConfig {
+    pub position: StatusBarPosition,
+    /// Height in logical pixels, reserved from the workspace area
+    pub height: i32,
+    pub show_workspaces: bool,
+    pub show_clock: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusBarPosition {
+    Top,
+    Bottom,
+}
+
+impl Default for StatusBarConfig {
+    fn default() -> Self {
+        Self {
+            position: StatusBarPosition::Top,
+            height: 24,
+            show_workspaces: true,
+            show_clock: true,
+        }
+    }
+}
+
+/// `tab_bar { height 30; gap 4; font_size 10; active_background #333333; ... }`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TabBarConfig {
+    pub size: crate::tab_bar::TabBarSize,
+    pub colors: crate::tab_bar::TabBarColors,
+    /// Tab text size in points. When set, bars are as high as this text
+    /// needs at each output's scale instead of `size.height`.
+    pub font_size: Option<f64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct BorderConfig {
     pub width: i32,
     pub floating_width: i32,
+    /// Which borders of tiled windows touching the workspace edge are hidden
+    pub hide_edge: HideEdgeBorders,
+    /// When tiled windows lose all their borders
+    pub smart: SmartBorders,
+}
+
+/// `hide_edge_borders` mode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HideEdgeBorders {
+    #[default]
+    None,
+    /// Left and right borders on the workspace edge
+    Vertical,
+    /// Top and bottom borders on the workspace edge
+    Horizontal,
+    /// All borders on the workspace edge
+    Both,
+    /// All borders, but only when the window is alone on its workspace
+    Smart,
+}
+
+/// `smart_borders` mode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SmartBorders {
+    #[default]
+    Off,
+    /// No borders on a window that is alone on its workspace
+    On,
+    /// No borders while the gaps between windows are zero
+    NoGaps,
+}
+
+/// `workspace_switch_warp` mode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WorkspaceSwitchWarp {
+    /// Center of the newly focused window
+    Focused,
+    /// Center of the output, when switching moved to another output
+    #[default]
+    Output,
+    /// The pointer stays where it is
+    None,
+}
+
+/// `workspace_switch_mode`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WorkspaceSwitchMode {
+    /// Switch on the output the workspace belongs to and focus that output
+    #[default]
+    Follow,
+    /// Move the workspace to the focused output. One shown on another output
+    /// leaves it, and that output shows a free workspace instead.
+    Bring,
+}
+
+/// `fullscreen_output_removed`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FullscreenOutputRemoved {
+    /// Leave fullscreen, back to the window's place before it on the
+    /// workspace shown on a remaining output
+    #[default]
+    Restore,
+    /// Go fullscreen again on a remaining output
+    Move,
+}
+
+/// `focus_on_window_activation` mode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FocusOnWindowActivation {
+    /// Focus the window if its workspace is shown, mark it urgent otherwise
+    #[default]
+    Smart,
+    /// Mark the window urgent
+    Urgent,
+    /// Focus the window, switching to its workspace if needed
+    Focus,
+    /// Ignore the request
+    None,
+}
+
+/// `xwayland_scale <factor|auto>`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum XWaylandScale {
+    /// The same scale wherever X11 windows are
+    Fixed(f64),
+    /// The scale of the output the focused X11 window is on, or its
+    /// `output <name> xwayland_scale`
+    FollowOutput,
+}
+
+impl Default for XWaylandScale {
+    fn default() -> Self {
+        Self::Fixed(1.0)
+    }
 }
 
 impl Default for Config {
@@ -202,14 +955,102 @@ impl Default for Config {
         Self {
             variables: HashMap::new(),
             keybindings: Vec::new(),
+            keycode_bindings: HashMap::new(),
             outputs: Vec::new(),
             virtual_outputs: Vec::new(),
             workspaces: Vec::new(),
             gaps: GapConfig::default(),
             border: BorderConfig::default(),
             font: "monospace 10".to_string(),
+            title_format: "%title".to_string(),
             startup_commands: Vec::new(),
             input_configs: Vec::new(),
+            window_rules: Vec::new(),
+            assignments: Vec::new(),
+            no_focus_rules: Vec::new(),
+            clipboard: ClipboardConfig::default(),
+            gestures: GestureConfig::default(),
+            floating_modifier: None,
+            animations: AnimationConfig::default(),
+            workspace_auto_back_and_forth: false,
+            workspace_switch_warp: WorkspaceSwitchWarp::default(),
+            workspace_switch_mode: WorkspaceSwitchMode::default(),
+            fullscreen_output_removed: FullscreenOutputRemoved::default(),
+            status_bar: None,
+            tab_bar: TabBarConfig::default(),
+            hide_cursor_when_typing: false,
+            hide_cursor_timeout: None,
+            double_click_time: crate::input::click::DEFAULT_DOUBLE_CLICK_TIME,
+            shortcuts_inhibit_escape: Some(KeyChord {
+                modifiers: ModifiersState {
+                    logo: true,
+                    ..Default::default()
+                },
+                key: Keysym::Escape,
+            }),
+            render_node: None,
+            max_render_fps: None,
+            new_window_position: NewWindowPosition::default(),
+            workspace_state_file: None,
+            remember_window_geometry: false,
+            focus_on_window_activation: FocusOnWindowActivation::default(),
+            focus_follows_mouse_delay_ms: 0,
+            path: None,
+            xwayland_scale: XWaylandScale::default(),
+            xwayland: true,
+        }
+    }
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        let swipe = |fingers, direction, command| GestureBinding {
+            kind: GestureKind::Swipe,
+            fingers,
+            direction,
+            command,
+        };
+        Self {
+            bindings: vec![
+                // Content follows the fingers: swiping left brings in the next workspace
+                swipe(
+                    3,
+                    GestureDirection::Left,
+                    Command::Workspace(WorkspaceTarget::Next),
+                ),
+                swipe(
+                    3,
+                    GestureDirection::Right,
+                    Command::Workspace(WorkspaceTarget::Previous),
+                ),
+                swipe(4, GestureDirection::Left, Command::Move(Direction::Left)),
+                swipe(4, GestureDirection::Right, Command::Move(Direction::Right)),
+                swipe(4, GestureDirection::Up, Command::Move(Direction::Up)),
+                swipe(4, GestureDirection::Down, Command::Move(Direction::Down)),
+            ],
+            swipe_threshold: 100.0,
+            pinch_threshold: 0.25,
+        }
+    }
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            persist: false,
+            max_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            workspace_duration: 200,
+            window_open_duration: 150,
+            window_close_duration: 150,
+            easing: Easing::EaseOut,
         }
     }
 }
@@ -220,9 +1061,9 @@ impl Default for GapConfig {
             inner: None,
             outer: None,
             top: None,
-            bottom: Some(7),
-            left: Some(7),
-            right: Some(7),
+            bottom: None,
+            left: None,
+            right: None,
             smart: false,
         }
     }
@@ -233,6 +1074,8 @@ impl Default for BorderConfig {
         Self {
             width: 2,
             floating_width: 2,
+            hide_edge: HideEdgeBorders::default(),
+            smart: SmartBorders::default(),
         }
     }
 }
@@ -265,6 +1108,11 @@ pub struct InputConfig {
     pub tap_button_map: Option<TapButtonMap>,
     /// Scroll method (two_finger, edge, on_button_down)
     pub scroll_method: Option<ScrollMethod>,
+    /// Button held to scroll with `scroll_method on_button_down`, as a Linux
+    /// input event code (middle button if unset)
+    pub scroll_button: Option<u32>,
+    /// Multiplier applied to scroll distances
+    pub scroll_factor: Option<f64>,
     /// Left handed mode
     pub left_handed: Option<bool>,
     /// Middle button emulation
@@ -294,7 +1142,43 @@ impl Config {
     /// Load config from file
     pub fn load_from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
         let content = std::fs::read_to_string(path)?;
-        parser::parse_config(&content)
+        let mut config = parser::parse_config(&content)?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Re-read only the keybindings of the config file at `path`
+    pub fn load_bindings_from_file(path: &Path) -> Result<Bindings, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        parser::parse_bindings(&content)
+    }
+
+    /// Shortest time between two frames on an output set by `max_render_fps`
+    pub fn min_render_interval(&self) -> Option<std::time::Duration> {
+        self.max_render_fps
+            .map(|fps| std::time::Duration::from_secs(1) / fps)
+    }
+
+    /// Names of the binding modes, "default" first. "resize" is always
+    /// there, its arrow keys are built in.
+    pub fn binding_modes(&self) -> Vec<&str> {
+        let mut modes = vec!["default", "resize"];
+        let bindings = self
+            .keybindings
+            .iter()
+            .chain(self.keycode_bindings.values().flatten());
+        for mode in bindings.filter_map(|binding| binding.mode.as_deref()) {
+            if !modes.contains(&mode) {
+                modes.push(mode);
+            }
+        }
+        modes
+    }
+
+    /// Swap in re-read keybindings, leaving the rest of the config alone
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.keybindings = bindings.keybindings;
+        self.keycode_bindings = bindings.keycode_bindings;
     }
 
     /// Get a variable value, expanding nested variables
@@ -315,6 +1199,114 @@ impl Config {
         self.get_bool("focus_follows_mouse").unwrap_or(true)
     }
 
+    /// Floating state forced by `for_window` rules for a window, if any.
+    /// When several rules match, the last one wins.
+    pub fn floating_override(&self, window: &WindowCriteria) -> Option<bool> {
+        self.window_rules
+            .iter()
+            .filter(|rule| rule.criteria.matches(window))
+            .filter_map(|rule| match rule.command {
+                Command::FloatingEnable => Some(true),
+                Command::FloatingDisable => Some(false),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// focus_follows_mouse setting forced by `for_window` rules for a
+    /// window, if any. When several rules match, the last one wins.
+    pub fn focus_follows_mouse_override(&self, window: &WindowCriteria) -> Option<bool> {
+        self.window_rules
+            .iter()
+            .filter(|rule| rule.criteria.matches(window))
+            .filter_map(|rule| match rule.command {
+                Command::FocusFollowsMouse(enabled) => Some(enabled),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Workspace number a new window is assigned to, if any.
+    /// When several assignments match, the first one wins.
+    pub fn assigned_workspace(&self, window: &WindowCriteria) -> Option<u8> {
+        self.assignments
+            .iter()
+            .find(|assignment| assignment.criteria.matches(window))
+            .map(|assignment| assignment.workspace)
+    }
+
+    /// Workspace numbers (1-10) in the order bars show them and `workspace
+    /// next`/`prev` walk them. Workspaces with a `workspace N` directive come
+    /// first, sorted by their `order` or else by where the directive appears;
+    /// the rest follow numerically.
+    pub fn workspace_order(&self) -> Vec<u8> {
+        let mut configured: Vec<(i32, u8)> = self
+            .workspaces
+            .iter()
+            .enumerate()
+            .map(|(position, workspace)| {
+                (workspace.order.unwrap_or(position as i32), workspace.number)
+            })
+            .collect();
+        configured.sort_by_key(|&(order, _)| order);
+
+        let configured: Vec<u8> = configured.into_iter().map(|(_, number)| number).collect();
+        let unconfigured = (1..=10).filter(|number| !configured.contains(number));
+        configured.iter().copied().chain(unconfigured).collect()
+    }
+
+    /// Name of the output marked `primary`, the last one if several are
+    pub fn primary_output(&self) -> Option<&str> {
+        self.outputs
+            .iter()
+            .rev()
+            .find(|output| output.primary)
+            .map(|output| output.name.as_str())
+    }
+
+    /// Whether a `no_focus` rule matches a new window
+    pub fn no_focus(&self, window: &WindowCriteria) -> bool {
+        self.no_focus_rules
+            .iter()
+            .any(|rule| rule.criteria.matches(window))
+    }
+
+    /// Expand `title_format` for a window. `class` is only set for X11 windows.
+    pub fn format_title(
+        &self,
+        title: &str,
+        app_id: Option<&str>,
+        class: Option<&str>,
+        workspace: &str,
+    ) -> String {
+        let mut result = String::new();
+        let mut rest = self.title_format.as_str();
+        while let Some(pos) = rest.find('%') {
+            result.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+            let placeholder = [
+                ("%title", title),
+                ("%app_id", app_id.unwrap_or_default()),
+                ("%class", class.unwrap_or_default()),
+                ("%workspace", workspace),
+            ]
+            .into_iter()
+            .find(|(name, _)| rest.starts_with(name));
+            match placeholder {
+                Some((name, value)) => {
+                    result.push_str(value);
+                    rest = &rest[name.len()..];
+                }
+                None => {
+                    result.push('%');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
     /// Expand variables in a string
     pub fn expand_variables(&self, text: &str) -> String {
         let mut result = text.to_string();
@@ -1,42 +1,313 @@
 use super::*;
-use smithay::input::keyboard::{keysyms, Keysym, ModifiersState};
+use smithay::input::keyboard::{keysyms, xkb, Keysym, ModifiersState};
 
 /// Parse a sway config file
 pub fn parse_config(content: &str) -> Result<Config, Box<dyn std::error::Error>> {
     let mut config = Config::default();
 
-    for (_line_num, line) in content.lines().enumerate() {
-        let line = line.trim();
-
-        // Skip empty lines and comments
-        if line.is_empty() || line.starts_with('#') {
+    // Whether a valid floating_modifier line was seen, including `none`
+    let mut floating_modifier_set = false;
+    // The `mode "<name>" { ... }` block being read
+    let mut mode: Option<String> = None;
+    for line in config_lines(content) {
+        if let Some(name) = mode_block_start(line) {
+            mode = Some(name);
+            continue;
+        }
+        if mode.is_some() && line == "}" {
+            mode = None;
+            continue;
+        }
+        if let Some(name) = mode.as_deref() {
+            if let Err(e) = parse_mode_line(&mut config, name, line) {
+                eprintln!("Warning: Failed to parse config line '{}': {}", line, e);
+            }
             continue;
         }
 
         // Parse the line
-        if let Err(e) = parse_line(&mut config, line) {
-            eprintln!("Warning: Failed to parse config line '{}': {}", line, e);
+        match parse_line(&mut config, line) {
+            Ok(()) => {
+                floating_modifier_set |= line.split_whitespace().next() == Some("floating_modifier")
+            }
+            Err(e) => eprintln!("Warning: Failed to parse config line '{}': {}", line, e),
+        }
+    }
+    if let Some(name) = mode {
+        eprintln!("Warning: mode \"{name}\" block is never closed");
+    }
+
+    // Without a valid floating_modifier line, the main mod key drags windows if
+    // the config defines one
+    if !floating_modifier_set {
+        if let Some(mod_key) = config.get_variable("mod") {
+            let mut modifiers = ModifiersState::default();
+            if apply_modifier(&mut modifiers, &mod_key).is_ok() {
+                config.floating_modifier = Some(FloatingModifier {
+                    modifiers,
+                    inverse: false,
+                });
+            }
         }
     }
 
     Ok(config)
 }
 
+/// Parse only the variables and keybindings of a config, for reloading the
+/// bindings alone. Unlike `parse_config`, a line that fails to parse is an
+/// error, so a typo can't silently drop a binding.
+pub fn parse_bindings(content: &str) -> Result<Bindings, Box<dyn std::error::Error>> {
+    let mut config = Config::default();
+
+    let mut mode: Option<String> = None;
+    for line in config_lines(content) {
+        if let Some(name) = mode_block_start(line) {
+            mode = Some(name);
+            continue;
+        }
+        if mode.is_some() && line == "}" {
+            mode = None;
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let result = match (mode.as_deref(), parts[0]) {
+            (Some(name), _) => parse_mode_line(&mut config, name, line),
+            (None, "set") => parse_set(&mut config, &parts[1..]),
+            (None, "bindsym") => parse_bindsym(&mut config, &parts[1..], None),
+            (None, "bindcode") => parse_bindcode(&mut config, &parts[1..], None),
+            _ => continue,
+        };
+        result.map_err(|e| format!("Failed to parse config line '{line}': {e}"))?;
+    }
+    if let Some(name) = mode {
+        return Err(format!("mode \"{name}\" block is never closed").into());
+    }
+
+    Ok(Bindings {
+        keybindings: config.keybindings,
+        keycode_bindings: config.keycode_bindings,
+    })
+}
+
+/// The name of the mode a `mode [--pango_markup] "<name>" {` line opens
+fn mode_block_start(line: &str) -> Option<String> {
+    let mut parts = line.split_whitespace();
+    if parts.next() != Some("mode") || parts.next_back() != Some("{") {
+        return None;
+    }
+    let parts: Vec<&str> = parts.filter(|part| *part != "--pango_markup").collect();
+    let name = parts.join(" ");
+    let name = name.trim_matches('"');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// A line inside a `mode "<name>" { ... }` block, whose bindings only apply
+/// while the mode is on
+fn parse_mode_line(
+    config: &mut Config,
+    mode: &str,
+    line: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    match parts[0] {
+        "set" => parse_set(config, &parts[1..]),
+        "bindsym" => parse_bindsym(config, &parts[1..], Some(mode)),
+        "bindcode" => parse_bindcode(config, &parts[1..], Some(mode)),
+        _ => Err("only bindsym, bindcode and set are allowed in a mode block".into()),
+    }
+}
+
+/// Trimmed lines of a config, without blank lines and comments
+fn config_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
 fn parse_line(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error::Error>> {
     let parts: Vec<&str> = line.split_whitespace().collect();
     let first_part = parts.first().ok_or("Empty command line")?;
 
     match *first_part {
         "set" => parse_set(config, &parts[1..])?,
-        "bindsym" => parse_bindsym(config, &parts[1..])?,
+        "bindsym" => parse_bindsym(config, &parts[1..], None)?,
+        "bindcode" => parse_bindcode(config, &parts[1..], None)?,
         "exec" => parse_exec(config, &parts[1..])?,
         "output" => parse_output(config, &parts[1..])?,
         "virtual_output" => parse_virtual_output(config, &parts[1..])?,
         "workspace" => parse_workspace(config, &parts[1..])?,
         "gaps" => parse_gaps(config, &parts[1..])?,
+        "smart_gaps" => match parts[1..] {
+            [value] => config.gaps.smart = parse_flag(value)?,
+            _ => return Err("smart_gaps requires 'on' or 'off'".into()),
+        },
         "default_border" => parse_border(config, &parts[1..])?,
+        "hide_edge_borders" => parse_hide_edge_borders(config, &parts[1..])?,
+        "smart_borders" => {
+            config.border.smart = match parts[1..] {
+                ["on"] => SmartBorders::On,
+                ["no_gaps"] => SmartBorders::NoGaps,
+                ["off"] => SmartBorders::Off,
+                _ => return Err("smart_borders requires 'on', 'no_gaps' or 'off'".into()),
+            }
+        }
+        "workspace_switch_warp" => {
+            config.workspace_switch_warp = match parts[1..] {
+                ["focused"] => WorkspaceSwitchWarp::Focused,
+                ["output"] => WorkspaceSwitchWarp::Output,
+                ["none"] => WorkspaceSwitchWarp::None,
+                _ => {
+                    return Err(
+                        "workspace_switch_warp requires 'focused', 'output' or 'none'".into(),
+                    )
+                }
+            }
+        }
+        "workspace_switch_mode" => {
+            config.workspace_switch_mode = match parts[1..] {
+                ["follow"] => WorkspaceSwitchMode::Follow,
+                ["bring"] => WorkspaceSwitchMode::Bring,
+                _ => return Err("workspace_switch_mode requires 'follow' or 'bring'".into()),
+            }
+        }
+        "fullscreen_output_removed" => {
+            config.fullscreen_output_removed = match parts[1..] {
+                ["restore"] => FullscreenOutputRemoved::Restore,
+                ["move"] => FullscreenOutputRemoved::Move,
+                _ => return Err("fullscreen_output_removed requires 'restore' or 'move'".into()),
+            }
+        }
+        "xwayland_scale" => {
+            config.xwayland_scale = match parts[1..] {
+                ["auto"] => XWaylandScale::FollowOutput,
+                [scale] => XWaylandScale::Fixed(parse_scale(scale)?),
+                _ => return Err("xwayland_scale requires a scale or 'auto'".into()),
+            }
+        }
+        "xwayland" => {
+            config.xwayland = match parts[1..] {
+                ["enable"] => true,
+                ["disable"] => false,
+                // Accepted for sway configs; smithay's XWayland binds its
+                // sockets and spawns the X server in one go, so it can't
+                // wait for the first X11 client
+                ["lazy"] => {
+                    eprintln!("Warning: xwayland lazy is not supported, starting XWayland now");
+                    true
+                }
+                _ => return Err("xwayland requires 'enable', 'disable' or 'lazy'".into()),
+            }
+        }
+        "focus_on_window_activation" => {
+            config.focus_on_window_activation =
+                match parts[1..] {
+                    ["smart"] => FocusOnWindowActivation::Smart,
+                    ["urgent"] => FocusOnWindowActivation::Urgent,
+                    ["focus"] => FocusOnWindowActivation::Focus,
+                    ["none"] => FocusOnWindowActivation::None,
+                    _ => return Err(
+                        "focus_on_window_activation requires 'smart', 'urgent', 'focus' or 'none'"
+                            .into(),
+                    ),
+                }
+        }
         "font" => parse_font(config, &parts[1..])?,
+        "title_format" => parse_title_format(config, line)?,
+        "clipboard" => parse_clipboard(config, &parts[1..])?,
+        "bindgesture" => parse_bindgesture(config, &parts[1..])?,
+        "gesture_threshold" => parse_gesture_threshold(config, &parts[1..])?,
+        "floating_modifier" => parse_floating_modifier(config, &parts[1..])?,
+        "animations" | "animation_duration" | "animation_easing" => {
+            parse_animations(config, &parts)?
+        }
+        "workspace_auto_back_and_forth" => {
+            parse_workspace_auto_back_and_forth(config, &parts[1..])?
+        }
         "input" => parse_input(config, line)?,
+        "status_bar" => parse_status_bar(config, line)?,
+        "tab_bar" => parse_tab_bar(config, line)?,
+        "hide_cursor" => parse_hide_cursor(config, &parts[1..])?,
+        "hide_cursor_when_typing" => {
+            let [value] = &parts[1..] else {
+                return Err("hide_cursor_when_typing requires 'yes' or 'no'".into());
+            };
+            config.hide_cursor_when_typing = parse_flag(value)?;
+        }
+        "focus_follows_mouse_delay_ms" => {
+            let [value] = &parts[1..] else {
+                return Err("focus_follows_mouse_delay_ms requires a time in milliseconds".into());
+            };
+            config.focus_follows_mouse_delay_ms = value.trim_end_matches("ms").parse()?;
+        }
+        "double_click_time" => {
+            let [value] = &parts[1..] else {
+                return Err("double_click_time requires a time in milliseconds".into());
+            };
+            let time: u32 = value.trim_end_matches("ms").parse()?;
+            if time == 0 {
+                return Err("double_click_time must be greater than 0".into());
+            }
+            config.double_click_time = time;
+        }
+        "shortcuts_inhibit_escape" => {
+            config.shortcuts_inhibit_escape = match parts[1..] {
+                ["none"] => None,
+                [combo] => match parse_key_combo(config, combo)? {
+                    (modifiers, BindingKey::Key(key)) => Some(KeyChord { modifiers, key }),
+                    _ => return Err("shortcuts_inhibit_escape requires a key".into()),
+                },
+                _ => {
+                    return Err(
+                        "shortcuts_inhibit_escape requires a key combination or 'none'".into(),
+                    )
+                }
+            }
+        }
+        "render_node" => {
+            let [path] = &parts[1..] else {
+                return Err("render_node requires a device path".into());
+            };
+            config.render_node = Some(path.to_string());
+        }
+        "workspace_state_file" => {
+            let [path] = &parts[1..] else {
+                return Err("workspace_state_file requires a path".into());
+            };
+            config.workspace_state_file = Some(path.to_string());
+        }
+        "remember_window_geometry" => {
+            let [value] = &parts[1..] else {
+                return Err("remember_window_geometry requires 'yes' or 'no'".into());
+            };
+            config.remember_window_geometry = parse_flag(value)?;
+        }
+        "max_render_fps" => {
+            config.max_render_fps = match parts[1..] {
+                ["off"] => None,
+                [value] => match value.parse()? {
+                    0 => return Err("max_render_fps must be greater than 0".into()),
+                    fps => Some(fps),
+                },
+                _ => return Err("max_render_fps requires a rate or 'off'".into()),
+            }
+        }
+        "new_window_position" => {
+            config.new_window_position = match parts[1..] {
+                ["after_focused"] => NewWindowPosition::AfterFocused,
+                ["end"] => NewWindowPosition::End,
+                ["beginning"] => NewWindowPosition::Beginning,
+                _ => {
+                    return Err(
+                        "new_window_position requires 'after_focused', 'end' or 'beginning'".into(),
+                    )
+                }
+            }
+        }
+        "for_window" => parse_for_window(config, line)?,
+        "assign" => parse_assign(config, line)?,
+        "no_focus" => parse_no_focus(config, line)?,
         _ => {
             // Ignore unrecognized commands for now
         }
@@ -69,27 +340,95 @@ fn parse_set(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::err
     Ok(())
 }
 
-fn parse_bindsym(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+fn parse_bindsym(
+    config: &mut Config,
+    parts: &[&str],
+    mode: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut binding = parse_binding(config, "bindsym", parts, parse_key_or_button)?;
+    binding.mode = mode.map(str::to_string);
+    config.keybindings.push(binding);
+    Ok(())
+}
+
+fn parse_bindcode(
+    config: &mut Config,
+    parts: &[&str],
+    mode: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut binding = parse_binding(config, "bindcode", parts, |name| {
+        let code = name
+            .parse()
+            .map_err(|_| format!("Invalid keycode: {name}"))?;
+        Ok(BindingKey::Code(code))
+    })?;
+    binding.mode = mode.map(str::to_string);
+    let BindingKey::Code(code) = binding.key else {
+        unreachable!("bindcode only parses keycodes");
+    };
+    config
+        .keycode_bindings
+        .entry(code)
+        .or_default()
+        .push(binding);
+    Ok(())
+}
+
+/// Parse the flags, key combination and command of a `bindsym` or
+/// `bindcode`, with `parse_key` turning each key of the combination into the
+/// key it binds
+fn parse_binding(
+    config: &Config,
+    kind: &str,
+    parts: &[&str],
+    parse_key: impl Fn(&str) -> Result<BindingKey, Box<dyn std::error::Error>>,
+) -> Result<Keybinding, Box<dyn std::error::Error>> {
+    // Leading --flags modify how the binding fires
+    let flag_count = parts.iter().take_while(|p| p.starts_with("--")).count();
+    let (flags, parts) = parts.split_at(flag_count);
+
     if parts.len() < 2 {
-        return Err("bindsym requires key combination and command".into());
+        return Err(format!("{kind} requires key combination and command").into());
+    }
+
+    let mut release = false;
+    let mut no_repeat = false;
+    let mut locked = false;
+    let mut whole_window = false;
+    for flag in flags {
+        match *flag {
+            "--release" => release = true,
+            "--whole-window" => whole_window = true,
+            // Stored for sway configs; there is no session lock to bypass yet
+            "--locked" => {
+                eprintln!("Warning: {kind} --locked has no effect: stilch has no session lock");
+                locked = true;
+            }
+            "--no-repeat" => no_repeat = true,
+            _ => return Err(format!("Unknown {kind} flag: {flag}").into()),
+        }
     }
 
     let key_combo = parts.first().ok_or("Missing key combination")?;
     let command_parts = &parts[1..];
 
-    // Parse modifiers and key
-    let (modifiers, key) = parse_key_combo(config, key_combo)?;
+    // Parse modifiers and keys
+    let (modifiers, held, key) = parse_chord(config, key_combo, parse_key)?;
 
     // Parse command
-    let command = parse_command(config, command_parts)?;
+    let command = parse_command_chain(config, command_parts)?;
 
-    config.keybindings.push(Keybinding {
+    Ok(Keybinding {
         modifiers,
         key,
+        held,
         command,
-    });
-
-    Ok(())
+        release,
+        repeat: !no_repeat,
+        locked,
+        whole_window,
+        mode: None,
+    })
 }
 
 fn parse_exec(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
@@ -107,21 +446,36 @@ fn parse_exec(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::er
 fn parse_key_combo(
     config: &Config,
     combo: &str,
-) -> Result<(ModifiersState, Keysym), Box<dyn std::error::Error>> {
+) -> Result<(ModifiersState, BindingKey), Box<dyn std::error::Error>> {
+    let (modifiers, held, key) = parse_chord(config, combo, parse_key_or_button)?;
+    if !held.is_empty() {
+        return Err(format!("Expected a single key, not a chord: {combo}").into());
+    }
+    Ok((modifiers, key))
+}
+
+/// Split a `+`-joined key combination into its modifiers, the keys of a
+/// chord that must already be held, and the key that fires the binding
+fn parse_chord(
+    config: &Config,
+    combo: &str,
+    parse_key: impl Fn(&str) -> Result<BindingKey, Box<dyn std::error::Error>>,
+) -> Result<(ModifiersState, Vec<BindingKey>, BindingKey), Box<dyn std::error::Error>> {
     let parts: Vec<&str> = combo.split('+').collect();
     if parts.is_empty() {
         return Err("Empty key combination".into());
     }
 
     let mut modifiers = ModifiersState::default();
+    let mut held = Vec::new();
     let key_part = parts
         .last()
         // SAFETY: split always produces at least one part
         .expect("split always produces at least one part");
 
-    // Parse modifiers
+    // Parse modifiers, and the other keys of a chord
     for part in &parts[..parts.len() - 1] {
-        let modifier_name = if part.starts_with('$') {
+        let name = if part.starts_with('$') {
             // Variable reference
             config
                 .get_variable(&part[1..])
@@ -130,19 +484,92 @@ fn parse_key_combo(
             part.to_string()
         };
 
-        match modifier_name.as_str() {
-            "Mod1" | "Alt" => modifiers.alt = true,
-            "Mod4" | "Super" | "Logo" => modifiers.logo = true,
-            "Ctrl" | "Control" => modifiers.ctrl = true,
-            "Shift" => modifiers.shift = true,
-            _ => return Err(format!("Unknown modifier: {modifier_name}").into()),
+        if set_modifier(&mut modifiers, &name) {
+            continue;
+        }
+        match parse_key(&name) {
+            Ok(key @ (BindingKey::Key(_) | BindingKey::Code(_))) => held.push(key),
+            Ok(_) => return Err(format!("Mouse buttons can't be part of a chord: {name}").into()),
+            Err(_) => return Err(format!("Unknown modifier or key: {name}").into()),
         }
     }
 
-    // Parse key
+    // Parse key, or mouse button
     let key = parse_key(key_part)?;
+    if !held.is_empty() && !matches!(key, BindingKey::Key(_) | BindingKey::Code(_)) {
+        return Err(format!("Mouse buttons can't be part of a chord: {key_part}").into());
+    }
 
-    Ok((modifiers, key))
+    Ok((modifiers, held, key))
+}
+
+/// Parse `+`-joined modifier names such as `Alt` or `Ctrl+Shift`
+pub fn parse_modifiers(combo: &str) -> Result<ModifiersState, Box<dyn std::error::Error>> {
+    let mut modifiers = ModifiersState::default();
+    for name in combo.split('+') {
+        apply_modifier(&mut modifiers, name)?;
+    }
+    Ok(modifiers)
+}
+
+/// Set the modifier named `name` in `modifiers`
+fn apply_modifier(
+    modifiers: &mut ModifiersState,
+    name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !set_modifier(modifiers, name) {
+        return Err(format!("Unknown modifier: {name}").into());
+    }
+    Ok(())
+}
+
+/// Set the modifier named `name` in `modifiers`, or return false if `name`
+/// isn't a modifier. The X names follow the usual xkb mapping: Mod2 is Num
+/// Lock, Mod3 the level 5 shift and Mod5 AltGr.
+fn set_modifier(modifiers: &mut ModifiersState, name: &str) -> bool {
+    match name {
+        "Mod1" | "Alt" => modifiers.alt = true,
+        "Mod2" => modifiers.num_lock = true,
+        "Mod3" => modifiers.iso_level5_shift = true,
+        "Mod4" | "Super" | "Logo" => modifiers.logo = true,
+        "Mod5" | "AltGr" => modifiers.iso_level3_shift = true,
+        "Ctrl" | "Control" => modifiers.ctrl = true,
+        "Shift" => modifiers.shift = true,
+        _ => return false,
+    }
+    true
+}
+
+/// Parse a key name, or a mouse button
+fn parse_key_or_button(name: &str) -> Result<BindingKey, Box<dyn std::error::Error>> {
+    match parse_button(name) {
+        Some(button) => Ok(button),
+        None => Ok(BindingKey::Key(parse_key(name)?)),
+    }
+}
+
+/// Parse i3-style `button1`..`button9`; buttons 4-7 are the scroll wheel
+fn parse_button(name: &str) -> Option<BindingKey> {
+    // Linux input event codes
+    const BTN_LEFT: u32 = 0x110;
+    const BTN_RIGHT: u32 = 0x111;
+    const BTN_MIDDLE: u32 = 0x112;
+    const BTN_SIDE: u32 = 0x113;
+    const BTN_EXTRA: u32 = 0x114;
+
+    let key = match name {
+        "button1" => BindingKey::Button(BTN_LEFT),
+        "button2" => BindingKey::Button(BTN_MIDDLE),
+        "button3" => BindingKey::Button(BTN_RIGHT),
+        "button4" | "wheel_up" => BindingKey::Wheel(Direction::Up),
+        "button5" | "wheel_down" => BindingKey::Wheel(Direction::Down),
+        "button6" | "wheel_left" => BindingKey::Wheel(Direction::Left),
+        "button7" | "wheel_right" => BindingKey::Wheel(Direction::Right),
+        "button8" => BindingKey::Button(BTN_SIDE),
+        "button9" => BindingKey::Button(BTN_EXTRA),
+        _ => return None,
+    };
+    Some(key)
 }
 
 fn parse_key(key: &str) -> Result<Keysym, Box<dyn std::error::Error>> {
@@ -227,12 +654,74 @@ fn parse_key(key: &str) -> Result<Keysym, Box<dyn std::error::Error>> {
         "XF86AudioNext" => keysyms::KEY_XF86AudioNext,
         "XF86AudioPrev" => keysyms::KEY_XF86AudioPrev,
 
-        _ => return Err(format!("Unknown key: {key}").into()),
+        // Any other keysym by its xkb name
+        _ => {
+            let keysym = xkb::keysym_from_name(key, xkb::KEYSYM_NO_FLAGS);
+            if keysym == Keysym::NoSymbol {
+                return Err(format!("Unknown key: {key}").into());
+            }
+            return Ok(keysym);
+        }
     };
 
     Ok(Keysym::new(keysym))
 }
 
+/// Parse commands chained with `;`. Sub-commands that fail to parse are
+/// skipped with a warning so the rest of the chain still runs.
+fn parse_command_chain(
+    config: &Config,
+    parts: &[&str],
+) -> Result<Command, Box<dyn std::error::Error>> {
+    let line = parts.join(" ");
+    let mut commands = Vec::new();
+    for (segment, command) in parse_command_list(config, &line) {
+        match command {
+            Ok(command) => commands.push(command),
+            Err(e) => eprintln!("Warning: Skipping command '{segment}': {e}"),
+        }
+    }
+
+    if commands.len() > 1 {
+        return Ok(Command::Sequence(commands));
+    }
+    commands.pop().ok_or_else(|| "No valid command".into())
+}
+
+/// Parse each command chained with `;` on its own, with the text it came
+/// from. Command lines given over IPC get a result for every command.
+pub fn parse_command_list<'a>(
+    config: &Config,
+    line: &'a str,
+) -> Vec<(&'a str, Result<Command, Box<dyn std::error::Error>>)> {
+    split_unquoted(line, ';')
+        .into_iter()
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let segment_parts: Vec<&str> = segment.split_whitespace().collect();
+            (segment, parse_command(config, &segment_parts))
+        })
+        .collect()
+}
+
+/// Split `input` on `separator`, ignoring separators inside double quotes
+fn split_unquoted(input: &str, separator: char) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == separator && !in_quotes {
+            segments.push(&input[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    segments.push(&input[start..]);
+    segments
+}
+
 fn parse_command(config: &Config, parts: &[&str]) -> Result<Command, Box<dyn std::error::Error>> {
     if parts.is_empty() {
         return Err("Empty command".into());
@@ -244,6 +733,8 @@ fn parse_command(config: &Config, parts: &[&str]) -> Result<Command, Box<dyn std
             Command::Exec(config.expand_variables(&program))
         }
         "kill" => Command::Kill,
+        "nop" => Command::Nop((parts.len() > 1).then(|| parts[1..].join(" "))),
+        "reload" if parts.get(1) == Some(&"bindings") => Command::ReloadBindings,
         "reload" => Command::Reload,
         "exit" => Command::Exit,
         "debugswapwindows" => Command::DebugSwapWindows,
@@ -252,6 +743,51 @@ fn parse_command(config: &Config, parts: &[&str]) -> Result<Command, Box<dyn std
         "splitauto" => Command::SplitAutomatic,
         "movetableft" => Command::MoveTabLeft,
         "movetabright" => Command::MoveTabRight,
+        "rotate" => Command::RotateContainer(match parts[1..] {
+            ["container", "clockwise"] => RotateDirection::Clockwise,
+            ["container", "counterclockwise"] => RotateDirection::CounterClockwise,
+            _ => return Err("rotate requires 'container clockwise|counterclockwise'".into()),
+        }),
+        "balance" => Command::BalanceSplits {
+            scope: match parts[1..] {
+                [] | ["workspace"] => BalanceScope::Workspace,
+                ["container"] => BalanceScope::Container,
+                _ => return Err("balance takes 'workspace' or 'container'".into()),
+            },
+        },
+        "gaps" => parse_gaps_command(&parts[1..])?,
+        "focus" if parts.get(1) == Some(&"output") => Command::FocusOutput(match parts[2..] {
+            [target] => parse_output_target(target)?,
+            _ => return Err("focus output requires a direction, primary or a name".into()),
+        }),
+        "swap" if parts.get(1) == Some(&"workspace") => match parts[2..] {
+            ["with", "output", target] => {
+                Command::SwapOutputWorkspaces(parse_output_target(target)?)
+            }
+            _ => return Err("swap workspace requires 'with output <direction|name>'".into()),
+        },
+        "focus" if parts.get(1) == Some(&"urgent") => Command::FocusUrgent,
+        "focus" if parts.get(1) == Some(&"back") => Command::FocusBack,
+        "focus" if parts.get(1) == Some(&"forward") => Command::FocusForward,
+        "focus" if parts.get(1) == Some(&"parent") => Command::FocusParent,
+        "focus" if parts.get(1) == Some(&"child") => Command::FocusChild,
+        "focus" if matches!(parts.get(1), Some(&("next" | "prev"))) => {
+            let direction = match parts[1] {
+                "next" => SiblingDirection::Next,
+                _ => SiblingDirection::Prev,
+            };
+            match parts[2..] {
+                [] | ["sibling"] => Command::FocusSibling(direction),
+                _ => return Err("focus next|prev only takes 'sibling'".into()),
+            }
+        }
+        "focus" if matches!(parts.get(1), Some(&("tiling" | "floating" | "mode_toggle"))) => {
+            Command::FocusMode(match parts[1] {
+                "tiling" => FocusMode::Tiling,
+                "floating" => FocusMode::Floating,
+                _ => FocusMode::ModeToggle,
+            })
+        }
         "focus" => {
             if parts.len() < 2 {
                 return Err("focus requires direction".into());
@@ -260,50 +796,111 @@ fn parse_command(config: &Config, parts: &[&str]) -> Result<Command, Box<dyn std
                 parts.get(1).ok_or("Missing direction for focus")?,
             )?)
         }
-        "move" => {
-            if parts.len() < 2 {
-                return Err("move requires argument".into());
+        "move" if parts.len() == 1 => Command::InteractiveMove,
+        "resize" if parts.len() == 1 => Command::InteractiveResize,
+        "move" => match parts.get(1).ok_or("Missing move target")?.as_ref() {
+            "left" | "right" | "up" | "down" => {
+                Command::Move(parse_direction(parts.get(1).ok_or("Missing direction")?)?)
             }
-            match parts.get(1).ok_or("Missing move target")?.as_ref() {
-                "left" | "right" | "up" | "down" => {
-                    Command::Move(parse_direction(parts.get(1).ok_or("Missing direction")?)?)
-                }
-                "container" | "window" => {
-                    if parts.len() >= 4 && parts[2] == "to" && parts[3] == "workspace" {
-                        Command::MoveToWorkspace(parse_workspace_target(&parts[4..])?)
-                    } else {
-                        Command::Raw(parts.join(" "))
-                    }
+            "container" | "window" => {
+                if parts[2..] == ["to", "output", "pointer"] {
+                    Command::MoveWindowToPointerOutput
+                } else if parts.len() >= 4 && parts[2] == "to" && parts[3] == "workspace" {
+                    Command::MoveToWorkspace(parse_workspace_target(&parts[4..])?)
+                } else if matches!(parts.get(2), Some(&"position" | &"absolute")) {
+                    parse_move_position(&parts[2..])?
+                } else {
+                    Command::Raw(parts.join(" "))
                 }
-                "workspace" => {
-                    if parts.len() >= 5 && parts[2] == "to" && parts[3] == "output" {
-                        Command::MoveWorkspaceToOutput(parse_direction(parts[4])?)
-                    } else {
-                        Command::Raw(parts.join(" "))
-                    }
+            }
+            "workspace" => {
+                if parts.len() >= 5 && parts[2] == "to" && parts[3] == "output" {
+                    Command::MoveWorkspaceToOutput(parse_output_target(parts[4])?)
+                } else {
+                    Command::Raw(parts.join(" "))
                 }
-                "scratchpad" => Command::Scratchpad(ScratchpadCommand::Move),
-                _ => Command::Raw(parts.join(" ")),
             }
-        }
+            "scratchpad" => Command::Scratchpad(ScratchpadCommand::Move),
+            "position" | "absolute" => parse_move_position(&parts[1..])?,
+            _ => Command::Raw(parts.join(" ")),
+        },
+        "resize" if parts[1] == "set" => parse_resize_set(&parts[2..])?,
+        "resize" if matches!(parts[1], "grow" | "shrink") => parse_resize_by(&parts[1..])?,
+        "output" => match parts[1..] {
+            [name, "scale", value] => Command::SetScale {
+                output: name.to_string(),
+                scale: parse_scale(value)?,
+            },
+            [name, "mode" | "resolution" | "res", spec] => parse_output_mode(name, spec)?,
+            _ => Command::Raw(parts.join(" ")),
+        },
         "workspace" => Command::Workspace(parse_workspace_target(&parts[1..])?),
         "fullscreen" => {
-            if parts.len() >= 2 {
-                match parts[1] {
-                    "container" => Command::FullscreenContainer,
-                    "virtual" | "virtual_output" => Command::FullscreenVirtualOutput,
-                    "physical" | "physical_output" => Command::FullscreenPhysicalOutput,
-                    _ => Command::Fullscreen,
+            use crate::window::FullscreenMode;
+            let (state, target) = match parts[1..] {
+                [state @ ("enable" | "disable" | "toggle"), ref target @ ..] => (state, target),
+                ref target => ("toggle", target),
+            };
+            let mode = match target {
+                [] => None,
+                ["container"] => Some(FullscreenMode::Container),
+                ["virtual" | "virtual_output"] => Some(FullscreenMode::VirtualOutput),
+                ["physical" | "physical_output"] => Some(FullscreenMode::PhysicalOutput),
+                // Unknown targets used to fall back to the default toggle
+                _ if state == "toggle" => None,
+                _ => return Err(format!("Unknown fullscreen mode: {}", target.join(" ")).into()),
+            };
+            match (state, mode) {
+                ("toggle", None) => Command::Fullscreen,
+                ("toggle", Some(FullscreenMode::Container)) => Command::FullscreenContainer,
+                ("toggle", Some(FullscreenMode::VirtualOutput)) => Command::FullscreenVirtualOutput,
+                ("toggle", Some(FullscreenMode::PhysicalOutput)) => {
+                    Command::FullscreenPhysicalOutput
                 }
-            } else {
-                Command::Fullscreen
+                (state, mode) => Command::SetFullscreen {
+                    enable: state == "enable",
+                    mode: mode.unwrap_or(FullscreenMode::VirtualOutput),
+                },
             }
         }
-        "floating" => {
-            if parts.len() >= 2 && parts[1] == "toggle" {
-                Command::FloatingToggle
+        "floating" => match parts.get(1).map(|s| s.as_ref()) {
+            Some("toggle") => Command::FloatingToggle,
+            Some("enable") => Command::FloatingEnable,
+            Some("disable") => Command::FloatingDisable,
+            _ => Command::Raw(parts.join(" ")),
+        },
+        "focus_follows_mouse" => match parts.get(1).map(|s| s.as_ref()) {
+            Some("enable" | "yes") => Command::FocusFollowsMouse(true),
+            Some("disable" | "no") => Command::FocusFollowsMouse(false),
+            Some("toggle") => Command::ToggleFocusFollowsMouse,
+            _ => return Err("focus_follows_mouse requires 'enable', 'disable' or 'toggle'".into()),
+        },
+        "overview" => Command::Overview(match parts.get(1).map(|s| s.as_ref()) {
+            None | Some("toggle") => Toggle::Toggle,
+            Some("enable") => Toggle::Enable,
+            Some("disable") => Toggle::Disable,
+            Some(other) => return Err(format!("Unknown overview argument: {other}").into()),
+        }),
+        "debug_overlay" => Command::DebugOverlay(match parts.get(1).map(|s| s.as_ref()) {
+            None | Some("toggle") => Toggle::Toggle,
+            Some("enable") => Toggle::Enable,
+            Some("disable") => Toggle::Disable,
+            Some(other) => return Err(format!("Unknown debug_overlay argument: {other}").into()),
+        }),
+        "cursor_mode" => Command::CursorMode(match parts.get(1).map(|s| s.as_ref()) {
+            Some("hardware") => CursorMode::Hardware,
+            Some("software") => CursorMode::Software,
+            _ => return Err("cursor_mode requires 'hardware' or 'software'".into()),
+        }),
+        "save_layout" | "append_layout" => {
+            let path = parts[1..].join(" ");
+            if path.is_empty() {
+                return Err(format!("{} requires a file path", parts[0]).into());
+            }
+            if parts[0] == "save_layout" {
+                Command::SaveLayout(path)
             } else {
-                Command::Raw(parts.join(" "))
+                Command::AppendLayout(path)
             }
         }
         "layout" => {
@@ -332,11 +929,14 @@ fn parse_command(config: &Config, parts: &[&str]) -> Result<Command, Box<dyn std
         }
         "mode" => {
             if parts.len() >= 2 && parts[1] == "toggle" {
-                Command::FocusModeToggle
-            } else if parts.len() >= 2 && parts[1] == "\"resize\"" {
-                Command::ResizeMode
+                Command::FocusMode(FocusMode::ModeToggle)
             } else {
-                Command::Raw(parts.join(" "))
+                let name = parts[1..].join(" ");
+                let name = name.trim_matches('"');
+                if name.is_empty() {
+                    return Err("mode requires a name".into());
+                }
+                Command::Mode(name.to_string())
             }
         }
         _ => Command::Raw(parts.join(" ")),
@@ -345,6 +945,74 @@ fn parse_command(config: &Config, parts: &[&str]) -> Result<Command, Box<dyn std
     Ok(cmd)
 }
 
+/// `[absolute] position center|mouse|<x> [px] <y> [px]`, after `move [window]`
+fn parse_move_position(parts: &[&str]) -> Result<Command, Box<dyn std::error::Error>> {
+    let (absolute, parts) = match parts {
+        ["absolute", rest @ ..] => (true, rest),
+        _ => (false, parts),
+    };
+    let ["position", args @ ..] = parts else {
+        return Err("Expected 'position' after 'move absolute'".into());
+    };
+    let args: Vec<&str> = args.iter().copied().filter(|arg| *arg != "px").collect();
+    let position = match args.as_slice() {
+        ["center"] => FloatingPosition::Center,
+        ["mouse" | "cursor" | "pointer"] => FloatingPosition::Cursor,
+        [x, y] => FloatingPosition::Position {
+            x: x.parse()?,
+            y: y.parse()?,
+            absolute,
+        },
+        _ => return Err("move position requires 'center', 'mouse' or '<x> <y>'".into()),
+    };
+    Ok(Command::MoveFloating(position))
+}
+
+/// `[width] <w> [px] [height] <h> [px]`, after `resize set`
+fn parse_resize_set(parts: &[&str]) -> Result<Command, Box<dyn std::error::Error>> {
+    let args: Vec<&str> = parts
+        .iter()
+        .copied()
+        .filter(|arg| !matches!(*arg, "width" | "height" | "px"))
+        .collect();
+    let [width, height] = args.as_slice() else {
+        return Err("resize set requires a width and a height in pixels".into());
+    };
+    let (width, height): (i32, i32) = (width.parse()?, height.parse()?);
+    if width <= 0 || height <= 0 {
+        return Err("resize set requires a positive width and height".into());
+    }
+    Ok(Command::ResizeFloating { width, height })
+}
+
+/// `grow|shrink width|height|left|right|up|down [<n> [px]] [or <n> ppt]`,
+/// after `resize`. Sizes go by pixels, 10 unless given.
+fn parse_resize_by(parts: &[&str]) -> Result<Command, Box<dyn std::error::Error>> {
+    let sign = match parts[0] {
+        "grow" => 1,
+        _ => -1,
+    };
+    let direction = match parts.get(1).copied() {
+        Some("width" | "horizontal") => Direction::Right,
+        Some("height" | "vertical") => Direction::Down,
+        Some(direction) => parse_direction(direction)?,
+        None => return Err(format!("resize {} requires width or height", parts[0]).into()),
+    };
+    let amount = match parts.get(2) {
+        Some(amount) => amount
+            .parse::<i32>()
+            .map_err(|_| format!("Invalid resize amount: {amount}"))?,
+        None => 10,
+    };
+    if amount < 0 {
+        return Err("resize amount can't be negative".into());
+    }
+    Ok(Command::Resize {
+        direction,
+        amount: sign * amount,
+    })
+}
+
 fn parse_direction(dir: &str) -> Result<Direction, Box<dyn std::error::Error>> {
     match dir {
         "left" => Ok(Direction::Left),
@@ -355,6 +1023,15 @@ fn parse_direction(dir: &str) -> Result<Direction, Box<dyn std::error::Error>> {
     }
 }
 
+/// A direction, `primary` or an output name
+fn parse_output_target(target: &str) -> Result<OutputTarget, Box<dyn std::error::Error>> {
+    Ok(match target {
+        "primary" => OutputTarget::Primary,
+        "left" | "right" | "up" | "down" => OutputTarget::Direction(parse_direction(target)?),
+        name => OutputTarget::Name(name.to_string()),
+    })
+}
+
 fn parse_workspace_target(parts: &[&str]) -> Result<WorkspaceTarget, Box<dyn std::error::Error>> {
     let first = parts.first().ok_or("Workspace target required")?;
 
@@ -369,14 +1046,66 @@ fn parse_workspace_target(parts: &[&str]) -> Result<WorkspaceTarget, Box<dyn std
         "8" => Ok(WorkspaceTarget::Number(8)),
         "9" => Ok(WorkspaceTarget::Number(9)),
         "10" => Ok(WorkspaceTarget::Number(10)),
-        "next" | "next_on_output" => Ok(WorkspaceTarget::Next),
-        "prev" | "previous" | "prev_on_output" => Ok(WorkspaceTarget::Previous),
+        "next" => Ok(WorkspaceTarget::Next),
+        "prev" | "previous" => Ok(WorkspaceTarget::Previous),
+        "next_on_output" => Ok(WorkspaceTarget::NextOnOutput),
+        "prev_on_output" => Ok(WorkspaceTarget::PreviousOnOutput),
         name => Ok(WorkspaceTarget::Name(name.to_string())),
     }
 }
 
+/// Output scale factor, within the range outputs support
+fn parse_scale(value: &str) -> Result<f64, Box<dyn std::error::Error>> {
+    let scale: f64 = value
+        .parse()
+        .map_err(|_| format!("Invalid scale value: {value}"))?;
+    if !(0.5..=4.0).contains(&scale) {
+        return Err("Scale must be between 0.5 and 4.0".into());
+    }
+    Ok(scale)
+}
+
+/// `<width>x<height>[@<rate>[Hz]]`, with the rate in hertz
+fn parse_output_mode(name: &str, spec: &str) -> Result<Command, Box<dyn std::error::Error>> {
+    let (size, rate) = match spec.split_once('@') {
+        Some((size, rate)) => (size, Some(rate)),
+        None => (spec, None),
+    };
+    let (width, height) = size
+        .split_once('x')
+        .ok_or_else(|| format!("Invalid mode: {spec}"))?;
+    let width: i32 = width
+        .parse()
+        .map_err(|_| format!("Invalid width: {width}"))?;
+    let height: i32 = height
+        .parse()
+        .map_err(|_| format!("Invalid height: {height}"))?;
+    if width <= 0 || height <= 0 {
+        return Err(format!("Invalid mode: {spec}").into());
+    }
+    let refresh = match rate {
+        Some(rate) => {
+            let hz: f64 = rate
+                .trim_end_matches("Hz")
+                .parse()
+                .map_err(|_| format!("Invalid refresh rate: {rate}"))?;
+            if hz <= 0.0 {
+                return Err(format!("Invalid refresh rate: {rate}").into());
+            }
+            Some((hz * 1000.0).round() as u32)
+        }
+        None => None,
+    };
+    Ok(Command::OutputMode {
+        name: name.to_string(),
+        width,
+        height,
+        refresh,
+    })
+}
+
 fn parse_output(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
-    // Format: output <name> [scale <value>] [resolution <WxH>] [position <x,y>] [transform <value>] [split <horizontal|vertical|grid> <count>]
+    // Format: output <name> [scale <value>] [resolution <WxH>] [position <x,y>] [transform <value>] [split <horizontal|vertical|grid> <count>] [primary] [gaps inner <px>] [border <px>] [xwayland_scale <value>]
     // Note: resolution and position are in physical pixels
     // Note: parts[0] is the output name since we're called with &parts[1..]
     // Transform values match sway: normal, 90, 180, 270, flipped, flipped-90, flipped-180, flipped-270
@@ -395,19 +1124,21 @@ fn parse_output(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::
         split: None,
         physical_size_mm: None,
         physical_position_mm: None,
+        primary: false,
+        gaps_inner: None,
+        border_width: None,
+        xwayland_scale: None,
     };
 
     let mut i = 1; // Start at 1 since parts[0] is the output name
     while i < parts.len() {
         match parts[i] {
             "scale" if i + 1 < parts.len() => {
-                let scale: f64 = parts[i + 1]
-                    .parse()
-                    .map_err(|_| format!("Invalid scale value: {}", parts[i + 1]))?;
-                if scale < 0.5 || scale > 4.0 {
-                    return Err("Scale must be between 0.5 and 4.0".into());
-                }
-                output_config.scale = Some(scale);
+                output_config.scale = Some(parse_scale(parts[i + 1])?);
+                i += 2;
+            }
+            "xwayland_scale" if i + 1 < parts.len() => {
+                output_config.xwayland_scale = Some(parse_scale(parts[i + 1])?);
                 i += 2;
             }
             "resolution" if i + 1 < parts.len() => {
@@ -535,6 +1266,26 @@ fn parse_output(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::
                 output_config.physical_position_mm = Some((x_mm, y_mm));
                 i += 2;
             }
+            "primary" => {
+                output_config.primary = true;
+                i += 1;
+            }
+            "gaps" if i + 2 < parts.len() && parts[i + 1] == "inner" => {
+                output_config.gaps_inner = Some(
+                    parts[i + 2]
+                        .parse()
+                        .map_err(|_| format!("Invalid gap: {}", parts[i + 2]))?,
+                );
+                i += 3;
+            }
+            "border" if i + 1 < parts.len() => {
+                output_config.border_width = Some(
+                    parts[i + 1]
+                        .parse()
+                        .map_err(|_| format!("Invalid border width: {}", parts[i + 1]))?,
+                );
+                i += 2;
+            }
             "split" if i + 2 < parts.len() => {
                 match parts[i + 1] {
                     "horizontal" | "h" => {
@@ -671,12 +1422,49 @@ fn parse_virtual_output(
     Ok(())
 }
 
-fn parse_workspace(
-    _config: &mut Config,
-    _parts: &[&str],
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Workspace configuration would be parsed here
-    // For now, workspaces are created dynamically as needed
+fn parse_workspace(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    // Format: workspace <1-10> [order <position> | output <name> | persistent]
+    let number = parts.first().ok_or("workspace requires a number")?;
+    let number = number
+        .parse::<u8>()
+        .ok()
+        .filter(|n| (1..=10).contains(n))
+        .ok_or_else(|| format!("workspace requires a number from 1 to 10, got {number}"))?;
+
+    let mut directive = WorkspaceConfig {
+        number,
+        output: None,
+        order: None,
+        persistent: false,
+    };
+    match parts[1..] {
+        ["order", position] => {
+            let position = position
+                .parse::<i32>()
+                .map_err(|_| format!("Invalid workspace order: {position}"))?;
+            directive.order = Some(position);
+        }
+        ["output", name] => directive.output = Some(name.to_string()),
+        ["persistent"] => directive.persistent = true,
+        // Other per-workspace settings aren't supported, the directive
+        // still places the workspace in the order
+        _ => {}
+    }
+
+    // Repeated directives for one workspace add to its first entry, which
+    // keeps its place in the order
+    match config
+        .workspaces
+        .iter_mut()
+        .find(|workspace| workspace.number == number)
+    {
+        Some(workspace) => {
+            workspace.order = directive.order.or(workspace.order);
+            workspace.output = directive.output.or(workspace.output.take());
+            workspace.persistent |= directive.persistent;
+        }
+        None => config.workspaces.push(directive),
+    }
     Ok(())
 }
 
@@ -700,6 +1488,36 @@ fn parse_gaps(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::er
     Ok(())
 }
 
+/// `gaps <scope> [all] [set|plus|minus] <px>`, the runtime form of `gaps`
+fn parse_gaps_command(parts: &[&str]) -> Result<Command, Box<dyn std::error::Error>> {
+    let usage = "gaps requires 'inner|outer|top|bottom|left|right [all] [set|plus|minus] <px>'";
+    let (scope, rest) = parts.split_first().ok_or(usage)?;
+    let scope = match *scope {
+        "inner" => GapScope::Inner,
+        "outer" => GapScope::Outer,
+        "top" => GapScope::Top,
+        "bottom" => GapScope::Bottom,
+        "left" => GapScope::Left,
+        "right" => GapScope::Right,
+        _ => return Err(usage.into()),
+    };
+    let rest = match rest {
+        ["current", ..] => return Err("gaps only supports 'all', not per workspace gaps".into()),
+        ["all", rest @ ..] => rest,
+        rest => rest,
+    };
+    let amount = match rest {
+        [px] | ["set", px] => GapAmount::Set(px.parse()?),
+        ["plus", px] => GapAmount::Plus(px.parse()?),
+        ["minus", px] => GapAmount::Minus(px.parse()?),
+        _ => return Err(usage.into()),
+    };
+    if matches!(amount, GapAmount::Set(px) | GapAmount::Plus(px) | GapAmount::Minus(px) if px < 0) {
+        return Err("Gaps can't be negative".into());
+    }
+    Ok(Command::Gaps { scope, amount })
+}
+
 fn parse_border(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
     if parts.len() >= 2 && parts.first() == Some(&"pixel") {
         config.border.width = parts.get(1).ok_or("Missing border width")?.parse()?;
@@ -707,6 +1525,25 @@ fn parse_border(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::
     Ok(())
 }
 
+fn parse_hide_edge_borders(
+    config: &mut Config,
+    parts: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    config.border.hide_edge =
+        match parts {
+            ["none"] => HideEdgeBorders::None,
+            ["vertical"] => HideEdgeBorders::Vertical,
+            ["horizontal"] => HideEdgeBorders::Horizontal,
+            ["both"] => HideEdgeBorders::Both,
+            ["smart"] => HideEdgeBorders::Smart,
+            _ => return Err(
+                "hide_edge_borders requires 'none', 'vertical', 'horizontal', 'both' or 'smart'"
+                    .into(),
+            ),
+        };
+    Ok(())
+}
+
 fn parse_font(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
     if parts.len() >= 2 && parts.first() == Some(&"pango:") {
         config.font = parts[1..].join(" ");
@@ -714,6 +1551,320 @@ fn parse_font(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::er
     Ok(())
 }
 
+fn parse_title_format(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // The format keeps its spacing, so work on the raw line
+    let format = line["title_format".len()..].trim();
+    let format = format
+        .strip_prefix('"')
+        .and_then(|format| format.strip_suffix('"'))
+        .unwrap_or(format);
+    if format.is_empty() {
+        return Err("title_format requires a format".into());
+    }
+    config.title_format = format.to_string();
+    Ok(())
+}
+
+fn parse_clipboard(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    match parts {
+        ["persist", value] => {
+            config.clipboard.persist = match *value {
+                "yes" | "enable" | "true" => true,
+                "no" | "disable" | "false" => false,
+                _ => return Err(format!("Invalid clipboard persist value: {value}").into()),
+            };
+        }
+        ["max_size", value] => {
+            let (number, multiplier) = match value.chars().last() {
+                Some('K' | 'k') => (&value[..value.len() - 1], 1024),
+                Some('M' | 'm') => (&value[..value.len() - 1], 1024 * 1024),
+                _ => (*value, 1),
+            };
+            config.clipboard.max_size = number.parse::<usize>()? * multiplier;
+        }
+        _ => return Err("clipboard requires 'persist <yes|no>' or 'max_size <bytes>'".into()),
+    }
+    Ok(())
+}
+
+fn parse_animations(config: &mut Config, parts: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let animations = &mut config.animations;
+    match parts {
+        ["animations", value] => {
+            animations.enabled = match *value {
+                "on" | "yes" | "enable" | "true" => true,
+                "off" | "no" | "disable" | "false" => false,
+                _ => return Err(format!("Invalid animations value: {value}").into()),
+            };
+        }
+        ["animation_duration", event, ms] => {
+            let ms = ms.trim_end_matches("ms").parse::<u32>()?;
+            match *event {
+                "workspace" => animations.workspace_duration = ms,
+                "window_open" => animations.window_open_duration = ms,
+                "window_close" => animations.window_close_duration = ms,
+                _ => return Err(format!("Unknown animation event: {event}").into()),
+            }
+        }
+        ["animation_easing", easing] => {
+            animations.easing = match *easing {
+                "linear" => Easing::Linear,
+                "ease_out" | "ease-out" => Easing::EaseOut,
+                "ease_in_out" | "ease-in-out" => Easing::EaseInOut,
+                _ => return Err(format!("Unknown easing: {easing}").into()),
+            };
+        }
+        _ => {
+            return Err(
+                "expected 'animations <on|off>', 'animation_duration <event> <ms>' or 'animation_easing <curve>'"
+                    .into(),
+            )
+        }
+    }
+    Ok(())
+}
+
+fn parse_bindgesture(
+    config: &mut Config,
+    parts: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if parts.len() < 2 {
+        return Err("bindgesture requires a gesture and command".into());
+    }
+
+    // <kind>:<fingers>:<direction>, fingers defaulting to 3 for swipes and
+    // holds and 2 for pinches. Holds have no direction.
+    let (kind, fingers, direction) = match parts[0].split(':').collect::<Vec<_>>()[..] {
+        ["hold"] => (GestureKind::Hold, 3, ""),
+        ["hold", fingers] => (GestureKind::Hold, fingers.parse::<u32>()?, ""),
+        [kind, direction] => {
+            let kind = parse_gesture_kind(kind)?;
+            let fingers = match kind {
+                GestureKind::Swipe | GestureKind::Hold => 3,
+                GestureKind::Pinch => 2,
+            };
+            (kind, fingers, direction)
+        }
+        [kind, fingers, direction] => (
+            parse_gesture_kind(kind)?,
+            fingers.parse::<u32>()?,
+            direction,
+        ),
+        _ => return Err(format!("Invalid gesture: {}", parts[0]).into()),
+    };
+
+    let direction = match (kind, direction) {
+        (GestureKind::Swipe, "left") => GestureDirection::Left,
+        (GestureKind::Swipe, "right") => GestureDirection::Right,
+        (GestureKind::Swipe, "up") => GestureDirection::Up,
+        (GestureKind::Swipe, "down") => GestureDirection::Down,
+        (GestureKind::Pinch, "inward") => GestureDirection::Inward,
+        (GestureKind::Pinch, "outward") => GestureDirection::Outward,
+        (GestureKind::Hold, "") => GestureDirection::Still,
+        _ => return Err(format!("Invalid direction for {}: {direction}", parts[0]).into()),
+    };
+    if fingers < 2 {
+        return Err(format!("Gestures need at least 2 fingers: {}", parts[0]).into());
+    }
+
+    let command = parse_command_chain(config, &parts[1..])?;
+
+    // A later binding for the same gesture replaces the earlier (or default) one
+    let bindings = &mut config.gestures.bindings;
+    bindings.retain(|b| !(b.kind == kind && b.fingers == fingers && b.direction == direction));
+    bindings.push(GestureBinding {
+        kind,
+        fingers,
+        direction,
+        command,
+    });
+
+    Ok(())
+}
+
+fn parse_gesture_kind(kind: &str) -> Result<GestureKind, Box<dyn std::error::Error>> {
+    match kind {
+        "swipe" => Ok(GestureKind::Swipe),
+        "pinch" => Ok(GestureKind::Pinch),
+        "hold" => Ok(GestureKind::Hold),
+        _ => Err(format!("Unknown gesture: {kind}").into()),
+    }
+}
+
+fn parse_gesture_threshold(
+    config: &mut Config,
+    parts: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    match parts {
+        ["swipe", value] => config.gestures.swipe_threshold = value.parse()?,
+        ["pinch", value] => config.gestures.pinch_threshold = value.parse()?,
+        _ => return Err("gesture_threshold requires 'swipe <pixels>' or 'pinch <scale>'".into()),
+    }
+    Ok(())
+}
+
+fn parse_workspace_auto_back_and_forth(
+    config: &mut Config,
+    parts: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    config.workspace_auto_back_and_forth = match parts {
+        ["yes" | "enable" | "true" | "on"] => true,
+        ["no" | "disable" | "false" | "off"] => false,
+        _ => return Err("workspace_auto_back_and_forth requires 'yes' or 'no'".into()),
+    };
+    Ok(())
+}
+
+fn parse_status_bar(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // status_bar { position top; height 24; show_workspaces true; show_clock true }
+    let rest = line["status_bar".len()..].trim();
+    let content = rest
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or("status_bar settings must be in a { ... } block on one line")?;
+
+    let mut status_bar = StatusBarConfig::default();
+    for setting in content.split(';') {
+        let setting: Vec<&str> = setting.split_whitespace().collect();
+        match setting.as_slice() {
+            [] => {}
+            ["position", "top"] => status_bar.position = StatusBarPosition::Top,
+            ["position", "bottom"] => status_bar.position = StatusBarPosition::Bottom,
+            ["height", height] => {
+                status_bar.height = height.parse()?;
+                if status_bar.height <= 0 {
+                    return Err("status_bar height must be positive".into());
+                }
+            }
+            ["show_workspaces", value] => status_bar.show_workspaces = parse_flag(value)?,
+            ["show_clock", value] => status_bar.show_clock = parse_flag(value)?,
+            _ => return Err(format!("Unknown status_bar setting: {}", setting.join(" ")).into()),
+        }
+    }
+
+    config.status_bar = Some(status_bar);
+    Ok(())
+}
+
+fn parse_tab_bar(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // tab_bar { height 30; gap 4; font_size 10; active_background #333333; inactive_text #aaaaaa }
+    let rest = line["tab_bar".len()..].trim();
+    let content = rest
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or("tab_bar settings must be in a { ... } block on one line")?;
+
+    let tab_bar = &mut config.tab_bar;
+    for setting in content.split(';') {
+        let setting: Vec<&str> = setting.split_whitespace().collect();
+        match setting.as_slice() {
+            [] => {}
+            ["height", height] => {
+                let height: i32 = height.parse()?;
+                if height <= 0 {
+                    return Err("tab_bar height must be positive".into());
+                }
+                tab_bar.size.height = height;
+            }
+            ["gap", gap] => {
+                let gap: i32 = gap.parse()?;
+                if gap < 0 {
+                    return Err("tab_bar gap can't be negative".into());
+                }
+                tab_bar.size.gap = gap;
+            }
+            ["font_size", points] => {
+                let points: f64 = points.parse()?;
+                if !(points > 0.0 && points.is_finite()) {
+                    return Err("tab_bar font_size must be positive".into());
+                }
+                tab_bar.font_size = Some(points);
+            }
+            ["active_background", color] => tab_bar.colors.active_bg = parse_color(color)?,
+            ["inactive_background", color] => tab_bar.colors.inactive_bg = parse_color(color)?,
+            ["active_text", color] => tab_bar.colors.active_text = parse_color(color)?,
+            ["inactive_text", color] => tab_bar.colors.inactive_text = parse_color(color)?,
+            ["indicator", color] => tab_bar.colors.indicator = parse_color(color)?,
+            ["separator", color] => tab_bar.colors.border = parse_color(color)?,
+            _ => return Err(format!("Unknown tab_bar setting: {}", setting.join(" ")).into()),
+        }
+    }
+    Ok(())
+}
+
+/// `#rrggbb` or `#rrggbbaa` as RGBA components between 0 and 1
+fn parse_color(value: &str) -> Result<[f32; 4], Box<dyn std::error::Error>> {
+    let hex = value
+        .strip_prefix('#')
+        .filter(|hex| (hex.len() == 6 || hex.len() == 8) && hex.is_ascii())
+        .ok_or_else(|| format!("Invalid color '{value}', expected #rrggbb or #rrggbbaa"))?;
+
+    let mut color = [1.0; 4];
+    for (component, i) in color.iter_mut().zip((0..hex.len()).step_by(2)) {
+        *component = u8::from_str_radix(&hex[i..i + 2], 16)
+            .map_err(|_| format!("Invalid color '{value}', expected #rrggbb or #rrggbbaa"))?
+            as f32
+            / 255.0;
+    }
+    Ok(color)
+}
+
+fn parse_hide_cursor(
+    config: &mut Config,
+    parts: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    match parts {
+        ["timeout", timeout] | [timeout] => {
+            // 0 disables, like in sway
+            let timeout: u32 = timeout.parse()?;
+            config.hide_cursor_timeout = (timeout > 0).then_some(timeout);
+        }
+        ["when-typing", value] => config.hide_cursor_when_typing = parse_flag(value)?,
+        _ => return Err("hide_cursor requires 'timeout <ms>' or 'when-typing yes|no'".into()),
+    }
+    Ok(())
+}
+
+fn parse_flag(value: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    match value {
+        "yes" | "enable" | "true" | "on" => Ok(true),
+        "no" | "disable" | "false" | "off" => Ok(false),
+        _ => Err(format!("Expected 'true' or 'false', got '{value}'").into()),
+    }
+}
+
+fn parse_floating_modifier(
+    config: &mut Config,
+    parts: &[&str],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (combo, inverse) = match parts {
+        ["none"] => {
+            config.floating_modifier = None;
+            return Ok(());
+        }
+        [combo] | [combo, "normal"] => (combo, false),
+        [combo, "inverse"] => (combo, true),
+        _ => {
+            return Err("floating_modifier requires '<modifier> [normal|inverse]' or 'none'".into())
+        }
+    };
+
+    let mut modifiers = ModifiersState::default();
+    for name in combo.split('+') {
+        let name = match name.strip_prefix('$') {
+            Some(var) => config
+                .get_variable(var)
+                .ok_or_else(|| format!("Unknown variable: {name}"))?
+                .to_string(),
+            None => name.to_string(),
+        };
+        apply_modifier(&mut modifiers, &name)?;
+    }
+
+    config.floating_modifier = Some(FloatingModifier { modifiers, inverse });
+    Ok(())
+}
+
 fn parse_input(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error::Error>> {
     // Input lines are special - they have the format:
     // input <identifier> { <settings> }
@@ -748,6 +1899,8 @@ fn parse_input(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error
             tap: None,
             tap_button_map: None,
             scroll_method: None,
+            scroll_button: None,
+            scroll_factor: None,
             left_handed: None,
             middle_emulation: None,
         };
@@ -813,6 +1966,19 @@ fn parse_input(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error
                             _ => None,
                         };
                     }
+                    "scroll_button" => {
+                        input_config.scroll_button = match parse_button(setting[1]) {
+                            Some(BindingKey::Button(code)) => Some(code),
+                            _ => Some(setting[1].parse()?),
+                        };
+                    }
+                    "scroll_factor" => {
+                        let factor: f64 = setting[1].parse()?;
+                        if factor < 0.0 {
+                            return Err("scroll_factor must not be negative".into());
+                        }
+                        input_config.scroll_factor = Some(factor);
+                    }
                     "left_handed" => {
                         input_config.left_handed = match setting[1] {
                             "enabled" | "yes" | "true" | "on" => Some(true),
@@ -837,3 +2003,134 @@ fn parse_input(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error
 
     Ok(())
 }
+
+fn parse_for_window(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Format: for_window [key="value" ...] <command>
+    // Criteria values may be quoted and contain spaces, so work on the raw line
+    let rest = line["for_window".len()..].trim_start();
+    let (criteria, command) = parse_criteria(rest)?;
+
+    let command_parts: Vec<&str> = command.split_whitespace().collect();
+    if command_parts.is_empty() {
+        return Err("for_window requires a command".into());
+    }
+    let command = parse_command(config, &command_parts)?;
+    // A rule sets a window's value, there is no earlier one to toggle
+    if matches!(command, Command::ToggleFocusFollowsMouse) {
+        return Err("for_window focus_follows_mouse requires 'enable' or 'disable'".into());
+    }
+
+    config.window_rules.push(WindowRule { criteria, command });
+    Ok(())
+}
+
+fn parse_assign(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Format: assign [key="value" ...] [→] [workspace] [number] <1-10>
+    let rest = line["assign".len()..].trim_start();
+    let (criteria, target) = parse_criteria(rest)?;
+
+    let number = target
+        .split_whitespace()
+        .find(|part| !matches!(*part, "→" | "workspace" | "number"))
+        .ok_or("assign requires a workspace")?;
+    let workspace = number
+        .parse::<u8>()
+        .ok()
+        .filter(|n| (1..=10).contains(n))
+        .ok_or_else(|| format!("assign requires a workspace number from 1 to 10, got {number}"))?;
+
+    config.assignments.push(Assignment {
+        criteria,
+        workspace,
+    });
+    Ok(())
+}
+
+fn parse_no_focus(config: &mut Config, line: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Format: no_focus [key="value" ...]
+    let rest = line["no_focus".len()..].trim_start();
+    let (criteria, trailing) = parse_criteria(rest)?;
+    if !trailing.is_empty() {
+        return Err(format!("Unexpected arguments after no_focus criteria: {trailing}").into());
+    }
+
+    config.no_focus_rules.push(NoFocusRule { criteria });
+    Ok(())
+}
+
+/// Parse a `[key="value" ...]` criteria block, returning it and the remainder of the line
+fn parse_criteria(input: &str) -> Result<(Criteria, &str), Box<dyn std::error::Error>> {
+    let inner = input
+        .strip_prefix('[')
+        .ok_or("Criteria must start with '['")?;
+
+    let mut criteria = Criteria::default();
+    let mut chars = inner.char_indices().peekable();
+    let mut any = false;
+
+    loop {
+        // Skip whitespace between entries
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+        let Some(&(start, c)) = chars.peek() else {
+            return Err("Unterminated criteria, missing ']'".into());
+        };
+        if c == ']' {
+            if !any {
+                return Err("Empty criteria".into());
+            }
+            return Ok((criteria, inner[start + 1..].trim()));
+        }
+
+        // Key up to '='
+        let mut key_end = start;
+        while let Some((i, c)) = chars.next() {
+            if c == '=' {
+                key_end = i;
+                break;
+            }
+        }
+        let key = inner[start..key_end].trim();
+        if key.is_empty() {
+            return Err("Criteria entry is missing a key".into());
+        }
+
+        // Value, optionally quoted. Only \" is unescaped, other backslashes
+        // are left for the regex
+        let value = if chars.next_if(|(_, c)| *c == '"').is_some() {
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) => {
+                        if chars.next_if(|(_, c)| *c == '"').is_some() {
+                            value.push('"');
+                        } else {
+                            value.push('\\');
+                        }
+                    }
+                    Some((_, c)) => value.push(c),
+                    None => return Err("Unterminated quoted criteria value".into()),
+                }
+            }
+            value
+        } else {
+            let mut value = String::new();
+            while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && *c != ']') {
+                value.push(c);
+            }
+            value
+        };
+
+        let pattern = || Pattern::new(&value).map_err(|e| format!("Invalid {key} pattern: {e}"));
+        match key {
+            "app_id" => criteria.app_id = Some(pattern()?),
+            "class" => criteria.class = Some(pattern()?),
+            "instance" => criteria.instance = Some(pattern()?),
+            "title" => criteria.title = Some(pattern()?),
+            "window_type" => criteria.window_type = Some(value),
+            _ => return Err(format!("Unknown criteria key: {key}").into()),
+        }
+        any = true;
+    }
+}
@@ -1,5 +1,15 @@
 use super::*;
-use crate::config::parser::parse_config;
+use crate::config::parser::{parse_bindings, parse_config};
+
+/// Properties of a window as criteria see them
+fn window(app_id: Option<&str>, class: Option<&str>, title: &str) -> WindowCriteria {
+    WindowCriteria {
+        app_id: app_id.map(str::to_string),
+        class: class.map(str::to_string),
+        title: title.to_string(),
+        ..Default::default()
+    }
+}
 
 #[test]
 fn test_parse_input_config_keyboard() {
@@ -31,6 +41,28 @@ fn test_parse_input_config_touchpad() {
     assert!(matches!(input.scroll_method, Some(ScrollMethod::TwoFinger)));
 }
 
+#[test]
+fn test_parse_input_config_scrolling() {
+    let config_str = r#"input type:pointer { natural_scroll enabled scroll_factor 0.5 scroll_method on_button_down scroll_button button8 }"#;
+
+    let config = parse_config(config_str).unwrap();
+    let input = &config.input_configs[0];
+    assert_eq!(input.natural_scroll, Some(true));
+    assert_eq!(input.scroll_factor, Some(0.5));
+    assert!(matches!(
+        input.scroll_method,
+        Some(ScrollMethod::OnButtonDown)
+    ));
+    assert_eq!(input.scroll_button, Some(0x113));
+
+    // Raw event codes work too
+    let config = parse_config("input * { scroll_button 274 }").unwrap();
+    assert_eq!(config.input_configs[0].scroll_button, Some(0x112));
+
+    let config = parse_config("input * { scroll_factor -2 }").unwrap();
+    assert!(config.input_configs.is_empty());
+}
+
 #[test]
 fn test_parse_input_config_pointer() {
     let config_str =
@@ -45,3 +77,1618 @@ fn test_parse_input_config_pointer() {
     assert_eq!(input.left_handed, Some(true));
     assert_eq!(input.middle_emulation, Some(true));
 }
+
+#[test]
+fn test_parse_for_window_floating_rules() {
+    let config_str = r#"
+for_window [app_id="pavucontrol"] floating enable
+for_window [class="Gimp" title="Export Image as PNG"] floating disable
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.window_rules.len(), 2);
+
+    let rule = &config.window_rules[1];
+    assert_eq!(
+        rule.criteria.class.as_ref().map(Pattern::as_str),
+        Some("Gimp")
+    );
+    assert_eq!(
+        rule.criteria.title.as_ref().map(Pattern::as_str),
+        Some("Export Image as PNG")
+    );
+    assert!(matches!(rule.command, Command::FloatingDisable));
+
+    assert_eq!(
+        config.floating_override(&window(Some("pavucontrol"), None, "Volume Control")),
+        Some(true)
+    );
+    assert_eq!(
+        config.floating_override(&window(None, Some("Gimp"), "Export Image as PNG")),
+        Some(false)
+    );
+    assert_eq!(
+        config.floating_override(&window(Some("foot"), None, "foot")),
+        None
+    );
+}
+
+#[test]
+fn test_parse_clipboard() {
+    let config = parse_config("").unwrap();
+    assert!(!config.clipboard.persist);
+
+    let config_str = r#"
+clipboard persist yes
+clipboard max_size 2M
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert!(config.clipboard.persist);
+    assert_eq!(config.clipboard.max_size, 2 * 1024 * 1024);
+}
+
+#[test]
+fn test_parse_bindgesture() {
+    let config = parse_config("").unwrap();
+    assert!(matches!(
+        config
+            .gestures
+            .find(GestureKind::Swipe, 3, GestureDirection::Left)
+            .map(|b| &b.command),
+        Some(Command::Workspace(WorkspaceTarget::Next))
+    ));
+
+    let config_str = r#"
+bindgesture swipe:3:left workspace prev
+bindgesture swipe:up exec rofi
+bindgesture pinch:4:inward kill
+gesture_threshold swipe 50
+gesture_threshold pinch 0.1
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    let gestures = &config.gestures;
+    assert!(matches!(
+        gestures
+            .find(GestureKind::Swipe, 3, GestureDirection::Left)
+            .map(|b| &b.command),
+        Some(Command::Workspace(WorkspaceTarget::Previous))
+    ));
+    assert!(matches!(
+        gestures
+            .find(GestureKind::Swipe, 3, GestureDirection::Up)
+            .map(|b| &b.command),
+        Some(Command::Exec(cmd)) if cmd == "rofi"
+    ));
+    assert!(matches!(
+        gestures
+            .find(GestureKind::Pinch, 4, GestureDirection::Inward)
+            .map(|b| &b.command),
+        Some(Command::Kill)
+    ));
+    assert_eq!(gestures.swipe_threshold, 50.0);
+    assert_eq!(gestures.pinch_threshold, 0.1);
+
+    // Invalid bindings are skipped
+    let config =
+        parse_config("bindgesture swipe:3:inward kill\nbindgesture swipe:1:left kill").unwrap();
+    assert_eq!(
+        config.gestures.bindings.len(),
+        GestureConfig::default().bindings.len()
+    );
+}
+
+#[test]
+fn test_parse_bindgesture_hold() {
+    let config = parse_config(
+        "bindgesture hold overview
+bindgesture hold:4 exec rofi",
+    )
+    .unwrap();
+    let gestures = &config.gestures;
+    assert!(gestures.binds(GestureKind::Hold, 3));
+    assert!(matches!(
+        gestures
+            .find(GestureKind::Hold, 3, GestureDirection::Still)
+            .map(|b| &b.command),
+        Some(Command::Overview(Toggle::Toggle))
+    ));
+    assert!(matches!(
+        gestures
+            .find(GestureKind::Hold, 4, GestureDirection::Still)
+            .map(|b| &b.command),
+        Some(Command::Exec(cmd)) if cmd == "rofi"
+    ));
+    // Holds don't replace the swipes on the same fingers
+    assert!(gestures
+        .find(GestureKind::Swipe, 3, GestureDirection::Left)
+        .is_some());
+
+    // Holds have no direction, swipes and pinches need one
+    let config = parse_config(
+        "bindgesture hold:3:left kill
+bindgesture hold:1 kill
+bindgesture swipe:3 kill",
+    )
+    .unwrap();
+    assert!(!config.gestures.binds(GestureKind::Hold, 3));
+    assert!(!config.gestures.binds(GestureKind::Hold, 1));
+    assert_eq!(
+        config.gestures.bindings.len(),
+        GestureConfig::default().bindings.len()
+    );
+}
+
+#[test]
+fn test_parse_command_chain_and_nop() {
+    let config_str = r#"
+bindsym Mod4+x move left; focus right
+bindsym Mod4+n nop shadowed scroll
+bindsym Mod4+e exec "notify-send 'a; b'"; bogus_layout_cmd; layout nonsense; kill
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.keybindings.len(), 3);
+
+    match &config.keybindings[0].command {
+        Command::Sequence(commands) => {
+            assert_eq!(commands.len(), 2);
+            assert!(matches!(commands[0], Command::Move(Direction::Left)));
+            assert!(matches!(commands[1], Command::Focus(Direction::Right)));
+        }
+        other => panic!("Expected a sequence, got {other:?}"),
+    }
+
+    assert!(matches!(
+        &config.keybindings[1].command,
+        Command::Nop(Some(comment)) if comment == "shadowed scroll"
+    ));
+
+    // Quoted semicolons stay in the command, and an invalid sub-command is skipped
+    match &config.keybindings[2].command {
+        Command::Sequence(commands) => {
+            assert_eq!(commands.len(), 3);
+            assert!(matches!(&commands[0], Command::Exec(cmd) if cmd == "\"notify-send 'a; b'\""));
+            assert!(matches!(commands[1], Command::Raw(_)));
+            assert!(matches!(commands[2], Command::Kill));
+        }
+        other => panic!("Expected a sequence, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_bindsym_flags() {
+    let config_str = r#"
+bindsym --release Print exec grim
+bindsym --locked --no-repeat XF86AudioMute workspace next
+bindsym Mod4+Return exec foot
+bindsym --bogus Mod4+q kill
+bindsym Mod4+l focus right
+bindsym Mod4+Shift+q focus left; kill
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.keybindings.len(), 5);
+
+    let print = &config.keybindings[0];
+    assert!(print.release && print.repeat && !print.locked);
+    assert!(matches!(&print.command, Command::Exec(cmd) if cmd == "grim"));
+
+    // --locked is stored, though there is no session lock for it to matter yet
+    let mute = &config.keybindings[1];
+    assert!(!mute.release && !mute.repeat && mute.locked);
+
+    // Every command repeats unless bound with --no-repeat
+    let term = &config.keybindings[2];
+    assert!(!term.release && term.repeat && !term.locked);
+    assert!(config.keybindings[3].repeat);
+    assert!(config.keybindings[4].repeat);
+}
+
+#[test]
+fn test_parse_mouse_bindings() {
+    let config_str = r#"
+set $mod Mod4
+bindsym --whole-window $mod+button1 move
+bindsym $mod+button3 resize
+bindsym button4 workspace prev
+bindsym $mod+button9 kill
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.keybindings.len(), 4);
+
+    let drag = &config.keybindings[0];
+    assert_eq!(drag.key, BindingKey::Button(0x110));
+    assert!(drag.whole_window && drag.modifiers.logo);
+    assert!(matches!(drag.command, Command::InteractiveMove));
+
+    let resize = &config.keybindings[1];
+    assert_eq!(resize.key, BindingKey::Button(0x111));
+    assert!(!resize.whole_window);
+    assert!(matches!(resize.command, Command::InteractiveResize));
+
+    assert_eq!(config.keybindings[2].key, BindingKey::Wheel(Direction::Up));
+    assert_eq!(config.keybindings[3].key, BindingKey::Button(0x114));
+}
+
+#[test]
+fn test_parse_bindcode_and_chords() {
+    let config_str = r#"
+set $mod Mod4
+bindcode 24 kill
+bindcode --release $mod+Mod2+38 exec foot
+bindcode 38+39 workspace number 1
+bindsym $mod+a+s exec grim
+bindsym Mod5+Mod3+XF86Calculator exec qalculate
+bindsym a+button1 kill
+bindcode q kill
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.keycode_bindings.len(), 3);
+    assert_eq!(config.keybindings.len(), 2);
+
+    let kill = &config.keycode_bindings[&24][0];
+    assert_eq!(kill.key, BindingKey::Code(24));
+    assert!(kill.held.is_empty() && !kill.modifiers.logo);
+
+    let foot = &config.keycode_bindings[&38][0];
+    assert!(foot.release && foot.modifiers.logo && foot.modifiers.num_lock);
+
+    let chord = &config.keycode_bindings[&39][0];
+    assert_eq!(chord.held, vec![BindingKey::Code(38)]);
+
+    let grim = &config.keybindings[0];
+    assert_eq!(grim.key, BindingKey::Key(Keysym::s));
+    assert_eq!(grim.held, vec![BindingKey::Key(Keysym::a)]);
+    assert!(grim.modifiers.logo);
+
+    let calc = &config.keybindings[1];
+    assert_eq!(calc.key, BindingKey::Key(Keysym::XF86_Calculator));
+    assert!(calc.modifiers.iso_level3_shift && calc.modifiers.iso_level5_shift);
+}
+
+#[test]
+fn test_parse_bindings_only() {
+    let config_str = r#"
+set $mod Mod4
+gaps inner 10
+exec foot
+bindsym $mod+Return exec foot
+bindcode $mod+24 kill
+bindsym $mod+Shift+c reload bindings
+"#;
+
+    let bindings = parse_bindings(config_str).unwrap();
+    assert_eq!(bindings.keybindings.len(), 2);
+    assert!(bindings.keybindings[0].modifiers.logo);
+    assert!(matches!(
+        bindings.keybindings[1].command,
+        Command::ReloadBindings
+    ));
+    assert_eq!(bindings.keycode_bindings[&24].len(), 1);
+
+    // A broken binding fails the whole reload instead of being dropped
+    assert!(parse_bindings("bindsym Mod4+Return exec foot\nbindsym Mod4+Bogus_Key kill").is_err());
+    assert!(parse_bindings("bindsym --bogus Mod4+q kill").is_err());
+}
+
+#[test]
+fn test_parse_mode_blocks() {
+    let config_str = r#"
+set $mod Mod4
+mode "resize" {
+    bindsym h resize shrink width 10 px
+    bindsym j resize grow height
+    bindsym Escape mode "default"
+}
+mode --pango_markup "launch" {
+    bindcode 41 exec firefox
+}
+bindsym $mod+r mode "resize"
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.keybindings.len(), 4);
+    let modes: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| binding.mode.as_deref())
+        .collect();
+    assert_eq!(
+        modes,
+        vec![Some("resize"), Some("resize"), Some("resize"), None]
+    );
+    assert_eq!(
+        config.keycode_bindings[&41][0].mode.as_deref(),
+        Some("launch")
+    );
+    assert_eq!(config.binding_modes(), vec!["default", "resize", "launch"]);
+
+    assert!(matches!(
+        config.keybindings[0].command,
+        Command::Resize {
+            direction: Direction::Right,
+            amount: -10
+        }
+    ));
+    assert!(matches!(
+        config.keybindings[1].command,
+        Command::Resize {
+            direction: Direction::Down,
+            amount: 10
+        }
+    ));
+    assert!(matches!(&config.keybindings[2].command, Command::Mode(name) if name == "default"));
+    assert!(matches!(&config.keybindings[3].command, Command::Mode(name) if name == "resize"));
+
+    // Reloading the bindings keeps their modes, and only bindings go in a block
+    let bindings = parse_bindings(config_str).unwrap();
+    assert_eq!(bindings.keybindings[0].mode.as_deref(), Some("resize"));
+    assert!(parse_bindings("mode \"resize\" {\n    gaps inner 10\n}").is_err());
+    assert!(parse_bindings("mode \"resize\" {\n    bindsym h kill").is_err());
+}
+
+#[test]
+fn test_set_bindings_keeps_rest_of_config() {
+    let mut config = parse_config("gaps inner 10\nbindsym Mod4+q kill").unwrap();
+    let bindings = parse_bindings("bindcode Mod4+24 kill").unwrap();
+    config.set_bindings(bindings);
+
+    assert!(config.keybindings.is_empty());
+    assert_eq!(config.keycode_bindings[&24].len(), 1);
+    assert_eq!(config.gaps.inner, Some(10));
+}
+
+#[test]
+fn test_parse_floating_modifier() {
+    // Defaults to $mod
+    let config = parse_config("set $mod Mod1\n").unwrap();
+    let floating = config.floating_modifier.unwrap();
+    assert!(floating.modifiers.alt && !floating.modifiers.logo);
+    assert!(!floating.inverse);
+
+    let config = parse_config("set $mod Mod1\nfloating_modifier Mod4+Shift inverse\n").unwrap();
+    let floating = config.floating_modifier.unwrap();
+    assert!(floating.modifiers.logo && floating.modifiers.shift && !floating.modifiers.alt);
+    assert!(floating.inverse);
+
+    let config = parse_config("set $mod Mod4\nfloating_modifier none\n").unwrap();
+    assert!(config.floating_modifier.is_none());
+
+    // No default without $mod
+    let config = parse_config("").unwrap();
+    assert!(config.floating_modifier.is_none());
+
+    // A line that fails to parse leaves the default alone
+    let config = parse_config("set $mod Mod1\nfloating_modifier Bogus\n").unwrap();
+    let floating = config.floating_modifier.unwrap();
+    assert!(floating.modifiers.alt);
+}
+
+#[test]
+fn test_parse_overview_command() {
+    let config_str = r#"
+bindsym Mod4+o overview
+bindsym Mod4+Shift+o overview disable
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.keybindings.len(), 2);
+    assert!(matches!(
+        config.keybindings[0].command,
+        Command::Overview(Toggle::Toggle)
+    ));
+    assert!(matches!(
+        config.keybindings[1].command,
+        Command::Overview(Toggle::Disable)
+    ));
+}
+
+#[test]
+fn test_parse_animations() {
+    let config = parse_config("").unwrap();
+    assert!(!config.animations.enabled);
+
+    let config_str = r#"
+animations on
+animation_duration workspace 250
+animation_duration window_open 100ms
+animation_duration window_close 80
+animation_easing ease_in_out
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert!(config.animations.enabled);
+    assert_eq!(config.animations.workspace_duration, 250);
+    assert_eq!(config.animations.window_open_duration, 100);
+    assert_eq!(config.animations.window_close_duration, 80);
+    assert_eq!(config.animations.easing, Easing::EaseInOut);
+}
+
+#[test]
+fn test_parse_hide_edge_borders() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.border.hide_edge, HideEdgeBorders::None);
+
+    let config = parse_config("hide_edge_borders smart").unwrap();
+    assert_eq!(config.border.hide_edge, HideEdgeBorders::Smart);
+
+    let config = parse_config("hide_edge_borders vertical").unwrap();
+    assert_eq!(config.border.hide_edge, HideEdgeBorders::Vertical);
+
+    // Invalid values keep the default
+    let config = parse_config("hide_edge_borders sideways").unwrap();
+    assert_eq!(config.border.hide_edge, HideEdgeBorders::None);
+}
+
+#[test]
+fn test_parse_debug_overlay_command() {
+    let config = parse_config("bindsym Mod4+F12 debug_overlay").unwrap();
+    assert_eq!(config.keybindings.len(), 1);
+    assert!(matches!(
+        config.keybindings[0].command,
+        Command::DebugOverlay(Toggle::Toggle)
+    ));
+}
+
+#[test]
+fn test_parse_cursor_mode_command() {
+    let config = parse_config(
+        "bindsym Mod4+F11 cursor_mode software\nbindsym Mod4+Shift+F11 cursor_mode hardware",
+    )
+    .unwrap();
+    assert_eq!(config.keybindings.len(), 2);
+    assert!(matches!(
+        config.keybindings[0].command,
+        Command::CursorMode(CursorMode::Software)
+    ));
+    assert!(matches!(
+        config.keybindings[1].command,
+        Command::CursorMode(CursorMode::Hardware)
+    ));
+
+    // Without a mode the binding is skipped
+    let config = parse_config("bindsym Mod4+F11 cursor_mode").unwrap();
+    assert!(config.keybindings.is_empty());
+}
+
+#[test]
+fn test_parse_scratchpad_commands() {
+    let config = parse_config(
+        "bindsym Mod4+Shift+minus move scratchpad\nbindsym Mod4+minus scratchpad show",
+    )
+    .unwrap();
+    assert_eq!(config.keybindings.len(), 2);
+    assert!(matches!(
+        config.keybindings[0].command,
+        Command::Scratchpad(ScratchpadCommand::Move)
+    ));
+    assert!(matches!(
+        config.keybindings[1].command,
+        Command::Scratchpad(ScratchpadCommand::Show)
+    ));
+}
+
+#[test]
+fn test_parse_workspace_auto_back_and_forth() {
+    let config = parse_config("").unwrap();
+    assert!(!config.workspace_auto_back_and_forth);
+
+    let config = parse_config("workspace_auto_back_and_forth yes").unwrap();
+    assert!(config.workspace_auto_back_and_forth);
+
+    let config = parse_config("workspace_auto_back_and_forth no").unwrap();
+    assert!(!config.workspace_auto_back_and_forth);
+}
+
+#[test]
+fn test_parse_layout_file_commands() {
+    let config = parse_config(
+        "bindsym Mod4+F5 save_layout ~/.config/stilch/dev.json\n\
+         bindsym Mod4+F6 append_layout ~/.config/stilch/dev.json",
+    )
+    .unwrap();
+    assert_eq!(config.keybindings.len(), 2);
+    assert!(matches!(
+        &config.keybindings[0].command,
+        Command::SaveLayout(path) if path == "~/.config/stilch/dev.json"
+    ));
+    assert!(matches!(
+        &config.keybindings[1].command,
+        Command::AppendLayout(path) if path == "~/.config/stilch/dev.json"
+    ));
+}
+
+#[test]
+fn test_parse_assign() {
+    let config_str = r#"
+assign [app_id="firefox"] workspace 2
+assign [class="Steam"] → number 9
+assign [title="Mail"] 3
+assign [app_id="broken"] workspace 11
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.assignments.len(), 3);
+    assert_eq!(
+        config.assigned_workspace(&window(Some("firefox"), None, "Mozilla")),
+        Some(2)
+    );
+    assert_eq!(
+        config.assigned_workspace(&window(None, Some("Steam"), "Steam")),
+        Some(9)
+    );
+    assert_eq!(
+        config.assigned_workspace(&window(None, None, "Mail")),
+        Some(3)
+    );
+    assert_eq!(
+        config.assigned_workspace(&window(Some("foot"), None, "Mail - foot")),
+        None
+    );
+}
+
+#[test]
+fn test_parse_no_focus() {
+    let config_str = r#"
+no_focus [app_id="org.kde.kdeconnect.daemon"]
+no_focus [title="Picture-in-Picture"]
+no_focus
+"#;
+
+    let config = parse_config(config_str).unwrap();
+    assert_eq!(config.no_focus_rules.len(), 2);
+    assert!(config.no_focus(&window(
+        Some("org.kde.kdeconnect.daemon"),
+        None,
+        "KDE Connect"
+    )));
+    assert!(config.no_focus(&window(Some("firefox"), None, "Picture-in-Picture")));
+    assert!(!config.no_focus(&window(Some("firefox"), None, "Mozilla Firefox")));
+}
+
+#[test]
+fn test_parse_title_format() {
+    let config = parse_config("").unwrap();
+    assert_eq!(
+        config.format_title("Inbox", Some("thunderbird"), None, "2"),
+        "Inbox"
+    );
+
+    let config = parse_config(r#"title_format "[%app_id] %title (ws %workspace) 100%""#).unwrap();
+    assert_eq!(
+        config.format_title("Inbox %class", Some("thunderbird"), None, "2"),
+        "[thunderbird] Inbox %class (ws 2) 100%"
+    );
+    assert_eq!(
+        config.format_title("Steam", None, Some("steam"), "9"),
+        "[] Steam (ws 9) 100%"
+    );
+}
+
+#[test]
+fn test_parse_status_bar() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.status_bar, None);
+
+    let config = parse_config("status_bar { }").unwrap();
+    assert_eq!(config.status_bar, Some(StatusBarConfig::default()));
+
+    let config = parse_config(
+        "status_bar { position bottom; height 30; show_workspaces true; show_clock false }",
+    )
+    .unwrap();
+    assert_eq!(
+        config.status_bar,
+        Some(StatusBarConfig {
+            position: StatusBarPosition::Bottom,
+            height: 30,
+            show_workspaces: true,
+            show_clock: false,
+        })
+    );
+
+    // Invalid settings leave the bar disabled
+    let config = parse_config("status_bar { height -5 }").unwrap();
+    assert_eq!(config.status_bar, None);
+    let config = parse_config("status_bar { colour red }").unwrap();
+    assert_eq!(config.status_bar, None);
+}
+
+#[test]
+fn test_parse_hide_cursor() {
+    let config = parse_config("").unwrap();
+    assert!(!config.hide_cursor_when_typing);
+    assert_eq!(config.hide_cursor_timeout, None);
+
+    let config = parse_config("hide_cursor_when_typing yes\nhide_cursor timeout 3000").unwrap();
+    assert!(config.hide_cursor_when_typing);
+    assert_eq!(config.hide_cursor_timeout, Some(3000));
+
+    let config = parse_config("hide_cursor 5000\nhide_cursor when-typing on").unwrap();
+    assert!(config.hide_cursor_when_typing);
+    assert_eq!(config.hide_cursor_timeout, Some(5000));
+
+    // 0 turns the timeout off again
+    let config = parse_config("hide_cursor 5000\nhide_cursor 0").unwrap();
+    assert_eq!(config.hide_cursor_timeout, None);
+
+    let config = parse_config("hide_cursor soon\nhide_cursor_when_typing maybe").unwrap();
+    assert!(!config.hide_cursor_when_typing);
+    assert_eq!(config.hide_cursor_timeout, None);
+}
+
+#[test]
+fn test_parse_output_scale_command() {
+    let config = parse_config(
+        "bindsym Mod4+p output HDMI-A-1 scale 1.5\n\
+         bindsym Mod4+o output HDMI-A-1 scale 9\n\
+         output eDP-1 scale 1.25",
+    )
+    .unwrap();
+    assert_eq!(config.keybindings.len(), 1);
+    assert!(matches!(
+        &config.keybindings[0].command,
+        Command::SetScale { output, scale } if output == "HDMI-A-1" && *scale == 1.5
+    ));
+    assert_eq!(config.outputs[0].scale, Some(1.25));
+}
+
+#[test]
+fn test_parse_output_mode_command() {
+    let config = parse_config(
+        "bindsym Mod4+F1 output DP-1 mode 1280x720\n\
+         bindsym Mod4+F2 output DP-1 mode 2560x1440@143.998Hz\n\
+         bindsym Mod4+F3 output DP-1 resolution 1920x1080@60\n\
+         bindsym Mod4+F4 output DP-1 mode 1920by1080\n\
+         bindsym Mod4+F5 output DP-1 mode 1920x1080@fast",
+    )
+    .unwrap();
+    let modes: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::OutputMode {
+                name,
+                width,
+                height,
+                refresh,
+            } => (name.as_str(), *width, *height, *refresh),
+            command => panic!("unexpected command {command:?}"),
+        })
+        .collect();
+    assert_eq!(
+        modes,
+        vec![
+            ("DP-1", 1280, 720, None),
+            ("DP-1", 2560, 1440, Some(143_998)),
+            ("DP-1", 1920, 1080, Some(60_000)),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_workspace_switch_warp() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.workspace_switch_warp, WorkspaceSwitchWarp::Output);
+
+    let config = parse_config("workspace_switch_warp focused").unwrap();
+    assert_eq!(config.workspace_switch_warp, WorkspaceSwitchWarp::Focused);
+
+    let config = parse_config("workspace_switch_warp none").unwrap();
+    assert_eq!(config.workspace_switch_warp, WorkspaceSwitchWarp::None);
+
+    let config = parse_config("workspace_switch_warp none\nworkspace_switch_warp window").unwrap();
+    assert_eq!(config.workspace_switch_warp, WorkspaceSwitchWarp::None);
+}
+
+#[test]
+fn test_parse_workspace_switch_mode() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.workspace_switch_mode, WorkspaceSwitchMode::Follow);
+
+    let config = parse_config("workspace_switch_mode bring").unwrap();
+    assert_eq!(config.workspace_switch_mode, WorkspaceSwitchMode::Bring);
+
+    let config = parse_config("workspace_switch_mode bring\nworkspace_switch_mode swap").unwrap();
+    assert_eq!(config.workspace_switch_mode, WorkspaceSwitchMode::Bring);
+}
+
+#[test]
+fn test_parse_fullscreen_output_removed() {
+    let config = parse_config("").unwrap();
+    assert_eq!(
+        config.fullscreen_output_removed,
+        FullscreenOutputRemoved::Restore
+    );
+
+    let config = parse_config("fullscreen_output_removed move").unwrap();
+    assert_eq!(
+        config.fullscreen_output_removed,
+        FullscreenOutputRemoved::Move
+    );
+
+    let config =
+        parse_config("fullscreen_output_removed move\nfullscreen_output_removed exit").unwrap();
+    assert_eq!(
+        config.fullscreen_output_removed,
+        FullscreenOutputRemoved::Move
+    );
+}
+
+#[test]
+fn test_parse_fullscreen_enable_disable() {
+    use crate::window::FullscreenMode;
+
+    let config = parse_config(
+        "bindsym Mod4+a fullscreen enable\n\
+         bindsym Mod4+b fullscreen disable\n\
+         bindsym Mod4+c fullscreen enable physical\n\
+         bindsym Mod4+d fullscreen disable container\n\
+         bindsym Mod4+e fullscreen toggle container\n\
+         bindsym Mod4+f fullscreen\n\
+         bindsym Mod4+g fullscreen enable sideways",
+    )
+    .unwrap();
+    let commands: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| &binding.command)
+        .collect();
+    assert_eq!(commands.len(), 6);
+    assert!(matches!(
+        commands[0],
+        Command::SetFullscreen {
+            enable: true,
+            mode: FullscreenMode::VirtualOutput
+        }
+    ));
+    assert!(matches!(
+        commands[1],
+        Command::SetFullscreen {
+            enable: false,
+            mode: FullscreenMode::VirtualOutput
+        }
+    ));
+    assert!(matches!(
+        commands[2],
+        Command::SetFullscreen {
+            enable: true,
+            mode: FullscreenMode::PhysicalOutput
+        }
+    ));
+    assert!(matches!(
+        commands[3],
+        Command::SetFullscreen {
+            enable: false,
+            mode: FullscreenMode::Container
+        }
+    ));
+    assert!(matches!(commands[4], Command::FullscreenContainer));
+    assert!(matches!(commands[5], Command::Fullscreen));
+}
+
+#[test]
+fn test_parse_double_click_time() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.double_click_time, 400);
+
+    let config = parse_config("double_click_time 250").unwrap();
+    assert_eq!(config.double_click_time, 250);
+
+    let config = parse_config("double_click_time 300ms").unwrap();
+    assert_eq!(config.double_click_time, 300);
+
+    let config = parse_config("double_click_time 0\ndouble_click_time fast").unwrap();
+    assert_eq!(config.double_click_time, 400);
+}
+
+#[test]
+fn test_parse_floating_placement_commands() {
+    let config = parse_config(
+        "bindsym Mod4+c move position center\n\
+         bindsym Mod4+m move window position mouse\n\
+         bindsym Mod4+p move position 100 px 50 px\n\
+         bindsym Mod4+a move container absolute position 2000 10\n\
+         bindsym Mod4+r resize set width 800 px height 600 px\n\
+         bindsym Mod4+s resize set 640 480",
+    )
+    .unwrap();
+    let commands: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| &binding.command)
+        .collect();
+    assert_eq!(commands.len(), 6);
+    assert!(matches!(
+        commands[0],
+        Command::MoveFloating(FloatingPosition::Center)
+    ));
+    assert!(matches!(
+        commands[1],
+        Command::MoveFloating(FloatingPosition::Cursor)
+    ));
+    assert!(matches!(
+        commands[2],
+        Command::MoveFloating(FloatingPosition::Position {
+            x: 100,
+            y: 50,
+            absolute: false
+        })
+    ));
+    assert!(matches!(
+        commands[3],
+        Command::MoveFloating(FloatingPosition::Position {
+            x: 2000,
+            y: 10,
+            absolute: true
+        })
+    ));
+    assert!(matches!(
+        commands[4],
+        Command::ResizeFloating {
+            width: 800,
+            height: 600
+        }
+    ));
+    assert!(matches!(
+        commands[5],
+        Command::ResizeFloating {
+            width: 640,
+            height: 480
+        }
+    ));
+
+    // Malformed placements are rejected rather than bound
+    let config =
+        parse_config("bindsym Mod4+c move position somewhere\nbindsym Mod4+r resize set 0 480")
+            .unwrap();
+    assert!(config.keybindings.is_empty());
+}
+
+#[test]
+fn test_parse_smart_borders() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.border.smart, SmartBorders::Off);
+
+    let config = parse_config("smart_borders on").unwrap();
+    assert_eq!(config.border.smart, SmartBorders::On);
+
+    let config = parse_config("smart_borders no_gaps\nhide_edge_borders both").unwrap();
+    assert_eq!(config.border.smart, SmartBorders::NoGaps);
+    assert_eq!(config.border.hide_edge, HideEdgeBorders::Both);
+
+    // Invalid values keep the default
+    let config = parse_config("smart_borders sometimes").unwrap();
+    assert_eq!(config.border.smart, SmartBorders::Off);
+}
+
+#[test]
+fn test_workspace_order() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.workspace_order(), (1..=10).collect::<Vec<u8>>());
+
+    // Directive order, with unconfigured workspaces after
+    let config = parse_config("workspace 3\nworkspace 1 output DP-1\nworkspace 2").unwrap();
+    assert_eq!(config.workspaces.len(), 3);
+    assert_eq!(config.workspaces[1].output.as_deref(), Some("DP-1"));
+    assert_eq!(
+        config.workspace_order(),
+        vec![3, 1, 2, 4, 5, 6, 7, 8, 9, 10]
+    );
+
+    // Explicit orders, negative ones first; repeats keep the first position
+    let config = parse_config(
+        "workspace 5 order 10\n\
+         workspace 9 order -1\n\
+         workspace 2\n\
+         workspace 5 output HDMI-A-1\n\
+         workspace 11 order 0\n\
+         workspace 4 order first",
+    )
+    .unwrap();
+    assert_eq!(config.workspaces.len(), 3);
+    assert_eq!(config.workspaces[0].order, Some(10));
+    assert_eq!(config.workspaces[0].output.as_deref(), Some("HDMI-A-1"));
+    assert_eq!(
+        config.workspace_order(),
+        vec![9, 2, 5, 1, 3, 4, 6, 7, 8, 10]
+    );
+}
+
+#[test]
+fn test_parse_workspace_next_on_output() {
+    let config = parse_config(
+        "bindsym Mod4+n workspace next\n\
+         bindsym Mod4+p workspace prev\n\
+         bindsym Mod4+Shift+n workspace next_on_output\n\
+         bindsym Mod4+Shift+p workspace prev_on_output",
+    )
+    .unwrap();
+
+    let targets: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::Workspace(target) => target.clone(),
+            command => panic!("Unexpected command {command:?}"),
+        })
+        .collect();
+    assert!(matches!(
+        targets[..],
+        [
+            WorkspaceTarget::Next,
+            WorkspaceTarget::Previous,
+            WorkspaceTarget::NextOnOutput,
+            WorkspaceTarget::PreviousOnOutput
+        ]
+    ));
+}
+
+#[test]
+fn test_parse_workspace_persistent() {
+    let config = parse_config(
+        "workspace 1 persistent\n\
+         workspace 2 order 5\n\
+         workspace 2 persistent\n\
+         workspace 3",
+    )
+    .unwrap();
+    let persistent: Vec<u8> = config
+        .workspaces
+        .iter()
+        .filter(|workspace| workspace.persistent)
+        .map(|workspace| workspace.number)
+        .collect();
+    assert_eq!(persistent, vec![1, 2]);
+    assert_eq!(config.workspaces[1].order, Some(5));
+}
+
+#[test]
+fn test_parse_shortcuts_inhibit_escape() {
+    let config = parse_config("").unwrap();
+    let escape = config.shortcuts_inhibit_escape.unwrap();
+    assert!(escape.modifiers.logo);
+    assert_eq!(escape.key, Keysym::Escape);
+
+    let config = parse_config("set $mod Mod1\nshortcuts_inhibit_escape $mod+Shift+F12").unwrap();
+    let escape = config.shortcuts_inhibit_escape.unwrap();
+    assert!(escape.modifiers.alt && escape.modifiers.shift && !escape.modifiers.logo);
+    assert_eq!(escape.key, Keysym::F12);
+
+    let config = parse_config("shortcuts_inhibit_escape none").unwrap();
+    assert!(config.shortcuts_inhibit_escape.is_none());
+
+    // A mouse button is rejected, keeping the default
+    let config = parse_config("shortcuts_inhibit_escape Mod4+button1").unwrap();
+    assert!(config.shortcuts_inhibit_escape.is_some());
+}
+
+#[test]
+fn test_parse_swap_workspace_with_output() {
+    let config = parse_config(
+        "bindsym Mod4+x swap workspace with output left\n\
+         bindsym Mod4+Shift+x swap workspace with output DP-1\n\
+         bindsym Mod4+Ctrl+x swap workspace left",
+    )
+    .unwrap();
+
+    let targets: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::SwapOutputWorkspaces(target) => target.clone(),
+            command => panic!("Unexpected command {command:?}"),
+        })
+        .collect();
+    assert_eq!(
+        targets,
+        [
+            OutputTarget::Direction(Direction::Left),
+            OutputTarget::Name("DP-1".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_move_workspace_to_output() {
+    let config = parse_config(
+        "bindsym Mod4+Ctrl+l move workspace to output right\n\
+         bindsym Mod4+Ctrl+1 move workspace to output DP-1\n\
+         bindsym Mod4+Ctrl+p move workspace to output primary",
+    )
+    .unwrap();
+
+    let targets: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::MoveWorkspaceToOutput(target) => target.clone(),
+            command => panic!("Unexpected command {command:?}"),
+        })
+        .collect();
+    assert_eq!(
+        targets,
+        [
+            OutputTarget::Direction(Direction::Right),
+            OutputTarget::Name("DP-1".to_string()),
+            OutputTarget::Primary,
+        ]
+    );
+}
+
+#[test]
+fn test_parse_output_primary() {
+    let config = parse_config(
+        "output HDMI-A-1 scale 1\n\
+         output DP-1 position 1920,0 primary\n\
+         bindsym Mod4+o focus output primary\n\
+         bindsym Mod4+Shift+o focus output right\n\
+         bindsym Mod4+Ctrl+o focus output HDMI-A-1",
+    )
+    .unwrap();
+
+    assert_eq!(config.primary_output(), Some("DP-1"));
+    assert_eq!(config.outputs[1].position, Some((1920, 0)));
+
+    let targets: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::FocusOutput(target) => target.clone(),
+            command => panic!("Unexpected command {command:?}"),
+        })
+        .collect();
+    assert_eq!(
+        targets,
+        [
+            OutputTarget::Primary,
+            OutputTarget::Direction(Direction::Right),
+            OutputTarget::Name("HDMI-A-1".to_string()),
+        ]
+    );
+
+    let config = parse_config("output HDMI-A-1 scale 1").unwrap();
+    assert_eq!(config.primary_output(), None);
+}
+
+#[test]
+fn test_parse_render_node() {
+    let config = parse_config("render_node /dev/dri/renderD129").unwrap();
+    assert_eq!(config.render_node.as_deref(), Some("/dev/dri/renderD129"));
+
+    let config = parse_config("gaps inner 5").unwrap();
+    assert_eq!(config.render_node, None);
+}
+
+#[test]
+fn test_parse_max_render_fps() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.max_render_fps, None);
+    assert_eq!(config.min_render_interval(), None);
+
+    let config = parse_config("max_render_fps 50").unwrap();
+    assert_eq!(config.max_render_fps, Some(50));
+    assert_eq!(
+        config.min_render_interval(),
+        Some(std::time::Duration::from_millis(20))
+    );
+
+    let config = parse_config("max_render_fps 50\nmax_render_fps off").unwrap();
+    assert_eq!(config.max_render_fps, None);
+
+    let config = parse_config("max_render_fps 50\nmax_render_fps 0").unwrap();
+    assert_eq!(config.max_render_fps, Some(50));
+}
+
+#[test]
+fn test_parse_rotate_container() {
+    let config = parse_config(
+        "bindsym Mod4+r rotate container clockwise\n\
+         bindsym Mod4+Shift+r rotate container counterclockwise\n\
+         bindsym Mod4+t rotate sideways",
+    )
+    .unwrap();
+
+    let directions: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::RotateContainer(direction) => *direction,
+            other => panic!("Expected RotateContainer, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        directions,
+        vec![
+            RotateDirection::Clockwise,
+            RotateDirection::CounterClockwise
+        ]
+    );
+}
+
+#[test]
+fn test_parse_balance() {
+    let config = parse_config(
+        "bindsym Mod4+b balance\n\
+         bindsym Mod4+Shift+b balance container\n\
+         bindsym Mod4+Ctrl+b balance workspace\n\
+         bindsym Mod4+n balance everything",
+    )
+    .unwrap();
+
+    let scopes: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::BalanceSplits { scope } => *scope,
+            other => panic!("Expected BalanceSplits, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        scopes,
+        vec![
+            BalanceScope::Workspace,
+            BalanceScope::Container,
+            BalanceScope::Workspace
+        ]
+    );
+}
+
+#[test]
+fn test_parse_new_window_position() {
+    let config = parse_config("gaps inner 5").unwrap();
+    assert_eq!(config.new_window_position, NewWindowPosition::End);
+
+    let config = parse_config("new_window_position after_focused").unwrap();
+    assert_eq!(config.new_window_position, NewWindowPosition::AfterFocused);
+
+    let config = parse_config("new_window_position beginning").unwrap();
+    assert_eq!(config.new_window_position, NewWindowPosition::Beginning);
+
+    let config = parse_config("new_window_position middle").unwrap();
+    assert_eq!(config.new_window_position, NewWindowPosition::End);
+}
+
+#[test]
+fn test_parse_workspace_state_file() {
+    let config = parse_config("gaps inner 5").unwrap();
+    assert_eq!(config.workspace_state_file, None);
+
+    let config =
+        parse_config("workspace_state_file ~/.local/state/stilch/workspaces.json").unwrap();
+    assert_eq!(
+        config.workspace_state_file.as_deref(),
+        Some("~/.local/state/stilch/workspaces.json")
+    );
+
+    // Without a path the line is skipped
+    let config = parse_config("workspace_state_file").unwrap();
+    assert_eq!(config.workspace_state_file, None);
+}
+
+#[test]
+fn test_parse_remember_window_geometry() {
+    let config = parse_config("gaps inner 5").unwrap();
+    assert!(!config.remember_window_geometry);
+
+    let config = parse_config("remember_window_geometry yes").unwrap();
+    assert!(config.remember_window_geometry);
+
+    // Invalid lines are skipped, leaving the earlier value
+    let config = parse_config(
+        "remember_window_geometry yes\nremember_window_geometry\nremember_window_geometry maybe",
+    )
+    .unwrap();
+    assert!(config.remember_window_geometry);
+}
+
+#[test]
+fn test_parse_focus_mode() {
+    let config = parse_config(
+        "bindsym Mod4+space focus mode_toggle\n\
+         bindsym Mod4+t focus tiling\n\
+         bindsym Mod4+f focus floating",
+    )
+    .unwrap();
+
+    let modes: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::FocusMode(mode) => *mode,
+            other => panic!("Expected FocusMode, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        modes,
+        vec![
+            FocusMode::ModeToggle,
+            FocusMode::Tiling,
+            FocusMode::Floating
+        ]
+    );
+}
+
+#[test]
+fn test_parse_focus_on_window_activation() {
+    // Like i3, smart unless configured otherwise
+    let config = parse_config("gaps inner 5").unwrap();
+    assert_eq!(
+        config.focus_on_window_activation,
+        FocusOnWindowActivation::Smart
+    );
+
+    let config = parse_config("focus_on_window_activation urgent").unwrap();
+    assert_eq!(
+        config.focus_on_window_activation,
+        FocusOnWindowActivation::Urgent
+    );
+
+    let config = parse_config("focus_on_window_activation focus").unwrap();
+    assert_eq!(
+        config.focus_on_window_activation,
+        FocusOnWindowActivation::Focus
+    );
+
+    let config = parse_config("focus_on_window_activation smart").unwrap();
+    assert_eq!(
+        config.focus_on_window_activation,
+        FocusOnWindowActivation::Smart
+    );
+
+    // An unknown value is skipped, leaving the earlier one
+    let config =
+        parse_config("focus_on_window_activation focus\nfocus_on_window_activation always")
+            .unwrap();
+    assert_eq!(
+        config.focus_on_window_activation,
+        FocusOnWindowActivation::Focus
+    );
+}
+
+#[test]
+fn test_parse_focus_urgent() {
+    let config = parse_config("bindsym Mod4+u focus urgent").unwrap();
+    assert!(matches!(
+        config.keybindings[0].command,
+        Command::FocusUrgent
+    ));
+}
+
+#[test]
+fn test_parse_focus_back_and_forward() {
+    let config = parse_config("bindsym Mod4+o focus back\nbindsym Mod4+i focus forward").unwrap();
+    assert!(matches!(config.keybindings[0].command, Command::FocusBack));
+    assert!(matches!(
+        config.keybindings[1].command,
+        Command::FocusForward
+    ));
+}
+
+#[test]
+fn test_parse_focus_tree_commands() {
+    let config = parse_config(
+        "bindsym Mod4+a focus parent
+         bindsym Mod4+Shift+a focus child
+         bindsym Mod4+Tab focus next
+         bindsym Mod4+Shift+Tab focus prev sibling",
+    )
+    .unwrap();
+    assert_eq!(config.keybindings.len(), 4);
+    assert!(matches!(
+        config.keybindings[0].command,
+        Command::FocusParent
+    ));
+    assert!(matches!(config.keybindings[1].command, Command::FocusChild));
+    assert!(matches!(
+        config.keybindings[2].command,
+        Command::FocusSibling(SiblingDirection::Next)
+    ));
+    assert!(matches!(
+        config.keybindings[3].command,
+        Command::FocusSibling(SiblingDirection::Prev)
+    ));
+
+    // Anything but 'sibling' after next/prev skips the binding
+    let config = parse_config("bindsym Mod4+Tab focus next window").unwrap();
+    assert!(config.keybindings.is_empty());
+}
+
+#[test]
+fn test_parse_move_to_pointer_output() {
+    let config = parse_config(
+        "bindsym Mod4+p move container to output pointer
+         bindsym Mod4+Shift+p move window to output pointer",
+    )
+    .unwrap();
+    assert!(config
+        .keybindings
+        .iter()
+        .all(|binding| matches!(binding.command, Command::MoveWindowToPointerOutput)));
+}
+
+#[test]
+fn test_parse_output_gaps_and_border() {
+    let config =
+        parse_config("output DP-1 scale 2 gaps inner 20 border 4\noutput DP-2 scale 1").unwrap();
+    assert_eq!(config.outputs[0].gaps_inner, Some(20));
+    assert_eq!(config.outputs[0].border_width, Some(4));
+    assert_eq!(config.outputs[1].gaps_inner, None);
+    assert_eq!(config.outputs[1].border_width, None);
+
+    // An output line with an invalid gap is skipped as a whole
+    let config = parse_config("output DP-1 gaps inner wide").unwrap();
+    assert!(config.outputs.is_empty());
+}
+
+#[test]
+fn test_parse_tab_bar() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.tab_bar, TabBarConfig::default());
+    assert_eq!(config.tab_bar.size.height, crate::tab_bar::TAB_BAR_HEIGHT);
+
+    let config = parse_config(
+        "tab_bar { height 24; gap 4; active_background #ff0000; inactive_text #00000080 }",
+    )
+    .unwrap();
+    assert_eq!(config.tab_bar.size.height, 24);
+    assert_eq!(config.tab_bar.size.gap, 4);
+    assert_eq!(config.tab_bar.colors.active_bg, [1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(
+        config.tab_bar.colors.inactive_text,
+        [0.0, 0.0, 0.0, 128.0 / 255.0]
+    );
+    assert_eq!(
+        config.tab_bar.colors.border,
+        crate::tab_bar::TabBarColors::default().border
+    );
+
+    assert_eq!(config.tab_bar.font_size, None);
+
+    let config = parse_config("tab_bar { font_size 10.5 }").unwrap();
+    assert_eq!(config.tab_bar.font_size, Some(10.5));
+    let config = parse_config("tab_bar { font_size 10.5 }\ntab_bar { font_size 0 }").unwrap();
+    assert_eq!(config.tab_bar.font_size, Some(10.5));
+
+    // Invalid settings are skipped, keeping what was there
+    let config = parse_config(
+        "tab_bar { height 24; gap 4 }\n\
+         tab_bar { height 0 }\n\
+         tab_bar { gap -1 }\n\
+         tab_bar { indicator blue }\n\
+         tab_bar { separator #12345 }",
+    )
+    .unwrap();
+    assert_eq!(config.tab_bar.size.height, 24);
+    assert_eq!(config.tab_bar.size.gap, 4);
+    assert_eq!(
+        config.tab_bar.colors.indicator,
+        crate::tab_bar::TabBarColors::default().indicator
+    );
+    assert_eq!(
+        config.tab_bar.colors.border,
+        crate::tab_bar::TabBarColors::default().border
+    );
+}
+
+#[test]
+fn test_parse_focus_follows_mouse_delay() {
+    let config = parse_config("").unwrap();
+    assert_eq!(config.focus_follows_mouse_delay_ms, 0);
+
+    let config = parse_config("focus_follows_mouse_delay_ms 150").unwrap();
+    assert_eq!(config.focus_follows_mouse_delay_ms, 150);
+
+    let config = parse_config("focus_follows_mouse_delay_ms 80ms").unwrap();
+    assert_eq!(config.focus_follows_mouse_delay_ms, 80);
+
+    let config = parse_config("focus_follows_mouse_delay_ms soon").unwrap();
+    assert_eq!(config.focus_follows_mouse_delay_ms, 0);
+}
+
+#[test]
+fn test_parse_focus_follows_mouse_rule() {
+    let config = parse_config(
+        r#"for_window [app_id="krita"] focus_follows_mouse disable
+           for_window [app_id="krita" title="Preview"] focus_follows_mouse enable"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.focus_follows_mouse_override(&window(Some("krita"), None, "Untitled")),
+        Some(false)
+    );
+    // The last matching rule wins
+    assert_eq!(
+        config.focus_follows_mouse_override(&window(Some("krita"), None, "Preview")),
+        Some(true)
+    );
+    assert_eq!(
+        config.focus_follows_mouse_override(&window(Some("foot"), None, "Untitled")),
+        None
+    );
+
+    let config = parse_config(r#"for_window [app_id="krita"] focus_follows_mouse maybe"#).unwrap();
+    assert!(config.window_rules.is_empty());
+
+    let config = parse_config(r#"for_window [app_id="krita"] focus_follows_mouse toggle"#).unwrap();
+    assert!(config.window_rules.is_empty());
+}
+
+#[test]
+fn test_parse_xwayland_scale() {
+    assert_eq!(
+        parse_config("").unwrap().xwayland_scale,
+        XWaylandScale::Fixed(1.0)
+    );
+    assert_eq!(
+        parse_config("xwayland_scale 2").unwrap().xwayland_scale,
+        XWaylandScale::Fixed(2.0)
+    );
+
+    let config =
+        parse_config("xwayland_scale auto\noutput DP-1 scale 1.5 xwayland_scale 2").unwrap();
+    assert_eq!(config.xwayland_scale, XWaylandScale::FollowOutput);
+    assert_eq!(config.outputs[0].scale, Some(1.5));
+    assert_eq!(config.outputs[0].xwayland_scale, Some(2.0));
+
+    // Out of range scales are rejected
+    let config = parse_config("xwayland_scale 9").unwrap();
+    assert_eq!(config.xwayland_scale, XWaylandScale::Fixed(1.0));
+}
+
+#[test]
+fn test_parse_xwayland_enable() {
+    assert!(parse_config("").unwrap().xwayland);
+    assert!(!parse_config("xwayland disable").unwrap().xwayland);
+    assert!(
+        parse_config("xwayland disable\nxwayland enable")
+            .unwrap()
+            .xwayland
+    );
+
+    // There is no on-demand start, lazy starts XWayland right away
+    assert!(parse_config("xwayland lazy").unwrap().xwayland);
+    assert!(
+        parse_config("xwayland disable\nxwayland lazy")
+            .unwrap()
+            .xwayland
+    );
+}
+
+#[test]
+fn test_criteria_patterns() {
+    let config = parse_config(
+        r#"for_window [title="^Export .*" class="(?i)gimp"] floating enable
+           for_window [class="Steam" instance="steamwebhelper" window_type="dialog"] floating disable
+           for_window [title="(unclosed"] floating enable"#,
+    )
+    .unwrap();
+    // The invalid pattern is rejected
+    assert_eq!(config.window_rules.len(), 2);
+
+    assert_eq!(
+        config.floating_override(&window(None, Some("GIMP"), "Export Image as PNG")),
+        Some(true)
+    );
+    // Patterns have to match the whole property
+    assert_eq!(
+        config.floating_override(&window(None, Some("gimp-2.10"), "Export Image")),
+        None
+    );
+
+    let mut steam = window(None, Some("Steam"), "Friends");
+    steam.instance = Some("steamwebhelper".into());
+    assert_eq!(config.floating_override(&steam), None);
+    steam.window_type = Some("dialog");
+    assert_eq!(config.floating_override(&steam), Some(false));
+
+    // Backslashes reach the regex, only \" is unescaped by the parser
+    let config = parse_config(
+        r#"for_window [title="Firefox \(Private Browsing\)"] floating enable
+           for_window [title="say \"hi\""] floating disable"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.floating_override(&window(None, None, "Firefox (Private Browsing)")),
+        Some(true)
+    );
+    assert_eq!(
+        config.floating_override(&window(None, None, "Firefox Private Browsing")),
+        None
+    );
+    assert_eq!(
+        config.floating_override(&window(None, None, r#"say "hi""#)),
+        Some(false)
+    );
+
+    // Saved layouts may hold patterns that don't compile, those match literally
+    let pattern: Pattern = serde_json::from_str(r#""(unclosed""#).unwrap();
+    assert!(pattern.matches("(unclosed"));
+    assert!(!pattern.matches("unclosed"));
+
+    // Literal patterns escape metacharacters and keep the text they match
+    let literal = Pattern::literal("Firefox (Private Browsing)");
+    assert!(literal.matches("Firefox (Private Browsing)"));
+    assert!(!literal.matches("Firefox Private Browsing"));
+    let saved = serde_json::to_string(&literal).unwrap();
+    assert_eq!(serde_json::from_str::<Pattern>(&saved).unwrap(), literal);
+}
+
+#[test]
+fn test_parse_focus_follows_mouse_binding() {
+    let config = parse_config(
+        "bindsym Mod4+m focus_follows_mouse toggle\nbindsym Mod4+Shift+m focus_follows_mouse disable",
+    )
+    .unwrap();
+    assert!(matches!(
+        config.keybindings[0].command,
+        Command::ToggleFocusFollowsMouse
+    ));
+    assert!(matches!(
+        config.keybindings[1].command,
+        Command::FocusFollowsMouse(false)
+    ));
+}
+
+#[test]
+fn test_parse_gaps_command() {
+    let config = parse_config(
+        "bindsym Mod4+g gaps inner 20\n\
+         bindsym Mod4+Shift+g gaps outer all plus 5\n\
+         bindsym Mod4+Ctrl+g gaps top set 0\n\
+         bindsym Mod4+Alt+g gaps left minus 5\n\
+         bindsym Mod4+h gaps inner current plus 5\n\
+         bindsym Mod4+j gaps inner -5",
+    )
+    .unwrap();
+
+    let gaps: Vec<_> = config
+        .keybindings
+        .iter()
+        .map(|binding| match &binding.command {
+            Command::Gaps { scope, amount } => (*scope, *amount),
+            command => panic!("Unexpected command {command:?}"),
+        })
+        .collect();
+    assert_eq!(
+        gaps,
+        [
+            (GapScope::Inner, GapAmount::Set(20)),
+            (GapScope::Outer, GapAmount::Plus(5)),
+            (GapScope::Top, GapAmount::Set(0)),
+            (GapScope::Left, GapAmount::Minus(5)),
+        ]
+    );
+}
+
+#[test]
+fn test_gaps_outer_edges_and_smart() {
+    let config = parse_config("gaps outer 5\ngaps top 20\nsmart_gaps on").unwrap();
+    assert!(config.gaps.smart);
+    assert_eq!(config.gaps.outer_edges(), [20, 5, 5, 5]);
+
+    let mut gaps = config.gaps.clone();
+    gaps.apply(GapScope::Outer, GapAmount::Plus(10));
+    assert_eq!(gaps.outer_edges(), [30, 15, 15, 15]);
+    gaps.apply(GapScope::Inner, GapAmount::Minus(20));
+    assert_eq!(gaps.inner, Some(0));
+
+    // Repeated large steps stop at the limit instead of overflowing
+    let config = parse_config("bindsym Mod4+g gaps inner plus 2000000000").unwrap();
+    let Command::Gaps { scope, amount } = config.keybindings[0].command else {
+        panic!("Expected a gaps command");
+    };
+    let mut gaps = config.gaps.clone();
+    gaps.apply(scope, amount);
+    gaps.apply(scope, amount);
+    assert_eq!(gaps.inner, Some(i32::MAX));
+    assert_eq!(GapAmount::Minus(i32::MAX).apply(-10), 0);
+
+    assert_eq!(Config::default().gaps.outer_edges(), [0; 4]);
+    assert!(!parse_config("smart_gaps maybe").unwrap().gaps.smart);
+}
@@ -0,0 +1,133 @@
+//! Performance overlay
+//!
+//! `debug_overlay` shows the frame rate, the time the last frame took to
+//! render and the number of windows in the top right corner of the output
+//! under the pointer. The numbers come from the backends' render loops, which
+//! report every frame they actually drew. The text is only redrawn every
+//! [`REFRESH_INTERVAL`] and only while something else is being rendered, so
+//! the overlay never schedules frames of its own and damages nothing but its
+//! own rectangle.
+//!
+//! Damage rectangles are not outlined. Outlines of the last frame's damage
+//! would be new damage themselves, so every frame would repaint the previous
+//! outlines and the overlay would stop being cheap to leave on.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use smithay::{
+    backend::renderer::element::memory::MemoryRenderBuffer,
+    output::Output,
+    utils::{Logical, Point},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    config::Toggle,
+    state::{Backend, StilchState},
+};
+
+/// How often the overlay text is regenerated
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+/// Window over which the frame rate is averaged
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+const SIZE: (i32, i32) = (220, 64);
+const MARGIN: i32 = 8;
+
+/// Render statistics and the overlay showing them
+#[derive(Debug, Default)]
+pub struct DebugOverlay {
+    /// When recent frames finished rendering
+    frames: VecDeque<Instant>,
+    last_render_time: Duration,
+    buffer: Option<MemoryRenderBuffer>,
+    updated: Option<Instant>,
+}
+
+impl DebugOverlay {
+    fn record_frame(&mut self, render_time: Duration) {
+        let now = Instant::now();
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|&frame| now.duration_since(frame) > FPS_WINDOW)
+        {
+            self.frames.pop_front();
+        }
+        self.last_render_time = render_time;
+    }
+
+    fn text(&self, window_count: usize) -> String {
+        format!(
+            "FPS: {}\nFrame: {:.2} ms\nWindows: {}",
+            self.frames.len(),
+            self.last_render_time.as_secs_f64() * 1000.0,
+            window_count,
+        )
+    }
+
+    /// The overlay buffer, regenerated once the refresh interval has passed
+    fn buffer(&mut self, window_count: usize) -> Option<MemoryRenderBuffer> {
+        let stale = self
+            .updated
+            .is_none_or(|updated| updated.elapsed() >= REFRESH_INTERVAL);
+        if stale || self.buffer.is_none() {
+            let text = self.text(window_count);
+            match crate::tab_bar::text_render::render_text_block(&text, SIZE.0, SIZE.1) {
+                Ok(buffer) => self.buffer = Some(buffer),
+                Err(err) => warn!(?err, "Failed to render debug overlay"),
+            }
+            self.updated = Some(Instant::now());
+        }
+        self.buffer.clone()
+    }
+}
+
+/// What an output draws of the overlay, in output-relative coordinates
+#[derive(Debug, Clone)]
+pub struct DebugOverlayFrame {
+    pub buffer: MemoryRenderBuffer,
+    pub location: Point<i32, Logical>,
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Show, hide or toggle the debug overlay
+    pub fn set_debug_overlay(&mut self, toggle: Toggle) {
+        let show = match toggle {
+            Toggle::Enable => true,
+            Toggle::Disable => false,
+            Toggle::Toggle => self.debug_overlay.is_none(),
+        };
+        if show == self.debug_overlay.is_some() {
+            return;
+        }
+        debug!("Debug overlay: {show}");
+        self.debug_overlay = show.then(DebugOverlay::default);
+        self.backend_data.request_render();
+    }
+
+    /// Record how long a frame that was actually drawn took to render
+    pub fn record_frame_time(&mut self, render_time: Duration) {
+        if let Some(overlay) = self.debug_overlay.as_mut() {
+            overlay.record_frame(render_time);
+        }
+    }
+
+    /// The overlay for `output`, if it is the output under the pointer
+    pub fn debug_overlay_frame(&mut self, output: &Output) -> Option<DebugOverlayFrame> {
+        self.debug_overlay.as_ref()?;
+        let pointer = self.pointer().current_location();
+        let output_geometry = self.space().output_geometry(output)?;
+        if !output_geometry.to_f64().contains(pointer) {
+            return None;
+        }
+
+        let window_count = self.window_registry().len();
+        let buffer = self.debug_overlay.as_mut()?.buffer(window_count)?;
+        let location = Point::from((output_geometry.size.w - SIZE.0 - MARGIN, MARGIN));
+        Some(DebugOverlayFrame { buffer, location })
+    }
+}
@@ -29,6 +29,9 @@ pub static CLEAR_COLOR_FULLSCREEN: Color32F = Color32F::new(0.0, 0.0, 0.0, 0.0);
 pub struct PointerElement {
     buffer: Option<MemoryRenderBuffer>,
     status: CursorImageStatus,
+    /// `Kind::Cursor` lets the DRM compositor put the cursor on a cursor
+    /// plane, anything else has it composited
+    kind: Kind,
 }
 
 impl Default for PointerElement {
@@ -36,6 +39,7 @@ impl Default for PointerElement {
         Self {
             buffer: Default::default(),
             status: CursorImageStatus::default_named(),
+            kind: Kind::Cursor,
         }
     }
 }
@@ -45,6 +49,10 @@ impl PointerElement {
         self.status = status;
     }
 
+    pub fn set_kind(&mut self, kind: Kind) {
+        self.kind = kind;
+    }
+
     pub fn set_buffer(&mut self, buffer: MemoryRenderBuffer) {
         self.buffer = Some(buffer);
     }
@@ -94,7 +102,7 @@ where
                             None,
                             None,
                             None,
-                            Kind::Cursor,
+                            self.kind,
                         )
                         // SAFETY: We only release buffers we created
                         .expect("Lost system pointer buffer"),
@@ -107,12 +115,7 @@ where
             CursorImageStatus::Surface(surface) => {
                 let elements: Vec<PointerRenderElement<R>> =
                     smithay::backend::renderer::element::surface::render_elements_from_surface_tree(
-                        renderer,
-                        surface,
-                        location,
-                        scale,
-                        alpha,
-                        Kind::Cursor,
+                        renderer, surface, location, scale, alpha, self.kind,
                     );
                 elements.into_iter().map(E::from).collect()
             }
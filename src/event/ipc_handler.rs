@@ -4,7 +4,7 @@
 
 use crate::{
     event::{Event, EventHandler, WindowEvent, WorkspaceEvent},
-    ipc::{IpcServer, WorkspaceInfo},
+    ipc::{i3compat::I3Event, IpcServer, WorkspaceInfo},
     virtual_output::VirtualOutputId,
 };
 use std::sync::Arc;
@@ -21,6 +21,16 @@ impl IpcEventHandler {
         Self { ipc_server }
     }
 
+    fn send_i3_event(&self, event: I3Event) {
+        if let Some(ipc_server) = &self.ipc_server {
+            ipc_server.send_i3_event(event);
+        }
+    }
+
+    fn send_i3_window_event(&self, change: &'static str, window_id: crate::window::WindowId) {
+        self.send_i3_event(I3Event::Window { change, window_id });
+    }
+
     /// Send workspace update to IPC clients
     fn send_workspace_update(
         &self,
@@ -37,22 +47,59 @@ impl EventHandler for IpcEventHandler {
     fn handle_event(&mut self, event: &Event) {
         match event {
             Event::Window(window_event) => match window_event {
-                WindowEvent::Created { .. }
-                | WindowEvent::Destroyed { .. }
-                | WindowEvent::WorkspaceChanged { .. } => {
+                WindowEvent::Created { window_id, .. } => {
                     // The workspace state will be updated via WorkspaceEvent::LayoutChanged
                     // which should be emitted after window changes
                     debug!("Window event received, waiting for workspace update");
+                    self.send_i3_window_event("new", *window_id);
+                }
+                WindowEvent::Destroyed { window_id, .. } => {
+                    self.send_i3_window_event("close", *window_id);
+                }
+                WindowEvent::WorkspaceChanged { window_id, .. } => {
+                    self.send_i3_window_event("move", *window_id);
+                }
+                WindowEvent::Focused { window_id, .. } => {
+                    self.send_i3_window_event("focus", *window_id);
+                }
+                WindowEvent::FullscreenEntered { window_id, .. }
+                | WindowEvent::FullscreenExited { window_id, .. } => {
+                    self.send_i3_window_event("fullscreen_mode", *window_id);
+                }
+                WindowEvent::TitleChanged {
+                    window_id, title, ..
+                } => {
+                    if let Some(ipc_server) = &self.ipc_server {
+                        ipc_server.send_title_changed(*window_id, title.clone());
+                    }
+                    self.send_i3_window_event("title", *window_id);
+                }
+                WindowEvent::AppIdChanged {
+                    window_id, app_id, ..
+                } => {
+                    if let Some(ipc_server) = &self.ipc_server {
+                        ipc_server.send_app_id_changed(*window_id, app_id.clone());
+                    }
                 }
                 _ => {}
             },
             Event::Workspace(workspace_event) => match workspace_event {
-                WorkspaceEvent::Switched { virtual_output, .. } => {
+                WorkspaceEvent::Switched {
+                    old_workspace,
+                    new_workspace,
+                    virtual_output,
+                    ..
+                } => {
                     debug!(
                         "Workspace switched on output {}, updating IPC",
                         virtual_output
                     );
                     // The caller should emit a StateUpdate event with the new workspace info
+                    self.send_i3_event(I3Event::Workspace {
+                        change: "focus",
+                        current: Some(*new_workspace),
+                        old: Some(*old_workspace),
+                    });
                 }
                 WorkspaceEvent::LayoutChanged { .. } => {
                     debug!("Workspace layout changed, waiting for state update");
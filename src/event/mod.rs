@@ -77,6 +77,20 @@ pub enum WindowEvent {
         window_id: WindowId,
         timestamp: Instant,
     },
+
+    /// A window's title changed
+    TitleChanged {
+        window_id: WindowId,
+        title: String,
+        timestamp: Instant,
+    },
+
+    /// A window's app_id changed
+    AppIdChanged {
+        window_id: WindowId,
+        app_id: Option<String>,
+        timestamp: Instant,
+    },
 }
 
 /// Workspace-related events
@@ -29,6 +29,7 @@ use smithay::{
 use crate::{
     shell::{WindowElement, SSD},
     state::{Backend, StilchState},
+    window::WindowId,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -659,3 +660,167 @@ impl From<KeyboardFocusTarget> for PointerFocusTarget {
         }
     }
 }
+
+/// Windows in the order they got keyboard focus, with a cursor for
+/// `focus back` and `focus forward`
+///
+/// Unlike a most recently used list, stepping through the history leaves
+/// its order alone, so going back and then forward retraces the same
+/// windows. Focusing a window any other way drops the entries ahead of the
+/// cursor, like following a link after going back in a browser.
+#[derive(Debug, Default)]
+pub struct FocusHistory {
+    entries: Vec<WindowId>,
+    /// Index of the entry focus is at
+    position: usize,
+    /// Set while a step focuses its window, which may pass focus through
+    /// other windows on the way
+    stepping: bool,
+}
+
+impl FocusHistory {
+    /// Oldest entries are dropped beyond this
+    const MAX_ENTRIES: usize = 100;
+
+    /// Note that a window got focus
+    pub fn record(&mut self, window_id: WindowId) {
+        if self.stepping || self.entries.get(self.position) == Some(&window_id) {
+            return;
+        }
+        self.entries.truncate(self.position + 1);
+        self.entries.push(window_id);
+        if self.entries.len() > Self::MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.position = self.entries.len() - 1;
+    }
+
+    /// Move the cursor to the nearest entry behind it, or ahead of it with
+    /// `forward`, that `focusable` accepts. Leaves the cursor where it is if
+    /// there is none.
+    pub fn step(
+        &mut self,
+        forward: bool,
+        focusable: impl Fn(WindowId) -> bool,
+    ) -> Option<WindowId> {
+        let position = if forward {
+            (self.position + 1..self.entries.len()).find(|&index| focusable(self.entries[index]))
+        } else {
+            (0..self.position.min(self.entries.len()))
+                .rev()
+                .find(|&index| focusable(self.entries[index]))
+        }?;
+        self.position = position;
+        Some(self.entries[position])
+    }
+
+    /// Forget a window that is gone. The cursor moves back to the entry
+    /// before it if it was on the window, and neighbours that become equal
+    /// are merged.
+    pub fn remove(&mut self, window_id: WindowId) {
+        let mut entries: Vec<WindowId> = Vec::with_capacity(self.entries.len());
+        let mut position = 0;
+        for (index, &id) in self.entries.iter().enumerate() {
+            if id != window_id && entries.last() != Some(&id) {
+                entries.push(id);
+            }
+            if index == self.position {
+                position = entries.len().saturating_sub(1);
+            }
+        }
+        self.entries = entries;
+        self.position = position;
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Focus the window before the focused one in the focus history, or the
+    /// one after it with `forward`, switching workspaces as needed. Hidden
+    /// scratchpad windows are stepped over.
+    pub fn focus_history_step(&mut self, forward: bool) {
+        let step = self.focus_history.step(forward, |window_id| {
+            self.window_manager.registry().get(window_id).is_some()
+                && !self.scratchpad.is_hidden(window_id)
+        });
+        let Some(window_id) = step else {
+            tracing::debug!(
+                "No window {} in the focus history",
+                if forward { "ahead" } else { "behind" }
+            );
+            return;
+        };
+
+        tracing::info!("Focusing window {} from the focus history", window_id);
+        self.focus_history.stepping = true;
+        self.activate_window(window_id);
+        self.focus_history.stepping = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(history: &FocusHistory) -> Vec<u64> {
+        history.entries.iter().map(WindowId::get).collect()
+    }
+
+    fn history(windows: &[u32]) -> FocusHistory {
+        let mut history = FocusHistory::default();
+        for &window in windows {
+            history.record(WindowId::new(window));
+        }
+        history
+    }
+
+    #[test]
+    fn test_back_and_forward_retrace_the_history() {
+        let mut history = history(&[1, 2, 3]);
+        let any = |_| true;
+
+        assert_eq!(history.step(false, any), Some(WindowId::new(2)));
+        assert_eq!(history.step(false, any), Some(WindowId::new(1)));
+        assert_eq!(history.step(false, any), None);
+        assert_eq!(history.step(true, any), Some(WindowId::new(2)));
+        assert_eq!(history.step(true, any), Some(WindowId::new(3)));
+        assert_eq!(history.step(true, any), None);
+        assert_eq!(ids(&history), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_focusing_after_going_back_drops_what_was_ahead() {
+        let mut history = history(&[1, 2, 3]);
+        history.step(false, |_| true);
+
+        // Focus landing on the window stepped to changes nothing
+        history.record(WindowId::new(2));
+        assert_eq!(ids(&history), [1, 2, 3]);
+
+        history.record(WindowId::new(4));
+        assert_eq!(ids(&history), [1, 2, 4]);
+        assert_eq!(history.step(true, |_| true), None);
+    }
+
+    #[test]
+    fn test_steps_skip_windows_that_cant_be_focused() {
+        let mut history = history(&[1, 2, 3]);
+        let step = history.step(false, |window_id| window_id != WindowId::new(2));
+        assert_eq!(step, Some(WindowId::new(1)));
+    }
+
+    #[test]
+    fn test_removed_windows_are_pruned() {
+        let mut history = history(&[1, 2, 1, 3]);
+        history.step(false, |_| true);
+        history.step(false, |_| true);
+
+        // The cursor was on 2, and the 1s around it merge
+        history.remove(WindowId::new(2));
+        assert_eq!(ids(&history), [1, 3]);
+        assert_eq!(history.step(true, |_| true), Some(WindowId::new(3)));
+
+        history.remove(WindowId::new(3));
+        assert_eq!(ids(&history), [1]);
+        assert_eq!(history.step(false, |_| true), None);
+    }
+}
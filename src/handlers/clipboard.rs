@@ -0,0 +1,396 @@
+//! Clipboard persistence
+//!
+//! With `clipboard persist yes`, the text a Wayland client puts on the
+//! clipboard or primary selection is copied into memory as soon as it is
+//! set, for a short list of plain text MIME types and up to the configured
+//! size. The client keeps serving its selection while it runs. Only once it
+//! disconnects does the compositor take the selection over and serve the
+//! copy, so pasting keeps working after the client exits. A selection its
+//! client clears is forgotten.
+//!
+//! Clients need keyboard focus to set a selection, so the focused client
+//! when a selection is set is taken as its owner.
+
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Write},
+    os::{fd::OwnedFd, unix::net::UnixStream},
+    sync::{atomic::Ordering, Arc},
+};
+
+use smithay::{
+    input::Seat,
+    reexports::{
+        calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction},
+        rustix::fs::{fcntl_getfl, fcntl_setfl, OFlags},
+        wayland_server::{backend::ClientId, Client},
+    },
+    wayland::{
+        seat::WaylandFocus,
+        selection::{
+            data_device::{
+                current_data_device_selection_userdata, request_data_device_client_selection,
+                set_data_device_selection,
+            },
+            primary_selection::{
+                current_primary_selection_userdata, request_primary_client_selection,
+                set_primary_selection,
+            },
+            SelectionSource, SelectionTarget,
+        },
+    },
+};
+use tracing::debug;
+
+use crate::state::{Backend, ClientState, StilchState};
+
+/// MIME types copied for persistence, all of them plain text
+const PERSISTED_MIME_TYPES: &[&str] = &[
+    "text/plain;charset=utf-8",
+    "text/plain",
+    "UTF8_STRING",
+    "STRING",
+    "TEXT",
+];
+
+/// Compositor-side owner of a selection
+#[derive(Debug, Clone)]
+pub enum SelectionData {
+    /// Selection forwarded from an X11 client
+    Xwayland,
+    /// Selection served from the persistence cache
+    Cached(Arc<Vec<(String, Vec<u8>)>>),
+}
+
+/// A copy of a client's selection, taken over once the client goes away
+#[derive(Debug)]
+struct Capture {
+    generation: u64,
+    owner: Client,
+    owner_gone: bool,
+    /// MIME types still being read
+    pending: usize,
+    total_size: usize,
+    too_large: bool,
+    contents: Vec<(String, Vec<u8>)>,
+}
+
+/// Copies of the current clipboard and primary selections
+#[derive(Debug, Default)]
+pub struct ClipboardPersistence {
+    generation: u64,
+    clipboard: Option<Capture>,
+    primary: Option<Capture>,
+}
+
+impl ClipboardPersistence {
+    fn capture_mut(&mut self, target: SelectionTarget) -> &mut Option<Capture> {
+        match target {
+            SelectionTarget::Clipboard => &mut self.clipboard,
+            SelectionTarget::Primary => &mut self.primary,
+        }
+    }
+}
+
+/// Whether a client has disconnected; clients stilch did not accept itself,
+/// such as Xwayland, are taken to be connected
+fn is_disconnected(client: &Client) -> bool {
+    client
+        .get_data::<ClientState>()
+        .is_some_and(|data| data.disconnected.load(Ordering::Acquire))
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Start copying a selection a client just set, or forget the copy of
+    /// one its client cleared
+    pub fn capture_selection(&mut self, target: SelectionTarget, source: Option<&SelectionSource>) {
+        let Some(source) = source else {
+            // A selection also goes away with its client, which is only
+            // known once the client's disconnection has been handled
+            let generation = self
+                .clipboard
+                .capture_mut(target)
+                .as_ref()
+                .map(|capture| capture.generation);
+            if let Some(generation) = generation {
+                self.handle
+                    .insert_idle(move |state| state.selection_cleared(target, generation));
+            }
+            return;
+        };
+
+        let persistence = &mut self.clipboard;
+        persistence.generation += 1;
+        let generation = persistence.generation;
+        // A copy of the previous selection is stale now
+        *persistence.capture_mut(target) = None;
+
+        if !self.config.clipboard.persist {
+            return;
+        }
+        let Some(owner) = self
+            .seat()
+            .get_keyboard()
+            .and_then(|keyboard| keyboard.current_focus())
+            .and_then(|focus| focus.wl_surface().and_then(|surface| surface.client()))
+        else {
+            return;
+        };
+        let mime_types: Vec<String> = source
+            .mime_types()
+            .into_iter()
+            .filter(|mime_type| PERSISTED_MIME_TYPES.contains(&mime_type.as_str()))
+            .collect();
+        if mime_types.is_empty() {
+            return;
+        }
+
+        *self.clipboard.capture_mut(target) = Some(Capture {
+            generation,
+            owner,
+            owner_gone: false,
+            pending: mime_types.len(),
+            total_size: 0,
+            too_large: false,
+            contents: Vec::new(),
+        });
+
+        let seat = self.seat().clone();
+        for mime_type in mime_types {
+            if let Err(err) = self.request_mime_type(&seat, target, generation, mime_type.clone()) {
+                debug!(?err, ?target, "Failed to read selection as {mime_type}");
+                self.mime_type_done(target, generation, mime_type, None);
+            }
+        }
+    }
+
+    /// Take over the selections of a client that disconnected
+    pub fn client_disconnected(&mut self, client_id: ClientId) {
+        for target in [SelectionTarget::Clipboard, SelectionTarget::Primary] {
+            let Some(capture) = self
+                .clipboard
+                .capture_mut(target)
+                .as_mut()
+                .filter(|capture| capture.owner.id() == client_id)
+            else {
+                continue;
+            };
+            capture.owner_gone = true;
+            self.persist_selection(target);
+        }
+    }
+
+    /// A selection was cleared: keep its copy if that was because its
+    /// client went away, forget it otherwise
+    fn selection_cleared(&mut self, target: SelectionTarget, generation: u64) {
+        let slot = self.clipboard.capture_mut(target);
+        let Some(capture) = slot.as_mut().filter(|c| c.generation == generation) else {
+            return;
+        };
+        if is_disconnected(&capture.owner) {
+            capture.owner_gone = true;
+            self.persist_selection(target);
+        } else {
+            debug!(?target, "Selection cleared by its client");
+            *slot = None;
+        }
+    }
+
+    fn request_mime_type(
+        &mut self,
+        seat: &Seat<Self>,
+        target: SelectionTarget,
+        generation: u64,
+        mime_type: String,
+    ) -> std::io::Result<()> {
+        let (mut reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        let watched = reader.try_clone()?;
+
+        let fd = OwnedFd::from(writer);
+        let requested = match target {
+            SelectionTarget::Clipboard => {
+                request_data_device_client_selection(seat, mime_type.clone(), fd)
+            }
+            SelectionTarget::Primary => {
+                request_primary_client_selection(seat, mime_type.clone(), fd)
+            }
+        };
+        if let Err(err) = requested {
+            return Err(std::io::Error::new(ErrorKind::Other, format!("{err:?}")));
+        }
+
+        let max_size = self.config.clipboard.max_size;
+        let mut buffer = Vec::new();
+        self.handle
+            .insert_source(
+                Generic::new(watched, Interest::READ, Mode::Level),
+                move |_, _, state: &mut StilchState<BackendData>| {
+                    let mut chunk = [0u8; 4096];
+                    loop {
+                        match reader.read(&mut chunk) {
+                            Ok(0) => {
+                                let data = std::mem::take(&mut buffer);
+                                let mime_type = mime_type.clone();
+                                state.mime_type_done(target, generation, mime_type, Some(data));
+                                return Ok(PostAction::Remove);
+                            }
+                            Ok(n) => {
+                                buffer.extend_from_slice(&chunk[..n]);
+                                if buffer.len() > max_size {
+                                    let mime_type = mime_type.clone();
+                                    state.mime_type_done(target, generation, mime_type, None);
+                                    return Ok(PostAction::Remove);
+                                }
+                            }
+                            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                                return Ok(PostAction::Continue);
+                            }
+                            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                            Err(err) => {
+                                debug!(?err, "Failed to read selection data");
+                                let mime_type = mime_type.clone();
+                                state.mime_type_done(target, generation, mime_type, None);
+                                return Ok(PostAction::Remove);
+                            }
+                        }
+                    }
+                },
+            )
+            .map_err(|err| std::io::Error::new(ErrorKind::Other, err.to_string()))?;
+        Ok(())
+    }
+
+    /// Record one MIME type's data, None if it could not be read in full
+    fn mime_type_done(
+        &mut self,
+        target: SelectionTarget,
+        generation: u64,
+        mime_type: String,
+        data: Option<Vec<u8>>,
+    ) {
+        let max_size = self.config.clipboard.max_size;
+        let Some(capture) = self
+            .clipboard
+            .capture_mut(target)
+            .as_mut()
+            .filter(|c| c.generation == generation)
+        else {
+            return;
+        };
+
+        capture.pending -= 1;
+        match data {
+            Some(data) => {
+                capture.total_size += data.len();
+                capture.contents.push((mime_type, data));
+            }
+            None => capture.too_large = true,
+        }
+        if capture.total_size > max_size {
+            capture.too_large = true;
+        }
+        self.persist_selection(target);
+    }
+
+    /// Serve a copied selection from the compositor once its client is gone
+    /// and every MIME type has been read
+    fn persist_selection(&mut self, target: SelectionTarget) {
+        let slot = self.clipboard.capture_mut(target);
+        if !slot
+            .as_ref()
+            .is_some_and(|capture| capture.owner_gone && capture.pending == 0)
+        {
+            return;
+        }
+        let Some(capture) = slot.take() else {
+            return;
+        };
+        if capture.too_large || capture.contents.is_empty() {
+            debug!(?target, "Selection not persisted");
+            return;
+        }
+
+        // Another selection may have been set since without going through
+        // a client, such as one from an X11 client
+        let seat = self.seat().clone();
+        let taken = match target {
+            SelectionTarget::Clipboard => current_data_device_selection_userdata(&seat).is_some(),
+            SelectionTarget::Primary => current_primary_selection_userdata(&seat).is_some(),
+        };
+        if taken {
+            return;
+        }
+
+        let mime_types = capture
+            .contents
+            .iter()
+            .map(|(mime, _)| mime.clone())
+            .collect();
+        let data = SelectionData::Cached(Arc::new(capture.contents));
+        match target {
+            SelectionTarget::Clipboard => {
+                set_data_device_selection(&self.display_handle, &seat, mime_types, data)
+            }
+            SelectionTarget::Primary => {
+                set_primary_selection(&self.display_handle, &seat, mime_types, data)
+            }
+        }
+        debug!(?target, "Selection persisted");
+    }
+}
+
+/// Write a cached selection to a client's fd from the event loop, as fast as
+/// the client reads it
+pub fn send_cached_selection<BackendData: Backend + 'static>(
+    handle: &LoopHandle<'static, StilchState<BackendData>>,
+    contents: &Arc<Vec<(String, Vec<u8>)>>,
+    mime_type: &str,
+    fd: OwnedFd,
+) {
+    let Some(index) = contents.iter().position(|(mime, _)| mime == mime_type) else {
+        return;
+    };
+    if contents[index].1.is_empty() {
+        return;
+    }
+    let flags = fcntl_getfl(&fd).map(|flags| flags | OFlags::NONBLOCK);
+    if let Err(err) = flags.and_then(|flags| fcntl_setfl(&fd, flags)) {
+        debug!(?err, "Failed to make selection fd non-blocking");
+        return;
+    }
+    let mut file = File::from(fd);
+    let Ok(watched) = file.try_clone() else {
+        return;
+    };
+
+    let contents = contents.clone();
+    let mut written = 0;
+    let inserted = handle.insert_source(
+        Generic::new(watched, Interest::WRITE, Mode::Level),
+        move |_, _, _state: &mut StilchState<BackendData>| {
+            let data = &contents[index].1;
+            loop {
+                match file.write(&data[written..]) {
+                    Ok(n) => {
+                        written += n;
+                        if written == data.len() {
+                            return Ok(PostAction::Remove);
+                        }
+                    }
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                        return Ok(PostAction::Continue);
+                    }
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => {
+                        debug!(?err, "Failed to send cached selection");
+                        return Ok(PostAction::Remove);
+                    }
+                }
+            }
+        },
+    );
+    if let Err(err) = inserted {
+        debug!(?err, "Failed to send cached selection");
+    }
+}
@@ -0,0 +1,461 @@
+//! wlr-foreign-toplevel-management protocol
+//!
+//! Lets taskbars and window switchers (e.g. waybar's `wlr/taskbar`) list
+//! windows and ask for them to be activated, closed or fullscreened.
+//! Smithay has no implementation of this protocol, so the globals are
+//! dispatched here directly. Handles follow the window events on the
+//! `EventBus`: they are created and closed with the windows, and updated when
+//! a title, app_id, focus, fullscreen state, position or size changes.
+//! Minimizing hides a window in the scratchpad.
+
+use std::collections::HashMap;
+
+use smithay::{
+    output::Output,
+    reexports::{
+        calloop::{self, channel::Sender, LoopHandle},
+        wayland_protocols_wlr::foreign_toplevel::v1::server::{
+            zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+            zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+        },
+        wayland_server::{
+            backend::ClientId, protocol::wl_output::WlOutput, Client, DataInit, Dispatch,
+            DisplayHandle, GlobalDispatch, New, Resource,
+        },
+    },
+};
+use tracing::{debug, warn};
+
+use crate::{
+    event::{Event, EventHandler, WindowEvent},
+    state::{Backend, StilchState},
+    window::{FullscreenMode, WindowId},
+};
+
+const VERSION: u32 = 3;
+
+/// Snapshot of the properties advertised for one window
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToplevelInfo {
+    pub window_id: WindowId,
+    pub title: Option<String>,
+    pub app_id: Option<String>,
+    pub activated: bool,
+    pub fullscreen: bool,
+    pub minimized: bool,
+    pub output: Option<Output>,
+}
+
+impl ToplevelInfo {
+    fn states(&self) -> Vec<u8> {
+        let mut states = Vec::new();
+        if self.activated {
+            states.push(zwlr_foreign_toplevel_handle_v1::State::Activated as u32);
+        }
+        if self.fullscreen {
+            states.push(zwlr_foreign_toplevel_handle_v1::State::Fullscreen as u32);
+        }
+        if self.minimized {
+            states.push(zwlr_foreign_toplevel_handle_v1::State::Minimized as u32);
+        }
+        states
+            .iter()
+            .flat_map(|state| state.to_ne_bytes())
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct ToplevelData {
+    info: ToplevelInfo,
+    instances: HashMap<ZwlrForeignToplevelHandleV1, Vec<WlOutput>>,
+}
+
+/// State of the `zwlr_foreign_toplevel_manager_v1` global
+#[derive(Debug)]
+pub struct ForeignToplevelManagerState {
+    display: DisplayHandle,
+    instances: Vec<ZwlrForeignToplevelManagerV1>,
+    toplevels: HashMap<WindowId, ToplevelData>,
+    /// Window last advertised as activated
+    activated: Option<WindowId>,
+}
+
+impl ForeignToplevelManagerState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<ZwlrForeignToplevelManagerV1, ()>
+            + Dispatch<ZwlrForeignToplevelManagerV1, ()>
+            + Dispatch<ZwlrForeignToplevelHandleV1, WindowId>
+            + 'static,
+    {
+        display.create_global::<D, ZwlrForeignToplevelManagerV1, _>(VERSION, ());
+        Self {
+            display: display.clone(),
+            instances: Vec::new(),
+            toplevels: HashMap::new(),
+            activated: None,
+        }
+    }
+
+    /// Advertise a window, or send what changed about one already advertised
+    pub fn update<D>(&mut self, info: ToplevelInfo)
+    where
+        D: Dispatch<ZwlrForeignToplevelHandleV1, WindowId> + 'static,
+    {
+        match self.toplevels.get_mut(&info.window_id) {
+            Some(data) => data.update(info),
+            None => {
+                let mut data = ToplevelData {
+                    info,
+                    instances: HashMap::new(),
+                };
+                for manager in &self.instances {
+                    data.add_instance::<D>(&self.display, manager);
+                }
+                self.toplevels.insert(data.info.window_id, data);
+            }
+        }
+    }
+
+    /// Close the handles of a window that went away
+    pub fn remove(&mut self, window_id: WindowId) {
+        if let Some(data) = self.toplevels.remove(&window_id) {
+            for handle in data.instances.keys() {
+                handle.closed();
+            }
+        }
+        if self.activated == Some(window_id) {
+            self.activated = None;
+        }
+    }
+}
+
+/// Forwards the window events that change a toplevel handle to the event
+/// loop, where the state needed to update the handles is at hand
+struct ForeignToplevelEvents(Sender<WindowEvent>);
+
+impl EventHandler for ForeignToplevelEvents {
+    fn handle_event(&mut self, event: &Event) {
+        let Event::Window(event) = event else {
+            return;
+        };
+        if matches!(
+            event,
+            WindowEvent::Created { .. }
+                | WindowEvent::Destroyed { .. }
+                | WindowEvent::Moved { .. }
+                | WindowEvent::Resized { .. }
+                | WindowEvent::Focused { .. }
+                | WindowEvent::Unfocused { .. }
+                | WindowEvent::WorkspaceChanged { .. }
+                | WindowEvent::FullscreenEntered { .. }
+                | WindowEvent::FullscreenExited { .. }
+                | WindowEvent::TitleChanged { .. }
+                | WindowEvent::AppIdChanged { .. }
+        ) {
+            let _ = self.0.send(event.clone());
+        }
+    }
+}
+
+/// Update foreign toplevel handles from the window events on the event bus
+pub fn start_event_source<BackendData: Backend + 'static>(
+    handle: &LoopHandle<'static, StilchState<BackendData>>,
+) -> Option<Box<dyn EventHandler>> {
+    let (sender, channel) = calloop::channel::channel();
+    let source = handle.insert_source(channel, |event, _, state| {
+        if let calloop::channel::Event::Msg(event) = event {
+            state.handle_foreign_toplevel_event(event);
+        }
+    });
+    match source {
+        Ok(_) => Some(Box::new(ForeignToplevelEvents(sender))),
+        Err(err) => {
+            warn!("Failed to start foreign toplevel updates: {}", err);
+            None
+        }
+    }
+}
+
+impl ToplevelData {
+    fn add_instance<D>(&mut self, display: &DisplayHandle, manager: &ZwlrForeignToplevelManagerV1)
+    where
+        D: Dispatch<ZwlrForeignToplevelHandleV1, WindowId> + 'static,
+    {
+        let Some(client) = manager.client() else {
+            return;
+        };
+        let Ok(handle) = client.create_resource::<ZwlrForeignToplevelHandleV1, _, D>(
+            display,
+            manager.version(),
+            self.info.window_id,
+        ) else {
+            return;
+        };
+        manager.toplevel(&handle);
+
+        if let Some(title) = &self.info.title {
+            handle.title(title.clone());
+        }
+        if let Some(app_id) = &self.info.app_id {
+            handle.app_id(app_id.clone());
+        }
+        let outputs = client_outputs(&client, self.info.output.as_ref());
+        for wl_output in &outputs {
+            handle.output_enter(wl_output);
+        }
+        handle.state(self.info.states());
+        handle.done();
+
+        self.instances.insert(handle, outputs);
+    }
+
+    fn update(&mut self, info: ToplevelInfo) {
+        if self.info == info {
+            return;
+        }
+
+        let title_changed = self.info.title != info.title;
+        let app_id_changed = self.info.app_id != info.app_id;
+        let states_changed = self.info.activated != info.activated
+            || self.info.fullscreen != info.fullscreen
+            || self.info.minimized != info.minimized;
+        let output_changed = self.info.output != info.output;
+        self.info = info;
+
+        for (handle, outputs) in &mut self.instances {
+            if title_changed {
+                if let Some(title) = &self.info.title {
+                    handle.title(title.clone());
+                }
+            }
+            if app_id_changed {
+                if let Some(app_id) = &self.info.app_id {
+                    handle.app_id(app_id.clone());
+                }
+            }
+            if states_changed {
+                handle.state(self.info.states());
+            }
+            if output_changed {
+                for wl_output in outputs.drain(..) {
+                    handle.output_leave(&wl_output);
+                }
+                if let Some(client) = handle.client() {
+                    *outputs = client_outputs(&client, self.info.output.as_ref());
+                    for wl_output in outputs.iter() {
+                        handle.output_enter(wl_output);
+                    }
+                }
+            }
+            handle.done();
+        }
+    }
+}
+
+fn client_outputs(client: &Client, output: Option<&Output>) -> Vec<WlOutput> {
+    output
+        .map(|output| output.client_outputs(client).collect())
+        .unwrap_or_default()
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// What foreign toplevel clients are told about a window
+    fn toplevel_info(&self, window_id: WindowId) -> Option<ToplevelInfo> {
+        let managed_window = self.window_registry().get(window_id)?;
+        let criteria = managed_window.criteria();
+        let activated = self
+            .focused_window()
+            .is_some_and(|focused| focused == managed_window.element);
+        Some(ToplevelInfo {
+            window_id,
+            app_id: criteria.app().map(str::to_string),
+            title: Some(criteria.title),
+            activated,
+            fullscreen: managed_window.is_fullscreen(),
+            minimized: self.scratchpad.is_hidden(window_id),
+            output: self
+                .space()
+                .outputs_for_element(&managed_window.element)
+                .into_iter()
+                .next(),
+        })
+    }
+
+    /// Send foreign toplevel clients the current state of a window
+    pub fn update_foreign_toplevel(&mut self, window_id: WindowId) {
+        match self.toplevel_info(window_id) {
+            Some(info) => self.protocols.foreign_toplevel_state.update::<Self>(info),
+            None => self.protocols.foreign_toplevel_state.remove(window_id),
+        }
+    }
+
+    fn handle_foreign_toplevel_event(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::Destroyed { window_id, .. } => {
+                self.protocols.foreign_toplevel_state.remove(window_id);
+            }
+            WindowEvent::Focused { window_id, .. } => {
+                let previous = self
+                    .protocols
+                    .foreign_toplevel_state
+                    .activated
+                    .replace(window_id);
+                if let Some(previous) = previous.filter(|previous| *previous != window_id) {
+                    self.update_foreign_toplevel(previous);
+                }
+                self.update_foreign_toplevel(window_id);
+            }
+            WindowEvent::Unfocused { window_id, .. } => {
+                let protocol_state = &mut self.protocols.foreign_toplevel_state;
+                if protocol_state.activated == Some(window_id) {
+                    protocol_state.activated = None;
+                }
+                self.update_foreign_toplevel(window_id);
+            }
+            WindowEvent::Created { window_id, .. }
+            | WindowEvent::Moved { window_id, .. }
+            | WindowEvent::Resized { window_id, .. }
+            | WindowEvent::WorkspaceChanged { window_id, .. }
+            | WindowEvent::FullscreenEntered { window_id, .. }
+            | WindowEvent::FullscreenExited { window_id, .. }
+            | WindowEvent::TitleChanged { window_id, .. }
+            | WindowEvent::AppIdChanged { window_id, .. } => {
+                self.update_foreign_toplevel(window_id);
+            }
+        }
+    }
+
+    /// Show a minimized window again where it was, or just focus it
+    fn unminimize_window(&mut self, window_id: WindowId) {
+        if !self.restore_hidden_scratchpad_window(window_id) {
+            self.activate_window(window_id);
+        }
+    }
+}
+
+impl<BackendData: Backend> GlobalDispatch<ZwlrForeignToplevelManagerV1, ()>
+    for StilchState<BackendData>
+{
+    fn bind(
+        state: &mut Self,
+        handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrForeignToplevelManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+
+        let protocol_state = &mut state.protocols.foreign_toplevel_state;
+        for data in protocol_state.toplevels.values_mut() {
+            data.add_instance::<Self>(handle, &manager);
+        }
+        protocol_state.instances.push(manager);
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrForeignToplevelManagerV1, ()> for StilchState<BackendData> {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrForeignToplevelManagerV1,
+        request: zwlr_foreign_toplevel_manager_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwlr_foreign_toplevel_manager_v1::Request::Stop => {
+                resource.finished();
+                state
+                    .protocols
+                    .foreign_toplevel_state
+                    .instances
+                    .retain(|manager| manager != resource);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ZwlrForeignToplevelManagerV1,
+        _data: &(),
+    ) {
+        state
+            .protocols
+            .foreign_toplevel_state
+            .instances
+            .retain(|manager| manager != resource);
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrForeignToplevelHandleV1, WindowId>
+    for StilchState<BackendData>
+{
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ZwlrForeignToplevelHandleV1,
+        request: zwlr_foreign_toplevel_handle_v1::Request,
+        window_id: &WindowId,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        let window_id = *window_id;
+        match request {
+            zwlr_foreign_toplevel_handle_v1::Request::Activate { .. } => {
+                state.activate_window(window_id);
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::Close => {
+                let element = state
+                    .window_registry()
+                    .get(window_id)
+                    .map(|mw| mw.element.clone());
+                if let Some(element) = element {
+                    state.close_window(&element);
+                }
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::SetFullscreen { output } => match output {
+                Some(output) => state.fullscreen_on_requested_output(window_id, &output),
+                None => state.set_fullscreen(window_id, true, FullscreenMode::VirtualOutput),
+            },
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetFullscreen => {
+                state.set_fullscreen(window_id, false, FullscreenMode::VirtualOutput);
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::SetMinimized => {
+                state.move_to_scratchpad(window_id);
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::UnsetMinimized => {
+                state.unminimize_window(window_id);
+            }
+            // Tiled windows cannot be maximized
+            zwlr_foreign_toplevel_handle_v1::Request::SetMaximized
+            | zwlr_foreign_toplevel_handle_v1::Request::UnsetMaximized => {
+                debug!("Ignoring foreign toplevel request {request:?} for window {window_id}");
+            }
+            zwlr_foreign_toplevel_handle_v1::Request::SetRectangle { .. } => {}
+            zwlr_foreign_toplevel_handle_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ZwlrForeignToplevelHandleV1,
+        window_id: &WindowId,
+    ) {
+        if let Some(data) = state
+            .protocols
+            .foreign_toplevel_state
+            .toplevels
+            .get_mut(window_id)
+        {
+            data.instances.remove(resource);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! wlr-gamma-control protocol
+//!
+//! Lets blue-light filters such as wlsunset and gammastep set per-output
+//! gamma ramps. The ramps are handed to the backend, which on udev programs
+//! the CRTC gamma LUT. The nested winit and X11 backends leave gamma to the
+//! host, so controls there fail immediately. Only one client may control an
+//! output at a time, and the default ramp is restored when that control goes
+//! away.
+
+use std::{collections::HashMap, os::fd::OwnedFd};
+
+use smithay::{
+    output::Output,
+    reexports::{
+        rustix::{
+            fs::{fcntl_getfl, fcntl_setfl, OFlags},
+            io::pread,
+        },
+        wayland_protocols_wlr::gamma_control::v1::server::{
+            zwlr_gamma_control_manager_v1::{self, ZwlrGammaControlManagerV1},
+            zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+        },
+        wayland_server::{
+            backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New,
+            Resource,
+        },
+    },
+};
+use tracing::{debug, warn};
+
+use crate::state::{Backend, StilchState};
+
+const VERSION: u32 = 1;
+
+/// State of the `zwlr_gamma_control_manager_v1` global
+#[derive(Debug, Default)]
+pub struct GammaControlManagerState {
+    /// The control currently owning each output's gamma
+    controls: HashMap<Output, ZwlrGammaControlV1>,
+}
+
+impl GammaControlManagerState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<ZwlrGammaControlManagerV1, ()>
+            + Dispatch<ZwlrGammaControlManagerV1, ()>
+            + Dispatch<ZwlrGammaControlV1, Option<Output>>
+            + 'static,
+    {
+        display.create_global::<D, ZwlrGammaControlManagerV1, _>(VERSION, ());
+        Self::default()
+    }
+
+    /// Fail the control of an output that is going away
+    pub fn output_removed(&mut self, output: &Output) {
+        if let Some(control) = self.controls.remove(output) {
+            control.failed();
+        }
+    }
+}
+
+/// Read the red, green and blue ramps of `size` entries each from a client
+/// fd. Like wlroots, the fd is read from its start without blocking, so a
+/// client handing over a pipe it never writes can't stall the event loop.
+/// Returns `Ok(None)` when the fd holds fewer bytes than the ramps need.
+fn read_ramps(fd: OwnedFd, size: usize) -> std::io::Result<Option<Vec<u16>>> {
+    let flags = fcntl_getfl(&fd)?;
+    fcntl_setfl(&fd, flags | OFlags::NONBLOCK)?;
+
+    let mut bytes = vec![0u8; size * 3 * std::mem::size_of::<u16>()];
+    if pread(&fd, bytes.as_mut_slice(), 0)? != bytes.len() {
+        return Ok(None);
+    }
+    Ok(Some(
+        bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+            .collect(),
+    ))
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Give an output its default gamma back and forget its control
+    fn release_gamma_control(&mut self, output: &Output) {
+        if self
+            .protocols
+            .gamma_control_state
+            .controls
+            .remove(output)
+            .is_some()
+        {
+            self.backend_data.set_gamma(output, None);
+        }
+    }
+}
+
+impl<BackendData: Backend> GlobalDispatch<ZwlrGammaControlManagerV1, ()>
+    for StilchState<BackendData>
+{
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrGammaControlManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrGammaControlManagerV1, ()> for StilchState<BackendData> {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ZwlrGammaControlManagerV1,
+        request: zwlr_gamma_control_manager_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwlr_gamma_control_manager_v1::Request::GetGammaControl { id, output } => {
+                let output = Output::from_resource(&output);
+                let control = data_init.init(id, output.clone());
+                let Some(output) = output else {
+                    // The output is already gone
+                    control.failed();
+                    return;
+                };
+
+                let controls = &mut state.protocols.gamma_control_state.controls;
+                if controls.contains_key(&output) {
+                    debug!("Output {} already has a gamma control", output.name());
+                    control.failed();
+                    return;
+                }
+                let Some(size) = state.backend_data.gamma_size(&output) else {
+                    debug!("Output {} does not support gamma control", output.name());
+                    control.failed();
+                    return;
+                };
+
+                control.gamma_size(size);
+                controls.insert(output, control);
+            }
+            zwlr_gamma_control_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrGammaControlV1, Option<Output>>
+    for StilchState<BackendData>
+{
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrGammaControlV1,
+        request: zwlr_gamma_control_v1::Request,
+        output: &Option<Output>,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        let Some(output) = output else {
+            return;
+        };
+        match request {
+            zwlr_gamma_control_v1::Request::SetGamma { fd } => {
+                if state.protocols.gamma_control_state.controls.get(output) != Some(resource) {
+                    // Failed controls are inert until the client destroys them
+                    return;
+                }
+                let Some(size) = state.backend_data.gamma_size(output) else {
+                    state.release_gamma_control(output);
+                    resource.failed();
+                    return;
+                };
+
+                let ramps = match read_ramps(fd, size as usize) {
+                    Ok(Some(ramps)) => ramps,
+                    Ok(None) => {
+                        resource.post_error(
+                            zwlr_gamma_control_v1::Error::InvalidGamma,
+                            "the gamma ramps don't have the correct size",
+                        );
+                        return;
+                    }
+                    Err(err) => {
+                        debug!("Failed to read gamma ramps: {}", err);
+                        state.release_gamma_control(output);
+                        resource.failed();
+                        return;
+                    }
+                };
+
+                if !state.backend_data.set_gamma(output, Some(&ramps)) {
+                    warn!("Failed to set gamma on output {}", output.name());
+                    state.release_gamma_control(output);
+                    resource.failed();
+                }
+            }
+            zwlr_gamma_control_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(
+        state: &mut Self,
+        _client: ClientId,
+        resource: &ZwlrGammaControlV1,
+        output: &Option<Output>,
+    ) {
+        let Some(output) = output else {
+            return;
+        };
+        // Also runs when the client disconnects
+        if state.protocols.gamma_control_state.controls.get(output) == Some(resource) {
+            state.release_gamma_control(output);
+        }
+    }
+}
@@ -27,20 +27,25 @@ use smithay::{
 };
 use tracing::warn;
 
-use crate::state::{Backend, StilchState};
+use crate::{
+    handlers::clipboard::{send_cached_selection, SelectionData},
+    state::{Backend, StilchState},
+};
 
 impl<BackendData: Backend> OutputHandler for StilchState<BackendData> {}
 
 impl<BackendData: Backend> SelectionHandler for StilchState<BackendData> {
-    type SelectionUserData = ();
+    type SelectionUserData = SelectionData;
 
-    #[cfg(feature = "xwayland")]
     fn new_selection(
         &mut self,
         ty: SelectionTarget,
         source: Option<SelectionSource>,
         _seat: Seat<Self>,
     ) {
+        self.capture_selection(ty, source.as_ref());
+
+        #[cfg(feature = "xwayland")]
         if let Some(xwm) = self.xwm.as_mut() {
             if let Err(err) = xwm.new_selection(ty, source.map(|source| source.mime_types())) {
                 warn!(?err, ?ty, "Failed to set Xwayland selection");
@@ -48,18 +53,29 @@ impl<BackendData: Backend> SelectionHandler for StilchState<BackendData> {
         }
     }
 
-    #[cfg(feature = "xwayland")]
     fn send_selection(
         &mut self,
         ty: SelectionTarget,
         mime_type: String,
         fd: OwnedFd,
         _seat: Seat<Self>,
-        _user_data: &(),
+        user_data: &SelectionData,
     ) {
-        if let Some(xwm) = self.xwm.as_mut() {
-            if let Err(err) = xwm.send_selection(ty, mime_type, fd, self.handle.clone()) {
-                warn!(?err, "Failed to send primary (X11 -> Wayland)");
+        match user_data {
+            SelectionData::Cached(contents) => {
+                send_cached_selection(&self.handle, contents, &mime_type, fd)
+            }
+            #[cfg(feature = "xwayland")]
+            SelectionData::Xwayland => {
+                if let Some(xwm) = self.xwm.as_mut() {
+                    if let Err(err) = xwm.send_selection(ty, mime_type, fd, self.handle.clone()) {
+                        warn!(?err, "Failed to send primary (X11 -> Wayland)");
+                    }
+                }
+            }
+            #[cfg(not(feature = "xwayland"))]
+            SelectionData::Xwayland => {
+                let _ = (ty, mime_type, fd);
             }
         }
     }
@@ -95,19 +111,8 @@ impl<BackendData: Backend> XdgActivationHandler for StilchState<BackendData> {
         surface: WlSurface,
     ) {
         if token_data.timestamp.elapsed() < std::time::Duration::from_secs(10) {
-            // Just grant the wish
-            let w = self
-                .space()
-                .elements()
-                .find(|window| {
-                    window
-                        .wl_surface()
-                        .map(|s| s.as_ref() == &surface)
-                        .unwrap_or(false)
-                })
-                .cloned();
-            if let Some(window) = w {
-                self.space_mut().raise_element(&window, true);
+            if let Some(window_id) = self.window_registry().find_by_surface(&surface) {
+                self.request_window_activation(window_id);
             }
         } else {
             tracing::info!("Activation request was too old, ignoring");
@@ -118,7 +123,8 @@ impl<BackendData: Backend> XdgActivationHandler for StilchState<BackendData> {
 impl<BackendData: Backend> XdgDecorationHandler for StilchState<BackendData> {
     fn new_decoration(&mut self, toplevel: smithay::wayland::shell::xdg::ToplevelSurface) {
         use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
-        // Tell clients we'll handle decorations (but we won't actually draw any)
+        // Prefer drawing decorations ourselves: borders and tabs fit the tiling
+        // layout, a client titlebar would sit inside them
         toplevel.with_pending_state(|state| {
             state.decoration_mode = Some(Mode::ServerSide);
         });
@@ -127,14 +133,12 @@ impl<BackendData: Backend> XdgDecorationHandler for StilchState<BackendData> {
     fn request_mode(
         &mut self,
         toplevel: smithay::wayland::shell::xdg::ToplevelSurface,
-        _mode: smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode,
+        mode: smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode,
     ) {
-        use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
-
-        // Always tell clients we're doing server-side decorations
-        // This prevents them from drawing their own decorations
+        // Clients that insist on drawing their own decorations get their way,
+        // stilch then leaves those windows undecorated
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(Mode::ServerSide);
+            state.decoration_mode = Some(mode);
         });
 
         // If the initial configure has been sent, send a new configure
@@ -145,7 +149,7 @@ impl<BackendData: Backend> XdgDecorationHandler for StilchState<BackendData> {
 
     fn unset_mode(&mut self, toplevel: smithay::wayland::shell::xdg::ToplevelSurface) {
         use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode;
-        // Even when unset, claim we're doing server-side decorations
+        // No preference from the client, fall back to ours
         toplevel.with_pending_state(|state| {
             state.decoration_mode = Some(Mode::ServerSide);
         });
@@ -3,8 +3,12 @@
 //! This module contains implementations of various Wayland protocol handlers.
 //! These are separated from the main state module for better organization.
 
+pub mod clipboard;
 pub mod data_device;
+pub mod foreign_toplevel;
+pub mod gamma_control;
 pub mod misc;
+pub mod output_management;
 pub mod seat;
 
 // Re-export handler implementations
@@ -0,0 +1,989 @@
+//! wlr-output-management protocol
+//!
+//! Lets tools such as kanshi and wdisplays inspect and reconfigure physical
+//! outputs at runtime. The heads are kept in sync by diffing the mapped
+//! outputs after each event loop dispatch; every change bumps the serial so
+//! configurations built against stale state are cancelled instead of applied.
+//!
+//! A disabled output is unmapped like an unplugged one, but stays advertised
+//! as a disabled head so it can be enabled again.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, PoisonError},
+};
+
+use smithay::{
+    output::{Mode, Output, Scale},
+    reexports::{
+        wayland_protocols_wlr::output_management::v1::server::{
+            zwlr_output_configuration_head_v1::{self, ZwlrOutputConfigurationHeadV1},
+            zwlr_output_configuration_v1::{self, ZwlrOutputConfigurationV1},
+            zwlr_output_head_v1::{self, ZwlrOutputHeadV1},
+            zwlr_output_manager_v1::{self, ZwlrOutputManagerV1},
+            zwlr_output_mode_v1::{self, ZwlrOutputModeV1},
+        },
+        wayland_server::{
+            backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New,
+            Resource,
+        },
+    },
+    utils::{Logical, Physical, Point, Rectangle, Size, Transform},
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    physical_layout::PhysicalDisplay,
+    state::{Backend, StilchState},
+};
+
+const VERSION: u32 = 3;
+
+/// Snapshot of the properties advertised for one physical output
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadInfo {
+    pub name: String,
+    pub description: String,
+    pub make: String,
+    pub model: String,
+    pub physical_size: (i32, i32),
+    pub modes: Vec<Mode>,
+    pub preferred_mode: Option<Mode>,
+    pub current_mode: Option<Mode>,
+    pub position: Point<i32, Logical>,
+    pub transform: Transform,
+    pub scale: f64,
+    pub enabled: bool,
+}
+
+impl HeadInfo {
+    fn from_output(output: &Output) -> Self {
+        let properties = output.physical_properties();
+        Self {
+            name: output.name(),
+            description: output.description(),
+            make: properties.make,
+            model: properties.model,
+            physical_size: (properties.size.w, properties.size.h),
+            modes: output.modes(),
+            preferred_mode: output.preferred_mode(),
+            current_mode: output.current_mode(),
+            position: output.current_location(),
+            transform: output.current_transform(),
+            scale: output.current_scale().fractional_scale(),
+            enabled: true,
+        }
+    }
+}
+
+/// Changes requested for one head in a configuration
+#[derive(Debug, Default, Clone)]
+pub struct HeadChanges {
+    pub mode: Option<Mode>,
+    /// Custom mode as (width, height, refresh in mHz), resolved against the
+    /// advertised modes when the configuration is applied
+    pub custom_mode: Option<(i32, i32, i32)>,
+    pub position: Option<Point<i32, Logical>>,
+    pub transform: Option<Transform>,
+    pub scale: Option<f64>,
+}
+
+#[derive(Debug)]
+struct ClientHead {
+    head: ZwlrOutputHeadV1,
+    modes: Vec<(Mode, ZwlrOutputModeV1)>,
+}
+
+/// State of a `zwlr_output_configuration_v1` object
+#[derive(Debug)]
+pub struct ConfigurationData {
+    serial: u32,
+    inner: Mutex<ConfigurationInner>,
+}
+
+#[derive(Debug, Default)]
+struct ConfigurationInner {
+    used: bool,
+    configured: HashSet<String>,
+    enabled: Vec<ZwlrOutputConfigurationHeadV1>,
+    disabled: Vec<String>,
+}
+
+/// State of a `zwlr_output_configuration_head_v1` object
+#[derive(Debug)]
+pub struct ConfigurationHeadData {
+    output_name: String,
+    changes: Mutex<HeadChanges>,
+}
+
+/// An output turned off through output management
+#[derive(Debug)]
+struct DisabledOutput {
+    output: Output,
+    /// Its entry in the physical layout, put back when it is enabled again
+    physical_display: Option<PhysicalDisplay>,
+}
+
+/// State of the `zwlr_output_manager_v1` global
+#[derive(Debug)]
+pub struct OutputManagementState {
+    display: DisplayHandle,
+    serial: u32,
+    heads: HashMap<String, HeadInfo>,
+    managers: HashMap<ZwlrOutputManagerV1, HashMap<String, ClientHead>>,
+    disabled: Vec<DisabledOutput>,
+}
+
+impl OutputManagementState {
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<ZwlrOutputManagerV1, ()>
+            + Dispatch<ZwlrOutputManagerV1, ()>
+            + Dispatch<ZwlrOutputHeadV1, String>
+            + Dispatch<ZwlrOutputModeV1, Mode>
+            + 'static,
+    {
+        display.create_global::<D, ZwlrOutputManagerV1, _>(VERSION, ());
+        Self {
+            display: display.clone(),
+            serial: 0,
+            heads: HashMap::new(),
+            managers: HashMap::new(),
+            disabled: Vec::new(),
+        }
+    }
+
+    /// Forget a disabled output that was unplugged
+    pub fn output_removed(&mut self, output: &Output) {
+        self.disabled.retain(|disabled| disabled.output != *output);
+    }
+
+    fn disabled_output(&self, name: &str) -> Option<&Output> {
+        self.disabled
+            .iter()
+            .map(|disabled| &disabled.output)
+            .find(|output| output.name() == name)
+    }
+
+    /// Bring all heads in line with the current outputs, bumping the serial on change
+    pub fn refresh<D>(&mut self, outputs: Vec<HeadInfo>)
+    where
+        D: Dispatch<ZwlrOutputHeadV1, String> + Dispatch<ZwlrOutputModeV1, Mode> + 'static,
+    {
+        let alive: HashSet<&str> = outputs.iter().map(|info| info.name.as_str()).collect();
+        let removed: Vec<String> = self
+            .heads
+            .keys()
+            .filter(|name| !alive.contains(name.as_str()))
+            .cloned()
+            .collect();
+        let mut changed = !removed.is_empty();
+
+        for name in removed {
+            self.heads.remove(&name);
+            for heads in self.managers.values_mut() {
+                if let Some(client_head) = heads.remove(&name) {
+                    client_head.finish();
+                }
+            }
+        }
+
+        for info in outputs {
+            let previous = self.heads.get(&info.name);
+            if previous == Some(&info) {
+                continue;
+            }
+            changed = true;
+
+            for (manager, heads) in &mut self.managers {
+                match (previous, heads.remove(&info.name)) {
+                    (Some(previous), Some(client_head))
+                        if previous.modes == info.modes
+                            && previous.preferred_mode == info.preferred_mode
+                            && previous.enabled == info.enabled =>
+                    {
+                        client_head.update(previous, &info);
+                        heads.insert(info.name.clone(), client_head);
+                    }
+                    // New output, its mode list changed, or it was enabled or
+                    // disabled: mode objects cannot be removed from a live head
+                    // and a disabled head has no current state, so replace it
+                    (_, old_head) => {
+                        if let Some(old_head) = old_head {
+                            old_head.finish();
+                        }
+                        if let Some(client_head) =
+                            ClientHead::new::<D>(&self.display, manager, &info)
+                        {
+                            heads.insert(info.name.clone(), client_head);
+                        }
+                    }
+                }
+            }
+            self.heads.insert(info.name.clone(), info);
+        }
+
+        if changed {
+            self.serial = self.serial.wrapping_add(1);
+            for manager in self.managers.keys() {
+                manager.done(self.serial);
+            }
+        }
+    }
+}
+
+impl ClientHead {
+    fn new<D>(
+        display: &DisplayHandle,
+        manager: &ZwlrOutputManagerV1,
+        info: &HeadInfo,
+    ) -> Option<Self>
+    where
+        D: Dispatch<ZwlrOutputHeadV1, String> + Dispatch<ZwlrOutputModeV1, Mode> + 'static,
+    {
+        let client = manager.client()?;
+        let head = client
+            .create_resource::<ZwlrOutputHeadV1, _, D>(
+                display,
+                manager.version(),
+                info.name.clone(),
+            )
+            .ok()?;
+        manager.head(&head);
+
+        head.name(info.name.clone());
+        head.description(info.description.clone());
+        if info.physical_size.0 > 0 && info.physical_size.1 > 0 {
+            head.physical_size(info.physical_size.0, info.physical_size.1);
+        }
+        if head.version() >= zwlr_output_head_v1::EVT_MAKE_SINCE {
+            head.make(info.make.clone());
+            head.model(info.model.clone());
+        }
+
+        let mut modes = Vec::with_capacity(info.modes.len());
+        for &mode in &info.modes {
+            let Ok(mode_resource) =
+                client.create_resource::<ZwlrOutputModeV1, _, D>(display, head.version(), mode)
+            else {
+                continue;
+            };
+            head.mode(&mode_resource);
+            mode_resource.size(mode.size.w, mode.size.h);
+            if mode.refresh > 0 {
+                mode_resource.refresh(mode.refresh);
+            }
+            if info.preferred_mode == Some(mode) {
+                mode_resource.preferred();
+            }
+            modes.push((mode, mode_resource));
+        }
+
+        let client_head = Self { head, modes };
+        client_head.head.enabled(info.enabled as i32);
+        if info.enabled {
+            client_head.send_current_mode(info.current_mode);
+            client_head.head.position(info.position.x, info.position.y);
+            client_head.head.transform(info.transform.into());
+            client_head.head.scale(info.scale);
+        }
+        Some(client_head)
+    }
+
+    fn update(&self, previous: &HeadInfo, info: &HeadInfo) {
+        if previous.current_mode != info.current_mode {
+            self.send_current_mode(info.current_mode);
+        }
+        if previous.position != info.position {
+            self.head.position(info.position.x, info.position.y);
+        }
+        if previous.transform != info.transform {
+            self.head.transform(info.transform.into());
+        }
+        if previous.scale != info.scale {
+            self.head.scale(info.scale);
+        }
+        if previous.description != info.description {
+            self.head.description(info.description.clone());
+        }
+    }
+
+    fn send_current_mode(&self, current_mode: Option<Mode>) {
+        if let Some((_, mode)) = self
+            .modes
+            .iter()
+            .find(|(mode, _)| Some(*mode) == current_mode)
+        {
+            self.head.current_mode(mode);
+        }
+    }
+
+    fn finish(self) {
+        for (_, mode) in &self.modes {
+            mode.finished();
+        }
+        self.head.finished();
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Push output changes to output management clients
+    pub fn refresh_output_management(&mut self) {
+        let mut outputs: Vec<HeadInfo> =
+            self.space().outputs().map(HeadInfo::from_output).collect();
+        outputs.extend(
+            self.protocols
+                .output_management_state
+                .disabled
+                .iter()
+                .map(|disabled| HeadInfo {
+                    enabled: false,
+                    ..HeadInfo::from_output(&disabled.output)
+                }),
+        );
+        self.protocols
+            .output_management_state
+            .refresh::<Self>(outputs);
+    }
+
+    /// Change an output's fractional scale at runtime
+    ///
+    /// Goes through the same path as output management clients, so the
+    /// virtual outputs on it are resized and their workspaces reflowed.
+    /// Surfaces get the new preferred scale when the output next repaints.
+    pub fn set_output_scale(&mut self, name: &str, scale: f64) {
+        let changes = HeadChanges {
+            scale: Some(scale),
+            ..Default::default()
+        };
+        if !self.apply_output_configuration(vec![(name.to_string(), changes)], Vec::new(), false) {
+            warn!("Failed to set scale {scale} on output {name}");
+            return;
+        }
+        info!("Output {name} scale changed to {scale}");
+        self.clamp_pointer_to_outputs();
+    }
+
+    /// Switch an output to its advertised mode of `size`, with the refresh
+    /// rate (mHz) closest to `refresh`, or the fastest one when not given
+    ///
+    /// Outputs without fixed modes, such as a nested window, are resized
+    /// instead if the backend can. An unsupported size leaves the output as is.
+    pub fn set_output_resolution(
+        &mut self,
+        name: &str,
+        size: Size<i32, Physical>,
+        refresh: Option<u32>,
+    ) {
+        let Some(output) = self.space().outputs().find(|o| o.name() == name).cloned() else {
+            warn!("No output named {name}");
+            return;
+        };
+        let modes = output.modes();
+        let mode = modes
+            .iter()
+            .filter(|mode| mode.size == size)
+            .min_by_key(|mode| match refresh {
+                Some(refresh) => (mode.refresh as i64 - refresh as i64).abs(),
+                None => -(mode.refresh as i64),
+            })
+            .copied();
+
+        match mode {
+            Some(mode) => {
+                let changes = HeadChanges {
+                    mode: Some(mode),
+                    ..Default::default()
+                };
+                if !self.apply_output_configuration(
+                    vec![(name.to_string(), changes)],
+                    Vec::new(),
+                    false,
+                ) {
+                    warn!("Failed to switch output {name} to {:?}", mode);
+                    return;
+                }
+                info!("Output {name} switched to {:?}", mode);
+            }
+            None if self.backend_data.resize_output(&output, size) => {
+                info!("Resizing output {name} to {}x{}", size.w, size.h);
+            }
+            None => {
+                let available: Vec<String> = modes
+                    .iter()
+                    .map(|mode| {
+                        format!(
+                            "{}x{}@{:.3}Hz",
+                            mode.size.w,
+                            mode.size.h,
+                            mode.refresh as f64 / 1000.0
+                        )
+                    })
+                    .collect();
+                warn!(
+                    "Output {name} has no {}x{} mode, available: {}",
+                    size.w,
+                    size.h,
+                    available.join(", ")
+                );
+                return;
+            }
+        }
+        self.clamp_pointer_to_outputs();
+    }
+
+    /// Keep the pointer on the outputs after their geometry changed
+    fn clamp_pointer_to_outputs(&mut self) {
+        let location = self.clamp_pointer_location(self.pointer().current_location());
+        self.pointer().set_location(location);
+    }
+
+    /// Apply (or with `test_only`, just validate) an output configuration
+    ///
+    /// All heads are validated before anything is touched so a rejected
+    /// configuration leaves the outputs unchanged. The backend steps come
+    /// first since they are the only ones that can fail: outputs are turned
+    /// on, modes switched, then outputs turned off, and if one step fails the
+    /// ones already done are undone. A configuration that would leave no
+    /// output enabled is rejected.
+    pub fn apply_output_configuration(
+        &mut self,
+        enabled: Vec<(String, HeadChanges)>,
+        disabled: Vec<String>,
+        test_only: bool,
+    ) -> bool {
+        let mut to_disable = Vec::new();
+        for name in disabled {
+            match self.space().outputs().find(|o| o.name() == name).cloned() {
+                Some(output) => to_disable.push(output),
+                // Already off
+                None if self
+                    .protocols
+                    .output_management_state
+                    .disabled_output(&name)
+                    .is_some() => {}
+                None => {
+                    warn!("Output configuration references unknown output {name}");
+                    return false;
+                }
+            }
+        }
+
+        let mut pending = Vec::with_capacity(enabled.len());
+        for (name, changes) in enabled {
+            let mapped = self.space().outputs().find(|o| o.name() == name).cloned();
+            let (output, enable) = match mapped {
+                Some(output) => (output, false),
+                None => match self
+                    .protocols
+                    .output_management_state
+                    .disabled_output(&name)
+                    .cloned()
+                {
+                    Some(output) => (output, true),
+                    None => {
+                        warn!("Output configuration references unknown output {name}");
+                        return false;
+                    }
+                },
+            };
+            let mode = match (changes.mode, changes.custom_mode) {
+                (Some(mode), _) => Some(mode),
+                (None, Some((width, height, refresh))) => {
+                    let found = output.modes().into_iter().find(|mode| {
+                        mode.size.w == width
+                            && mode.size.h == height
+                            && (refresh == 0 || mode.refresh == refresh)
+                    });
+                    if found.is_none() {
+                        warn!("Output {name} has no mode {width}x{height}@{refresh}");
+                        return false;
+                    }
+                    found
+                }
+                (None, None) => None,
+            }
+            .filter(|mode| Some(*mode) != output.current_mode());
+            if let Some(mode) = mode {
+                if !output.modes().contains(&mode) {
+                    warn!("Output {name} does not support mode {:?}", mode);
+                    return false;
+                }
+            }
+            pending.push((output, mode, changes, enable));
+        }
+
+        let enabled_after = self.space().outputs().count() - to_disable.len()
+            + pending.iter().filter(|(_, _, _, enable)| *enable).count();
+        if enabled_after == 0 {
+            warn!("Refusing output configuration that disables every output");
+            return false;
+        }
+
+        if test_only {
+            return true;
+        }
+
+        // Backend changes, with what it takes to undo them
+        let mut powered: Vec<(Output, bool)> = Vec::new();
+        let mut switched: Vec<(Output, Mode)> = Vec::new();
+        for (output, _, _, enable) in &pending {
+            if !*enable {
+                continue;
+            }
+            if !self.backend_data.set_output_enabled(output, true) {
+                warn!("Backend failed to enable {}", output.name());
+                self.undo_output_changes(switched, powered);
+                return false;
+            }
+            powered.push((output.clone(), true));
+        }
+        for (output, mode, _, _) in &pending {
+            let Some(mode) = *mode else {
+                continue;
+            };
+            let previous = output.current_mode();
+            if !self.backend_data.set_output_mode(output, mode) {
+                warn!("Backend failed to switch {} to {:?}", output.name(), mode);
+                self.undo_output_changes(switched, powered);
+                return false;
+            }
+            if let Some(previous) = previous {
+                switched.push((output.clone(), previous));
+            }
+        }
+        for output in &to_disable {
+            if !self.backend_data.set_output_enabled(output, false) {
+                warn!("Backend failed to disable {}", output.name());
+                self.undo_output_changes(switched, powered);
+                return false;
+            }
+            powered.push((output.clone(), false));
+        }
+
+        for output in to_disable {
+            let physical_display = self
+                .physical_layout
+                .as_ref()
+                .and_then(|layout| layout.display(&output.name()))
+                .cloned();
+            self.protocols.gamma_control_state.output_removed(&output);
+            self.remove_physical_output(&output);
+            info!("Disabled output {}", output.name());
+            self.protocols
+                .output_management_state
+                .disabled
+                .push(DisabledOutput {
+                    output,
+                    physical_display,
+                });
+        }
+
+        let old_geometries: HashMap<Output, Rectangle<i32, Logical>> = pending
+            .iter()
+            .filter_map(|(output, _, _, _)| {
+                Some((output.clone(), self.space().output_geometry(output)?))
+            })
+            .collect();
+
+        for (output, _, changes, _) in &pending {
+            output.change_current_state(
+                None,
+                changes.transform,
+                changes.scale.map(Scale::Fractional),
+                changes.position,
+            );
+            let position = changes
+                .position
+                .unwrap_or_else(|| output.current_location());
+            self.space_mut().map_output(output, position);
+            info!(
+                "Applied output configuration to {}: {:?}",
+                output.name(),
+                changes
+            );
+        }
+
+        let new_geometries: HashMap<Output, Rectangle<i32, Logical>> = self
+            .space()
+            .outputs()
+            .filter_map(|output| Some((output.clone(), self.space().output_geometry(output)?)))
+            .collect();
+        self.virtual_output_manager
+            .relocate_physical_outputs(&old_geometries, &new_geometries);
+
+        for (output, _, _, enable) in pending {
+            self.backend_data.reset_buffers(&output);
+            if enable {
+                self.enable_physical_output(output);
+            }
+        }
+
+        self.update_tiling_area_from_output();
+        self.update_all_workspace_layouts();
+        self.backend_data.request_render();
+        true
+    }
+
+    /// Put an output back after the backend turned it on again, with the
+    /// virtual outputs its config asks for
+    fn enable_physical_output(&mut self, output: Output) {
+        let name = output.name();
+        let Some(index) = self
+            .protocols
+            .output_management_state
+            .disabled
+            .iter()
+            .position(|disabled| disabled.output == output)
+        else {
+            return;
+        };
+        let disabled = self
+            .protocols
+            .output_management_state
+            .disabled
+            .remove(index);
+        let Some(geometry) = self.space().output_geometry(&output) else {
+            return;
+        };
+
+        if let Some(mut physical_display) = disabled.physical_display {
+            physical_display.logical_position = geometry.loc;
+            physical_display.logical_size = geometry.size;
+            if let Some(ref mut physical_layout) = self.physical_layout {
+                physical_layout.add_display(physical_display);
+            }
+        }
+
+        let split = self
+            .config
+            .outputs
+            .iter()
+            .find(|o| o.name == name || o.name == "*")
+            .and_then(|o| o.split.clone());
+        let virtual_outputs = match split {
+            Some((split_type, count)) => self
+                .virtual_output_manager
+                .split_physical(output, geometry, split_type, count),
+            None => vec![self
+                .virtual_output_manager
+                .create_from_physical(output, geometry)],
+        };
+        for vo_id in virtual_outputs {
+            self.initialize_virtual_output(vo_id);
+        }
+        info!("Enabled output {name}");
+    }
+
+    /// Undo backend steps of a configuration that failed part way
+    fn undo_output_changes(&mut self, switched: Vec<(Output, Mode)>, powered: Vec<(Output, bool)>) {
+        for (output, previous) in switched {
+            self.backend_data.set_output_mode(&output, previous);
+        }
+        for (output, enabled) in powered {
+            self.backend_data.set_output_enabled(&output, !enabled);
+        }
+    }
+}
+
+impl<BackendData: Backend> GlobalDispatch<ZwlrOutputManagerV1, ()> for StilchState<BackendData> {
+    fn bind(
+        state: &mut Self,
+        handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrOutputManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let manager = data_init.init(resource, ());
+
+        let protocol_state = &mut state.protocols.output_management_state;
+        let heads = protocol_state
+            .heads
+            .values()
+            .filter_map(|info| {
+                ClientHead::new::<Self>(handle, &manager, info)
+                    .map(|client_head| (info.name.clone(), client_head))
+            })
+            .collect();
+        manager.done(protocol_state.serial);
+        protocol_state.managers.insert(manager, heads);
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputManagerV1, ()> for StilchState<BackendData> {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrOutputManagerV1,
+        request: zwlr_output_manager_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwlr_output_manager_v1::Request::CreateConfiguration { id, serial } => {
+                data_init.init(
+                    id,
+                    ConfigurationData {
+                        serial,
+                        inner: Mutex::new(ConfigurationInner::default()),
+                    },
+                );
+            }
+            zwlr_output_manager_v1::Request::Stop => {
+                resource.finished();
+                if let Some(heads) = state
+                    .protocols
+                    .output_management_state
+                    .managers
+                    .remove(resource)
+                {
+                    for client_head in heads.into_values() {
+                        client_head.finish();
+                    }
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &ZwlrOutputManagerV1, _data: &()) {
+        state
+            .protocols
+            .output_management_state
+            .managers
+            .remove(resource);
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputHeadV1, String> for StilchState<BackendData> {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrOutputHeadV1,
+        request: zwlr_output_head_v1::Request,
+        _name: &String,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwlr_output_head_v1::Request::Release => {
+                for heads in state
+                    .protocols
+                    .output_management_state
+                    .managers
+                    .values_mut()
+                {
+                    heads.retain(|_, client_head| client_head.head != *resource);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputModeV1, Mode> for StilchState<BackendData> {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &ZwlrOutputModeV1,
+        request: zwlr_output_mode_v1::Request,
+        _mode: &Mode,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            zwlr_output_mode_v1::Request::Release => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputConfigurationV1, ConfigurationData>
+    for StilchState<BackendData>
+{
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrOutputConfigurationV1,
+        request: zwlr_output_configuration_v1::Request,
+        data: &ConfigurationData,
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let test_only = match request {
+            zwlr_output_configuration_v1::Request::EnableHead { id, head } => {
+                let Some(name) = head.data::<String>().cloned() else {
+                    return;
+                };
+                let mut inner = data.inner.lock().unwrap_or_else(PoisonError::into_inner);
+                if !inner.configured.insert(name.clone()) {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+                        format!("head {name} was already configured"),
+                    );
+                    return;
+                }
+                let configuration_head = data_init.init(
+                    id,
+                    ConfigurationHeadData {
+                        output_name: name,
+                        changes: Mutex::new(HeadChanges::default()),
+                    },
+                );
+                inner.enabled.push(configuration_head);
+                return;
+            }
+            zwlr_output_configuration_v1::Request::DisableHead { head } => {
+                let Some(name) = head.data::<String>().cloned() else {
+                    return;
+                };
+                let mut inner = data.inner.lock().unwrap_or_else(PoisonError::into_inner);
+                if !inner.configured.insert(name.clone()) {
+                    resource.post_error(
+                        zwlr_output_configuration_v1::Error::AlreadyConfiguredHead,
+                        format!("head {name} was already configured"),
+                    );
+                    return;
+                }
+                inner.disabled.push(name);
+                return;
+            }
+            zwlr_output_configuration_v1::Request::Apply => false,
+            zwlr_output_configuration_v1::Request::Test => true,
+            zwlr_output_configuration_v1::Request::Destroy => return,
+            _ => unreachable!(),
+        };
+
+        let (enabled, disabled) = {
+            let mut inner = data.inner.lock().unwrap_or_else(PoisonError::into_inner);
+            if inner.used {
+                resource.post_error(
+                    zwlr_output_configuration_v1::Error::AlreadyUsed,
+                    "configuration was already applied or tested",
+                );
+                return;
+            }
+            inner.used = true;
+
+            let enabled: Vec<_> = inner
+                .enabled
+                .iter()
+                .filter_map(|head| head.data::<ConfigurationHeadData>())
+                .map(|head| {
+                    (
+                        head.output_name.clone(),
+                        head.changes
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .clone(),
+                    )
+                })
+                .collect();
+            (enabled, std::mem::take(&mut inner.disabled))
+        };
+
+        let protocol_state = &state.protocols.output_management_state;
+        if data.serial != protocol_state.serial {
+            debug!(
+                "Cancelling output configuration with stale serial {} (current {})",
+                data.serial, protocol_state.serial
+            );
+            resource.cancelled();
+            return;
+        }
+        if let Some(name) = protocol_state.heads.keys().find(|name| {
+            !enabled.iter().any(|(enabled, _)| enabled == *name) && !disabled.contains(*name)
+        }) {
+            resource.post_error(
+                zwlr_output_configuration_v1::Error::UnconfiguredHead,
+                format!("head {name} was neither enabled nor disabled"),
+            );
+            return;
+        }
+        if state.apply_output_configuration(enabled, disabled, test_only) {
+            resource.succeeded();
+        } else {
+            resource.failed();
+        }
+    }
+}
+
+impl<BackendData: Backend> Dispatch<ZwlrOutputConfigurationHeadV1, ConfigurationHeadData>
+    for StilchState<BackendData>
+{
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrOutputConfigurationHeadV1,
+        request: zwlr_output_configuration_head_v1::Request,
+        data: &ConfigurationHeadData,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        use zwlr_output_configuration_head_v1::Error;
+
+        let mut changes = data.changes.lock().unwrap_or_else(PoisonError::into_inner);
+        let already_set = |resource: &ZwlrOutputConfigurationHeadV1, property: &str| {
+            resource.post_error(Error::AlreadySet, format!("{property} was already set"));
+        };
+
+        match request {
+            zwlr_output_configuration_head_v1::Request::SetMode { mode } => {
+                if changes.mode.is_some() || changes.custom_mode.is_some() {
+                    return already_set(resource, "mode");
+                }
+                let Some(&mode) = mode.data::<Mode>() else {
+                    resource.post_error(Error::InvalidMode, "unknown mode");
+                    return;
+                };
+                changes.mode = Some(mode);
+            }
+            zwlr_output_configuration_head_v1::Request::SetCustomMode {
+                width,
+                height,
+                refresh,
+            } => {
+                if changes.mode.is_some() || changes.custom_mode.is_some() {
+                    return already_set(resource, "mode");
+                }
+                if width <= 0 || height <= 0 || refresh < 0 {
+                    resource.post_error(
+                        Error::InvalidCustomMode,
+                        format!("invalid custom mode {width}x{height}@{refresh}"),
+                    );
+                    return;
+                }
+                changes.custom_mode = Some((width, height, refresh));
+            }
+            zwlr_output_configuration_head_v1::Request::SetPosition { x, y } => {
+                if changes.position.is_some() {
+                    return already_set(resource, "position");
+                }
+                changes.position = Some(Point::from((x, y)));
+            }
+            zwlr_output_configuration_head_v1::Request::SetTransform { transform } => {
+                if changes.transform.is_some() {
+                    return already_set(resource, "transform");
+                }
+                let Ok(transform) = transform.into_result() else {
+                    resource.post_error(Error::InvalidTransform, "invalid transform");
+                    return;
+                };
+                changes.transform = Some(transform.into());
+            }
+            zwlr_output_configuration_head_v1::Request::SetScale { scale } => {
+                if changes.scale.is_some() {
+                    return already_set(resource, "scale");
+                }
+                if !scale.is_finite() || scale <= 0.0 {
+                    resource.post_error(Error::InvalidScale, format!("invalid scale {scale}"));
+                    return;
+                }
+                changes.scale = Some(scale);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
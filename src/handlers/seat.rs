@@ -18,7 +18,7 @@ use smithay::{
         input_method::{InputMethodHandler, PopupSurface},
         keyboard_shortcuts_inhibit::{
             KeyboardShortcutsInhibitHandler, KeyboardShortcutsInhibitState,
-            KeyboardShortcutsInhibitor,
+            KeyboardShortcutsInhibitor, KeyboardShortcutsInhibitorSeat,
         },
         pointer_constraints::{with_pointer_constraint, PointerConstraintsHandler},
         seat::WaylandFocus, // Trait needed for wl_surface() method
@@ -46,6 +46,31 @@ impl<BackendData: Backend> SeatHandler for StilchState<BackendData> {
         let dh = &self.display_handle;
 
         let wl_surface = target.and_then(|t| t.wl_surface());
+        self.update_inhibit_focus(seat, wl_surface.as_deref().cloned());
+
+        let focused_window_id = match target {
+            Some(KeyboardFocusTarget::Window(window)) => {
+                self.window_registry().find_by_window(window)
+            }
+            _ => None,
+        };
+        let unfocused_window_id =
+            std::mem::replace(&mut self.keyboard_focus_window, focused_window_id);
+        if let Some(window_id) = unfocused_window_id.filter(|id| Some(*id) != focused_window_id) {
+            self.event_bus
+                .emit_window(crate::event::WindowEvent::Unfocused {
+                    window_id,
+                    timestamp: std::time::Instant::now(),
+                });
+        }
+        if let Some(window_id) = focused_window_id {
+            self.focus_history.record(window_id);
+            self.event_bus
+                .emit_window(crate::event::WindowEvent::Focused {
+                    window_id,
+                    timestamp: std::time::Instant::now(),
+                });
+        }
 
         if let Some(surface) = wl_surface {
             use smithay::reexports::wayland_server::Resource;
@@ -114,8 +139,32 @@ impl<BackendData: Backend> KeyboardShortcutsInhibitHandler for StilchState<Backe
     }
 
     fn new_inhibitor(&mut self, inhibitor: KeyboardShortcutsInhibitor) {
-        // Just grant the wish for everyone
-        inhibitor.activate();
+        // Granted right away for the focused surface, otherwise once it gets focus
+        if self.input_manager.inhibit_focus.as_ref() == Some(inhibitor.wl_surface()) {
+            inhibitor.activate();
+        }
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Move shortcut inhibition along with keyboard focus: the surface losing
+    /// focus has its inhibitor deactivated, the one gaining it activated. This
+    /// also re-arms an inhibitor turned off with the escape chord.
+    fn update_inhibit_focus(&mut self, seat: &Seat<Self>, focused: Option<WlSurface>) {
+        if self.input_manager.inhibit_focus == focused {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.input_manager.inhibit_focus, focused.clone());
+        if let Some(inhibitor) =
+            previous.and_then(|surface| seat.keyboard_shortcuts_inhibitor_for_surface(&surface))
+        {
+            inhibitor.inactivate();
+        }
+        if let Some(inhibitor) =
+            focused.and_then(|surface| seat.keyboard_shortcuts_inhibitor_for_surface(&surface))
+        {
+            inhibitor.activate();
+        }
     }
 }
 
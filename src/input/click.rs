@@ -0,0 +1,71 @@
+//! Multi-click detection
+//!
+//! Presses of the same button close together in time and place count up to
+//! double and triple clicks, within `double_click_time` milliseconds of each
+//! other. Tabs, title bars and floating windows all read the count from here
+//! instead of timing clicks themselves.
+
+use smithay::utils::{Logical, Point};
+
+/// How far the pointer may drift between presses of one multi-click
+const CLICK_SLOP: f64 = 4.0;
+
+/// Double-click threshold used when the config doesn't set one
+pub const DEFAULT_DOUBLE_CLICK_TIME: u32 = 400;
+
+/// A button press, with the presses before it counted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Click {
+    Single,
+    Double,
+    Triple,
+}
+
+/// The last press, which the next one may continue
+#[derive(Debug, Clone, Copy)]
+struct LastPress {
+    button: u32,
+    time: u32,
+    location: Point<f64, Logical>,
+    click: Click,
+}
+
+/// Counts consecutive presses into double and triple clicks
+#[derive(Debug, Default)]
+pub struct ClickTracker {
+    last: Option<LastPress>,
+}
+
+impl ClickTracker {
+    /// Record a press at `time` (milliseconds) and return which click it makes.
+    /// A fourth quick press starts over at a single click.
+    pub fn press(
+        &mut self,
+        button: u32,
+        time: u32,
+        location: Point<f64, Logical>,
+        threshold: u32,
+    ) -> Click {
+        let continues = self.last.is_some_and(|last| {
+            last.button == button
+                && time.wrapping_sub(last.time) <= threshold
+                && (location.x - last.location.x).abs() <= CLICK_SLOP
+                && (location.y - last.location.y).abs() <= CLICK_SLOP
+        });
+        let click = match self.last {
+            Some(last) if continues => match last.click {
+                Click::Single => Click::Double,
+                Click::Double => Click::Triple,
+                Click::Triple => Click::Single,
+            },
+            _ => Click::Single,
+        };
+        self.last = Some(LastPress {
+            button,
+            time,
+            location,
+            click,
+        });
+        click
+    }
+}
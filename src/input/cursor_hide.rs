@@ -0,0 +1,103 @@
+//! Hiding the cursor while typing or when idle
+//!
+//! `hide_cursor_when_typing` hides the cursor on key presses, and
+//! `hide_cursor timeout <ms>` hides it once the pointer has been idle that
+//! long. While hidden the cursor isn't drawn and no surface has pointer
+//! focus, but the image clients asked for is kept. Pointer activity shows it
+//! again and re-enters the surface under it.
+
+use std::time::{Duration, Instant};
+
+use smithay::{
+    input::pointer::MotionEvent,
+    reexports::calloop::{
+        timer::{TimeoutAction, Timer},
+        LoopHandle,
+    },
+    utils::SERIAL_COUNTER,
+};
+use tracing::{debug, warn};
+
+use crate::state::{Backend, StilchState};
+
+/// Hide the cursor once the pointer has been idle for `timeout` milliseconds
+pub fn start_cursor_idle_timer<BackendData: Backend + 'static>(
+    handle: &LoopHandle<'static, StilchState<BackendData>>,
+    timeout: u32,
+) {
+    let timeout = Duration::from_millis(timeout as u64);
+    let source = handle.insert_source(
+        Timer::from_duration(timeout),
+        move |_, _, state: &mut StilchState<BackendData>| {
+            let idle = state.input_manager.last_pointer_activity.elapsed();
+            if idle >= timeout {
+                state.hide_cursor();
+                TimeoutAction::ToDuration(timeout)
+            } else {
+                TimeoutAction::ToDuration(timeout - idle)
+            }
+        },
+    );
+    if let Err(err) = source {
+        warn!("Failed to start the cursor idle timer: {}", err);
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Stop drawing the cursor and take pointer focus away from clients
+    pub fn hide_cursor(&mut self) {
+        if self.input_manager.cursor_hidden {
+            return;
+        }
+        // Buttons held for a drag or an open menu keep the cursor
+        let pointer = self.pointer().clone();
+        if pointer.is_grabbed() {
+            return;
+        }
+        debug!("Hiding cursor");
+        self.input_manager.cursor_hidden = true;
+
+        pointer.motion(
+            self,
+            None,
+            &MotionEvent {
+                location: pointer.current_location(),
+                serial: SERIAL_COUNTER.next_serial(),
+                time: self.clock.now().as_millis() as u32,
+            },
+        );
+        pointer.frame(self);
+        self.backend_data.request_render();
+    }
+
+    /// Pointer activity: restart the idle timeout and show the cursor again
+    /// over whatever is under it now
+    pub fn reveal_cursor(&mut self) {
+        self.input_manager.last_pointer_activity = Instant::now();
+        if !self.input_manager.cursor_hidden {
+            return;
+        }
+        debug!("Showing cursor");
+        self.input_manager.cursor_hidden = false;
+
+        let pointer = self.pointer().clone();
+        let location = pointer.current_location();
+        let under = self.surface_under(location);
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                location,
+                serial: SERIAL_COUNTER.next_serial(),
+                time: self.clock.now().as_millis() as u32,
+            },
+        );
+        pointer.frame(self);
+        self.backend_data.request_render();
+    }
+
+    /// Whether the cursor is currently hidden by typing or idling
+    pub fn cursor_hidden(&self) -> bool {
+        self.input_manager.cursor_hidden
+    }
+}
@@ -1,4 +1,15 @@
 //! Gesture input handling
+//!
+//! Swipes and pinches matching a `bindgesture` binding are consumed by the
+//! compositor and run their command when they end past the configured
+//! threshold. Horizontal swipes bound to workspace switching slide the
+//! current workspace along with the fingers while in progress. Holds run
+//! their command when the fingers are lifted after resting long enough.
+//! Gestures without a binding are forwarded to clients.
+//!
+//! libinput cancels a hold as soon as the fingers move and starts a swipe
+//! instead. When that swipe is bound too, it carries the hold along and a
+//! swipe that barely moved still runs the hold binding.
 
 use smithay::{
     backend::input::{
@@ -10,14 +21,66 @@ use smithay::{
         GesturePinchUpdateEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
         GestureSwipeUpdateEvent,
     },
+    utils::{Logical, Point},
 };
 
+use crate::config::{Command, GestureDirection, GestureKind};
+use crate::input::manager::{ActiveGesture, InterruptedHold};
 use crate::state::StilchState;
 
+/// How long fingers must rest for a hold binding to run, in milliseconds
+const HOLD_DURATION: u32 = 300;
+/// How far a hold may drift, in logical pixels, and still count as a hold
+const HOLD_SLOP: f64 = 10.0;
+/// libinput starts the swipe right after cancelling the hold, in milliseconds
+const HOLD_TO_SWIPE: u32 = 20;
+
+/// Direction of a finished swipe, if it travelled far enough
+fn swipe_direction(delta: Point<f64, Logical>, threshold: f64) -> Option<GestureDirection> {
+    if delta.x.abs() >= delta.y.abs() {
+        if delta.x.abs() < threshold {
+            None
+        } else if delta.x < 0.0 {
+            Some(GestureDirection::Left)
+        } else {
+            Some(GestureDirection::Right)
+        }
+    } else if delta.y.abs() < threshold {
+        None
+    } else if delta.y < 0.0 {
+        Some(GestureDirection::Up)
+    } else {
+        Some(GestureDirection::Down)
+    }
+}
+
+/// Direction of a finished pinch, if it scaled far enough
+fn pinch_direction(scale: f64, threshold: f64) -> Option<GestureDirection> {
+    if scale <= 1.0 - threshold {
+        Some(GestureDirection::Inward)
+    } else if scale >= 1.0 + threshold {
+        Some(GestureDirection::Outward)
+    } else {
+        None
+    }
+}
+
 #[cfg(feature = "udev")]
 impl StilchState<crate::udev::UdevData> {
     /// Handle gesture swipe begin
     pub fn on_gesture_swipe_begin<B: InputBackend>(&mut self, evt: B::GestureSwipeBeginEvent) {
+        let hold_start = self
+            .input_manager
+            .interrupted_hold
+            .take()
+            .filter(|hold| {
+                hold.fingers == evt.fingers()
+                    && evt.time_msec().wrapping_sub(hold.cancelled_at) <= HOLD_TO_SWIPE
+            })
+            .map(|hold| hold.start);
+        if self.begin_bound_gesture(GestureKind::Swipe, evt.fingers(), hold_start) {
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.gesture_swipe_begin(
             self,
@@ -31,6 +94,11 @@ impl StilchState<crate::udev::UdevData> {
 
     /// Handle gesture swipe update
     pub fn on_gesture_swipe_update<B: InputBackend>(&mut self, evt: B::GestureSwipeUpdateEvent) {
+        if let Some(gesture) = self.input_manager.gesture.as_mut() {
+            gesture.delta += evt.delta();
+            self.update_gesture_preview();
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.gesture_swipe_update(
             self,
@@ -43,6 +111,10 @@ impl StilchState<crate::udev::UdevData> {
 
     /// Handle gesture swipe end
     pub fn on_gesture_swipe_end<B: InputBackend>(&mut self, evt: B::GestureSwipeEndEvent) {
+        if self.input_manager.gesture.is_some() {
+            self.end_bound_gesture(evt.cancelled(), evt.time_msec());
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.gesture_swipe_end(
             self,
@@ -56,6 +128,9 @@ impl StilchState<crate::udev::UdevData> {
 
     /// Handle gesture pinch begin
     pub fn on_gesture_pinch_begin<B: InputBackend>(&mut self, evt: B::GesturePinchBeginEvent) {
+        if self.begin_bound_gesture(GestureKind::Pinch, evt.fingers(), None) {
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.gesture_pinch_begin(
             self,
@@ -69,6 +144,10 @@ impl StilchState<crate::udev::UdevData> {
 
     /// Handle gesture pinch update
     pub fn on_gesture_pinch_update<B: InputBackend>(&mut self, evt: B::GesturePinchUpdateEvent) {
+        if let Some(gesture) = self.input_manager.gesture.as_mut() {
+            gesture.scale = evt.scale();
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.gesture_pinch_update(
             self,
@@ -83,6 +162,10 @@ impl StilchState<crate::udev::UdevData> {
 
     /// Handle gesture pinch end
     pub fn on_gesture_pinch_end<B: InputBackend>(&mut self, evt: B::GesturePinchEndEvent) {
+        if self.input_manager.gesture.is_some() {
+            self.end_bound_gesture(evt.cancelled(), evt.time_msec());
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.gesture_pinch_end(
             self,
@@ -96,6 +179,10 @@ impl StilchState<crate::udev::UdevData> {
 
     /// Handle gesture hold begin
     pub fn on_gesture_hold_begin<B: InputBackend>(&mut self, evt: B::GestureHoldBeginEvent) {
+        self.input_manager.interrupted_hold = None;
+        if self.begin_bound_gesture(GestureKind::Hold, evt.fingers(), Some(evt.time_msec())) {
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.gesture_hold_begin(
             self,
@@ -109,6 +196,27 @@ impl StilchState<crate::udev::UdevData> {
 
     /// Handle gesture hold end
     pub fn on_gesture_hold_end<B: InputBackend>(&mut self, evt: B::GestureHoldEndEvent) {
+        let bound_hold = self
+            .input_manager
+            .gesture
+            .as_ref()
+            .is_some_and(|gesture| gesture.kind == GestureKind::Hold);
+        if bound_hold {
+            if evt.cancelled() {
+                // The fingers moved; a swipe follows that may continue the hold
+                if let Some(gesture) = self.input_manager.gesture.take() {
+                    self.input_manager.interrupted_hold =
+                        gesture.hold_start.map(|start| InterruptedHold {
+                            fingers: gesture.fingers,
+                            start,
+                            cancelled_at: evt.time_msec(),
+                        });
+                }
+            } else {
+                self.end_bound_gesture(false, evt.time_msec());
+            }
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.gesture_hold_end(
             self,
@@ -119,4 +227,139 @@ impl StilchState<crate::udev::UdevData> {
             },
         );
     }
+
+    /// Start tracking a gesture if a binding uses it. Returns true if the
+    /// gesture is consumed by the compositor.
+    fn begin_bound_gesture(
+        &mut self,
+        kind: GestureKind,
+        fingers: u32,
+        hold_start: Option<u32>,
+    ) -> bool {
+        let gestures = &self.config.gestures;
+        if !gestures.binds(kind, fingers) {
+            return false;
+        }
+
+        // Only preview when a horizontal swipe would switch workspaces
+        let switches_workspace = kind == GestureKind::Swipe
+            && [GestureDirection::Left, GestureDirection::Right]
+                .into_iter()
+                .filter_map(|direction| gestures.find(kind, fingers, direction))
+                .any(|binding| matches!(binding.command, Command::Workspace(_)));
+        let preview = if switches_workspace {
+            self.gesture_preview_windows()
+        } else {
+            None
+        };
+
+        self.input_manager.gesture = Some(ActiveGesture {
+            kind,
+            fingers,
+            delta: Point::default(),
+            scale: 1.0,
+            preview,
+            hold_start,
+        });
+        true
+    }
+
+    /// Windows of the workspace under the pointer, with their current locations
+    fn gesture_preview_windows(
+        &self,
+    ) -> Option<(
+        crate::workspace::WorkspaceId,
+        Vec<(crate::shell::WindowElement, Point<i32, Logical>)>,
+    )> {
+        let pointer_loc = self.pointer().current_location().to_i32_round();
+        let vo_id = self.virtual_output_manager.virtual_output_at(pointer_loc)?;
+        let workspace_id = self
+            .virtual_output_manager
+            .get(vo_id)?
+            .active_workspace_id()?;
+        let workspace = self.workspace_manager.get_workspace(workspace_id)?;
+
+        let space = self.window_manager.space();
+        let windows = workspace
+            .windows
+            .iter()
+            .filter_map(|&id| self.window_registry().get(id))
+            .filter_map(|managed| {
+                let loc = space.element_location(&managed.element)?;
+                Some((managed.element.clone(), loc))
+            })
+            .collect();
+        Some((workspace_id, windows))
+    }
+
+    /// Slide the previewed workspace horizontally with the swipe
+    fn update_gesture_preview(&mut self) {
+        let Some(gesture) = &self.input_manager.gesture else {
+            return;
+        };
+        let Some((workspace_id, windows)) = &gesture.preview else {
+            return;
+        };
+        let Some(area) = self
+            .workspace_manager
+            .get_workspace(*workspace_id)
+            .map(|workspace| workspace.area)
+        else {
+            return;
+        };
+
+        let offset = (gesture.delta.x.round() as i32).clamp(-area.size.w, area.size.w);
+        let windows = windows.clone();
+        for (element, loc) in windows {
+            self.window_manager
+                .space_mut()
+                .map_element(element, (loc.x + offset, loc.y), false);
+        }
+        self.backend_data.request_render();
+    }
+
+    /// Finish a bound gesture, running its command if it went far enough
+    fn end_bound_gesture(&mut self, cancelled: bool, time: u32) {
+        let Some(gesture) = self.input_manager.gesture.take() else {
+            return;
+        };
+
+        // Put the previewed workspace back before any switch happens
+        if let Some((workspace_id, _)) = gesture.preview {
+            self.apply_workspace_layout(workspace_id);
+            self.backend_data.request_render();
+        }
+        if cancelled {
+            return;
+        }
+
+        let gestures = &self.config.gestures;
+        let held = gesture
+            .hold_start
+            .is_some_and(|start| time.wrapping_sub(start) >= HOLD_DURATION);
+        let (kind, direction) = match gesture.kind {
+            GestureKind::Swipe => match swipe_direction(gesture.delta, gestures.swipe_threshold) {
+                // A hold whose fingers drifted a little is still a hold
+                None if held && gesture.delta.x.hypot(gesture.delta.y) <= HOLD_SLOP => {
+                    (GestureKind::Hold, Some(GestureDirection::Still))
+                }
+                direction => (GestureKind::Swipe, direction),
+            },
+            GestureKind::Pinch => (
+                GestureKind::Pinch,
+                pinch_direction(gesture.scale, gestures.pinch_threshold),
+            ),
+            GestureKind::Hold => (GestureKind::Hold, held.then_some(GestureDirection::Still)),
+        };
+        let Some(binding) =
+            direction.and_then(|direction| gestures.find(kind, gesture.fingers, direction))
+        else {
+            return;
+        };
+
+        tracing::debug!(?binding, "Gesture binding triggered");
+        if let Some(action) = self.command_to_action(&binding.command) {
+            self.handle_key_action(action);
+        }
+    }
 }
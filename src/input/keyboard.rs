@@ -1,23 +1,21 @@
 //! Keyboard input handling
 
+use std::time::Duration;
+
 use smithay::{
     backend::input::{Event, InputBackend, KeyState, KeyboardKeyEvent},
-    desktop::layer_map_for_output,
-    input::keyboard::FilterResult,
+    input::keyboard::{FilterResult, Keycode},
+    reexports::calloop::timer::{TimeoutAction, Timer},
     utils::SERIAL_COUNTER as SCOUNTER,
-    wayland::{
-        compositor::with_states,
-        keyboard_shortcuts_inhibit::KeyboardShortcutsInhibitorSeat,
-        shell::wlr_layer::{KeyboardInteractivity, Layer as WlrLayer, LayerSurfaceCachedState},
-    },
+    wayland::{keyboard_shortcuts_inhibit::KeyboardShortcutsInhibitorSeat, seat::WaylandFocus},
 };
-use tracing::debug;
+use tracing::{debug, warn};
 
 #[cfg(feature = "udev")]
 use smithay::backend::session::Session;
 
 use crate::{
-    keybindings::KeyAction,
+    keybindings::{BindingAction, KeyAction},
     state::{Backend, StilchState},
 };
 
@@ -38,6 +36,20 @@ impl<BackendData: Backend> StilchState<BackendData> {
         let keycode = evt.key_code();
         let state = evt.state();
         debug!(?keycode, ?state, "key");
+
+        // Another key press, or releasing the held key, ends any binding repeat
+        let repeating_key = self.input_manager.binding_repeat.as_ref().map(|(k, _)| *k);
+        if state == KeyState::Pressed || repeating_key == Some(keycode) {
+            self.stop_binding_repeat();
+        }
+        if state == KeyState::Released {
+            self.input_manager
+                .held_keys
+                .retain(|&(held, _)| held != keycode);
+        }
+        if state == KeyState::Pressed && self.config.hide_cursor_when_typing {
+            self.hide_cursor();
+        }
         let serial = SCOUNTER.next_serial();
         let time = Event::time_msec(&evt);
         let keyboard = self
@@ -46,42 +58,26 @@ impl<BackendData: Backend> StilchState<BackendData> {
             // SAFETY: We always initialize the keyboard with the seat
             .expect("Keyboard not initialized");
 
-        // Check layer shell exclusive keyboard
-        for layer in self.protocols.layer_shell_state.layer_surfaces().rev() {
-            let data = with_states(layer.wl_surface(), |states| {
-                *states
-                    .cached_state
-                    .get::<LayerSurfaceCachedState>()
-                    .current()
+        // An exclusive layer surface gets every key, bypassing keybindings
+        if let Some(layer) = self.exclusive_layer() {
+            self.focus_layer_surface(&layer);
+            keyboard.input::<(), _>(self, keycode, state, serial, time, |_, _, _| {
+                FilterResult::Forward
             });
-            if data.keyboard_interactivity == KeyboardInteractivity::Exclusive
-                && (data.layer == WlrLayer::Top || data.layer == WlrLayer::Overlay)
-            {
-                let surface = self.space().outputs().find_map(|o| {
-                    let map = layer_map_for_output(o);
-                    let cloned = map.layers().find(|l| l.layer_surface() == &layer).cloned();
-                    cloned
-                });
-                if let Some(surface) = surface {
-                    keyboard.set_focus(self, Some(surface.into()), serial);
-                    keyboard.input::<(), _>(self, keycode, state, serial, time, |_, _, _| {
-                        FilterResult::Forward
-                    });
-                    return KeyAction::None;
-                };
-            }
+            return KeyAction::None;
         }
 
-        let inhibited = self
-            .space()
-            .element_under(self.pointer().current_location())
-            .and_then(|(window, _)| {
-                let surface = window.wl_surface()?;
+        // Only the focused surface's inhibitor is ever active
+        let inhibitor = keyboard
+            .current_focus()
+            .and_then(|focus| focus.wl_surface().map(|surface| surface.into_owned()))
+            .and_then(|surface| {
                 self.seat()
                     .keyboard_shortcuts_inhibitor_for_surface(&surface)
-            })
-            .map(|inhibitor| inhibitor.is_active())
-            .unwrap_or(false);
+            });
+        let inhibited = inhibitor
+            .as_ref()
+            .is_some_and(|inhibitor| inhibitor.is_active());
 
         // Process the key input, checking for keybindings
         let action = keyboard.input(
@@ -104,61 +100,144 @@ impl<BackendData: Backend> StilchState<BackendData> {
                     "keysym"
                 );
 
+                // Nested backends may remap the logo key for bindings
+                let modifiers = stilch.backend_data.binding_modifiers(*modifiers);
+
+                let keysym = raw_keysym.unwrap_or(modified_keysym);
+                let held_keys = &mut stilch.input_manager.held_keys;
+                if state == KeyState::Pressed && !held_keys.iter().any(|&(k, _)| k == keycode) {
+                    held_keys.push((keycode, keysym));
+                }
+
                 // Check if keyboard is grabbed
                 if keyboard.is_grabbed() && !inhibited {
                     return FilterResult::Forward;
                 }
 
-                // Only check keybindings on key press, not release
-                if let KeyState::Pressed = state {
-                    if !inhibited {
-                        // Check if this is a keybinding
-                        match stilch.process_keybinding(
-                            *modifiers,
-                            modified_keysym,
-                            raw_keysym,
-                            state,
-                        ) {
-                            FilterResult::Intercept(action) => {
-                                // Suppress the raw keysym if available, otherwise the modified one
-                                let keysym_to_suppress = raw_keysym.unwrap_or(modified_keysym);
-                                stilch
-                                    .input_manager
-                                    .suppressed_keys
-                                    .push(keysym_to_suppress);
-                                // Return the action to be handled after this closure
-                                return FilterResult::Intercept(action);
-                            }
-                            FilterResult::Forward => {
-                                // Not a keybinding, forward to client
-                                return FilterResult::Forward;
-                            }
-                        }
-                    } else {
-                        // Forward when inhibited
+                if let KeyState::Released = state {
+                    // Only keys whose press was intercepted can trigger --release bindings,
+                    // and their release never reaches the client
+                    if !stilch.input_manager.suppressed_keys.contains(&keysym) {
                         return FilterResult::Forward;
                     }
-                } else {
-                    // Key release - check if it was suppressed
-                    let keysym_to_check = raw_keysym.unwrap_or(modified_keysym);
-                    let suppressed = stilch
-                        .input_manager
-                        .suppressed_keys
-                        .contains(&keysym_to_check);
-                    if suppressed {
-                        stilch
-                            .input_manager
-                            .suppressed_keys
-                            .retain(|k| *k != keysym_to_check);
-                        return FilterResult::Intercept(KeyAction::None);
-                    } else {
-                        return FilterResult::Forward;
+                    stilch.input_manager.unsuppress_key(keysym);
+                    return match stilch.process_keybinding(
+                        modifiers,
+                        keycode,
+                        modified_keysym,
+                        raw_keysym,
+                        state,
+                    ) {
+                        FilterResult::Forward => FilterResult::Intercept(KeyAction::None.into()),
+                        intercepted => intercepted,
+                    };
+                }
+
+                // The escape chord takes the shortcuts back from the client,
+                // or hands them over again
+                if let Some(inhibitor) = &inhibitor {
+                    if stilch.is_shortcuts_inhibit_escape(modifiers, keysym) {
+                        if inhibitor.is_active() {
+                            inhibitor.inactivate();
+                        } else {
+                            inhibitor.activate();
+                        }
+                        stilch.input_manager.suppressed_keys.push(keysym);
+                        return FilterResult::Intercept(KeyAction::None.into());
+                    }
+                }
+
+                // Forward when inhibited
+                if inhibited {
+                    return FilterResult::Forward;
+                }
+
+                if let Some(action) = stilch.overview_key_action(modified_keysym) {
+                    stilch.input_manager.suppressed_keys.push(keysym);
+                    return FilterResult::Intercept(action.into());
+                }
+
+                match stilch.process_keybinding(
+                    modifiers,
+                    keycode,
+                    modified_keysym,
+                    raw_keysym,
+                    state,
+                ) {
+                    FilterResult::Intercept(binding) => {
+                        // Suppress the raw keysym if available, otherwise the modified one
+                        stilch.input_manager.suppressed_keys.push(keysym);
+                        // Return the action to be handled after this closure
+                        FilterResult::Intercept(binding)
                     }
+                    // Keys a binding mode doesn't bind don't reach clients
+                    // either, some have built-in actions
+                    FilterResult::Forward if stilch.binding_mode.is_some() => {
+                        stilch.input_manager.suppressed_keys.push(keysym);
+                        FilterResult::Intercept(stilch.mode_fallback_key_action(modified_keysym))
+                    }
+                    // Clients don't get keys while the overview covers them
+                    FilterResult::Forward if stilch.overview.is_some() => {
+                        stilch.input_manager.suppressed_keys.push(keysym);
+                        FilterResult::Intercept(KeyAction::None.into())
+                    }
+                    // Not a keybinding, forward to client
+                    FilterResult::Forward => FilterResult::Forward,
                 }
             },
         );
 
-        action.unwrap_or(KeyAction::None)
+        match action {
+            Some(BindingAction {
+                action,
+                repeat: true,
+            }) => {
+                self.start_binding_repeat(keycode, action.clone());
+                action
+            }
+            Some(binding) => binding.action,
+            None => KeyAction::None,
+        }
+    }
+
+    /// Re-run a held keybinding's action at the keyboard repeat rate
+    fn start_binding_repeat(&mut self, keycode: Keycode, action: KeyAction) {
+        let (delay, rate) = self.keyboard_repeat_info();
+        if rate == 0 {
+            return;
+        }
+        let interval = Duration::from_millis(1000 / rate as u64);
+        let timer = Timer::from_duration(Duration::from_millis(delay as u64));
+        let source =
+            self.handle
+                .insert_source(timer, move |_, _, state: &mut StilchState<BackendData>| {
+                    state.handle_key_action(action.clone());
+                    TimeoutAction::ToDuration(interval)
+                });
+        match source {
+            Ok(token) => self.input_manager.binding_repeat = Some((keycode, token)),
+            Err(err) => warn!("Failed to start keybinding repeat: {}", err),
+        }
+    }
+
+    /// Stop repeating the held keybinding, if any
+    fn stop_binding_repeat(&mut self) {
+        if let Some((_, token)) = self.input_manager.binding_repeat.take() {
+            self.handle.remove(token);
+        }
+    }
+
+    /// Keyboard repeat delay and rate from the keyboard input config
+    fn keyboard_repeat_info(&self) -> (u32, u32) {
+        let keyboard_config = self
+            .config
+            .input_configs
+            .iter()
+            .find(|c| c.identifier == "type:keyboard" || c.identifier == "*");
+        (
+            keyboard_config.and_then(|c| c.repeat_delay).unwrap_or(200),
+            keyboard_config.and_then(|c| c.repeat_rate).unwrap_or(25),
+        )
     }
 
     // Allow in this method because of existing usage
@@ -1,17 +1,71 @@
-use smithay::input::{
-    keyboard::Keysym,
-    pointer::{CursorImageStatus, PointerHandle},
-    Seat,
+use std::time::Instant;
+
+use smithay::{
+    backend::input::TouchSlot,
+    input::{
+        keyboard::{Keycode, Keysym},
+        pointer::{CursorImageStatus, PointerHandle},
+        Seat,
+    },
+    reexports::{calloop::RegistrationToken, wayland_server::protocol::wl_surface::WlSurface},
+    utils::{Logical, Point},
 };
 
+use crate::config::GestureKind;
 use crate::cursor_manager::CursorManager;
+use crate::focus::KeyboardFocusTarget;
+use crate::input::click::ClickTracker;
+use crate::input::scroll::ButtonScroll;
+use crate::input::tab_scroll::TabScroll;
+use crate::shell::WindowElement;
 use crate::state::{Backend, DndIcon, StilchState};
+use crate::workspace::WorkspaceId;
+
+/// A touchpad gesture consumed by a compositor binding
+#[derive(Debug)]
+pub struct ActiveGesture {
+    pub kind: GestureKind,
+    pub fingers: u32,
+    /// Accumulated swipe motion
+    pub delta: Point<f64, Logical>,
+    /// Current pinch scale relative to the start of the gesture
+    pub scale: f64,
+    /// Workspace slid along with a horizontal swipe, and where its windows started
+    pub preview: Option<(WorkspaceId, Vec<(WindowElement, Point<i32, Logical>)>)>,
+    /// When the fingers started resting, in milliseconds, for holds and for
+    /// swipes that began as one
+    pub hold_start: Option<u32>,
+}
+
+/// A bound hold that libinput cancelled because the fingers moved. The swipe
+/// it starts right after picks it up, so a small drift still counts as a hold.
+#[derive(Debug)]
+pub struct InterruptedHold {
+    pub fingers: u32,
+    pub start: u32,
+    pub cancelled_at: u32,
+}
+
+/// First finger of the current touch sequence
+#[derive(Debug)]
+pub struct TouchContact {
+    pub slot: TouchSlot,
+    pub start: Point<f64, Logical>,
+    /// Time of the touch down, in milliseconds
+    pub time: u32,
+    /// Floating window under the finger, which a long press can move
+    pub floating_window: Option<WindowElement>,
+    /// The contact already moved, so it can no longer start a long-press move
+    pub moved: bool,
+}
 
 /// Centralized input management
 #[derive(Debug)]
 pub struct InputManager<BackendData: Backend + 'static> {
     /// Keys that are currently suppressed
     pub suppressed_keys: Vec<Keysym>,
+    /// Keys held down, with their raw keysym, for chord bindings
+    pub held_keys: Vec<(Keycode, Keysym)>,
     /// Current cursor image status
     pub cursor_status: CursorImageStatus,
     /// Cursor manager for loading and caching cursor images
@@ -22,6 +76,38 @@ pub struct InputManager<BackendData: Backend + 'static> {
     pub pointer: PointerHandle<StilchState<BackendData>>,
     /// Current drag-and-drop icon
     pub dnd_icon: Option<DndIcon>,
+    /// Gesture in progress that is bound to a compositor action
+    pub gesture: Option<ActiveGesture>,
+    /// Bound hold cancelled by finger motion, until a swipe continues it
+    pub interrupted_hold: Option<InterruptedHold>,
+    /// Scroll button held under `scroll_method on_button_down`
+    pub button_scroll: Option<ButtonScroll>,
+    /// Touch slots currently down
+    pub touch_slots: Vec<TouchSlot>,
+    /// The contact that started the current touch sequence
+    pub touch_contact: Option<TouchContact>,
+    /// Mouse buttons whose press was consumed by a binding
+    pub suppressed_buttons: Vec<u32>,
+    /// Recent button presses, for double and triple clicks
+    pub clicks: ClickTracker,
+    /// Scroll over tab bars not yet turned into tab switches
+    pub tab_scroll: TabScroll,
+    /// Held key whose binding is repeating, and its repeat timer
+    pub binding_repeat: Option<(Keycode, RegistrationToken)>,
+    /// Timer focusing the window under the pointer once it rests there,
+    /// with `focus_follows_mouse_delay_ms`
+    pub hover_focus: Option<RegistrationToken>,
+    /// Keyboard focus to restore once the focused layer surface goes away
+    pub focus_before_layer: Option<KeyboardFocusTarget>,
+    /// Keyboard-focused surface, whose shortcuts inhibitor is the only one
+    /// allowed to be active
+    pub inhibit_focus: Option<WlSurface>,
+    /// Cursor hidden by typing or idling, until the pointer is used again
+    pub cursor_hidden: bool,
+    /// Last pointer motion, button or scroll, for the idle timeout
+    pub last_pointer_activity: Instant,
+    /// Cursor showing a resize arrow over a split boundary
+    pub split_hover: bool,
 }
 
 impl<BackendData: Backend + 'static> InputManager<BackendData> {
@@ -32,11 +118,27 @@ impl<BackendData: Backend + 'static> InputManager<BackendData> {
     ) -> Self {
         Self {
             suppressed_keys: Vec::new(),
+            held_keys: Vec::new(),
             cursor_status: CursorImageStatus::default_named(),
             cursor_manager: CursorManager::new(),
             seat,
             pointer,
             dnd_icon: None,
+            gesture: None,
+            interrupted_hold: None,
+            button_scroll: None,
+            touch_slots: Vec::new(),
+            touch_contact: None,
+            suppressed_buttons: Vec::new(),
+            clicks: ClickTracker::default(),
+            tab_scroll: TabScroll::default(),
+            binding_repeat: None,
+            hover_focus: None,
+            focus_before_layer: None,
+            inhibit_focus: None,
+            cursor_hidden: false,
+            last_pointer_activity: Instant::now(),
+            split_hover: false,
         }
     }
 
@@ -3,14 +3,21 @@
 //! This module handles all input events including keyboard, pointer, touch,
 //! tablet, and gesture inputs.
 
+pub mod click;
+mod cursor_hide;
 mod gesture;
 mod keyboard;
 pub mod manager;
 mod pointer;
+pub mod scroll;
+mod split_resize;
+pub mod tab_scroll;
 mod tablet;
 mod touch;
 
+pub use self::cursor_hide::start_cursor_idle_timer;
 pub use self::manager::InputManager;
+pub use self::pointer::FocusFollowsMouseOverride;
 
 use smithay::backend::input::{Device, InputBackend, InputEvent};
 use smithay::reexports::wayland_server::DisplayHandle;
@@ -106,29 +113,7 @@ impl StilchState<crate::udev::UdevData> {
         use smithay::backend::input::DeviceCapability;
 
         let device_name = device.name();
-        let device_sysname = device
-            .syspath()
-            .and_then(|p| {
-                p.file_name()
-                    .and_then(|n| n.to_str())
-                    .map(|s| s.to_string())
-            })
-            .unwrap_or_default();
-
-        // Find matching input config
-        let matching_config = self.config.input_configs.iter().find(|config| {
-            // Check if identifier matches
-            match config.identifier.as_str() {
-                "type:keyboard" => device.has_capability(DeviceCapability::Keyboard),
-                "type:pointer" => device.has_capability(DeviceCapability::Pointer),
-                "type:touchpad" => device.has_capability(DeviceCapability::Touch),
-                "*" => true,
-                identifier => {
-                    // Check exact name match or glob pattern
-                    device_name == identifier || device_sysname == identifier
-                }
-            }
-        });
+        let matching_config = self.input_config_for(device);
 
         if let Some(config) = matching_config {
             tracing::info!(
@@ -147,18 +132,12 @@ impl StilchState<crate::udev::UdevData> {
                 if config.accel_profile.is_some() {
                     tracing::debug!("Would set acceleration profile: {:?}", config.accel_profile);
                 }
-                if config.natural_scroll.is_some() {
-                    tracing::debug!("Would set natural scrolling: {:?}", config.natural_scroll);
-                }
                 if config.tap.is_some() {
                     tracing::debug!("Would set tap to click: {:?}", config.tap);
                 }
                 if config.tap_button_map.is_some() {
                     tracing::debug!("Would set tap button map: {:?}", config.tap_button_map);
                 }
-                if config.scroll_method.is_some() {
-                    tracing::debug!("Would set scroll method: {:?}", config.scroll_method);
-                }
                 if config.left_handed.is_some() {
                     tracing::debug!("Would set left handed mode: {:?}", config.left_handed);
                 }
@@ -172,7 +151,8 @@ impl StilchState<crate::udev::UdevData> {
 
             // Note: The actual device configuration would need to be done at the libinput level,
             // which requires access to the underlying libinput device, not just the smithay Device trait.
-            // Keyboard repeat rate/delay and XKB settings are already handled when creating the keyboard.
+            // Keyboard repeat rate/delay and XKB settings are already handled when creating the keyboard,
+            // natural scrolling, scroll factor and button scrolling when scroll events come in.
         } else {
             tracing::debug!("No input config found for device '{}'", device_name);
         }
@@ -2,54 +2,299 @@
 
 use smithay::{
     backend::input::{
-        AbsolutePositionEvent, Axis, AxisSource, Event, InputBackend, PointerAxisEvent,
-        PointerButtonEvent, PointerMotionEvent,
+        AbsolutePositionEvent, Axis, AxisSource, ButtonState, Event, InputBackend,
+        PointerAxisEvent, PointerButtonEvent, PointerMotionEvent,
     },
     input::pointer::{AxisFrame, ButtonEvent, MotionEvent, RelativeMotionEvent},
     output::Output,
-    reexports::wayland_server::{protocol::wl_pointer, Resource},
+    reexports::{
+        calloop::timer::{TimeoutAction, Timer},
+        wayland_server::{protocol::wl_pointer, Resource},
+    },
     utils::{Logical, Point, Serial, SERIAL_COUNTER as SCOUNTER},
     wayland::{
         pointer_constraints::{with_pointer_constraint, PointerConstraint},
         seat::WaylandFocus,
     },
 };
-use tracing::debug;
+use std::time::Duration;
+use tracing::{debug, warn};
 
 use crate::{
+    config::{BindingKey, Direction, Toggle},
     focus::PointerFocusTarget,
+    input::{
+        click::Click,
+        tab_scroll::{DISTANCE_PER_TAB, V120_PER_TAB},
+    },
+    keybindings::KeyAction,
+    shell::WindowElement,
     state::{Backend, StilchState},
+    window::FullscreenMode,
 };
 
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+
+/// focus_follows_mouse switched by a binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusFollowsMouseOverride {
+    pub enabled: bool,
+    /// Set with `enable`/`disable` rather than toggled, so a reload keeps it
+    pub explicit: bool,
+}
+
 impl<BackendData: Backend> StilchState<BackendData> {
+    /// Whether focus follows the mouse outside windows with their own rule:
+    /// the runtime override if a binding switched it, else the config
+    pub fn focus_follows_mouse(&self) -> bool {
+        self.focus_follows_mouse_override
+            .map_or_else(|| self.config.focus_follows_mouse(), |o| o.enabled)
+    }
+
+    /// Switch focus_follows_mouse at runtime
+    pub fn set_focus_follows_mouse(&mut self, toggle: Toggle) {
+        let enabled = match toggle {
+            Toggle::Enable => true,
+            Toggle::Disable => false,
+            Toggle::Toggle => !self.focus_follows_mouse(),
+        };
+        self.focus_follows_mouse_override = Some(FocusFollowsMouseOverride {
+            enabled,
+            explicit: toggle != Toggle::Toggle,
+        });
+        if !enabled {
+            self.cancel_hover_focus();
+        }
+        debug!("focus_follows_mouse: {enabled}");
+        if let Some(ipc_server) = &self.ipc_server {
+            ipc_server.send_focus_follows_mouse(enabled);
+        }
+    }
+
+    /// Go back to the config value after `reload bindings`, unless a binding set
+    /// focus_follows_mouse explicitly
+    pub(crate) fn reset_focus_follows_mouse_override(&mut self) {
+        if self
+            .focus_follows_mouse_override
+            .is_some_and(|o| !o.explicit)
+        {
+            self.focus_follows_mouse_override = None;
+            if let Some(ipc_server) = &self.ipc_server {
+                ipc_server.send_focus_follows_mouse(self.config.focus_follows_mouse());
+            }
+        }
+    }
+
     /// Handle pointer button events
     pub fn on_pointer_button<B: InputBackend>(&mut self, evt: B::PointerButtonEvent) {
-        let serial = SCOUNTER.next_serial();
+        self.reveal_cursor();
         let button = evt.button_code();
 
-        let state = wl_pointer::ButtonState::from(evt.state());
+        if self.scroll_button_event(&evt.device(), button, evt.state(), evt.time_msec()) {
+            return;
+        }
+
+        self.pointer_button(button, evt.state(), evt.time_msec());
+    }
+
+    /// Handle a button press or release that is not part of on-button
+    /// scrolling: compositor drags and bindings first, then the client
+    pub(crate) fn pointer_button(&mut self, button: u32, button_state: ButtonState, time: u32) {
+        let serial = SCOUNTER.next_serial();
+        let state = wl_pointer::ButtonState::from(button_state);
 
         if wl_pointer::ButtonState::Pressed == state {
-            self.update_keyboard_focus(self.pointer().current_location(), serial);
-        };
+            // Clicks while the overview is open only pick from it
+            if self.overview.is_some() {
+                self.overview_click(self.pointer().current_location());
+                self.input_manager.suppressed_buttons.push(button);
+                return;
+            }
+
+            let location = self.pointer().current_location();
+            let click = self.input_manager.clicks.press(
+                button,
+                time,
+                location,
+                self.config.double_click_time,
+            );
+
+            // Pressing on the boundary between tiled windows drags it, the
+            // grab taking the press below
+            if !(button == BTN_LEFT && self.start_split_drag(location, button, serial)) {
+                if let Some(window_id) = self.tab_under(location) {
+                    self.activate_window(window_id);
+                } else {
+                    self.update_keyboard_focus(location, serial);
+                    self.raise_floating_under_pointer();
+                }
+
+                if let Some(action) = self.mouse_binding_action(BindingKey::Button(button)) {
+                    self.run_mouse_binding(action, button, serial);
+                    // A started drag needs the press to track the button; otherwise
+                    // the click (release included) belongs to the binding alone
+                    if !self.pointer().is_grabbed() {
+                        self.input_manager.suppressed_buttons.push(button);
+                        return;
+                    }
+                } else if button == BTN_LEFT && self.title_double_click(location, click) {
+                    self.input_manager.suppressed_buttons.push(button);
+                    return;
+                } else {
+                    self.floating_modifier_drag(button, serial);
+                }
+            }
+        } else if self.input_manager.suppressed_buttons.contains(&button) {
+            self.input_manager
+                .suppressed_buttons
+                .retain(|&b| b != button);
+            return;
+        }
         let pointer = self.pointer().clone();
         pointer.button(
             self,
             &ButtonEvent {
                 button,
-                state: state.try_into().unwrap_or_else(|_| {
-                    tracing::error!("Invalid button state");
-                    smithay::backend::input::ButtonState::Released
-                }),
+                state: button_state,
                 serial,
-                time: evt.time_msec(),
+                time,
             },
         );
         pointer.frame(self);
     }
 
+    /// Action bound to a mouse button or wheel notch with the current modifiers
+    fn mouse_binding_action(&self, key: BindingKey) -> Option<KeyAction> {
+        let modifiers = self.seat().get_keyboard()?.modifier_state();
+        let over_window = !matches!(
+            self.surface_under(self.pointer().current_location()),
+            None | Some((PointerFocusTarget::SSD(_), _))
+        );
+        self.process_mouse_binding(modifiers, key, over_window)
+    }
+
+    /// Floating window under the pointer, if any
+    fn floating_window_under_pointer(&self) -> Option<WindowElement> {
+        self.space()
+            .element_under(self.pointer().current_location())
+            .map(|(window, _)| window.clone())
+            .filter(|window| {
+                self.window_registry()
+                    .find_by_element(window)
+                    .and_then(|id| self.window_registry().get(id))
+                    .is_some_and(|managed| managed.is_floating())
+            })
+    }
+
+    /// Raise the floating window under the pointer above the others
+    fn raise_floating_under_pointer(&mut self) {
+        let Some(window) = self.floating_window_under_pointer() else {
+            return;
+        };
+        self.space_mut().raise_element(&window, true);
+        if let Some(window_id) = self.window_registry().find_by_element(&window) {
+            self.raise_transient_children(window_id);
+        }
+        #[cfg(feature = "xwayland")]
+        self.raise_override_redirect_windows();
+    }
+
+    /// Double-clicking a tab, stacked title or title bar toggles container
+    /// fullscreen for its window. Returns true if the click was used.
+    fn title_double_click(&mut self, location: Point<f64, Logical>, click: Click) -> bool {
+        if click != Click::Double {
+            return false;
+        }
+        let window = if let Some(window_id) = self.tab_under(location) {
+            match self.window_registry().get(window_id) {
+                Some(managed) => managed.element.clone(),
+                None => return false,
+            }
+        } else {
+            match self.surface_under(location) {
+                Some((PointerFocusTarget::SSD(ssd), _))
+                    if ssd.window().decoration_state().header_bar.over_title() =>
+                {
+                    ssd.window().clone()
+                }
+                _ => return false,
+            }
+        };
+        self.focus_window(&window);
+        self.toggle_fullscreen(FullscreenMode::Container);
+        true
+    }
+
+    /// Run a mouse binding's action; drags start on the floating window under the pointer
+    fn run_mouse_binding(&mut self, action: KeyAction, button: u32, serial: Serial) {
+        match action {
+            KeyAction::InteractiveMove => {
+                if let Some(window) = self.floating_window_under_pointer() {
+                    self.start_pointer_move(window, button, serial);
+                }
+            }
+            KeyAction::InteractiveResize => {
+                if let Some(window) = self.floating_window_under_pointer() {
+                    self.start_pointer_resize(window, button, serial);
+                }
+            }
+            action => self.handle_key_action(action),
+        }
+    }
+
+    /// Move (left button) or resize (right button) the window under the
+    /// pointer while the floating modifier is held. Floating windows follow
+    /// the pointer; tiled ones swap places with the window they are dropped
+    /// on, or resize from their nearest edge.
+    fn floating_modifier_drag(&mut self, button: u32, serial: Serial) {
+        let Some(floating_modifier) = self.config.floating_modifier else {
+            return;
+        };
+        let Some(held) = self.seat().get_keyboard().map(|k| k.modifier_state()) else {
+            return;
+        };
+        let required = floating_modifier.modifiers;
+        let pressed = (!required.ctrl || held.ctrl)
+            && (!required.alt || held.alt)
+            && (!required.shift || held.shift)
+            && (!required.logo || held.logo);
+        if !pressed {
+            return;
+        }
+
+        let resize = match button {
+            BTN_LEFT => floating_modifier.inverse,
+            BTN_RIGHT => !floating_modifier.inverse,
+            _ => return,
+        };
+        if let Some(window) = self.floating_window_under_pointer() {
+            if resize {
+                self.start_pointer_resize(window, button, serial);
+            } else {
+                self.start_pointer_move(window, button, serial);
+            }
+            return;
+        }
+
+        let location = self.pointer().current_location();
+        let Some(window_id) = self
+            .space()
+            .element_under(location)
+            .and_then(|(window, _)| self.window_registry().find_by_element(window))
+        else {
+            return;
+        };
+        if resize {
+            self.start_tiled_resize(window_id, location, button, serial);
+        } else {
+            self.start_tiled_move(window_id, button, serial);
+        }
+    }
+
     /// Handle pointer axis (scroll) events
     pub fn on_pointer_axis<B: InputBackend>(&mut self, evt: B::PointerAxisEvent) {
+        self.reveal_cursor();
         let horizontal_amount = evt
             .amount(Axis::Horizontal)
             .unwrap_or_else(|| evt.amount_v120(Axis::Horizontal).unwrap_or(0.0) * 15.0 / 120.);
@@ -59,23 +304,54 @@ impl<BackendData: Backend> StilchState<BackendData> {
         let horizontal_amount_discrete = evt.amount_v120(Axis::Horizontal);
         let vertical_amount_discrete = evt.amount_v120(Axis::Vertical);
 
+        // Wheel bindings fire once per notch; smooth (touchpad) scrolling never triggers them
+        let notch = match (horizontal_amount_discrete, vertical_amount_discrete) {
+            (_, Some(v)) if v < 0.0 => Some(Direction::Up),
+            (_, Some(v)) if v > 0.0 => Some(Direction::Down),
+            (Some(h), _) if h < 0.0 => Some(Direction::Left),
+            (Some(h), _) if h > 0.0 => Some(Direction::Right),
+            _ => None,
+        };
+        if let Some(action) = notch.and_then(|d| self.mouse_binding_action(BindingKey::Wheel(d))) {
+            self.handle_key_action(action);
+            return;
+        }
+
+        // Scrolling over a tab bar switches tabs instead of reaching a client
+        let (amount, per_tab) = match (vertical_amount_discrete, horizontal_amount_discrete) {
+            (Some(v), _) if v != 0.0 => (v, V120_PER_TAB),
+            (_, Some(h)) if h != 0.0 => (h, V120_PER_TAB),
+            _ if vertical_amount != 0.0 => (vertical_amount, DISTANCE_PER_TAB),
+            _ => (horizontal_amount, DISTANCE_PER_TAB),
+        };
+        if self.scroll_tab_bar(amount, per_tab, evt.time_msec()) {
+            return;
+        }
+
+        // Bindings follow the wheel itself, clients get natural scrolling and
+        // the scroll factor
+        let scroll = self.scroll_settings(&evt.device());
         {
             let mut frame = AxisFrame::new(evt.time_msec()).source(evt.source());
             if horizontal_amount != 0.0 {
-                frame = frame
-                    .relative_direction(Axis::Horizontal, evt.relative_direction(Axis::Horizontal));
-                frame = frame.value(Axis::Horizontal, horizontal_amount);
+                frame = frame.relative_direction(
+                    Axis::Horizontal,
+                    scroll.direction(evt.relative_direction(Axis::Horizontal)),
+                );
+                frame = frame.value(Axis::Horizontal, scroll.apply(horizontal_amount));
             }
             if let Some(discrete) = horizontal_amount_discrete {
-                frame = frame.v120(Axis::Horizontal, discrete as i32);
+                frame = frame.v120(Axis::Horizontal, scroll.apply(discrete) as i32);
             }
             if vertical_amount != 0.0 {
-                frame = frame
-                    .relative_direction(Axis::Vertical, evt.relative_direction(Axis::Vertical));
-                frame = frame.value(Axis::Vertical, vertical_amount);
+                frame = frame.relative_direction(
+                    Axis::Vertical,
+                    scroll.direction(evt.relative_direction(Axis::Vertical)),
+                );
+                frame = frame.value(Axis::Vertical, scroll.apply(vertical_amount));
             }
             if let Some(discrete) = vertical_amount_discrete {
-                frame = frame.v120(Axis::Vertical, discrete as i32);
+                frame = frame.v120(Axis::Vertical, scroll.apply(discrete) as i32);
             }
             if evt.source() == AxisSource::Finger {
                 if evt.amount(Axis::Horizontal) == Some(0.0) {
@@ -91,12 +367,35 @@ impl<BackendData: Backend> StilchState<BackendData> {
         }
     }
 
+    /// Move the active tab of the tab bar under the pointer by the scroll,
+    /// one tab per notch. Returns false if the pointer isn't over a tab bar.
+    fn scroll_tab_bar(&mut self, amount: f64, per_tab: f64, time: u32) -> bool {
+        let Some((tabs, _)) = self.tab_bar_under(self.pointer().current_location()) else {
+            self.input_manager.tab_scroll.reset();
+            return false;
+        };
+        let steps = self.input_manager.tab_scroll.scroll(amount, per_tab, time);
+        if steps == 0 {
+            return true;
+        }
+
+        // Like i3, scrolling stops at the first and last tab
+        let active = tabs.iter().position(|&(_, active)| active).unwrap_or(0);
+        let target = (active as i64 + steps as i64).clamp(0, tabs.len() as i64 - 1) as usize;
+        if target != active {
+            debug!("Scrolled tab bar from tab {} to {}", active, target);
+            self.activate_window(tabs[target].0);
+        }
+        true
+    }
+
     /// Handle absolute pointer motion for windowed backends
     pub fn on_pointer_absolute_windowed<B: InputBackend>(
         &mut self,
         evt: B::PointerMotionAbsoluteEvent,
         output_name: &str,
     ) {
+        self.reveal_cursor();
         let output = self
             .space()
             .outputs()
@@ -124,27 +423,9 @@ impl<BackendData: Backend> StilchState<BackendData> {
         let serial = SCOUNTER.next_serial();
 
         let pointer = self.pointer().clone();
-        let under = self.surface_under(pos);
-
-        // Update keyboard focus if focus_follows_mouse is enabled
-        if self.config.focus_follows_mouse() {
-            if let Some((focus, _)) = under.as_ref() {
-                // Only update focus if we're hovering over a different window
-                let current_focus = self.seat().get_keyboard().unwrap().current_focus();
-                let should_update_focus = match current_focus {
-                    Some(current) => {
-                        // Convert current keyboard focus to pointer focus target for comparison
-                        let current_as_pointer: PointerFocusTarget = current.into();
-                        current_as_pointer != *focus
-                    }
-                    None => true,
-                };
+        let under = self.pointer_target_at(pos);
 
-                if should_update_focus {
-                    self.update_keyboard_focus(pos, serial);
-                }
-            }
-        }
+        self.focus_follows_pointer(pos, under.as_ref(), serial);
 
         pointer.motion(
             self,
@@ -167,6 +448,10 @@ impl StilchState<crate::udev::UdevData> {
         _dh: &smithay::reexports::wayland_server::DisplayHandle,
         evt: B::PointerMotionEvent,
     ) {
+        self.reveal_cursor();
+        if self.scroll_with_motion(evt.delta(), evt.time_msec()) {
+            return;
+        }
         let mut pointer_location = self.pointer().current_location();
         let serial = SCOUNTER.next_serial();
 
@@ -230,27 +515,9 @@ impl StilchState<crate::udev::UdevData> {
             self.clamp_pointer_location(new_location)
         };
 
-        let under = self.surface_under(pointer_location);
-
-        // Update keyboard focus if focus_follows_mouse is enabled
-        if self.config.focus_follows_mouse() {
-            if let Some((focus, _)) = under.as_ref() {
-                // Only update focus if we're hovering over a different window
-                let current_focus = self.seat().get_keyboard().unwrap().current_focus();
-                let should_update_focus = match current_focus {
-                    Some(current) => {
-                        // Convert current keyboard focus to pointer focus target for comparison
-                        let current_as_pointer: PointerFocusTarget = current.into();
-                        current_as_pointer != *focus
-                    }
-                    None => true,
-                };
+        let under = self.pointer_target_at(pointer_location);
 
-                if should_update_focus {
-                    self.update_keyboard_focus(pointer_location, serial);
-                }
-            }
-        }
+        self.focus_follows_pointer(pointer_location, under.as_ref(), serial);
 
         pointer.motion(
             self,
@@ -281,6 +548,7 @@ impl StilchState<crate::udev::UdevData> {
         _dh: &smithay::reexports::wayland_server::DisplayHandle,
         evt: B::PointerMotionAbsoluteEvent,
     ) {
+        self.reveal_cursor();
         let serial = SCOUNTER.next_serial();
 
         // For absolute motion, we need to determine which output it's on
@@ -321,27 +589,9 @@ impl StilchState<crate::udev::UdevData> {
         }
 
         let pointer = self.pointer().clone();
-        let under = self.surface_under(location);
-
-        // Update keyboard focus if focus_follows_mouse is enabled
-        if self.config.focus_follows_mouse() {
-            if let Some((focus, _)) = under.as_ref() {
-                // Only update focus if we're hovering over a different window
-                let current_focus = self.seat().get_keyboard().unwrap().current_focus();
-                let should_update_focus = match current_focus {
-                    Some(current) => {
-                        // Convert current keyboard focus to pointer focus target for comparison
-                        let current_as_pointer: PointerFocusTarget = current.into();
-                        current_as_pointer != *focus
-                    }
-                    None => true,
-                };
+        let under = self.pointer_target_at(location);
 
-                if should_update_focus {
-                    self.update_keyboard_focus(location, serial);
-                }
-            }
-        }
+        self.focus_follows_pointer(location, under.as_ref(), serial);
 
         pointer.motion(
             self,
@@ -359,6 +609,62 @@ impl StilchState<crate::udev::UdevData> {
 }
 
 impl<BackendData: Backend> StilchState<BackendData> {
+    /// With focus_follows_mouse, globally or for the window by a `for_window`
+    /// rule, focus the window the pointer moved onto, right away or once the pointer has rested on it for
+    /// `focus_follows_mouse_delay_ms`. Each motion restarts the wait.
+    fn focus_follows_pointer(
+        &mut self,
+        location: Point<f64, Logical>,
+        under: Option<&(PointerFocusTarget, Point<f64, Logical>)>,
+        serial: Serial,
+    ) {
+        self.cancel_hover_focus();
+        let Some((focus, _)) = under else {
+            return;
+        };
+        // `for_window` rules can turn it on or off for single windows
+        let window_override = self
+            .space()
+            .element_under(location)
+            .and_then(|(window, _)| self.window_registry().find_by_element(window))
+            .and_then(|id| self.window_registry().get(id))
+            .and_then(|managed| managed.focus_follows_mouse);
+        if !window_override.unwrap_or_else(|| self.focus_follows_mouse()) {
+            return;
+        }
+        // Only update focus if we're hovering over a different window
+        let current_focus = self.seat().get_keyboard().and_then(|k| k.current_focus());
+        if current_focus.is_some_and(|current| PointerFocusTarget::from(current) == *focus) {
+            return;
+        }
+
+        let delay = self.config.focus_follows_mouse_delay_ms;
+        if delay == 0 {
+            self.update_keyboard_focus(location, serial);
+            return;
+        }
+        let timer = Timer::from_duration(Duration::from_millis(delay as u64));
+        let source =
+            self.handle
+                .insert_source(timer, |_, _, state: &mut StilchState<BackendData>| {
+                    state.input_manager.hover_focus = None;
+                    let location = state.pointer().current_location();
+                    state.update_keyboard_focus(location, SCOUNTER.next_serial());
+                    TimeoutAction::Drop
+                });
+        match source {
+            Ok(token) => self.input_manager.hover_focus = Some(token),
+            Err(err) => warn!("Failed to start the focus hover delay: {}", err),
+        }
+    }
+
+    /// Forget a focus change waiting for the pointer to rest
+    fn cancel_hover_focus(&mut self) {
+        if let Some(token) = self.input_manager.hover_focus.take() {
+            self.handle.remove(token);
+        }
+    }
+
     /// Update keyboard focus when pointer is clicked
     pub(crate) fn update_keyboard_focus(&mut self, location: Point<f64, Logical>, serial: Serial) {
         tracing::info!("update_keyboard_focus called at location: {:?}", location);
@@ -369,6 +675,10 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 return;
             }
         };
+        // An exclusive layer surface keeps the keyboard until it goes away
+        if self.exclusive_layer().is_some() {
+            return;
+        }
         // change the keyboard focus unless the pointer or keyboard is grabbed
         // We test for any matching surface type here but always use the root
         // (in case of a window the toplevel) surface for the focus.
@@ -390,18 +700,25 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 if keyboard.is_grabbed() && !keyboard.has_grab(parent.id().protocol_id().into()) {
                     return;
                 }
+                // Layer surfaces that accept keyboard input are focused by clicking them
+                if self.focus_layer_on_click(&parent) {
+                    return;
+                }
             }
 
             #[cfg(feature = "xwayland")]
             if let PointerFocusTarget::X11Surface(x11_surface) = &target {
-                if !x11_surface.is_override_redirect() {
-                    if let Some(xwm) = self.xwm.as_mut() {
-                        if let Err(e) = xwm.raise_window(x11_surface) {
-                            tracing::warn!("Failed to raise X11 window: {:?}", e);
-                        }
-                    } else {
-                        tracing::warn!("XWM not available to raise window");
+                // Menus and tooltips get the click through the pointer, the
+                // keyboard stays with the window that opened them
+                if x11_surface.is_override_redirect() {
+                    return;
+                }
+                if let Some(xwm) = self.xwm.as_mut() {
+                    if let Err(e) = xwm.raise_window(x11_surface) {
+                        tracing::warn!("Failed to raise X11 window: {:?}", e);
                     }
+                } else {
+                    tracing::warn!("XWM not available to raise window");
                 }
             }
 
@@ -0,0 +1,193 @@
+//! Compositor-side scroll settings
+//!
+//! `natural_scroll` and `scroll_factor` from the device's `input` block are
+//! applied to axis events before they reach clients, so they work for every
+//! client whether or not libinput was configured. With
+//! `scroll_method on_button_down`, holding the scroll button turns pointer
+//! motion into scrolling; releasing it without moving clicks it as usual.
+
+use smithay::{
+    backend::input::{
+        Axis, AxisRelativeDirection, AxisSource, ButtonState, Device, DeviceCapability,
+    },
+    input::pointer::{AxisFrame, ButtonEvent},
+    utils::{Logical, Point, SERIAL_COUNTER as SCOUNTER},
+};
+
+use crate::{
+    config::{InputConfig, ScrollMethod},
+    state::{Backend, StilchState},
+};
+
+const BTN_MIDDLE: u32 = 0x112;
+
+/// How a device's scroll distances are adjusted
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollSettings {
+    pub natural: bool,
+    pub factor: f64,
+}
+
+impl Default for ScrollSettings {
+    fn default() -> Self {
+        Self {
+            natural: false,
+            factor: 1.0,
+        }
+    }
+}
+
+impl ScrollSettings {
+    fn from_config(config: &InputConfig) -> Self {
+        Self {
+            natural: config.natural_scroll.unwrap_or(false),
+            factor: config.scroll_factor.unwrap_or(1.0),
+        }
+    }
+
+    /// Scale a scroll distance, inverting it for natural scrolling
+    pub fn apply(&self, value: f64) -> f64 {
+        if self.natural {
+            -value * self.factor
+        } else {
+            value * self.factor
+        }
+    }
+
+    /// Direction reported to clients for the adjusted distances
+    pub fn direction(&self, direction: AxisRelativeDirection) -> AxisRelativeDirection {
+        match (self.natural, direction) {
+            (false, direction) => direction,
+            (true, AxisRelativeDirection::Identical) => AxisRelativeDirection::Inverted,
+            (true, AxisRelativeDirection::Inverted) => AxisRelativeDirection::Identical,
+        }
+    }
+}
+
+/// Scroll button held under `scroll_method on_button_down`
+#[derive(Debug)]
+pub struct ButtonScroll {
+    pub button: u32,
+    pub settings: ScrollSettings,
+    /// The pointer moved while the button was held, so it won't click
+    pub scrolled: bool,
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// The `input` block that applies to a device, if any
+    pub fn input_config_for<D: Device>(&self, device: &D) -> Option<&InputConfig> {
+        let device_name = device.name();
+        let device_sysname = device
+            .syspath()
+            .and_then(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|s| s.to_string())
+            })
+            .unwrap_or_default();
+
+        self.config
+            .input_configs
+            .iter()
+            .find(|config| match config.identifier.as_str() {
+                "type:keyboard" => device.has_capability(DeviceCapability::Keyboard),
+                "type:pointer" => device.has_capability(DeviceCapability::Pointer),
+                // Touchpads report gestures, touchscreens report touch
+                "type:touchpad" => device.has_capability(DeviceCapability::Gesture),
+                "*" => true,
+                identifier => device_name == identifier || device_sysname == identifier,
+            })
+    }
+
+    /// Scroll adjustments for a device
+    pub fn scroll_settings<D: Device>(&self, device: &D) -> ScrollSettings {
+        self.input_config_for(device)
+            .map(ScrollSettings::from_config)
+            .unwrap_or_default()
+    }
+
+    /// Start or finish scrolling with a held button. Returns true if the
+    /// button event was consumed.
+    pub fn scroll_button_event<D: Device>(
+        &mut self,
+        device: &D,
+        button: u32,
+        state: ButtonState,
+        time: u32,
+    ) -> bool {
+        match state {
+            ButtonState::Pressed => {
+                let Some(config) = self.input_config_for(device) else {
+                    return false;
+                };
+                if !matches!(config.scroll_method, Some(ScrollMethod::OnButtonDown))
+                    || config.scroll_button.unwrap_or(BTN_MIDDLE) != button
+                {
+                    return false;
+                }
+                let settings = ScrollSettings::from_config(config);
+                self.input_manager.button_scroll = Some(ButtonScroll {
+                    button,
+                    settings,
+                    scrolled: false,
+                });
+                true
+            }
+            ButtonState::Released => {
+                let held = self
+                    .input_manager
+                    .button_scroll
+                    .as_ref()
+                    .is_some_and(|scroll| scroll.button == button);
+                if !held {
+                    return false;
+                }
+                let scrolled = self
+                    .input_manager
+                    .button_scroll
+                    .take()
+                    .is_some_and(|scroll| scroll.scrolled);
+                if !scrolled {
+                    // Released without moving: deliver the click it held back
+                    let pointer = self.pointer().clone();
+                    for state in [ButtonState::Pressed, ButtonState::Released] {
+                        pointer.button(
+                            self,
+                            &ButtonEvent {
+                                button,
+                                state,
+                                serial: SCOUNTER.next_serial(),
+                                time,
+                            },
+                        );
+                    }
+                    pointer.frame(self);
+                }
+                true
+            }
+        }
+    }
+
+    /// Turn pointer motion into scrolling while the scroll button is held.
+    /// Returns true if the motion was consumed.
+    pub fn scroll_with_motion(&mut self, delta: Point<f64, Logical>, time: u32) -> bool {
+        let Some(scroll) = self.input_manager.button_scroll.as_mut() else {
+            return false;
+        };
+        scroll.scrolled = true;
+        let settings = scroll.settings;
+
+        let mut frame = AxisFrame::new(time).source(AxisSource::Continuous);
+        for (axis, value) in [(Axis::Horizontal, delta.x), (Axis::Vertical, delta.y)] {
+            if value != 0.0 {
+                frame = frame
+                    .relative_direction(axis, settings.direction(AxisRelativeDirection::Identical))
+                    .value(axis, settings.apply(value));
+            }
+        }
+        let pointer = self.pointer().clone();
+        pointer.axis(self, frame);
+        pointer.frame(self);
+        true
+    }
+}
@@ -0,0 +1,157 @@
+//! Dragging the boundary between tiled windows
+//!
+//! Over the gap between two children of a split, the cursor turns into a
+//! resize arrow and no client has pointer focus. Pressing the left button
+//! there drags the boundary until release, trading space between those two
+//! children only. Windows keep every pixel of their own, so without gaps
+//! there is nothing to grab this way. The new share is kept in the layout, so it
+//! survives later relayouts like a keyboard resize does.
+//!
+//! Dragging inside a tiled window with the floating modifier held drags the
+//! boundary along that window's nearest edge the same way.
+
+use smithay::{
+    input::pointer::{CursorIcon, CursorImageStatus, Focus, GrabStartData},
+    utils::{Logical, Point, Serial},
+};
+
+use crate::{
+    focus::PointerFocusTarget,
+    shell::PointerSplitResizeGrab,
+    state::{Backend, StilchState},
+    window::WindowId,
+    workspace::{layout::SplitBoundary, WorkspaceId},
+};
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Split boundary of the workspace shown under `location`, unless a
+    /// floating window or a fullscreen one covers it
+    fn split_boundary_under(
+        &self,
+        location: Point<f64, Logical>,
+    ) -> Option<(WorkspaceId, SplitBoundary)> {
+        if self.overview.is_some() {
+            return None;
+        }
+        let vo_id = self
+            .virtual_output_manager
+            .virtual_output_at(location.to_i32_round())?;
+        let workspace_id = self.workspace_manager.workspace_on_output(vo_id)?;
+        let workspace = self.workspace_manager.get_workspace(workspace_id)?;
+        if workspace.fullscreen_window.is_some() {
+            return None;
+        }
+        let over_floating = self
+            .space()
+            .element_under(location)
+            .and_then(|(window, _)| self.window_registry().find_by_element(window))
+            .and_then(|id| self.window_registry().get(id))
+            .is_some_and(|managed| managed.is_floating());
+        if over_floating {
+            return None;
+        }
+        // Only the gap, never the edge of a window
+        let boundary = workspace.layout.split_boundary_at(location, 0)?;
+        Some((workspace_id, boundary))
+    }
+
+    /// What pointer motion to `location` focuses: nothing over a split
+    /// boundary, which shows a resize cursor instead, else the surface there
+    pub(crate) fn pointer_target_at(
+        &mut self,
+        location: Point<f64, Logical>,
+    ) -> Option<(PointerFocusTarget, Point<f64, Logical>)> {
+        // A grab decides on the cursor until it ends
+        if self.pointer().is_grabbed() {
+            return self.surface_under(location);
+        }
+        let boundary = self.split_boundary_under(location);
+        self.show_split_cursor(boundary.map(|(_, boundary)| boundary));
+        match boundary {
+            Some(_) => None,
+            None => self.surface_under(location),
+        }
+    }
+
+    /// Show the resize cursor for `boundary`, or give the cursor back to
+    /// whatever sets it once off a boundary
+    pub(crate) fn show_split_cursor(&mut self, boundary: Option<SplitBoundary>) {
+        let image = match boundary {
+            Some(boundary) if boundary.side_by_side => {
+                CursorImageStatus::Named(CursorIcon::ColResize)
+            }
+            Some(_) => CursorImageStatus::Named(CursorIcon::RowResize),
+            None if self.input_manager.split_hover => CursorImageStatus::default_named(),
+            None => return,
+        };
+        self.input_manager.split_hover = boundary.is_some();
+        self.input_manager.cursor_status = image.clone();
+        self.input_manager.cursor_manager.set_cursor_image(image);
+    }
+
+    /// Start dragging the split boundary under `location` until `button` is
+    /// released. Returns false if there is no boundary there.
+    pub(crate) fn start_split_drag(
+        &mut self,
+        location: Point<f64, Logical>,
+        button: u32,
+        serial: Serial,
+    ) -> bool {
+        let Some((workspace, boundary)) = self.split_boundary_under(location) else {
+            return false;
+        };
+        tracing::debug!("Dragging split boundary on workspace {}", workspace);
+        self.show_split_cursor(Some(boundary));
+        let pointer = self.pointer().clone();
+        let grab = PointerSplitResizeGrab {
+            start_data: GrabStartData {
+                focus: None,
+                button,
+                location,
+            },
+            workspace,
+            boundary,
+        };
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+        true
+    }
+
+    /// Resize a tiled window by dragging the boundary along its edge
+    /// nearest to `location` until `button` is released. Returns false if
+    /// that window has no neighbour to trade space with.
+    pub(crate) fn start_tiled_resize(
+        &mut self,
+        window_id: WindowId,
+        location: Point<f64, Logical>,
+        button: u32,
+        serial: Serial,
+    ) -> bool {
+        let Some(workspace) = self
+            .window_registry()
+            .get(window_id)
+            .map(|managed| managed.workspace)
+        else {
+            return false;
+        };
+        let Some(boundary) = self
+            .workspace_manager
+            .get_workspace(workspace)
+            .and_then(|ws| ws.layout.window_edge_boundary(window_id, location))
+        else {
+            return false;
+        };
+        self.show_split_cursor(Some(boundary));
+        let pointer = self.pointer().clone();
+        let grab = PointerSplitResizeGrab {
+            start_data: GrabStartData {
+                focus: None,
+                button,
+                location,
+            },
+            workspace,
+            boundary,
+        };
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+        true
+    }
+}
@@ -0,0 +1,49 @@
+//! Scrolling over tab bars to switch tabs
+//!
+//! Scroll over a tab bar or stacked titles adds up here and turns into whole
+//! tab steps: one wheel notch (120 in v120 units, less on high-resolution
+//! wheels) or the same distance of smooth scrolling moves one tab. A pause or
+//! a change of direction starts over, so leftovers don't carry into the next
+//! scroll.
+
+/// Wheel v120 units that move one tab
+pub const V120_PER_TAB: f64 = 120.0;
+
+/// Smooth scroll distance that moves one tab, the distance of one notch
+pub const DISTANCE_PER_TAB: f64 = 15.0;
+
+/// Pause in milliseconds after which scrolling starts over
+const SCROLL_RESET_TIME: u32 = 300;
+
+/// Scroll over tab bars not yet turned into tab steps
+#[derive(Debug, Default)]
+pub struct TabScroll {
+    pending: f64,
+    last_time: Option<u32>,
+}
+
+impl TabScroll {
+    /// Add `amount` of scroll at `time` (milliseconds), `per_tab` of which
+    /// move one tab, and return how many tabs to move: positive towards the
+    /// next tab, negative towards the previous one
+    pub fn scroll(&mut self, amount: f64, per_tab: f64, time: u32) -> i32 {
+        let paused = self
+            .last_time
+            .is_none_or(|last| time.wrapping_sub(last) > SCROLL_RESET_TIME);
+        if paused || self.pending * amount < 0.0 {
+            self.pending = 0.0;
+        }
+        self.last_time = Some(time);
+
+        self.pending += amount;
+        let steps = (self.pending / per_tab).trunc();
+        self.pending -= steps * per_tab;
+        steps as i32
+    }
+
+    /// Drop scroll left over, e.g. after the pointer left the tab bar
+    pub fn reset(&mut self) {
+        self.pending = 0.0;
+        self.last_time = None;
+    }
+}
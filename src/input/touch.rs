@@ -1,13 +1,28 @@
 //! Touch input handling
+//!
+//! The first finger of a touch sequence focuses the window under it, or
+//! switches tabs when it lands on a tab bar; further fingers leave focus
+//! alone. Holding a floating window before dragging it moves the window.
+//! Everything is also routed through the seat's touch handle so clients
+//! receive regular touch events.
 
 use smithay::{
-    backend::input::{AbsolutePositionEvent, Event, InputBackend, TouchEvent},
-    input::touch::{DownEvent, MotionEvent as TouchMotionEvent, UpEvent},
+    backend::input::{AbsolutePositionEvent, Event, InputBackend, TouchEvent, TouchSlot},
+    input::touch::{
+        DownEvent, GrabStartData as TouchGrabStartData, MotionEvent as TouchMotionEvent, UpEvent,
+    },
     utils::{Logical, Point, SERIAL_COUNTER as SCOUNTER},
 };
 
+use crate::input::manager::TouchContact;
+use crate::shell::TouchMoveSurfaceGrab;
 use crate::state::StilchState;
 
+/// How long a finger must rest before dragging moves a floating window
+const LONG_PRESS_MS: u32 = 500;
+/// Distance a finger may drift while still counting as resting
+const TOUCH_SLOP: f64 = 8.0;
+
 #[cfg(feature = "udev")]
 impl StilchState<crate::udev::UdevData> {
     /// Handle touch down events
@@ -21,14 +36,42 @@ impl StilchState<crate::udev::UdevData> {
         };
 
         let serial = SCOUNTER.next_serial();
-        self.update_keyboard_focus(touch_location, serial);
+        let slot = evt.slot();
+        let first_contact = self.input_manager.touch_slots.is_empty();
+        self.input_manager.touch_slots.push(slot);
+
+        if first_contact {
+            if let Some(window_id) = self.tab_under(touch_location) {
+                self.activate_window(window_id);
+            } else {
+                self.update_keyboard_focus(touch_location, serial);
+            }
+
+            let floating_window = self
+                .space()
+                .element_under(touch_location)
+                .map(|(window, _)| window.clone())
+                .filter(|window| {
+                    self.window_registry()
+                        .find_by_element(window)
+                        .and_then(|id| self.window_registry().get(id))
+                        .is_some_and(|managed| managed.is_floating())
+                });
+            self.input_manager.touch_contact = Some(TouchContact {
+                slot,
+                start: touch_location,
+                time: evt.time_msec(),
+                floating_window,
+                moved: false,
+            });
+        }
 
         let under = self.surface_under(touch_location);
         handle.down(
             self,
             under,
             &DownEvent {
-                slot: evt.slot(),
+                slot,
                 location: touch_location,
                 serial,
                 time: evt.time_msec(),
@@ -42,11 +85,22 @@ impl StilchState<crate::udev::UdevData> {
             return;
         };
 
+        let slot = evt.slot();
+        self.input_manager.touch_slots.retain(|&s| s != slot);
+        if self
+            .input_manager
+            .touch_contact
+            .as_ref()
+            .is_some_and(|contact| contact.slot == slot)
+        {
+            self.input_manager.touch_contact = None;
+        }
+
         let serial = SCOUNTER.next_serial();
         handle.up(
             self,
             &UpEvent {
-                slot: evt.slot(),
+                slot,
                 serial,
                 time: evt.time_msec(),
             },
@@ -63,6 +117,9 @@ impl StilchState<crate::udev::UdevData> {
             return;
         };
 
+        // A started move grab receives this motion through the handle below
+        self.start_touch_move(evt.slot(), touch_location, evt.time_msec());
+
         let under = self.surface_under(touch_location);
         handle.motion(
             self,
@@ -90,8 +147,56 @@ impl StilchState<crate::udev::UdevData> {
             return;
         };
 
+        self.input_manager.touch_slots.clear();
+        self.input_manager.touch_contact = None;
         handle.cancel(self);
     }
+
+    /// Start moving a floating window once its first contact was held
+    /// still for the long-press delay and then dragged
+    fn start_touch_move(&mut self, slot: TouchSlot, location: Point<f64, Logical>, time: u32) {
+        let Some(contact) = self
+            .input_manager
+            .touch_contact
+            .as_mut()
+            .filter(|contact| contact.slot == slot && !contact.moved)
+        else {
+            return;
+        };
+
+        let delta = location - contact.start;
+        if delta.x.hypot(delta.y) < TOUCH_SLOP {
+            return;
+        }
+        contact.moved = true;
+        if time.wrapping_sub(contact.time) < LONG_PRESS_MS {
+            // Dragged right away: a regular drag for the client
+            return;
+        }
+
+        let Some(window) = contact.floating_window.clone() else {
+            return;
+        };
+        let start = contact.start;
+        let Some(initial_window_location) = self.space().element_location(&window) else {
+            return;
+        };
+        let Some(handle) = self.seat().get_touch() else {
+            return;
+        };
+
+        let grab = TouchMoveSurfaceGrab {
+            start_data: TouchGrabStartData {
+                focus: None,
+                slot,
+                location: start,
+            },
+            window,
+            initial_window_location,
+        };
+        handle.set_grab(self, grab, SCOUNTER.next_serial());
+    }
+
     /// Transform touch location to logical coordinates
     fn touch_location_transformed<B: InputBackend, E: AbsolutePositionEvent<B>>(
         &self,
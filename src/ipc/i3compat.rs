@@ -0,0 +1,687 @@
+//! i3's IPC protocol
+//!
+//! Bars and tools written for i3 (i3status-rust, waybar's i3 modules, the
+//! i3ipc libraries) frame every message as the magic `i3-ipc`, the payload
+//! length and the message type as native-endian u32s, then a JSON payload.
+//! They connect to the socket in `I3SOCK`, which sits next to the stilch
+//! socket and is served only this protocol.
+//!
+//! Replies are put together on the compositor thread: each request goes
+//! there as an [`I3Request`] and its JSON comes back through a oneshot
+//! channel. Events take the same way, so a window event describes the
+//! window just like GET_TREE does.
+
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+use smithay::reexports::calloop::channel::Sender;
+use smithay::utils::{Logical, Rectangle};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{debug, warn};
+
+use crate::state::{Backend, StilchState};
+use crate::window::{FullscreenMode, WindowId, WindowLayout};
+use crate::workspace::layout::{ContainerLayout, LayoutNode};
+use crate::workspace::{Workspace, WorkspaceId};
+
+/// Every i3 message starts with this
+pub const MAGIC: &[u8; 6] = b"i3-ipc";
+
+/// Longest payload accepted from a client. Command lines and subscriptions
+/// are far shorter; a client announcing more is dropped rather than having
+/// that much allocated for it.
+const MAX_PAYLOAD: u32 = 1 << 20;
+
+pub const RUN_COMMAND: u32 = 0;
+pub const GET_WORKSPACES: u32 = 1;
+pub const SUBSCRIBE: u32 = 2;
+pub const GET_OUTPUTS: u32 = 3;
+pub const GET_TREE: u32 = 4;
+pub const GET_MARKS: u32 = 5;
+pub const GET_BAR_CONFIG: u32 = 6;
+pub const GET_VERSION: u32 = 7;
+pub const GET_BINDING_MODES: u32 = 8;
+pub const GET_CONFIG: u32 = 9;
+pub const SEND_TICK: u32 = 10;
+pub const GET_BINDING_STATE: u32 = 12;
+
+/// Event types have the high bit set
+pub const EVENT_WORKSPACE: u32 = 1 << 31;
+pub const EVENT_MODE: u32 = (1 << 31) | 2;
+pub const EVENT_WINDOW: u32 = (1 << 31) | 3;
+
+/// Node ids of containers, workspaces, outputs and the root are kept apart
+/// from window ids, which are used as they are
+const CONTAINER_NODE: u64 = 1 << 32;
+const WORKSPACE_NODE: u64 = 2 << 32;
+const OUTPUT_NODE: u64 = 3 << 32;
+const ROOT_NODE: u64 = 4 << 32;
+
+/// Something i3 clients subscribe to
+#[derive(Debug, Clone)]
+pub enum I3Event {
+    /// A `workspace` event, `change` being i3's name for what happened
+    Workspace {
+        change: &'static str,
+        current: Option<WorkspaceId>,
+        old: Option<WorkspaceId>,
+    },
+    /// A `window` event about one window
+    Window {
+        change: &'static str,
+        window_id: WindowId,
+    },
+    /// A `mode` event, `change` being the binding mode switched to
+    Mode { change: String },
+}
+
+impl I3Event {
+    fn event_type(&self) -> u32 {
+        match self {
+            I3Event::Workspace { .. } => EVENT_WORKSPACE,
+            I3Event::Window { .. } => EVENT_WINDOW,
+            I3Event::Mode { .. } => EVENT_MODE,
+        }
+    }
+}
+
+/// What the compositor thread is asked for
+#[derive(Debug)]
+pub enum I3Query {
+    /// The reply to a request message
+    Message { message_type: u32, payload: String },
+    /// The payload of an event
+    Event(I3Event),
+}
+
+/// A query with the channel its JSON goes back through
+#[derive(Debug)]
+pub struct I3Request {
+    pub query: I3Query,
+    pub reply: oneshot::Sender<String>,
+}
+
+/// Serve an i3 client until it hangs up
+pub(super) async fn serve(
+    stream: UnixStream,
+    requests: Sender<I3Request>,
+    mut events: broadcast::Receiver<I3Event>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    // Reads aren't cancel safe, so they go on in their own task
+    let (messages_tx, mut messages) = mpsc::channel(16);
+    tokio::spawn(read_messages(reader, messages_tx));
+
+    let mut subscribed = HashSet::new();
+    loop {
+        tokio::select! {
+            message = messages.recv() => {
+                let Some((message_type, payload)) = message else {
+                    break;
+                };
+                let reply = if message_type == SUBSCRIBE {
+                    subscribe(&mut subscribed, &payload)
+                } else {
+                    let query = I3Query::Message { message_type, payload };
+                    match ask(&requests, query).await {
+                        Some(reply) => reply,
+                        None => break,
+                    }
+                };
+                if write_message(&mut writer, message_type, &reply).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("i3 IPC client missed {missed} events");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let event_type = event.event_type();
+                if !subscribed.contains(&event_type) {
+                    continue;
+                }
+                let Some(payload) = ask(&requests, I3Query::Event(event)).await else {
+                    break;
+                };
+                if write_message(&mut writer, event_type, &payload).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    debug!("i3 IPC client disconnected");
+}
+
+/// Read messages until the client hangs up, breaks the framing or sends a
+/// payload over [`MAX_PAYLOAD`]
+async fn read_messages(mut reader: OwnedReadHalf, messages: mpsc::Sender<(u32, String)>) {
+    loop {
+        let mut magic = [0u8; 6];
+        if reader.read_exact(&mut magic).await.is_err() || &magic != MAGIC {
+            break;
+        }
+
+        let mut header = [0u8; 8];
+        if reader.read_exact(&mut header).await.is_err() {
+            break;
+        }
+        let length = u32::from_ne_bytes([header[0], header[1], header[2], header[3]]);
+        let message_type = u32::from_ne_bytes([header[4], header[5], header[6], header[7]]);
+        if length > MAX_PAYLOAD {
+            warn!("Dropping i3 IPC client sending a {length} byte payload");
+            break;
+        }
+        let mut payload = vec![0u8; length as usize];
+        if reader.read_exact(&mut payload).await.is_err() {
+            break;
+        }
+        let payload = String::from_utf8_lossy(&payload).into_owned();
+        if messages.send((message_type, payload)).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn write_message(
+    writer: &mut OwnedWriteHalf,
+    message_type: u32,
+    payload: &str,
+) -> std::io::Result<()> {
+    let mut message = Vec::with_capacity(14 + payload.len());
+    message.extend_from_slice(MAGIC);
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&message_type.to_ne_bytes());
+    message.extend_from_slice(payload.as_bytes());
+    writer.write_all(&message).await
+}
+
+/// Have the compositor thread answer, None once it is gone
+async fn ask(requests: &Sender<I3Request>, query: I3Query) -> Option<String> {
+    let (reply, answer) = oneshot::channel();
+    requests.send(I3Request { query, reply }).ok()?;
+    answer.await.ok()
+}
+
+fn subscribe(subscribed: &mut HashSet<u32>, payload: &str) -> String {
+    let Ok(names) = serde_json::from_str::<Vec<String>>(payload) else {
+        return json!({ "success": false, "error": "expected a list of event names" }).to_string();
+    };
+    for name in names {
+        match name.as_str() {
+            "workspace" => {
+                subscribed.insert(EVENT_WORKSPACE);
+            }
+            "window" => {
+                subscribed.insert(EVENT_WINDOW);
+            }
+            "mode" => {
+                subscribed.insert(EVENT_MODE);
+            }
+            // Known to i3 but never sent by stilch
+            "output" | "barconfig_update" | "binding" | "shutdown" | "tick" => {}
+            _ => return json!({ "success": false }).to_string(),
+        }
+    }
+    json!({ "success": true }).to_string()
+}
+
+fn rect(rect: Rectangle<i32, Logical>) -> Value {
+    json!({
+        "x": rect.loc.x,
+        "y": rect.loc.y,
+        "width": rect.size.w,
+        "height": rect.size.h,
+    })
+}
+
+/// The fields every i3 node has, with the defaults of an empty container
+fn node(id: u64, node_type: &str, name: Option<String>, area: Rectangle<i32, Logical>) -> Value {
+    json!({
+        "id": id,
+        "type": node_type,
+        "name": name,
+        "rect": rect(area),
+        "window_rect": rect(Rectangle::default()),
+        "deco_rect": rect(Rectangle::default()),
+        "geometry": rect(Rectangle::default()),
+        "layout": "none",
+        "orientation": "none",
+        "border": "none",
+        "current_border_width": 0,
+        "percent": null,
+        "urgent": false,
+        "focused": false,
+        "focus": [],
+        "marks": [],
+        "sticky": false,
+        "fullscreen_mode": 0,
+        "floating": "auto_off",
+        "scratchpad_state": "none",
+        "window": null,
+        "nodes": [],
+        "floating_nodes": [],
+    })
+}
+
+fn container_layout(layout: ContainerLayout) -> (&'static str, &'static str) {
+    match layout {
+        ContainerLayout::Horizontal => ("splith", "horizontal"),
+        ContainerLayout::Vertical => ("splitv", "vertical"),
+        ContainerLayout::Tabbed => ("tabbed", "horizontal"),
+        ContainerLayout::Stacked => ("stacked", "vertical"),
+    }
+}
+
+fn layout_node_id(node: &LayoutNode) -> u64 {
+    match node {
+        LayoutNode::Window { id, .. } => id.get(),
+        LayoutNode::Container { id, .. } => CONTAINER_NODE | id.get(),
+    }
+}
+
+fn workspace_num(workspace_id: WorkspaceId) -> i64 {
+    workspace_id.display_name().parse().unwrap_or(-1)
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Answer a query of an i3 client
+    pub fn answer_i3_query(&mut self, query: I3Query) -> String {
+        let reply = match query {
+            I3Query::Message {
+                message_type,
+                payload,
+            } => self.i3_reply(message_type, &payload),
+            I3Query::Event(event) => self.i3_event(&event),
+        };
+        reply.to_string()
+    }
+
+    fn i3_reply(&mut self, message_type: u32, payload: &str) -> Value {
+        match message_type {
+            RUN_COMMAND => self.i3_run_command(payload),
+            GET_WORKSPACES => self.i3_workspaces(),
+            GET_OUTPUTS => self.i3_outputs(),
+            GET_TREE => self.i3_tree(),
+            GET_MARKS | GET_BAR_CONFIG => json!([]),
+            GET_BINDING_MODES => json!(self.config.binding_modes()),
+            GET_BINDING_STATE => json!({
+                "name": self.binding_mode.as_deref().unwrap_or("default"),
+            }),
+            GET_VERSION => json!({
+                "major": 4,
+                "minor": 0,
+                "patch": 0,
+                "human_readable": format!("stilch {}", env!("CARGO_PKG_VERSION")),
+                "loaded_config_file_name": self.config.path.as_ref().map(|path| path.display().to_string()),
+            }),
+            GET_CONFIG => json!({
+                "config": self
+                    .config
+                    .path
+                    .as_ref()
+                    .and_then(|path| std::fs::read_to_string(path).ok())
+                    .unwrap_or_default(),
+            }),
+            SEND_TICK => json!({ "success": true }),
+            _ => json!({
+                "success": false,
+                "error": format!("unsupported message type {message_type}"),
+            }),
+        }
+    }
+
+    /// Run a command line the way a binding would, one result per command.
+    /// Commands that don't parse or aren't supported are reported and the
+    /// others still run.
+    fn i3_run_command(&mut self, line: &str) -> Value {
+        let commands = crate::config::parser::parse_command_list(&self.config, line);
+        if commands.is_empty() {
+            return json!([{ "success": false, "parse_error": true, "error": "no command" }]);
+        }
+        let results: Vec<Value> = commands
+            .into_iter()
+            .map(|(_, command)| match command {
+                Ok(command) => match self.command_to_action(&command) {
+                    Some(action) => match self.action_error(&action) {
+                        Some(error) => json!({ "success": false, "error": error }),
+                        None => {
+                            self.handle_key_action(action);
+                            json!({ "success": true })
+                        }
+                    },
+                    None => json!({ "success": false, "error": "command not supported" }),
+                },
+                Err(e) => json!({ "success": false, "parse_error": true, "error": e.to_string() }),
+            })
+            .collect();
+        json!(results)
+    }
+
+    /// The workspace keyboard focus is on, or the one under the pointer
+    fn i3_focused_workspace(&self) -> Option<WorkspaceId> {
+        self.focused_window()
+            .and_then(|element| self.window_registry().find_by_element(&element))
+            .and_then(|id| self.window_registry().get(id))
+            .map(|managed_window| managed_window.workspace)
+            .or_else(|| {
+                self.virtual_output_at_pointer()
+                    .and_then(|vo_id| self.workspace_manager.workspace_on_output(vo_id))
+            })
+    }
+
+    fn i3_workspace_summary(&self, workspace: &Workspace) -> Value {
+        let output = workspace
+            .output()
+            .or(workspace.associated_output())
+            .and_then(|vo_id| self.virtual_output_manager.get(vo_id))
+            .map(|vo| vo.name().to_string());
+        json!({
+            "id": WORKSPACE_NODE | workspace.id.get() as u64,
+            "num": workspace_num(workspace.id),
+            "name": workspace.id.display_name(),
+            "visible": workspace.is_visible(),
+            "focused": self.i3_focused_workspace() == Some(workspace.id),
+            "urgent": self.i3_workspace_urgent(workspace),
+            "rect": rect(workspace.area),
+            "output": output,
+            "layout": "splith",
+            "orientation": "horizontal",
+            "representation": null,
+            "focus": [],
+        })
+    }
+
+    fn i3_workspace_urgent(&self, workspace: &Workspace) -> bool {
+        workspace.windows.iter().any(|window_id| {
+            self.window_registry()
+                .get(*window_id)
+                .is_some_and(|managed_window| managed_window.is_urgent())
+        })
+    }
+
+    /// GET_WORKSPACES: the existing workspaces, in the configured order
+    fn i3_workspaces(&self) -> Value {
+        self.workspace_manager
+            .order()
+            .iter()
+            .filter(|&&id| self.workspace_manager.exists(id))
+            .filter_map(|&id| self.workspace_manager.get(id))
+            .map(|workspace| self.i3_workspace_summary(workspace))
+            .collect()
+    }
+
+    /// GET_OUTPUTS: i3 outputs are stilch's virtual outputs
+    fn i3_outputs(&self) -> Value {
+        let primary = self.virtual_output_manager.primary_virtual_output();
+        let focused = self.virtual_output_at_pointer();
+        self.virtual_output_manager
+            .all_virtual_outputs()
+            .map(|vo| {
+                let physical = vo.physical_outputs().first();
+                let properties = physical.map(|output| output.physical_properties());
+                json!({
+                    "id": OUTPUT_NODE | vo.id().get() as u64,
+                    "name": vo.name(),
+                    "make": properties.as_ref().map(|p| p.make.clone()).unwrap_or_default(),
+                    "model": properties.as_ref().map(|p| p.model.clone()).unwrap_or_default(),
+                    "serial": "Unknown",
+                    "active": true,
+                    "dpms": true,
+                    "power": true,
+                    "primary": primary == Some(vo.id()),
+                    "focused": focused == Some(vo.id()),
+                    "scale": physical.map(|output| output.current_scale().fractional_scale()),
+                    "transform": "normal",
+                    "current_workspace": vo
+                        .active_workspace_id()
+                        .map(|id| id.display_name()),
+                    "modes": [],
+                    "current_mode": null,
+                    "rect": rect(vo.logical_region()),
+                })
+            })
+            .collect()
+    }
+
+    /// GET_TREE: root, then outputs, then workspaces with their containers
+    /// and windows
+    fn i3_tree(&self) -> Value {
+        let focused_window = self
+            .focused_window()
+            .and_then(|element| self.window_registry().find_by_element(&element));
+
+        let mut outputs = Vec::new();
+        let mut root_area: Option<Rectangle<i32, Logical>> = None;
+        for vo in self.virtual_output_manager.all_virtual_outputs() {
+            let area = vo.logical_region();
+            root_area = Some(root_area.map_or(area, |root| root.merge(area)));
+
+            let workspaces: Vec<Value> = self
+                .workspace_manager
+                .order()
+                .iter()
+                .filter(|&&id| self.workspace_manager.exists(id))
+                .filter_map(|&id| self.workspace_manager.get(id))
+                .filter(|workspace| {
+                    workspace.output().or(workspace.associated_output()) == Some(vo.id())
+                })
+                .map(|workspace| self.i3_workspace_node(workspace, focused_window))
+                .collect();
+
+            let mut content = node(
+                CONTAINER_NODE | (1 << 31) | vo.id().get() as u64,
+                "con",
+                Some("content".to_string()),
+                area,
+            );
+            content["layout"] = json!("splith");
+            content["nodes"] = json!(workspaces);
+
+            let mut output = node(
+                OUTPUT_NODE | vo.id().get() as u64,
+                "output",
+                Some(vo.name().to_string()),
+                area,
+            );
+            output["layout"] = json!("output");
+            output["current_workspace"] =
+                json!(vo.active_workspace_id().map(|id| id.display_name()));
+            output["nodes"] = json!([content]);
+            outputs.push(output);
+        }
+
+        let mut root = node(
+            ROOT_NODE,
+            "root",
+            Some("root".to_string()),
+            root_area.unwrap_or_default(),
+        );
+        root["layout"] = json!("splith");
+        root["nodes"] = json!(outputs);
+        root
+    }
+
+    fn i3_workspace_node(&self, workspace: &Workspace, focused_window: Option<WindowId>) -> Value {
+        let mut node = node(
+            WORKSPACE_NODE | workspace.id.get() as u64,
+            "workspace",
+            Some(workspace.id.display_name()),
+            workspace.area,
+        );
+        node["num"] = json!(workspace_num(workspace.id));
+        node["urgent"] = json!(self.i3_workspace_urgent(workspace));
+        node["output"] = json!(workspace
+            .output()
+            .or(workspace.associated_output())
+            .and_then(|vo_id| self.virtual_output_manager.get(vo_id))
+            .map(|vo| vo.name().to_string()));
+        match workspace.layout.root() {
+            Some(LayoutNode::Container { layout, .. }) => {
+                let (layout, orientation) = container_layout(*layout);
+                node["layout"] = json!(layout);
+                node["orientation"] = json!(orientation);
+            }
+            _ => {
+                node["layout"] = json!("splith");
+                node["orientation"] = json!("horizontal");
+            }
+        }
+
+        let tiled = match workspace.layout.root() {
+            // The root container is the workspace itself
+            Some(LayoutNode::Container { children, .. }) => children
+                .iter()
+                .filter_map(|child| self.i3_layout_node(workspace, child, focused_window))
+                .collect(),
+            Some(window) => self
+                .i3_layout_node(workspace, window, focused_window)
+                .into_iter()
+                .collect(),
+            None => Vec::new(),
+        };
+        let floating: Vec<Value> = workspace
+            .floating_windows
+            .iter()
+            .map(|&id| {
+                let mut window = self.i3_window_node(id, focused_window);
+                window["type"] = json!("floating_con");
+                window["floating"] = json!("user_on");
+                window
+            })
+            .collect();
+        node["focus"] = json!(workspace
+            .focused_window
+            .map(|id| vec![id.get()])
+            .unwrap_or_default());
+        node["nodes"] = json!(tiled);
+        node["floating_nodes"] = json!(floating);
+        node
+    }
+
+    fn i3_layout_node(
+        &self,
+        workspace: &Workspace,
+        layout_node: &LayoutNode,
+        focused_window: Option<WindowId>,
+    ) -> Option<Value> {
+        match layout_node {
+            LayoutNode::Window { id, .. } => (!workspace.layout.is_placeholder(*id))
+                .then(|| self.i3_window_node(*id, focused_window)),
+            LayoutNode::Container {
+                id,
+                layout,
+                children,
+                geometry,
+            } => {
+                let nodes: Vec<Value> = children
+                    .iter()
+                    .filter_map(|child| self.i3_layout_node(workspace, child, focused_window))
+                    .collect();
+                let (layout, orientation) = container_layout(*layout);
+                let mut node = node(CONTAINER_NODE | id.get(), "con", None, *geometry);
+                node["layout"] = json!(layout);
+                node["orientation"] = json!(orientation);
+                let active = children.iter().nth(children.active_index());
+                node["focus"] = json!(active.map(|active| vec![layout_node_id(active)]));
+                node["nodes"] = json!(nodes);
+                Some(node)
+            }
+        }
+    }
+
+    fn i3_window_node(&self, window_id: WindowId, focused_window: Option<WindowId>) -> Value {
+        let Some(managed_window) = self.window_registry().get(window_id) else {
+            // A window that has closed is all id
+            return node(window_id.get(), "con", None, Rectangle::default());
+        };
+        let criteria = managed_window.criteria();
+        let (geometry, fullscreen_mode) = match &managed_window.layout {
+            WindowLayout::Tiled { geometry, .. } | WindowLayout::Floating { geometry } => {
+                (*geometry, 0)
+            }
+            WindowLayout::Fullscreen { mode, geometry, .. } => (
+                *geometry,
+                match mode {
+                    FullscreenMode::Container | FullscreenMode::VirtualOutput => 1,
+                    FullscreenMode::PhysicalOutput => 2,
+                },
+            ),
+        };
+
+        let mut node = node(
+            window_id.get(),
+            "con",
+            Some(criteria.title.clone()),
+            geometry,
+        );
+        node["window_rect"] = rect(Rectangle::from_size(geometry.size));
+        node["geometry"] = rect(Rectangle::from_size(geometry.size));
+        node["urgent"] = json!(managed_window.is_urgent());
+        node["focused"] = json!(focused_window == Some(window_id));
+        node["fullscreen_mode"] = json!(fullscreen_mode);
+        node["visible"] = json!(self
+            .space()
+            .element_geometry(&managed_window.element)
+            .is_some());
+        node["app_id"] = json!(criteria.app_id);
+        node["shell"] = json!("xdg_shell");
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = managed_window.element.0.x11_surface() {
+            node["shell"] = json!("xwayland");
+            node["window"] = json!(surface.window_id());
+            node["window_properties"] = json!({
+                "class": criteria.class,
+                "instance": criteria.instance,
+                "title": criteria.title,
+                "window_role": criteria.window_role,
+                "window_type": criteria.window_type,
+            });
+        }
+        node
+    }
+
+    /// The payload of an event for subscribers
+    fn i3_event(&self, event: &I3Event) -> Value {
+        match event {
+            I3Event::Workspace {
+                change,
+                current,
+                old,
+            } => {
+                let focused_window = self
+                    .focused_window()
+                    .and_then(|element| self.window_registry().find_by_element(&element));
+                let workspace = |id: &Option<WorkspaceId>| {
+                    id.and_then(|id| self.workspace_manager.get(id))
+                        .map(|workspace| self.i3_workspace_node(workspace, focused_window))
+                };
+                json!({
+                    "change": change,
+                    "current": workspace(current),
+                    "old": workspace(old),
+                })
+            }
+            I3Event::Window { change, window_id } => {
+                let focused_window = self
+                    .focused_window()
+                    .and_then(|element| self.window_registry().find_by_element(&element));
+                json!({
+                    "change": change,
+                    "container": self.i3_window_node(*window_id, focused_window),
+                })
+            }
+            I3Event::Mode { change } => json!({
+                "change": change,
+                "pango_markup": false,
+            }),
+        }
+    }
+}
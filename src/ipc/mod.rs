@@ -1,28 +1,46 @@
+pub mod i3compat;
+
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
+use smithay::reexports::calloop::channel::Sender;
 use tokio::io::AsyncWriteExt;
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::{broadcast, RwLock};
 use tracing::{error, info, warn};
 
 use crate::virtual_output::VirtualOutputId;
+use crate::window::WindowId;
+use i3compat::{I3Event, I3Request};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum IpcMessage {
     WorkspaceUpdate {
         virtual_output: usize,
+        /// In the configured workspace order, which bars should keep
         workspaces: Vec<WorkspaceInfo>,
     },
     Subscribe {
         events: Vec<String>,
     },
+    TitleChanged {
+        window_id: u64,
+        title: String,
+    },
+    AppIdChanged {
+        window_id: u64,
+        app_id: Option<String>,
+    },
+    /// Effective focus_follows_mouse after a binding switched it
+    FocusFollowsMouse {
+        enabled: bool,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WorkspaceInfo {
     pub id: usize,
     pub active: bool,
@@ -33,7 +51,17 @@ pub struct WorkspaceInfo {
 #[derive(Debug)]
 pub struct IpcServer {
     socket_path: PathBuf,
+    /// Where i3 clients connect, next to `socket_path`. Unlike sway, which
+    /// tells the protocols apart on one socket, stilch's own clients never
+    /// write anything, so an i3 client could only be recognised by waiting
+    /// for its first bytes. A socket of its own, exported as `I3SOCK` to
+    /// spawned programs and the activation environment, needs no guessing.
+    i3_socket_path: PathBuf,
     tx: broadcast::Sender<IpcMessage>,
+    i3_events: broadcast::Sender<I3Event>,
+    /// Workspaces of the last update, to tell i3 clients which came, went or
+    /// turned urgent
+    last_workspaces: std::sync::Mutex<Vec<WorkspaceInfo>>,
     clients: Arc<RwLock<HashMap<usize, UnixStream>>>,
     next_client_id: Arc<RwLock<usize>>,
 }
@@ -45,24 +73,57 @@ impl IpcServer {
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("/tmp/stilch-ipc.sock"));
 
-        // Remove existing socket if it exists
-        if socket_path.exists() {
-            std::fs::remove_file(&socket_path)?;
+        let i3_socket_path = socket_path.with_extension("i3.sock");
+
+        // Remove existing sockets if they exist
+        for path in [&socket_path, &i3_socket_path] {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
         }
 
         let (tx, _) = broadcast::channel(100);
+        let (i3_events, _) = broadcast::channel(100);
 
         Ok(Self {
             socket_path,
+            i3_socket_path,
             tx,
+            i3_events,
+            last_workspaces: std::sync::Mutex::new(Vec::new()),
             clients: Arc::new(RwLock::new(HashMap::new())),
             next_client_id: Arc::new(RwLock::new(0)),
         })
     }
 
-    pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Listen for clients. Queries of i3 clients go to `i3_requests`.
+    pub async fn start(
+        &self,
+        i3_requests: Sender<I3Request>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let listener = UnixListener::bind(&self.socket_path)?;
         info!("IPC server listening on {:?}", self.socket_path);
+        let i3_listener = UnixListener::bind(&self.i3_socket_path)?;
+        info!("i3 IPC server listening on {:?}", self.i3_socket_path);
+
+        let i3_events = self.i3_events.clone();
+        tokio::spawn(async move {
+            loop {
+                match i3_listener.accept().await {
+                    Ok((stream, _)) => {
+                        info!("New i3 IPC client connected");
+                        tokio::spawn(i3compat::serve(
+                            stream,
+                            i3_requests.clone(),
+                            i3_events.subscribe(),
+                        ));
+                    }
+                    Err(e) => {
+                        error!("Failed to accept i3 IPC connection: {e}");
+                    }
+                }
+            }
+        });
 
         let tx = self.tx.clone();
         let clients = self.clients.clone();
@@ -145,6 +206,8 @@ impl IpcServer {
         virtual_output_id: VirtualOutputId,
         workspaces: Vec<WorkspaceInfo>,
     ) {
+        self.send_i3_workspace_changes(&workspaces);
+
         let msg = IpcMessage::WorkspaceUpdate {
             virtual_output: virtual_output_id.get() as usize,
             workspaces,
@@ -155,16 +218,89 @@ impl IpcServer {
         }
     }
 
+    pub fn send_title_changed(&self, window_id: WindowId, title: String) {
+        let msg = IpcMessage::TitleChanged {
+            window_id: window_id.get(),
+            title,
+        };
+
+        if let Err(e) = self.tx.send(msg) {
+            warn!("Failed to broadcast title change: {e}");
+        }
+    }
+
+    pub fn send_app_id_changed(&self, window_id: WindowId, app_id: Option<String>) {
+        let msg = IpcMessage::AppIdChanged {
+            window_id: window_id.get(),
+            app_id,
+        };
+
+        if let Err(e) = self.tx.send(msg) {
+            warn!("Failed to broadcast app_id change: {e}");
+        }
+    }
+
+    pub fn send_focus_follows_mouse(&self, enabled: bool) {
+        let msg = IpcMessage::FocusFollowsMouse { enabled };
+
+        if let Err(e) = self.tx.send(msg) {
+            warn!("Failed to broadcast focus_follows_mouse: {e}");
+        }
+    }
+
+    /// i3's `init`, `empty` and `urgent` workspace events for what changed
+    /// since the last update
+    fn send_i3_workspace_changes(&self, workspaces: &[WorkspaceInfo]) {
+        let previous = match self.last_workspaces.lock() {
+            Ok(mut last) => std::mem::replace(&mut *last, workspaces.to_vec()),
+            Err(_) => return,
+        };
+        // WorkspaceInfo ids are display numbers, one above the workspace id
+        let event = |change, info: &WorkspaceInfo| I3Event::Workspace {
+            change,
+            current: info
+                .id
+                .checked_sub(1)
+                .and_then(|id| u8::try_from(id).ok())
+                .map(crate::workspace::WorkspaceId::new),
+            old: None,
+        };
+        for info in workspaces {
+            match previous.iter().find(|old| old.id == info.id) {
+                None => self.send_i3_event(event("init", info)),
+                Some(old) if old.urgent != info.urgent => self.send_i3_event(event("urgent", info)),
+                Some(_) => {}
+            }
+        }
+        for old in &previous {
+            if !workspaces.iter().any(|info| info.id == old.id) {
+                self.send_i3_event(event("empty", old));
+            }
+        }
+    }
+
+    /// Pass an event on to the i3 clients subscribed to it
+    pub fn send_i3_event(&self, event: I3Event) {
+        // Nobody listening is no error here, most sessions have no i3 client
+        let _ = self.i3_events.send(event);
+    }
+
     pub fn get_socket_path(&self) -> &PathBuf {
         &self.socket_path
     }
+
+    pub fn get_i3_socket_path(&self) -> &PathBuf {
+        &self.i3_socket_path
+    }
 }
 
 impl Drop for IpcServer {
     fn drop(&mut self) {
-        if self.socket_path.exists() {
-            if let Err(e) = std::fs::remove_file(&self.socket_path) {
-                warn!("Failed to remove IPC socket: {e}");
+        for path in [&self.socket_path, &self.i3_socket_path] {
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(path) {
+                    warn!("Failed to remove IPC socket: {e}");
+                }
             }
         }
     }
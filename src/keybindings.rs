@@ -1,17 +1,25 @@
 use crate::{
-    config::{Command, Direction, LayoutCommand, WorkspaceTarget},
+    config::{
+        BalanceScope, BindingKey, Command, Config, CursorMode, Direction, FloatingPosition,
+        FocusMode, GapAmount, GapScope, Keybinding, LayoutCommand, OutputTarget, RotateDirection,
+        ScratchpadCommand, SiblingDirection, Toggle, WorkspaceTarget,
+    },
     shell::WindowElement,
     state::{Backend, StilchState},
 };
 use smithay::{
     backend::input::KeyState,
     desktop::space::SpaceElement,
-    input::keyboard::{FilterResult, Keysym, ModifiersState},
-    utils::{Logical, Point},
+    input::keyboard::{keysyms, FilterResult, Keycode, Keysym, ModifiersState},
+    utils::{Logical, Point, Size},
 };
+use std::cmp::Reverse;
 use std::process::Command as ProcessCommand;
 use tracing::{debug, error, info, warn};
 
+/// How far an arrow key resizes in resize mode, in logical pixels
+const RESIZE_STEP: i32 = 10;
+
 /// Represents a focus target which can be either a window or an empty virtual output
 #[derive(Debug, Clone)]
 enum FocusTarget {
@@ -45,18 +53,67 @@ pub enum KeyAction {
     FullscreenVirtualOutput,
     /// Toggle physical output fullscreen
     FullscreenPhysicalOutput,
+    /// Set fullscreen on or off for the focused window
+    SetFullscreen {
+        enable: bool,
+        mode: crate::window::FullscreenMode,
+    },
     /// Toggle floating
     FloatingToggle,
+    /// Make the focused window floating
+    FloatingEnable,
+    /// Make the focused window tiled
+    FloatingDisable,
+    /// Move the focused floating window
+    MoveFloating(FloatingPosition),
+    /// Resize the focused floating window
+    ResizeFloating { width: i32, height: i32 },
     /// Reload config
     Reload,
+    /// Re-read the keybindings alone
+    ReloadBindings,
     /// Scale output up
     ScaleUp,
     /// Scale output down
     ScaleDown,
     /// Rotate output
     RotateOutput,
-    /// Toggle window preview
-    TogglePreview,
+    /// Set a named output's fractional scale
+    SetScale { output: String, scale: f64 },
+    /// Switch a named output's mode, refresh rate in millihertz
+    OutputMode {
+        name: String,
+        width: i32,
+        height: i32,
+        refresh: Option<u32>,
+    },
+    /// Open or close the window overview
+    Overview(Toggle),
+    /// Move the overview selection
+    OverviewNavigate(Direction),
+    /// Focus the window selected in the overview
+    OverviewSelect,
+    /// Close the overview without changing focus
+    OverviewCancel,
+    /// Switch binding mode, "default" for the top-level bindings
+    Mode(String),
+    /// Grow the focused window by this many pixels along the direction's
+    /// axis, or shrink it for a negative amount
+    Resize(Direction, i32),
+    /// Show or hide the performance overlay
+    DebugOverlay(Toggle),
+    /// Switch between hardware and software cursor
+    CursorMode(CursorMode),
+    /// Switch focus_follows_mouse at runtime
+    FocusFollowsMouse(Toggle),
+    /// Hide the focused window in the scratchpad
+    ScratchpadMove,
+    /// Show the next scratchpad window, or hide the focused one
+    ScratchpadShow,
+    /// Write the workspace's tiling layout to a file
+    SaveLayout(String),
+    /// Load placeholders from a saved layout file
+    AppendLayout(String),
     /// Toggle decorations
     ToggleDecorations,
     /// Switch screen/output (udev specific)
@@ -73,14 +130,80 @@ pub enum KeyAction {
     SplitVertical,
     /// Set automatic (BSP) split
     SplitAutomatic,
-    /// Move workspace to output in direction
-    MoveWorkspaceToOutput(Direction),
+    /// Focus an output
+    FocusOutput(OutputTarget),
+    /// Move focus between tiled and floating windows
+    FocusMode(FocusMode),
+    /// Focus the most recently urgent window
+    FocusUrgent,
+    /// Step back through the focus history
+    FocusBack,
+    /// Step forward through the focus history
+    FocusForward,
+    /// Select the container holding the focused window or container
+    FocusParent,
+    /// Go back down from a selected container
+    FocusChild,
+    /// Step through the children of the focused window's container
+    FocusSibling(SiblingDirection),
+    /// Move workspace to an output
+    MoveWorkspaceToOutput(OutputTarget),
+    /// Exchange the workspaces of the focused output and another one
+    SwapOutputWorkspaces(OutputTarget),
+    /// Move the focused window to the output under the pointer
+    MoveWindowToPointerOutput,
     /// Layout commands (tabbed, stacking, etc)
     Layout(LayoutCommand),
     /// Move tab to the left in a tabbed/stacked container
     MoveTabLeft,
     /// Move tab to the right in a tabbed/stacked container
     MoveTabRight,
+    /// Shift every window in the focused container by one slot
+    RotateContainer(RotateDirection),
+    /// Even out the splits of the focused workspace or container
+    BalanceSplits(BalanceScope),
+    /// Change a gap on every workspace
+    Gaps(GapScope, GapAmount),
+    /// Drag the window under the pointer (mouse bindings only)
+    InteractiveMove,
+    /// Resize the window under the pointer (mouse bindings only)
+    InteractiveResize,
+    /// Run several actions in order
+    Sequence(Vec<KeyAction>),
+}
+
+/// Action of a matched keybinding
+#[derive(Debug, Clone)]
+pub struct BindingAction {
+    pub action: KeyAction,
+    /// Fire again while the key is held
+    pub repeat: bool,
+}
+
+/// Modifiers as bindings see them when `stand_in` replaces the logo key, for
+/// a nested compositor whose host already binds Super. Holding all of
+/// `stand_in` counts as logo, while the real logo key is left to the host.
+pub fn remap_logo_modifier(modifiers: ModifiersState, stand_in: ModifiersState) -> ModifiersState {
+    let held = (!stand_in.ctrl || modifiers.ctrl)
+        && (!stand_in.alt || modifiers.alt)
+        && (!stand_in.shift || modifiers.shift)
+        && (!stand_in.logo || modifiers.logo);
+    ModifiersState {
+        ctrl: modifiers.ctrl && !stand_in.ctrl,
+        alt: modifiers.alt && !stand_in.alt,
+        shift: modifiers.shift && !stand_in.shift,
+        logo: held,
+        ..modifiers
+    }
+}
+
+impl From<KeyAction> for BindingAction {
+    fn from(action: KeyAction) -> Self {
+        Self {
+            action,
+            repeat: false,
+        }
+    }
 }
 
 impl<BackendData: Backend> StilchState<BackendData> {
@@ -323,17 +446,49 @@ impl<BackendData: Backend> StilchState<BackendData> {
         None
     }
 
+    /// Action of a key that drives the open overview (arrows, Enter, Escape)
+    pub fn overview_key_action(&self, keysym: Keysym) -> Option<KeyAction> {
+        self.overview.as_ref()?;
+        match keysym.raw() {
+            keysyms::KEY_Left => Some(KeyAction::OverviewNavigate(Direction::Left)),
+            keysyms::KEY_Right => Some(KeyAction::OverviewNavigate(Direction::Right)),
+            keysyms::KEY_Up => Some(KeyAction::OverviewNavigate(Direction::Up)),
+            keysyms::KEY_Down => Some(KeyAction::OverviewNavigate(Direction::Down)),
+            keysyms::KEY_Return | keysyms::KEY_KP_Enter => Some(KeyAction::OverviewSelect),
+            keysyms::KEY_Escape => Some(KeyAction::OverviewCancel),
+            _ => None,
+        }
+    }
+
+    /// Action of a key the current binding mode doesn't bind. Escape and
+    /// Enter leave the mode, in resize mode the arrow keys resize and keep
+    /// repeating while held, and other keys do nothing.
+    pub fn mode_fallback_key_action(&self, keysym: Keysym) -> BindingAction {
+        let step = |direction, amount| BindingAction {
+            action: KeyAction::Resize(direction, amount),
+            repeat: true,
+        };
+        let resize = self.binding_mode.as_deref() == Some("resize");
+        match keysym.raw() {
+            keysyms::KEY_Return | keysyms::KEY_KP_Enter | keysyms::KEY_Escape => {
+                KeyAction::Mode("default".to_string()).into()
+            }
+            keysyms::KEY_Left if resize => step(Direction::Right, -RESIZE_STEP),
+            keysyms::KEY_Right if resize => step(Direction::Right, RESIZE_STEP),
+            keysyms::KEY_Up if resize => step(Direction::Down, -RESIZE_STEP),
+            keysyms::KEY_Down if resize => step(Direction::Down, RESIZE_STEP),
+            _ => KeyAction::None.into(),
+        }
+    }
+
     pub fn process_keybinding(
         &self,
         modifiers: ModifiersState,
+        keycode: Keycode,
         modified_keysym: Keysym,
         raw_keysym: Option<Keysym>,
         state: KeyState,
-    ) -> FilterResult<KeyAction> {
-        if state != KeyState::Pressed {
-            return FilterResult::Forward;
-        }
-
+    ) -> FilterResult<BindingAction> {
         debug!("process_keybinding: modified_keysym={:?} ({}) raw={:#x}, raw_keysym={:?}, modifiers={:?}",
             modified_keysym,
             xkbcommon::xkb::keysym_get_name(modified_keysym),
@@ -344,27 +499,63 @@ impl<BackendData: Backend> StilchState<BackendData> {
 
         // Check config keybindings - use raw keysym for matching (if available)
         let keysym_for_binding = raw_keysym.unwrap_or(modified_keysym);
-
-        for binding in &self.config.keybindings {
-            if binding.key == keysym_for_binding {
-                debug!("Key matches binding: key={:?} ({}), required_modifiers={:?}, current_modifiers={:?}",
-                    binding.key,
-                    xkbcommon::xkb::keysym_get_name(binding.key),
-                    binding.modifiers,
-                    modifiers
-                );
-                if self.modifiers_match(modifiers, binding.modifiers) {
-                    debug!("Keybinding matched! Command: {:?}", binding.command);
-                    if let Some(action) = self.command_to_action(&binding.command) {
-                        return FilterResult::Intercept(action);
-                    }
-                }
+        let release = state == KeyState::Released;
+
+        // bindcode bindings are matched first, and chords before the single
+        // keys they end with
+        let mut bindings: Vec<&Keybinding> = self
+            .config
+            .keycode_bindings
+            .get(&keycode.raw())
+            .into_iter()
+            .flatten()
+            .chain(
+                self.config
+                    .keybindings
+                    .iter()
+                    .filter(|binding| binding.key == BindingKey::Key(keysym_for_binding)),
+            )
+            .filter(|binding| binding.mode == self.binding_mode)
+            .filter(|binding| self.chord_held(binding, keycode))
+            .collect();
+        bindings.sort_by_key(|binding| Reverse(binding.held.len()));
+
+        let mut release_binding_pressed = false;
+        for binding in bindings {
+            debug!("Key matches binding: key={:?} ({}), required_modifiers={:?}, current_modifiers={:?}",
+                binding.key,
+                xkbcommon::xkb::keysym_get_name(keysym_for_binding),
+                binding.modifiers,
+                modifiers
+            );
+            if !self.modifiers_match(modifiers, binding.modifiers) {
+                continue;
+            }
+            if binding.release != release {
+                release_binding_pressed |= binding.release;
+                continue;
+            }
+            debug!("Keybinding matched! Command: {:?}", binding.command);
+            if let Some(action) = self.command_to_action(&binding.command) {
+                return FilterResult::Intercept(BindingAction {
+                    action,
+                    repeat: !release && binding.repeat,
+                });
             }
         }
 
+        if release {
+            return FilterResult::Forward;
+        }
+
+        // Swallow the press of a --release binding so the client never sees half a key
+        if release_binding_pressed {
+            return FilterResult::Intercept(KeyAction::None.into());
+        }
+
         // Fallback to hardcoded keybindings for essential functions
         if modifiers.ctrl && modifiers.alt && keysym_for_binding == Keysym::BackSpace {
-            return FilterResult::Intercept(KeyAction::Quit);
+            return FilterResult::Intercept(KeyAction::Quit.into());
         }
 
         // VT switching keybindings using XF86Switch_VT keysyms
@@ -377,26 +568,70 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 .contains(&modified_keysym.raw())
             {
                 let vt = (modified_keysym.raw() - keysyms::KEY_XF86Switch_VT_1 + 1) as i32;
-                return FilterResult::Intercept(KeyAction::VtSwitch(vt));
+                return FilterResult::Intercept(KeyAction::VtSwitch(vt).into());
             }
         }
 
         FilterResult::Forward
     }
 
+    /// Action of the mouse binding for a button or wheel notch, if any.
+    /// `over_window` is true when the pointer is over a client surface rather
+    /// than a decoration, where only `--whole-window` bindings apply.
+    pub fn process_mouse_binding(
+        &self,
+        modifiers: ModifiersState,
+        key: BindingKey,
+        over_window: bool,
+    ) -> Option<KeyAction> {
+        let binding = self.config.keybindings.iter().find(|binding| {
+            binding.key == key
+                && binding.mode == self.binding_mode
+                && (binding.whole_window || !over_window)
+                && self.modifiers_match(modifiers, binding.modifiers)
+        })?;
+        debug!("Mouse binding matched! Command: {:?}", binding.command);
+        self.command_to_action(&binding.command)
+    }
+
+    /// Whether a key press is the chord that toggles a shortcuts inhibitor
+    pub fn is_shortcuts_inhibit_escape(&self, modifiers: ModifiersState, keysym: Keysym) -> bool {
+        self.config.shortcuts_inhibit_escape.is_some_and(|chord| {
+            chord.key == keysym && self.modifiers_match(modifiers, chord.modifiers)
+        })
+    }
+
+    /// Whether `current` modifiers trigger a binding on `required` ones.
+    /// Num Lock and the level 3 and 5 shifts only count when the binding asks
+    /// for them, so bindings without them work whatever their state.
     fn modifiers_match(&self, current: ModifiersState, required: ModifiersState) -> bool {
         current.ctrl == required.ctrl
             && current.alt == required.alt
             && current.shift == required.shift
             && current.logo == required.logo
+            && (current.num_lock || !required.num_lock)
+            && (current.iso_level3_shift || !required.iso_level3_shift)
+            && (current.iso_level5_shift || !required.iso_level5_shift)
+    }
+
+    /// Whether the other keys of `binding`'s chord are held while `keycode`
+    /// is pressed
+    fn chord_held(&self, binding: &Keybinding, keycode: Keycode) -> bool {
+        binding.held.iter().all(|key| {
+            self.input_manager.held_keys.iter().any(|&(code, keysym)| {
+                code != keycode
+                    && (*key == BindingKey::Key(keysym) || *key == BindingKey::Code(code.raw()))
+            })
+        })
     }
 
-    fn command_to_action(&self, command: &Command) -> Option<KeyAction> {
+    pub(crate) fn command_to_action(&self, command: &Command) -> Option<KeyAction> {
         match command {
             Command::Exec(cmd) => Some(KeyAction::Run(cmd.clone())),
             Command::Kill => Some(KeyAction::Kill),
             Command::Exit => Some(KeyAction::Quit),
             Command::Reload => Some(KeyAction::Reload),
+            Command::ReloadBindings => Some(KeyAction::ReloadBindings),
             Command::DebugSwapWindows => Some(KeyAction::DebugSwapWindows),
             Command::SplitHorizontal => Some(KeyAction::SplitHorizontal),
             Command::SplitVertical => Some(KeyAction::SplitVertical),
@@ -404,21 +639,108 @@ impl<BackendData: Backend> StilchState<BackendData> {
             Command::Workspace(target) => Some(KeyAction::Workspace(target.clone())),
             Command::MoveToWorkspace(target) => Some(KeyAction::MoveToWorkspace(target.clone())),
             Command::Focus(dir) => Some(KeyAction::Focus(*dir)),
+            Command::FocusOutput(target) => Some(KeyAction::FocusOutput(target.clone())),
+            Command::FocusMode(mode) => Some(KeyAction::FocusMode(*mode)),
+            Command::FocusUrgent => Some(KeyAction::FocusUrgent),
+            Command::FocusBack => Some(KeyAction::FocusBack),
+            Command::FocusForward => Some(KeyAction::FocusForward),
+            Command::FocusParent => Some(KeyAction::FocusParent),
+            Command::FocusChild => Some(KeyAction::FocusChild),
+            Command::FocusSibling(direction) => Some(KeyAction::FocusSibling(*direction)),
             Command::Move(dir) => Some(KeyAction::Move(*dir)),
             Command::Fullscreen => Some(KeyAction::Fullscreen),
             Command::FullscreenContainer => Some(KeyAction::FullscreenContainer),
             Command::FullscreenVirtualOutput => Some(KeyAction::FullscreenVirtualOutput),
             Command::FullscreenPhysicalOutput => Some(KeyAction::FullscreenPhysicalOutput),
+            Command::SetFullscreen { enable, mode } => Some(KeyAction::SetFullscreen {
+                enable: *enable,
+                mode: *mode,
+            }),
             Command::FloatingToggle => Some(KeyAction::FloatingToggle),
-            Command::MoveWorkspaceToOutput(dir) => Some(KeyAction::MoveWorkspaceToOutput(*dir)),
+            Command::FloatingEnable => Some(KeyAction::FloatingEnable),
+            Command::FloatingDisable => Some(KeyAction::FloatingDisable),
+            Command::MoveFloating(position) => Some(KeyAction::MoveFloating(*position)),
+            Command::ResizeFloating { width, height } => Some(KeyAction::ResizeFloating {
+                width: *width,
+                height: *height,
+            }),
+            Command::MoveWorkspaceToOutput(target) => {
+                Some(KeyAction::MoveWorkspaceToOutput(target.clone()))
+            }
+            Command::SwapOutputWorkspaces(target) => {
+                Some(KeyAction::SwapOutputWorkspaces(target.clone()))
+            }
+            Command::MoveWindowToPointerOutput => Some(KeyAction::MoveWindowToPointerOutput),
+            Command::SetScale { output, scale } => Some(KeyAction::SetScale {
+                output: output.clone(),
+                scale: *scale,
+            }),
+            Command::OutputMode {
+                name,
+                width,
+                height,
+                refresh,
+            } => Some(KeyAction::OutputMode {
+                name: name.clone(),
+                width: *width,
+                height: *height,
+                refresh: *refresh,
+            }),
             Command::Layout(layout_cmd) => Some(KeyAction::Layout(layout_cmd.clone())),
             Command::MoveTabLeft => Some(KeyAction::MoveTabLeft),
             Command::MoveTabRight => Some(KeyAction::MoveTabRight),
+            Command::RotateContainer(direction) => Some(KeyAction::RotateContainer(*direction)),
+            Command::BalanceSplits { scope } => Some(KeyAction::BalanceSplits(*scope)),
+            Command::Gaps { scope, amount } => Some(KeyAction::Gaps(*scope, *amount)),
+            Command::InteractiveMove => Some(KeyAction::InteractiveMove),
+            Command::InteractiveResize => Some(KeyAction::InteractiveResize),
+            Command::Mode(name) => Some(KeyAction::Mode(name.clone())),
+            Command::Resize { direction, amount } => Some(KeyAction::Resize(*direction, *amount)),
+            Command::Overview(toggle) => Some(KeyAction::Overview(*toggle)),
+            Command::DebugOverlay(toggle) => Some(KeyAction::DebugOverlay(*toggle)),
+            Command::CursorMode(mode) => Some(KeyAction::CursorMode(*mode)),
+            Command::FocusFollowsMouse(true) => Some(KeyAction::FocusFollowsMouse(Toggle::Enable)),
+            Command::FocusFollowsMouse(false) => {
+                Some(KeyAction::FocusFollowsMouse(Toggle::Disable))
+            }
+            Command::ToggleFocusFollowsMouse => Some(KeyAction::FocusFollowsMouse(Toggle::Toggle)),
+            Command::Scratchpad(ScratchpadCommand::Move) => Some(KeyAction::ScratchpadMove),
+            Command::Scratchpad(ScratchpadCommand::Show) => Some(KeyAction::ScratchpadShow),
+            Command::SaveLayout(path) => Some(KeyAction::SaveLayout(path.clone())),
+            Command::AppendLayout(path) => Some(KeyAction::AppendLayout(path.clone())),
+            // Still intercepts the key, so nop can shadow a binding
+            Command::Nop(_) => Some(KeyAction::None),
+            // Unimplemented sub-commands are dropped without stopping the rest
+            Command::Sequence(commands) => Some(KeyAction::Sequence(
+                commands
+                    .iter()
+                    .filter_map(|command| self.command_to_action(command))
+                    .collect(),
+            )),
             _ => None, // Unimplemented commands
         }
     }
 
+    /// Why `action` can't be carried out in the current state, if it can't
+    pub fn action_error(&self, action: &KeyAction) -> Option<String> {
+        match action {
+            KeyAction::Mode(name) if !self.config.binding_modes().contains(&name.as_str()) => {
+                Some(format!("Unknown mode \"{name}\""))
+            }
+            // Only the focused window would go, leaving the rest of the selection
+            KeyAction::ScratchpadMove if self.selected_container().is_some() => {
+                Some("Moving a selected container to the scratchpad is not supported".into())
+            }
+            _ => None,
+        }
+    }
+
     pub fn handle_key_action(&mut self, action: KeyAction) {
+        if let Some(error) = self.action_error(&action) {
+            warn!("{error}");
+            return;
+        }
+
         match action {
             KeyAction::Quit => {
                 info!("Quitting compositor");
@@ -431,15 +753,6 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 let mut command = ProcessCommand::new("sh");
                 command.arg("-c").arg(&cmd);
 
-                if let Some(socket_name) = &self.socket_name {
-                    command.env("WAYLAND_DISPLAY", socket_name);
-                }
-
-                #[cfg(feature = "xwayland")]
-                if let Some(xdisplay) = self.xdisplay {
-                    command.env("DISPLAY", format!(":{xdisplay}"));
-                }
-
                 // Set virtual output environment variables based on keyboard focus or pointer location
                 let focus_location = if let Some(keyboard) = self.seat().get_keyboard() {
                     if let Some(focus) = keyboard.current_focus() {
@@ -474,9 +787,7 @@ impl<BackendData: Backend> StilchState<BackendData> {
                     }
                 }
 
-                if let Err(e) = command.spawn() {
-                    error!(cmd, err = %e, "Failed to start program");
-                }
+                self.spawn_command(command, cmd);
             }
 
             KeyAction::Kill => {
@@ -498,25 +809,60 @@ impl<BackendData: Backend> StilchState<BackendData> {
                     if let Some(virtual_output) =
                         self.virtual_output_manager.get_mut(virtual_output_id)
                     {
+                        let previous = virtual_output
+                            .previous_workspace_id()
+                            .map(|ws| ws.get() as usize);
                         let workspace_idx = match target {
                             WorkspaceTarget::Number(n) => {
                                 if n >= 1 && n <= 10 {
-                                    Some((n - 1) as usize)
+                                    let idx = (n - 1) as usize;
+                                    if self.config.workspace_auto_back_and_forth
+                                        && virtual_output.active_workspace() == Some(idx)
+                                    {
+                                        previous
+                                    } else {
+                                        Some(idx)
+                                    }
                                 } else {
                                     None
                                 }
                             }
                             WorkspaceTarget::Previous => {
-                                // TODO: Track previous workspace
-                                None
+                                virtual_output.active_workspace().and_then(|current| {
+                                    self.workspace_manager
+                                        .previous_in_order(crate::workspace::WorkspaceId::new(
+                                            current as u8,
+                                        ))
+                                        .map(|ws| ws.get() as usize)
+                                })
                             }
                             WorkspaceTarget::Next => {
                                 virtual_output.active_workspace().and_then(|current| {
-                                    if current < 9 {
-                                        Some(current + 1)
-                                    } else {
-                                        None
-                                    }
+                                    self.workspace_manager
+                                        .next_in_order(crate::workspace::WorkspaceId::new(
+                                            current as u8,
+                                        ))
+                                        .map(|ws| ws.get() as usize)
+                                })
+                            }
+                            WorkspaceTarget::PreviousOnOutput => {
+                                virtual_output.active_workspace().and_then(|current| {
+                                    self.workspace_manager
+                                        .previous_on_output(
+                                            crate::workspace::WorkspaceId::new(current as u8),
+                                            virtual_output_id,
+                                        )
+                                        .map(|ws| ws.get() as usize)
+                                })
+                            }
+                            WorkspaceTarget::NextOnOutput => {
+                                virtual_output.active_workspace().and_then(|current| {
+                                    self.workspace_manager
+                                        .next_on_output(
+                                            crate::workspace::WorkspaceId::new(current as u8),
+                                            virtual_output_id,
+                                        )
+                                        .map(|ws| ws.get() as usize)
                                 })
                             }
                             WorkspaceTarget::Name(_) => {
@@ -543,23 +889,45 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 if let Some(window_elem) = self.focused_window() {
                     // Find window in registry
                     if let Some(window_id) = self.window_registry().find_by_element(&window_elem) {
+                        // With workspace_auto_back_and_forth, moving to the window's own
+                        // workspace sends it to the one its output showed before
+                        let current = self.window_registry().get(window_id).map(|mw| mw.workspace);
+                        let previous = current
+                            .and_then(|ws| self.workspace_manager.find_workspace_location(ws))
+                            .and_then(|vo_id| self.virtual_output_manager.get(vo_id))
+                            .and_then(|vo| vo.previous_workspace_id())
+                            .map(|ws| ws.get());
                         let workspace_idx = match target {
                             WorkspaceTarget::Number(n) => {
                                 if n >= 1 && n <= 10 {
-                                    Some((n - 1) as u8)
+                                    let idx = n - 1;
+                                    if self.config.workspace_auto_back_and_forth
+                                        && current.map(|ws| ws.get()) == Some(idx)
+                                    {
+                                        previous
+                                    } else {
+                                        Some(idx)
+                                    }
                                 } else {
                                     None
                                 }
                             }
-                            WorkspaceTarget::Previous => None,
-                            WorkspaceTarget::Next => None,
+                            WorkspaceTarget::Previous
+                            | WorkspaceTarget::Next
+                            | WorkspaceTarget::PreviousOnOutput
+                            | WorkspaceTarget::NextOnOutput => None,
                             WorkspaceTarget::Name(_) => None,
                         };
 
                         if let Some(idx) = workspace_idx {
                             let target_workspace_id = crate::workspace::WorkspaceId::new(idx);
                             info!("Moving window to workspace {}", idx + 1);
-                            self.move_window_to_workspace_by_id(window_id, target_workspace_id);
+                            if self.selected_container().is_some() {
+                                self.move_selected_container_to_workspace(target_workspace_id);
+                            } else if !self.restore_from_scratchpad(window_id, target_workspace_id)
+                            {
+                                self.move_window_to_workspace_by_id(window_id, target_workspace_id);
+                            }
                         }
                     } else {
                         tracing::warn!("Focused window not found in registry");
@@ -697,6 +1065,11 @@ impl<BackendData: Backend> StilchState<BackendData> {
             KeyAction::Move(dir) => {
                 debug!("Move window {:?}", dir);
 
+                if self.selected_container().is_some() {
+                    self.move_selected_container(dir);
+                    return;
+                }
+
                 if let Some(window_element) = self.focused_window() {
                     // Check if we're in a tabbed/stacked container and moving left/right
                     if matches!(dir, Direction::Left | Direction::Right) {
@@ -771,8 +1144,17 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 self.toggle_fullscreen(crate::window::FullscreenMode::PhysicalOutput);
             }
 
-            KeyAction::FloatingToggle => {
-                debug!("Toggle floating");
+            KeyAction::SetFullscreen { enable, mode } => {
+                let focused = self
+                    .focused_window()
+                    .and_then(|window| self.window_registry().find_by_element(&window));
+                if let Some(window_id) = focused {
+                    self.set_fullscreen(window_id, enable, mode);
+                }
+            }
+
+            KeyAction::FloatingToggle | KeyAction::FloatingEnable | KeyAction::FloatingDisable => {
+                debug!("Change floating state: {:?}", action);
                 // Get the focused window from the active workspace
                 let focused_window = self.space().outputs().find_map(|output| {
                     let vo_ids = self
@@ -788,52 +1170,26 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 });
 
                 if let Some(window_id) = focused_window {
-                    // Get info and toggle floating state
-                    let (is_floating, workspace_id) = {
-                        if let Some(managed_window) = self.window_registry_mut().get_mut(window_id)
-                        {
-                            // Toggle between tiled and floating
-                            let is_now_floating = match &managed_window.layout {
-                                crate::window::WindowLayout::Tiled { geometry, .. } => {
-                                    // Switch to floating, preserve geometry
-                                    managed_window.layout = crate::window::WindowLayout::Floating {
-                                        geometry: *geometry,
-                                    };
-                                    true
-                                }
-                                crate::window::WindowLayout::Floating { geometry } => {
-                                    // Switch to tiled, workspace will assign container
-                                    managed_window.layout = crate::window::WindowLayout::Tiled {
-                                        container: crate::window::ContainerId::next(), // Temporary
-                                        geometry: *geometry,
-                                    };
-                                    false
-                                }
-                                crate::window::WindowLayout::Fullscreen { .. } => {
-                                    // Don't toggle floating while fullscreen
-                                    return;
-                                }
-                            };
-                            (is_now_floating, managed_window.workspace)
-                        } else {
-                            return; // Window not found
-                        }
+                    let Some(is_floating) = self
+                        .window_registry()
+                        .get(window_id)
+                        .map(|mw| mw.is_floating())
+                    else {
+                        return; // Window not found
+                    };
+                    let floating = match action {
+                        KeyAction::FloatingEnable => true,
+                        KeyAction::FloatingDisable => false,
+                        _ => !is_floating,
                     };
+                    self.set_window_floating(window_id, floating);
+                }
+            }
 
-                    // Re-apply layout to update the window's position
-                    if let Some(vo_id) =
-                        self.workspace_manager.find_workspace_location(workspace_id)
-                    {
-                        if let Some(vo) = self.virtual_output_manager.get(vo_id) {
-                            if vo.active_workspace() == Some(workspace_id.get() as usize) {
-                                // Apply layout
-                                self.apply_workspace_layout(workspace_id);
-                            }
-                        }
-                    }
+            KeyAction::MoveFloating(position) => self.move_floating(position),
 
-                    info!("Window {} floating: {}", window_id.get(), is_floating);
-                }
+            KeyAction::ResizeFloating { width, height } => {
+                self.resize_floating(Size::from((width, height)))
             }
 
             KeyAction::Reload => {
@@ -843,38 +1199,33 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 warn!("Config reload not yet implemented - requires config file path tracking");
             }
 
+            KeyAction::ReloadBindings => self.reload_bindings(),
+
             KeyAction::ScaleUp => {
                 info!("Scale up output");
-                if let Some(output) = self.space().outputs().next() {
+                if let Some(output) = self.space().outputs().next().cloned() {
                     let current_scale = output.current_scale().fractional_scale();
-                    let new_scale = (current_scale + 0.25).min(3.0);
-                    output.change_current_state(
-                        None,
-                        None,
-                        Some(smithay::output::Scale::Fractional(new_scale)),
-                        None,
-                    );
-                    info!("Output scale changed to: {new_scale}");
-                    // Trigger re-render on next frame
+                    self.set_output_scale(&output.name(), (current_scale + 0.25).min(3.0));
                 }
             }
 
             KeyAction::ScaleDown => {
                 info!("Scale down output");
-                if let Some(output) = self.space().outputs().next() {
+                if let Some(output) = self.space().outputs().next().cloned() {
                     let current_scale = output.current_scale().fractional_scale();
-                    let new_scale = (current_scale - 0.25).max(0.5);
-                    output.change_current_state(
-                        None,
-                        None,
-                        Some(smithay::output::Scale::Fractional(new_scale)),
-                        None,
-                    );
-                    info!("Output scale changed to: {new_scale}");
-                    // Trigger re-render on next frame
+                    self.set_output_scale(&output.name(), (current_scale - 0.25).max(0.5));
                 }
             }
 
+            KeyAction::SetScale { output, scale } => self.set_output_scale(&output, scale),
+
+            KeyAction::OutputMode {
+                name,
+                width,
+                height,
+                refresh,
+            } => self.set_output_resolution(&name, Size::from((width, height)), refresh),
+
             KeyAction::RotateOutput => {
                 info!("Rotate output");
                 if let Some(output) = self.space().outputs().next() {
@@ -896,9 +1247,53 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 }
             }
 
-            KeyAction::TogglePreview => {
-                self.show_window_preview = !self.show_window_preview;
-                debug!("Toggle window preview: {}", self.show_window_preview);
+            KeyAction::Overview(toggle) => {
+                debug!("Overview: {:?}", toggle);
+                self.set_overview(toggle);
+            }
+
+            KeyAction::OverviewNavigate(direction) => self.overview_navigate(direction),
+
+            KeyAction::OverviewSelect => self.overview_select(),
+
+            KeyAction::OverviewCancel => self.set_overview(Toggle::Disable),
+
+            KeyAction::Mode(name) => self.set_binding_mode(&name),
+
+            KeyAction::Resize(direction, amount) => self.resize_focused_by(direction, amount),
+
+            KeyAction::DebugOverlay(toggle) => self.set_debug_overlay(toggle),
+            KeyAction::CursorMode(mode) => {
+                if self.backend_data.set_cursor_mode(mode) {
+                    info!("Cursor mode: {:?}", mode);
+                    self.backend_data.request_render();
+                } else {
+                    debug!("This backend always draws the cursor itself");
+                }
+            }
+            KeyAction::FocusFollowsMouse(toggle) => self.set_focus_follows_mouse(toggle),
+
+            KeyAction::ScratchpadMove => {
+                let focused = self
+                    .focused_window()
+                    .and_then(|window| self.window_registry().find_by_element(&window));
+                if let Some(window_id) = focused {
+                    self.move_to_scratchpad(window_id);
+                }
+            }
+
+            KeyAction::ScratchpadShow => self.scratchpad_show(),
+
+            KeyAction::SaveLayout(path) => {
+                if let Err(err) = self.save_layout(&path) {
+                    error!(path, err = %err, "Failed to save layout");
+                }
+            }
+
+            KeyAction::AppendLayout(path) => {
+                if let Err(err) = self.append_layout(&path) {
+                    error!(path, err = %err, "Failed to append layout");
+                }
             }
 
             KeyAction::ToggleDecorations => {
@@ -963,12 +1358,21 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 // VT switching is handled by the backend
             }
 
-            KeyAction::MoveWorkspaceToOutput(direction) => {
-                info!(
-                    "KeyAction::MoveWorkspaceToOutput called with direction: {:?}",
-                    direction
-                );
-                self.move_workspace_to_output(direction);
+            KeyAction::FocusOutput(target) => {
+                info!("Focus output {:?}", target);
+                self.focus_output(target);
+            }
+
+            KeyAction::MoveWorkspaceToOutput(target) => {
+                info!("Move workspace to output {:?}", target);
+                if let Err(err) = self.move_workspace_to_output(target) {
+                    warn!(err = %err, "Failed to move workspace to output");
+                }
+            }
+
+            KeyAction::SwapOutputWorkspaces(target) => {
+                info!("Swap workspaces with output {:?}", target);
+                self.swap_output_workspaces(target);
             }
 
             KeyAction::Layout(layout_cmd) => {
@@ -985,6 +1389,49 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 self.handle_move_tab(false);
             }
 
+            KeyAction::RotateContainer(direction) => {
+                tracing::info!("Rotating container {:?}", direction);
+                self.handle_rotate_container(direction);
+            }
+
+            KeyAction::BalanceSplits(scope) => {
+                tracing::info!("Balancing splits ({:?})", scope);
+                self.handle_balance_splits(scope);
+            }
+
+            KeyAction::Gaps(scope, amount) => {
+                info!("Gaps {:?} {:?}", scope, amount);
+                self.set_gaps(scope, amount);
+            }
+
+            KeyAction::FocusMode(mode) => {
+                tracing::info!("Focus {:?}", mode);
+                self.handle_focus_mode(mode);
+            }
+
+            KeyAction::FocusUrgent => self.focus_urgent(),
+            KeyAction::FocusBack => self.focus_history_step(false),
+            KeyAction::FocusForward => self.focus_history_step(true),
+            KeyAction::FocusParent => self.focus_parent(),
+            KeyAction::FocusChild => self.focus_child(),
+            KeyAction::FocusSibling(direction) => self.focus_sibling(direction),
+
+            KeyAction::MoveWindowToPointerOutput => {
+                tracing::info!("Moving window to the output under the pointer");
+                self.handle_move_window_to_pointer_output();
+            }
+
+            KeyAction::InteractiveMove | KeyAction::InteractiveResize => {
+                // Started from the pointer button handler, which knows the held button
+                debug!("{:?} only works from a mouse binding", action);
+            }
+
+            KeyAction::Sequence(actions) => {
+                for action in actions {
+                    self.handle_key_action(action);
+                }
+            }
+
             KeyAction::None => {}
         }
     }
@@ -1015,6 +1462,8 @@ impl<BackendData: Backend> StilchState<BackendData> {
 
         if let Some(workspace_id) = workspace_id {
             if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
+                // A container selected with `focus parent` changes as a whole
+                let selected = workspace.focused_container;
                 match layout_cmd {
                     LayoutCommand::Tabbed => {
                         tracing::info!(
@@ -1022,9 +1471,14 @@ impl<BackendData: Backend> StilchState<BackendData> {
                             focused_window_id,
                             workspace_id
                         );
-                        workspace
-                            .layout
-                            .set_container_layout(focused_window_id, ContainerLayout::Tabbed);
+                        match selected {
+                            Some(container) => workspace
+                                .layout
+                                .set_layout_of(container, ContainerLayout::Tabbed),
+                            None => workspace
+                                .layout
+                                .set_container_layout(focused_window_id, ContainerLayout::Tabbed),
+                        }
                         workspace.relayout();
                         tracing::info!("Layout set and relayout done");
                     }
@@ -1033,16 +1487,26 @@ impl<BackendData: Backend> StilchState<BackendData> {
                             "Setting stacking layout for container with window {}",
                             focused_window_id
                         );
-                        workspace
-                            .layout
-                            .set_container_layout(focused_window_id, ContainerLayout::Stacked);
+                        match selected {
+                            Some(container) => workspace
+                                .layout
+                                .set_layout_of(container, ContainerLayout::Stacked),
+                            None => workspace
+                                .layout
+                                .set_container_layout(focused_window_id, ContainerLayout::Stacked),
+                        }
                         workspace.relayout();
                     }
                     LayoutCommand::ToggleSplit => {
                         info!("Toggling split layout");
-                        workspace
-                            .layout
-                            .toggle_container_split(focused_window_id, workspace.next_split);
+                        match selected {
+                            Some(container) => workspace
+                                .layout
+                                .toggle_split_of(container, workspace.next_split),
+                            None => workspace
+                                .layout
+                                .toggle_container_split(focused_window_id, workspace.next_split),
+                        }
                         workspace.relayout();
                     }
                     LayoutCommand::SplitH => {
@@ -1062,6 +1526,17 @@ impl<BackendData: Backend> StilchState<BackendData> {
     }
 
     fn handle_move_tab(&mut self, left: bool) {
+        // A selected container moves as a whole
+        if self.selected_container().is_some() {
+            let direction = if left {
+                Direction::Left
+            } else {
+                Direction::Right
+            };
+            self.move_selected_container(direction);
+            return;
+        }
+
         // Get the current focused window element
         let Some(focused_element) = self.focused_window() else {
             tracing::warn!("No focused window for move tab command");
@@ -1106,4 +1581,212 @@ impl<BackendData: Backend> StilchState<BackendData> {
         // Apply the workspace layout to actually update the space
         self.apply_workspace_layout(workspace_id);
     }
+
+    fn handle_rotate_container(&mut self, direction: RotateDirection) {
+        let Some(focused_element) = self.focused_window() else {
+            tracing::warn!("No focused window for rotate command");
+            return;
+        };
+
+        let Some(focused_window_id) = self.window_registry().find_by_element(&focused_element)
+        else {
+            tracing::warn!("Focused element not found in window registry");
+            return;
+        };
+
+        let Some(workspace_id) = self
+            .workspace_manager
+            .find_window_workspace(focused_window_id)
+        else {
+            tracing::warn!("Window not found in any workspace");
+            return;
+        };
+
+        // Window ids travel with their slots, so focus stays on the same window
+        let rotated = self
+            .workspace_manager
+            .get_workspace_mut(workspace_id)
+            .is_some_and(|workspace| {
+                workspace
+                    .layout
+                    .rotate_container(focused_window_id, direction == RotateDirection::Clockwise)
+            });
+        if !rotated {
+            tracing::info!("Nothing to rotate around window {}", focused_window_id);
+            return;
+        }
+
+        self.apply_workspace_layout(workspace_id);
+        self.event_bus
+            .emit_workspace(crate::event::WorkspaceEvent::LayoutChanged {
+                workspace: workspace_id,
+                timestamp: std::time::Instant::now(),
+            });
+    }
+
+    fn handle_balance_splits(&mut self, scope: BalanceScope) {
+        let Some(focused_element) = self.focused_window() else {
+            tracing::warn!("No focused window for balance command");
+            return;
+        };
+
+        let Some(focused_window_id) = self.window_registry().find_by_element(&focused_element)
+        else {
+            tracing::warn!("Focused element not found in window registry");
+            return;
+        };
+
+        let Some(workspace_id) = self
+            .workspace_manager
+            .find_window_workspace(focused_window_id)
+        else {
+            tracing::warn!("Window not found in any workspace");
+            return;
+        };
+
+        tracing::debug!(
+            "Balancing {:?} of window {} on workspace {}",
+            scope,
+            focused_window_id,
+            workspace_id
+        );
+        let window = match scope {
+            BalanceScope::Workspace => None,
+            BalanceScope::Container => Some(focused_window_id),
+        };
+        if let Some(workspace) = self.workspace_manager.get_mut(workspace_id) {
+            workspace.layout.balance(window);
+        }
+        self.apply_workspace_layout(workspace_id);
+        self.event_bus
+            .emit_workspace(crate::event::WorkspaceEvent::LayoutChanged {
+                workspace: workspace_id,
+                timestamp: std::time::Instant::now(),
+            });
+    }
+
+    fn handle_focus_mode(&mut self, mode: FocusMode) {
+        let focused = self
+            .focused_window()
+            .and_then(|element| self.window_registry().find_by_element(&element));
+
+        // The focused window's workspace, or the one under the pointer
+        let Some(workspace_id) = focused
+            .and_then(|window_id| self.workspace_manager.find_window_workspace(window_id))
+            .or_else(|| {
+                self.virtual_output_at_pointer()
+                    .and_then(|vo_id| self.workspace_manager.workspace_on_output(vo_id))
+            })
+        else {
+            tracing::warn!("No workspace for focus {:?}", mode);
+            return;
+        };
+        let Some(workspace) = self.workspace_manager.get(workspace_id) else {
+            return;
+        };
+
+        let floating = match mode {
+            FocusMode::Tiling => false,
+            FocusMode::Floating => true,
+            FocusMode::ModeToggle => !focused.is_some_and(|id| workspace.is_floating(id)),
+        };
+        let Some(target) = workspace.focus_mode_target(floating) else {
+            tracing::debug!(
+                "No {} window on workspace {}",
+                if floating { "floating" } else { "tiled" },
+                workspace_id
+            );
+            return;
+        };
+        if Some(target) == focused {
+            return;
+        }
+
+        let element = self
+            .window_registry()
+            .get(target)
+            .map(|mw| mw.element.clone());
+        if let Some(element) = element {
+            self.focus_window(&element);
+        }
+    }
+
+    fn handle_move_window_to_pointer_output(&mut self) {
+        let Some(window_id) = self
+            .focused_window()
+            .and_then(|element| self.window_registry().find_by_element(&element))
+        else {
+            tracing::warn!("No focused window to move");
+            return;
+        };
+        let Some(source_workspace) = self.window_registry().get(window_id).map(|mw| mw.workspace)
+        else {
+            return;
+        };
+        let Some(target_vo) = self.virtual_output_at_pointer() else {
+            tracing::warn!("Pointer is not over any virtual output");
+            return;
+        };
+
+        if self
+            .workspace_manager
+            .find_workspace_location(source_workspace)
+            == Some(target_vo)
+        {
+            tracing::debug!(
+                "Window {} is already on virtual output {}",
+                window_id,
+                target_vo
+            );
+            return;
+        }
+        let Some(target_workspace) = self.workspace_manager.workspace_on_output(target_vo) else {
+            tracing::warn!("No workspace shown on virtual output {}", target_vo);
+            return;
+        };
+
+        if self.selected_container().is_some() {
+            self.move_selected_container_to_workspace(target_workspace);
+        } else {
+            self.move_window_to_workspace_by_id(window_id, target_workspace);
+        }
+
+        // The window went where the pointer is, so focus goes with it
+        let element = self
+            .window_registry()
+            .get(window_id)
+            .map(|mw| mw.element.clone());
+        if let Some(element) = element {
+            self.focus_window(&element);
+        }
+    }
+
+    /// Swap in the keybindings re-read from the config file, keeping the
+    /// current ones if the file can't be read or a binding doesn't parse
+    fn reload_bindings(&mut self) {
+        let Some(path) = self.config.path.clone() else {
+            warn!("Not reloading keybindings: the config wasn't loaded from a file");
+            return;
+        };
+        match Config::load_bindings_from_file(&path) {
+            Ok(bindings) => {
+                info!(
+                    "Reloaded {} keybindings and {} keycode bindings from {:?}",
+                    bindings.keybindings.len(),
+                    bindings
+                        .keycode_bindings
+                        .values()
+                        .map(Vec::len)
+                        .sum::<usize>(),
+                    path
+                );
+                self.config.set_bindings(bindings);
+                self.reset_focus_follows_mouse_override();
+            }
+            Err(err) => warn!(
+                "Keeping the current keybindings, failed to reload {:?}: {}",
+                path, err
+            ),
+        }
+    }
 }
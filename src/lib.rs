@@ -32,12 +32,14 @@
     allow(dead_code, unused_imports)
 )]
 
+pub mod animation;
 pub mod backend;
 pub mod command;
 pub mod config;
 #[cfg(any(feature = "udev", feature = "xwayland"))]
 pub mod cursor;
 pub mod cursor_manager;
+pub mod debug_overlay;
 pub mod drawing;
 pub mod error;
 pub mod event;
@@ -46,10 +48,13 @@ pub mod handlers;
 pub mod input;
 pub mod ipc;
 pub mod keybindings;
+pub mod overview;
 pub mod physical_layout;
 pub mod render;
+pub mod selection_outline;
 pub mod shell;
 pub mod state;
+pub mod status_bar;
 pub mod tab_bar;
 pub mod test_ipc;
 pub mod test_ipc_server;
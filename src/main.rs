@@ -11,6 +11,10 @@
 static POSSIBLE_BACKENDS: &[&str] = &[
     #[cfg(feature = "winit")]
     "--winit : Run stilch as a X11 or Wayland client using winit.",
+    #[cfg(feature = "winit")]
+    "          --nested-mod MOD : Use MOD (e.g. Alt) for Super bindings, leaving Super to the host",
+    #[cfg(feature = "winit")]
+    "                             (or set STILCH_NESTED_MOD)",
     #[cfg(feature = "udev")]
     "--tty-udev : Run stilch as a tty udev client (requires root if without logind).",
     "             --enable-test-ipc : Enable test IPC server for debugging (with udev backend).",
@@ -20,6 +24,7 @@ static POSSIBLE_BACKENDS: &[&str] = &[
     "         Options: --ascii-size WIDTHxHEIGHT (default: 160x90)",
     "                  --logical-size WIDTHxHEIGHT (default: 3840x2160)",
     "                  --ascii-output WIDTHxHEIGHT+X+Y (add additional outputs)",
+    "                  --xwayland (start XWayland for X11 clients)",
 ];
 
 #[cfg(feature = "profile-with-tracy-mem")]
@@ -55,7 +60,24 @@ fn main() {
         #[cfg(feature = "winit")]
         Some("--winit") => {
             tracing::info!("Starting stilch with winit backend");
-            if let Err(e) = stilch::winit::run_winit() {
+            let args: Vec<String> = ::std::env::args().collect();
+            let nested_mod = args
+                .iter()
+                .position(|arg| arg == "--nested-mod")
+                .and_then(|i| args.get(i + 1).cloned())
+                .or_else(|| std::env::var("STILCH_NESTED_MOD").ok());
+            let nested_modifier = match nested_mod
+                .as_deref()
+                .map(stilch::winit::NestedModifier::parse)
+            {
+                Some(Ok(nested)) => Some(nested),
+                Some(Err(e)) => {
+                    tracing::error!("Invalid nested modifier: {e}");
+                    std::process::exit(1);
+                }
+                None => None,
+            };
+            if let Err(e) = stilch::winit::run_winit(nested_modifier) {
                 tracing::error!("Failed to run Winit backend: {e}");
                 std::process::exit(1);
             }
@@ -131,6 +153,9 @@ fn main() {
                         }
                     }
                     i += 2;
+                } else if args[i] == "--xwayland" {
+                    config.xwayland = true;
+                    i += 1;
                 } else if args[i] == "--config" && i + 1 < args.len() {
                     config_file = Some(args[i + 1].clone());
                     tracing::info!("Using config file: {}", args[i + 1]);
@@ -0,0 +1,253 @@
+//! Window overview (expose)
+//!
+//! Shows a thumbnail of every window on the workspaces of one virtual output,
+//! laid out in a grid covering that output. Arrow keys or the pointer pick a
+//! window; Enter or a click switches to its workspace and focuses it, while
+//! Escape leaves focus where it was.
+
+use smithay::{
+    backend::renderer::element::solid::SolidColorBuffer,
+    output::Output,
+    utils::{Logical, Point, Rectangle},
+};
+use tracing::debug;
+
+use crate::{
+    config::{Direction, Toggle},
+    shell::WindowElement,
+    state::{Backend, StilchState},
+    window::WindowId,
+};
+
+/// Space around and between thumbnails
+const PADDING: i32 = 20;
+/// How far the selection highlight reaches past its thumbnail slot
+const HIGHLIGHT_WIDTH: i32 = 4;
+const BACKDROP_COLOR: [f32; 4] = [0.08, 0.08, 0.1, 1.0];
+const HIGHLIGHT_COLOR: [f32; 4] = [0.29, 0.47, 0.85, 1.0];
+
+/// An open overview
+#[derive(Debug)]
+pub struct Overview {
+    region: Rectangle<i32, Logical>,
+    /// Windows and their thumbnail slots, in global coordinates
+    thumbnails: Vec<(WindowId, Rectangle<i32, Logical>)>,
+    columns: usize,
+    selected: usize,
+    backdrop: SolidColorBuffer,
+    highlight: SolidColorBuffer,
+}
+
+impl Overview {
+    fn new(region: Rectangle<i32, Logical>, windows: Vec<WindowId>, selected: usize) -> Self {
+        let (columns, slots) = grid(region, windows.len());
+        let mut overview = Self {
+            region,
+            thumbnails: windows.into_iter().zip(slots).collect(),
+            columns,
+            selected,
+            backdrop: SolidColorBuffer::new(region.size, BACKDROP_COLOR),
+            highlight: SolidColorBuffer::default(),
+        };
+        overview.update_highlight();
+        overview
+    }
+
+    /// Currently selected window
+    pub fn selected(&self) -> Option<WindowId> {
+        self.thumbnails.get(self.selected).map(|(id, _)| *id)
+    }
+
+    /// Index of the thumbnail at a global location
+    fn thumbnail_at(&self, location: Point<i32, Logical>) -> Option<usize> {
+        self.thumbnails
+            .iter()
+            .position(|(_, slot)| slot.contains(location))
+    }
+
+    /// Move the selection one step through the grid
+    fn navigate(&mut self, direction: Direction) {
+        let last = self.thumbnails.len().saturating_sub(1);
+        self.selected = match direction {
+            Direction::Left => self.selected.saturating_sub(1),
+            Direction::Right => (self.selected + 1).min(last),
+            Direction::Up if self.selected >= self.columns => self.selected - self.columns,
+            Direction::Down if self.selected + self.columns <= last => self.selected + self.columns,
+            Direction::Up | Direction::Down => self.selected,
+        };
+        self.update_highlight();
+    }
+
+    fn update_highlight(&mut self) {
+        if let Some((_, slot)) = self.thumbnails.get(self.selected) {
+            let size = (
+                slot.size.w + HIGHLIGHT_WIDTH * 2,
+                slot.size.h + HIGHLIGHT_WIDTH * 2,
+            );
+            self.highlight.update(size, HIGHLIGHT_COLOR);
+        }
+    }
+}
+
+/// Lay `count` equally sized slots out in a grid filling `region`,
+/// returning the number of columns and the slots in row-major order
+fn grid(region: Rectangle<i32, Logical>, count: usize) -> (usize, Vec<Rectangle<i32, Logical>>) {
+    if count == 0 {
+        return (1, Vec::new());
+    }
+    let columns = (count as f64).sqrt().ceil() as usize;
+    let rows = count.div_ceil(columns);
+
+    let slot_w = ((region.size.w - PADDING * (columns as i32 + 1)) / columns as i32).max(1);
+    let slot_h = ((region.size.h - PADDING * (rows as i32 + 1)) / rows as i32).max(1);
+
+    let slots = (0..count)
+        .map(|index| {
+            let column = (index % columns) as i32;
+            let row = (index / columns) as i32;
+            Rectangle::new(
+                (
+                    region.loc.x + PADDING + (slot_w + PADDING) * column,
+                    region.loc.y + PADDING + (slot_h + PADDING) * row,
+                )
+                    .into(),
+                (slot_w, slot_h).into(),
+            )
+        })
+        .collect();
+    (columns, slots)
+}
+
+/// What one physical output draws of the overview, in output-relative coordinates
+#[derive(Debug, Clone)]
+pub struct OverviewFrame {
+    pub backdrop: (SolidColorBuffer, Point<i32, Logical>),
+    pub highlight: Option<(SolidColorBuffer, Point<i32, Logical>)>,
+    pub thumbnails: Vec<(WindowElement, Rectangle<i32, Logical>)>,
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Open, close or toggle the overview on the virtual output under the pointer
+    pub fn set_overview(&mut self, toggle: Toggle) {
+        let open = match toggle {
+            Toggle::Enable => true,
+            Toggle::Disable => false,
+            Toggle::Toggle => self.overview.is_none(),
+        };
+        if !open {
+            if self.overview.take().is_some() {
+                self.backend_data.request_render();
+            }
+            return;
+        }
+        if self.overview.is_some() {
+            return;
+        }
+
+        let Some(vo_id) = self.virtual_output_at_pointer() else {
+            return;
+        };
+        let Some(region) = self
+            .virtual_output_manager
+            .get(vo_id)
+            .map(|vo| vo.logical_region())
+        else {
+            return;
+        };
+
+        let mut workspaces: Vec<_> = self
+            .workspace_manager
+            .workspaces()
+            .iter()
+            .filter(|ws| self.workspace_manager.workspace_association(ws.id) == Some(vo_id))
+            .collect();
+        workspaces.sort_by_key(|ws| ws.id.get());
+
+        let mut windows: Vec<WindowId> = Vec::new();
+        for workspace in workspaces {
+            for &id in workspace.windows.iter().chain(&workspace.floating_windows) {
+                if !windows.contains(&id) && self.window_registry().get(id).is_some() {
+                    windows.push(id);
+                }
+            }
+        }
+        if windows.is_empty() {
+            debug!("No windows to show in the overview");
+            return;
+        }
+
+        let focused = self
+            .focused_window()
+            .and_then(|window| self.window_registry().find_by_element(&window));
+        let selected = windows
+            .iter()
+            .position(|&id| Some(id) == focused)
+            .unwrap_or(0);
+        debug!("Opening overview with {} windows", windows.len());
+        self.overview = Some(Overview::new(region, windows, selected));
+        self.backend_data.request_render();
+    }
+
+    /// Move the overview selection
+    pub fn overview_navigate(&mut self, direction: Direction) {
+        if let Some(overview) = self.overview.as_mut() {
+            overview.navigate(direction);
+            self.backend_data.request_render();
+        }
+    }
+
+    /// Close the overview and focus the selected window on its workspace
+    pub fn overview_select(&mut self) {
+        let Some(overview) = self.overview.take() else {
+            return;
+        };
+        self.backend_data.request_render();
+        if let Some(window_id) = overview.selected() {
+            self.activate_window(window_id);
+        }
+    }
+
+    /// Handle a click while the overview is open: pick the window under the
+    /// pointer, or close the overview when clicking between thumbnails
+    pub fn overview_click(&mut self, location: Point<f64, Logical>) {
+        let Some(overview) = self.overview.as_mut() else {
+            return;
+        };
+        match overview.thumbnail_at(location.to_i32_floor()) {
+            Some(index) => {
+                overview.selected = index;
+                self.overview_select();
+            }
+            None => self.set_overview(Toggle::Disable),
+        }
+    }
+
+    /// The part of the overview shown on `output`, if any
+    pub fn overview_frame(&self, output: &Output) -> Option<OverviewFrame> {
+        let overview = self.overview.as_ref()?;
+        let output_geometry = self.space().output_geometry(output)?;
+        if !output_geometry.overlaps(overview.region) {
+            return None;
+        }
+        let offset = output_geometry.loc;
+
+        let thumbnails = overview
+            .thumbnails
+            .iter()
+            .filter_map(|(id, slot)| {
+                let element = self.window_registry().get(*id)?.element.clone();
+                Some((element, Rectangle::new(slot.loc - offset, slot.size)))
+            })
+            .collect();
+        let highlight = overview.thumbnails.get(overview.selected).map(|(_, slot)| {
+            let loc = slot.loc - offset - Point::from((HIGHLIGHT_WIDTH, HIGHLIGHT_WIDTH));
+            (overview.highlight.clone(), loc)
+        });
+
+        Some(OverviewFrame {
+            backdrop: (overview.backdrop.clone(), overview.region.loc - offset),
+            highlight,
+            thumbnails,
+        })
+    }
+}
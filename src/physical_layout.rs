@@ -133,6 +133,11 @@ impl PhysicalLayoutManager {
             .insert(display_info.name.clone(), display_info);
     }
 
+    /// The display named `name`
+    pub fn display(&self, name: &str) -> Option<&PhysicalDisplay> {
+        self.displays.get(name)
+    }
+
     /// Remove a display
     pub fn remove_display(&mut self, name: &str) {
         self.displays.remove(name);
@@ -2,28 +2,36 @@ use smithay::{
     backend::renderer::{
         damage::{Error as OutputDamageTrackerError, OutputDamageTracker, RenderOutputResult},
         element::{
+            memory::MemoryRenderBufferRenderElement,
+            solid::SolidColorRenderElement,
             surface::WaylandSurfaceRenderElement,
             utils::{
                 ConstrainAlign, ConstrainScaleBehavior, CropRenderElement, RelocateRenderElement,
                 RescaleRenderElement,
             },
-            AsRenderElements, RenderElement, Wrap,
+            AsRenderElements, Kind, RenderElement, Wrap,
         },
         Color32F, ImportAll, ImportMem, Renderer,
     },
     desktop::space::{
-        constrain_space_element, ConstrainBehavior, ConstrainReference, Space, SpaceRenderElements,
+        constrain_space_element, ConstrainBehavior, ConstrainReference, Space, SpaceElement,
+        SpaceRenderElements,
     },
     output::Output,
-    utils::{Logical, Point, Rectangle, Scale, Size},
+    utils::{Logical, Rectangle, Scale},
 };
 
 #[cfg(feature = "debug")]
 use crate::drawing::FpsElement;
 use crate::{
+    animation::ClosingFrame,
+    debug_overlay::DebugOverlayFrame,
     drawing::{PointerRenderElement, CLEAR_COLOR, CLEAR_COLOR_FULLSCREEN},
+    overview::OverviewFrame,
+    selection_outline::SelectionFrame,
     shell::{FullscreenSurface, WindowElement, WindowRenderElement},
     state::{Backend, StilchState},
+    status_bar::StatusBarFrame,
 };
 
 smithay::backend::renderer::element::render_elements! {
@@ -32,6 +40,7 @@ smithay::backend::renderer::element::render_elements! {
     Pointer=PointerRenderElement<R>,
     Surface=WaylandSurfaceRenderElement<R>,
     TabBar=smithay::backend::renderer::element::memory::MemoryRenderBufferRenderElement<R>,
+    Solid=SolidColorRenderElement,
     #[cfg(feature = "debug")]
     // Note: We would like to borrow this element instead, but that would introduce
     // a feature-dependent lifetime, which introduces a lot more feature bounds
@@ -46,6 +55,7 @@ impl<R: Renderer> std::fmt::Debug for CustomRenderElements<R> {
             Self::Pointer(arg0) => f.debug_tuple("Pointer").field(arg0).finish(),
             Self::Surface(arg0) => f.debug_tuple("Surface").field(arg0).finish(),
             Self::TabBar(arg0) => f.debug_tuple("TabBar").field(arg0).finish(),
+            Self::Solid(arg0) => f.debug_tuple("Solid").field(arg0).finish(),
             #[cfg(feature = "debug")]
             Self::Fps(arg0) => f.debug_tuple("Fps").field(arg0).finish(),
             Self::_GenericCatcher(arg0) => f.debug_tuple("_GenericCatcher").field(arg0).finish(),
@@ -59,6 +69,7 @@ smithay::backend::renderer::element::render_elements! {
     Window=Wrap<E>,
     Custom=CustomRenderElements<R>,
     Preview=CropRenderElement<RelocateRenderElement<RescaleRenderElement<WindowRenderElement<R>>>>,
+    Solid=SolidColorRenderElement,
 }
 
 impl<R: Renderer + ImportAll + ImportMem, E: RenderElement<R> + std::fmt::Debug> std::fmt::Debug
@@ -70,73 +81,91 @@ impl<R: Renderer + ImportAll + ImportMem, E: RenderElement<R> + std::fmt::Debug>
             Self::Window(arg0) => f.debug_tuple("Window").field(arg0).finish(),
             Self::Custom(arg0) => f.debug_tuple("Custom").field(arg0).finish(),
             Self::Preview(arg0) => f.debug_tuple("Preview").field(arg0).finish(),
+            Self::Solid(arg0) => f.debug_tuple("Solid").field(arg0).finish(),
             Self::_GenericCatcher(arg0) => f.debug_tuple("_GenericCatcher").field(arg0).finish(),
         }
     }
 }
 
-pub fn space_preview_elements<'a, R, C>(
-    renderer: &'a mut R,
-    space: &'a Space<WindowElement>,
-    output: &'a Output,
-) -> impl Iterator<Item = C> + 'a
+/// Overview thumbnails scaled into their slots, over the highlight and backdrop
+pub fn overview_elements<R>(
+    renderer: &mut R,
+    frame: &OverviewFrame,
+    output: &Output,
+) -> Vec<OutputRenderElements<R, WindowRenderElement<R>>>
 where
     R: Renderer + ImportAll + ImportMem,
     R::TextureId: Clone + 'static,
-    C: From<CropRenderElement<RelocateRenderElement<RescaleRenderElement<WindowRenderElement<R>>>>>
-        + 'a,
 {
     let constrain_behavior = ConstrainBehavior {
         reference: ConstrainReference::BoundingBox,
         behavior: ConstrainScaleBehavior::Fit,
         align: ConstrainAlign::CENTER,
     };
-
-    let preview_padding = 10;
-
-    let elements_on_space = space.elements_for_output(output).count();
     let output_scale = output.current_scale().fractional_scale();
-    let output_transform = output.current_transform();
-    let output_size = output
-        .current_mode()
-        .map(|mode| {
-            output_transform
-                .transform_size(mode.size)
-                .to_f64()
-                .to_logical(output_scale)
-        })
-        .unwrap_or_default();
-
-    let max_elements_per_row = 4;
-    let elements_per_row = usize::min(elements_on_space, max_elements_per_row);
-    let rows = f64::ceil(elements_on_space as f64 / elements_per_row as f64);
+    let scale = Scale::from(output_scale);
 
-    let preview_size = Size::from((
-        f64::round(output_size.w / elements_per_row as f64) as i32 - preview_padding * 2,
-        f64::round(output_size.h / rows) as i32 - preview_padding * 2,
-    ));
+    let mut elements: Vec<OutputRenderElements<R, WindowRenderElement<R>>> = Vec::new();
+    for (window, slot) in &frame.thumbnails {
+        elements.extend(constrain_space_element(
+            renderer,
+            window,
+            slot.loc,
+            1.0,
+            output_scale,
+            *slot,
+            constrain_behavior,
+        ));
+    }
+    let solids = frame.highlight.iter().chain([&frame.backdrop]);
+    for (buffer, location) in solids {
+        elements.push(OutputRenderElements::Solid(
+            SolidColorRenderElement::from_buffer(
+                buffer,
+                location.to_physical_precise_round(scale),
+                scale,
+                1.0,
+                Kind::Unspecified,
+            ),
+        ));
+    }
+    elements
+}
 
-    space
+/// Render elements of the override-redirect windows on `output`, topmost first
+fn override_redirect_elements<R>(
+    renderer: &mut R,
+    space: &Space<WindowElement>,
+    output: &Output,
+    scale: Scale<f64>,
+) -> Vec<WindowRenderElement<R>>
+where
+    R: Renderer + ImportAll + ImportMem,
+    R::TextureId: Clone + Send + 'static,
+{
+    let Some(output_geo) = space.output_geometry(output) else {
+        return Vec::new();
+    };
+    let windows: Vec<_> = space
         .elements_for_output(output)
-        .enumerate()
-        .flat_map(move |(element_index, window)| {
-            let column = element_index % elements_per_row;
-            let row = element_index / elements_per_row;
-            let preview_location = Point::from((
-                preview_padding + (preview_padding + preview_size.w) * column as i32,
-                preview_padding + (preview_padding + preview_size.h) * row as i32,
-            ));
-            let constrain = Rectangle::new(preview_location, preview_size);
-            constrain_space_element(
-                renderer,
+        .filter(|window| window.is_override_redirect())
+        .collect();
+    windows
+        .into_iter()
+        .rev()
+        .flat_map(|window| {
+            let location = space.element_location(window).unwrap_or_default()
+                - output_geo.loc
+                - window.geometry().loc;
+            AsRenderElements::<R>::render_elements(
                 window,
-                preview_location,
+                renderer,
+                location.to_physical_precise_round(scale),
+                scale,
                 1.0,
-                output_scale,
-                constrain,
-                constrain_behavior,
             )
         })
+        .collect()
 }
 
 #[profiling::function]
@@ -145,7 +174,7 @@ pub fn output_elements<R>(
     space: &Space<WindowElement>,
     custom_elements: impl IntoIterator<Item = CustomRenderElements<R>>,
     renderer: &mut R,
-    show_window_preview: bool,
+    overview: Option<&OverviewFrame>,
     tab_bar_data: &[TabBarData],
     text_cache: &mut crate::tab_bar::TabTextCache,
 ) -> (
@@ -165,9 +194,20 @@ where
         let window_render_elements: Vec<WindowRenderElement<R>> =
             AsRenderElements::<R>::render_elements(&window, renderer, (0, 0).into(), scale, 1.0);
 
+        let overview_render_elements = overview
+            .map(|frame| overview_elements(renderer, frame, output))
+            .unwrap_or_default();
+        // Menus and tooltips the fullscreen window opens still go on top of it
+        let override_redirect_elements = override_redirect_elements(renderer, space, output, scale);
         let elements = custom_elements
             .into_iter()
             .map(OutputRenderElements::from)
+            .chain(overview_render_elements)
+            .chain(
+                override_redirect_elements
+                    .into_iter()
+                    .map(|e| OutputRenderElements::Window(Wrap::from(e))),
+            )
             .chain(
                 window_render_elements
                     .into_iter()
@@ -181,15 +221,16 @@ where
             .map(OutputRenderElements::from)
             .collect::<Vec<_>>();
 
+        // The overview covers the workspace it was opened on
+        if let Some(frame) = overview {
+            output_render_elements.extend(overview_elements(renderer, frame, output));
+        }
+
         // Add tab bar elements
         let scale = Scale::from(output.current_scale().fractional_scale());
         let tab_elements = generate_tab_bar_elements(renderer, tab_bar_data, scale, text_cache);
         output_render_elements.extend(tab_elements.into_iter().map(OutputRenderElements::from));
 
-        if show_window_preview && space.elements_for_output(output).count() > 0 {
-            output_render_elements.extend(space_preview_elements(renderer, space, output));
-        }
-
         let space_elements = smithay::desktop::space::space_render_elements::<_, WindowElement, _>(
             renderer,
             [space],
@@ -209,6 +250,8 @@ pub struct TabBarData {
     pub tabs: Vec<crate::tab_bar::TabInfo>,
     pub geometry: Rectangle<i32, Logical>,
     pub is_stacked: bool,
+    /// Bar height and colors
+    pub config: crate::config::TabBarConfig,
 }
 
 /// Collect tab bar data for rendering
@@ -260,6 +303,12 @@ where
             // Get the layout tree
             let layout = &workspace.layout;
 
+            // Bars are drawn as high as the layout left room for on this output
+            let tab_bar_config = crate::config::TabBarConfig {
+                size: layout.tab_bar(),
+                ..state.config.tab_bar
+            };
+
             // Find all tabbed containers and their windows
             let tabbed_containers = layout.find_tabbed_containers();
 
@@ -268,19 +317,7 @@ where
                 let tab_infos: Vec<_> = tabs
                     .iter()
                     .map(|(window_id, is_active)| {
-                        // Get window element to get title
-                        let title = if let Some(managed) = state.window_registry().get(*window_id) {
-                            managed.element.title()
-                        } else {
-                            format!("Window {window_id}")
-                        };
-
-                        crate::tab_bar::TabInfo {
-                            window_id: *window_id,
-                            title,
-                            app_id: None,
-                            is_active: *is_active,
-                        }
+                        tab_info(state, *window_id, *is_active, &workspace.name)
                     })
                     .collect();
 
@@ -288,6 +325,7 @@ where
                     tabs: tab_infos,
                     geometry: container_geometry,
                     is_stacked: false,
+                    config: tab_bar_config,
                 });
             }
 
@@ -299,19 +337,7 @@ where
                 let tab_infos: Vec<_> = tabs
                     .iter()
                     .map(|(window_id, is_active)| {
-                        // Get window element to get title
-                        let title = if let Some(managed) = state.window_registry().get(*window_id) {
-                            managed.element.title()
-                        } else {
-                            format!("Window {window_id}")
-                        };
-
-                        crate::tab_bar::TabInfo {
-                            window_id: *window_id,
-                            title,
-                            app_id: None,
-                            is_active: *is_active,
-                        }
+                        tab_info(state, *window_id, *is_active, &workspace.name)
                     })
                     .collect();
 
@@ -319,6 +345,7 @@ where
                     tabs: tab_infos,
                     geometry: container_geometry,
                     is_stacked: true,
+                    config: tab_bar_config,
                 });
             }
         }
@@ -327,6 +354,35 @@ where
     tab_bars
 }
 
+/// Tab text for a window, formatted with `title_format`
+fn tab_info<B: Backend>(
+    state: &StilchState<B>,
+    window_id: crate::window::WindowId,
+    is_active: bool,
+    workspace: &str,
+) -> crate::tab_bar::TabInfo {
+    let (title, app_id) = match state.window_registry().get(window_id) {
+        Some(managed) => {
+            let criteria = managed.criteria();
+            let title = state.config.format_title(
+                &criteria.title,
+                criteria.app_id.as_deref(),
+                criteria.class.as_deref(),
+                workspace,
+            );
+            (title, criteria.app().map(str::to_string))
+        }
+        None => (format!("Window {window_id}"), None),
+    };
+
+    crate::tab_bar::TabInfo {
+        window_id,
+        title,
+        app_id,
+        is_active,
+    }
+}
+
 /// Generate tab bar elements from collected data
 pub fn generate_tab_bar_elements<R>(
     renderer: &mut R,
@@ -346,6 +402,7 @@ where
                 renderer,
                 data.tabs.clone(),
                 data.geometry,
+                &data.config,
                 scale,
                 text_cache,
             )
@@ -354,6 +411,7 @@ where
                 renderer,
                 data.tabs.clone(),
                 data.geometry,
+                &data.config,
                 scale,
                 text_cache,
             )
@@ -367,6 +425,113 @@ where
     elements
 }
 
+/// The debug overlay as a custom element, drawn above everything but the pointer
+pub fn debug_overlay_element<R>(
+    renderer: &mut R,
+    frame: &DebugOverlayFrame,
+    scale: Scale<f64>,
+) -> Option<CustomRenderElements<R>>
+where
+    R: Renderer + ImportAll + ImportMem,
+    R::TextureId: Clone + Send + 'static,
+{
+    let location = frame.location.to_f64().to_physical(scale);
+    MemoryRenderBufferRenderElement::from_buffer(
+        renderer,
+        location,
+        &frame.buffer,
+        None,
+        None,
+        None,
+        Kind::Unspecified,
+    )
+    .ok()
+    // Memory buffer elements all share the TabBar variant
+    .map(CustomRenderElements::TabBar)
+}
+
+/// The outline of the selected container as custom elements, drawn above
+/// windows
+pub fn selection_elements<R>(
+    frame: &SelectionFrame,
+    scale: Scale<f64>,
+) -> Vec<CustomRenderElements<R>>
+where
+    R: Renderer + ImportAll + ImportMem,
+    R::TextureId: Clone + Send + 'static,
+{
+    frame
+        .edges
+        .iter()
+        .map(|(buffer, location)| {
+            CustomRenderElements::Solid(SolidColorRenderElement::from_buffer(
+                buffer,
+                location.to_physical_precise_round(scale),
+                scale,
+                1.0,
+                Kind::Unspecified,
+            ))
+        })
+        .collect()
+}
+
+/// Snapshots of closing windows as custom elements, drawn above the windows
+/// that stay
+pub fn closing_window_elements<R>(
+    renderer: &mut R,
+    frames: &[ClosingFrame],
+    scale: Scale<f64>,
+) -> Vec<CustomRenderElements<R>>
+where
+    R: Renderer + ImportAll + ImportMem,
+    R::TextureId: Clone + Send + 'static,
+{
+    frames
+        .iter()
+        .filter_map(|frame| {
+            MemoryRenderBufferRenderElement::from_buffer(
+                renderer,
+                frame.location.to_f64().to_physical(scale),
+                &frame.buffer,
+                Some(frame.alpha),
+                None,
+                Some(frame.size),
+                Kind::Unspecified,
+            )
+            .ok()
+        })
+        .map(CustomRenderElements::TabBar)
+        .collect()
+}
+
+/// Status bars as custom elements, drawn above windows
+pub fn status_bar_elements<R>(
+    renderer: &mut R,
+    frames: &[StatusBarFrame],
+    scale: Scale<f64>,
+) -> Vec<CustomRenderElements<R>>
+where
+    R: Renderer + ImportAll + ImportMem,
+    R::TextureId: Clone + Send + 'static,
+{
+    frames
+        .iter()
+        .filter_map(|frame| {
+            MemoryRenderBufferRenderElement::from_buffer(
+                renderer,
+                frame.location.to_f64().to_physical(scale),
+                &frame.buffer,
+                None,
+                None,
+                Some(frame.size),
+                Kind::Unspecified,
+            )
+            .ok()
+        })
+        .map(CustomRenderElements::TabBar)
+        .collect()
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn render_output<'a, 'd, R>(
     output: &'a Output,
@@ -376,7 +541,7 @@ pub fn render_output<'a, 'd, R>(
     framebuffer: &'a mut R::Framebuffer<'_>,
     damage_tracker: &'d mut OutputDamageTracker,
     age: usize,
-    show_window_preview: bool,
+    overview: Option<&OverviewFrame>,
     tab_bar_data: &[TabBarData],
     text_cache: &mut crate::tab_bar::TabTextCache,
 ) -> Result<RenderOutputResult<'d>, OutputDamageTrackerError<R::Error>>
@@ -389,7 +554,7 @@ where
         space,
         custom_elements,
         renderer,
-        show_window_preview,
+        overview,
         tab_bar_data,
         text_cache,
     );
@@ -0,0 +1,91 @@
+//! Outline around the container selected with `focus parent`
+//!
+//! While a container is selected, a thin frame in the tab bar's indicator
+//! color is drawn just inside its edges, over the windows in it, so it is
+//! clear what layout and move commands will act on. The edges are solid
+//! color buffers kept across frames, so an unchanged selection damages
+//! nothing.
+
+use smithay::{
+    backend::renderer::element::solid::SolidColorBuffer,
+    output::Output,
+    utils::{Logical, Point, Rectangle, Size},
+};
+
+use crate::state::{Backend, StilchState};
+
+/// Thickness of the outline
+const WIDTH: i32 = 2;
+
+/// The four edges of the outline
+#[derive(Debug, Default)]
+pub struct SelectionOutline {
+    edges: [SolidColorBuffer; 4],
+}
+
+/// The outline on one output, with the edges relative to it
+pub struct SelectionFrame {
+    pub edges: Vec<(SolidColorBuffer, Point<i32, Logical>)>,
+}
+
+impl SelectionOutline {
+    /// Fit the edges just inside `area`
+    fn edges_around(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        color: [f32; 4],
+    ) -> Vec<(SolidColorBuffer, Point<i32, Logical>)> {
+        let width = WIDTH.min(area.size.w / 2).min(area.size.h / 2);
+        let (x, y) = (area.loc.x, area.loc.y);
+        let (w, h) = (area.size.w, area.size.h);
+        let side = h - 2 * width;
+        let rects = [
+            ((x, y), (w, width)),
+            ((x, y + h - width), (w, width)),
+            ((x, y + width), (width, side)),
+            ((x + w - width, y + width), (width, side)),
+        ];
+        self.edges
+            .iter_mut()
+            .zip(rects)
+            .map(|(buffer, (loc, size))| {
+                buffer.update(Size::from(size), color);
+                (buffer.clone(), Point::from(loc))
+            })
+            .collect()
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// The outline of the selected container, if it shows on `output`
+    pub fn selection_frame(&mut self, output: &Output) -> Option<SelectionFrame> {
+        if self.overview.is_some() {
+            return None;
+        }
+        let (workspace_id, container) = self.selected_container()?;
+        let vo_id = self
+            .workspace_manager
+            .find_workspace_location(workspace_id)?;
+        let shown = self
+            .virtual_output_manager
+            .get(vo_id)?
+            .active_workspace_id()
+            == Some(workspace_id);
+        let workspace = self.workspace_manager.get_workspace(workspace_id)?;
+        if !shown || workspace.fullscreen_window.is_some() {
+            return None;
+        }
+        let area = workspace.layout.container_geometry(container)?;
+        let output_geometry = self.space().output_geometry(output)?;
+        if !output_geometry.overlaps(area) {
+            return None;
+        }
+
+        let color = self.config.tab_bar.colors.indicator;
+        let edges = self.selection_outline.edges_around(
+            Rectangle::new(area.loc - output_geometry.loc, area.size),
+            color,
+        );
+        Some(SelectionFrame { edges })
+    }
+}
@@ -1,9 +1,10 @@
-use std::{borrow::Cow, time::Duration};
+use std::{borrow::Cow, cell::Cell, time::Duration};
 
 use smithay::{
     backend::renderer::{
         element::{
-            solid::SolidColorRenderElement, surface::WaylandSurfaceRenderElement, AsRenderElements,
+            solid::SolidColorRenderElement, surface::WaylandSurfaceRenderElement,
+            utils::CropRenderElement, AsRenderElements,
         },
         ImportAll, ImportMem, Renderer, Texture,
     },
@@ -34,7 +35,7 @@ use smithay::{
 };
 
 use super::ssd::HEADER_BAR_HEIGHT;
-use crate::{focus::PointerFocusTarget, state::Backend, StilchState};
+use crate::{animation::WindowTransform, focus::PointerFocusTarget, state::Backend, StilchState};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct WindowElement(pub Window);
@@ -62,7 +63,11 @@ impl WindowElement {
                     })
             })
         } else {
-            // Fallback for non-XDG windows
+            #[cfg(feature = "xwayland")]
+            if let Some(surface) = self.0.x11_surface() {
+                return surface.title();
+            }
+            // Fallback for other windows
             if let Some(id) = self.user_data().get::<usize>() {
                 format!("Window {id}")
             } else {
@@ -95,14 +100,21 @@ impl WindowElement {
         window_type: WindowSurfaceType,
     ) -> Option<(PointerFocusTarget, Point<i32, Logical>)> {
         let state = self.decoration_state();
+        let border = state.border.offset();
+        let location = location - border.to_f64();
+        if location.x < 0.0 || location.y < 0.0 {
+            // On the border
+            return None;
+        }
         if state.is_ssd && location.y < HEADER_BAR_HEIGHT as f64 {
-            return Some((PointerFocusTarget::SSD(SSD(self.clone())), Point::default()));
+            return Some((PointerFocusTarget::SSD(SSD(self.clone())), border));
         }
         let offset = if state.is_ssd {
             Point::from((0, HEADER_BAR_HEIGHT))
         } else {
             Point::default()
         };
+        drop(state);
 
         let surface_under = self
             .0
@@ -116,7 +128,7 @@ impl WindowElement {
                 surface_under.map(|(_, loc)| (PointerFocusTarget::X11Surface(s.clone()), loc))
             }
         }?;
-        Some((under, loc + offset))
+        Some((under, loc + offset + border))
     }
 
     pub fn with_surfaces<F>(&self, processor: F)
@@ -180,6 +192,16 @@ impl WindowElement {
         self.0.is_wayland()
     }
 
+    /// Whether this is an X11 menu, tooltip or the like that isn't managed
+    #[inline]
+    pub fn is_override_redirect(&self) -> bool {
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = self.0.x11_surface() {
+            return surface.is_override_redirect();
+        }
+        false
+    }
+
     #[inline]
     pub fn wl_surface(&self) -> Option<Cow<'_, WlSurface>> {
         self.0.wl_surface()
@@ -189,6 +211,40 @@ impl WindowElement {
     pub fn user_data(&self) -> &UserDataMap {
         self.0.user_data()
     }
+
+    /// Draw the window faded and scaled around its center, or normally with `None`
+    pub fn set_render_transform(&self, transform: Option<WindowTransform>) {
+        self.user_data()
+            .insert_if_missing(|| Cell::new(None::<WindowTransform>));
+        if let Some(cell) = self.user_data().get::<Cell<Option<WindowTransform>>>() {
+            cell.set(transform);
+        }
+    }
+
+    pub fn render_transform(&self) -> Option<WindowTransform> {
+        self.user_data()
+            .get::<Cell<Option<WindowTransform>>>()
+            .and_then(Cell::get)
+    }
+
+    /// Only draw the part of the window inside `clip`, given relative to
+    /// its location in the space, or all of it with `None`
+    pub fn set_render_clip(&self, clip: Option<Rectangle<i32, Logical>>) {
+        self.user_data()
+            .insert_if_missing(|| Cell::new(None::<Rectangle<i32, Logical>>));
+        if let Some(cell) = self
+            .user_data()
+            .get::<Cell<Option<Rectangle<i32, Logical>>>>()
+        {
+            cell.set(clip);
+        }
+    }
+
+    pub fn render_clip(&self) -> Option<Rectangle<i32, Logical>> {
+        self.user_data()
+            .get::<Cell<Option<Rectangle<i32, Logical>>>>()
+            .and_then(Cell::get)
+    }
 }
 
 impl IsAlive for WindowElement {
@@ -201,6 +257,13 @@ impl IsAlive for WindowElement {
 #[derive(Debug, Clone, PartialEq)]
 pub struct SSD(WindowElement);
 
+impl SSD {
+    /// Window the decoration belongs to
+    pub fn window(&self) -> &WindowElement {
+        &self.0
+    }
+}
+
 impl IsAlive for SSD {
     #[inline]
     fn alive(&self) -> bool {
@@ -415,27 +478,29 @@ impl<BackendData: Backend> TouchTarget<StilchState<BackendData>> for SSD {
 impl SpaceElement for WindowElement {
     fn geometry(&self) -> Rectangle<i32, Logical> {
         let mut geo = SpaceElement::geometry(&self.0);
-        if self.decoration_state().is_ssd {
-            geo.size.h += HEADER_BAR_HEIGHT;
-        }
+        geo.size += self.decoration_size();
         geo
     }
     fn bbox(&self) -> Rectangle<i32, Logical> {
         let mut bbox = SpaceElement::bbox(&self.0);
-        if self.decoration_state().is_ssd {
-            bbox.size.h += HEADER_BAR_HEIGHT;
-        }
+        bbox.size += self.decoration_size();
         bbox
     }
     fn is_in_input_region(&self, point: &Point<f64, Logical>) -> bool {
-        if self.decoration_state().is_ssd {
+        let state = self.decoration_state();
+        // The border takes no input, the header bar does
+        let point = *point - state.border.offset().to_f64();
+        if point.x < 0.0 || point.y < 0.0 {
+            return false;
+        }
+        if state.is_ssd {
             point.y < HEADER_BAR_HEIGHT as f64
                 || SpaceElement::is_in_input_region(
                     &self.0,
-                    &(*point - Point::from((0.0, HEADER_BAR_HEIGHT as f64))),
+                    &(point - Point::from((0.0, HEADER_BAR_HEIGHT as f64))),
                 )
         } else {
-            SpaceElement::is_in_input_region(&self.0, point)
+            SpaceElement::is_in_input_region(&self.0, &point)
         }
     }
     fn z_index(&self) -> u8 {
@@ -444,6 +509,7 @@ impl SpaceElement for WindowElement {
 
     fn set_activate(&self, activated: bool) {
         SpaceElement::set_activate(&self.0, activated);
+        self.decoration_state().border.active = activated;
     }
     fn output_enter(&self, output: &Output, overlap: Rectangle<i32, Logical>) {
         SpaceElement::output_enter(&self.0, output, overlap);
@@ -461,6 +527,8 @@ render_elements!(
     pub WindowRenderElement<R> where R: ImportAll + ImportMem;
     Window=WaylandSurfaceRenderElement<R>,
     Decoration=SolidColorRenderElement,
+    ClippedWindow=CropRenderElement<WaylandSurfaceRenderElement<R>>,
+    ClippedDecoration=CropRenderElement<SolidColorRenderElement>,
 );
 
 impl<R: Renderer> std::fmt::Debug for WindowRenderElement<R> {
@@ -468,6 +536,10 @@ impl<R: Renderer> std::fmt::Debug for WindowRenderElement<R> {
         match self {
             Self::Window(arg0) => f.debug_tuple("Window").field(arg0).finish(),
             Self::Decoration(arg0) => f.debug_tuple("Decoration").field(arg0).finish(),
+            Self::ClippedWindow(arg0) => f.debug_tuple("ClippedWindow").field(arg0).finish(),
+            Self::ClippedDecoration(arg0) => {
+                f.debug_tuple("ClippedDecoration").field(arg0).finish()
+            }
             Self::_GenericCatcher(arg0) => f.debug_tuple("_GenericCatcher").field(arg0).finish(),
         }
     }
@@ -484,36 +556,80 @@ where
         &self,
         renderer: &mut R,
         mut location: Point<i32, Physical>,
-        scale: Scale<f64>,
-        alpha: f32,
+        mut scale: Scale<f64>,
+        mut alpha: f32,
     ) -> Vec<C> {
         let window_bbox = SpaceElement::bbox(&self.0);
 
-        if self.decoration_state().is_ssd && !window_bbox.is_empty() {
-            let window_geo = SpaceElement::geometry(&self.0);
+        // `location` is where the bounding box goes, the clip is relative
+        // to where the window geometry does
+        let clip = self.render_clip().map(|clip| {
+            let origin = SpaceElement::geometry(&self.0).loc + clip.loc;
+            Rectangle::new(
+                location + origin.to_physical_precise_round(scale),
+                clip.size.to_physical_precise_round(scale),
+            )
+        });
+
+        if let Some(transform) = self.render_transform() {
+            let size = SpaceElement::geometry(&self.0)
+                .size
+                .to_f64()
+                .to_physical(scale);
+            let shrink = (1.0 - transform.scale) / 2.0;
+            location += Point::from(((size.w * shrink) as i32, (size.h * shrink) as i32));
+            scale = Scale::from((scale.x * transform.scale, scale.y * transform.scale));
+            alpha *= transform.alpha;
+        }
 
-            let mut state = self.decoration_state();
-            let width = window_geo.size.w;
-            state.header_bar.redraw(width as u32);
-            let mut vec = AsRenderElements::<R>::render_elements::<WindowRenderElement<R>>(
-                &state.header_bar,
-                renderer,
-                location,
-                scale,
-                alpha,
-            );
+        if window_bbox.is_empty() {
+            return Vec::new();
+        }
 
+        let content = self.decorated_content_size();
+        let mut state = self.decoration_state();
+        // Borders go under the surface, which they never overlap
+        let borders: Vec<WindowRenderElement<R>> = state
+            .border
+            .render_elements(content, location, scale, alpha)
+            .into_iter()
+            .map(WindowRenderElement::from)
+            .collect();
+        location += state.border.offset().to_physical_precise_round(scale);
+
+        let mut vec = Vec::new();
+        if state.is_ssd {
+            let width = SpaceElement::geometry(&self.0).size.w;
+            state.header_bar.redraw(width as u32);
+            vec.extend(AsRenderElements::<R>::render_elements::<
+                WindowRenderElement<R>,
+            >(
+                &state.header_bar, renderer, location, scale, alpha
+            ));
             location.y += (scale.y * HEADER_BAR_HEIGHT as f64) as i32;
-
-            let window_elements =
-                AsRenderElements::render_elements(&self.0, renderer, location, scale, alpha);
-            vec.extend(window_elements);
-            vec.into_iter().map(C::from).collect()
-        } else {
-            AsRenderElements::render_elements(&self.0, renderer, location, scale, alpha)
-                .into_iter()
-                .map(C::from)
-                .collect()
         }
+        drop(state);
+
+        vec.extend(AsRenderElements::render_elements::<WindowRenderElement<R>>(
+            &self.0, renderer, location, scale, alpha,
+        ));
+        vec.extend(borders);
+        let Some(clip) = clip else {
+            return vec.into_iter().map(C::from).collect();
+        };
+        vec.into_iter()
+            .filter_map(|element| match element {
+                WindowRenderElement::Window(element) => {
+                    CropRenderElement::from_element(element, scale, clip)
+                        .map(WindowRenderElement::ClippedWindow)
+                }
+                WindowRenderElement::Decoration(element) => {
+                    CropRenderElement::from_element(element, scale, clip)
+                        .map(WindowRenderElement::ClippedDecoration)
+                }
+                element => Some(element),
+            })
+            .map(C::from)
+            .collect()
     }
 }
@@ -1,5 +1,7 @@
 use std::cell::RefCell;
 
+#[cfg(feature = "xwayland")]
+use smithay::xwayland::xwm::ResizeEdge as X11ResizeEdge;
 use smithay::{
     desktop::{space::SpaceElement, WindowSurface},
     input::{
@@ -13,16 +15,16 @@ use smithay::{
         touch::{GrabStartData as TouchGrabStartData, TouchGrab},
     },
     reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
-    utils::{IsAlive, Logical, Point, Serial, Size},
+    utils::{IsAlive, Logical, Point, Rectangle, Serial, Size},
     wayland::{compositor::with_states, shell::xdg::SurfaceCachedState},
 };
-#[cfg(feature = "xwayland")]
-use smithay::{utils::Rectangle, xwayland::xwm::ResizeEdge as X11ResizeEdge};
 
 use super::{SurfaceData, WindowElement};
 use crate::{
     focus::PointerFocusTarget,
     state::{Backend, StilchState},
+    window::WindowId,
+    workspace::{layout::SplitBoundary, WorkspaceId},
 };
 
 pub struct PointerMoveSurfaceGrab<BackendData: Backend + 'static> {
@@ -171,6 +173,15 @@ impl<BackendData: Backend> PointerGrab<StilchState<BackendData>>
     }
 
     fn unset(&mut self, data: &mut StilchState<BackendData>) {
+        // Floating windows stay where they were dropped
+        if let (Some(window_id), Some(location)) = (
+            data.window_registry().find_by_element(&self.window),
+            data.space().element_location(&self.window),
+        ) {
+            let geometry = Rectangle::new(location, self.window.geometry().size);
+            data.set_floating_geometry(window_id, geometry);
+        }
+
         // Mark any moved windows for update
         data.window_manager.mark_moved_windows();
 
@@ -195,6 +206,309 @@ impl<BackendData: Backend> PointerGrab<StilchState<BackendData>>
     }
 }
 
+/// Drags the boundary between two children of a tiled split
+pub struct PointerSplitResizeGrab<BackendData: Backend + 'static> {
+    pub start_data: PointerGrabStartData<StilchState<BackendData>>,
+    pub workspace: WorkspaceId,
+    pub boundary: SplitBoundary,
+}
+
+impl<BackendData: Backend> PointerGrab<StilchState<BackendData>>
+    for PointerSplitResizeGrab<BackendData>
+{
+    fn motion(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        _focus: Option<(PointerFocusTarget, Point<f64, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus
+        handle.motion(data, None, event);
+
+        let delta = event.location - self.start_data.location;
+        let delta = if self.boundary.side_by_side {
+            delta.x
+        } else {
+            delta.y
+        };
+        let moved = data
+            .workspace_manager
+            .get_workspace_mut(self.workspace)
+            .is_some_and(|workspace| {
+                workspace
+                    .layout
+                    .drag_split_boundary(self.boundary, delta.round() as i32)
+            });
+        if moved {
+            data.apply_workspace_layout(self.workspace);
+        }
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        focus: Option<(PointerFocusTarget, Point<f64, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(data, focus, event);
+    }
+
+    fn button(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &ButtonEvent,
+    ) {
+        handle.button(data, event);
+        if handle.current_pressed().is_empty() {
+            // No more buttons are pressed, release the grab.
+            handle.unset_grab(self, data, event.serial, event.time, true);
+        }
+    }
+
+    fn axis(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        details: AxisFrame,
+    ) {
+        handle.axis(data, details)
+    }
+
+    fn frame(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+    ) {
+        handle.frame(data);
+    }
+
+    fn gesture_swipe_begin(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureSwipeBeginEvent,
+    ) {
+        handle.gesture_swipe_begin(data, event);
+    }
+
+    fn gesture_swipe_update(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureSwipeUpdateEvent,
+    ) {
+        handle.gesture_swipe_update(data, event);
+    }
+
+    fn gesture_swipe_end(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureSwipeEndEvent,
+    ) {
+        handle.gesture_swipe_end(data, event);
+    }
+
+    fn gesture_pinch_begin(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GesturePinchBeginEvent,
+    ) {
+        handle.gesture_pinch_begin(data, event);
+    }
+
+    fn gesture_pinch_update(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GesturePinchUpdateEvent,
+    ) {
+        handle.gesture_pinch_update(data, event);
+    }
+
+    fn gesture_pinch_end(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GesturePinchEndEvent,
+    ) {
+        handle.gesture_pinch_end(data, event);
+    }
+
+    fn gesture_hold_begin(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureHoldBeginEvent,
+    ) {
+        handle.gesture_hold_begin(data, event);
+    }
+
+    fn gesture_hold_end(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureHoldEndEvent,
+    ) {
+        handle.gesture_hold_end(data, event);
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData<StilchState<BackendData>> {
+        &self.start_data
+    }
+
+    fn unset(&mut self, data: &mut StilchState<BackendData>) {
+        // The dragged share is already in the layout's split weights, so
+        // relayouts keep it; only the cursor needs giving back
+        data.show_split_cursor(None);
+    }
+}
+
+/// Drags a tiled window; dropping it on another tiled window of its
+/// workspace makes the two trade places
+pub struct PointerTiledMoveGrab<BackendData: Backend + 'static> {
+    pub start_data: PointerGrabStartData<StilchState<BackendData>>,
+    pub window_id: WindowId,
+}
+
+impl<BackendData: Backend> PointerGrab<StilchState<BackendData>>
+    for PointerTiledMoveGrab<BackendData>
+{
+    fn motion(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        _focus: Option<(PointerFocusTarget, Point<f64, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus
+        handle.motion(data, None, event);
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        focus: Option<(PointerFocusTarget, Point<f64, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(data, focus, event);
+    }
+
+    fn button(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &ButtonEvent,
+    ) {
+        handle.button(data, event);
+        if handle.current_pressed().is_empty() {
+            data.drop_tiled_window(self.window_id, handle.current_location());
+            // No more buttons are pressed, release the grab.
+            handle.unset_grab(self, data, event.serial, event.time, true);
+        }
+    }
+
+    fn axis(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        details: AxisFrame,
+    ) {
+        handle.axis(data, details)
+    }
+
+    fn frame(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+    ) {
+        handle.frame(data);
+    }
+
+    fn gesture_swipe_begin(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureSwipeBeginEvent,
+    ) {
+        handle.gesture_swipe_begin(data, event);
+    }
+
+    fn gesture_swipe_update(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureSwipeUpdateEvent,
+    ) {
+        handle.gesture_swipe_update(data, event);
+    }
+
+    fn gesture_swipe_end(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureSwipeEndEvent,
+    ) {
+        handle.gesture_swipe_end(data, event);
+    }
+
+    fn gesture_pinch_begin(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GesturePinchBeginEvent,
+    ) {
+        handle.gesture_pinch_begin(data, event);
+    }
+
+    fn gesture_pinch_update(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GesturePinchUpdateEvent,
+    ) {
+        handle.gesture_pinch_update(data, event);
+    }
+
+    fn gesture_pinch_end(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GesturePinchEndEvent,
+    ) {
+        handle.gesture_pinch_end(data, event);
+    }
+
+    fn gesture_hold_begin(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureHoldBeginEvent,
+    ) {
+        handle.gesture_hold_begin(data, event);
+    }
+
+    fn gesture_hold_end(
+        &mut self,
+        data: &mut StilchState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, StilchState<BackendData>>,
+        event: &GestureHoldEndEvent,
+    ) {
+        handle.gesture_hold_end(data, event);
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData<StilchState<BackendData>> {
+        &self.start_data
+    }
+
+    fn unset(&mut self, _data: &mut StilchState<BackendData>) {}
+}
+
 pub struct TouchMoveSurfaceGrab<BackendData: Backend + 'static> {
     pub start_data: TouchGrabStartData<StilchState<BackendData>>,
     pub window: WindowElement,
@@ -300,6 +614,15 @@ impl<BackendData: Backend> TouchGrab<StilchState<BackendData>>
     }
 
     fn unset(&mut self, data: &mut StilchState<BackendData>) {
+        // Floating windows stay where they were dropped
+        if let (Some(window_id), Some(location)) = (
+            data.window_registry().find_by_element(&self.window),
+            data.space().element_location(&self.window),
+        ) {
+            let geometry = Rectangle::new(location, self.window.geometry().size);
+            data.set_floating_geometry(window_id, geometry);
+        }
+
         // Mark any moved windows for update
         data.window_manager.mark_moved_windows();
 
@@ -722,6 +1045,19 @@ impl<BackendData: Backend> PointerGrab<StilchState<BackendData>>
     }
 
     fn unset(&mut self, data: &mut StilchState<BackendData>) {
+        // Floating windows keep their new size, anchored at the edges opposite the drag
+        if let Some(window_id) = data.window_registry().find_by_element(&self.window) {
+            let mut location = self.initial_window_location;
+            if self.edges.intersects(ResizeEdge::LEFT) {
+                location.x += self.initial_window_size.w - self.last_window_size.w;
+            }
+            if self.edges.intersects(ResizeEdge::TOP) {
+                location.y += self.initial_window_size.h - self.last_window_size.h;
+            }
+            let geometry = Rectangle::new(location, self.last_window_size);
+            data.set_floating_geometry(window_id, geometry);
+        }
+
         // Mark any moved windows for update
         data.window_manager.mark_moved_windows();
 
@@ -1022,6 +1358,19 @@ impl<BackendData: Backend> TouchGrab<StilchState<BackendData>>
     }
 
     fn unset(&mut self, data: &mut StilchState<BackendData>) {
+        // Floating windows keep their new size, anchored at the edges opposite the drag
+        if let Some(window_id) = data.window_registry().find_by_element(&self.window) {
+            let mut location = self.initial_window_location;
+            if self.edges.intersects(ResizeEdge::LEFT) {
+                location.x += self.initial_window_size.w - self.last_window_size.w;
+            }
+            if self.edges.intersects(ResizeEdge::TOP) {
+                location.y += self.initial_window_size.h - self.last_window_size.h;
+            }
+            let geometry = Rectangle::new(location, self.last_window_size);
+            data.set_floating_geometry(window_id, geometry);
+        }
+
         // Mark any moved windows for update
         data.window_manager.mark_moved_windows();
 
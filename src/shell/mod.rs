@@ -13,7 +13,9 @@ use smithay::{
         layer_map_for_output, space::SpaceElement, LayerSurface, PopupKind, PopupManager, Space,
         Window, WindowSurfaceType,
     },
-    input::pointer::{CursorImageStatus, CursorImageSurfaceData},
+    input::pointer::{
+        CursorImageStatus, CursorImageSurfaceData, Focus, GrabStartData as PointerGrabStartData,
+    },
     output::Output,
     reexports::{
         calloop::Interest,
@@ -22,7 +24,7 @@ use smithay::{
             Client, Resource,
         },
     },
-    utils::{IsAlive, Logical, Point, Rectangle, Size},
+    utils::{IsAlive, Logical, Point, Rectangle, Serial, Size},
     wayland::{
         buffer::BufferHandler,
         compositor::{
@@ -36,13 +38,14 @@ use smithay::{
                 Layer, LayerSurface as WlrLayerSurface, LayerSurfaceData, WlrLayerShellHandler,
                 WlrLayerShellState,
             },
-            xdg::XdgToplevelSurfaceData,
+            xdg::{PopupSurface, XdgToplevelSurfaceData},
         },
     },
 };
 
 use crate::{
     state::{Backend, StilchState},
+    window::WindowId,
     ClientState,
 };
 
@@ -56,6 +59,8 @@ mod xdg;
 
 pub use self::element::*;
 pub use self::grabs::*;
+#[cfg(feature = "xwayland")]
+pub use self::x11::xwayland_scale_from_env;
 
 #[derive(Default)]
 pub struct FullscreenSurface(RefCell<Option<WindowElement>>);
@@ -83,6 +88,49 @@ impl FullscreenSurface {
 static POISON_COMPOSITOR_STATE: Lazy<CompositorClientState> =
     Lazy::new(|| CompositorClientState::default());
 
+/// Set `STILCH_DEBUG_SYNCOBJ` to log explicit-sync timeline activity and report
+/// buffers that reach the renderer before their acquire point has signaled.
+#[cfg(feature = "udev")]
+static DEBUG_SYNCOBJ: Lazy<bool> = Lazy::new(|| std::env::var("STILCH_DEBUG_SYNCOBJ").is_ok());
+
+/// Report a committed buffer whose acquire point has not signaled yet.
+///
+/// The pre-commit blocker should make this impossible; hitting it means the
+/// buffer is about to be imported while the client's GPU work is still in flight.
+#[cfg(feature = "udev")]
+fn check_acquire_point(surface: &WlSurface) {
+    let points = with_states(surface, |surface_data| {
+        let mut cached = surface_data.cached_state.get::<DrmSyncobjCachedState>();
+        let current = cached.current();
+        (
+            current.acquire_point.clone(),
+            current.release_point.is_some(),
+        )
+    });
+    match points {
+        (Some(acquire_point), has_release) => {
+            // A zero timeout turns the wait into a poll of the timeline point.
+            if acquire_point.wait(0).is_err() {
+                tracing::warn!(
+                    "syncobj: buffer for {:?} used before its acquire point signaled",
+                    surface.id()
+                );
+            }
+            if !has_release {
+                tracing::warn!(
+                    "syncobj: buffer for {:?} has an acquire point but no release point",
+                    surface.id()
+                );
+            }
+        }
+        (None, true) => tracing::warn!(
+            "syncobj: buffer for {:?} has a release point but no acquire point",
+            surface.id()
+        ),
+        (None, false) => {}
+    }
+}
+
 impl<BackendData: Backend> BufferHandler for StilchState<BackendData> {
     fn buffer_destroyed(&mut self, _buffer: &WlBuffer) {}
 }
@@ -152,27 +200,58 @@ impl<BackendData: Backend> CompositorHandler for StilchState<BackendData> {
                     })
             });
             if let Some(dmabuf) = maybe_dmabuf {
+                // Explicit-sync clients attach no implicit fence to the dmabuf, so the
+                // acquire point is the only thing keeping us from sampling or scanning
+                // out a buffer the GPU is still writing. The matching release point is
+                // carried by the buffer itself and signaled once the last renderer or
+                // DRM framebuffer reference is dropped, i.e. after the next page flip.
                 #[cfg(feature = "udev")]
                 if let Some(acquire_point) = acquire_point {
-                    if let Ok((blocker, source)) = acquire_point.generate_blocker() {
-                        let client = match surface.client() {
-                            Some(client) => client,
-                            None => {
-                                tracing::warn!("Surface has no client");
-                                return;
-                            }
-                        };
-                        let res = state.handle.insert_source(source, move |_, _, data| {
-                            let dh = data.display_handle.clone();
-                            data.client_compositor_state(&client)
-                                .blocker_cleared(data, &dh);
-                            Ok(())
-                        });
-                        if res.is_ok() {
-                            add_blocker(surface, blocker);
+                    let client = match surface.client() {
+                        Some(client) => client,
+                        None => {
+                            tracing::warn!("Surface has no client");
                             return;
                         }
+                    };
+                    match acquire_point.generate_blocker() {
+                        Ok((blocker, source)) => {
+                            let surface_id = surface.id();
+                            let blocked_at = std::time::Instant::now();
+                            let res = state.handle.insert_source(source, move |_, _, data| {
+                                if *DEBUG_SYNCOBJ {
+                                    tracing::info!(
+                                        "syncobj: acquire point for {:?} signaled after {:?}",
+                                        surface_id,
+                                        blocked_at.elapsed()
+                                    );
+                                }
+                                let dh = data.display_handle.clone();
+                                data.client_compositor_state(&client)
+                                    .blocker_cleared(data, &dh);
+                                Ok(())
+                            });
+                            match res {
+                                Ok(_) => {
+                                    add_blocker(surface, blocker);
+                                    return;
+                                }
+                                Err(err) => tracing::warn!(
+                                    "Failed to watch acquire point for {:?}: {}",
+                                    surface.id(),
+                                    err
+                                ),
+                            }
+                        }
+                        Err(err) => tracing::warn!(
+                            "Failed to create acquire point blocker for {:?}: {}",
+                            surface.id(),
+                            err
+                        ),
                     }
+                    // Blocking the event loop on a client-controlled timeline is not an
+                    // option, so fall back to the implicit fence and accept that this
+                    // frame may be presented early.
                 }
                 if let Ok((blocker, source)) = dmabuf.generate_blocker(Interest::READ) {
                     if let Some(client) = surface.client() {
@@ -192,6 +271,11 @@ impl<BackendData: Backend> CompositorHandler for StilchState<BackendData> {
     }
 
     fn commit(&mut self, surface: &WlSurface) {
+        #[cfg(feature = "udev")]
+        if *DEBUG_SYNCOBJ {
+            check_acquire_point(surface);
+        }
+
         on_commit_buffer_handler::<Self>(surface);
         self.backend_data.early_import(surface);
 
@@ -222,6 +306,9 @@ impl<BackendData: Backend> CompositorHandler for StilchState<BackendData> {
                 window.0.on_commit();
 
                 if &root == surface {
+                    // X11 titles change without a callback, they show up with the next commit
+                    self.check_title_change(surface);
+
                     let buffer_offset = with_states(surface, |states| {
                         states
                             .cached_state
@@ -244,18 +331,9 @@ impl<BackendData: Backend> CompositorHandler for StilchState<BackendData> {
                     }
                 }
             }
-            // Check if this is a layer surface and queue redraw for its output
-            else {
-                // Check all outputs for layer surfaces
-                for output in self.space().outputs().cloned().collect::<Vec<_>>() {
-                    let map = layer_map_for_output(&output);
-                    if map
-                        .layer_for_surface(&root, WindowSurfaceType::TOPLEVEL)
-                        .is_some()
-                    {
-                        break;
-                    }
-                }
+            // Layer surfaces may have changed their keyboard interactivity
+            else if let Some(layer) = self.layer_for_surface(&root) {
+                self.update_layer_focus(&layer);
             }
         }
         self.popups_mut().commit(surface);
@@ -325,23 +403,16 @@ impl<BackendData: Backend> WlrLayerShellHandler for StilchState<BackendData> {
         let output = wl_output
             .as_ref()
             .and_then(Output::from_resource)
-            .unwrap_or_else(|| {
-                self.space().outputs().next().cloned().unwrap_or_else(|| {
-                    tracing::error!("No outputs available for layer surface");
-                    // Create a dummy output as fallback
-                    Output::new(
-                        "dummy".to_string(),
-                        smithay::output::PhysicalProperties {
-                            size: (0, 0).into(),
-                            subpixel: smithay::output::Subpixel::Unknown,
-                            make: "Unknown".to_string(),
-                            model: "Unknown".to_string(),
-                        },
-                    )
-                })
-            });
+            .or_else(|| self.primary_output());
+        let layer = LayerSurface::new(surface, namespace);
+        let Some(output) = output else {
+            // Held back until an output shows up, rather than mapped nowhere
+            tracing::warn!("No output for layer surface yet, deferring it");
+            self.pending_layer_surfaces.push(layer);
+            return;
+        };
         let mut map = layer_map_for_output(&output);
-        if let Err(e) = map.map_layer(&LayerSurface::new(surface, namespace)) {
+        if let Err(e) = map.map_layer(&layer) {
             tracing::error!("Failed to map layer surface: {:?}", e);
         } else {
             // Queue redraw for the output when a new layer surface is added
@@ -349,7 +420,11 @@ impl<BackendData: Backend> WlrLayerShellHandler for StilchState<BackendData> {
     }
 
     fn layer_destroyed(&mut self, surface: WlrLayerSurface) {
+        self.pending_layer_surfaces
+            .retain(|layer| layer.layer_surface() != &surface);
+
         // Find and unmap the layer
+        let mut unmapped = None;
         for output in self.space().outputs() {
             let layer_to_unmap = {
                 let map = layer_map_for_output(output);
@@ -362,13 +437,63 @@ impl<BackendData: Backend> WlrLayerShellHandler for StilchState<BackendData> {
             if let Some(layer) = layer_to_unmap {
                 let mut map = layer_map_for_output(output);
                 map.unmap_layer(&layer);
+                unmapped = Some(layer);
                 break;
             }
         }
+
+        if let Some(layer) = unmapped {
+            self.layer_focus_lost(&layer);
+        }
+    }
+
+    fn new_popup(&mut self, _parent: WlrLayerSurface, popup: PopupSurface) {
+        // The xdg popup was created without a parent and tracked then, only
+        // now is it known where it opens
+        self.unconstrain_popup(&popup);
     }
 }
 
 impl<BackendData: Backend> StilchState<BackendData> {
+    /// Map the layer surfaces deferred while there was no output onto the
+    /// primary output, sending their initial configure now that they have
+    /// somewhere to be
+    pub fn map_pending_layer_surfaces(&mut self) {
+        if self.pending_layer_surfaces.is_empty() {
+            return;
+        }
+        let Some(output) = self.primary_output() else {
+            return;
+        };
+
+        let layers: Vec<LayerSurface> = self
+            .pending_layer_surfaces
+            .drain(..)
+            .filter(|layer| layer.alive())
+            .collect();
+        let mut map = layer_map_for_output(&output);
+        for layer in &layers {
+            if let Err(e) = map.map_layer(layer) {
+                tracing::error!("Failed to map deferred layer surface: {:?}", e);
+            }
+        }
+        map.arrange();
+
+        for layer in &layers {
+            let initial_configure_sent = with_states(layer.wl_surface(), |states| {
+                states
+                    .data_map
+                    .get::<LayerSurfaceData>()
+                    .and_then(|data| data.lock().ok())
+                    .map(|data| data.initial_configure_sent)
+                    .unwrap_or(false)
+            });
+            if !initial_configure_sent {
+                layer.layer_surface().send_configure();
+            }
+        }
+    }
+
     pub fn get_window_id(&self, window: &Window) -> Option<usize> {
         window.user_data().get::<usize>().copied()
     }
@@ -414,90 +539,32 @@ impl<BackendData: Backend> StilchState<BackendData> {
                 vo_region
             );
 
-            // Calculate the most restrictive exclusive zone from all physical outputs
-            let mut effective_area = vo_region;
-
-            for physical_output in &physical_outputs {
-                // Arrange the layer map to ensure exclusive zones are calculated
-                layer_map_for_output(physical_output).arrange();
+            // Each physical output's non-exclusive zone (the area NOT reserved by
+            // panels) in global coordinates
+            let zones: Vec<_> = physical_outputs
+                .iter()
+                .filter_map(|physical_output| {
+                    // Arrange the layer map to ensure exclusive zones are calculated
+                    layer_map_for_output(physical_output).arrange();
+                    let mut non_exclusive =
+                        layer_map_for_output(physical_output).non_exclusive_zone();
+                    let physical_geo = self.space().output_geometry(physical_output)?;
+                    non_exclusive.loc += physical_geo.loc;
 
-                let layer_map = layer_map_for_output(physical_output);
-                let non_exclusive = layer_map.non_exclusive_zone();
-
-                tracing::info!(
-                    "Physical output {:?} has non_exclusive zone: {:?}",
-                    physical_output.name(),
-                    non_exclusive
-                );
-
-                // The non_exclusive zone is the area NOT reserved by panels
-                // It's in physical output local coordinates
-                let physical_geo = self
-                    .space()
-                    .output_geometry(physical_output)
-                    .unwrap_or_else(|| Rectangle::from_size((0, 0).into()));
-
-                // The physical output's full size in logical coords
-                let physical_size = physical_geo.size;
-
-                // Calculate exclusive zones (what panels reserve) from the non-exclusive zone
-                // These are the amounts reserved on each edge
-                let exclusive_top = non_exclusive.loc.y;
-                let exclusive_left = non_exclusive.loc.x;
-                let exclusive_bottom =
-                    physical_size.h - (non_exclusive.loc.y + non_exclusive.size.h);
-                let exclusive_right =
-                    physical_size.w - (non_exclusive.loc.x + non_exclusive.size.w);
-
-                tracing::info!("Physical output {:?}: exclusive zones - top: {}, left: {}, bottom: {}, right: {}",
-                             physical_output.name(), exclusive_top, exclusive_left, exclusive_bottom, exclusive_right);
-
-                // Apply exclusions to our virtual output region
-                // Only apply if the virtual output touches that edge of the physical output
-
-                // Top edge - if virtual output starts at physical top
-                if vo_region.loc.y == physical_geo.loc.y && exclusive_top > 0 {
-                    let new_y = effective_area.loc.y + exclusive_top;
-                    let new_h = effective_area.size.h - exclusive_top;
-                    if new_h > 0 {
-                        effective_area.loc.y = new_y;
-                        effective_area.size.h = new_h;
-                        tracing::info!("Applied top exclusion: {} pixels", exclusive_top);
-                    }
-                }
-
-                // Left edge - if virtual output starts at physical left
-                if vo_region.loc.x == physical_geo.loc.x && exclusive_left > 0 {
-                    let new_x = effective_area.loc.x + exclusive_left;
-                    let new_w = effective_area.size.w - exclusive_left;
-                    if new_w > 0 {
-                        effective_area.loc.x = new_x;
-                        effective_area.size.w = new_w;
-                        tracing::info!("Applied left exclusion: {} pixels", exclusive_left);
-                    }
-                }
+                    tracing::info!(
+                        "Physical output {:?} at {:?} has non_exclusive zone: {:?}",
+                        physical_output.name(),
+                        physical_geo,
+                        non_exclusive
+                    );
+                    Some((physical_geo, non_exclusive))
+                })
+                .collect();
 
-                // Bottom edge - if virtual output extends to physical bottom
-                if vo_region.loc.y + vo_region.size.h >= physical_geo.loc.y + physical_size.h - 1
-                    && exclusive_bottom > 0
-                {
-                    let new_h = effective_area.size.h - exclusive_bottom;
-                    if new_h > 0 {
-                        effective_area.size.h = new_h;
-                        tracing::info!("Applied bottom exclusion: {} pixels", exclusive_bottom);
-                    }
-                }
+            let mut effective_area = crate::virtual_output::usable_area(vo_region, &zones);
 
-                // Right edge - if virtual output extends to physical right
-                if vo_region.loc.x + vo_region.size.w >= physical_geo.loc.x + physical_size.w - 1
-                    && exclusive_right > 0
-                {
-                    let new_w = effective_area.size.w - exclusive_right;
-                    if new_w > 0 {
-                        effective_area.size.w = new_w;
-                        tracing::info!("Applied right exclusion: {} pixels", exclusive_right);
-                    }
-                }
+            if let Some(status_bar) = &self.config.status_bar {
+                effective_area = self.status_bar.reserve(vo_id, effective_area, status_bar);
             }
 
             tracing::info!(
@@ -543,9 +610,127 @@ impl<BackendData: Backend> StilchState<BackendData> {
         let workspace_id = crate::workspace::WorkspaceId::new(workspace_idx as u8);
         self.switch_workspace(virtual_output_id, workspace_id);
 
+        // It may have been shown on the output it belongs to instead
+        if let Some(shown_on) = self.workspace_manager.find_workspace_location(workspace_id) {
+            self.warp_pointer_after_switch(shown_on);
+        }
+
         tracing::info!("Switched to workspace {}", workspace_idx + 1);
     }
 
+    /// Drag a window with the pointer until `button` is released
+    pub fn start_pointer_move(&mut self, window: WindowElement, button: u32, serial: Serial) {
+        let Some(initial_window_location) = self.space().element_location(&window) else {
+            return;
+        };
+        let pointer = self.pointer().clone();
+        let start_data = PointerGrabStartData {
+            focus: None,
+            button,
+            location: pointer.current_location(),
+        };
+        let grab = PointerMoveSurfaceGrab {
+            start_data,
+            window,
+            initial_window_location,
+        };
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+    }
+
+    /// Drag a tiled window with the pointer until `button` is released,
+    /// then swap it with the tiled window it was dropped on
+    pub fn start_tiled_move(&mut self, window_id: WindowId, button: u32, serial: Serial) {
+        let pointer = self.pointer().clone();
+        let start_data = PointerGrabStartData {
+            focus: None,
+            button,
+            location: pointer.current_location(),
+        };
+        let grab = PointerTiledMoveGrab {
+            start_data,
+            window_id,
+        };
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+    }
+
+    /// Swap a dragged tiled window with the tiled window under `location`,
+    /// if that one is on the same workspace
+    pub fn drop_tiled_window(&mut self, window_id: WindowId, location: Point<f64, Logical>) {
+        let Some(target) = self
+            .space()
+            .element_under(location)
+            .and_then(|(window, _)| self.window_registry().find_by_element(window))
+        else {
+            return;
+        };
+        let Some(workspace_id) = self
+            .window_registry()
+            .get(window_id)
+            .map(|managed| managed.workspace)
+        else {
+            return;
+        };
+        let swapped = self
+            .workspace_manager
+            .get_workspace_mut(workspace_id)
+            .is_some_and(|workspace| workspace.swap_windows(window_id, target));
+        if swapped {
+            tracing::debug!("Swapped window {window_id} with window {target}");
+            self.apply_workspace_layout(workspace_id);
+        }
+    }
+
+    /// Resize a window with the pointer until `button` is released, moving
+    /// the corner nearest to the pointer
+    pub fn start_pointer_resize(&mut self, window: WindowElement, button: u32, serial: Serial) {
+        let Some(initial_window_location) = self.space().element_location(&window) else {
+            return;
+        };
+        let initial_window_size = window.geometry().size;
+        let pointer = self.pointer().clone();
+        let location = pointer.current_location();
+
+        let center_x = initial_window_location.x as f64 + initial_window_size.w as f64 / 2.0;
+        let center_y = initial_window_location.y as f64 + initial_window_size.h as f64 / 2.0;
+        let mut edges = if location.x < center_x {
+            ResizeEdge::LEFT
+        } else {
+            ResizeEdge::RIGHT
+        };
+        edges |= if location.y < center_y {
+            ResizeEdge::TOP
+        } else {
+            ResizeEdge::BOTTOM
+        };
+
+        if let Some(surface) = window.wl_surface() {
+            with_states(&surface, |states| {
+                if let Some(data) = states.data_map.get::<RefCell<SurfaceData>>() {
+                    data.borrow_mut().resize_state = ResizeState::Resizing(ResizeData {
+                        edges,
+                        initial_window_location,
+                        initial_window_size,
+                    });
+                }
+            });
+        }
+
+        let start_data = PointerGrabStartData {
+            focus: None,
+            button,
+            location,
+        };
+        let grab = PointerResizeSurfaceGrab {
+            start_data,
+            window,
+            edges,
+            initial_window_location,
+            initial_window_size,
+            last_window_size: initial_window_size,
+        };
+        pointer.set_grab(self, grab, serial, Focus::Clear);
+    }
+
     /// Warp pointer to center of window
     pub fn warp_pointer_to_window(&mut self, elem: &WindowElement) {
         let window_loc = self.space().element_location(elem).unwrap_or_default();
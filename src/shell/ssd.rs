@@ -6,21 +6,27 @@ use smithay::{
         },
         Renderer,
     },
-    desktop::WindowSurface,
+    desktop::{space::SpaceElement, WindowSurface},
     input::Seat,
-    utils::{Logical, Point, Serial},
+    utils::{Logical, Physical, Point, Rectangle, Scale, Serial, Size},
     wayland::shell::xdg::XdgShellHandler,
 };
 
 use std::cell::{RefCell, RefMut};
 
-use crate::{state::Backend, StilchState};
+use crate::{state::Backend, workspace::BorderEdges, StilchState};
 
 use super::WindowElement;
 
 pub struct WindowState {
+    /// The header bar is drawn: a floating window using server-side decorations
     pub is_ssd: bool,
+    /// Decoration mode negotiated over xdg-decoration is server-side
+    pub server_side: bool,
+    /// Tiled windows only get borders and tab bars, never a header bar
+    pub floating: bool,
     pub header_bar: HeaderBar,
+    pub border: Border,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,6 +53,9 @@ const CLOSE_COLOR: [f32; 4] = [1f32, 0.66f32, 0.612f32, 1f32];
 const MAX_COLOR_HOVER: [f32; 4] = [0.71f32, 0.624f32, 0f32, 1f32];
 const CLOSE_COLOR_HOVER: [f32; 4] = [0.75f32, 0.11f32, 0.016f32, 1f32];
 
+const BORDER_COLOR_FOCUSED: [f32; 4] = [0.29f32, 0.47f32, 0.85f32, 1f32];
+const BORDER_COLOR_UNFOCUSED: [f32; 4] = [0.2f32, 0.2f32, 0.2f32, 1f32];
+
 pub const HEADER_BAR_HEIGHT: i32 = 32;
 const BUTTON_HEIGHT: u32 = HEADER_BAR_HEIGHT as u32;
 const BUTTON_WIDTH: u32 = 32;
@@ -60,6 +69,12 @@ impl HeaderBar {
         self.pointer_loc = None;
     }
 
+    /// The pointer is over the title rather than one of the buttons
+    pub fn over_title(&self) -> bool {
+        self.pointer_loc
+            .is_some_and(|loc| loc.x < self.width.saturating_sub(BUTTON_WIDTH * 2) as f64)
+    }
+
     pub fn clicked<BackendData: Backend>(
         &mut self,
         seat: &Seat<StilchState<BackendData>>,
@@ -232,8 +247,8 @@ impl<R: Renderer> AsRenderElements<R> for HeaderBar {
     fn render_elements<C: From<Self::RenderElement>>(
         &self,
         _renderer: &mut R,
-        location: Point<i32, smithay::utils::Physical>,
-        scale: smithay::utils::Scale<f64>,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
         alpha: f32,
     ) -> Vec<C> {
         let header_end_offset: Point<i32, Logical> = Point::from((self.width as i32, 0));
@@ -270,11 +285,127 @@ impl<R: Renderer> AsRenderElements<R> for HeaderBar {
     }
 }
 
+/// The border stilch draws around a window, on the edges `default_border`,
+/// `hide_edge_borders` and `smart_borders` leave it
+#[derive(Debug, Clone)]
+pub struct Border {
+    pub width: i32,
+    pub edges: BorderEdges,
+    /// Drawn in the focused color
+    pub active: bool,
+    /// Top, bottom, left and right
+    buffers: [SolidColorBuffer; 4],
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            edges: BorderEdges::NONE,
+            active: false,
+            buffers: Default::default(),
+        }
+    }
+}
+
+impl Border {
+    /// Where the window's content starts inside the border
+    pub fn offset(&self) -> Point<i32, Logical> {
+        let width = |drawn: bool| if drawn { self.width } else { 0 };
+        Point::from((width(self.edges.left), width(self.edges.top)))
+    }
+
+    /// Space the border adds around the content
+    pub fn size(&self) -> Size<i32, Logical> {
+        let width = |drawn: bool| if drawn { self.width } else { 0 };
+        Size::from((
+            width(self.edges.left) + width(self.edges.right),
+            width(self.edges.top) + width(self.edges.bottom),
+        ))
+    }
+
+    /// The drawn edges around content of `content` size, relative to the
+    /// border's top-left corner. Top and bottom span the corners.
+    pub fn rects(&self, content: Size<i32, Logical>) -> Vec<Rectangle<i32, Logical>> {
+        self.edge_rects(content).into_iter().flatten().collect()
+    }
+
+    fn edge_rects(&self, content: Size<i32, Logical>) -> [Option<Rectangle<i32, Logical>>; 4] {
+        if self.width <= 0 {
+            return [None; 4];
+        }
+        let outer = content + self.size();
+        let offset = self.offset();
+        let width = self.width;
+        let rect = |drawn: bool, loc: (i32, i32), size: (i32, i32)| {
+            drawn.then(|| Rectangle::new(Point::from(loc), Size::from(size)))
+        };
+        [
+            rect(self.edges.top, (0, 0), (outer.w, width)),
+            rect(self.edges.bottom, (0, outer.h - width), (outer.w, width)),
+            rect(self.edges.left, (0, offset.y), (width, content.h)),
+            rect(
+                self.edges.right,
+                (outer.w - width, offset.y),
+                (width, content.h),
+            ),
+        ]
+    }
+
+    /// Update the edge buffers for content of `content` size, returning
+    /// those drawn with where they go
+    fn redraw(
+        &mut self,
+        content: Size<i32, Logical>,
+    ) -> Vec<(SolidColorBuffer, Point<i32, Logical>)> {
+        let color = if self.active {
+            BORDER_COLOR_FOCUSED
+        } else {
+            BORDER_COLOR_UNFOCUSED
+        };
+        let rects = self.edge_rects(content);
+        self.buffers
+            .iter_mut()
+            .zip(rects)
+            .filter_map(|(buffer, rect)| {
+                let rect = rect?;
+                buffer.update(rect.size, color);
+                Some((buffer.clone(), rect.loc))
+            })
+            .collect()
+    }
+
+    /// Render elements of the drawn edges, with the border's top-left
+    /// corner at `location`
+    pub fn render_elements(
+        &mut self,
+        content: Size<i32, Logical>,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        alpha: f32,
+    ) -> Vec<SolidColorRenderElement> {
+        self.redraw(content)
+            .into_iter()
+            .map(|(buffer, loc)| {
+                SolidColorRenderElement::from_buffer(
+                    &buffer,
+                    location + loc.to_physical_precise_round(scale),
+                    scale,
+                    alpha,
+                    Kind::Unspecified,
+                )
+            })
+            .collect()
+    }
+}
+
 impl WindowElement {
     pub fn decoration_state(&self) -> RefMut<'_, WindowState> {
         self.user_data().insert_if_missing(|| {
             RefCell::new(WindowState {
                 is_ssd: false,
+                server_side: false,
+                floating: false,
                 header_bar: HeaderBar {
                     pointer_loc: None,
                     width: 0,
@@ -283,6 +414,7 @@ impl WindowElement {
                     close_button: SolidColorBuffer::default(),
                     maximize_button: SolidColorBuffer::default(),
                 },
+                border: Border::default(),
             })
         });
 
@@ -293,9 +425,66 @@ impl WindowElement {
             .borrow_mut()
     }
 
-    pub fn set_ssd(&self, _ssd: bool) {
-        // In a tiling window manager, we never use decorations
-        // Always keep is_ssd as false
-        self.decoration_state().is_ssd = false;
+    /// Record whether the client agreed to server-side decorations
+    pub fn set_ssd(&self, ssd: bool) {
+        let mut state = self.decoration_state();
+        state.server_side = ssd;
+        state.is_ssd = ssd && state.floating;
+    }
+
+    /// Whether stilch draws this window's decorations
+    pub fn is_server_side(&self) -> bool {
+        self.decoration_state().server_side
+    }
+
+    /// Set the border drawn around the window. Its content shrinks by the
+    /// edges drawn on the next resize.
+    pub fn set_border(&self, width: i32, edges: BorderEdges) {
+        let mut state = self.decoration_state();
+        state.border.width = width.max(0);
+        state.border.edges = edges;
+    }
+
+    /// Where the client's surface starts inside the border and header bar
+    pub fn content_offset(&self) -> Point<i32, Logical> {
+        let state = self.decoration_state();
+        let mut offset = state.border.offset();
+        if state.is_ssd {
+            offset.y += HEADER_BAR_HEIGHT;
+        }
+        offset
+    }
+
+    /// Space the border and header bar take out of the window's size
+    pub fn decoration_size(&self) -> Size<i32, Logical> {
+        let state = self.decoration_state();
+        let mut size = state.border.size();
+        if state.is_ssd {
+            size.h += HEADER_BAR_HEIGHT;
+        }
+        size
+    }
+
+    /// The border edges as drawn, relative to the window's top-left corner
+    pub fn border_rects(&self) -> Vec<Rectangle<i32, Logical>> {
+        let content = self.decorated_content_size();
+        self.decoration_state().border.rects(content)
+    }
+
+    /// Size of what the border goes around: the surface and header bar
+    pub(super) fn decorated_content_size(&self) -> Size<i32, Logical> {
+        let mut size = SpaceElement::geometry(&self.0).size;
+        if self.decoration_state().is_ssd {
+            size.h += HEADER_BAR_HEIGHT;
+        }
+        size
+    }
+
+    /// Track whether the window floats, which decides if a server-side
+    /// window gets a header bar
+    pub fn set_floating_decorations(&self, floating: bool) {
+        let mut state = self.decoration_state();
+        state.floating = floating;
+        state.is_ssd = state.server_side && floating;
     }
 }
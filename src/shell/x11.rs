@@ -1,10 +1,11 @@
-use std::{cell::RefCell, os::unix::io::OwnedFd};
+use std::{cell::RefCell, os::unix::io::OwnedFd, time::Duration};
 
 use smithay::{
     desktop::Window,
     input::pointer::Focus,
-    utils::{Logical, Rectangle, SERIAL_COUNTER},
+    utils::{Logical, Point, Rectangle, Size, SERIAL_COUNTER},
     wayland::{
+        compositor::CompositorHandler,
         selection::{
             data_device::{
                 clear_data_device_selection, current_data_device_selection_userdata,
@@ -23,12 +24,26 @@ use smithay::{
         X11Surface, X11Wm, XwmHandler,
     },
 };
-use tracing::{error, info, trace, warn};
-
-use crate::{focus::KeyboardFocusTarget, state::Backend, StilchState};
+use tracing::{debug, error, info, trace, warn};
+
+use crate::{
+    config::XWaylandScale,
+    focus::KeyboardFocusTarget,
+    handlers::clipboard::{send_cached_selection, SelectionData},
+    state::Backend,
+    StilchState,
+};
 
 use super::{FullscreenSurface, PointerMoveSurfaceGrab, TouchMoveSurfaceGrab, WindowElement};
 
+/// XWayland scale from `STILCH_XWAYLAND_SCALE`, or the older
+/// `ANVIL_XWAYLAND_SCALE`, which overrides `xwayland_scale`
+pub fn xwayland_scale_from_env() -> Option<f64> {
+    ["STILCH_XWAYLAND_SCALE", "ANVIL_XWAYLAND_SCALE"]
+        .into_iter()
+        .find_map(|name| std::env::var(name).ok()?.parse().ok())
+}
+
 #[derive(Debug, Default)]
 struct OldGeometry(RefCell<Option<Rectangle<i32, Logical>>>);
 impl OldGeometry {
@@ -79,12 +94,11 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
                 // Configure the X11 window geometry
                 if let Some(managed_window) = self.window_registry().get(window_id) {
                     if let Some(xsurface) = managed_window.element.0.x11_surface() {
+                        // Windows on hidden workspaces were configured by add_window
                         if let Some(bbox) = self.space().element_bbox(&managed_window.element) {
                             if let Err(e) = xsurface.configure(Some(bbox)) {
                                 tracing::error!("Failed to configure X11 surface: {:?}", e);
                             }
-                        } else {
-                            tracing::warn!("No bounding box for X11 window element");
                         }
                     }
                 }
@@ -115,12 +129,11 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
                     // Configure the X11 window geometry
                     if let Some(managed_window) = self.window_registry().get(window_id) {
                         if let Some(xsurface) = managed_window.element.0.x11_surface() {
+                            // Windows on hidden workspaces were configured by add_window
                             if let Some(bbox) = self.space().element_bbox(&managed_window.element) {
                                 if let Err(e) = xsurface.configure(Some(bbox)) {
                                     tracing::error!("Failed to configure X11 surface: {:?}", e);
                                 }
-                            } else {
-                                tracing::warn!("No bounding box for X11 window element");
                             }
                         }
                     }
@@ -134,33 +147,33 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
     }
 
     fn mapped_override_redirect_window(&mut self, _xwm: XwmId, window: X11Surface) {
+        // Menus, tooltips and drag icons are not managed: they go exactly
+        // where the client put them, above everything else, without focus
         let location = window.geometry().loc;
         let window_element = WindowElement(Window::new_x11_window(window));
-
-        // Override redirect windows bypass normal window management
-        // but we still want to track them in the registry
-        // They don't belong to any workspace and maintain their own position
-
-        // Override-redirect windows bypass the window manager
-        // Use the centralized update method which handles unmanaged windows
-        self.window_manager
-            .update_element_position(&window_element, location);
+        self.space_mut()
+            .map_element(window_element, location, false);
     }
 
     fn unmapped_window(&mut self, _xwm: XwmId, window: X11Surface) {
-        // Find the window element
+        // Managed windows are looked up in the registry, since scratchpad
+        // windows and those on hidden workspaces aren't in the space
         let maybe = self
-            .space()
-            .elements()
+            .window_registry()
+            .windows()
+            .map(|mw| &mw.element)
+            .chain(self.space().elements())
             .find(|e| matches!(e.0.x11_surface(), Some(w) if w == &window))
             .cloned();
 
         if let Some(elem) = maybe {
+            self.start_window_close_animation(&elem);
             // Check if it's in the registry
             if let Some(window_id) = self.window_registry().find_by_element(&elem) {
                 // Use the proper window removal flow
                 if let Some(managed_window) = self.window_registry().get(window_id) {
                     let workspace_id = managed_window.workspace;
+                    self.scratchpad.remove(window_id);
 
                     // Remove from workspace
                     if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id)
@@ -168,6 +181,9 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
                         workspace.remove_window(window_id);
                     }
 
+                    self.focus_history.remove(window_id);
+                    self.transient_children.remove(window_id);
+
                     // Remove from window manager (this will emit the event and unmap from space)
                     let (removed, event) = self.window_manager.remove_window(window_id);
                     if let Some(event) = event {
@@ -237,10 +253,13 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
         else {
             return;
         };
+        if window.is_override_redirect() {
+            // Mapping again moves it and puts it back on top
+            self.space_mut().map_element(elem, geometry.loc, false);
+            return;
+        }
         self.window_manager
             .update_element_position(&elem, geometry.loc);
-        // TODO: We don't properly handle the order of override-redirect windows here,
-        //       they are always mapped top and then never reordered.
     }
 
     fn maximize_request(&mut self, _xwm: XwmId, window: X11Surface) {
@@ -282,11 +301,12 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
             let outputs_for_window = self.space().outputs_for_element(elem);
             let output = outputs_for_window
                 .first()
+                .cloned()
                 // The window hasn't been mapped yet, use the primary output instead
-                .or_else(|| self.space().outputs().next())
+                .or_else(|| self.primary_output())
                 // SAFETY: We always have at least one output in normal operation
                 .expect("No outputs found");
-            let geometry = self.space().output_geometry(output).unwrap_or_else(|| {
+            let geometry = self.space().output_geometry(&output).unwrap_or_else(|| {
                 tracing::error!("No geometry for output, using default");
                 Rectangle::from_size((1920, 1080).into())
             });
@@ -391,7 +411,13 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
     ) {
         match selection {
             SelectionTarget::Clipboard => {
-                if let Err(err) = request_data_device_client_selection(self.seat(), mime_type, fd) {
+                if let Some(SelectionData::Cached(contents)) =
+                    current_data_device_selection_userdata(self.seat())
+                {
+                    send_cached_selection(&self.handle, &contents, &mime_type, fd);
+                } else if let Err(err) =
+                    request_data_device_client_selection(self.seat(), mime_type, fd)
+                {
                     error!(
                         ?err,
                         "Failed to request current wayland clipboard for Xwayland",
@@ -399,7 +425,13 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
                 }
             }
             SelectionTarget::Primary => {
-                if let Err(err) = request_primary_client_selection(self.seat(), mime_type, fd) {
+                if let Some(SelectionData::Cached(contents)) =
+                    current_primary_selection_userdata(self.seat())
+                {
+                    send_cached_selection(&self.handle, &contents, &mime_type, fd);
+                } else if let Err(err) =
+                    request_primary_client_selection(self.seat(), mime_type, fd)
+                {
                     error!(
                         ?err,
                         "Failed to request current wayland primary selection for Xwayland",
@@ -413,24 +445,36 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
         trace!(?selection, ?mime_types, "Got Selection from X11",);
         // TODO check, that focused windows is X11 window before doing this
         match selection {
-            SelectionTarget::Clipboard => {
-                set_data_device_selection(&self.display_handle, self.seat(), mime_types, ())
-            }
-            SelectionTarget::Primary => {
-                set_primary_selection(&self.display_handle, self.seat(), mime_types, ())
-            }
+            SelectionTarget::Clipboard => set_data_device_selection(
+                &self.display_handle,
+                self.seat(),
+                mime_types,
+                SelectionData::Xwayland,
+            ),
+            SelectionTarget::Primary => set_primary_selection(
+                &self.display_handle,
+                self.seat(),
+                mime_types,
+                SelectionData::Xwayland,
+            ),
         }
     }
 
     fn cleared_selection(&mut self, _xwm: XwmId, selection: SelectionTarget) {
         match selection {
             SelectionTarget::Clipboard => {
-                if current_data_device_selection_userdata(self.seat()).is_some() {
+                if matches!(
+                    current_data_device_selection_userdata(self.seat()),
+                    Some(SelectionData::Xwayland)
+                ) {
                     clear_data_device_selection(&self.display_handle, self.seat())
                 }
             }
             SelectionTarget::Primary => {
-                if current_primary_selection_userdata(self.seat()).is_some() {
+                if matches!(
+                    current_primary_selection_userdata(self.seat()),
+                    Some(SelectionData::Xwayland)
+                ) {
                     clear_primary_selection(&self.display_handle, self.seat())
                 }
             }
@@ -439,6 +483,104 @@ impl<BackendData: Backend> XwmHandler for StilchState<BackendData> {
 }
 
 impl<BackendData: Backend> StilchState<BackendData> {
+    /// Scale XWayland should have now: the fixed `xwayland_scale`, or with
+    /// `xwayland_scale auto` the scale of the output the focused X11 window is
+    /// on. `None` when there is nothing to follow, keeping the current scale.
+    pub fn wanted_xwayland_scale(&self) -> Option<f64> {
+        match self.config.xwayland_scale {
+            XWaylandScale::Fixed(scale) => Some(scale),
+            XWaylandScale::FollowOutput => {
+                let window = self
+                    .focused_window()
+                    .filter(|window| window.0.x11_surface().is_some())?;
+                let output = self
+                    .space()
+                    .outputs_for_element(&window)
+                    .into_iter()
+                    .next()?;
+                let output_name = output.name();
+                let configured = self
+                    .config
+                    .outputs
+                    .iter()
+                    .find(|config| config.name == output_name)
+                    .and_then(|config| config.xwayland_scale);
+                Some(configured.unwrap_or_else(|| output.current_scale().fractional_scale()))
+            }
+        }
+    }
+
+    /// Follow `xwayland_scale` as X11 windows get focus or move between
+    /// outputs. Every X11 window shares the one XWayland client scale, so
+    /// they are all configured again at the new scale.
+    pub fn refresh_xwayland_scale(&mut self) {
+        let Some(client) = self.xwayland_client.clone() else {
+            return;
+        };
+        let Some(scale) = self.wanted_xwayland_scale() else {
+            return;
+        };
+        let client_state = self.client_compositor_state(&client);
+        if client_state.client_scale() == scale {
+            return;
+        }
+        info!("Setting XWayland scale to {scale}");
+        client_state.set_client_scale(scale);
+
+        let x11_windows: Vec<_> = self
+            .space()
+            .elements()
+            .filter_map(|element| {
+                let surface = element.0.x11_surface()?.clone();
+                Some((surface, self.space().element_bbox(element)?))
+            })
+            .collect();
+        for (surface, bbox) in x11_windows {
+            if let Err(e) = surface.configure(Some(bbox)) {
+                warn!("Failed to configure X11 window at the new scale: {:?}", e);
+            }
+        }
+        self.set_xwayland_cursor(scale);
+    }
+
+    /// Give XWayland a default cursor sized for its scale
+    pub fn set_xwayland_cursor(&mut self, scale: f64) {
+        let cursor = self
+            .input_manager
+            .cursor_manager
+            .get_current_cursor_for_xwayland(scale.ceil() as u32, Duration::ZERO);
+        let Some(wm) = self.xwm.as_mut() else {
+            return;
+        };
+        if let Some((pixels, width, height, xhot, yhot)) = cursor {
+            wm.set_cursor(
+                &pixels,
+                Size::from((width, height)),
+                Point::from((xhot, yhot)),
+            )
+            .unwrap_or_else(|e| {
+                error!("Failed to set xwayland default cursor: {:?}", e);
+                // Non-critical error, continue without cursor
+            });
+        } else {
+            debug!("No cursor available for xwayland, using xwayland default");
+        }
+    }
+
+    /// Keep override-redirect windows (menus, tooltips) above a window that
+    /// was just raised, they belong to whatever is on top
+    pub fn raise_override_redirect_windows(&mut self) {
+        let windows: Vec<_> = self
+            .space()
+            .elements()
+            .filter(|e| e.is_override_redirect())
+            .cloned()
+            .collect();
+        for window in windows {
+            self.space_mut().raise_element(&window, false);
+        }
+    }
+
     pub fn maximize_request_x11(&mut self, window: &X11Surface) {
         let Some(elem) = self
             .space()
@@ -456,11 +598,12 @@ impl<BackendData: Backend> StilchState<BackendData> {
         let outputs_for_window = self.space().outputs_for_element(&elem);
         let output = outputs_for_window
             .first()
+            .cloned()
             // The window hasn't been mapped yet, use the primary output instead
-            .or_else(|| self.space().outputs().next())
+            .or_else(|| self.primary_output())
             // SAFETY: We always have at least one output in normal operation
             .expect("No outputs found");
-        let geometry = self.space().output_geometry(output).unwrap_or_else(|| {
+        let geometry = self.space().output_geometry(&output).unwrap_or_else(|| {
             tracing::error!("No geometry for output, using default");
             Rectangle::from_size((1920, 1080).into())
         });
@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::{cell::RefCell, time::Instant};
 
 use smithay::{
     desktop::{
@@ -8,13 +8,15 @@ use smithay::{
     },
     input::{pointer::Focus, Seat},
     reexports::{
-        wayland_protocols::xdg::shell::server::xdg_toplevel,
+        wayland_protocols::xdg::{
+            decoration::zv1::server::zxdg_toplevel_decoration_v1, shell::server::xdg_toplevel,
+        },
         wayland_server::{
             protocol::{wl_output, wl_seat, wl_surface::WlSurface},
             Resource,
         },
     },
-    utils::{Logical, Point, Serial},
+    utils::{Logical, Point, Rectangle, Serial},
     wayland::{
         compositor::{self, with_states},
         seat::WaylandFocus,
@@ -27,6 +29,7 @@ use smithay::{
 use tracing::{debug, error, info, trace, warn};
 
 use crate::{
+    event::WindowEvent,
     focus::KeyboardFocusTarget,
     shell::TouchMoveSurfaceGrab,
     state::{Backend, StilchState},
@@ -206,18 +209,8 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
             }
         }
 
-        // Set focus to the new window using focus_window to ensure workspace tracking
-        self.focus_window(&window);
-
-        // Move cursor to center of new window
-        if let Some(loc) = self.space().element_location(&window) {
-            let geo = window.geometry();
-            let center = smithay::utils::Point::<f64, smithay::utils::Logical>::from((
-                (loc.x + geo.size.w / 2) as f64,
-                (loc.y + geo.size.h / 2) as f64,
-            ));
-            self.pointer().set_location(center);
-        }
+        // Focus and the pointer follow once the toplevel commits its app_id
+        // and title, which no_focus rules match against
 
         // Debug dump all windows before adding post commit hook
         self.debug_dump_all_windows();
@@ -234,6 +227,8 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
             let surface_id = surface.id().protocol_id();
             tracing::debug!("Post commit hook called for wl_surface@{surface_id}");
             handle_toplevel_commit(state.space_mut(), surface);
+            state.apply_initial_window_rules(surface);
+            state.check_parent_on_commit(surface);
         });
     }
 
@@ -255,150 +250,144 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
             surface.wl_surface().id()
         );
 
-        // Find the window element for this surface
-        let window_element = self
-            .space()
-            .elements()
-            .find(|elem| {
-                elem.0
-                    .toplevel()
-                    .map(|t| t.wl_surface() == surface.wl_surface())
-                    .unwrap_or(false)
-            })
-            .cloned();
+        // Scratchpad windows and those on hidden workspaces aren't in the
+        // space, so the window is looked up by its surface
+        let window_id = self.window_registry().find_by_surface(surface.wl_surface());
+        let window_element = window_id
+            .and_then(|id| self.window_registry().get(id))
+            .map(|mw| mw.element.clone());
 
-        if let Some(window_element) = window_element {
+        if let Some((window_id, window_element)) = window_id.zip(window_element) {
             tracing::info!("Found window element for destroyed toplevel");
-            // Find the window in the registry
-            if let Some(window_id) = self.window_registry().find_by_element(&window_element) {
-                // Get the workspace this window is in
-                let workspace_id = self.window_registry().get(window_id).map(|mw| mw.workspace);
+            self.start_window_close_animation(&window_element);
+            self.scratchpad.remove(window_id);
+            // Get the workspace this window is in
+            let workspace_id = self.window_registry().get(window_id).map(|mw| mw.workspace);
+
+            if let Some(workspace_id) = workspace_id {
+                // Find which virtual output this workspace is on
+                let virtual_output_id =
+                    self.workspace_manager.find_workspace_location(workspace_id);
+
+                // Remove window from workspace first
+                if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
+                    workspace.remove_window(window_id);
+                }
 
-                if let Some(workspace_id) = workspace_id {
-                    // Find which virtual output this workspace is on
-                    let virtual_output_id =
-                        self.workspace_manager.find_workspace_location(workspace_id);
+                // Find the next window to focus AFTER removing (so we don't pick the removed window)
+                let next_focus = self
+                    .workspace_manager
+                    .get_workspace(workspace_id)
+                    .and_then(|ws| ws.layout.find_next_focus());
 
-                    // Remove window from workspace first
-                    if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id)
-                    {
-                        workspace.remove_window(window_id);
-                    }
+                tracing::debug!(
+                    "After removing window {}, next focus candidate: {:?}",
+                    window_id,
+                    next_focus
+                );
 
-                    // Find the next window to focus AFTER removing (so we don't pick the removed window)
-                    let next_focus = self
-                        .workspace_manager
-                        .get_workspace(workspace_id)
-                        .and_then(|ws| ws.layout.find_next_focus());
+                self.tab_text_cache.invalidate_window(window_id);
+                self.focus_history.remove(window_id);
+                self.transient_children.remove(window_id);
 
-                    tracing::debug!(
-                        "After removing window {}, next focus candidate: {:?}",
-                        window_id,
-                        next_focus
+                // Remove from window manager (this will emit the event)
+                let (removed, event) = self.window_manager.remove_window(window_id);
+                if let Some(event) = event {
+                    self.event_bus.emit_window(event);
+                }
+                if removed {
+                    debug!("Removed window {} from manager", window_id);
+                }
+
+                // Debug check consistency after removal
+                #[cfg(debug_assertions)]
+                self.check_consistency();
+
+                // Check if we need to update positions and focus
+                let needs_update = virtual_output_id
+                    .and_then(|vo_id| {
+                        self.virtual_output_manager
+                            .get(vo_id)
+                            .map(|vo| vo.active_workspace() == Some(workspace_id.get() as usize))
+                    })
+                    .unwrap_or(false);
+
+                if needs_update {
+                    tracing::info!(
+                        "Window closed in active workspace {}, updating positions",
+                        workspace_id
                     );
 
-                    // Remove from window manager (this will emit the event)
-                    let (removed, event) = self.window_manager.remove_window(window_id);
-                    if let Some(event) = event {
-                        self.event_bus.emit_window(event);
-                    }
-                    if removed {
-                        debug!("Removed window {} from manager", window_id);
+                    // Apply new layout
+                    if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id)
+                    {
+                        workspace.relayout();
                     }
+                    self.apply_workspace_layout(workspace_id);
 
-                    // Debug check consistency after removal
-                    #[cfg(debug_assertions)]
-                    self.check_consistency();
-
-                    // Check if we need to update positions and focus
-                    let needs_update = virtual_output_id
-                        .and_then(|vo_id| {
-                            self.virtual_output_manager.get(vo_id).map(|vo| {
-                                vo.active_workspace() == Some(workspace_id.get() as usize)
-                            })
-                        })
-                        .unwrap_or(false);
-
-                    if needs_update {
-                        tracing::info!(
-                            "Window closed in active workspace {}, updating positions",
-                            workspace_id
-                        );
+                    // Update IPC state
+                    self.update_ipc_workspace_state();
 
-                        // Apply new layout
-                        if let Some(workspace) =
-                            self.workspace_manager.get_workspace_mut(workspace_id)
-                        {
-                            workspace.relayout();
-                        }
-                        self.apply_workspace_layout(workspace_id);
-
-                        // Update IPC state
-                        self.update_ipc_workspace_state();
-
-                        // Log current focus state
-                        let current_focus = self.focused_window();
-                        tracing::info!(
-                            "Current focused window before focus transfer: {:?}",
-                            current_focus
-                                .as_ref()
-                                .and_then(|w| self.window_registry().find_by_element(w))
-                        );
+                    // Log current focus state
+                    let current_focus = self.focused_window();
+                    tracing::info!(
+                        "Current focused window before focus transfer: {:?}",
+                        current_focus
+                            .as_ref()
+                            .and_then(|w| self.window_registry().find_by_element(w))
+                    );
 
-                        // Focus next window
-                        if let Some(next_window_id) = next_focus {
-                            let focus_info = self
-                                .window_registry()
-                                .get(next_window_id)
-                                .map(|mw| (mw.element.clone(), mw.element.0.clone()));
-
-                            if let Some((window_elem, _window)) = focus_info {
-                                tracing::info!(
-                                    "Focusing next window {} after closing window",
-                                    next_window_id
-                                );
-                                // Use the public focus_window method which updates workspace tracking
-                                self.focus_window(&window_elem);
-
-                                // Also move cursor to the newly focused window
-                                if let Some(loc) = self.space().element_location(&window_elem) {
-                                    let geo = window_elem.geometry();
-                                    let center = smithay::utils::Point::<
-                                        f64,
-                                        smithay::utils::Logical,
-                                    >::from((
+                    // Focus next window
+                    if let Some(next_window_id) = next_focus {
+                        let focus_info = self
+                            .window_registry()
+                            .get(next_window_id)
+                            .map(|mw| (mw.element.clone(), mw.element.0.clone()));
+
+                        if let Some((window_elem, _window)) = focus_info {
+                            tracing::info!(
+                                "Focusing next window {} after closing window",
+                                next_window_id
+                            );
+                            // Use the public focus_window method which updates workspace tracking
+                            self.focus_window(&window_elem);
+
+                            // Also move cursor to the newly focused window
+                            if let Some(loc) = self.space().element_location(&window_elem) {
+                                let geo = window_elem.geometry();
+                                let center =
+                                    smithay::utils::Point::<f64, smithay::utils::Logical>::from((
                                         (loc.x + geo.size.w / 2) as f64,
                                         (loc.y + geo.size.h / 2) as f64,
                                     ));
-                                    self.pointer().set_location(center);
-                                }
-
-                                // Verify focus was set
-                                let new_focus = self.focused_window();
-                                tracing::info!(
-                                    "Focus after transfer: {:?}",
-                                    new_focus
-                                        .as_ref()
-                                        .and_then(|w| self.window_registry().find_by_element(w))
-                                );
-                            } else {
-                                tracing::warn!(
-                                    "Could not find window element for window_id {}",
-                                    next_window_id
-                                );
+                                self.pointer().set_location(center);
                             }
+
+                            // Verify focus was set
+                            let new_focus = self.focused_window();
+                            tracing::info!(
+                                "Focus after transfer: {:?}",
+                                new_focus
+                                    .as_ref()
+                                    .and_then(|w| self.window_registry().find_by_element(w))
+                            );
                         } else {
-                            // No window to focus, clear keyboard focus
-                            tracing::debug!(
-                                "No window to focus after closing window, clearing keyboard focus"
+                            tracing::warn!(
+                                "Could not find window element for window_id {}",
+                                next_window_id
+                            );
+                        }
+                    } else {
+                        // No window to focus, clear keyboard focus
+                        tracing::debug!(
+                            "No window to focus after closing window, clearing keyboard focus"
+                        );
+                        if let Some(keyboard) = self.seat().get_keyboard() {
+                            keyboard.set_focus(
+                                self,
+                                None,
+                                smithay::utils::SERIAL_COUNTER.next_serial(),
                             );
-                            if let Some(keyboard) = self.seat().get_keyboard() {
-                                keyboard.set_focus(
-                                    self,
-                                    None,
-                                    smithay::utils::SERIAL_COUNTER.next_serial(),
-                                );
-                            }
                         }
                     }
                 }
@@ -483,8 +472,25 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
                 }
             }
 
-            // In a tiling WM, we never use server-side decorations
-            // The decoration mode is always forced to client-side in the XdgDecorationHandler
+            // The client acked the negotiated decoration mode, draw ours only
+            // for windows that don't decorate themselves
+            let is_ssd = configure
+                .state
+                .decoration_mode
+                .is_some_and(|mode| mode == zxdg_toplevel_decoration_v1::Mode::ServerSide);
+            let changed = self
+                .window_registry()
+                .find_by_surface(&surface)
+                .and_then(|id| self.window_registry().get(id))
+                .filter(|managed_window| managed_window.element.is_server_side() != is_ssd)
+                .map(|managed_window| {
+                    managed_window.element.set_ssd(is_ssd);
+                    managed_window.workspace
+                });
+            // The window gains or loses its border and header bar, size it again
+            if let Some(workspace_id) = changed {
+                self.apply_workspace_layout(workspace_id);
+            }
         }
     }
 
@@ -493,37 +499,38 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
         surface: ToplevelSurface,
         wl_output: Option<wl_output::WlOutput>,
     ) {
-        if surface
-            .current_state()
-            .capabilities
-            .contains(xdg_toplevel::WmCapabilities::Fullscreen)
-        {
-            let wl_surface = surface.wl_surface();
-
-            // Find the window element
-            let window = self
-                .space()
-                .elements()
-                .find(|window| {
-                    window
-                        .wl_surface()
-                        .map(|s| &*s == wl_surface)
-                        .unwrap_or(false)
-                })
-                .cloned();
-
-            if let Some(window) = window {
-                if let Some(window_id) = self.window_registry().find_by_element(&window) {
-                    // Determine fullscreen mode based on whether output was specified
-                    let mode = if wl_output.is_some() {
-                        crate::window::FullscreenMode::PhysicalOutput
-                    } else {
-                        crate::window::FullscreenMode::VirtualOutput
-                    };
+        let wl_surface = surface.wl_surface();
 
-                    self.set_window_fullscreen(window_id, true, mode);
-                    return;
+        // The window may be on a hidden workspace, so it is looked up by its
+        // surface rather than in the space
+        let window_id = self.window_registry().find_by_surface(wl_surface);
+        let element = window_id
+            .and_then(|id| self.window_registry().get(id))
+            .map(|mw| mw.element.clone());
+
+        if let Some((window_id, element)) = window_id.zip(element) {
+            // Before the initial commit, an assign rule may still move the
+            // window, so the request waits for it
+            if self.initial_commit_pending(&element) {
+                self.defer_fullscreen_request(wl_surface, wl_output);
+                return;
+            }
+            if surface
+                .current_state()
+                .capabilities
+                .contains(xdg_toplevel::WmCapabilities::Fullscreen)
+            {
+                // A named output gets the window fullscreen on that
+                // monitor; otherwise it fills its virtual output
+                match wl_output {
+                    Some(wl_output) => self.fullscreen_on_requested_output(window_id, &wl_output),
+                    None => self.set_window_fullscreen(
+                        window_id,
+                        true,
+                        crate::window::FullscreenMode::VirtualOutput,
+                    ),
                 }
+                return;
             }
         }
 
@@ -537,6 +544,7 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
     }
 
     fn unfullscreen_request(&mut self, surface: ToplevelSurface) {
+        self.cancel_fullscreen_request(surface.wl_surface());
         if !surface
             .current_state()
             .states
@@ -545,30 +553,14 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
             return;
         }
 
-        let wl_surface = surface.wl_surface();
-
-        // Find the window element
-        let window = self
-            .space()
-            .elements()
-            .find(|window| {
-                window
-                    .wl_surface()
-                    .map(|s| &*s == wl_surface)
-                    .unwrap_or(false)
-            })
-            .cloned();
-
-        if let Some(window) = window {
-            if let Some(window_id) = self.window_registry().find_by_element(&window) {
-                // Clear fullscreen mode
-                self.set_window_fullscreen(
-                    window_id,
-                    false,
-                    crate::window::FullscreenMode::VirtualOutput,
-                );
-                return;
-            }
+        if let Some(window_id) = self.window_registry().find_by_surface(surface.wl_surface()) {
+            // Clear fullscreen mode
+            self.set_window_fullscreen(
+                window_id,
+                false,
+                crate::window::FullscreenMode::VirtualOutput,
+            );
+            return;
         }
 
         surface.send_pending_configure();
@@ -589,13 +581,14 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
             let outputs_for_window = self.space().outputs_for_element(&window);
             let Some(output) = outputs_for_window
                 .first()
+                .cloned()
                 // The window hasn't been mapped yet, use the primary output instead
-                .or_else(|| self.space().outputs().next())
+                .or_else(|| self.primary_output())
             else {
                 tracing::error!("No outputs found for maximize request");
                 return;
             };
-            let Some(geometry) = self.space().output_geometry(output) else {
+            let Some(geometry) = self.space().output_geometry(&output) else {
                 tracing::error!("No geometry for output during maximize");
                 return;
             };
@@ -690,30 +683,75 @@ impl<BackendData: Backend> XdgShellHandler for StilchState<BackendData> {
         }
     }
 
+    fn parent_changed(&mut self, surface: ToplevelSurface) {
+        // A window that gains a parent after mapping is a dialog; float it like i3 does
+        if surface.parent().is_some() {
+            self.reevaluate_floating(surface.wl_surface());
+        }
+    }
+
     fn title_changed(&mut self, surface: ToplevelSurface) {
         // The title is already stored in the XdgToplevelSurfaceData by Smithay
-        // We just need to log it and trigger any necessary updates
         tracing::info!(
             "Window title changed for surface {:?}",
             surface.wl_surface().id()
         );
 
-        // TODO: Trigger tab bar update if this window is in a tabbed container
+        self.check_title_change(surface.wl_surface());
     }
 
     fn app_id_changed(&mut self, surface: ToplevelSurface) {
         // The app_id is already stored in the XdgToplevelSurfaceData by Smithay
-        // We just need to log it and trigger any necessary updates
         tracing::info!(
             "Window app_id changed for surface {:?}",
             surface.wl_surface().id()
         );
 
-        // TODO: Trigger tab bar update if this window is in a tabbed container
+        self.check_title_change(surface.wl_surface());
     }
 }
 
 impl<BackendData: Backend> StilchState<BackendData> {
+    /// Notice a new title or app_id of the window of `surface`: announce it
+    /// on the event bus and redraw its tab, as title_format may show either
+    pub(crate) fn check_title_change(&mut self, surface: &WlSurface) {
+        let Some(window_id) = self.window_registry().find_by_surface(surface) else {
+            return;
+        };
+        let Some(managed) = self.window_registry_mut().get_mut(window_id) else {
+            return;
+        };
+
+        let mut events = Vec::new();
+        let title = managed.element.title();
+        if title != managed.last_title {
+            managed.last_title = title.clone();
+            events.push(WindowEvent::TitleChanged {
+                window_id,
+                title,
+                timestamp: Instant::now(),
+            });
+        }
+        let app_id = managed.element.app_id();
+        if app_id != managed.last_app_id {
+            managed.last_app_id = app_id.clone();
+            events.push(WindowEvent::AppIdChanged {
+                window_id,
+                app_id,
+                timestamp: Instant::now(),
+            });
+        }
+        if events.is_empty() {
+            return;
+        }
+
+        for event in events {
+            self.event_bus.emit_window(event);
+        }
+        self.tab_text_cache.invalidate_window(window_id);
+        self.backend_data.request_render();
+    }
+
     fn debug_dump_all_windows(&self) {
         tracing::debug!("============ WINDOW STATE DUMP ============");
         tracing::debug!("Total space elements: {}", self.space().elements().count());
@@ -908,49 +946,67 @@ impl<BackendData: Backend> StilchState<BackendData> {
         pointer.set_grab(self, grab, serial, Focus::Clear);
     }
 
-    fn unconstrain_popup(&self, popup: &PopupSurface) {
-        let Ok(root) = find_popup_root_surface(&PopupKind::Xdg(popup.clone())) else {
-            return;
-        };
-        let Some(window) = self.window_for_surface(&root) else {
+    /// Flip, slide or resize a popup as its positioner allows, so that it
+    /// stays on the virtual output its anchor is on
+    pub(super) fn unconstrain_popup(&self, popup: &PopupSurface) {
+        let kind = PopupKind::Xdg(popup.clone());
+        let Ok(root) = find_popup_root_surface(&kind) else {
             return;
         };
 
-        let mut outputs_for_window = self.space().outputs_for_element(&window);
-        if outputs_for_window.is_empty() {
-            return;
-        }
-
-        // Get a union of all outputs' geometries.
-        let Some(first_output) = outputs_for_window.pop() else {
-            tracing::error!("outputs_for_window was empty after check");
-            return;
-        };
-        let Some(mut outputs_geo) = self.space().output_geometry(&first_output) else {
-            tracing::error!("No geometry for output");
+        // Where the root surface's window geometry is, in global coordinates
+        let root_loc = if let Some(window) = self.window_for_surface(&root) {
+            let Some(window_geo) = self.space().element_geometry(&window) else {
+                tracing::error!("No geometry for window");
+                return;
+            };
+            window_geo.loc
+        } else if let Some(loc) = self.layer_surface_location(&root) {
+            loc
+        } else {
             return;
         };
-        for output in outputs_for_window {
-            if let Some(geo) = self.space().output_geometry(&output) {
-                outputs_geo = outputs_geo.merge(geo);
-            }
-        }
+        let parent_loc = root_loc + get_popup_toplevel_coords(&kind);
 
-        let Some(window_geo) = self.space().element_geometry(&window) else {
-            tracing::error!("No geometry for window");
+        let anchor = popup.with_pending_state(|state| state.positioner.anchor_rect);
+        let anchor_center =
+            parent_loc + anchor.loc + Point::from((anchor.size.w / 2, anchor.size.h / 2));
+        let Some(bounds) = self.popup_bounds(anchor_center) else {
             return;
         };
 
-        // The target geometry for the positioner should be relative to its parent's geometry, so
-        // we will compute that here.
-        let mut target = outputs_geo;
-        target.loc -= get_popup_toplevel_coords(&PopupKind::Xdg(popup.clone()));
-        target.loc -= window_geo.loc;
+        // The target geometry for the positioner should be relative to its parent's geometry
+        let mut target = bounds;
+        target.loc -= parent_loc;
 
         popup.with_pending_state(|state| {
             state.geometry = state.positioner.get_unconstrained_geometry(target);
         });
     }
+
+    /// The area a popup anchored at `point` has to fit in: the virtual output
+    /// there, or the physical output if no virtual output covers it
+    fn popup_bounds(&self, point: Point<i32, Logical>) -> Option<Rectangle<i32, Logical>> {
+        if let Some(vo) = self
+            .virtual_output_manager
+            .virtual_output_at(point)
+            .and_then(|id| self.virtual_output_manager.get(id))
+        {
+            return Some(vo.logical_region());
+        }
+        let output = self.space().output_under(point.to_f64()).next()?;
+        self.space().output_geometry(output)
+    }
+
+    /// Global location of a layer surface
+    fn layer_surface_location(&self, surface: &WlSurface) -> Option<Point<i32, Logical>> {
+        self.space().outputs().find_map(|output| {
+            let map = layer_map_for_output(output);
+            let layer = map.layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)?;
+            let layer_geo = map.layer_geometry(layer)?;
+            Some(self.space().output_geometry(output)?.loc + layer_geo.loc)
+        })
+    }
 }
 
 /// Should be called on `WlSurface::commit` of xdg toplevel
@@ -0,0 +1,79 @@
+//! Binding modes and keyboard resizing
+//!
+//! `mode "<name>"` swaps the top-level bindings for those of the config's
+//! `mode "<name>" { ... }` block until `mode "default"`, like i3. Escape and
+//! Enter leave any mode the block doesn't bind them in, and in resize mode
+//! the arrow keys resize unless the block binds them. Right and Down grow the
+//! focused window, Left and Up shrink it. A tiled window trades space with
+//! its neighbour in the nearest split running that way only; a floating
+//! window changes size, keeping its top-left corner.
+
+use smithay::utils::Size;
+use tracing::{debug, warn};
+
+use super::{Backend, StilchState};
+use crate::{config::Direction, ipc::i3compat::I3Event, workspace::layout::MIN_WINDOW_SIZE};
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Switch to the bindings of the mode called `name`, or back to the
+    /// top-level ones for "default"
+    pub fn set_binding_mode(&mut self, name: &str) {
+        let mode = (name != "default").then(|| name.to_string());
+        if mode == self.binding_mode {
+            return;
+        }
+        if !self.config.binding_modes().contains(&name) {
+            warn!("No mode \"{name}\" in the config");
+            return;
+        }
+        debug!("Binding mode {name}");
+        self.binding_mode = mode;
+        if let Some(ipc_server) = &self.ipc_server {
+            ipc_server.send_i3_event(I3Event::Mode {
+                change: name.to_string(),
+            });
+        }
+        // The status bar shows the mode
+        self.backend_data.request_render();
+    }
+
+    /// Grow the focused window by `delta` pixels along `direction`'s axis,
+    /// or shrink it for a negative `delta`
+    pub fn resize_focused_by(&mut self, direction: Direction, delta: i32) {
+        if let Some((_, geometry)) = self.focused_floating() {
+            let size = match direction {
+                Direction::Left | Direction::Right => Size::from((
+                    (geometry.size.w + delta).max(MIN_WINDOW_SIZE),
+                    geometry.size.h,
+                )),
+                Direction::Up | Direction::Down => Size::from((
+                    geometry.size.w,
+                    (geometry.size.h + delta).max(MIN_WINDOW_SIZE),
+                )),
+            };
+            self.resize_floating(size);
+            return;
+        }
+
+        let Some(window_id) = self
+            .focused_window()
+            .and_then(|window| self.window_registry().find_by_element(&window))
+        else {
+            return;
+        };
+        let Some(workspace_id) = self.workspace_manager.find_window_workspace(window_id) else {
+            return;
+        };
+        let resized = self
+            .workspace_manager
+            .get_mut(workspace_id)
+            .is_some_and(|workspace| workspace.resize_focused(direction, delta));
+        if resized {
+            debug!(
+                "Resized window {} by {} along {:?}",
+                window_id, delta, direction
+            );
+            self.apply_workspace_layout(workspace_id);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Keyboard placement of floating windows
+//!
+//! `move position center|mouse|<x> <y>` and `resize set <w> <h>` place the
+//! focused floating window without the mouse. Positions are relative to the
+//! window's virtual output unless `absolute` is given, in which case a window
+//! moved onto another virtual output goes to the workspace shown there.
+//! Windows are kept inside the usable area far enough that their top edge
+//! can still be grabbed.
+
+use smithay::utils::{Logical, Point, Rectangle, Size};
+use tracing::debug;
+
+use super::{window_rules::center_in, Backend, StilchState};
+use crate::{config::FloatingPosition, window::WindowId};
+
+/// How much of a floating window stays on screen along each axis
+const MIN_VISIBLE: i32 = 32;
+
+/// Keep the top edge of `geometry` inside `area`, with at least
+/// `MIN_VISIBLE` pixels of the window showing each way
+pub(super) fn clamp_to_area(
+    mut geometry: Rectangle<i32, Logical>,
+    area: Rectangle<i32, Logical>,
+) -> Rectangle<i32, Logical> {
+    let visible = MIN_VISIBLE.min(geometry.size.w).min(area.size.w);
+    let min_x = area.loc.x - geometry.size.w + visible;
+    let max_x = area.loc.x + area.size.w - visible;
+    geometry.loc.x = geometry.loc.x.clamp(min_x, max_x.max(min_x));
+    let visible = MIN_VISIBLE.min(geometry.size.h).min(area.size.h);
+    let max_y = area.loc.y + area.size.h - visible;
+    geometry.loc.y = geometry.loc.y.clamp(area.loc.y, max_y.max(area.loc.y));
+    geometry
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// The focused window with its floating geometry, if it floats
+    pub(super) fn focused_floating(&self) -> Option<(WindowId, Rectangle<i32, Logical>)> {
+        let window_id = self
+            .focused_window()
+            .and_then(|window| self.window_registry().find_by_element(&window))?;
+        let managed = self.window_registry().get(window_id)?;
+        if !managed.is_floating() {
+            debug!("Window {} is not floating", window_id);
+            return None;
+        }
+        Some((window_id, managed.geometry()))
+    }
+
+    /// Move the focused floating window
+    pub fn move_floating(&mut self, position: FloatingPosition) {
+        let Some((window_id, mut geometry)) = self.focused_floating() else {
+            return;
+        };
+        let Some(workspace_id) = self.window_registry().get(window_id).map(|mw| mw.workspace)
+        else {
+            return;
+        };
+        let Some(vo_id) = self.workspace_manager.find_workspace_location(workspace_id) else {
+            return;
+        };
+        let Some(region) = self
+            .virtual_output_manager
+            .get(vo_id)
+            .map(|vo| vo.logical_region())
+        else {
+            return;
+        };
+        let area = self
+            .workspace_manager
+            .get(workspace_id)
+            .map_or(region, |ws| ws.area);
+
+        geometry.loc = match position {
+            FloatingPosition::Center => center_in(geometry.size, area),
+            FloatingPosition::Cursor => {
+                let pointer = self.pointer_location();
+                Point::from((
+                    pointer.x - geometry.size.w / 2,
+                    pointer.y - geometry.size.h / 2,
+                ))
+            }
+            FloatingPosition::Position { x, y, absolute } => {
+                let position = Point::from((x, y));
+                if absolute {
+                    position
+                } else {
+                    region.loc + position
+                }
+            }
+        };
+
+        // A window moved onto another virtual output belongs to the
+        // workspace shown there
+        let target = self
+            .virtual_output_manager
+            .virtual_output_at(geometry.loc)
+            .filter(|&target| target != vo_id)
+            .and_then(|target| {
+                let workspace = self.workspace_manager.workspace_on_output(target)?;
+                let area = self.workspace_manager.get(workspace)?.area;
+                Some((workspace, area))
+            });
+        let area = target.map_or(area, |(_, area)| area);
+        let geometry = clamp_to_area(geometry, area);
+        debug!("Moving floating window {} to {:?}", window_id, geometry.loc);
+        self.set_floating_geometry(window_id, geometry);
+        match target {
+            Some((workspace, _)) => {
+                self.move_window_to_workspace_by_id(window_id, workspace);
+                // Moving hands focus to what is left behind; the window is
+                // still on screen, so it keeps it
+                let element = self
+                    .window_registry()
+                    .get(window_id)
+                    .map(|mw| mw.element.clone());
+                if let Some(element) = element {
+                    self.focus_window(&element);
+                }
+            }
+            None => self.apply_workspace_layout(workspace_id),
+        }
+    }
+
+    /// Resize the focused floating window, keeping its top-left corner
+    pub fn resize_floating(&mut self, size: Size<i32, Logical>) {
+        let Some((window_id, mut geometry)) = self.focused_floating() else {
+            return;
+        };
+        let Some(workspace_id) = self.window_registry().get(window_id).map(|mw| mw.workspace)
+        else {
+            return;
+        };
+        let Some(area) = self.workspace_manager.get(workspace_id).map(|ws| ws.area) else {
+            return;
+        };
+
+        geometry.size = size;
+        let geometry = clamp_to_area(geometry, area);
+        debug!("Resizing floating window {} to {:?}", window_id, size);
+        self.set_floating_geometry(window_id, geometry);
+        self.apply_workspace_layout(workspace_id);
+    }
+}
@@ -0,0 +1,99 @@
+//! Reopening floating windows where they were last left
+//!
+//! With `remember_window_geometry yes`, moving or resizing a floating window
+//! records its size and its position within the workspace, keyed by app_id
+//! (X11 class for XWayland windows). A new window of the same app that
+//! floats opens with that geometry, kept inside its workspace. Dialogs are
+//! left out both ways, since they are placed over their parent. The memory
+//! lasts until the compositor exits.
+
+use smithay::utils::{Logical, Rectangle};
+use tracing::debug;
+
+use super::{floating::clamp_to_area, Backend, StilchState};
+use crate::{
+    shell::WindowElement,
+    window::{WindowCriteria, WindowId},
+};
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Key a window's geometry is remembered under, None for windows whose
+    /// geometry isn't remembered
+    fn floating_memory_key(&self, window: &WindowElement) -> Option<String> {
+        if !self.config.remember_window_geometry || self.parent_window_id(window).is_some() {
+            return None;
+        }
+        WindowCriteria::from_element(window)
+            .app()
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Record where a floating window was moved or resized to, so later
+    /// relayouts keep it there and, if enabled, the next window of its app
+    /// opens there
+    pub fn set_floating_geometry(
+        &mut self,
+        window_id: WindowId,
+        geometry: Rectangle<i32, Logical>,
+    ) {
+        self.window_manager
+            .set_floating_geometry(window_id, geometry);
+
+        let Some(managed) = self.window_registry().get(window_id) else {
+            return;
+        };
+        if !managed.is_floating() {
+            return;
+        }
+        let Some(key) = self.floating_memory_key(&managed.element) else {
+            return;
+        };
+        let Some(area) = self
+            .workspace_manager
+            .get(managed.workspace)
+            .map(|ws| ws.area)
+        else {
+            return;
+        };
+
+        let relative = Rectangle::new(geometry.loc - area.loc, geometry.size);
+        self.floating_geometries.insert(key, relative);
+    }
+
+    /// Give a new floating window the geometry last remembered for its app.
+    /// Returns whether there was one.
+    pub(crate) fn restore_floating_geometry(&mut self, window_id: WindowId) -> bool {
+        let Some(managed) = self.window_registry().get(window_id) else {
+            return false;
+        };
+        if !managed.is_floating() {
+            return false;
+        }
+        let Some(relative) = self
+            .floating_memory_key(&managed.element)
+            .and_then(|key| self.floating_geometries.get(&key).copied())
+        else {
+            return false;
+        };
+        let Some(area) = self
+            .workspace_manager
+            .get(managed.workspace)
+            .map(|ws| ws.area)
+        else {
+            return false;
+        };
+
+        let mut geometry = Rectangle::new(area.loc + relative.loc, relative.size);
+        geometry.size.w = geometry.size.w.min(area.size.w);
+        geometry.size.h = geometry.size.h.min(area.size.h);
+        let geometry = clamp_to_area(geometry, area);
+        debug!(
+            "Opening floating window {} at remembered {:?}",
+            window_id, geometry
+        );
+        self.window_manager
+            .set_floating_geometry(window_id, geometry);
+        true
+    }
+}
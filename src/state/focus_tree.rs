@@ -0,0 +1,204 @@
+//! Walking focus along the layout tree
+//!
+//! `focus parent` selects the container holding the focused window, or the
+//! one holding the selected container, so layout commands act on all of it.
+//! `focus child` goes back down towards the focused window. `focus next` and
+//! `focus prev` step through the children of the same container in tree
+//! order, wrapping around, which in a tabbed or stacked container means
+//! through its tabs. Stepping from a window onto a container focuses the
+//! window active in it; stepping from a selected container selects the
+//! sibling container.
+//!
+//! A selected container is outlined, and `move` moves all of it, within its
+//! workspace or to another workspace or output, keeping its layout. Moving
+//! it to the scratchpad isn't supported, so that command fails while a
+//! container is selected rather than take the focused window out of it.
+
+use tracing::debug;
+
+use super::{Backend, StilchState};
+use crate::{
+    config::{Direction, SiblingDirection},
+    window::{ContainerId, WindowId},
+    workspace::{layout::NodeKey, WorkspaceId},
+};
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// The focused tiled window, its workspace and what is selected there:
+    /// a container chosen with `focus parent`, or else the window itself
+    fn tree_selection(&self) -> Option<(WindowId, WorkspaceId, NodeKey)> {
+        let window_id = self
+            .focused_window()
+            .and_then(|window| self.window_registry().find_by_element(&window))?;
+        let workspace_id = self.workspace_manager.find_window_workspace(window_id)?;
+        let workspace = self.workspace_manager.get_workspace(workspace_id)?;
+        if workspace.is_floating(window_id) {
+            return None;
+        }
+        let selected = workspace
+            .focused_container
+            .map_or(NodeKey::Window(window_id), NodeKey::Container);
+        Some((window_id, workspace_id, selected))
+    }
+
+    /// The container selected with `focus parent` and its workspace, if
+    /// the focused window isn't selected by itself
+    pub fn selected_container(&self) -> Option<(WorkspaceId, ContainerId)> {
+        match self.tree_selection()? {
+            (_, workspace_id, NodeKey::Container(container)) => Some((workspace_id, container)),
+            (_, _, NodeKey::Window(_)) => None,
+        }
+    }
+
+    /// Move the selected container one step the way of `direction`
+    pub fn move_selected_container(&mut self, direction: Direction) {
+        let Some((workspace_id, container)) = self.selected_container() else {
+            return;
+        };
+        let moved = self
+            .workspace_manager
+            .get_workspace_mut(workspace_id)
+            .is_some_and(|workspace| workspace.layout.move_container(container, direction));
+        if moved {
+            debug!("Moved container {:?} {:?}", container, direction);
+            self.apply_workspace_layout(workspace_id);
+        }
+    }
+
+    /// Move the selected container to another workspace, keeping its
+    /// layout. Focus stays on the workspace it left, as with a window.
+    pub fn move_selected_container_to_workspace(&mut self, target_workspace_id: WorkspaceId) {
+        let Some((source_workspace_id, container)) = self.selected_container() else {
+            return;
+        };
+        if source_workspace_id == target_workspace_id {
+            return;
+        }
+        let Some(detached) = self
+            .workspace_manager
+            .get_workspace_mut(source_workspace_id)
+            .and_then(|workspace| workspace.take_container(container))
+        else {
+            return;
+        };
+
+        let windows = detached.windows();
+        for &window_id in &windows {
+            let element = self
+                .window_registry()
+                .get(window_id)
+                .map(|mw| mw.element.clone());
+            if let Some(element) = element {
+                self.space_mut().unmap_elem(&element);
+            }
+            self.window_registry_mut()
+                .set_workspace(window_id, target_workspace_id);
+        }
+        if let Some(workspace) = self
+            .workspace_manager
+            .get_workspace_mut(target_workspace_id)
+        {
+            workspace.add_container(detached);
+        }
+        debug!(
+            "Moved container {:?} with {} windows from workspace {} to {}",
+            container,
+            windows.len(),
+            source_workspace_id,
+            target_workspace_id
+        );
+
+        for workspace_id in [source_workspace_id, target_workspace_id] {
+            if self
+                .workspace_manager
+                .get_workspace(workspace_id)
+                .is_some_and(|workspace| workspace.is_visible())
+            {
+                self.apply_workspace_layout(workspace_id);
+            }
+        }
+        let next_focus = self
+            .workspace_manager
+            .get_workspace(source_workspace_id)
+            .and_then(|workspace| workspace.focused_window)
+            .and_then(|id| self.window_registry().get(id))
+            .map(|mw| mw.element.clone());
+        if let Some(element) = next_focus {
+            self.focus_window(&element);
+        }
+        self.update_ipc_workspace_state();
+    }
+
+    /// Select the container holding the focused window or container
+    pub fn focus_parent(&mut self) {
+        let Some((_, workspace_id, selected)) = self.tree_selection() else {
+            return;
+        };
+        let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) else {
+            return;
+        };
+        match workspace.layout.parent_of(selected) {
+            Some(parent) => {
+                debug!("Selected container {:?}", parent);
+                workspace.focused_container = Some(parent);
+                // Outline the selection
+                self.backend_data.request_render();
+            }
+            None => debug!("{:?} has no parent container", selected),
+        }
+    }
+
+    /// Go one level down from the selected container, towards the focused
+    /// window
+    pub fn focus_child(&mut self) {
+        let Some((window_id, workspace_id, NodeKey::Container(container))) = self.tree_selection()
+        else {
+            return;
+        };
+        let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) else {
+            return;
+        };
+        match workspace.layout.child_towards(container, window_id) {
+            Some(NodeKey::Container(child)) => {
+                debug!("Selected container {:?}", child);
+                workspace.focused_container = Some(child);
+                self.backend_data.request_render();
+            }
+            Some(NodeKey::Window(child)) => {
+                workspace.focused_container = None;
+                self.activate_window(child);
+                self.backend_data.request_render();
+            }
+            None => {}
+        }
+    }
+
+    /// Step to the next or previous sibling of the focused window or
+    /// selected container
+    pub fn focus_sibling(&mut self, direction: SiblingDirection) {
+        let Some((_, workspace_id, selected)) = self.tree_selection() else {
+            return;
+        };
+        let Some(workspace) = self.workspace_manager.get_workspace(workspace_id) else {
+            return;
+        };
+        let next = direction == SiblingDirection::Next;
+        let Some(sibling) = workspace.layout.sibling_of(selected, next) else {
+            debug!("{:?} has no siblings", selected);
+            return;
+        };
+        let Some(window_id) = workspace.layout.focus_leaf(sibling) else {
+            return;
+        };
+
+        self.activate_window(window_id);
+        // Focusing the window dropped the selection; a container stepped to
+        // from a container stays selected as a whole
+        if let (NodeKey::Container(_), NodeKey::Container(container)) = (selected, sibling) {
+            if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
+                workspace.focused_container = Some(container);
+            }
+        }
+        self.backend_data.request_render();
+    }
+}
@@ -0,0 +1,87 @@
+//! Fullscreen on the output a client asks for
+//!
+//! `xdg_toplevel.set_fullscreen` may name a `wl_output`, like a media
+//! player's "play on TV". The window then moves to the workspace shown on
+//! that output, unless it is there already, and goes fullscreen on that
+//! physical output. An output that is gone by the time the request arrives
+//! falls back to the output the window is on.
+
+use smithay::{output::Output, reexports::wayland_server::protocol::wl_output::WlOutput};
+use tracing::{debug, info};
+
+use super::{Backend, StilchState};
+use crate::{
+    virtual_output::VirtualOutputId,
+    window::{FullscreenMode, WindowId},
+};
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Fullscreen a window on the output its client asked for
+    pub fn fullscreen_on_requested_output(&mut self, window_id: WindowId, wl_output: &WlOutput) {
+        let Some(output) = Output::from_resource(wl_output) else {
+            debug!("Fullscreen requested on an output that is gone");
+            self.set_fullscreen(window_id, true, FullscreenMode::PhysicalOutput);
+            return;
+        };
+        let Some(workspace_id) = self
+            .virtual_output_for_request(window_id, &output)
+            .and_then(|vo_id| self.workspace_manager.workspace_on_output(vo_id))
+        else {
+            debug!("No workspace shown on {}", output.name());
+            self.set_fullscreen(window_id, true, FullscreenMode::PhysicalOutput);
+            return;
+        };
+
+        // Leaving fullscreen elsewhere first restores the window's place
+        // there, which it then carries along
+        self.set_fullscreen(window_id, false, FullscreenMode::PhysicalOutput);
+        let current = self
+            .window_registry()
+            .get(window_id)
+            .map(|managed| managed.workspace);
+        if current != Some(workspace_id) {
+            info!(
+                "Moving window {} to workspace {} to fullscreen it on {}",
+                window_id,
+                workspace_id,
+                output.name()
+            );
+            self.move_window_to_workspace_by_id(window_id, workspace_id);
+        }
+        self.set_window_fullscreen_on(
+            window_id,
+            true,
+            FullscreenMode::PhysicalOutput,
+            Some(output),
+        );
+
+        let element = self
+            .window_registry()
+            .get(window_id)
+            .map(|managed| managed.element.clone());
+        if let Some(element) = element {
+            self.focus_window(&element);
+        }
+    }
+
+    /// Virtual output of `output` to put the window on: the one the window
+    /// is already on if `output` is split, else the first
+    fn virtual_output_for_request(
+        &self,
+        window_id: WindowId,
+        output: &Output,
+    ) -> Option<VirtualOutputId> {
+        let candidates = self
+            .virtual_output_manager
+            .virtual_outputs_for_physical(output);
+        let current = self
+            .window_registry()
+            .get(window_id)
+            .and_then(|managed| {
+                self.workspace_manager
+                    .find_workspace_location(managed.workspace)
+            })
+            .filter(|vo_id| candidates.contains(vo_id));
+        current.or_else(|| candidates.iter().min().copied())
+    }
+}
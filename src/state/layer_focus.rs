@@ -0,0 +1,160 @@
+//! Keyboard focus for layer-shell surfaces
+//!
+//! Layer surfaces ask for keyboard input through `keyboard_interactivity`:
+//! `exclusive` surfaces on the top or overlay layer (launchers, lock-adjacent
+//! prompts) take focus as soon as they are mapped and keep it until they go
+//! away, `on_demand` surfaces are focused when clicked. Whatever had focus
+//! before a layer surface took it gets it back when that surface is
+//! destroyed or stops asking for input.
+
+use smithay::{
+    desktop::{layer_map_for_output, LayerSurface, WindowSurfaceType},
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
+    utils::{IsAlive, SERIAL_COUNTER},
+    wayland::{
+        compositor::with_states,
+        shell::wlr_layer::{KeyboardInteractivity, Layer, LayerSurfaceCachedState},
+    },
+};
+use tracing::debug;
+
+use super::{Backend, StilchState};
+use crate::focus::KeyboardFocusTarget;
+
+fn layer_state(layer: &LayerSurface) -> LayerSurfaceCachedState {
+    with_states(layer.wl_surface(), |states| {
+        *states
+            .cached_state
+            .get::<LayerSurfaceCachedState>()
+            .current()
+    })
+}
+
+/// Whether a layer surface grabs all keyboard input while it is mapped
+fn is_exclusive(state: &LayerSurfaceCachedState) -> bool {
+    state.keyboard_interactivity == KeyboardInteractivity::Exclusive
+        && matches!(state.layer, Layer::Top | Layer::Overlay)
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// The mapped layer surface `surface` belongs to, including its subsurfaces
+    pub fn layer_for_surface(&self, surface: &WlSurface) -> Option<LayerSurface> {
+        self.space().outputs().find_map(|output| {
+            layer_map_for_output(output)
+                .layer_for_surface(surface, WindowSurfaceType::ALL)
+                .cloned()
+        })
+    }
+
+    /// The layer surface holding an exclusive keyboard grab, overlay before top
+    pub fn exclusive_layer(&self) -> Option<LayerSurface> {
+        let mut top = None;
+        for output in self.space().outputs() {
+            for layer in layer_map_for_output(output).layers() {
+                let state = layer_state(layer);
+                if !is_exclusive(&state) {
+                    continue;
+                }
+                if state.layer == Layer::Overlay {
+                    return Some(layer.clone());
+                }
+                top.get_or_insert_with(|| layer.clone());
+            }
+        }
+        top
+    }
+
+    fn layer_has_focus(&self, layer: &LayerSurface) -> bool {
+        self.seat()
+            .get_keyboard()
+            .and_then(|keyboard| keyboard.current_focus())
+            .is_some_and(|focus| focus == KeyboardFocusTarget::LayerSurface(layer.clone()))
+    }
+
+    /// Give keyboard focus to a layer surface, remembering what had it
+    pub fn focus_layer_surface(&mut self, layer: &LayerSurface) {
+        let Some(keyboard) = self.seat().get_keyboard() else {
+            return;
+        };
+        let current = keyboard.current_focus();
+        if current == Some(KeyboardFocusTarget::LayerSurface(layer.clone())) {
+            return;
+        }
+        // Moving between layer surfaces keeps the window to return to
+        if !matches!(current, Some(KeyboardFocusTarget::LayerSurface(_))) {
+            self.input_manager.focus_before_layer = current;
+        }
+        debug!(namespace = layer.namespace(), "Focusing layer surface");
+        keyboard.set_focus(
+            self,
+            Some(KeyboardFocusTarget::LayerSurface(layer.clone())),
+            SERIAL_COUNTER.next_serial(),
+        );
+    }
+
+    /// Hand keyboard focus back to what had it before a layer surface took it
+    fn restore_focus_after_layer(&mut self) {
+        // Another exclusive surface still wants the keyboard
+        if let Some(layer) = self.exclusive_layer() {
+            self.focus_layer_surface(&layer);
+            return;
+        }
+
+        let previous = self
+            .input_manager
+            .focus_before_layer
+            .take()
+            .filter(IsAlive::alive);
+        if let Some(KeyboardFocusTarget::Window(window)) = &previous {
+            let element = self
+                .space()
+                .elements()
+                .find(|element| &element.0 == window)
+                .cloned();
+            if let Some(element) = element {
+                self.focus_window(&element);
+                return;
+            }
+        }
+        if let Some(keyboard) = self.seat().get_keyboard() {
+            keyboard.set_focus(self, previous, SERIAL_COUNTER.next_serial());
+        }
+    }
+
+    /// Apply a layer surface's keyboard interactivity after it committed.
+    /// Of several exclusive surfaces, the one `exclusive_layer` picks gets
+    /// the keyboard, so a top surface mapping doesn't take it from an
+    /// overlay one.
+    pub fn update_layer_focus(&mut self, layer: &LayerSurface) {
+        let state = layer_state(layer);
+        if is_exclusive(&state) {
+            if let Some(exclusive) = self.exclusive_layer() {
+                self.focus_layer_surface(&exclusive);
+            }
+        } else if state.keyboard_interactivity == KeyboardInteractivity::None
+            && self.layer_has_focus(layer)
+        {
+            self.restore_focus_after_layer();
+        }
+    }
+
+    /// A clicked layer surface takes focus if it accepts keyboard input.
+    /// Returns whether it did.
+    pub fn focus_layer_on_click(&mut self, surface: &WlSurface) -> bool {
+        let Some(layer) = self.layer_for_surface(surface) else {
+            return false;
+        };
+        if layer_state(&layer).keyboard_interactivity == KeyboardInteractivity::None {
+            return false;
+        }
+        self.focus_layer_surface(&layer);
+        true
+    }
+
+    /// A layer surface was unmapped; return focus if it had it
+    pub fn layer_focus_lost(&mut self, layer: &LayerSurface) {
+        if self.layer_has_focus(layer) {
+            self.restore_focus_after_layer();
+        }
+    }
+}
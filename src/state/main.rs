@@ -1,7 +1,10 @@
 use std::{
     collections::HashMap,
     path::Path,
-    sync::{atomic::AtomicBool, Arc},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
@@ -28,7 +31,7 @@ use smithay::{
     },
     output::Output,
     reexports::{
-        calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction},
+        calloop::{self, generic::Generic, Interest, LoopHandle, Mode, PostAction},
         wayland_protocols::xdg::shell::server::xdg_toplevel,
         wayland_server::{
             backend::{ClientData, ClientId, DisconnectReason},
@@ -36,7 +39,10 @@ use smithay::{
             Client, Display, DisplayHandle, Resource,
         },
     },
-    utils::{Clock, Logical, Monotonic, Point, Rectangle, Time, SERIAL_COUNTER as SCOUNTER},
+    utils::{
+        Clock, Logical, Monotonic, Physical, Point, Rectangle, Size, Time,
+        SERIAL_COUNTER as SCOUNTER,
+    },
     wayland::{
         commit_timing::CommitTimerBarrierStateUserData,
         compositor::{CompositorClientState, CompositorHandler},
@@ -59,6 +65,7 @@ use smithay::{
 use crate::{
     command::CommandExecutor,
     config::Config,
+    error::{StilchError, StilchResult},
     event::{ipc_handler::IpcEventHandler, EventBus},
     focus::KeyboardFocusTarget, // Import handlers module
     ipc::IpcServer,
@@ -78,12 +85,21 @@ use smithay::{
 pub struct ClientState {
     pub compositor_state: CompositorClientState,
     pub security_context: Option<SecurityContext>,
+    /// Set once the client has disconnected
+    pub disconnected: AtomicBool,
+    /// Tells the event loop when the client disconnects
+    pub disconnect_notifier: Option<calloop::channel::Sender<ClientId>>,
 }
 impl ClientData for ClientState {
     /// Notification that a client was initialized
     fn initialized(&self, _client_id: ClientId) {}
     /// Notification that a client is disconnected
-    fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
+    fn disconnected(&self, client_id: ClientId, _reason: DisconnectReason) {
+        self.disconnected.store(true, Ordering::Release);
+        if let Some(notifier) = &self.disconnect_notifier {
+            let _ = notifier.send(client_id);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -99,6 +115,8 @@ pub struct StilchState<BackendData: Backend + 'static> {
     pub virtual_output_manager: VirtualOutputManager,
     pub virtual_output_exclusive_zones:
         HashMap<crate::virtual_output::VirtualOutputId, Rectangle<i32, Logical>>,
+    /// Layer surfaces created while there was no output to put them on
+    pub pending_layer_surfaces: Vec<smithay::desktop::LayerSurface>,
     pub config: Config,
     pub ipc_server: Option<Arc<IpcServer>>,
 
@@ -126,15 +144,50 @@ pub struct StilchState<BackendData: Backend + 'static> {
     // Tab text rendering cache
     pub tab_text_cache: crate::tab_bar::text_render::TabTextCache,
 
+    // Selections cached so they outlive their source client
+    pub clipboard: crate::handlers::clipboard::ClipboardPersistence,
+
     #[cfg(feature = "xwayland")]
     pub xwm: Option<X11Wm>,
     #[cfg(feature = "xwayland")]
     pub xdisplay: Option<u32>,
+    /// Commands spawned while XWayland is starting, held back until its
+    /// DISPLAY is known; `None` once it is ready or when it isn't started
+    #[cfg(feature = "xwayland")]
+    pub pending_x11_commands: Option<Vec<(std::process::Command, String)>>,
+    /// The XWayland client, whose scale follows `xwayland_scale`
+    #[cfg(feature = "xwayland")]
+    pub xwayland_client: Option<Client>,
 
     #[cfg(feature = "debug")]
     pub renderdoc: Option<renderdoc::RenderDoc<renderdoc::V141>>,
 
-    pub show_window_preview: bool,
+    /// Window overview, while it is open
+    pub overview: Option<crate::overview::Overview>,
+    /// The binding mode whose bindings apply, None for the top-level ones
+    pub binding_mode: Option<String>,
+    /// Running workspace and window animations
+    pub animations: crate::animation::Animations,
+    /// Performance overlay, while it is shown
+    pub debug_overlay: Option<crate::debug_overlay::DebugOverlay>,
+    /// Outline of the container selected with `focus parent`
+    pub selection_outline: crate::selection_outline::SelectionOutline,
+    /// focus_follows_mouse as switched by a binding, over the config value
+    pub focus_follows_mouse_override: Option<crate::input::FocusFollowsMouseOverride>,
+    pub status_bar: crate::status_bar::StatusBar,
+    /// Windows moved to the scratchpad
+    pub scratchpad: super::Scratchpad,
+    /// Windows in the order they were focused, for `focus back|forward`
+    pub focus_history: crate::focus::FocusHistory,
+    /// Window holding keyboard focus, to announce when it loses it
+    pub keyboard_focus_window: Option<crate::window::WindowId>,
+    /// Last floating geometry per app, relative to the workspace area, with
+    /// `remember_window_geometry` enabled
+    pub floating_geometries: HashMap<String, Rectangle<i32, Logical>>,
+    /// Dialogs by the window they are transient for, to stack them above it
+    pub transient_children: super::TransientChildren,
+    /// Where workspaces were last shown, with `workspace_state_file` set
+    pub workspace_state: Option<super::workspace_state::WorkspaceStatePersistence>,
     pub startup_done: std::cell::Cell<bool>,
 }
 
@@ -174,7 +227,30 @@ delegate_xwayland_keyboard_grab!(@<BackendData: Backend + 'static> StilchState<B
 #[cfg(feature = "xwayland")]
 delegate_xwayland_shell!(@<BackendData: Backend + 'static> StilchState<BackendData>);
 
-fn load_config() -> Config {
+/// Start `command`, logging what it writes to stderr if that is piped
+fn spawn_logged(mut command: std::process::Command, description: &str) {
+    match command.spawn() {
+        Ok(mut child) => {
+            info!("Started '{}' with PID: {}", description, child.id());
+
+            // Spawn a thread to log any output from the process
+            if let Some(stderr) = child.stderr.take() {
+                let description = description.to_string();
+                std::thread::spawn(move || {
+                    use std::io::{BufRead, BufReader};
+                    let reader = BufReader::new(stderr);
+                    for line in reader.lines().map_while(Result::ok) {
+                        warn!("'{description}' stderr: {line}");
+                    }
+                });
+            }
+        }
+        Err(e) => error!("Failed to start '{}': {}", description, e),
+    }
+}
+
+/// Load the config from `STILCH_CONFIG_FILE` or the usual locations
+pub fn load_config() -> Config {
     // Check if a specific config file was provided via environment variable
     if let Ok(config_file) = std::env::var("STILCH_CONFIG_FILE") {
         let path = Path::new(&config_file);
@@ -241,6 +317,23 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         handle: LoopHandle<'static, StilchState<BackendData>>,
         backend_data: BackendData,
         listen_on_socket: bool,
+    ) -> StilchState<BackendData> {
+        Self::init_with_config(
+            display,
+            handle,
+            backend_data,
+            listen_on_socket,
+            load_config(),
+        )
+    }
+
+    /// Like `init`, for backends that need the config before the state exists
+    pub fn init_with_config(
+        display: Display<StilchState<BackendData>>,
+        handle: LoopHandle<'static, StilchState<BackendData>>,
+        backend_data: BackendData,
+        listen_on_socket: bool,
+        config: Config,
     ) -> StilchState<BackendData> {
         let dh = display.handle();
 
@@ -265,12 +358,29 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                 })
             };
             let socket_name = source.socket_name().to_string_lossy().into_owned();
+
+            // Selections of clients that go away are taken over
+            let (disconnects, disconnect_channel) = calloop::channel::channel();
+            handle
+                .insert_source(disconnect_channel, |event, _, data| {
+                    if let calloop::channel::Event::Msg(client_id) = event {
+                        data.client_disconnected(client_id);
+                    }
+                })
+                .unwrap_or_else(|e| {
+                    error!("Failed to init client disconnect source: {:?}", e);
+                    std::process::exit(1);
+                });
             handle
-                .insert_source(source, |client_stream, _, data| {
+                .insert_source(source, move |client_stream, _, data| {
                     info!("New Wayland client connecting!");
+                    let client_state = ClientState {
+                        disconnect_notifier: Some(disconnects.clone()),
+                        ..Default::default()
+                    };
                     if let Err(err) = data
                         .display_handle
-                        .insert_client(client_stream, Arc::new(ClientState::default()))
+                        .insert_client(client_stream, Arc::new(client_state))
                     {
                         warn!("Error adding wayland client: {err}");
                     } else {
@@ -310,9 +420,6 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         // init globals
         let mut seat_state = SeatState::new();
 
-        // Load configuration
-        let config = load_config();
-
         // init input
         let seat_name = backend_data.seat_name();
         let mut seat = seat_state.new_wl_seat(&dh, seat_name.clone());
@@ -408,7 +515,54 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
 
         let input_manager = crate::input::InputManager::new(seat, pointer);
 
-        let inner_gap = config.gaps.inner.unwrap_or(10);
+        let inner_gap = config
+            .gaps
+            .inner
+            .unwrap_or(crate::config::GapConfig::DEFAULT_INNER);
+        let mut workspace_manager = crate::workspace::WorkspaceManager::new(inner_gap);
+        workspace_manager.set_order(
+            config
+                .workspace_order()
+                .into_iter()
+                .map(|number| crate::workspace::WorkspaceId::new(number - 1)),
+        );
+        workspace_manager.set_persistent(
+            config
+                .workspaces
+                .iter()
+                .filter(|workspace| workspace.persistent)
+                .map(|workspace| crate::workspace::WorkspaceId::new(workspace.number - 1)),
+        );
+        workspace_manager.set_output_assignments(config.workspaces.iter().filter_map(
+            |workspace| {
+                let output = workspace.output.clone()?;
+                Some((
+                    crate::workspace::WorkspaceId::new(workspace.number - 1),
+                    output,
+                ))
+            },
+        ));
+        workspace_manager.set_new_window_position(config.new_window_position);
+
+        let workspace_state = config
+            .workspace_state_file
+            .as_deref()
+            .map(super::workspace_state::WorkspaceStatePersistence::load);
+
+        let mut virtual_output_manager = VirtualOutputManager::new();
+        virtual_output_manager.set_primary_output(config.primary_output().map(str::to_string));
+
+        if config.status_bar.as_ref().is_some_and(|bar| bar.show_clock) {
+            crate::status_bar::start_clock(&handle);
+        }
+        if let Some(timeout) = config.hide_cursor_timeout {
+            crate::input::start_cursor_idle_timer(&handle, timeout);
+        }
+
+        let mut event_bus = EventBus::new();
+        if let Some(handler) = crate::handlers::foreign_toplevel::start_event_source(&handle) {
+            event_bus.register_handler(handler);
+        }
 
         StilchState {
             backend_data,
@@ -417,69 +571,107 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             running: Arc::new(AtomicBool::new(true)),
             handle,
             window_manager: crate::window::WindowManager::new(),
-            virtual_output_manager: VirtualOutputManager::new(),
+            virtual_output_manager,
             virtual_output_exclusive_zones: HashMap::new(),
+            pending_layer_surfaces: Vec::new(),
             config,
             ipc_server: None,
             protocols,
-            workspace_manager: crate::workspace::WorkspaceManager::new(inner_gap),
+            workspace_manager,
             input_manager,
             physical_layout: None, // Will be initialized when outputs are configured
-            event_bus: EventBus::new(),
+            event_bus,
             command_executor: CommandExecutor::new(),
             seat_name,
             clock,
             tab_text_cache: crate::tab_bar::TabTextCache::new(),
+            clipboard: Default::default(),
 
             #[cfg(feature = "xwayland")]
             xwm: None,
             #[cfg(feature = "xwayland")]
             xdisplay: None,
+            #[cfg(feature = "xwayland")]
+            pending_x11_commands: None,
+            #[cfg(feature = "xwayland")]
+            xwayland_client: None,
             #[cfg(feature = "debug")]
             renderdoc: renderdoc::RenderDoc::new().ok(),
-            show_window_preview: false,
+            overview: None,
+            binding_mode: None,
+            animations: Default::default(),
+            debug_overlay: None,
+            selection_outline: Default::default(),
+            focus_follows_mouse_override: None,
+            status_bar: Default::default(),
+            scratchpad: Default::default(),
+            focus_history: Default::default(),
+            keyboard_focus_window: None,
+            floating_geometries: HashMap::new(),
+            transient_children: Default::default(),
+            workspace_state,
             startup_done: std::cell::Cell::new(false),
         }
     }
 
-    pub fn execute_startup_commands(&self) {
-        for cmd in &self.config.startup_commands {
-            info!("Executing startup command: {cmd}");
+    pub fn execute_startup_commands(&mut self) {
+        self.update_activation_environment();
 
-            // Set WAYLAND_DISPLAY environment variable
-            let wayland_display = self.socket_name.as_deref().unwrap_or("wayland-1");
+        for cmd in self.config.startup_commands.clone() {
+            info!("Executing startup command: {cmd}");
 
-            match std::process::Command::new("sh")
+            let mut command = std::process::Command::new("sh");
+            command
                 .arg("-c")
-                .arg(cmd)
-                .env("WAYLAND_DISPLAY", wayland_display)
-                .env(
-                    "XDG_RUNTIME_DIR",
-                    std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string()),
-                )
+                .arg(&cmd)
                 .stderr(std::process::Stdio::piped())
-                .stdout(std::process::Stdio::piped())
-                .spawn()
-            {
+                .stdout(std::process::Stdio::piped());
+            self.spawn_command(command, cmd);
+        }
+    }
+
+    /// Export WAYLAND_DISPLAY and DISPLAY to the systemd user manager and the
+    /// dbus activation environment, so services started on demand, like
+    /// portals, find the compositor. A nested stilch leaves them alone, they
+    /// belong to the host session.
+    pub fn update_activation_environment(&self) {
+        if !BackendData::OWNS_SESSION {
+            return;
+        }
+        let env = self.child_environment();
+        let names: Vec<&str> = env
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| *name != "XDG_RUNTIME_DIR")
+            .collect();
+        if names.is_empty() {
+            return;
+        }
+
+        let updaters: [(&str, &[&str]); 2] = [
+            ("systemctl", &["--user", "import-environment"]),
+            ("dbus-update-activation-environment", &[]),
+        ];
+        for (program, args) in updaters {
+            let child = std::process::Command::new(program)
+                .args(args)
+                .args(&names)
+                .envs(env.iter().cloned())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn();
+            match child {
                 Ok(mut child) => {
-                    info!("Started process with PID: {}", child.id());
-
-                    // Spawn a thread to log any output from the process
-                    if let Some(stderr) = child.stderr.take() {
-                        std::thread::spawn(move || {
-                            use std::io::{BufRead, BufReader};
-                            let reader = BufReader::new(stderr);
-                            for line in reader.lines() {
-                                if let Ok(line) = line {
-                                    warn!("Startup command stderr: {line}");
-                                }
-                            }
-                        });
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to execute startup command '{}': {}", cmd, e);
+                    // Reap it once it's done, so it doesn't linger as a zombie
+                    std::thread::spawn(move || match child.wait() {
+                        Ok(status) if !status.success() => {
+                            debug!("{program} failed to update the environment: {status}")
+                        }
+                        Ok(_) => {}
+                        Err(e) => debug!("Failed to wait for {program}: {e}"),
+                    });
                 }
+                Err(e) => debug!("Not updating the activation environment with {program}: {e}"),
             }
         }
     }
@@ -490,7 +682,12 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
 
         use smithay::wayland::compositor::CompositorHandler;
 
-        let (xwayland, client) = XWayland::spawn(
+        if !self.config.xwayland {
+            info!("XWayland is disabled in the config");
+            return;
+        }
+
+        let (xwayland, client) = match XWayland::spawn(
             &self.display_handle,
             None,
             std::iter::empty::<(String, String)>(),
@@ -498,14 +695,15 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             Stdio::null(),
             Stdio::null(),
             |_| (),
-        )
-        .unwrap_or_else(|e| {
-            error!("Failed to start XWayland: {:?}", e);
-            error!("Compositor will continue without XWayland support");
-            // Return a dummy value since XWayland is optional
-            std::process::exit(1);
-        });
-
+        ) {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                // Nothing waits for DISPLAY yet, commands run without it
+                error!("Failed to start XWayland: {:?}", e);
+                error!("Compositor will continue without XWayland support");
+                return;
+            }
+        };
         let ret = self
             .handle
             .insert_source(xwayland, move |event, _, data| match event {
@@ -513,51 +711,42 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                     x11_socket,
                     display_number,
                 } => {
-                    let xwayland_scale = std::env::var("ANVIL_XWAYLAND_SCALE")
-                        .ok()
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .unwrap_or(1.);
+                    data.xdisplay = Some(display_number);
+                    data.spawn_pending_commands();
+                    data.update_activation_environment();
+
+                    // The environment fixes the scale over the config
+                    if let Some(scale) = crate::shell::xwayland_scale_from_env() {
+                        data.config.xwayland_scale = crate::config::XWaylandScale::Fixed(scale);
+                    }
+                    let xwayland_scale = data.wanted_xwayland_scale().unwrap_or(1.0);
                     data.client_compositor_state(&client)
                         .set_client_scale(xwayland_scale);
-                    let mut wm =
-                        match X11Wm::start_wm(data.handle.clone(), x11_socket, client.clone()) {
-                            Ok(wm) => wm,
-                            Err(e) => {
-                                error!("Failed to attach X11 Window Manager: {:?}", e);
-                                return;
-                            }
-                        };
-
-                    // Set default cursor for xwayland
-                    if let Some((pixels, width, height, xhot, yhot)) = data
-                        .input_manager
-                        .cursor_manager
-                        .get_current_cursor_for_xwayland(1, Duration::ZERO)
+                    let wm = match X11Wm::start_wm(data.handle.clone(), x11_socket, client.clone())
                     {
-                        wm.set_cursor(
-                            &pixels,
-                            smithay::utils::Size::from((width, height)),
-                            Point::from((xhot, yhot)),
-                        )
-                        .unwrap_or_else(|e| {
-                            error!("Failed to set xwayland default cursor: {:?}", e);
-                            // Non-critical error, continue without cursor
-                        });
-                    } else {
-                        debug!("No cursor available for xwayland, using xwayland default");
-                    }
+                        Ok(wm) => wm,
+                        Err(e) => {
+                            error!("Failed to attach X11 Window Manager: {:?}", e);
+                            return;
+                        }
+                    };
                     data.xwm = Some(wm);
-                    data.xdisplay = Some(display_number);
+                    data.xwayland_client = Some(client.clone());
+                    data.set_xwayland_cursor(xwayland_scale);
                 }
                 XWaylandEvent::Error => {
                     warn!("XWayland crashed on startup");
+                    data.spawn_pending_commands();
                 }
             });
-        if let Err(e) = ret {
-            tracing::error!(
+        match ret {
+            // Commands spawned until XWayland is ready wait for its DISPLAY
+            Ok(_) => self.pending_x11_commands = Some(Vec::new()),
+            // Nothing would ever report it ready, commands run without it
+            Err(e) => tracing::error!(
                 "Failed to insert the XWaylandSource into the event loop: {}",
                 e
-            );
+            ),
         }
     }
 }
@@ -567,7 +756,18 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         let ipc_server = Arc::new(IpcServer::new()?);
         let runtime = tokio::runtime::Runtime::new()?;
 
-        runtime.block_on(ipc_server.start())?;
+        // i3 clients' queries are answered here, with the state at hand
+        let (i3_requests, i3_channel) = calloop::channel::channel();
+        self.handle
+            .insert_source(i3_channel, |event, _, state| {
+                if let calloop::channel::Event::Msg(request) = event {
+                    let crate::ipc::i3compat::I3Request { query, reply } = request;
+                    let _ = reply.send(state.answer_i3_query(query));
+                }
+            })
+            .map_err(|e| format!("Failed to insert i3 IPC request source: {e}"))?;
+
+        runtime.block_on(ipc_server.start(i3_requests))?;
 
         // Keep the runtime alive
         std::thread::spawn(move || {
@@ -591,43 +791,48 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         Ok(())
     }
 
-    pub fn update_ipc_workspace_state(&self) {
-        if let Some(ipc_server) = &self.ipc_server {
-            let mut workspaces = Vec::new();
+    /// State of every workspace, as reported over IPC and shown in the status bar
+    pub fn workspace_infos(&self) -> Vec<crate::ipc::WorkspaceInfo> {
+        let mut workspaces = Vec::new();
 
-            // Iterate through all global workspaces
-            for idx in 0..10 {
-                let workspace_id = crate::workspace::WorkspaceId::new(idx as u8);
-                if let Some(workspace) = self.workspace_manager.get_workspace(workspace_id) {
-                    // Check which output this workspace is on
-                    let location = self.workspace_manager.workspace_location(workspace_id);
-                    let is_active = location.is_some()
-                        && location
-                            .and_then(|loc| self.virtual_output_manager.get(loc))
-                            .map(|vo| vo.active_workspace() == Some(idx))
-                            .unwrap_or(false);
-
-                    // Check if any window in this workspace has urgency hints
-                    let urgent = workspace.windows.iter().any(|window_id| {
-                        self.window_registry()
-                            .get(*window_id)
-                            .and_then(|managed_window| managed_window.element.0.toplevel())
-                            .map(|_toplevel| {
-                                // Check if the window has the urgent hint set
-                                // For now, return false as urgency hints require additional protocol support
-                                false
-                            })
-                            .unwrap_or(false)
-                    });
+        // Iterate through the existing workspaces in the configured order;
+        // empty hidden ones are left out unless persistent
+        for &workspace_id in self.workspace_manager.order() {
+            if !self.workspace_manager.exists(workspace_id) {
+                continue;
+            }
+            let idx = workspace_id.get() as usize;
+            if let Some(workspace) = self.workspace_manager.get_workspace(workspace_id) {
+                // Check which output this workspace is on
+                let location = self.workspace_manager.workspace_location(workspace_id);
+                let is_active = location.is_some()
+                    && location
+                        .and_then(|loc| self.virtual_output_manager.get(loc))
+                        .map(|vo| vo.active_workspace() == Some(idx))
+                        .unwrap_or(false);
+
+                // Check if any window in this workspace wants attention
+                let urgent = workspace.windows.iter().any(|window_id| {
+                    self.window_registry()
+                        .get(*window_id)
+                        .is_some_and(|managed_window| managed_window.is_urgent())
+                });
 
-                    workspaces.push(crate::ipc::WorkspaceInfo {
-                        id: workspace_id.display_name().parse().unwrap_or(idx + 1), // Use display name
-                        active: is_active,
-                        windows: workspace.window_count(),
-                        urgent,
-                    });
-                }
+                workspaces.push(crate::ipc::WorkspaceInfo {
+                    id: workspace_id.display_name().parse().unwrap_or(idx + 1), // Use display name
+                    active: is_active,
+                    windows: workspace.window_count(),
+                    urgent,
+                });
             }
+        }
+
+        workspaces
+    }
+
+    pub fn update_ipc_workspace_state(&self) {
+        if let Some(ipc_server) = &self.ipc_server {
+            let workspaces = self.workspace_infos();
 
             // Send global workspace state (using virtual output 0 for compatibility)
             // Use the first virtual output for workspace updates
@@ -645,6 +850,7 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
 
     pub fn pre_repaint(&mut self, output: &Output, frame_target: impl Into<Time<Monotonic>>) {
         let frame_target = frame_target.into();
+        self.advance_animations();
 
         #[allow(clippy::mutable_key_type)]
         let mut clients: HashMap<ClientId, Client> = HashMap::new();
@@ -943,13 +1149,14 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         );
 
         // Get the managed window to find current workspace
-        let (element, source_workspace_id) = match self.window_registry().get(window_id) {
-            Some(mw) => (mw.element.clone(), mw.workspace),
-            None => {
-                tracing::warn!("Window {} not found in registry", window_id);
-                return;
-            }
-        };
+        let (element, source_workspace_id, is_floating) =
+            match self.window_registry().get(window_id) {
+                Some(mw) => (mw.element.clone(), mw.workspace, mw.is_floating()),
+                None => {
+                    tracing::warn!("Window {} not found in registry", window_id);
+                    return;
+                }
+            };
 
         if source_workspace_id == target_workspace_id {
             tracing::debug!("Window already in target workspace");
@@ -1011,7 +1218,11 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             .workspace_manager
             .get_workspace_mut(target_workspace_id)
         {
-            target_workspace.add_window(window_id);
+            if is_floating {
+                target_workspace.add_floating_window(window_id);
+            } else {
+                target_workspace.add_window(window_id);
+            }
             target_workspace.relayout();
 
             // Check if target workspace is visible on any output
@@ -1103,10 +1314,28 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         // Recalculate exclusive zones before switching
         self.update_tiling_area_from_output();
 
-        // Check if this workspace is associated with another output
-        if let Some(associated_output) = self.workspace_manager.workspace_association(workspace_id)
-        {
-            if associated_output != virtual_output_id {
+        // Check if this workspace belongs on another output
+        if let Some(associated_output) = self.home_output(workspace_id) {
+            if associated_output != virtual_output_id
+                && self.config.workspace_switch_mode == crate::config::WorkspaceSwitchMode::Bring
+            {
+                // Make it this output's workspace and switch to it here. One
+                // shown on its old output leaves it to a free workspace.
+                info!(
+                    "Bringing workspace {} from virtual output {} to {}",
+                    workspace_id, associated_output, virtual_output_id
+                );
+                self.finish_workspace_slides();
+                if self
+                    .workspace_manager
+                    .workspace_location(workspace_id)
+                    .is_some()
+                {
+                    self.release_workspace(workspace_id);
+                }
+                self.workspace_manager
+                    .associate_workspace_with_output(workspace_id, virtual_output_id);
+            } else if associated_output != virtual_output_id {
                 info!(
                     "Workspace {} is associated with virtual output {}, switching to that output",
                     workspace_id, associated_output
@@ -1115,17 +1344,7 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                 // Switch the associated output to show this workspace
                 self.switch_workspace(associated_output, workspace_id);
 
-                // Move pointer focus to the center of that output
-                if let Some(output) = self.virtual_output_manager.get(associated_output) {
-                    let output_rect = output.logical_region();
-                    let center = Point::from((
-                        output_rect.loc.x + output_rect.size.w / 2,
-                        output_rect.loc.y + output_rect.size.h / 2,
-                    ));
-
-                    // Move the pointer to the center of the target output
-                    self.pointer().set_location(center.to_f64());
-
+                if self.virtual_output_manager.get(associated_output).is_some() {
                     // Focus the first window in the target workspace
                     if let Some(workspace) = self.workspace_manager.get(workspace_id) {
                         if let Some(first_window_id) = workspace.windows.first() {
@@ -1153,8 +1372,8 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                     }
 
                     info!(
-                        "Moved pointer and keyboard focus to virtual output {} at {:?}",
-                        associated_output, center
+                        "Moved keyboard focus to virtual output {}",
+                        associated_output
                     );
                 }
 
@@ -1162,6 +1381,9 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             }
         }
 
+        // A slide still running from the last switch ends where it was headed
+        self.finish_workspace_slides();
+
         // Update the virtual output's active workspace
         self.virtual_output_manager
             .set_active_workspace(virtual_output_id, workspace_id.get() as usize);
@@ -1179,6 +1401,7 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             let previous_workspace_id = self
                 .workspace_manager
                 .workspace_on_output(virtual_output_id);
+            let mut hidden_windows = Vec::new();
             if let Some(current_workspace_id) = previous_workspace_id {
                 if current_workspace_id == workspace_id {
                     return; // Already on this workspace
@@ -1201,6 +1424,9 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                     .unwrap_or_default();
 
                 for window_elem in windows_to_hide {
+                    if let Some(loc) = self.space().element_location(&window_elem) {
+                        hidden_windows.push((window_elem.clone(), loc));
+                    }
                     self.space_mut().unmap_elem(&window_elem);
                 }
             }
@@ -1218,6 +1444,11 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             // Show windows from new workspace
             self.apply_workspace_layout(workspace_id);
 
+            if let Some(previous) = previous_workspace_id {
+                let forward = workspace_id.get() > previous.get();
+                self.start_workspace_slide(workspace_id, hidden_windows, forward);
+            }
+
             // Focus the first window in the workspace (or the previously focused one)
             if let Some(workspace) = self.workspace_manager.get(workspace_id) {
                 let window_to_focus = workspace
@@ -1292,11 +1523,66 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                     });
             }
 
+            self.save_workspace_state();
+
             // Update IPC state (the event handler will do this now, but keep for backwards compatibility)
             self.update_ipc_workspace_state();
         }
     }
 
+    /// Move the pointer after a workspace switch, as `workspace_switch_warp` says
+    ///
+    /// Only for switches the user asked for. Those the compositor makes by
+    /// itself, like showing a workspace on a new output, leave it alone.
+    pub fn warp_pointer_after_switch(
+        &mut self,
+        virtual_output_id: crate::virtual_output::VirtualOutputId,
+    ) {
+        let Some(region) = self
+            .virtual_output_manager
+            .get(virtual_output_id)
+            .map(|output| output.logical_region())
+        else {
+            return;
+        };
+        let center_of = |rect: Rectangle<i32, Logical>| {
+            Point::<i32, Logical>::from((
+                rect.loc.x + rect.size.w / 2,
+                rect.loc.y + rect.size.h / 2,
+            ))
+        };
+        // Only a switch that landed on another output pulls the pointer to it
+        let output_center = (!region.contains(self.pointer_location())).then(|| center_of(region));
+
+        let target = match self.config.workspace_switch_warp {
+            crate::config::WorkspaceSwitchWarp::None => None,
+            crate::config::WorkspaceSwitchWarp::Output => output_center,
+            crate::config::WorkspaceSwitchWarp::Focused => self
+                .focused_window()
+                .and_then(|window| self.space().element_geometry(&window))
+                .filter(|geometry| region.overlaps(*geometry))
+                .map(center_of)
+                .or(output_center),
+        };
+        let Some(target) = target else {
+            return;
+        };
+
+        let location = target.to_f64();
+        let pointer = self.pointer().clone();
+        let under = self.surface_under(location);
+        pointer.motion(
+            self,
+            under,
+            &smithay::input::pointer::MotionEvent {
+                location,
+                serial: SCOUNTER.next_serial(),
+                time: self.clock.now().as_millis() as u32,
+            },
+        );
+        pointer.frame(self);
+    }
+
     /// Get the current workspace for a virtual output
     pub fn current_workspace(
         &self,
@@ -1333,6 +1619,77 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         }
     }
 
+    /// The output a workspace belongs on: the one it was last shown on, else
+    /// the one the config assigns it to, falling back to the primary output
+    /// while that one is missing. None leaves it to whichever output it is
+    /// switched to on.
+    fn home_output(
+        &self,
+        workspace_id: crate::workspace::WorkspaceId,
+    ) -> Option<crate::virtual_output::VirtualOutputId> {
+        self.workspace_manager
+            .workspace_association(workspace_id)
+            .filter(|&vo| self.virtual_output_manager.get(vo).is_some())
+            .or_else(|| {
+                let name = self.workspace_manager.assigned_output(workspace_id)?;
+                self.virtual_output_manager
+                    .find_by_output_name(name)
+                    .or_else(|| self.virtual_output_manager.primary_virtual_output())
+            })
+    }
+
+    /// Take over the workspaces assigned to a newly added output, moving any
+    /// that were shown on the primary output while it was missing
+    fn claim_assigned_workspaces(
+        &mut self,
+        virtual_output_id: crate::virtual_output::VirtualOutputId,
+    ) {
+        let assigned: Vec<crate::workspace::WorkspaceId> = self
+            .workspace_manager
+            .order()
+            .iter()
+            .copied()
+            .filter(|&workspace_id| {
+                self.workspace_manager
+                    .assigned_output(workspace_id)
+                    .is_some_and(|name| {
+                        self.virtual_output_manager.find_by_output_name(name)
+                            == Some(virtual_output_id)
+                    })
+            })
+            .collect();
+        if assigned.is_empty() {
+            return;
+        }
+
+        for &workspace_id in &assigned {
+            match self.workspace_manager.workspace_location(workspace_id) {
+                Some(current) if current == virtual_output_id => {}
+                Some(current) => {
+                    info!(
+                        "Moving workspace {} from output {} to its assigned output {}",
+                        workspace_id, current, virtual_output_id
+                    );
+                    self.move_workspace_to_virtual_output(workspace_id, virtual_output_id);
+                }
+                None => self
+                    .workspace_manager
+                    .associate_workspace_with_output(workspace_id, virtual_output_id),
+            }
+        }
+
+        // With none of them moved over, show the first one
+        if self
+            .workspace_manager
+            .workspace_on_output(virtual_output_id)
+            .is_none()
+        {
+            self.switch_workspace(virtual_output_id, assigned[0]);
+        }
+
+        self.update_ipc_workspace_state();
+    }
+
     /// Initialize a virtual output with a default workspace
     pub fn initialize_virtual_output(
         &mut self,
@@ -1340,45 +1697,82 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
     ) {
         info!("Initializing virtual output {virtual_output_id}");
 
+        // Panels started before any output get one now
+        self.map_pending_layer_surfaces();
+
         // Update exclusive zones when a new virtual output is initialized
         self.update_tiling_area_from_output();
 
-        // Check if this virtual output already has a workspace
+        // Workspaces assigned to this output in the config come first
+        self.claim_assigned_workspaces(virtual_output_id);
+
+        // Then those it had when the workspace state was last saved
+        self.restore_saved_workspaces(virtual_output_id);
+
         if self
             .workspace_manager
             .workspace_on_output(virtual_output_id)
-            .is_none()
+            .is_some()
         {
-            // No workspace assigned yet
-            // If this is the first virtual output OR no workspaces are shown anywhere, use workspace 0
-            let all_workspaces_hidden = (0..10).all(|i| {
-                let ws_id = crate::workspace::WorkspaceId::new(i);
-                self.workspace_manager.workspace_location(ws_id).is_none()
-            });
-
             info!(
-                "Virtual output {} has no workspace. Output count: {}, all workspaces hidden: {}",
-                virtual_output_id,
-                self.virtual_output_manager.outputs().count(),
-                all_workspaces_hidden
+                "Virtual output {} already has a workspace",
+                virtual_output_id
             );
+            return;
+        }
 
-            if self.virtual_output_manager.outputs().count() <= 1 || all_workspaces_hidden {
+        match self.first_free_workspace(virtual_output_id) {
+            Some(workspace_id) => {
                 info!(
-                    "Assigning workspace 1 to virtual output {}",
-                    virtual_output_id
+                    "Showing first free workspace {} on virtual output {}",
+                    workspace_id, virtual_output_id
                 );
-                let workspace_id = crate::workspace::WorkspaceId::new(0); // First workspace is index 0
                 self.switch_workspace(virtual_output_id, workspace_id);
             }
-        } else {
-            info!(
-                "Virtual output {} already has a workspace",
+            None => warn!(
+                "No free workspace left for virtual output {}",
                 virtual_output_id
-            );
+            ),
         }
     }
 
+    /// The lowest-numbered workspace a new output can show: not visible
+    /// anywhere, not assigned to another output, and not last shown on
+    /// another output that is still around
+    fn first_free_workspace(
+        &self,
+        virtual_output_id: crate::virtual_output::VirtualOutputId,
+    ) -> Option<crate::workspace::WorkspaceId> {
+        let mut candidates = self.workspace_manager.order().to_vec();
+        candidates.sort_by_key(|id| id.get());
+        candidates.into_iter().find(|&workspace_id| {
+            let visible = self
+                .workspace_manager
+                .workspace_location(workspace_id)
+                .is_some();
+            let assigned_elsewhere = self
+                .workspace_manager
+                .assigned_output(workspace_id)
+                .is_some_and(
+                    |name| match self.virtual_output_manager.find_by_output_name(name) {
+                        Some(output) => output != virtual_output_id,
+                        // Meanwhile they live on the primary output
+                        None => {
+                            self.virtual_output_manager.primary_virtual_output()
+                                != Some(virtual_output_id)
+                        }
+                    },
+                );
+            let held_elsewhere = self
+                .workspace_manager
+                .workspace_association(workspace_id)
+                .is_some_and(|output| {
+                    output != virtual_output_id && self.virtual_output_manager.get(output).is_some()
+                });
+            !visible && !assigned_elsewhere && !held_elsewhere
+        })
+    }
+
     /// Add a new window to the workspace system
     pub fn add_window(
         &mut self,
@@ -1420,6 +1814,15 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             }
         };
 
+        // `assign` rules open matching windows on their workspace instead
+        let workspace_id = self.assigned_workspace(&window).unwrap_or(workspace_id);
+        let (workspace_visible, workspace_empty) = self
+            .workspace_manager
+            .get(workspace_id)
+            .map_or((false, false), |ws| {
+                (ws.is_visible(), ws.windows.is_empty())
+            });
+
         debug!(
             "Adding new window to virtual output {} workspace {}",
             virtual_output_id, workspace_id
@@ -1454,6 +1857,11 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             return None;
         }
 
+        // A window assigned to a hidden workspace stays unmapped until it is shown
+        if !workspace_visible {
+            self.space_mut().unmap_elem(&window);
+        }
+
         // Debug check consistency
         #[cfg(debug_assertions)]
         self.check_consistency();
@@ -1469,7 +1877,41 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                     workspace.layout.set_area(area);
                 }
             }
+        } else if workspace_empty {
+            // A hidden workspace keeps the area it was last shown with. An
+            // empty one is laid out for the output the window opened on,
+            // where it is most likely to be shown.
+            let area = self
+                .virtual_output_exclusive_zones
+                .get(&virtual_output_id)
+                .copied()
+                .or_else(|| {
+                    self.virtual_output_manager
+                        .get(virtual_output_id)
+                        .map(|vo| vo.logical_region())
+                });
+            if let Some(area) = area {
+                if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
+                    workspace.area = area;
+                    workspace.layout.set_area(area);
+                }
+            }
+        }
+
+        self.apply_focus_follows_mouse_rule(window_id);
+
+        // Dialogs and fixed-size windows float instead of being tiled, tiled
+        // windows fill a matching `append_layout` placeholder wherever it is
+        if self.should_float(&window) {
+            self.update_floating_state(window_id, true);
+            self.restore_floating_geometry(window_id);
+        } else {
+            self.swallow_into_placeholder(window_id);
         }
+        let workspace_id = self
+            .window_registry()
+            .get(window_id)
+            .map_or(workspace_id, |mw| mw.workspace);
 
         // Get the workspace and relayout
         let window_geometry = if let Some(workspace) = self.workspace_manager.get_mut(workspace_id)
@@ -1486,14 +1928,26 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         } else {
             None
         };
+        let window_geometry = window_geometry.or_else(|| {
+            self.window_registry()
+                .get(window_id)
+                .filter(|mw| mw.is_floating())
+                .map(|mw| mw.geometry())
+        });
 
         // Apply layout to space
-        self.apply_workspace_layout(workspace_id);
-
-        // Use the window's geometry for bounds
-        if let Some(geometry) = window_geometry {
-            debug!("Window {} geometry: {:?}", window_id, geometry);
-
+        if self
+            .workspace_manager
+            .get(workspace_id)
+            .is_some_and(|ws| ws.is_visible())
+        {
+            self.apply_workspace_layout(workspace_id);
+        }
+
+        // Use the window's geometry for bounds
+        if let Some(geometry) = window_geometry {
+            debug!("Window {} geometry: {:?}", window_id, geometry);
+
             // Set initial bounds and size
             if let Some(toplevel) = window.0.toplevel() {
                 toplevel.with_pending_state(|state| {
@@ -1515,6 +1969,20 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                     toplevel.send_configure();
                 }
             }
+            // Mapped X11 windows are configured from the space, one on a
+            // hidden workspace gets that workspace's layout instead
+            #[cfg(feature = "xwayland")]
+            if let Some(surface) = window.0.x11_surface() {
+                let visible = self
+                    .workspace_manager
+                    .get(workspace_id)
+                    .is_some_and(|ws| ws.is_visible());
+                if !visible {
+                    if let Err(e) = surface.configure(geometry) {
+                        warn!("Failed to configure X11 window {}: {:?}", window_id, e);
+                    }
+                }
+            }
         } else {
             warn!("No geometry found for window {} after layout", window_id);
         }
@@ -1526,6 +1994,9 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             self.space().elements().count()
         );
 
+        // X11 windows know what they are transient for when they map
+        self.record_transient_parent(window_id);
+
         // Focus the new window if the workspace is visible (i3/sway behavior - new windows steal focus)
         if let Some(_workspace) = self.workspace_manager.get(workspace_id) {
             // Check if this workspace is visible
@@ -1537,11 +2008,18 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                 .unwrap_or(false);
 
             if is_visible {
-                debug!(
-                    "Focusing new window {} in visible workspace {}",
-                    window_id, workspace_id
-                );
-                self.focus_window(&window);
+                if self.initial_commit_pending(&window) {
+                    debug!("Focusing new window {} on its first commit", window_id);
+                } else if self.refuses_focus(&window) {
+                    debug!("Not focusing new window {} (no_focus)", window_id);
+                } else {
+                    debug!(
+                        "Focusing new window {} in visible workspace {}",
+                        window_id, workspace_id
+                    );
+                    self.focus_window(&window);
+                }
+                self.start_window_open_animation(&window);
 
                 // Queue redraw for outputs where the new window is visible
                 if let Some(bbox) = self.space().element_bbox(&window) {
@@ -1600,6 +2078,8 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             );
             // Raise to top
             self.space_mut().raise_element(window, true);
+            #[cfg(feature = "xwayland")]
+            self.raise_override_redirect_windows();
 
             // Queue redraw for outputs affected by focus change
             if let Some(bbox) = self.space().element_bbox(window) {
@@ -1618,17 +2098,106 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
 
             // Update workspace's focused_window tracking
             if let Some(window_id) = self.window_registry().find_by_element(window) {
+                // Dialogs stay above the window they belong to
+                self.raise_transient_children(window_id);
+
+                // Focus is the attention an urgent window asked for
+                self.set_window_urgent(window_id, false);
+
                 if let Some(managed_window) = self.window_registry().get(window_id) {
                     let workspace_id = managed_window.workspace;
                     if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id)
                     {
-                        workspace.focused_window = Some(window_id);
+                        // Focusing a window drops the selected container's outline
+                        if workspace.focused_container.is_some() {
+                            self.backend_data.request_render();
+                        }
+                        workspace.set_focus(Some(window_id));
                     }
                 }
             }
         }
     }
 
+    /// Bring a window into view and focus it, switching to its workspace if hidden
+    pub fn activate_window(&mut self, window_id: crate::window::WindowId) {
+        let Some((element, workspace_id)) = self
+            .window_registry()
+            .get(window_id)
+            .map(|mw| (mw.element.clone(), mw.workspace))
+        else {
+            return;
+        };
+
+        let is_visible = self
+            .workspace_manager
+            .find_workspace_location(workspace_id)
+            .and_then(|vo_id| self.virtual_output_manager.get(vo_id))
+            .map(|vo| vo.active_workspace() == Some(workspace_id.get() as usize))
+            .unwrap_or(false);
+        if !is_visible {
+            let target_output = self
+                .workspace_manager
+                .workspace_association(workspace_id)
+                .or_else(|| self.virtual_output_at_pointer());
+            if let Some(vo_id) = target_output {
+                self.switch_workspace(vo_id, workspace_id);
+            }
+        }
+
+        // Activating a window in a tabbed/stacked container brings its tab to the front
+        if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
+            workspace.layout.update_active_child_for_window(window_id);
+        }
+        self.apply_workspace_layout(workspace_id);
+        self.focus_window(&element);
+    }
+
+    /// Window whose tab (or stacked title) is at `location`, if any
+    pub fn tab_under(&self, location: Point<f64, Logical>) -> Option<crate::window::WindowId> {
+        let (tabs, index) = self.tab_bar_under(location)?;
+        tabs.get(index).map(|(id, _)| *id)
+    }
+
+    /// Tabs of the tab bar (or stacked titles) at `location`, with whether
+    /// each is the active one, and the index of the tab under `location`
+    pub fn tab_bar_under(
+        &self,
+        location: Point<f64, Logical>,
+    ) -> Option<(Vec<(crate::window::WindowId, bool)>, usize)> {
+        let point = location.to_i32_floor();
+        let vo_id = self.virtual_output_manager.virtual_output_at(point)?;
+        let workspace_id = self
+            .virtual_output_manager
+            .get(vo_id)?
+            .active_workspace_id()?;
+        let layout = &self.workspace_manager.get_workspace(workspace_id)?.layout;
+        let bar_height = layout.tab_bar().height;
+
+        // Tabs split the bar width evenly, stacked titles are piled one bar high each
+        for (geometry, tabs) in layout.find_tabbed_containers() {
+            let bar = Rectangle::new(geometry.loc, (geometry.size.w, bar_height).into());
+            if bar.contains(point) && !tabs.is_empty() {
+                let tab_width = (geometry.size.w / tabs.len() as i32).max(1);
+                let index = ((point.x - geometry.loc.x) / tab_width) as usize;
+                let index = index.min(tabs.len() - 1);
+                return Some((tabs, index));
+            }
+        }
+        for (geometry, tabs) in layout.find_stacked_containers() {
+            let bar = Rectangle::new(
+                geometry.loc,
+                (geometry.size.w, bar_height * tabs.len() as i32).into(),
+            );
+            if bar.contains(point) && !tabs.is_empty() {
+                let index = ((point.y - geometry.loc.y) / bar_height) as usize;
+                let index = index.min(tabs.len() - 1);
+                return Some((tabs, index));
+            }
+        }
+        None
+    }
+
     /// Center pointer on window
     pub fn center_pointer_on_window(&mut self, window: &crate::shell::WindowElement) {
         if let Some(loc) = self.space().element_location(window) {
@@ -1686,38 +2255,92 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             .virtual_output_at(self.pointer_location())
     }
 
-    /// Move current workspace to output in direction
-    pub fn move_workspace_to_output(&mut self, direction: crate::config::Direction) {
+    /// Move the workspace under the pointer to the output `target` refers
+    /// to, with the pointer and focus following it
+    pub fn move_workspace_to_output(
+        &mut self,
+        target: crate::config::OutputTarget,
+    ) -> StilchResult<()> {
+        let current_vo_id = self.virtual_output_at_pointer().ok_or_else(|| {
+            StilchError::InvalidOperation("No virtual output under the pointer".into())
+        })?;
+        let workspace_id = self
+            .workspace_manager
+            .workspace_on_output(current_vo_id)
+            .ok_or_else(|| {
+                StilchError::InvalidOperation(format!(
+                    "No workspace on virtual output {current_vo_id}"
+                ))
+            })?;
+        let target_vo_id = self.resolve_output_target(&target).ok_or_else(|| {
+            StilchError::InvalidOperation(match &target {
+                crate::config::OutputTarget::Direction(direction) => {
+                    format!("No output {direction:?} of virtual output {current_vo_id}")
+                }
+                crate::config::OutputTarget::Primary => "No primary output".to_string(),
+                crate::config::OutputTarget::Name(name) => format!("No output named {name}"),
+            })
+        })?;
+
+        if target_vo_id == current_vo_id {
+            info!("Workspace {} already on target output", workspace_id);
+            return Ok(());
+        }
+
         info!(
-            "move_workspace_to_output called with direction: {:?}",
-            direction
+            "Moving workspace {} from output {} to output {} ({:?})",
+            workspace_id, current_vo_id, target_vo_id, target
         );
 
-        // Get current virtual output based on pointer
-        let current_vo_id = match self.virtual_output_at_pointer() {
-            Some(id) => id,
-            None => {
-                warn!("No virtual output at pointer location");
-                return;
-            }
-        };
+        if !self.move_workspace_to_virtual_output(workspace_id, target_vo_id) {
+            return Err(StilchError::InvalidOperation(format!(
+                "Workspace {workspace_id} could not be shown on virtual output {target_vo_id}"
+            )));
+        }
 
-        // Get current workspace on this output
-        let workspace_id = match self.workspace_manager.workspace_on_output(current_vo_id) {
-            Some(id) => id,
-            None => {
-                warn!("No workspace on current virtual output");
-                return;
+        // Move pointer to center of target output to follow the workspace
+        if let Some(target_vo) = self.virtual_output_manager.get(target_vo_id) {
+            let region = target_vo.logical_region();
+            let center = Point::<f64, Logical>::from((
+                (region.loc.x + region.size.w / 2) as f64,
+                (region.loc.y + region.size.h / 2) as f64,
+            ));
+            self.pointer().set_location(center);
+        }
+
+        // Focus the first window in the moved workspace
+        if let Some(workspace) = self.workspace_manager.get(workspace_id) {
+            if let Some(window_id) = workspace
+                .focused_window
+                .or_else(|| workspace.windows.first().copied())
+            {
+                let element = self
+                    .window_registry()
+                    .get(window_id)
+                    .map(|mw| mw.element.clone());
+                if let Some(element) = element {
+                    self.focus_window(&element);
+                }
             }
-        };
+        }
+
+        // Update IPC state
+        self.update_ipc_workspace_state();
+        Ok(())
+    }
 
-        // Find target virtual output in the given direction
+    /// The nearest virtual output in `direction` from `from`
+    pub fn virtual_output_in_direction(
+        &self,
+        from: crate::virtual_output::VirtualOutputId,
+        direction: crate::config::Direction,
+    ) -> Option<crate::virtual_output::VirtualOutputId> {
         let current_center = {
-            let vo = match self.virtual_output_manager.get(current_vo_id) {
+            let vo = match self.virtual_output_manager.get(from) {
                 Some(vo) => vo,
                 None => {
                     error!("Current virtual output should exist but was not found");
-                    return;
+                    return None;
                 }
             };
             let region = vo.logical_region();
@@ -1730,7 +2353,7 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         // Find all other virtual outputs
         let mut candidates = Vec::new();
         for vo in self.virtual_output_manager.all_virtual_outputs() {
-            if vo.id() == current_vo_id {
+            if vo.id() == from {
                 continue;
             }
 
@@ -1770,41 +2393,198 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         // Sort by distance and take the closest one
         candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
 
-        let target_vo_id = match candidates.first() {
-            Some((id, _)) => *id,
+        candidates.first().map(|&(id, _)| id)
+    }
+
+    /// Focus a virtual output: the pointer moves to its middle and the
+    /// keyboard to the window last focused on its workspace
+    pub fn focus_output(&mut self, target: crate::config::OutputTarget) {
+        let Some((target_vo_id, region)) = self.resolve_output_target(&target).and_then(|id| {
+            self.virtual_output_manager
+                .get(id)
+                .map(|vo| (id, vo.logical_region()))
+        }) else {
+            info!("No output to focus for {:?}", target);
+            return;
+        };
+
+        let location = Point::<f64, Logical>::from((
+            (region.loc.x + region.size.w / 2) as f64,
+            (region.loc.y + region.size.h / 2) as f64,
+        ));
+        let pointer = self.pointer().clone();
+        let under = self.surface_under(location);
+        pointer.motion(
+            self,
+            under,
+            &smithay::input::pointer::MotionEvent {
+                location,
+                serial: SCOUNTER.next_serial(),
+                time: self.clock.now().as_millis() as u32,
+            },
+        );
+        pointer.frame(self);
+
+        let window = self
+            .workspace_manager
+            .workspace_on_output(target_vo_id)
+            .and_then(|id| self.workspace_manager.get(id))
+            .and_then(|ws| ws.focused_window.or_else(|| ws.windows.first().copied()))
+            .and_then(|id| self.window_registry().get(id))
+            .map(|mw| mw.element.clone());
+        match window {
+            Some(element) => self.focus_window(&element),
             None => {
-                info!("No virtual output found in direction {:?}", direction);
-                return;
+                if let Some(keyboard) = self.seat().get_keyboard() {
+                    keyboard.set_focus(self, None, SCOUNTER.next_serial());
+                }
             }
-        };
+        }
+        self.update_ipc_workspace_state();
+    }
 
-        // Check if target already has this workspace
-        if let Some(target_workspace) = self.workspace_manager.workspace_on_output(target_vo_id) {
-            if target_workspace == workspace_id {
-                info!("Workspace {} already on target output", workspace_id);
-                return;
+    /// The virtual output an output target refers to, directions counting
+    /// from the one under the pointer
+    fn resolve_output_target(
+        &self,
+        target: &crate::config::OutputTarget,
+    ) -> Option<crate::virtual_output::VirtualOutputId> {
+        match target {
+            crate::config::OutputTarget::Primary => {
+                self.virtual_output_manager.primary_virtual_output()
+            }
+            crate::config::OutputTarget::Name(name) => {
+                self.virtual_output_manager.find_by_output_name(name)
             }
+            crate::config::OutputTarget::Direction(direction) => self
+                .virtual_output_at_pointer()
+                .and_then(|current| self.virtual_output_in_direction(current, *direction)),
         }
+    }
 
+    /// Exchange the workspaces shown on the focused virtual output and
+    /// `target`. Focus stays on the focused output, now showing the other
+    /// workspace. If only one of them shows a workspace, it moves over and
+    /// the output it leaves falls back to a hidden workspace.
+    pub fn swap_output_workspaces(&mut self, target: crate::config::OutputTarget) {
+        let Some(current_vo_id) = self.virtual_output_at_pointer() else {
+            warn!("No virtual output at pointer location");
+            return;
+        };
+        let Some(target_vo_id) = self.resolve_output_target(&target) else {
+            info!("No output to swap workspaces with for {:?}", target);
+            return;
+        };
+        if target_vo_id == current_vo_id {
+            info!(
+                "Not swapping workspaces of output {} with itself",
+                current_vo_id
+            );
+            return;
+        }
+
+        self.finish_workspace_slides();
+        let current_ws = self.workspace_manager.workspace_on_output(current_vo_id);
+        let target_ws = self.workspace_manager.workspace_on_output(target_vo_id);
         info!(
-            "Moving workspace {} from output {} to output {} (direction: {:?})",
-            workspace_id, current_vo_id, target_vo_id, direction
+            "Swapping workspaces {:?} on output {} and {:?} on output {}",
+            current_ws, current_vo_id, target_ws, target_vo_id
         );
 
-        // Get the area of the target output
-        let target_area = self
-            .virtual_output_exclusive_zones
-            .get(&target_vo_id)
-            .copied()
-            .unwrap_or_else(|| {
-                self.virtual_output_manager
-                    .get(target_vo_id)
-                    .map(|vo| vo.logical_region())
-                    .unwrap_or_else(|| {
-                        tracing::error!("No virtual output found for ID {target_vo_id}");
-                        Rectangle::from_size((1920, 1080).into())
-                    })
-            });
+        match (current_ws, target_ws) {
+            (None, None) => return,
+            (Some(workspace_id), None) => {
+                self.move_workspace_to_virtual_output(workspace_id, target_vo_id);
+            }
+            (None, Some(workspace_id)) => {
+                self.move_workspace_to_virtual_output(workspace_id, current_vo_id);
+            }
+            (Some(current_ws), Some(target_ws)) => {
+                // Take both off screen before showing either, so neither
+                // output falls back to some other workspace in between
+                for workspace_id in [current_ws, target_ws] {
+                    let windows: Vec<_> = self
+                        .workspace_manager
+                        .get(workspace_id)
+                        .map(|ws| {
+                            ws.windows
+                                .iter()
+                                .filter_map(|id| self.window_registry().get(*id))
+                                .map(|mw| mw.element.clone())
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    for window in &windows {
+                        self.space_mut().unmap_elem(window);
+                    }
+                    self.workspace_manager.hide_workspace(workspace_id);
+                }
+
+                for (workspace_id, vo_id) in
+                    [(current_ws, target_vo_id), (target_ws, current_vo_id)]
+                {
+                    let area = self
+                        .virtual_output_exclusive_zones
+                        .get(&vo_id)
+                        .copied()
+                        .unwrap_or_else(|| {
+                            self.virtual_output_manager
+                                .get(vo_id)
+                                .map(|vo| vo.logical_region())
+                                .unwrap_or_else(|| {
+                                    tracing::error!("No virtual output found for ID {vo_id}");
+                                    Rectangle::from_size((1920, 1080).into())
+                                })
+                        });
+                    if let Err(e) =
+                        self.workspace_manager
+                            .show_workspace_on_output(workspace_id, vo_id, area)
+                    {
+                        warn!(
+                            "Failed to show workspace {} on output {}: {:?}",
+                            workspace_id, vo_id, e
+                        );
+                        continue;
+                    }
+                    self.virtual_output_manager
+                        .set_active_workspace(vo_id, workspace_id.get() as usize);
+                }
+
+                // Showing a workspace resets hidden empty ones, association
+                // included, so the associations go last
+                self.workspace_manager
+                    .associate_workspace_with_output(current_ws, target_vo_id);
+                self.workspace_manager
+                    .associate_workspace_with_output(target_ws, current_vo_id);
+                self.apply_workspace_layout(current_ws);
+                self.apply_workspace_layout(target_ws);
+                self.save_workspace_state();
+            }
+        }
+
+        // Focus follows the content that arrived on the focused output
+        let window = self
+            .workspace_manager
+            .workspace_on_output(current_vo_id)
+            .and_then(|id| self.workspace_manager.get(id))
+            .and_then(|ws| ws.focused_window.or_else(|| ws.windows.first().copied()))
+            .and_then(|id| self.window_registry().get(id))
+            .map(|mw| mw.element.clone());
+        match window {
+            Some(element) => self.focus_window(&element),
+            None => {
+                if let Some(keyboard) = self.seat().get_keyboard() {
+                    keyboard.set_focus(self, None, SCOUNTER.next_serial());
+                }
+            }
+        }
+        self.update_ipc_workspace_state();
+    }
+
+    /// Take a workspace off the output showing it, which shows a hidden
+    /// workspace instead
+    fn release_workspace(&mut self, workspace_id: crate::workspace::WorkspaceId) {
+        let current_vo_id = self.workspace_manager.workspace_location(workspace_id);
 
         // Hide windows from current workspace on current output
         let windows_to_move: Vec<_> = self
@@ -1829,10 +2609,13 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         // Hide the workspace from current output
         self.workspace_manager.hide_workspace(workspace_id);
 
-        // Find a workspace to show on the current output (that just lost its workspace)
-        // Look for a workspace that's either hidden or already associated with this output
-        let replacement_workspace =
-            (0..10)
+        // The output it leaves, if still around, shows a hidden workspace instead
+        if let Some(current_vo_id) =
+            current_vo_id.filter(|&vo| self.virtual_output_manager.get(vo).is_some())
+        {
+            // Find a workspace to show on the current output (that just lost its workspace)
+            // Look for a workspace that's either hidden or already associated with this output
+            let replacement_workspace = (0..10)
                 .map(|i| crate::workspace::WorkspaceId::new(i))
                 .find(|&ws_id| {
                     ws_id != workspace_id
@@ -1840,36 +2623,62 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                     // Hidden workspace
                 });
 
-        if let Some(replacement_ws) = replacement_workspace {
-            info!(
-                "Switching current output {} to workspace {}",
-                current_vo_id, replacement_ws
-            );
-            // Get the current output's area
-            let current_area = self
-                .virtual_output_exclusive_zones
-                .get(&current_vo_id)
-                .copied()
-                .unwrap_or_else(|| {
-                    self.virtual_output_manager
-                        .get(current_vo_id)
-                        .map(|vo| vo.logical_region())
-                        .unwrap_or_else(|| {
-                            tracing::error!("No virtual output found for ID {current_vo_id}");
-                            Rectangle::from_size((1920, 1080).into())
-                        })
-                });
+            if let Some(replacement_ws) = replacement_workspace {
+                info!(
+                    "Switching current output {} to workspace {}",
+                    current_vo_id, replacement_ws
+                );
+                // Get the current output's area
+                let current_area = self
+                    .virtual_output_exclusive_zones
+                    .get(&current_vo_id)
+                    .copied()
+                    .unwrap_or_else(|| {
+                        self.virtual_output_manager
+                            .get(current_vo_id)
+                            .map(|vo| vo.logical_region())
+                            .unwrap_or_else(|| {
+                                tracing::error!("No virtual output found for ID {current_vo_id}");
+                                Rectangle::from_size((1920, 1080).into())
+                            })
+                    });
 
-            // Show the replacement workspace on the current output
-            let _ = self.workspace_manager.show_workspace_on_output(
-                replacement_ws,
-                current_vo_id,
-                current_area,
-            );
-            self.virtual_output_manager
-                .set_active_workspace(current_vo_id, replacement_ws.get() as usize);
-            self.apply_workspace_layout(replacement_ws);
+                // Show the replacement workspace on the current output
+                let _ = self.workspace_manager.show_workspace_on_output(
+                    replacement_ws,
+                    current_vo_id,
+                    current_area,
+                );
+                self.virtual_output_manager
+                    .set_active_workspace(current_vo_id, replacement_ws.get() as usize);
+                self.apply_workspace_layout(replacement_ws);
+            }
         }
+    }
+
+    /// Move a workspace to a virtual output, leaving focus and the pointer
+    /// where they are. Returns false if it could not be shown there.
+    pub fn move_workspace_to_virtual_output(
+        &mut self,
+        workspace_id: crate::workspace::WorkspaceId,
+        target_vo_id: crate::virtual_output::VirtualOutputId,
+    ) -> bool {
+        // Get the area of the target output
+        let target_area = self
+            .virtual_output_exclusive_zones
+            .get(&target_vo_id)
+            .copied()
+            .unwrap_or_else(|| {
+                self.virtual_output_manager
+                    .get(target_vo_id)
+                    .map(|vo| vo.logical_region())
+                    .unwrap_or_else(|| {
+                        tracing::error!("No virtual output found for ID {target_vo_id}");
+                        Rectangle::from_size((1920, 1080).into())
+                    })
+            });
+
+        self.release_workspace(workspace_id);
 
         // Update workspace association to target output
         self.workspace_manager
@@ -1881,7 +2690,7 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                 .show_workspace_on_output(workspace_id, target_vo_id, target_area)
         {
             warn!("Failed to move workspace to target output: {:?}", e);
-            return;
+            return false;
         }
 
         // Apply the layout on the new output
@@ -1891,34 +2700,8 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         self.virtual_output_manager
             .set_active_workspace(target_vo_id, workspace_id.get() as usize);
 
-        // Move pointer to center of target output to follow the workspace
-        if let Some(target_vo) = self.virtual_output_manager.get(target_vo_id) {
-            let region = target_vo.logical_region();
-            let center = Point::<f64, Logical>::from((
-                (region.loc.x + region.size.w / 2) as f64,
-                (region.loc.y + region.size.h / 2) as f64,
-            ));
-            self.pointer().set_location(center);
-        }
-
-        // Focus the first window in the moved workspace
-        if let Some(workspace) = self.workspace_manager.get(workspace_id) {
-            if let Some(window_id) = workspace
-                .focused_window
-                .or_else(|| workspace.windows.first().copied())
-            {
-                let element = self
-                    .window_registry()
-                    .get(window_id)
-                    .map(|mw| mw.element.clone());
-                if let Some(element) = element {
-                    self.focus_window(&element);
-                }
-            }
-        }
-
-        // Update IPC state
-        self.update_ipc_workspace_state();
+        self.save_workspace_state();
+        true
     }
 
     /// Toggle fullscreen mode for focused window
@@ -1945,9 +2728,7 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                 );
 
                 // Toggle: if already in this mode, turn off; otherwise switch to this mode
-                let enable_fullscreen = !is_in_mode;
-
-                self.set_window_fullscreen(window_id, enable_fullscreen, mode);
+                self.set_fullscreen(window_id, !is_in_mode, mode);
             } else {
                 tracing::warn!("Focused window not found in registry");
             }
@@ -1956,19 +2737,61 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         }
     }
 
+    /// Put a window in `mode` fullscreen, or take it out of fullscreen in
+    /// whatever mode it is in, regardless of its current state
+    ///
+    /// A window fullscreen in another mode leaves that first, so its
+    /// pre-fullscreen layout is what gets restored later.
+    pub fn set_fullscreen(
+        &mut self,
+        window_id: crate::window::WindowId,
+        enable: bool,
+        mode: crate::window::FullscreenMode,
+    ) {
+        let current = self
+            .window_registry()
+            .get(window_id)
+            .and_then(|mw| match &mw.layout {
+                crate::window::WindowLayout::Fullscreen { mode, .. } => Some(*mode),
+                _ => None,
+            });
+        match (enable, current) {
+            (true, Some(current)) if current == mode => {}
+            (true, Some(current)) => {
+                self.set_window_fullscreen(window_id, false, current);
+                self.set_window_fullscreen(window_id, true, mode);
+            }
+            (true, None) => self.set_window_fullscreen(window_id, true, mode),
+            (false, Some(current)) => self.set_window_fullscreen(window_id, false, current),
+            (false, None) => {}
+        }
+    }
+
     /// Set fullscreen mode for a window
     pub fn set_window_fullscreen(
         &mut self,
         window_id: crate::window::WindowId,
         enable: bool,
         mode: crate::window::FullscreenMode,
+    ) {
+        self.set_window_fullscreen_on(window_id, enable, mode, None);
+    }
+
+    /// Set fullscreen mode for a window, physical output fullscreen going to
+    /// `output` if given rather than the output the window is on
+    pub fn set_window_fullscreen_on(
+        &mut self,
+        window_id: crate::window::WindowId,
+        enable: bool,
+        mode: crate::window::FullscreenMode,
+        output: Option<Output>,
     ) {
         let window_info = self
             .window_registry()
             .get(window_id)
-            .map(|mw| (mw.element.clone(), mw.workspace));
+            .map(|mw| (mw.element.clone(), mw.workspace, mw.is_fullscreen()));
 
-        if let Some((window_element, workspace_id)) = window_info {
+        if let Some((window_element, workspace_id, was_fullscreen)) = window_info {
             // Get workspace area before borrowing window registry
             let workspace_area = if mode == crate::window::FullscreenMode::Container {
                 self.workspace_manager
@@ -2021,12 +2844,33 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                             window_id,
                             window_element,
                             workspace_id,
+                            output,
                         );
                     }
                 }
             } else {
                 self.unset_fullscreen(window_id, window_element, workspace_id);
             }
+
+            let is_fullscreen = self
+                .window_registry()
+                .get(window_id)
+                .is_some_and(|mw| mw.is_fullscreen());
+            let timestamp = std::time::Instant::now();
+            if is_fullscreen && !was_fullscreen {
+                self.event_bus
+                    .emit_window(crate::event::WindowEvent::FullscreenEntered {
+                        window_id,
+                        mode,
+                        timestamp,
+                    });
+            } else if was_fullscreen && !is_fullscreen {
+                self.event_bus
+                    .emit_window(crate::event::WindowEvent::FullscreenExited {
+                        window_id,
+                        timestamp,
+                    });
+            }
         }
     }
 
@@ -2166,27 +3010,22 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         }
     }
 
-    /// Set physical output fullscreen
+    /// Set physical output fullscreen, on `requested` if it is still there
     fn set_physical_output_fullscreen(
         &mut self,
         window_id: crate::window::WindowId,
         window_element: crate::shell::WindowElement,
         workspace_id: crate::workspace::WorkspaceId,
+        requested: Option<Output>,
     ) {
-        // Find the output containing this window
-        let outputs = self.space().outputs_for_element(&window_element);
-        if let Some(output) = outputs.first() {
-            let output_geo = self.space().output_geometry(output).unwrap_or_default();
-
-            // Update the window's geometry in the registry
-            if let Some(managed_window) = self.window_registry_mut().get_mut(window_id) {
-                if let crate::window::WindowLayout::Fullscreen {
-                    ref mut geometry, ..
-                } = &mut managed_window.layout
-                {
-                    *geometry = output_geo;
-                }
-            }
+        let target = requested
+            .and_then(|output| {
+                let geometry = self.space().output_geometry(&output)?;
+                Some((output, geometry))
+            })
+            .or_else(|| self.physical_output_for_window(window_id, workspace_id));
+        if let Some((output, output_geo)) = target {
+            let output = &output;
 
             // Configure the window for fullscreen on physical output
             if let Some(toplevel) = window_element.0.toplevel() {
@@ -2208,6 +3047,8 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
                 });
                 toplevel.send_configure();
             }
+            // Records the geometry in the registry; X11 windows are resized here
+            self.window_manager.resize_window(window_id, output_geo);
 
             // Set as fullscreen window in workspace (so other windows get hidden)
             if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
@@ -2230,6 +3071,85 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
         }
     }
 
+    /// Which borders stilch draws around a window
+    ///
+    /// Wayland windows that negotiated client-side decorations draw their own
+    /// frame and get none, so a client titlebar never ends up inside a border.
+    pub fn window_border_edges(
+        &self,
+        window_id: crate::window::WindowId,
+    ) -> crate::workspace::BorderEdges {
+        let Some(managed_window) = self.window_registry().get(window_id) else {
+            return crate::workspace::BorderEdges::NONE;
+        };
+        let element = &managed_window.element;
+        if element.0.toplevel().is_some() && !element.is_server_side() {
+            return crate::workspace::BorderEdges::NONE;
+        }
+        self.workspace_manager
+            .get_workspace(managed_window.workspace)
+            .map(|workspace| {
+                workspace.border_edges(
+                    window_id,
+                    self.config.border.hide_edge,
+                    self.config.border.smart,
+                )
+            })
+            .unwrap_or(crate::workspace::BorderEdges::ALL)
+    }
+
+    /// Physical output a window goes fullscreen on, with its geometry
+    ///
+    /// Only the outputs behind the workspace's virtual output are candidates,
+    /// so a split monitor resolves to the monitor rather than whichever
+    /// output the window's bounding box (shadows and popups included) touches
+    /// first. The output under the window's center wins, then the one it
+    /// overlaps most.
+    fn physical_output_for_window(
+        &self,
+        window_id: crate::window::WindowId,
+        workspace_id: crate::workspace::WorkspaceId,
+    ) -> Option<(Output, Rectangle<i32, Logical>)> {
+        let window_geo = self.window_registry().get(window_id)?.geometry();
+        let center = Point::from((
+            window_geo.loc.x + window_geo.size.w / 2,
+            window_geo.loc.y + window_geo.size.h / 2,
+        ));
+
+        let mut candidates: Vec<Output> = self
+            .workspace_manager
+            .find_workspace_location(workspace_id)
+            .and_then(|vo_id| self.virtual_output_manager.get(vo_id))
+            .map(|vo| vo.physical_outputs().to_vec())
+            .unwrap_or_default();
+        if candidates.is_empty() {
+            candidates = self.space().outputs().cloned().collect();
+        }
+        let candidates: Vec<_> = candidates
+            .into_iter()
+            .filter_map(|output| {
+                let geometry = self.space().output_geometry(&output)?;
+                Some((output, geometry))
+            })
+            .collect();
+
+        let overlap = |geometry: &Rectangle<i32, Logical>| {
+            geometry
+                .intersection(window_geo)
+                .map(|area| area.size.w * area.size.h)
+                .unwrap_or(0)
+        };
+        candidates
+            .iter()
+            .find(|(_, geometry)| geometry.contains(center))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .max_by_key(|(_, geometry)| overlap(geometry))
+            })
+            .cloned()
+    }
+
     /// Unset fullscreen mode
     fn unset_fullscreen(
         &mut self,
@@ -2269,11 +3189,21 @@ impl<BackendData: Backend + 'static> StilchState<BackendData> {
             toplevel.send_configure();
         }
 
+        // Back into its tab or tiled spot, which may have changed meanwhile
+        let floating = self
+            .window_registry()
+            .get(window_id)
+            .is_some_and(|mw| mw.is_floating());
+        if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
+            workspace.restore_after_fullscreen(window_id, floating);
+        }
+
         // Re-apply layout
         if let Some(vo_id) = self.workspace_manager.find_workspace_location(workspace_id) {
             if let Some(vo) = self.virtual_output_manager.get(vo_id) {
                 if vo.active_workspace() == Some(workspace_id.get() as usize) {
                     self.apply_workspace_layout(workspace_id);
+                    self.focus_window(&window_element);
                 }
             }
         }
@@ -2476,12 +3406,141 @@ pub fn take_presentation_feedback(
 }
 
 impl<BackendData: Backend> StilchState<BackendData> {
+    /// Environment for spawned commands, pointing them at this compositor's
+    /// Wayland socket and XWayland display rather than whatever stilch
+    /// itself was started with
+    pub fn child_environment(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![(
+            "XDG_RUNTIME_DIR",
+            std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string()),
+        )];
+        if let Some(socket_name) = &self.socket_name {
+            env.push(("WAYLAND_DISPLAY", socket_name.clone()));
+        }
+        // i3 tooling finds the IPC socket here
+        if let Some(ipc_server) = &self.ipc_server {
+            env.push((
+                "I3SOCK",
+                ipc_server.get_i3_socket_path().display().to_string(),
+            ));
+        }
+        #[cfg(feature = "xwayland")]
+        if let Some(xdisplay) = self.xdisplay {
+            env.push(("DISPLAY", format!(":{xdisplay}")));
+        }
+        env
+    }
+
+    /// Spawn `command` with the child environment. While XWayland is still
+    /// starting, it is held back so X11 programs get DISPLAY too.
+    pub fn spawn_command(&mut self, mut command: std::process::Command, description: String) {
+        command.envs(self.child_environment());
+        #[cfg(feature = "xwayland")]
+        if let Some(pending) = &mut self.pending_x11_commands {
+            debug!("Holding back '{description}' until XWayland is ready");
+            pending.push((command, description));
+            return;
+        }
+        spawn_logged(command, &description);
+    }
+
+    /// Spawn the commands held back while XWayland was starting, with its
+    /// DISPLAY if it came up
+    #[cfg(feature = "xwayland")]
+    pub fn spawn_pending_commands(&mut self) {
+        let display = self.xdisplay.map(|xdisplay| format!(":{xdisplay}"));
+        for (mut command, description) in self.pending_x11_commands.take().unwrap_or_default() {
+            if let Some(display) = &display {
+                command.env("DISPLAY", display);
+            }
+            spawn_logged(command, &description);
+        }
+    }
+
+    /// The `output` config for a virtual output, by its name or the name of
+    /// one of its physical outputs
+    fn output_config_for(
+        &self,
+        virtual_output_id: crate::virtual_output::VirtualOutputId,
+    ) -> Option<&crate::config::OutputConfig> {
+        let virtual_output = self.virtual_output_manager.get(virtual_output_id)?;
+        self.config.outputs.iter().find(|output| {
+            output.name == virtual_output.name()
+                || virtual_output
+                    .physical_outputs()
+                    .iter()
+                    .any(|physical| physical.name() == output.name)
+        })
+    }
+
+    /// Gap between tiled windows on a virtual output
+    pub fn inner_gap_on(&self, virtual_output_id: crate::virtual_output::VirtualOutputId) -> i32 {
+        self.output_config_for(virtual_output_id)
+            .and_then(|output| output.gaps_inner)
+            .or(self.config.gaps.inner)
+            .unwrap_or(crate::config::GapConfig::DEFAULT_INNER)
+    }
+
+    /// Change a gap and lay out the visible workspaces with it; hidden ones
+    /// pick it up when they are shown. Outputs with their own inner gap keep
+    /// it.
+    pub fn set_gaps(&mut self, scope: crate::config::GapScope, amount: crate::config::GapAmount) {
+        self.config.gaps.apply(scope, amount);
+        let visible: Vec<_> = self
+            .virtual_output_manager
+            .all_virtual_outputs()
+            .filter_map(|vo| vo.active_workspace_id())
+            .collect();
+        for workspace_id in visible {
+            self.apply_workspace_layout(workspace_id);
+        }
+    }
+
+    /// Border width of tiled windows on a virtual output
+    pub fn border_width_on(
+        &self,
+        virtual_output_id: crate::virtual_output::VirtualOutputId,
+    ) -> i32 {
+        self.output_config_for(virtual_output_id)
+            .and_then(|output| output.border_width)
+            .unwrap_or(self.config.border.width)
+    }
+
+    /// Space tab and title bars take on a virtual output, with the bar
+    /// height fitted to the tab font at the output's scale when
+    /// `tab_bar { font_size }` is set
+    pub fn tab_bar_size_on(
+        &mut self,
+        virtual_output_id: crate::virtual_output::VirtualOutputId,
+    ) -> crate::tab_bar::TabBarSize {
+        let mut size = self.config.tab_bar.size;
+        if let Some(font_size) = self.config.tab_bar.font_size {
+            let scale = self
+                .virtual_output_manager
+                .get(virtual_output_id)
+                .and_then(|vo| vo.physical_outputs().first())
+                .map(|output| output.current_scale().fractional_scale())
+                .unwrap_or(1.0);
+            size.height = self.tab_text_cache.bar_height(font_size, scale);
+        }
+        size
+    }
+
     /// Get a reference to the space (temporary delegation method)
     #[inline]
     pub fn space(&self) -> &Space<WindowElement> {
         self.window_manager.space()
     }
 
+    /// The physical output behind the primary virtual output
+    pub fn primary_output(&self) -> Option<Output> {
+        self.virtual_output_manager
+            .primary_virtual_output()
+            .and_then(|id| self.virtual_output_manager.get(id))
+            .and_then(|vo| vo.physical_outputs().first().cloned())
+            .or_else(|| self.space().outputs().next().cloned())
+    }
+
     /// Validate internal state consistency
     /// Returns Ok(()) if state is consistent, or Err with a list of validation errors
     #[cfg(debug_assertions)]
@@ -2666,6 +3725,20 @@ impl<BackendData: Backend> StilchState<BackendData> {
         &self.input_manager.suppressed_keys
     }
 
+    /// Device pixel grid of the physical output under the middle of `area`,
+    /// which tiled window edges are snapped to
+    fn pixel_grid_at(
+        &self,
+        area: Rectangle<i32, Logical>,
+    ) -> Option<crate::workspace::layout::PixelGrid> {
+        let center = Point::from((area.loc.x + area.size.w / 2, area.loc.y + area.size.h / 2));
+        let output = self.space().output_under(center.to_f64()).next()?;
+        Some(crate::workspace::layout::PixelGrid {
+            origin: self.space().output_geometry(output)?.loc,
+            scale: output.current_scale().fractional_scale(),
+        })
+    }
+
     /// Apply workspace layout to space - optimized to only update changed windows
     pub fn apply_workspace_layout(&mut self, workspace_id: crate::workspace::WorkspaceId) {
         tracing::debug!(
@@ -2674,8 +3747,38 @@ impl<BackendData: Backend> StilchState<BackendData> {
         );
 
         // First, call relayout if needed
+        let pixel_grid = self
+            .workspace_manager
+            .get_workspace(workspace_id)
+            .and_then(|workspace| self.pixel_grid_at(workspace.area));
+        let location = self.workspace_manager.find_workspace_location(workspace_id);
+        let gap = location.map(|vo_id| self.inner_gap_on(vo_id));
+        let tab_bar = match location {
+            Some(vo_id) => self.tab_bar_size_on(vo_id),
+            None => self.config.tab_bar.size,
+        };
+        let [top, right, bottom, left] = self.config.gaps.outer_edges();
+        let outer_gaps = crate::workspace::layout::OuterGaps {
+            top,
+            right,
+            bottom,
+            left,
+        };
+        let smart_gaps = self.config.gaps.smart;
+        let border_width = match location {
+            Some(vo_id) => self.border_width_on(vo_id),
+            None => self.config.border.width,
+        };
+        let floating_border_width = self.config.border.floating_width;
         if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
             // Just ensure layout is recalculated, don't apply yet
+            workspace.layout.set_pixel_grid(pixel_grid);
+            workspace.layout.set_tab_bar(tab_bar);
+            workspace.layout.set_outer_gaps(outer_gaps);
+            workspace.layout.set_smart_gaps(smart_gaps);
+            if let Some(gap) = gap {
+                workspace.layout.set_gap(gap);
+            }
             workspace.relayout();
         }
 
@@ -2692,6 +3795,9 @@ impl<BackendData: Backend> StilchState<BackendData> {
                         &managed_window.layout
                     {
                         let window_element = managed_window.element.clone();
+                        let fullscreen_geometry = managed_window.geometry();
+                        window_element.set_floating_decorations(false);
+                        window_element.set_border(0, crate::workspace::BorderEdges::NONE);
 
                         match mode {
                             crate::window::FullscreenMode::Container => {
@@ -2728,8 +3834,14 @@ impl<BackendData: Backend> StilchState<BackendData> {
                                     .resize_window(fullscreen_id, workspace.area);
                             }
                             crate::window::FullscreenMode::PhysicalOutput => {
-                                // Physical output fullscreen is handled elsewhere
-                                // The window is mapped directly to the physical output
+                                // Stays on the physical output picked when it went
+                                // fullscreen, not the workspace area
+                                self.window_manager.space_mut().map_element(
+                                    window_element,
+                                    fullscreen_geometry.loc,
+                                    true,
+                                );
+                                position_updates.push((fullscreen_id, fullscreen_geometry.loc));
                             }
                         }
 
@@ -2759,6 +3871,9 @@ impl<BackendData: Backend> StilchState<BackendData> {
                     if let Some(managed_window) = self.window_registry().get(window_id) {
                         // Ensure window is mapped to space
                         let window_element = managed_window.element.clone();
+                        window_element.set_floating_decorations(false);
+                        window_element
+                            .set_border(border_width, self.window_border_edges(window_id));
                         self.window_manager.space_mut().map_element(
                             window_element,
                             geometry.loc,
@@ -2772,6 +3887,32 @@ impl<BackendData: Backend> StilchState<BackendData> {
                         self.window_manager.resize_window(window_id, geometry);
                     }
                 }
+
+                // Floating windows are mapped last so they stack above the tiled layout
+                let area = workspace.area;
+                for &window_id in &workspace.floating_windows {
+                    if let Some(managed_window) = self.window_registry().get(window_id) {
+                        let window_element = managed_window.element.clone();
+                        window_element.set_floating_decorations(true);
+                        window_element
+                            .set_border(floating_border_width, self.window_border_edges(window_id));
+                        let mut geometry = managed_window.geometry();
+
+                        // Pull windows that ended up outside the workspace (e.g. moved from
+                        // another output) back to the center of this one
+                        if !area.overlaps(geometry) {
+                            geometry.loc = super::window_rules::center_in(geometry.size, area);
+                        }
+
+                        self.window_manager.space_mut().map_element(
+                            window_element,
+                            geometry.loc,
+                            true,
+                        );
+                        position_updates.push((window_id, geometry.loc));
+                        self.window_manager.resize_window(window_id, geometry);
+                    }
+                }
             }
         }
 
@@ -2786,6 +3927,9 @@ impl<BackendData: Backend> StilchState<BackendData> {
 pub trait Backend {
     const HAS_RELATIVE_MOTION: bool = false;
     const HAS_GESTURES: bool = false;
+    /// Whether stilch runs the session rather than nested in another one,
+    /// and so may update the session's activation environment
+    const OWNS_SESSION: bool = false;
     fn seat_name(&self) -> String;
     fn reset_buffers(&mut self, output: &Output);
     fn early_import(&mut self, surface: &WlSurface);
@@ -2801,9 +3945,55 @@ pub trait Backend {
         self.request_render();
     }
 
+    /// Modifiers as keybindings see them. A nested backend may have another
+    /// modifier stand in for the logo key its host compositor keeps.
+    fn binding_modifiers(
+        &self,
+        modifiers: smithay::input::keyboard::ModifiersState,
+    ) -> smithay::input::keyboard::ModifiersState {
+        modifiers
+    }
+
     fn should_schedule_render(&self) -> bool {
         // Default implementation always returns true
         // Backends can override to prevent duplicate idle callbacks
         true
     }
+
+    /// Switch a physical output to one of its advertised modes.
+    /// Returns false if the backend cannot change modes.
+    fn set_output_mode(&mut self, _output: &Output, _mode: smithay::output::Mode) -> bool {
+        false
+    }
+
+    /// Turn a physical output on or off. Outputs the backend cannot power
+    /// down, such as a nested window, just stop being drawn to.
+    /// Returns false on failure.
+    fn set_output_enabled(&mut self, _output: &Output, _enabled: bool) -> bool {
+        true
+    }
+
+    /// Resize an output that has no fixed modes, such as a nested window,
+    /// to `size` physical pixels. Returns false if the backend cannot.
+    fn resize_output(&mut self, _output: &Output, _size: Size<i32, Physical>) -> bool {
+        false
+    }
+
+    /// Number of entries per channel in the output's gamma ramp,
+    /// or None if the backend cannot set gamma
+    fn gamma_size(&self, _output: &Output) -> Option<u32> {
+        None
+    }
+
+    /// Set the output's red, green and blue ramps (concatenated), or restore
+    /// the default ramp when `ramps` is None. Returns false on failure.
+    fn set_gamma(&mut self, _output: &Output, _ramps: Option<&[u16]>) -> bool {
+        false
+    }
+
+    /// Show the cursor on a hardware cursor plane or composite it into
+    /// frames. Returns false if the backend always composites it.
+    fn set_cursor_mode(&mut self, _mode: crate::config::CursorMode) -> bool {
+        false
+    }
 }
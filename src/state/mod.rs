@@ -2,12 +2,26 @@
 //!
 //! This module contains the main compositor state and its components.
 
+mod binding_mode;
+mod floating;
+mod floating_memory;
+mod focus_tree;
+mod fullscreen_output;
+mod layer_focus;
 mod main;
+mod output_removal;
 mod protocols;
+mod saved_layout;
+mod scratchpad;
+mod urgency;
 pub mod validation;
+mod window_rules;
+pub mod workspace_state;
 
 pub use main::{
-    take_presentation_feedback, update_primary_scanout_output, Backend, ClientState, DndIcon,
-    StilchState, SurfaceDmabufFeedback,
+    load_config, take_presentation_feedback, update_primary_scanout_output, Backend, ClientState,
+    DndIcon, StilchState, SurfaceDmabufFeedback,
 };
 pub use protocols::ProtocolState;
+pub use scratchpad::{Scratchpad, ScratchpadEntry};
+pub use window_rules::TransientChildren;
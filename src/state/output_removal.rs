@@ -0,0 +1,92 @@
+//! Unplugging physical outputs
+//!
+//! The virtual outputs of an unplugged output go away with it. A window
+//! fullscreen on the output is moved to the workspace shown on the remaining
+//! output under the pointer, or else the first one left, and there either
+//! leaves fullscreen or goes fullscreen again as `fullscreen_output_removed`
+//! says.
+
+use smithay::{output::Output, utils::IsAlive};
+use tracing::{info, warn};
+
+use super::{Backend, StilchState};
+use crate::{
+    config::FullscreenOutputRemoved,
+    shell::FullscreenSurface,
+    window::{FullscreenMode, WindowId},
+};
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Forget a physical output that was unplugged
+    pub fn remove_physical_output(&mut self, output: &Output) {
+        // Taken before the output goes, which would leave it dangling there
+        let fullscreen = output
+            .user_data()
+            .get::<FullscreenSurface>()
+            .and_then(|surface| surface.clear())
+            .filter(|window| window.alive())
+            .and_then(|window| self.window_registry().find_by_element(&window));
+
+        if let Some(ref mut physical_layout) = self.physical_layout {
+            let output_name = output.name();
+            info!("Removing display '{}' from physical layout", output_name);
+            physical_layout.remove_display(&output_name);
+        }
+
+        for vo_id in self.virtual_output_manager.remove_physical_output(output) {
+            info!(
+                "Removed virtual output {:?} due to physical output disconnection",
+                vo_id
+            );
+        }
+
+        self.space_mut().unmap_output(output);
+        self.space_mut().refresh();
+
+        if let Some(window_id) = fullscreen {
+            self.rehome_fullscreen_window(window_id);
+        }
+    }
+
+    /// Move a window that was fullscreen on an unplugged output to a
+    /// remaining one
+    fn rehome_fullscreen_window(&mut self, window_id: WindowId) {
+        let target = self
+            .virtual_output_at_pointer()
+            .or_else(|| {
+                self.virtual_output_manager
+                    .list_virtual_outputs()
+                    .into_iter()
+                    .min()
+            })
+            .and_then(|vo_id| self.workspace_manager.workspace_on_output(vo_id));
+
+        // Back to its place on its own workspace first, so it carries its
+        // pre-fullscreen layout along
+        self.set_fullscreen(window_id, false, FullscreenMode::PhysicalOutput);
+
+        let Some(workspace_id) = target else {
+            warn!(
+                "No output left for window {}, which was fullscreen on the removed one",
+                window_id
+            );
+            return;
+        };
+        info!(
+            "Moving window {} off its removed fullscreen output to workspace {}",
+            window_id, workspace_id
+        );
+        self.move_window_to_workspace_by_id(window_id, workspace_id);
+        if self.config.fullscreen_output_removed == FullscreenOutputRemoved::Move {
+            self.set_fullscreen(window_id, true, FullscreenMode::PhysicalOutput);
+        }
+
+        let element = self
+            .window_registry()
+            .get(window_id)
+            .map(|mw| mw.element.clone());
+        if let Some(element) = element {
+            self.focus_window(&element);
+        }
+    }
+}
@@ -33,7 +33,13 @@ use smithay::{
 #[cfg(feature = "xwayland")]
 use smithay::wayland::xwayland_shell::XWaylandShellState;
 
-use crate::state::{Backend, StilchState};
+use crate::{
+    handlers::{
+        foreign_toplevel::ForeignToplevelManagerState, gamma_control::GammaControlManagerState,
+        output_management::OutputManagementState,
+    },
+    state::{Backend, StilchState},
+};
 
 /// Container for all Wayland protocol states
 #[derive(Debug)]
@@ -57,9 +63,12 @@ pub struct ProtocolState<BackendData: Backend + 'static> {
     pub xdg_activation_state: XdgActivationState,
     pub xdg_foreign_state: XdgForeignState,
     pub keyboard_shortcuts_inhibit_state: KeyboardShortcutsInhibitState,
+    pub foreign_toplevel_state: ForeignToplevelManagerState,
 
     // Output and presentation protocols
     pub output_manager_state: OutputManagerState,
+    pub output_management_state: OutputManagementState,
+    pub gamma_control_state: GammaControlManagerState,
     pub presentation_state: PresentationState,
     pub fractional_scale_manager_state: FractionalScaleManagerState,
     pub viewporter_state: ViewporterState,
@@ -119,11 +128,20 @@ impl<BackendData: Backend + 'static> ProtocolState<BackendData> {
             keyboard_shortcuts_inhibit_state: KeyboardShortcutsInhibitState::new::<
                 StilchState<BackendData>,
             >(display_handle),
+            foreign_toplevel_state: ForeignToplevelManagerState::new::<StilchState<BackendData>>(
+                display_handle,
+            ),
 
             // Output and presentation protocols
             output_manager_state: OutputManagerState::new_with_xdg_output::<StilchState<BackendData>>(
                 display_handle,
             ),
+            output_management_state: OutputManagementState::new::<StilchState<BackendData>>(
+                display_handle,
+            ),
+            gamma_control_state: GammaControlManagerState::new::<StilchState<BackendData>>(
+                display_handle,
+            ),
             presentation_state: PresentationState::new::<StilchState<BackendData>>(
                 display_handle,
                 clock.id() as u32,
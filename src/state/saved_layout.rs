@@ -0,0 +1,164 @@
+//! Saving and loading tiling layouts
+//!
+//! `save_layout <path>` writes the tiling tree of the workspace under the
+//! pointer to a JSON file, describing each window by its app_id (its class
+//! for X11 windows), or by its title when it has neither. `append_layout
+//! <path>` adds such a tree to the workspace under the pointer as
+//! placeholders that keep their space free. A new tiled window matching a
+//! placeholder's criteria takes its place, moving to the placeholder's
+//! workspace if it opened elsewhere. Criteria are saved as escaped regular
+//! expressions; edit the file to loosen or tighten them.
+
+use std::path::PathBuf;
+
+use tracing::{debug, info};
+
+use super::{Backend, StilchState};
+use crate::{
+    config::{Criteria, Pattern},
+    error::{StilchError, StilchResult},
+    shell::WindowElement,
+    window::{WindowCriteria, WindowId},
+    workspace::{layout::SavedLayoutNode, WorkspaceId},
+};
+
+/// Criteria a window is saved with, escaped since criteria are patterns
+fn swallow_criteria(window: &WindowElement) -> Option<Criteria> {
+    let window = WindowCriteria::from_element(window);
+    let literal = |value: Option<String>| {
+        value
+            .filter(|value| !value.is_empty())
+            .map(|value| Pattern::literal(&value))
+    };
+    let app_id = literal(window.app_id);
+    let class = literal(window.class);
+    let criteria = if app_id.is_some() || class.is_some() {
+        Criteria {
+            app_id,
+            class,
+            ..Default::default()
+        }
+    } else {
+        Criteria {
+            title: literal(Some(window.title)),
+            ..Default::default()
+        }
+    };
+    (!criteria.is_empty()).then_some(criteria)
+}
+
+/// Resolve a leading `~/` against $HOME
+pub(super) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    fn workspace_at_pointer(&self) -> StilchResult<WorkspaceId> {
+        self.virtual_output_at_pointer()
+            .and_then(|vo_id| self.workspace_manager.workspace_on_output(vo_id))
+            .ok_or_else(|| StilchError::InvalidOperation("No workspace under the pointer".into()))
+    }
+
+    /// Write the tiling layout of the workspace under the pointer to `path`
+    pub fn save_layout(&self, path: &str) -> StilchResult<()> {
+        let workspace_id = self.workspace_at_pointer()?;
+        let workspace = self
+            .workspace_manager
+            .get(workspace_id)
+            .ok_or(StilchError::WorkspaceNotFound(workspace_id.get()))?;
+        let registry = self.window_registry();
+        let saved = workspace
+            .layout
+            .save(|id| {
+                registry
+                    .get(id)
+                    .and_then(|mw| swallow_criteria(&mw.element))
+            })
+            .ok_or_else(|| {
+                StilchError::InvalidOperation(format!(
+                    "Workspace {workspace_id} has no tiled windows to save"
+                ))
+            })?;
+
+        let json = serde_json::to_string_pretty(&saved)
+            .map_err(|err| StilchError::Config(format!("Failed to serialize layout: {err}")))?;
+        std::fs::write(expand_home(path), json)?;
+        info!("Saved layout of workspace {} to {}", workspace_id, path);
+        Ok(())
+    }
+
+    /// Add placeholders for the layout saved in `path` to the workspace under
+    /// the pointer
+    pub fn append_layout(&mut self, path: &str) -> StilchResult<()> {
+        let workspace_id = self.workspace_at_pointer()?;
+        let contents = std::fs::read_to_string(expand_home(path))?;
+        let saved: SavedLayoutNode = serde_json::from_str(&contents)
+            .map_err(|err| StilchError::Config(format!("Invalid layout in {path}: {err}")))?;
+
+        let workspace = self
+            .workspace_manager
+            .get_workspace_mut(workspace_id)
+            .ok_or(StilchError::WorkspaceNotFound(workspace_id.get()))?;
+        let added = workspace.layout.append(&saved, workspace.next_split);
+        let visible = workspace.is_visible();
+        info!(
+            "Appended layout from {} to workspace {} with {} placeholders",
+            path, workspace_id, added
+        );
+
+        if visible {
+            self.apply_workspace_layout(workspace_id);
+        }
+        Ok(())
+    }
+
+    /// Put a new tiled window in the place of the first placeholder it
+    /// matches, on whichever workspace that is. Returns false if none matches.
+    pub fn swallow_into_placeholder(&mut self, window_id: WindowId) -> bool {
+        let Some(managed) = self.window_registry().get(window_id) else {
+            return false;
+        };
+        if !managed.is_tiled() {
+            return false;
+        }
+        let source_workspace = managed.workspace;
+        let window = managed.criteria();
+
+        let Some((workspace_id, placeholder)) =
+            self.workspace_manager.workspaces().iter().find_map(|ws| {
+                ws.layout
+                    .find_placeholder(|criteria| criteria.matches(&window))
+                    .map(|placeholder| (ws.id, placeholder))
+            })
+        else {
+            return false;
+        };
+        debug!(
+            "Window {} matches placeholder {} on workspace {}",
+            window_id, placeholder, workspace_id
+        );
+
+        if source_workspace != workspace_id {
+            self.move_window_to_workspace_by_id(window_id, workspace_id);
+        }
+        let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) else {
+            return false;
+        };
+        if !workspace.swallow_placeholder(placeholder, window_id) {
+            return false;
+        }
+        let visible = workspace.is_visible();
+        info!(
+            "Window {} took the place of a placeholder on workspace {}",
+            window_id, workspace_id
+        );
+
+        if visible {
+            self.apply_workspace_layout(workspace_id);
+        }
+        true
+    }
+}
@@ -0,0 +1,325 @@
+//! Scratchpad
+//!
+//! `move scratchpad` takes a window off its workspace and hides it,
+//! remembering the workspace it was on and whether it was tiled or floating,
+//! with its geometry. `scratchpad show` brings hidden windows back one at a
+//! time, floating and centered on the workspace under the pointer, whichever
+//! output they came from. Running it while a shown scratchpad window is
+//! focused hides that window again behind the others, so repeated presses
+//! cycle through the whole scratchpad. Moving a shown scratchpad window to a
+//! workspace takes it out of the scratchpad and restores its previous layout.
+//! Taskbars minimize windows into the scratchpad too, and unminimizing puts a
+//! window back where it was.
+
+use smithay::{
+    desktop::space::SpaceElement,
+    utils::{IsAlive, Rectangle, SERIAL_COUNTER as SCOUNTER},
+};
+use tracing::{debug, info};
+
+use super::{
+    window_rules::{center_in, set_tiled_states},
+    Backend, StilchState,
+};
+use crate::{
+    shell::WindowElement,
+    window::{NonFullscreenLayout, WindowId, WindowLayout},
+    workspace::WorkspaceId,
+};
+
+/// A window that belongs to the scratchpad
+#[derive(Debug, Clone)]
+pub struct ScratchpadEntry {
+    pub window_id: WindowId,
+    /// Layout the window had before it was first moved to the scratchpad
+    pub previous_layout: NonFullscreenLayout,
+    pub previous_workspace: WorkspaceId,
+    /// Hidden, or shown floating on some workspace
+    hidden: bool,
+}
+
+/// Scratchpad windows, in the order `scratchpad show` brings them back
+#[derive(Debug, Default)]
+pub struct Scratchpad {
+    entries: Vec<ScratchpadEntry>,
+}
+
+impl Scratchpad {
+    pub fn entry(&self, window_id: WindowId) -> Option<&ScratchpadEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.window_id == window_id)
+    }
+
+    /// Whether a window is in the scratchpad and currently hidden
+    pub fn is_hidden(&self, window_id: WindowId) -> bool {
+        self.entry(window_id).is_some_and(|entry| entry.hidden)
+    }
+
+    fn is_shown(&self, window_id: WindowId) -> bool {
+        self.entry(window_id).is_some_and(|entry| !entry.hidden)
+    }
+
+    /// Forget a window that is being destroyed
+    pub fn remove(&mut self, window_id: WindowId) {
+        self.entries.retain(|entry| entry.window_id != window_id);
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Hide a window in the scratchpad, behind the windows already there
+    pub fn move_to_scratchpad(&mut self, window_id: WindowId) {
+        self.prune_scratchpad();
+        let Some(managed) = self.window_registry().get(window_id) else {
+            return;
+        };
+        let Some(layout) = managed.layout.as_non_fullscreen() else {
+            debug!("Not moving fullscreen window {} to scratchpad", window_id);
+            return;
+        };
+        let workspace_id = managed.workspace;
+        let element = managed.element.clone();
+
+        // A window shown from the scratchpad keeps the state it had when it
+        // first went there
+        let mut entry = match self
+            .scratchpad
+            .entries
+            .iter()
+            .position(|entry| entry.window_id == window_id)
+        {
+            Some(index) => self.scratchpad.entries.remove(index),
+            None => ScratchpadEntry {
+                window_id,
+                previous_layout: layout,
+                previous_workspace: workspace_id,
+                hidden: true,
+            },
+        };
+        entry.hidden = true;
+        self.scratchpad.entries.push(entry);
+        info!("Moved window {} to scratchpad", window_id);
+
+        let was_focused = self
+            .focused_window()
+            .is_some_and(|focused| focused == element);
+        if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
+            workspace.remove_window(window_id);
+        }
+        self.space_mut().unmap_elem(&element);
+        self.update_foreign_toplevel(window_id);
+
+        let visible = self
+            .workspace_manager
+            .get(workspace_id)
+            .is_some_and(|ws| ws.is_visible());
+        if !visible {
+            return;
+        }
+        self.apply_workspace_layout(workspace_id);
+        if was_focused {
+            let next = self
+                .workspace_manager
+                .get(workspace_id)
+                .and_then(|ws| ws.focused_window)
+                .and_then(|id| self.window_registry().get(id))
+                .map(|mw| mw.element.clone());
+            match next {
+                Some(next) => self.focus_window(&next),
+                None => {
+                    if let Some(keyboard) = self.seat().get_keyboard() {
+                        keyboard.set_focus(self, None, SCOUNTER.next_serial());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hide the focused scratchpad window, or show the next hidden one
+    pub fn scratchpad_show(&mut self) {
+        self.prune_scratchpad();
+
+        let focused = self
+            .focused_window()
+            .and_then(|window| self.window_registry().find_by_element(&window));
+        if let Some(window_id) = focused.filter(|&id| self.scratchpad.is_shown(id)) {
+            self.move_to_scratchpad(window_id);
+            return;
+        }
+
+        let Some(workspace_id) = self
+            .virtual_output_at_pointer()
+            .and_then(|vo_id| self.workspace_manager.workspace_on_output(vo_id))
+        else {
+            return;
+        };
+        let Some(window_id) = self
+            .scratchpad
+            .entries
+            .iter()
+            .find(|entry| entry.hidden)
+            .map(|entry| entry.window_id)
+        else {
+            debug!("No hidden scratchpad windows");
+            return;
+        };
+        if let Some(element) = self.show_scratchpad_window(window_id, workspace_id) {
+            self.apply_workspace_layout(workspace_id);
+            self.focus_window(&element);
+        }
+    }
+
+    /// Show a hidden scratchpad window where it was before it went there,
+    /// tiled or floating as it was. Returns false for other windows.
+    pub fn restore_hidden_scratchpad_window(&mut self, window_id: WindowId) -> bool {
+        self.prune_scratchpad();
+        let Some(previous_workspace) = self
+            .scratchpad
+            .entry(window_id)
+            .filter(|entry| entry.hidden)
+            .map(|entry| entry.previous_workspace)
+        else {
+            return false;
+        };
+        // Empty workspaces are discarded, the window then comes back on the
+        // one under the pointer
+        let workspace_id = if self.workspace_manager.get(previous_workspace).is_some() {
+            Some(previous_workspace)
+        } else {
+            self.virtual_output_at_pointer()
+                .and_then(|vo_id| self.workspace_manager.workspace_on_output(vo_id))
+        };
+        let Some(workspace_id) = workspace_id else {
+            return false;
+        };
+        if self
+            .show_scratchpad_window(window_id, workspace_id)
+            .is_none()
+        {
+            return false;
+        }
+        self.restore_from_scratchpad(window_id, workspace_id);
+        self.activate_window(window_id);
+        true
+    }
+
+    /// Float a hidden scratchpad window, centered on `workspace_id`, without
+    /// applying the layout
+    fn show_scratchpad_window(
+        &mut self,
+        window_id: WindowId,
+        workspace_id: WorkspaceId,
+    ) -> Option<WindowElement> {
+        let area = self.workspace_manager.get(workspace_id).map(|ws| ws.area)?;
+        let entry = self.scratchpad.entry(window_id)?;
+        let previous = match entry.previous_layout {
+            NonFullscreenLayout::Tiled { geometry, .. } => geometry,
+            NonFullscreenLayout::Floating { geometry } => geometry,
+        };
+        let element = self
+            .window_registry()
+            .get(window_id)
+            .map(|mw| mw.element.clone())?;
+        if let Some(entry) = self
+            .scratchpad
+            .entries
+            .iter_mut()
+            .find(|entry| entry.window_id == window_id)
+        {
+            entry.hidden = false;
+        }
+        let mut size = previous.size;
+        if size.w <= 0 || size.h <= 0 {
+            size = SpaceElement::geometry(&element).size;
+        }
+        size.w = size.w.clamp(1, area.size.w);
+        size.h = size.h.clamp(1, area.size.h);
+        let geometry = Rectangle::new(center_in(size, area), size);
+
+        self.window_registry_mut()
+            .set_workspace(window_id, workspace_id);
+        if let Some(managed) = self.window_registry_mut().get_mut(window_id) {
+            managed.layout = WindowLayout::Floating { geometry };
+        }
+        if let Some(workspace) = self.workspace_manager.get_workspace_mut(workspace_id) {
+            workspace.add_floating_window(window_id);
+            workspace.set_focus(Some(window_id));
+        }
+        set_tiled_states(&element, false);
+        info!(
+            "Showing scratchpad window {} on workspace {}",
+            window_id, workspace_id
+        );
+
+        self.update_foreign_toplevel(window_id);
+        Some(element)
+    }
+
+    /// Take a shown scratchpad window out of the scratchpad onto `workspace_id`,
+    /// tiled or floating as it was before. Returns false for other windows.
+    pub fn restore_from_scratchpad(
+        &mut self,
+        window_id: WindowId,
+        workspace_id: WorkspaceId,
+    ) -> bool {
+        let Some(index) = self
+            .scratchpad
+            .entries
+            .iter()
+            .position(|entry| entry.window_id == window_id && !entry.hidden)
+        else {
+            return false;
+        };
+        let entry = self.scratchpad.entries.remove(index);
+        info!(
+            "Restoring scratchpad window {} to workspace {}",
+            window_id, workspace_id
+        );
+
+        match entry.previous_layout {
+            NonFullscreenLayout::Tiled { .. } => self.set_window_floating(window_id, false),
+            NonFullscreenLayout::Floating { geometry } => self
+                .window_manager
+                .set_floating_geometry(window_id, geometry),
+        }
+        self.move_window_to_workspace_by_id(window_id, workspace_id);
+        if self
+            .workspace_manager
+            .get(workspace_id)
+            .is_some_and(|ws| ws.is_visible())
+        {
+            self.apply_workspace_layout(workspace_id);
+        }
+        true
+    }
+
+    /// Forget scratchpad windows that were closed. Hidden windows are not in
+    /// the space, so the usual removal on destroy never finds them.
+    fn prune_scratchpad(&mut self) {
+        let closed: Vec<_> = self
+            .scratchpad
+            .entries
+            .iter()
+            .filter(|entry| {
+                self.window_registry()
+                    .get(entry.window_id)
+                    .is_none_or(|mw| !mw.element.alive())
+            })
+            .map(|entry| (entry.window_id, entry.hidden))
+            .collect();
+
+        for (window_id, hidden) in closed {
+            self.scratchpad
+                .entries
+                .retain(|entry| entry.window_id != window_id);
+            self.focus_history.remove(window_id);
+            self.transient_children.remove(window_id);
+            if hidden {
+                let (_, event) = self.window_manager.remove_window(window_id);
+                if let Some(event) = event {
+                    self.event_bus.emit_window(event);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Windows asking for attention
+//!
+//! An xdg-activation request from a window that doesn't have focus either
+//! focuses it or marks it urgent, as `focus_on_window_activation` says.
+//! Urgent windows show up in the IPC workspace state until they get focus.
+//! `focus urgent` jumps to the most recently urgent one, switching
+//! workspaces and outputs as needed.
+
+use std::time::Instant;
+
+use tracing::{debug, info};
+
+use super::{Backend, StilchState};
+use crate::{config::FocusOnWindowActivation, window::WindowId};
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Mark a window as wanting attention, or clear that
+    pub fn set_window_urgent(&mut self, window_id: WindowId, urgent: bool) {
+        let Some(managed) = self.window_registry_mut().get_mut(window_id) else {
+            return;
+        };
+        if managed.is_urgent() == urgent {
+            return;
+        }
+        managed.urgent_since = urgent.then(Instant::now);
+        debug!("Window {} urgent: {}", window_id, urgent);
+        self.update_ipc_workspace_state();
+    }
+
+    /// Act on a window's request for activation
+    pub fn request_window_activation(&mut self, window_id: WindowId) {
+        let focused = self
+            .focused_window()
+            .and_then(|element| self.window_registry().find_by_element(&element));
+        if focused == Some(window_id) {
+            return;
+        }
+        let Some(workspace_id) = self.window_registry().get(window_id).map(|mw| mw.workspace)
+        else {
+            return;
+        };
+
+        let focus = match self.config.focus_on_window_activation {
+            FocusOnWindowActivation::Focus => true,
+            FocusOnWindowActivation::Smart => self
+                .workspace_manager
+                .get(workspace_id)
+                .is_some_and(|ws| ws.is_visible()),
+            FocusOnWindowActivation::Urgent => false,
+            FocusOnWindowActivation::None => {
+                debug!("Ignoring activation request from window {}", window_id);
+                return;
+            }
+        };
+        if focus {
+            info!("Activating window {} on request", window_id);
+            self.activate_window(window_id);
+        } else {
+            self.set_window_urgent(window_id, true);
+        }
+    }
+
+    /// Focus the most recently urgent window, wherever it is
+    pub fn focus_urgent(&mut self) {
+        let latest = self
+            .window_registry()
+            .iter()
+            .filter_map(|(id, mw)| Some((id, mw.urgent_since?)))
+            .max_by_key(|&(_, since)| since)
+            .map(|(id, _)| id);
+        match latest {
+            Some(window_id) => {
+                info!("Focusing urgent window {}", window_id);
+                self.activate_window(window_id);
+            }
+            None => debug!("No urgent window to focus"),
+        }
+    }
+}
@@ -48,6 +48,20 @@ pub enum ValidationError {
     },
 }
 
+impl ValidationError {
+    /// Name of the violated invariant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::WindowReferencesInvalidWorkspace { .. } => "WindowReferencesInvalidWorkspace",
+            Self::WorkspaceContainsOrphanWindow { .. } => "WorkspaceContainsOrphanWindow",
+            Self::WindowInWorkspaceButNotInRegistry { .. } => "WindowInWorkspaceButNotInRegistry",
+            Self::WindowInRegistryButNotInWorkspace { .. } => "WindowInRegistryButNotInWorkspace",
+            Self::VirtualOutputShowsInvalidWorkspace { .. } => "VirtualOutputShowsInvalidWorkspace",
+            Self::WindowInMultipleWorkspaces { .. } => "WindowInMultipleWorkspaces",
+        }
+    }
+}
+
 /// Result type for validation operations
 pub type ValidationResult = Result<(), Vec<ValidationError>>;
 
@@ -177,8 +191,13 @@ pub fn validate_full_state<BackendData: crate::state::Backend + 'static>(
 ) -> ValidationResult {
     let mut all_errors = Vec::new();
 
-    // Get all windows from the registry
-    let windows: Vec<_> = state.window_registry().windows().cloned().collect();
+    // Get all windows from the registry; hidden scratchpad windows belong to no workspace
+    let windows: Vec<_> = state
+        .window_registry()
+        .windows()
+        .filter(|window| !state.scratchpad.is_hidden(window.id))
+        .cloned()
+        .collect();
 
     // Validate workspace-window consistency
     if let Err(errors) = validate_workspace_window_consistency(&state.workspace_manager, &windows) {
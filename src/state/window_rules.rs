@@ -0,0 +1,530 @@
+//! Window rules and floating placement
+//!
+//! Decides whether a new window should float (dialogs, transient and
+//! fixed-size windows), applies `for_window` overrides from the config and
+//! computes where floating windows are placed.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+
+use smithay::{
+    desktop::space::SpaceElement,
+    reexports::{
+        wayland_protocols::xdg::shell::server::xdg_toplevel,
+        wayland_server::protocol::{wl_output::WlOutput, wl_surface::WlSurface},
+    },
+    utils::{Logical, Point, Rectangle, Size},
+    wayland::{compositor::with_states, shell::xdg::SurfaceCachedState},
+};
+use tracing::{debug, info};
+
+use super::{Backend, StilchState};
+use crate::{
+    shell::WindowElement,
+    window::{ContainerId, FullscreenMode, WindowCriteria, WindowId, WindowLayout},
+    workspace::WorkspaceId,
+};
+
+/// Marker stored on a toplevel surface once its initial rules have been evaluated
+#[derive(Debug, Default)]
+struct InitialRulesApplied(Cell<bool>);
+
+/// Fullscreen request a toplevel made before its initial commit, with the
+/// output it named, carried out once the window has its place
+#[derive(Debug, Default)]
+struct PendingFullscreen(RefCell<Option<Option<WlOutput>>>);
+
+/// Parent last seen on a toplevel surface commit
+///
+/// Parents imported through xdg-foreign are set by smithay directly on the
+/// toplevel without going through `XdgShellHandler::parent_changed`, so
+/// changes are detected by comparing on commit instead.
+#[derive(Debug, Default)]
+struct KnownParent(RefCell<Option<WlSurface>>);
+
+/// Windows transient for each parent window, so raising a parent doesn't
+/// have to look up the parent of every other window
+#[derive(Debug, Default)]
+pub struct TransientChildren(HashMap<WindowId, Vec<WindowId>>);
+
+impl TransientChildren {
+    /// Record the parent of `child`, replacing any earlier one
+    pub fn set_parent(&mut self, child: WindowId, parent: Option<WindowId>) {
+        self.remove_child(child);
+        if let Some(parent) = parent.filter(|parent| *parent != child) {
+            self.0.entry(parent).or_default().push(child);
+        }
+    }
+
+    /// Forget a window that went away, as parent and as child
+    pub fn remove(&mut self, window: WindowId) {
+        self.0.remove(&window);
+        self.remove_child(window);
+    }
+
+    /// Windows transient for `parent`, in the order they got it as parent
+    pub fn children(&self, parent: WindowId) -> &[WindowId] {
+        self.0.get(&parent).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn remove_child(&mut self, child: WindowId) {
+        self.0.retain(|_, children| {
+            children.retain(|id| *id != child);
+            !children.is_empty()
+        });
+    }
+}
+
+/// Set or clear the xdg tiled states a toplevel is configured with
+pub(crate) fn set_tiled_states(element: &WindowElement, tiled: bool) {
+    if let Some(toplevel) = element.0.toplevel() {
+        toplevel.with_pending_state(|state| {
+            for edge in [
+                xdg_toplevel::State::TiledLeft,
+                xdg_toplevel::State::TiledRight,
+                xdg_toplevel::State::TiledTop,
+                xdg_toplevel::State::TiledBottom,
+            ] {
+                if tiled {
+                    state.states.set(edge);
+                } else {
+                    state.states.unset(edge);
+                }
+            }
+        });
+    }
+}
+
+/// Location that centers a rectangle of `size` inside `area`
+pub(crate) fn center_in(
+    size: Size<i32, Logical>,
+    area: Rectangle<i32, Logical>,
+) -> Point<i32, Logical> {
+    Point::from((
+        area.loc.x + (area.size.w - size.w) / 2,
+        area.loc.y + (area.size.h - size.h) / 2,
+    ))
+}
+
+/// A window whose minimum and maximum sizes are equal cannot be tiled sensibly
+fn is_fixed_size(min: Size<i32, Logical>, max: Size<i32, Logical>) -> bool {
+    min.w > 0 && min.h > 0 && min == max
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// Whether a window looks like a dialog that should float by default
+    pub fn window_wants_floating(&self, window: &WindowElement) -> bool {
+        if let Some(toplevel) = window.0.toplevel() {
+            if toplevel.parent().is_some() {
+                return true;
+            }
+            let (min, max) = with_states(toplevel.wl_surface(), |states| {
+                let mut cached = states.cached_state.get::<SurfaceCachedState>();
+                let current = cached.current();
+                (current.min_size, current.max_size)
+            });
+            return is_fixed_size(min, max);
+        }
+
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            use smithay::xwayland::xwm::WmWindowType;
+
+            if surface.is_override_redirect() || surface.is_transient_for().is_some() {
+                return true;
+            }
+            if matches!(
+                surface.window_type(),
+                Some(
+                    WmWindowType::Dialog
+                        | WmWindowType::Utility
+                        | WmWindowType::Toolbar
+                        | WmWindowType::Splash
+                )
+            ) {
+                return true;
+            }
+            if let (Some(min), Some(max)) = (surface.min_size(), surface.max_size()) {
+                return is_fixed_size(min, max);
+            }
+        }
+
+        false
+    }
+
+    /// Decide whether a window should float, letting `for_window` rules override the heuristics
+    pub fn should_float(&self, window: &WindowElement) -> bool {
+        self.config
+            .floating_override(&WindowCriteria::from_element(window))
+            .unwrap_or_else(|| self.window_wants_floating(window))
+    }
+
+    /// Workspace an `assign` rule opens a window on, if any
+    pub fn assigned_workspace(&self, window: &WindowElement) -> Option<WorkspaceId> {
+        self.config
+            .assigned_workspace(&WindowCriteria::from_element(window))
+            .map(|number| WorkspaceId::new(number - 1))
+    }
+
+    /// Whether a `no_focus` rule keeps a new window from taking focus. Like in
+    /// i3, this only applies while another window has focus to keep.
+    pub fn refuses_focus(&self, window: &WindowElement) -> bool {
+        self.config.no_focus(&WindowCriteria::from_element(window))
+            && self
+                .focused_window()
+                .is_some_and(|focused| &focused != window)
+    }
+
+    /// Whether a window is an xdg toplevel that hasn't committed its
+    /// initial state, so its app_id and title aren't known yet
+    pub fn initial_commit_pending(&self, window: &WindowElement) -> bool {
+        window.0.toplevel().is_some_and(|toplevel| {
+            with_states(toplevel.wl_surface(), |states| {
+                !states
+                    .data_map
+                    .get::<InitialRulesApplied>()
+                    .is_some_and(|applied| applied.0.get())
+            })
+        })
+    }
+
+    /// Hold a fullscreen request on `output` until the toplevel's initial
+    /// commit, when its workspace and `assign` rules are known
+    pub fn defer_fullscreen_request(&self, surface: &WlSurface, output: Option<WlOutput>) {
+        with_states(surface, |states| {
+            states
+                .data_map
+                .insert_if_missing(PendingFullscreen::default);
+            if let Some(pending) = states.data_map.get::<PendingFullscreen>() {
+                *pending.0.borrow_mut() = Some(output);
+            }
+        });
+    }
+
+    /// Drop a fullscreen request held back by `defer_fullscreen_request`
+    pub fn cancel_fullscreen_request(&self, surface: &WlSurface) {
+        with_states(surface, |states| {
+            if let Some(pending) = states.data_map.get::<PendingFullscreen>() {
+                pending.0.borrow_mut().take();
+            }
+        });
+    }
+
+    /// Find the managed window a dialog is transient for
+    pub fn parent_window_id(&self, window: &WindowElement) -> Option<WindowId> {
+        if let Some(toplevel) = window.0.toplevel() {
+            let parent = toplevel.parent()?;
+            return self.window_registry().find_by_surface(&parent);
+        }
+
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            let parent = surface.is_transient_for()?;
+            return self
+                .window_registry()
+                .iter()
+                .find(|(_, mw)| {
+                    mw.element
+                        .0
+                        .x11_surface()
+                        .is_some_and(|s| s.window_id() == parent)
+                })
+                .map(|(id, _)| id);
+        }
+
+        None
+    }
+
+    /// Geometry for a window that just became floating: its preferred size,
+    /// centered over its parent or, failing that, over the workspace
+    fn initial_floating_geometry(
+        &self,
+        window: &WindowElement,
+        workspace_id: WorkspaceId,
+    ) -> Rectangle<i32, Logical> {
+        let area = self
+            .workspace_manager
+            .get_workspace(workspace_id)
+            .map(|ws| ws.area)
+            .unwrap_or_else(|| Rectangle::from_size((800, 600).into()));
+
+        let min_size = window
+            .0
+            .toplevel()
+            .map(|toplevel| {
+                with_states(toplevel.wl_surface(), |states| {
+                    states
+                        .cached_state
+                        .get::<SurfaceCachedState>()
+                        .current()
+                        .min_size
+                })
+            })
+            .unwrap_or_default();
+
+        let current_size = window.geometry().size;
+        let mut size = if min_size.w > 0 && min_size.h > 0 {
+            min_size
+        } else if current_size.w > 0 && current_size.h > 0 {
+            current_size
+        } else {
+            Size::from((area.size.w * 2 / 3, area.size.h * 2 / 3))
+        };
+        size.w = size.w.min(area.size.w);
+        size.h = size.h.min(area.size.h);
+
+        let anchor = self
+            .parent_window_id(window)
+            .and_then(|parent| self.window_registry().get(parent))
+            .filter(|parent| parent.workspace == workspace_id)
+            .map(|parent| parent.geometry())
+            .filter(|geometry| !geometry.is_empty())
+            .unwrap_or(area);
+
+        Rectangle::new(center_in(size, anchor), size)
+    }
+
+    /// Move a window between the layout tree and the floating layer without
+    /// applying the layout. Returns whether anything changed.
+    pub(crate) fn update_floating_state(&mut self, window_id: WindowId, floating: bool) -> bool {
+        let Some(managed_window) = self.window_registry().get(window_id) else {
+            return false;
+        };
+        if managed_window.is_fullscreen() || managed_window.is_floating() == floating {
+            return false;
+        }
+        let workspace_id = managed_window.workspace;
+        let element = managed_window.element.clone();
+        let previous_geometry = managed_window.geometry();
+
+        let changed = self
+            .workspace_manager
+            .get_workspace_mut(workspace_id)
+            .map(|ws| ws.set_window_floating(window_id, floating))
+            .unwrap_or(false);
+        if !changed {
+            return false;
+        }
+
+        let layout = if floating {
+            WindowLayout::Floating {
+                geometry: self.initial_floating_geometry(&element, workspace_id),
+            }
+        } else {
+            WindowLayout::Tiled {
+                container: ContainerId::next(), // Temporary - will be replaced by workspace
+                geometry: previous_geometry,
+            }
+        };
+        if let Some(managed_window) = self.window_registry_mut().get_mut(window_id) {
+            managed_window.layout = layout;
+        }
+
+        set_tiled_states(&element, !floating);
+
+        true
+    }
+
+    /// Float or tile a window and re-apply the layout of its workspace
+    pub fn set_window_floating(&mut self, window_id: WindowId, floating: bool) {
+        if !self.update_floating_state(window_id, floating) {
+            return;
+        }
+        info!("Window {} floating: {}", window_id, floating);
+
+        let Some(workspace_id) = self.window_registry().get(window_id).map(|mw| mw.workspace)
+        else {
+            return;
+        };
+        let is_visible = self
+            .workspace_manager
+            .find_workspace_location(workspace_id)
+            .and_then(|vo_id| self.virtual_output_manager.get(vo_id))
+            .map(|vo| vo.active_workspace() == Some(workspace_id.get() as usize))
+            .unwrap_or(false);
+        if is_visible {
+            self.apply_workspace_layout(workspace_id);
+        }
+    }
+
+    /// Re-evaluate floating once the client has committed its initial state.
+    ///
+    /// Wayland clients set their parent and size hints after the toplevel is
+    /// created, so the check in `add_window` alone cannot see them.
+    pub fn apply_initial_window_rules(&mut self, surface: &WlSurface) {
+        let first_commit = with_states(surface, |states| {
+            states
+                .data_map
+                .insert_if_missing(InitialRulesApplied::default);
+            states
+                .data_map
+                .get::<InitialRulesApplied>()
+                .map(|applied| !applied.0.replace(true))
+                .unwrap_or(false)
+        });
+        if first_commit {
+            self.reevaluate_floating(surface);
+            // xdg toplevels only have an app_id and title once they commit,
+            // which is still before their first buffer shows up
+            if let Some(window_id) = self.window_registry().find_by_surface(surface) {
+                self.apply_assignment(window_id);
+                self.apply_focus_follows_mouse_rule(window_id);
+                self.swallow_into_placeholder(window_id);
+                self.apply_pending_fullscreen(window_id, surface);
+                self.focus_new_window(window_id);
+            }
+        }
+    }
+
+    /// Move a window to the workspace an `assign` rule gives it
+    fn apply_assignment(&mut self, window_id: WindowId) {
+        let Some(managed) = self.window_registry().get(window_id) else {
+            return;
+        };
+        let current = managed.workspace;
+        let Some(assigned) = self.assigned_workspace(&managed.element) else {
+            return;
+        };
+        if assigned != current {
+            info!("Assigning window {} to workspace {}", window_id, assigned);
+            self.move_window_to_workspace_by_id(window_id, assigned);
+        }
+    }
+
+    /// Carry out a fullscreen request made before the initial commit
+    fn apply_pending_fullscreen(&mut self, window_id: WindowId, surface: &WlSurface) {
+        let pending = with_states(surface, |states| {
+            states
+                .data_map
+                .get::<PendingFullscreen>()
+                .and_then(|pending| pending.0.borrow_mut().take())
+        });
+        match pending {
+            Some(Some(output)) => self.fullscreen_on_requested_output(window_id, &output),
+            Some(None) => {
+                self.set_window_fullscreen(window_id, true, FullscreenMode::VirtualOutput)
+            }
+            None => {}
+        }
+    }
+
+    /// Store the focus_follows_mouse override `for_window` rules give a
+    /// window. It stays with the window wherever it moves.
+    pub(crate) fn apply_focus_follows_mouse_rule(&mut self, window_id: WindowId) {
+        let Some(managed) = self.window_registry().get(window_id) else {
+            return;
+        };
+        let focus_follows_mouse = self
+            .config
+            .focus_follows_mouse_override(&managed.criteria());
+        if let Some(managed) = self.window_registry_mut().get_mut(window_id) {
+            managed.focus_follows_mouse = focus_follows_mouse;
+        }
+    }
+
+    /// Focus a toplevel that just committed its initial state, unless a
+    /// `no_focus` rule matches it or an `assign` rule sent it to a hidden
+    /// workspace, and move the pointer to its center
+    fn focus_new_window(&mut self, window_id: WindowId) {
+        let Some((element, geometry)) = self
+            .window_registry()
+            .get(window_id)
+            .map(|mw| (mw.element.clone(), mw.geometry()))
+        else {
+            return;
+        };
+        if self.space().element_location(&element).is_none() {
+            return;
+        }
+        if self.refuses_focus(&element) {
+            debug!("Not focusing new window {} (no_focus)", window_id);
+            return;
+        }
+
+        self.focus_window(&element);
+        let center = Point::<f64, Logical>::from((
+            geometry.loc.x as f64 + geometry.size.w as f64 / 2.0,
+            geometry.loc.y as f64 + geometry.size.h as f64 / 2.0,
+        ));
+        self.pointer().set_location(center);
+    }
+
+    /// Float a window whose parent changed since its last commit
+    pub fn check_parent_on_commit(&mut self, surface: &WlSurface) {
+        let Some(window_id) = self.window_registry().find_by_surface(surface) else {
+            return;
+        };
+        let Some(parent) = self
+            .window_registry()
+            .get(window_id)
+            .and_then(|mw| mw.element.0.toplevel().map(|toplevel| toplevel.parent()))
+        else {
+            return;
+        };
+
+        let changed = with_states(surface, |states| {
+            states.data_map.insert_if_missing(KnownParent::default);
+            states
+                .data_map
+                .get::<KnownParent>()
+                .map(|known| known.0.replace(parent.clone()) != parent)
+                .unwrap_or(false)
+        });
+        if changed {
+            self.record_transient_parent(window_id);
+            if parent.is_some() {
+                self.reevaluate_floating(surface);
+            }
+        }
+    }
+
+    /// Index a window under the parent it is transient for
+    pub(crate) fn record_transient_parent(&mut self, window_id: WindowId) {
+        let parent = self
+            .window_registry()
+            .get(window_id)
+            .and_then(|mw| self.parent_window_id(&mw.element));
+        self.transient_children.set_parent(window_id, parent);
+    }
+
+    /// Keep dialogs stacked above the window they are transient for
+    pub fn raise_transient_children(&mut self, parent_id: WindowId) {
+        let children: Vec<_> = self
+            .transient_children
+            .children(parent_id)
+            .iter()
+            .filter_map(|id| self.window_registry().get(*id))
+            .map(|mw| mw.element.clone())
+            .collect();
+
+        for child in children {
+            if self.space().element_location(&child).is_some() {
+                self.space_mut().raise_element(&child, false);
+            }
+        }
+    }
+
+    /// Float a tiled window if its current hints or rules say it should float
+    pub fn reevaluate_floating(&mut self, surface: &WlSurface) {
+        let Some(window_id) = self.window_registry().find_by_surface(surface) else {
+            return;
+        };
+        let Some(managed_window) = self.window_registry().get(window_id) else {
+            return;
+        };
+        if !managed_window.is_tiled() {
+            return;
+        }
+        let element = managed_window.element.clone();
+        let workspace_id = managed_window.workspace;
+        if self.should_float(&element) {
+            debug!("Window {} matches floating rules, floating it", window_id);
+            self.set_window_floating(window_id, true);
+            if self.restore_floating_geometry(window_id) {
+                self.apply_workspace_layout(workspace_id);
+            }
+        }
+    }
+}
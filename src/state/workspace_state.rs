@@ -0,0 +1,278 @@
+//! Remembering which output each workspace was on across restarts
+//!
+//! With `workspace_state_file <path>` set, the output each workspace was
+//! last shown on, and whether it was the one shown there, is written to a
+//! JSON file whenever that changes. On startup the file is read back and
+//! outputs that connect get their workspaces again, matched by name or, for
+//! outputs whose name isn't connected any more, by make and model. Only the
+//! arrangement is kept, not the windows. A missing or unreadable file is
+//! ignored.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use super::{saved_layout::expand_home, Backend, StilchState};
+use crate::{virtual_output::VirtualOutputId, workspace::WorkspaceId};
+
+/// An output as the state file identifies it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedOutput {
+    /// Virtual output name, like `DP-1`
+    pub name: String,
+    #[serde(default)]
+    pub make: String,
+    #[serde(default)]
+    pub model: String,
+}
+
+impl SavedOutput {
+    /// Whether this is the same monitor as `other` by make and model
+    fn same_model(&self, other: &SavedOutput) -> bool {
+        !self.make.is_empty()
+            && !self.model.is_empty()
+            && self.make == other.make
+            && self.model == other.model
+    }
+}
+
+/// Where a workspace was when the state was saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedWorkspace {
+    /// Workspace number as users see it (1-10)
+    pub number: u8,
+    /// Output the workspace was last shown on
+    pub output: SavedOutput,
+    /// Whether it was the workspace shown on that output
+    #[serde(default)]
+    pub visible: bool,
+}
+
+impl SavedWorkspace {
+    pub fn workspace_id(&self) -> Option<WorkspaceId> {
+        self.number.checked_sub(1).map(WorkspaceId::new)
+    }
+}
+
+/// Contents of the workspace state file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceStateFile {
+    #[serde(default)]
+    pub workspaces: Vec<SavedWorkspace>,
+}
+
+impl WorkspaceStateFile {
+    /// Read the state file, treating a missing or corrupt one as empty
+    pub fn load(path: &Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                warn!("Ignoring workspace state file {}: {}", path.display(), err);
+                return Self::default();
+            }
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            warn!(
+                "Ignoring corrupt workspace state file {}: {}",
+                path.display(),
+                err
+            );
+            Self::default()
+        })
+    }
+
+    /// Write the state file atomically, through a temporary file next to it
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// The state with `current` replacing what was saved for the same
+    /// workspaces. Other saved workspaces are kept only while their output is
+    /// not connected, so that unplugged monitors keep their arrangement.
+    pub fn updated_with(
+        &self,
+        current: Vec<SavedWorkspace>,
+        connected: impl Fn(&SavedOutput) -> bool,
+    ) -> Self {
+        let mut workspaces: Vec<SavedWorkspace> = self
+            .workspaces
+            .iter()
+            .filter(|saved| {
+                !current.iter().any(|live| live.number == saved.number) && !connected(&saved.output)
+            })
+            .cloned()
+            .collect();
+        workspaces.extend(current);
+        workspaces.sort_by_key(|saved| saved.number);
+        Self { workspaces }
+    }
+
+    /// Saved workspaces that belong on `output`: those saved for its name, and
+    /// those of a monitor of the same model whose name is not connected
+    pub fn for_output<'a>(
+        &'a self,
+        output: &'a SavedOutput,
+        connected: impl Fn(&str) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a SavedWorkspace> + 'a {
+        self.workspaces.iter().filter(move |saved| {
+            saved.output.name == output.name
+                || (!connected(&saved.output.name) && saved.output.same_model(output))
+        })
+    }
+}
+
+/// The state file and what was last read from or written to it
+#[derive(Debug)]
+pub struct WorkspaceStatePersistence {
+    path: PathBuf,
+    saved: WorkspaceStateFile,
+}
+
+impl WorkspaceStatePersistence {
+    /// Load the state file at `path`, `~/` meaning the home directory
+    pub fn load(path: &str) -> Self {
+        let path = expand_home(path);
+        let saved = WorkspaceStateFile::load(&path);
+        debug!(
+            "Loaded {} saved workspaces from {}",
+            saved.workspaces.len(),
+            path.display()
+        );
+        Self { path, saved }
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// How the state file identifies a virtual output
+    fn saved_output(&self, virtual_output_id: VirtualOutputId) -> Option<SavedOutput> {
+        let virtual_output = self.virtual_output_manager.get(virtual_output_id)?;
+        let properties = virtual_output
+            .physical_outputs()
+            .first()
+            .map(|output| output.physical_properties());
+        Some(SavedOutput {
+            name: virtual_output.name().to_string(),
+            make: properties
+                .as_ref()
+                .map(|p| p.make.clone())
+                .unwrap_or_default(),
+            model: properties.map(|p| p.model).unwrap_or_default(),
+        })
+    }
+
+    fn output_name_connected(&self, name: &str) -> bool {
+        self.virtual_output_manager
+            .outputs()
+            .any(|virtual_output| virtual_output.name() == name)
+    }
+
+    /// Give a newly added output back the workspaces it had when the state
+    /// was saved, unless the config assigns them elsewhere or another output
+    /// holds them now
+    pub(crate) fn restore_saved_workspaces(&mut self, virtual_output_id: VirtualOutputId) {
+        let Some(state) = &self.workspace_state else {
+            return;
+        };
+        let Some(output) = self.saved_output(virtual_output_id) else {
+            return;
+        };
+        let saved: Vec<(WorkspaceId, bool)> = state
+            .saved
+            .for_output(&output, |name| self.output_name_connected(name))
+            .filter_map(|saved| Some((saved.workspace_id()?, saved.visible)))
+            .filter(|&(workspace_id, _)| {
+                self.workspace_manager.get(workspace_id).is_some()
+                    && self
+                        .workspace_manager
+                        .assigned_output(workspace_id)
+                        .is_none()
+                    && self
+                        .workspace_manager
+                        .workspace_association(workspace_id)
+                        .is_none_or(|current| {
+                            current == virtual_output_id
+                                || self.virtual_output_manager.get(current).is_none()
+                        })
+            })
+            .collect();
+
+        let mut shown = None;
+        for &(workspace_id, visible) in &saved {
+            self.workspace_manager
+                .associate_workspace_with_output(workspace_id, virtual_output_id);
+            if visible {
+                shown = Some(workspace_id);
+            }
+        }
+        if !saved.is_empty() {
+            info!(
+                "Restored {} saved workspaces on output {}",
+                saved.len(),
+                output.name
+            );
+        }
+
+        if let Some(workspace_id) = shown {
+            if self
+                .workspace_manager
+                .workspace_on_output(virtual_output_id)
+                .is_none()
+            {
+                self.switch_workspace(virtual_output_id, workspace_id);
+            }
+        }
+    }
+
+    /// Write the workspace arrangement to the state file if it changed
+    pub(crate) fn save_workspace_state(&mut self) {
+        let Some(state) = &self.workspace_state else {
+            return;
+        };
+
+        let current: Vec<SavedWorkspace> = self
+            .workspace_manager
+            .order()
+            .iter()
+            .filter_map(|&workspace_id| {
+                let virtual_output_id =
+                    self.workspace_manager.workspace_association(workspace_id)?;
+                Some(SavedWorkspace {
+                    number: workspace_id.get() + 1,
+                    output: self.saved_output(virtual_output_id)?,
+                    visible: self.workspace_manager.workspace_location(workspace_id)
+                        == Some(virtual_output_id),
+                })
+            })
+            .collect();
+        let updated = state
+            .saved
+            .updated_with(current, |output| self.output_name_connected(&output.name));
+        if updated == state.saved {
+            return;
+        }
+
+        if let Err(err) = updated.save(&state.path) {
+            warn!(
+                "Failed to write workspace state to {}: {}",
+                state.path.display(),
+                err
+            );
+        }
+        if let Some(state) = &mut self.workspace_state {
+            state.saved = updated;
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Built-in status bar
+//!
+//! `status_bar { ... }` draws a strip with workspace indicators, the binding
+//! mode when it isn't the default one, and a clock on every virtual output, for setups that don't run a panel client. The strip
+//! is reserved from the workspace area after layer-shell exclusive zones, see
+//! `update_tiling_area_from_output`. A bar's buffer is only redrawn when its
+//! contents change; the clock asks for a frame once per second.
+
+use std::{collections::HashMap, time::Duration};
+
+use smithay::{
+    backend::renderer::element::memory::MemoryRenderBuffer,
+    output::Output,
+    reexports::calloop::{
+        timer::{TimeoutAction, Timer},
+        LoopHandle,
+    },
+    utils::{Logical, Point, Rectangle, Scale, Size},
+};
+use tracing::warn;
+
+use crate::{
+    config::{StatusBarConfig, StatusBarPosition},
+    ipc::WorkspaceInfo,
+    state::{Backend, StilchState},
+    virtual_output::VirtualOutputId,
+};
+
+const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
+const CLOCK_FORMAT: &str = "%a %d %b %H:%M:%S";
+
+/// What a bar shows, to tell when its buffer is stale
+#[derive(Debug, Clone, PartialEq)]
+struct BarContents {
+    workspaces: Vec<WorkspaceInfo>,
+    focused: Option<usize>,
+    mode: Option<String>,
+    clock: Option<String>,
+    size: Size<i32, Logical>,
+    scale: u64, // Scale as fixed point (multiply by 1000)
+}
+
+#[derive(Debug)]
+struct Bar {
+    region: Rectangle<i32, Logical>,
+    rendered: Option<(BarContents, MemoryRenderBuffer)>,
+}
+
+/// Regions reserved for the status bar and their rendered buffers
+#[derive(Debug, Default)]
+pub struct StatusBar {
+    bars: HashMap<VirtualOutputId, Bar>,
+}
+
+impl StatusBar {
+    /// Reserve the bar at the edge of a virtual output's usable `area`,
+    /// returning what is left for workspaces
+    pub fn reserve(
+        &mut self,
+        vo_id: VirtualOutputId,
+        area: Rectangle<i32, Logical>,
+        config: &StatusBarConfig,
+    ) -> Rectangle<i32, Logical> {
+        let height = config.height.min(area.size.h);
+        let mut region = Rectangle::new(area.loc, Size::from((area.size.w, height)));
+        let mut rest = area;
+        rest.size.h -= height;
+        match config.position {
+            StatusBarPosition::Top => rest.loc.y += height,
+            StatusBarPosition::Bottom => region.loc.y += rest.size.h,
+        }
+
+        let bar = self.bars.entry(vo_id).or_insert(Bar {
+            region,
+            rendered: None,
+        });
+        bar.region = region;
+        rest
+    }
+}
+
+/// A bar as drawn on one output, in output-relative coordinates
+#[derive(Debug, Clone)]
+pub struct StatusBarFrame {
+    pub buffer: MemoryRenderBuffer,
+    pub location: Point<i32, Logical>,
+    pub size: Size<i32, Logical>,
+}
+
+fn clock_text() -> Option<String> {
+    let now = pango::glib::DateTime::now_local().ok()?;
+    now.format(CLOCK_FORMAT).ok().map(|text| text.to_string())
+}
+
+/// Redraw once per second so the clock keeps ticking
+pub fn start_clock<BackendData: Backend + 'static>(
+    handle: &LoopHandle<'static, StilchState<BackendData>>,
+) {
+    let source = handle.insert_source(
+        Timer::from_duration(CLOCK_INTERVAL),
+        |_, _, state: &mut StilchState<BackendData>| {
+            state.backend_data.request_render();
+            TimeoutAction::ToDuration(CLOCK_INTERVAL)
+        },
+    );
+    if let Err(err) = source {
+        warn!("Failed to start the status bar clock: {}", err);
+    }
+}
+
+impl<BackendData: Backend> StilchState<BackendData> {
+    /// The status bars of the virtual outputs shown on `output`
+    pub fn status_bar_frames(&mut self, output: &Output) -> Vec<StatusBarFrame> {
+        let Some(config) = self.config.status_bar.clone() else {
+            return Vec::new();
+        };
+        let Some(output_geometry) = self.space().output_geometry(output) else {
+            return Vec::new();
+        };
+        let scale = Scale::from(output.current_scale().fractional_scale());
+        let workspaces: Vec<WorkspaceInfo> = if config.show_workspaces {
+            self.workspace_infos()
+                .into_iter()
+                .filter(|info| info.active || info.windows > 0)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let clock = if config.show_clock {
+            clock_text()
+        } else {
+            None
+        };
+
+        let mut frames = Vec::new();
+        for vo_id in self
+            .virtual_output_manager
+            .virtual_outputs_for_physical(output)
+        {
+            let Some(vo) = self.virtual_output_manager.get(vo_id) else {
+                continue;
+            };
+            let focused = vo.active_workspace();
+            // A fullscreen window covers the bar
+            let fullscreen = vo
+                .active_workspace_id()
+                .and_then(|ws| self.workspace_manager.get_workspace(ws))
+                .is_some_and(|ws| ws.fullscreen_window.is_some());
+            if fullscreen {
+                continue;
+            }
+            let Some(bar) = self.status_bar.bars.get_mut(&vo_id) else {
+                continue;
+            };
+
+            let contents = BarContents {
+                workspaces: workspaces.clone(),
+                // Workspace indicators use display numbers
+                focused: focused.map(|idx| idx + 1),
+                mode: self.binding_mode.clone(),
+                clock: clock.clone(),
+                size: bar.region.size,
+                scale: (scale.x * 1000.0) as u64,
+            };
+            let stale = bar
+                .rendered
+                .as_ref()
+                .is_none_or(|(rendered, _)| *rendered != contents);
+            if stale {
+                match crate::tab_bar::text_render::render_status_bar(
+                    &contents.workspaces,
+                    contents.focused,
+                    contents.mode.as_deref(),
+                    contents.clock.as_deref(),
+                    contents.size.w,
+                    contents.size.h,
+                    scale,
+                ) {
+                    Ok(buffer) => bar.rendered = Some((contents, buffer)),
+                    Err(err) => warn!(?err, "Failed to render status bar"),
+                }
+            }
+
+            if let Some((_, buffer)) = &bar.rendered {
+                frames.push(StatusBarFrame {
+                    buffer: buffer.clone(),
+                    location: bar.region.loc - output_geometry.loc,
+                    size: bar.region.size,
+                });
+            }
+        }
+        frames
+    }
+}
@@ -8,29 +8,61 @@ use smithay::backend::renderer::{
 };
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
 
+use crate::config::TabBarConfig;
+
 pub mod text_render;
 pub use text_render::TabTextCache;
 
-/// Tab bar height in logical pixels
+/// Default tab bar height in logical pixels
 pub const TAB_BAR_HEIGHT: i32 = 30;
 
+/// Space tab and title bars take above the windows of their container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TabBarSize {
+    /// Height of one bar in logical pixels
+    pub height: i32,
+    /// Space between the bars and the windows below them
+    pub gap: i32,
+}
+
+impl Default for TabBarSize {
+    fn default() -> Self {
+        Self {
+            height: TAB_BAR_HEIGHT,
+            gap: 0,
+        }
+    }
+}
+
+impl TabBarSize {
+    /// Height reserved above the windows of a container showing `bars` bars
+    pub fn reserved(&self, bars: i32) -> i32 {
+        self.height * bars + self.gap
+    }
+}
+
 /// Colors for tab bar
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TabBarColors {
     pub active_bg: [f32; 4],
     pub inactive_bg: [f32; 4],
     pub active_text: [f32; 4],
     pub inactive_text: [f32; 4],
+    /// Line under the active tab
+    pub indicator: [f32; 4],
+    /// Separator between tabs
     pub border: [f32; 4],
 }
 
 impl Default for TabBarColors {
     fn default() -> Self {
         Self {
-            active_bg: [0.2, 0.2, 0.3, 1.0],     // Dark blue-gray
-            inactive_bg: [0.1, 0.1, 0.15, 1.0],  // Darker gray
-            active_text: [1.0, 1.0, 1.0, 1.0],   // White
-            inactive_text: [0.7, 0.7, 0.7, 1.0], // Light gray
-            border: [0.3, 0.3, 0.4, 1.0],        // Medium gray
+            active_bg: [0.2, 0.2, 0.2, 1.0],      // Dark gray
+            inactive_bg: [0.15, 0.15, 0.15, 1.0], // Darker gray
+            active_text: [1.0, 1.0, 1.0, 1.0],    // White
+            inactive_text: [0.7, 0.7, 0.7, 1.0],  // Light gray
+            indicator: [0.4, 0.6, 1.0, 1.0],      // Blue accent
+            border: [0.3, 0.3, 0.3, 1.0],         // Medium gray
         }
     }
 }
@@ -48,7 +80,7 @@ pub struct TabInfo {
 pub struct TabBar<'a> {
     tabs: Vec<TabInfo>,
     geometry: Rectangle<i32, Logical>,
-    _colors: TabBarColors,
+    config: TabBarConfig,
     buffers: Vec<SolidColorBuffer>,
     text_cache: &'a mut TabTextCache,
     is_stacked: bool,
@@ -66,17 +98,19 @@ impl<'a> TabBar<'a> {
     pub fn new_with_cache(
         tabs: Vec<TabInfo>,
         geometry: Rectangle<i32, Logical>,
+        config: &TabBarConfig,
         text_cache: &'a mut TabTextCache,
     ) -> Self {
-        Self::new_internal_with_cache(tabs, geometry, false, text_cache)
+        Self::new_internal_with_cache(tabs, geometry, false, config, text_cache)
     }
 
     pub fn new_stacked_with_cache(
         tabs: Vec<TabInfo>,
         geometry: Rectangle<i32, Logical>,
+        config: &TabBarConfig,
         text_cache: &'a mut TabTextCache,
     ) -> Self {
-        Self::new_internal_with_cache(tabs, geometry, true, text_cache)
+        Self::new_internal_with_cache(tabs, geometry, true, config, text_cache)
     }
 
     fn new_internal(
@@ -91,9 +125,11 @@ impl<'a> TabBar<'a> {
         tabs: Vec<TabInfo>,
         geometry: Rectangle<i32, Logical>,
         is_stacked: bool,
+        config: &TabBarConfig,
         text_cache: &'a mut TabTextCache,
     ) -> Self {
-        let colors = TabBarColors::default();
+        let colors = config.colors;
+        let height = config.size.height;
         let mut buffers = Vec::new();
 
         // Create solid color buffers for each tab
@@ -108,7 +144,7 @@ impl<'a> TabBar<'a> {
                     };
 
                     let buffer =
-                        SolidColorBuffer::new(Size::from((geometry.size.w, TAB_BAR_HEIGHT)), color);
+                        SolidColorBuffer::new(Size::from((geometry.size.w, height)), color);
                     buffers.push(buffer);
                 }
             } else {
@@ -122,14 +158,13 @@ impl<'a> TabBar<'a> {
                         colors.inactive_bg
                     };
 
-                    let buffer =
-                        SolidColorBuffer::new(Size::from((tab_width, TAB_BAR_HEIGHT)), color);
+                    let buffer = SolidColorBuffer::new(Size::from((tab_width, height)), color);
                     buffers.push(buffer);
 
                     // Add border buffer between tabs
                     if i < tabs.len() - 1 {
                         let border_buffer =
-                            SolidColorBuffer::new(Size::from((1, TAB_BAR_HEIGHT)), colors.border);
+                            SolidColorBuffer::new(Size::from((1, height)), colors.border);
                         buffers.push(border_buffer);
                     }
                 }
@@ -139,7 +174,7 @@ impl<'a> TabBar<'a> {
         Self {
             tabs,
             geometry,
-            _colors: colors,
+            config: *config,
             buffers,
             text_cache,
             is_stacked,
@@ -169,10 +204,12 @@ impl<'a> TabBar<'a> {
             for tab in self.tabs.iter() {
                 // Get or create the rendered tab with text
                 if let Ok(buffer) = self.text_cache.get_or_create_tab(
+                    tab.window_id,
                     &tab.title,
                     self.geometry.size.w,
-                    TAB_BAR_HEIGHT,
+                    self.config.size.height,
                     tab.is_active,
+                    &self.config,
                     scale,
                 ) {
                     let location = Point::<i32, Logical>::from((
@@ -190,14 +227,14 @@ impl<'a> TabBar<'a> {
                         &buffer,
                         None,
                         None,
-                        None,
+                        Some(Size::from((self.geometry.size.w, self.config.size.height))),
                         Kind::Unspecified,
                     ) {
                         elements.push(elem);
                     }
                 }
 
-                y_offset += TAB_BAR_HEIGHT;
+                y_offset += self.config.size.height;
             }
         } else {
             // For tabbed layout, render title bars horizontally
@@ -207,10 +244,12 @@ impl<'a> TabBar<'a> {
             for tab in &self.tabs {
                 // Get or create the rendered tab with text
                 if let Ok(buffer) = self.text_cache.get_or_create_tab(
+                    tab.window_id,
                     &tab.title,
                     tab_width,
-                    TAB_BAR_HEIGHT,
+                    self.config.size.height,
                     tab.is_active,
+                    &self.config,
                     scale,
                 ) {
                     let location = Point::<i32, Logical>::from((
@@ -228,7 +267,7 @@ impl<'a> TabBar<'a> {
                         &buffer,
                         None,
                         None,
-                        None,
+                        Some(Size::from((tab_width, self.config.size.height))),
                         Kind::Unspecified,
                     ) {
                         elements.push(elem);
@@ -287,6 +326,7 @@ pub fn create_tab_bar_elements_with_text<R>(
     renderer: &mut R,
     tabs: Vec<TabInfo>,
     container_geometry: Rectangle<i32, Logical>,
+    config: &TabBarConfig,
     scale: Scale<f64>,
     text_cache: &mut TabTextCache,
 ) -> Vec<MemoryRenderBufferRenderElement<R>>
@@ -296,10 +336,10 @@ where
 {
     let tab_bar_geometry = Rectangle {
         loc: container_geometry.loc,
-        size: Size::from((container_geometry.size.w, TAB_BAR_HEIGHT)),
+        size: Size::from((container_geometry.size.w, config.size.height)),
     };
 
-    let mut tab_bar = TabBar::new_with_cache(tabs, tab_bar_geometry, text_cache);
+    let mut tab_bar = TabBar::new_with_cache(tabs, tab_bar_geometry, config, text_cache);
     tab_bar.render_elements_with_text(renderer, scale)
 }
 
@@ -308,6 +348,7 @@ pub fn create_stacked_bar_elements_with_text<R>(
     renderer: &mut R,
     tabs: Vec<TabInfo>,
     container_geometry: Rectangle<i32, Logical>,
+    config: &TabBarConfig,
     scale: Scale<f64>,
     text_cache: &mut TabTextCache,
 ) -> Vec<MemoryRenderBufferRenderElement<R>>
@@ -319,11 +360,12 @@ where
         loc: container_geometry.loc,
         size: Size::from((
             container_geometry.size.w,
-            TAB_BAR_HEIGHT * tabs.len() as i32,
+            config.size.height * tabs.len() as i32,
         )),
     };
 
-    let mut tab_bar = TabBar::new_stacked_with_cache(tabs, stacked_bar_geometry, text_cache);
+    let mut tab_bar =
+        TabBar::new_stacked_with_cache(tabs, stacked_bar_geometry, config, text_cache);
     tab_bar.render_elements_with_text(renderer, scale)
 }
 
@@ -342,18 +384,21 @@ pub fn create_tab_bar_elements(
     tab_bar.render_elements(scale)
 }
 
-/// Calculate the client area for a tabbed container (excluding tab bar)
+/// Calculate the client area for a tabbed container (excluding tab bar and
+/// the gap below it)
 pub fn calculate_client_area(
     container_geometry: Rectangle<i32, Logical>,
+    size: TabBarSize,
 ) -> Rectangle<i32, Logical> {
+    let reserved = size.reserved(1);
     Rectangle {
         loc: Point::from((
             container_geometry.loc.x,
-            container_geometry.loc.y + TAB_BAR_HEIGHT,
+            container_geometry.loc.y + reserved,
         )),
         size: Size::from((
             container_geometry.size.w,
-            container_geometry.size.h - TAB_BAR_HEIGHT,
+            container_geometry.size.h - reserved,
         )),
     }
 }
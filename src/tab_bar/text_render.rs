@@ -7,9 +7,19 @@ use smithay::{
 };
 use std::collections::HashMap;
 
+use super::TabBarColors;
+use crate::{config::TabBarConfig, ipc::WorkspaceInfo, window::WindowId};
+
+/// Tab text size when no font size is configured
+const DEFAULT_TAB_FONT: &str = "sans 14px";
+
+/// Space above and below the text of a tab bar sized to its font
+const TAB_TEXT_PADDING: f64 = 6.0;
+
 /// Cache key for rendered tab text
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 struct TabTextKey {
+    window_id: WindowId,
     title: String,
     width: i32,
     height: i32,
@@ -22,25 +32,51 @@ struct TabTextKey {
 #[derive(Debug)]
 pub struct TabTextCache {
     cache: HashMap<TabTextKey, MemoryRenderBuffer>,
+    /// Colors and font size everything in the cache was drawn with
+    style: (TabBarColors, Option<f64>),
+    /// Bar heights fitting a font size at an output scale, by their bits
+    heights: HashMap<(u64, u64), i32>,
 }
 
 impl TabTextCache {
     pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
+            style: (TabBarColors::default(), None),
+            heights: HashMap::new(),
         }
     }
 
+    /// Logical height of a tab bar fitting text of `font_size` points, as
+    /// laid out at `scale`
+    pub fn bar_height(&mut self, font_size: f64, scale: f64) -> i32 {
+        *self
+            .heights
+            .entry((font_size.to_bits(), scale.to_bits()))
+            .or_insert_with(|| fit_bar_height(font_size, scale).unwrap_or(super::TAB_BAR_HEIGHT))
+    }
+
     /// Get or create a rendered tab with text
+    #[allow(clippy::too_many_arguments)]
     pub fn get_or_create_tab(
         &mut self,
+        window_id: WindowId,
         title: &str,
         width: i32,
         height: i32,
         is_active: bool,
+        config: &TabBarConfig,
         scale: Scale<f64>,
     ) -> Result<MemoryRenderBuffer, Box<dyn std::error::Error>> {
+        // Tabs drawn before the colors or font changed are stale
+        let style = (config.colors, config.font_size);
+        if style != self.style {
+            self.cache.clear();
+            self.style = style;
+        }
+
         let key = TabTextKey {
+            window_id,
             title: title.to_string(),
             width,
             height,
@@ -52,7 +88,7 @@ impl TabTextCache {
             return Ok(buffer.clone());
         }
 
-        let buffer = render_tab_text(title, width, height, is_active, scale)?;
+        let buffer = render_tab_text(title, width, height, is_active, config, scale)?;
         self.cache.insert(key.clone(), buffer.clone());
         Ok(buffer)
     }
@@ -63,6 +99,12 @@ impl TabTextCache {
         self.cache.clear();
     }
 
+    /// Drop everything rendered for a window, e.g. after its title changed
+    /// or it was closed
+    pub fn invalidate_window(&mut self, window_id: WindowId) {
+        self.cache.retain(|key, _| key.window_id != window_id);
+    }
+
     /// Remove old entries to prevent unbounded growth
     /// Call this periodically or when tabs are closed
     #[allow(dead_code)]
@@ -75,43 +117,70 @@ impl TabTextCache {
     }
 }
 
+/// Font tab text is drawn with
+fn tab_font(font_size: Option<f64>) -> FontDescription {
+    match font_size {
+        Some(points) => FontDescription::from_string(&format!("sans {points}")),
+        None => FontDescription::from_string(DEFAULT_TAB_FONT),
+    }
+}
+
+/// Lay out a line of text at `font_size` points on a surface at `scale`,
+/// and size a bar to fit it with some padding
+fn fit_bar_height(font_size: f64, scale: f64) -> Result<i32, Box<dyn std::error::Error>> {
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 1, 1)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.scale(scale, scale);
+
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.set_font_description(Some(&tab_font(Some(font_size))));
+    layout.set_text("Ag");
+    let (_, text_height) = layout.pixel_size();
+
+    let physical = ((text_height as f64 + 2.0 * TAB_TEXT_PADDING) * scale).ceil();
+    Ok((physical / scale).ceil() as i32)
+}
+
 /// Render tab text to a memory buffer
 fn render_tab_text(
     title: &str,
     width: i32,
     height: i32,
     is_active: bool,
-    _scale: Scale<f64>, // Currently unused, but kept for future HiDPI support
+    config: &TabBarConfig,
+    scale: Scale<f64>,
 ) -> Result<MemoryRenderBuffer, Box<dyn std::error::Error>> {
-    // Don't scale dimensions - keep them logical
-    // The compositor will handle scaling for the output
-    let physical_width = width;
-    let physical_height = height;
+    let colors = &config.colors;
+    // Render at the output's pixel density so text stays crisp on HiDPI
+    // outputs; everything below is drawn in logical coordinates
+    let physical_width = (width as f64 * scale.x).ceil() as i32;
+    let physical_height = (height as f64 * scale.y).ceil() as i32;
 
     // Create Cairo surface
     let surface = ImageSurface::create(cairo::Format::ARgb32, physical_width, physical_height)?;
     let cr = cairo::Context::new(&surface)?;
+    cr.scale(scale.x, scale.y);
+
+    let set_color = |[r, g, b, a]: [f32; 4]| {
+        cr.set_source_rgba(r as f64, g as f64, b as f64, a as f64);
+    };
 
     // Background color
     if is_active {
-        // Active tab - darker background
-        cr.set_source_rgba(0.2, 0.2, 0.2, 1.0);
+        set_color(colors.active_bg);
     } else {
-        // Inactive tab - lighter background
-        cr.set_source_rgba(0.15, 0.15, 0.15, 1.0);
+        set_color(colors.inactive_bg);
     }
     cr.paint()?;
 
     // Set up font
-    // Don't scale font size - we're rendering at logical size
-    let font_size = 14.0;
-    let font = FontDescription::from_string(&format!("sans {}px", font_size));
+    let font = tab_font(config.font_size);
 
     // Create Pango layout
     let layout = pangocairo::functions::create_layout(&cr);
     layout.set_font_description(Some(&font));
-    layout.set_width(physical_width * pango::SCALE);
-    layout.set_height(physical_height * pango::SCALE);
+    layout.set_width(width * pango::SCALE);
+    layout.set_height(height * pango::SCALE);
     layout.set_alignment(Alignment::Center);
     layout.set_ellipsize(EllipsizeMode::End);
 
@@ -120,15 +189,15 @@ fn render_tab_text(
 
     // Text color
     if is_active {
-        cr.set_source_rgba(1.0, 1.0, 1.0, 1.0); // White for active
+        set_color(colors.active_text);
     } else {
-        cr.set_source_rgba(0.7, 0.7, 0.7, 1.0); // Gray for inactive
+        set_color(colors.inactive_text);
     }
 
     // Center the text vertically
     let (_ink_rect, logical_rect) = layout.extents();
     let text_height = logical_rect.height() / pango::SCALE;
-    let y_offset = (physical_height - text_height) / 2;
+    let y_offset = (height - text_height) / 2;
 
     // Draw the text
     cr.move_to(8.0, y_offset as f64); // Small left padding
@@ -136,18 +205,18 @@ fn render_tab_text(
 
     // Draw bottom border for active tab
     if is_active {
-        cr.set_source_rgba(0.4, 0.6, 1.0, 1.0); // Blue accent
+        set_color(colors.indicator);
         cr.set_line_width(2.0);
-        cr.move_to(0.0, physical_height as f64 - 1.0);
-        cr.line_to(physical_width as f64, physical_height as f64 - 1.0);
+        cr.move_to(0.0, height as f64 - 1.0);
+        cr.line_to(width as f64, height as f64 - 1.0);
         cr.stroke()?;
     }
 
     // Draw right border (tab separator)
-    cr.set_source_rgba(0.3, 0.3, 0.3, 1.0);
+    set_color(colors.border);
     cr.set_line_width(1.0);
-    cr.move_to(physical_width as f64 - 0.5, 0.0);
-    cr.line_to(physical_width as f64 - 0.5, physical_height as f64);
+    cr.move_to(width as f64 - 0.5, 0.0);
+    cr.line_to(width as f64 - 0.5, height as f64);
     cr.stroke()?;
 
     drop(cr);
@@ -162,10 +231,145 @@ fn render_tab_text(
         &data,
         Fourcc::Argb8888,
         (physical_width, physical_height),
-        1, // Buffer scale is 1:1 since we're rendering at logical size
+        1, // Pixel-sized; the render element scales it back to the logical tab size
         Transform::Normal,
         None, // No damage tracking
     );
 
     Ok(buffer)
 }
+
+/// Render a status bar: workspace indicators and the binding `mode` on the
+/// left, `clock` on the right. `focused` is the workspace shown on the bar's
+/// virtual output.
+pub fn render_status_bar(
+    workspaces: &[WorkspaceInfo],
+    focused: Option<usize>,
+    mode: Option<&str>,
+    clock: Option<&str>,
+    width: i32,
+    height: i32,
+    scale: Scale<f64>,
+) -> Result<MemoryRenderBuffer, Box<dyn std::error::Error>> {
+    let physical_width = (width as f64 * scale.x).ceil() as i32;
+    let physical_height = (height as f64 * scale.y).ceil() as i32;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, physical_width, physical_height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.scale(scale.x, scale.y);
+
+    cr.set_source_rgba(0.1, 0.1, 0.1, 1.0);
+    cr.paint()?;
+
+    let font = FontDescription::from_string("sans 13px");
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.set_font_description(Some(&font));
+
+    let mut x = 0;
+    for workspace in workspaces {
+        layout.set_text(&workspace.id.to_string());
+        let (text_width, text_height) = layout.pixel_size();
+        let button_width = (text_width + 16).max(height);
+
+        if Some(workspace.id) == focused {
+            cr.set_source_rgba(0.4, 0.6, 1.0, 1.0); // Blue accent, like the active tab
+        } else if workspace.urgent {
+            cr.set_source_rgba(0.8, 0.2, 0.2, 1.0);
+        } else if workspace.active {
+            // Shown on another virtual output
+            cr.set_source_rgba(0.3, 0.3, 0.3, 1.0);
+        } else {
+            cr.set_source_rgba(0.2, 0.2, 0.2, 1.0);
+        }
+        cr.rectangle(x as f64, 0.0, button_width as f64, height as f64);
+        cr.fill()?;
+
+        cr.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+        cr.move_to(
+            (x + (button_width - text_width) / 2) as f64,
+            ((height - text_height) / 2) as f64,
+        );
+        pangocairo::functions::show_layout(&cr, &layout);
+
+        x += button_width + 1;
+    }
+
+    // The binding mode follows the workspaces, like in i3bar
+    if let Some(mode) = mode {
+        layout.set_text(mode);
+        let (text_width, text_height) = layout.pixel_size();
+        let button_width = text_width + 16;
+
+        cr.set_source_rgba(0.56, 0.0, 0.0, 1.0);
+        cr.rectangle(x as f64, 0.0, button_width as f64, height as f64);
+        cr.fill()?;
+
+        cr.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+        cr.move_to((x + 8) as f64, ((height - text_height) / 2) as f64);
+        pangocairo::functions::show_layout(&cr, &layout);
+    }
+
+    if let Some(clock) = clock {
+        layout.set_text(clock);
+        let (text_width, text_height) = layout.pixel_size();
+        cr.set_source_rgba(0.9, 0.9, 0.9, 1.0);
+        cr.move_to(
+            (width - text_width - 8) as f64,
+            ((height - text_height) / 2) as f64,
+        );
+        pangocairo::functions::show_layout(&cr, &layout);
+    }
+
+    drop(cr);
+
+    let data = surface.take_data().map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::Other, "Failed to take surface data")
+    })?;
+
+    Ok(MemoryRenderBuffer::from_slice(
+        &data,
+        Fourcc::Argb8888,
+        (physical_width, physical_height),
+        1,
+        Transform::Normal,
+        None,
+    ))
+}
+
+/// Render multi-line, left-aligned text on a translucent dark background
+pub fn render_text_block(
+    text: &str,
+    width: i32,
+    height: i32,
+) -> Result<MemoryRenderBuffer, Box<dyn std::error::Error>> {
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.7);
+    cr.paint()?;
+
+    let font = FontDescription::from_string("monospace 12px");
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.set_font_description(Some(&font));
+    layout.set_width((width - 16) * pango::SCALE);
+    layout.set_alignment(Alignment::Left);
+    layout.set_text(text);
+
+    cr.set_source_rgba(0.9, 0.9, 0.9, 1.0);
+    cr.move_to(8.0, 6.0);
+    pangocairo::functions::show_layout(&cr, &layout);
+    drop(cr);
+
+    let data = surface.take_data().map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::Other, "Failed to take surface data")
+    })?;
+
+    Ok(MemoryRenderBuffer::from_slice(
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        1,
+        Transform::Normal,
+        None,
+    ))
+}
@@ -97,7 +97,7 @@ impl<BackendData: crate::state::Backend + 'static> CompositorTestHandler<Backend
                                 managed_window.layout,
                                 WindowLayout::Fullscreen { .. }
                             ),
-                            urgent: false,
+                            urgent: managed_window.is_urgent(),
                             tab_info: None,
                         };
 
@@ -165,7 +165,7 @@ impl<BackendData: crate::state::Backend + 'static> TestCommandHandler
 {
     fn handle_command(&mut self, command: TestCommand) -> TestResponse {
         match command {
-            TestCommand::CreateWindow { width, height } => {
+            TestCommand::CreateWindow { width, height, .. } => {
                 let id = self.create_test_window(width, height);
                 self.sync_ascii();
                 TestResponse::WindowCreated { id }
@@ -289,6 +289,10 @@ impl<BackendData: crate::state::Backend + 'static> TestCommandHandler
                         ),
                         title: None,   // Title not available in regular mode yet
                         visible: true, // All workspace windows are considered visible
+                        color: super::solid_window::window_color(&managed_window.element),
+                        stacking: None,
+                        borders: Vec::new(),
+                        urgent: managed_window.is_urgent(),
                     });
                 }
 
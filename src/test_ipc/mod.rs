@@ -4,6 +4,7 @@
 //! the compositor and request ASCII state representations.
 
 pub mod compositor_handler;
+pub mod solid_window;
 
 pub use compositor_handler::CompositorTestHandler;
 
@@ -97,6 +98,27 @@ impl std::fmt::Display for LayoutMode {
     }
 }
 
+/// Fullscreen mode for `SetFullscreen`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FullscreenMode {
+    Container,
+    #[default]
+    VirtualOutput,
+    PhysicalOutput,
+}
+
+impl FullscreenMode {
+    /// Convert to the window manager's fullscreen mode
+    pub fn to_window_mode(self) -> crate::window::FullscreenMode {
+        match self {
+            FullscreenMode::Container => crate::window::FullscreenMode::Container,
+            FullscreenMode::VirtualOutput => crate::window::FullscreenMode::VirtualOutput,
+            FullscreenMode::PhysicalOutput => crate::window::FullscreenMode::PhysicalOutput,
+        }
+    }
+}
+
 impl LayoutMode {
     /// Convert to config LayoutCommand
     pub fn to_layout_command(self) -> Option<crate::config::LayoutCommand> {
@@ -109,6 +131,32 @@ impl LayoutMode {
     }
 }
 
+/// Gap for `SetGaps`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GapScope {
+    Inner,
+    Outer,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl GapScope {
+    /// Convert to config::GapScope
+    pub fn to_config_scope(self) -> crate::config::GapScope {
+        match self {
+            GapScope::Inner => crate::config::GapScope::Inner,
+            GapScope::Outer => crate::config::GapScope::Outer,
+            GapScope::Top => crate::config::GapScope::Top,
+            GapScope::Bottom => crate::config::GapScope::Bottom,
+            GapScope::Left => crate::config::GapScope::Left,
+            GapScope::Right => crate::config::GapScope::Right,
+        }
+    }
+}
+
 /// Mouse button for click events
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -122,8 +170,21 @@ pub enum MouseButton {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum TestCommand {
-    /// Create a new window with specified size
-    CreateWindow { width: i32, height: i32 },
+    /// Create a solid color window with specified size, gray unless `color`
+    /// (a name or `#rrggbb[aa]`) is given. `title` gives tests a stable name
+    /// to find it by with `FindWindow`. With `server_side_decorations` it
+    /// asks stilch to draw its decorations. Answered with `WindowCreated`
+    /// once the client has mapped it.
+    CreateWindow {
+        width: i32,
+        height: i32,
+        #[serde(default)]
+        color: Option<String>,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        server_side_decorations: bool,
+    },
 
     /// Destroy a window
     DestroyWindow { id: u64 },
@@ -134,6 +195,9 @@ pub enum TestCommand {
     /// Focus a specific window
     FocusWindow { id: u64 },
 
+    /// Ask for a window to be activated, as an xdg-activation request does
+    RequestActivation { id: u64 },
+
     /// Move focus in a direction
     MoveFocus { direction: Direction },
 
@@ -158,36 +222,93 @@ pub enum TestCommand {
     /// Move the focused window to another workspace
     MoveFocusedWindowToWorkspace { workspace: usize },
 
-    /// Set window to fullscreen
-    SetFullscreen { id: u64, enabled: bool },
+    /// Set a window's fullscreen state, in virtual output mode unless given
+    SetFullscreen {
+        id: u64,
+        enabled: bool,
+        #[serde(default)]
+        mode: FullscreenMode,
+    },
 
     /// Set window to floating
     SetFloating { id: u64, enabled: bool },
 
+    /// Answer once the compositor has run a full event loop iteration after
+    /// everything sent before: client requests dispatched, the visible
+    /// layouts applied and a frame sent to every window
+    Sync,
+
     /// Request the current ASCII state
     GetState,
 
     /// Request list of windows
     GetWindows,
 
+    /// Get a single window by id
+    GetWindow { id: u64 },
+
+    /// Get the window with exactly this title, the oldest if several match
+    FindWindow { title: String },
+
     /// Get currently focused window
     GetFocusedWindow,
 
-    /// Get list of workspaces and their state
+    /// Get list of workspaces and their state, in the configured order
     GetWorkspaces,
 
+    /// Get the gaps, borders and usable area the layout works with
+    GetLayoutConfig,
+
     /// Get ASCII snapshot with optional annotations
     GetAsciiSnapshot { show_ids: bool, show_focus: bool },
 
     /// Get list of outputs
     GetOutputs,
 
+    /// Get a miniature of a workspace's layout, scaled to fit `width`x`height`
+    GetWorkspaceThumbnail {
+        workspace: usize,
+        width: i32,
+        height: i32,
+    },
+
+    /// Run the internal state validator (debug builds only)
+    GetConsistency,
+
     /// Set layout mode for current container
     SetLayout { mode: LayoutMode },
 
     /// Move workspace to output in direction
     MoveWorkspaceToOutput { direction: Direction },
 
+    /// Move workspace to an output by name, virtual or physical
+    MoveWorkspaceToNamedOutput { name: String },
+
+    /// Set a gap on every workspace, as a `gaps <scope> all set <amount>`
+    /// binding would
+    SetGaps { scope: GapScope, amount: i32 },
+
+    /// Plug in another output, as a monitor hotplugged after startup would be
+    AddOutput {
+        name: String,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+
+    /// Unplug an output, as a monitor disconnected at runtime would be
+    RemoveOutput { name: String },
+
+    /// Get the gamma ramps a client set on an output
+    GetGamma { output: String },
+
+    /// Count the render passes run so far
+    GetRenderCount,
+
+    /// Run a shell command the way an `exec` binding does
+    Exec { command: String },
+
     /// Simulate key press
     KeyPress {
         key: String, // e.g., "Super+1", "Super+Return"
@@ -206,6 +327,19 @@ pub enum TestCommand {
         y: Option<i32>,
     },
 
+    /// Press `button` at one point, move the pointer to another and release
+    /// it there, through the same path as real pointer input. `modifiers`
+    /// ("Super", "Alt", "Ctrl", "Shift") are held down throughout.
+    Drag {
+        button: MouseButton,
+        from_x: i32,
+        from_y: i32,
+        to_x: i32,
+        to_y: i32,
+        #[serde(default)]
+        modifiers: Vec<String>,
+    },
+
     /// Toggle fullscreen (default: virtual output)
     Fullscreen,
 
@@ -249,6 +383,9 @@ pub enum TestResponse {
     /// Window list
     Windows { windows: Vec<WindowInfo> },
 
+    /// A single window
+    Window { window: WindowInfo },
+
     /// Focused window
     FocusedWindow { id: Option<u64> },
 
@@ -258,6 +395,30 @@ pub enum TestResponse {
     /// Output list
     Outputs { outputs: Vec<OutputInfo> },
 
+    /// Red, green and blue ramps of an output, None for the default ramp
+    Gamma { ramps: Option<Vec<u16>> },
+
+    /// Render passes run so far
+    Renders { count: u64 },
+
+    /// Effective layout settings, with the area of every visible workspace
+    LayoutConfig {
+        border_width: i32,
+        floating_border_width: i32,
+        workspaces: Vec<LayoutArea>,
+    },
+
+    /// Workspace miniature; the size keeps the workspace's aspect ratio
+    WorkspaceThumbnail {
+        workspace: usize,
+        width: i32,
+        height: i32,
+        windows: Vec<ThumbnailWindow>,
+    },
+
+    /// Invariant violations found by the state validator; empty when consistent
+    Consistency { errors: Vec<ConsistencyError> },
+
     /// ASCII snapshot
     AsciiSnapshot {
         snapshot: String,
@@ -289,6 +450,19 @@ pub struct WindowInfo {
     pub fullscreen: bool,
     pub title: Option<String>,
     pub visible: bool,
+    /// `#rrggbb[aa]` of windows showing a single-pixel buffer
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Place in the stacking order of mapped windows, bottom first
+    #[serde(default)]
+    pub stacking: Option<usize>,
+    /// Border edges stilch draws, as `[x, y, width, height]` relative to
+    /// the window's top-left corner
+    #[serde(default)]
+    pub borders: Vec<[i32; 4]>,
+    /// Wants attention, until it gets focus
+    #[serde(default)]
+    pub urgent: bool,
 }
 
 /// Workspace information
@@ -302,6 +476,42 @@ pub struct WorkspaceInfo {
     pub focused: bool,
 }
 
+/// The area a visible workspace tiles into and the gap between its windows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutArea {
+    /// Same numbering as `WindowInfo::workspace`
+    pub workspace: usize,
+    pub gap: i32,
+    /// Border width of tiled windows on the workspace's output
+    pub border_width: i32,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// A window in a workspace thumbnail, in thumbnail coordinates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThumbnailWindow {
+    pub id: u64,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub title: String,
+    pub focused: bool,
+    pub floating: bool,
+}
+
+/// A state validation error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyError {
+    /// Name of the violated invariant, e.g. `WindowInMultipleWorkspaces`
+    pub kind: String,
+    /// The error with the ids involved
+    pub detail: String,
+}
+
 /// Output information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputInfo {
@@ -493,7 +703,13 @@ impl TestIpcClient {
 
     /// Create a window
     pub fn create_window(&mut self, width: i32, height: i32) -> std::io::Result<u64> {
-        match self.send_command(TestCommand::CreateWindow { width, height })? {
+        match self.send_command(TestCommand::CreateWindow {
+            width,
+            height,
+            color: None,
+            title: None,
+            server_side_decorations: false,
+        })? {
             TestResponse::WindowCreated { id } => Ok(id),
             TestResponse::Error { message } => {
                 Err(std::io::Error::new(std::io::ErrorKind::Other, message))
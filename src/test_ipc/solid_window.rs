@@ -0,0 +1,310 @@
+//! Solid color test windows
+//!
+//! `CreateWindow` spawns these in-process instead of launching the external
+//! `simple_window` binary. Each window is a tiny Wayland client on its own
+//! thread whose only content is a single-pixel buffer, stretched to the
+//! configured size with a viewport, so there is nothing to draw or upload.
+//! The compositor reads the color back from the buffer for `WindowInfo`.
+
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use smithay::{
+    backend::renderer::utils::with_renderer_surface_state,
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
+    wayland::{seat::WaylandFocus, single_pixel_buffer::get_single_pixel_buffer},
+};
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState},
+    output::{OutputHandler, OutputState},
+    reexports::{
+        client::{
+            delegate_noop,
+            globals::registry_queue_init,
+            protocol::{wl_buffer, wl_output, wl_surface},
+            Connection, QueueHandle,
+        },
+        protocols::wp::{
+            single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1,
+            viewporter::client::{wp_viewport::WpViewport, wp_viewporter::WpViewporter},
+        },
+    },
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    shell::{
+        xdg::{
+            window::{Window, WindowConfigure, WindowDecorations, WindowHandler},
+            XdgShell,
+        },
+        WaylandSurface,
+    },
+};
+use tracing::{debug, warn};
+
+/// Color of windows created without one, the same gray as `simple_window`
+pub const DEFAULT_COLOR: [u8; 4] = [0x80, 0x80, 0x80, 0xff];
+
+/// Parse `red`, `green`, `blue`, `yellow`, `#rrggbb` or `#rrggbbaa` into RGBA
+pub fn parse_color(color: &str) -> Option<[u8; 4]> {
+    match color {
+        "red" => return Some([0xff, 0, 0, 0xff]),
+        "green" => return Some([0, 0xff, 0, 0xff]),
+        "blue" => return Some([0, 0, 0xff, 0xff]),
+        "yellow" => return Some([0xff, 0xff, 0, 0xff]),
+        _ => {}
+    }
+    let hex = color.strip_prefix('#')?;
+    if !hex.is_ascii() || (hex.len() != 6 && hex.len() != 8) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(3)? } else { 0xff };
+    Some([channel(0)?, channel(1)?, channel(2)?, alpha])
+}
+
+/// Format RGBA as `#rrggbb`, or `#rrggbbaa` when not opaque
+pub fn format_color([r, g, b, a]: [u8; 4]) -> String {
+    if a == 0xff {
+        format!("#{r:02x}{g:02x}{b:02x}")
+    } else {
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+}
+
+/// Color of a surface whose current buffer is a single-pixel buffer
+pub fn surface_color(surface: &WlSurface) -> Option<[u8; 4]> {
+    with_renderer_surface_state(surface, |state| {
+        let buffer = state.buffer()?;
+        get_single_pixel_buffer(buffer)
+            .ok()
+            .map(|pixel| pixel.rgba8888())
+    })
+    .flatten()
+}
+
+/// `WindowInfo` color of a window, if it shows a single-pixel buffer
+pub fn window_color(window: &crate::shell::WindowElement) -> Option<String> {
+    window
+        .wl_surface()
+        .and_then(|surface| surface_color(&surface))
+        .map(format_color)
+}
+
+/// Connect a solid color window to the compositor listening on `socket_name`
+///
+/// The window lives on its own thread until the compositor closes it or
+/// goes away. `size` is only a hint, tiled windows take whatever size the
+/// layout configures. With `server_side` the window asks for server-side
+/// decorations over xdg-decoration instead of leaving them to itself.
+pub fn spawn(
+    socket_name: &str,
+    title: String,
+    size: (i32, i32),
+    color: [u8; 4],
+    server_side: bool,
+) {
+    let socket_path = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(socket_name);
+    std::thread::spawn(move || {
+        if let Err(err) = run(socket_path, title, size, color, server_side) {
+            warn!("Solid color test window failed: {err}");
+        }
+    });
+}
+
+fn run(
+    socket_path: PathBuf,
+    title: String,
+    size: (i32, i32),
+    color: [u8; 4],
+    server_side: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let conn = Connection::from_socket(UnixStream::connect(socket_path)?)?;
+    let (globals, mut event_queue) = registry_queue_init(&conn)?;
+    let qh = event_queue.handle();
+
+    let compositor = CompositorState::bind(&globals, &qh)?;
+    let xdg_shell = XdgShell::bind(&globals, &qh)?;
+    let single_pixel: WpSinglePixelBufferManagerV1 = globals.bind(&qh, 1..=1, ())?;
+    let viewporter: WpViewporter = globals.bind(&qh, 1..=1, ())?;
+
+    let surface = compositor.create_surface(&qh);
+    let viewport = viewporter.get_viewport(&surface, &qh, ());
+    let decorations = if server_side {
+        WindowDecorations::RequestServer
+    } else {
+        WindowDecorations::None
+    };
+    let window = xdg_shell.create_window(surface, decorations, &qh);
+    window.set_title(title.clone());
+    window.set_app_id("solid-window".to_string());
+    window.commit();
+
+    let mut solid = SolidWindow {
+        registry_state: RegistryState::new(&globals),
+        output_state: OutputState::new(&globals, &qh),
+        window,
+        viewport,
+        single_pixel,
+        color,
+        buffer: None,
+        width: size.0.max(1),
+        height: size.1.max(1),
+        closed: false,
+    };
+
+    while !solid.closed {
+        event_queue.blocking_dispatch(&mut solid)?;
+    }
+    debug!("Solid color test window {title} closed");
+    Ok(())
+}
+
+struct SolidWindow {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    window: Window,
+    viewport: WpViewport,
+    single_pixel: WpSinglePixelBufferManagerV1,
+    color: [u8; 4],
+    buffer: Option<wl_buffer::WlBuffer>,
+    width: i32,
+    height: i32,
+    closed: bool,
+}
+
+impl SolidWindow {
+    fn draw(&mut self, qh: &QueueHandle<Self>) {
+        // Channels are 32 bit, scale 0xff up to 0xffffffff
+        let [r, g, b, a] = self.color.map(|channel| channel as u32 * 0x0101_0101);
+        let buffer = self.single_pixel.create_u32_rgba_buffer(r, g, b, a, qh, ());
+
+        let surface = self.window.wl_surface();
+        self.viewport.set_destination(self.width, self.height);
+        surface.attach(Some(&buffer), 0, 0);
+        surface.damage_buffer(0, 0, 1, 1);
+        surface.commit();
+        // Keep the attached buffer alive, the compositor reads its color back
+        if let Some(old) = self.buffer.replace(buffer) {
+            old.destroy();
+        }
+    }
+}
+
+impl CompositorHandler for SolidWindow {
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_factor: i32,
+    ) {
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+    }
+
+    fn surface_enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+
+    fn surface_leave(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl OutputHandler for SolidWindow {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn update_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl WindowHandler for SolidWindow {
+    fn request_close(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &Window) {
+        self.closed = true;
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        _window: &Window,
+        configure: WindowConfigure,
+        _serial: u32,
+    ) {
+        if let (Some(w), Some(h)) = configure.new_size {
+            self.width = w.get() as i32;
+            self.height = h.get() as i32;
+        }
+        self.draw(qh);
+    }
+}
+
+impl ProvidesRegistryState for SolidWindow {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    registry_handlers!(OutputState);
+}
+
+smithay_client_toolkit::delegate_compositor!(SolidWindow);
+smithay_client_toolkit::delegate_output!(SolidWindow);
+smithay_client_toolkit::delegate_xdg_shell!(SolidWindow);
+smithay_client_toolkit::delegate_xdg_window!(SolidWindow);
+smithay_client_toolkit::delegate_registry!(SolidWindow);
+delegate_noop!(SolidWindow: WpSinglePixelBufferManagerV1);
+delegate_noop!(SolidWindow: WpViewporter);
+delegate_noop!(SolidWindow: WpViewport);
+delegate_noop!(SolidWindow: ignore wl_buffer::WlBuffer);
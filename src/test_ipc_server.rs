@@ -1,18 +1,20 @@
 //! Test IPC server for debugging and testing
 
-use smithay::reexports::calloop::{
-    generic::Generic, EventLoop, Interest, Mode as CallMode, PostAction,
+use smithay::{
+    reexports::calloop::{generic::Generic, EventLoop, Interest, Mode as CallMode, PostAction},
+    wayland::{compositor::with_states, shell::xdg::XdgToplevelSurfaceData},
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex as StdMutex};
 use tracing::{error, info};
 
 use crate::{
+    shell::WindowElement,
     state::{Backend as BackendTrait, StilchState},
-    test_ipc::{TestCommand, TestResponse, WindowInfo, WorkspaceInfo},
-    window::WindowLayout,
+    test_ipc::{LayoutArea, TestCommand, TestResponse, ThumbnailWindow, WindowInfo, WorkspaceInfo},
+    window::{ManagedWindow, WindowId, WindowLayout},
     workspace::WorkspaceId,
 };
 
@@ -205,64 +207,33 @@ fn process_client_data<BackendData: BackendTrait + 'static>(
                     .window_manager
                     .registry()
                     .windows()
-                    .map(|managed_window| {
-                        let window_id = managed_window.id.get();
-                        let geometry = match &managed_window.layout {
-                            WindowLayout::Tiled { geometry, .. } => geometry,
-                            WindowLayout::Floating { geometry } => geometry,
-                            WindowLayout::Fullscreen { geometry, .. } => geometry,
-                        };
-
-                        let is_focused = state
-                            .focused_window()
-                            .map(|w| {
-                                state.window_manager.registry().find_by_element(&w)
-                                    == Some(managed_window.id)
-                            })
-                            .unwrap_or(false);
-
-                        let is_floating =
-                            matches!(&managed_window.layout, WindowLayout::Floating { .. });
-                        let is_fullscreen =
-                            matches!(&managed_window.layout, WindowLayout::Fullscreen { .. });
-
-                        WindowInfo {
-                            id: window_id,
-                            x: geometry.loc.x,
-                            y: geometry.loc.y,
-                            width: geometry.size.w,
-                            height: geometry.size.h,
-                            workspace: managed_window
-                                .workspace
-                                .display_name()
-                                .parse()
-                                .unwrap_or(managed_window.workspace.get() as usize + 1),
-                            focused: is_focused,
-                            floating: is_floating,
-                            fullscreen: is_fullscreen,
-                            title: None,   // Title not available yet
-                            visible: true, // All returned windows are visible
-                        }
-                    })
+                    .map(|managed_window| window_info(state, managed_window))
                     .collect();
 
                 TestResponse::Windows { windows }
             }
 
+            TestCommand::GetWindow { id } => get_window(state, id),
+
+            TestCommand::FindWindow { title } => find_window(state, &title),
+
             TestCommand::GetWorkspaces => {
-                let workspaces: Vec<_> = (0..10)
-                    .map(|i| {
-                        let (visible, output, window_count) = if let Some(workspace) =
-                            state.workspace_manager.get(WorkspaceId::new(i))
-                        {
-                            (
-                                workspace.output().is_some(),
-                                workspace.output().map(|vo| vo.get() as usize),
-                                workspace.windows.len(),
-                            )
-                        } else {
-                            (false, None, 0)
-                        };
+                let workspaces: Vec<_> = state
+                    .workspace_manager
+                    .order()
+                    .iter()
+                    .map(|&workspace_id| {
+                        let i = workspace_id.get();
+                        let (visible, output, window_count) =
+                            if let Some(workspace) = state.workspace_manager.get(workspace_id) {
+                                (
+                                    workspace.output().is_some(),
+                                    workspace.output().map(|vo| vo.get() as usize),
+                                    workspace.windows.len(),
+                                )
+                            } else {
+                                (false, None, 0)
+                            };
 
                         // Check if this workspace is focused
                         let focused = state
@@ -270,7 +241,6 @@ fn process_client_data<BackendData: BackendTrait + 'static>(
                             .all_virtual_outputs()
                             .any(|vo| vo.active_workspace() == Some(i as usize));
 
-                        let workspace_id = WorkspaceId::new(i);
                         WorkspaceInfo {
                             id: workspace_id
                                 .display_name()
@@ -288,6 +258,16 @@ fn process_client_data<BackendData: BackendTrait + 'static>(
                 TestResponse::Workspaces { workspaces }
             }
 
+            TestCommand::GetWorkspaceThumbnail {
+                workspace,
+                width,
+                height,
+            } => workspace_thumbnail(state, workspace, width, height),
+
+            TestCommand::GetConsistency => consistency(state),
+
+            TestCommand::GetLayoutConfig => layout_config(state),
+
             TestCommand::SwitchWorkspace { index } => {
                 // Switch to the specified workspace
                 if index < 10 {
@@ -299,8 +279,8 @@ fn process_client_data<BackendData: BackendTrait + 'static>(
                         .map(|vo| vo.id());
 
                     if let Some(vo_id) = vo_id {
-                        let workspace_id = WorkspaceId::new(index as u8);
-                        state.switch_workspace(vo_id, workspace_id);
+                        // The same way the workspace keybinding switches
+                        state.switch_to_workspace(vo_id, index);
 
                         TestResponse::Success {
                             message: format!("Switched to workspace {index}"),
@@ -333,3 +313,250 @@ fn process_client_data<BackendData: BackendTrait + 'static>(
 
     Ok(())
 }
+
+/// Everything the test IPC reports about one window
+pub fn window_info<BackendData: BackendTrait>(
+    state: &StilchState<BackendData>,
+    managed_window: &ManagedWindow,
+) -> WindowInfo {
+    let geometry = managed_window.geometry();
+    let focused = state
+        .focused_window()
+        .is_some_and(|w| state.window_registry().find_by_element(&w) == Some(managed_window.id));
+    // Mapped in the space, so not on a hidden workspace or a background tab
+    let stacking = state
+        .space()
+        .elements()
+        .position(|elem| elem == &managed_window.element);
+    let visible = stacking.is_some();
+
+    WindowInfo {
+        id: managed_window.id.get(),
+        x: geometry.loc.x,
+        y: geometry.loc.y,
+        width: geometry.size.w,
+        height: geometry.size.h,
+        workspace: managed_window
+            .workspace
+            .display_name()
+            .parse()
+            .unwrap_or(managed_window.workspace.get() as usize + 1),
+        focused,
+        floating: matches!(managed_window.layout, WindowLayout::Floating { .. }),
+        fullscreen: matches!(managed_window.layout, WindowLayout::Fullscreen { .. }),
+        title: window_title(&managed_window.element),
+        visible,
+        color: crate::test_ipc::solid_window::window_color(&managed_window.element),
+        stacking,
+        borders: managed_window
+            .element
+            .border_rects()
+            .into_iter()
+            .map(|rect| [rect.loc.x, rect.loc.y, rect.size.w, rect.size.h])
+            .collect(),
+        urgent: managed_window.is_urgent(),
+    }
+}
+
+/// The title the client set, without the fallbacks used for display
+fn window_title(window: &WindowElement) -> Option<String> {
+    if let Some(toplevel) = window.0.toplevel() {
+        return with_states(toplevel.wl_surface(), |states| {
+            states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .and_then(|data| data.lock().ok()?.title.clone())
+        });
+    }
+    #[cfg(feature = "xwayland")]
+    if let Some(surface) = window.0.x11_surface() {
+        return Some(surface.title());
+    }
+    None
+}
+
+/// Info for the window with registry id `id`
+pub fn get_window<BackendData: BackendTrait>(
+    state: &StilchState<BackendData>,
+    id: u64,
+) -> TestResponse {
+    let window = u32::try_from(id)
+        .ok()
+        .and_then(|id| state.window_registry().get(WindowId::new(id)));
+    match window {
+        Some(managed_window) => TestResponse::Window {
+            window: window_info(state, managed_window),
+        },
+        None => TestResponse::Error {
+            message: format!("Window {id} not found"),
+        },
+    }
+}
+
+/// Info for the window titled exactly `title`, the oldest one if several are
+pub fn find_window<BackendData: BackendTrait>(
+    state: &StilchState<BackendData>,
+    title: &str,
+) -> TestResponse {
+    let window = state
+        .window_registry()
+        .windows()
+        .filter(|managed_window| window_title(&managed_window.element).as_deref() == Some(title))
+        .min_by_key(|managed_window| managed_window.id.get());
+    match window {
+        Some(managed_window) => TestResponse::Window {
+            window: window_info(state, managed_window),
+        },
+        None => TestResponse::Error {
+            message: format!("No window titled {title:?}"),
+        },
+    }
+}
+
+/// Id of the first window titled `title` that isn't one of `existing`
+pub fn new_window_titled<BackendData: BackendTrait>(
+    state: &StilchState<BackendData>,
+    title: &str,
+    existing: &HashSet<WindowId>,
+) -> Option<u64> {
+    state
+        .window_registry()
+        .windows()
+        .filter(|managed_window| !existing.contains(&managed_window.id))
+        .filter(|managed_window| window_title(&managed_window.element).as_deref() == Some(title))
+        .map(|managed_window| managed_window.id.get())
+        .min()
+}
+
+/// Border widths and the area and gap of every visible workspace
+///
+/// Tiled windows fill their layout cell exactly, borders don't shrink it, so
+/// tests derive expected geometry from the area and gap alone. Both are the
+/// ones in effect: the area inside the outer gaps, and no gaps at all where
+/// smart gaps leave them out.
+pub fn layout_config<BackendData: BackendTrait>(state: &StilchState<BackendData>) -> TestResponse {
+    let workspaces = state
+        .workspace_manager
+        .workspaces()
+        .iter()
+        .filter(|workspace| workspace.is_visible())
+        .map(|workspace| (workspace, workspace.layout.tiling_area()))
+        .map(|(workspace, area)| LayoutArea {
+            workspace: workspace
+                .id
+                .display_name()
+                .parse()
+                .unwrap_or(workspace.id.get() as usize + 1),
+            gap: workspace.layout.effective_gap(),
+            border_width: workspace
+                .output()
+                .map_or(state.config.border.width, |vo_id| {
+                    state.border_width_on(vo_id)
+                }),
+            x: area.loc.x,
+            y: area.loc.y,
+            width: area.size.w,
+            height: area.size.h,
+        })
+        .collect();
+    TestResponse::LayoutConfig {
+        border_width: state.config.border.width,
+        floating_border_width: state.config.border.floating_width,
+        workspaces,
+    }
+}
+
+/// Run the state validator and report every invariant it finds violated
+pub fn consistency<BackendData: BackendTrait + 'static>(
+    state: &StilchState<BackendData>,
+) -> TestResponse {
+    #[cfg(debug_assertions)]
+    {
+        let errors = match state.validate_consistency() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .iter()
+                .map(|error| crate::test_ipc::ConsistencyError {
+                    kind: error.kind().to_string(),
+                    detail: format!("{error:?}"),
+                })
+                .collect(),
+        };
+        TestResponse::Consistency { errors }
+    }
+    #[cfg(not(debug_assertions))]
+    {
+        let _ = state;
+        TestResponse::Error {
+            message: "GetConsistency is only available in debug builds".to_string(),
+        }
+    }
+}
+
+/// Scale a workspace's visible layout down to fit `width`x`height`
+pub fn workspace_thumbnail<BackendData: BackendTrait>(
+    state: &StilchState<BackendData>,
+    workspace: usize,
+    width: i32,
+    height: i32,
+) -> TestResponse {
+    if width <= 0 || height <= 0 {
+        return TestResponse::Error {
+            message: format!("Invalid thumbnail size: {width}x{height}"),
+        };
+    }
+    let Some(ws) = u8::try_from(workspace)
+        .ok()
+        .and_then(|id| state.workspace_manager.get(WorkspaceId::new(id)))
+    else {
+        return TestResponse::Error {
+            message: format!("Workspace {workspace} not found"),
+        };
+    };
+
+    let area = ws.area;
+    let scale = f64::min(
+        width as f64 / area.size.w.max(1) as f64,
+        height as f64 / area.size.h.max(1) as f64,
+    );
+    let scaled = |value: i32| (value as f64 * scale).round() as i32;
+
+    // A fullscreen window hides the rest; otherwise tiles (front tabs only)
+    // with the floating windows stacked on top
+    let geometries: Vec<_> = match ws.fullscreen_window {
+        Some(id) => vec![(id, area)],
+        None => ws
+            .layout
+            .get_visible_geometries()
+            .into_iter()
+            .chain(ws.floating_windows.iter().filter_map(|&id| {
+                let managed = state.window_registry().get(id)?;
+                Some((id, managed.geometry()))
+            }))
+            .collect(),
+    };
+
+    let windows = geometries
+        .into_iter()
+        .filter_map(|(id, geometry)| {
+            let managed = state.window_registry().get(id)?;
+            Some(ThumbnailWindow {
+                id: id.get() as u64,
+                x: scaled(geometry.loc.x - area.loc.x),
+                y: scaled(geometry.loc.y - area.loc.y),
+                width: scaled(geometry.size.w),
+                height: scaled(geometry.size.h),
+                title: managed.element.title(),
+                focused: ws.focused_window == Some(id),
+                floating: managed.is_floating(),
+            })
+        })
+        .collect();
+
+    TestResponse::WorkspaceThumbnail {
+        workspace,
+        width: scaled(area.size.w),
+        height: scaled(area.size.h),
+        windows,
+    }
+}
@@ -22,10 +22,23 @@ use crate::{
     state::{Backend as BackendTrait, StilchState},
 };
 
+/// Entries per channel in the gamma ramps of test outputs
+const TEST_GAMMA_SIZE: u32 = 256;
+
+/// How long `CreateWindow` waits for its client to map the window
+const CREATE_WINDOW_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
 pub struct TestBackendData {
     pub ascii_backend: Arc<Mutex<AsciiBackend>>,
     pub renderer: PixmanRenderer,
     seat_name: String,
+    /// Gamma ramps set on each output, by name; outputs not listed have the
+    /// default ramp
+    gamma: std::collections::HashMap<String, Vec<u16>>,
+    /// Something asked for a render since the last pass
+    render_requested: bool,
+    /// Render passes run so far, one per loop iteration at most
+    renders: u64,
 }
 
 impl BackendTrait for TestBackendData {
@@ -47,6 +60,28 @@ impl BackendTrait for TestBackendData {
     fn update_led_state(&mut self, _led_state: smithay::input::keyboard::LedState) {
         // No LEDs in test mode
     }
+
+    fn request_render(&mut self) {
+        // Coalesced into one pass at the end of the loop iteration
+        self.render_requested = true;
+    }
+
+    fn gamma_size(&self, _output: &Output) -> Option<u32> {
+        Some(TEST_GAMMA_SIZE)
+    }
+
+    fn set_gamma(&mut self, output: &Output, ramps: Option<&[u16]>) -> bool {
+        match ramps {
+            Some(ramps) if ramps.len() == TEST_GAMMA_SIZE as usize * 3 => {
+                self.gamma.insert(output.name(), ramps.to_vec());
+            }
+            Some(_) => return false,
+            None => {
+                self.gamma.remove(&output.name());
+            }
+        }
+        true
+    }
 }
 
 // NO MOCK WINDOWS - WE USE REAL WINDOWS ONLY
@@ -56,6 +91,22 @@ struct ClientConnection {
     stream: UnixStream,
     buffer: String,
     ascii_backend: Arc<Mutex<AsciiBackend>>,
+    /// A reply waiting for the end of an event loop iteration; later
+    /// commands stay buffered until it is sent
+    pending: Option<PendingReply>,
+}
+
+/// A reply held back until the end of an event loop iteration
+enum PendingReply {
+    /// `Sync`, answered as soon as the iteration is done
+    Sync,
+    /// `CreateWindow`, answered once the client maps a window titled
+    /// `title` that isn't one of `existing`
+    WindowCreated {
+        title: String,
+        existing: std::collections::HashSet<crate::window::WindowId>,
+        deadline: std::time::Instant,
+    },
 }
 
 impl ClientConnection {
@@ -64,6 +115,7 @@ impl ClientConnection {
             stream,
             buffer: String::new(),
             ascii_backend,
+            pending: None,
         }
     }
 }
@@ -92,6 +144,84 @@ impl TestIpcHandler {
         })
     }
 
+    /// Send the replies held back until the end of an event loop iteration
+    ///
+    /// Client requests have been dispatched by then. For a `Sync` the
+    /// visible layouts are applied and every output gets a repaint, which
+    /// sends frame callbacks, before replying. A `CreateWindow` is answered
+    /// once its window has mapped. Commands buffered behind a reply run
+    /// after it is sent.
+    fn complete_pending<BackendData: BackendTrait + 'static>(
+        connections: &mut std::collections::HashMap<usize, ClientConnection>,
+        state: &mut StilchState<BackendData>,
+    ) {
+        if !connections
+            .values()
+            .any(|c| matches!(c.pending, Some(PendingReply::Sync)))
+        {
+            if connections.values().any(|c| c.pending.is_some()) {
+                Self::send_pending(connections, state);
+            }
+            return;
+        }
+
+        let workspaces: Vec<_> = state
+            .virtual_output_manager
+            .outputs()
+            .filter_map(|vo| vo.active_workspace_id())
+            .collect();
+        for workspace_id in workspaces {
+            state.apply_workspace_layout(workspace_id);
+        }
+        let now = state.clock.now();
+        let outputs: Vec<_> = state.space().outputs().cloned().collect();
+        for output in outputs {
+            state.post_repaint(&output, now, None, &Default::default());
+        }
+        let _ = state.display_handle.flush_clients();
+
+        Self::send_pending(connections, state);
+    }
+
+    fn send_pending<BackendData: BackendTrait + 'static>(
+        connections: &mut std::collections::HashMap<usize, ClientConnection>,
+        state: &mut StilchState<BackendData>,
+    ) {
+        use std::io::Write;
+
+        for connection in connections.values_mut() {
+            let response = match &connection.pending {
+                None => continue,
+                Some(PendingReply::Sync) => crate::test_ipc::TestResponse::Success {
+                    message: "Synced".to_string(),
+                },
+                Some(PendingReply::WindowCreated {
+                    title,
+                    existing,
+                    deadline,
+                }) => match crate::test_ipc_server::new_window_titled(state, title, existing) {
+                    Some(id) => crate::test_ipc::TestResponse::WindowCreated { id },
+                    None if std::time::Instant::now() >= *deadline => {
+                        crate::test_ipc::TestResponse::Error {
+                            message: format!("Window {title} was never mapped"),
+                        }
+                    }
+                    None => continue,
+                },
+            };
+            connection.pending = None;
+            let response_json = serde_json::to_string(&response).unwrap();
+            if writeln!(connection.stream, "{}", response_json)
+                .and_then(|_| connection.stream.flush())
+                .is_err()
+            {
+                continue;
+            }
+            // A closed connection is dropped by its own event source
+            let _ = Self::process_client_data(connection, state);
+        }
+    }
+
     fn process_client_data<BackendData: BackendTrait + 'static>(
         connection: &mut ClientConnection,
         state: &mut StilchState<BackendData>,
@@ -139,6 +269,10 @@ impl TestIpcHandler {
 
         // Process all complete lines in the buffer
         while let Some(newline_pos) = connection.buffer.find('\n') {
+            if connection.pending.is_some() {
+                break;
+            }
+
             let line = connection.buffer.drain(..=newline_pos).collect::<String>();
             let line = line.trim();
 
@@ -161,6 +295,12 @@ impl TestIpcHandler {
                 }
             };
 
+            // Answered by `complete_pending` once the loop iteration is done
+            if matches!(command, crate::test_ipc::TestCommand::Sync) {
+                connection.pending = Some(PendingReply::Sync);
+                break;
+            }
+
             // Process command and generate response
             let response = match command {
                 crate::test_ipc::TestCommand::GetState => {
@@ -225,10 +365,47 @@ impl TestIpcHandler {
                     }
                 }
 
-                crate::test_ipc::TestCommand::CreateWindow { .. } => {
-                    // We don't create windows via IPC - real applications must create real Wayland windows
-                    crate::test_ipc::TestResponse::Error {
-                        message: "Windows must be created by real Wayland clients. Launch a real application.".to_string(),
+                crate::test_ipc::TestCommand::CreateWindow {
+                    width,
+                    height,
+                    color,
+                    title,
+                    server_side_decorations,
+                } => {
+                    // Still a real Wayland client, just one running on a thread
+                    // of ours that shows a single-pixel buffer
+                    use crate::test_ipc::solid_window;
+                    let rgba = match color.as_deref().map(solid_window::parse_color) {
+                        None => Some(solid_window::DEFAULT_COLOR),
+                        Some(rgba) => rgba,
+                    };
+                    match (rgba, state.socket_name.as_deref()) {
+                        (None, _) => crate::test_ipc::TestResponse::Error {
+                            message: format!("Invalid color {}", color.unwrap_or_default()),
+                        },
+                        (Some(_), None) => crate::test_ipc::TestResponse::Error {
+                            message: "Compositor is not listening on a Wayland socket".to_string(),
+                        },
+                        (Some(rgba), Some(socket_name)) => {
+                            let title = title.unwrap_or_else(|| {
+                                format!("solid-{}", solid_window::format_color(rgba))
+                            });
+                            solid_window::spawn(
+                                socket_name,
+                                title.clone(),
+                                (width, height),
+                                rgba,
+                                server_side_decorations,
+                            );
+                            // The window id only exists once the client maps
+                            // it, `complete_pending` replies with it then
+                            connection.pending = Some(PendingReply::WindowCreated {
+                                title,
+                                existing: state.window_registry().windows().map(|w| w.id).collect(),
+                                deadline: std::time::Instant::now() + CREATE_WINDOW_TIMEOUT,
+                            });
+                            continue;
+                        }
                     }
                 }
 
@@ -253,6 +430,20 @@ impl TestIpcHandler {
                     }
                 }
 
+                crate::test_ipc::TestCommand::RequestActivation { id } => {
+                    let window_id = crate::window::WindowId::new(id as u32);
+                    if state.window_registry().get(window_id).is_some() {
+                        state.request_window_activation(window_id);
+                        crate::test_ipc::TestResponse::Success {
+                            message: format!("Requested activation of window {id}"),
+                        }
+                    } else {
+                        crate::test_ipc::TestResponse::Error {
+                            message: format!("Window {} not found", id),
+                        }
+                    }
+                }
+
                 crate::test_ipc::TestCommand::GetFocusedWindow => {
                     // Get the currently focused window
                     let focused_id = state
@@ -265,9 +456,12 @@ impl TestIpcHandler {
 
                 crate::test_ipc::TestCommand::GetWorkspaces => {
                     // Get all workspaces and their state
-                    let workspaces: Vec<_> = (0..10)
-                        .map(|i| {
-                            let workspace_id = crate::workspace::WorkspaceId::new(i);
+                    let workspaces: Vec<_> = state
+                        .workspace_manager
+                        .order()
+                        .iter()
+                        .map(|&workspace_id| {
+                            let i = workspace_id.get();
                             let workspace = state.workspace_manager.get_workspace(workspace_id);
 
                             let (visible, output, window_count) = if let Some(ws) = workspace {
@@ -285,7 +479,6 @@ impl TestIpcHandler {
                                 .all_virtual_outputs()
                                 .any(|vo| vo.active_workspace() == Some(i as usize));
 
-                            let workspace_id = crate::workspace::WorkspaceId::new(i);
                             crate::test_ipc::WorkspaceInfo {
                                 id: workspace_id
                                     .display_name()
@@ -314,74 +507,33 @@ impl TestIpcHandler {
                         .registry()
                         .windows()
                         .map(|managed_window| {
-                            let window_id = managed_window.id.get();
-                            let geometry = match &managed_window.layout {
-                                crate::window::WindowLayout::Tiled { geometry, .. } => geometry,
-                                crate::window::WindowLayout::Floating { geometry } => geometry,
-                                crate::window::WindowLayout::Fullscreen { geometry, .. } => {
-                                    geometry
-                                }
-                            };
+                            crate::test_ipc_server::window_info(state, managed_window)
+                        })
+                        .collect();
 
-                            let is_focused = state
-                                .focused_window()
-                                .map(|w| {
-                                    state.window_manager.registry().find_by_element(&w)
-                                        == Some(managed_window.id)
-                                })
-                                .unwrap_or(false);
-
-                            let is_floating = matches!(
-                                &managed_window.layout,
-                                crate::window::WindowLayout::Floating { .. }
-                            );
-                            let is_fullscreen = matches!(
-                                &managed_window.layout,
-                                crate::window::WindowLayout::Fullscreen { .. }
-                            );
+                    crate::test_ipc::TestResponse::Windows { windows }
+                }
 
-                            // Get window title from the window element if available
-                            let title = managed_window.element.0.toplevel().and_then(|toplevel| {
-                                use smithay::wayland::compositor::with_states;
-                                use smithay::wayland::shell::xdg::XdgToplevelSurfaceData;
-                                let surface = toplevel.wl_surface();
-                                with_states(&surface, |states| {
-                                    states.data_map.get::<XdgToplevelSurfaceData>().and_then(
-                                        |data| {
-                                            let data = data.lock().unwrap();
-                                            data.app_id.clone()
-                                        },
-                                    )
-                                })
-                            });
+                crate::test_ipc::TestCommand::GetWindow { id } => {
+                    crate::test_ipc_server::get_window(state, id)
+                }
 
-                            // Check if window is visible (mapped in space)
-                            let is_visible = state.space().elements().any(|elem| {
-                                state.window_manager.registry().find_by_element(elem)
-                                    == Some(managed_window.id)
-                            });
+                crate::test_ipc::TestCommand::FindWindow { title } => {
+                    crate::test_ipc_server::find_window(state, &title)
+                }
 
-                            crate::test_ipc::WindowInfo {
-                                id: window_id,
-                                x: geometry.loc.x,
-                                y: geometry.loc.y,
-                                width: geometry.size.w,
-                                height: geometry.size.h,
-                                workspace: managed_window
-                                    .workspace
-                                    .display_name()
-                                    .parse()
-                                    .unwrap_or(managed_window.workspace.get() as usize + 1),
-                                focused: is_focused,
-                                floating: is_floating,
-                                fullscreen: is_fullscreen,
-                                title,
-                                visible: is_visible,
-                            }
-                        })
-                        .collect();
+                crate::test_ipc::TestCommand::GetWorkspaceThumbnail {
+                    workspace,
+                    width,
+                    height,
+                } => crate::test_ipc_server::workspace_thumbnail(state, workspace, width, height),
 
-                    crate::test_ipc::TestResponse::Windows { windows }
+                crate::test_ipc::TestCommand::GetConsistency => {
+                    crate::test_ipc_server::consistency(state)
+                }
+
+                crate::test_ipc::TestCommand::GetLayoutConfig => {
+                    crate::test_ipc_server::layout_config(state)
                 }
 
                 crate::test_ipc::TestCommand::SwitchWorkspace { index } => {
@@ -395,8 +547,8 @@ impl TestIpcHandler {
                             .map(|vo| vo.id());
 
                         if let Some(vo_id) = vo_id {
-                            let workspace_id = crate::workspace::WorkspaceId::new(index as u8);
-                            state.switch_workspace(vo_id, workspace_id);
+                            // The same way the workspace keybinding switches
+                            state.switch_to_workspace(vo_id, index);
 
                             crate::test_ipc::TestResponse::Success {
                                 message: format!("Switched to workspace {index}"),
@@ -652,6 +804,23 @@ impl TestIpcHandler {
                     }
                 }
 
+                crate::test_ipc::TestCommand::SetFullscreen { id, enabled, mode } => {
+                    let window_id = crate::window::WindowId::new(id as u32);
+                    if state.window_manager.registry().get(window_id).is_some() {
+                        state.set_fullscreen(window_id, enabled, mode.to_window_mode());
+                        crate::test_ipc::TestResponse::Success {
+                            message: format!(
+                                "Set fullscreen {} for window {id}",
+                                if enabled { "on" } else { "off" }
+                            ),
+                        }
+                    } else {
+                        crate::test_ipc::TestResponse::Error {
+                            message: format!("Window {} not found", id),
+                        }
+                    }
+                }
+
                 crate::test_ipc::TestCommand::GetAsciiSnapshot {
                     show_ids,
                     show_focus,
@@ -711,10 +880,33 @@ impl TestIpcHandler {
                         }
                     }
 
+                    // Walk the layout trees of the same workspaces for container
+                    // markers and the legend
+                    let focused_id = state
+                        .focused_window()
+                        .and_then(|w| state.window_manager.registry().find_by_element(&w))
+                        .filter(|_| show_focus);
+                    let layouts: Vec<_> = state
+                        .virtual_output_manager
+                        .outputs()
+                        .filter_map(|output| output.active_workspace_id())
+                        .filter_map(|workspace_id| {
+                            let workspace = state.workspace_manager.get_workspace(workspace_id)?;
+                            Some(crate::backend::ascii::AsciiLayout {
+                                workspace: workspace_id.display_name(),
+                                gap: workspace.layout.effective_gap(),
+                                root: crate::backend::ascii::AsciiNode::from_layout(
+                                    &workspace.layout,
+                                    focused_id,
+                                )?,
+                            })
+                        })
+                        .collect();
+
                     let snapshot = if show_ids {
-                        ascii.render() // IDs are shown in the render
+                        ascii.render_with_layouts(&layouts) // IDs are shown in the render
                     } else {
-                        ascii.render() // TODO: Add option to hide IDs
+                        ascii.render_with_layouts(&layouts) // TODO: Add option to hide IDs
                     };
 
                     crate::test_ipc::TestResponse::AsciiSnapshot {
@@ -826,13 +1018,182 @@ impl TestIpcHandler {
                 }
 
                 crate::test_ipc::TestCommand::MoveWorkspaceToOutput { direction } => {
-                    // Parse direction
-                    let dir = direction.to_config_direction();
+                    let target =
+                        crate::config::OutputTarget::Direction(direction.to_config_direction());
+                    match state.move_workspace_to_output(target) {
+                        Ok(()) => crate::test_ipc::TestResponse::Success {
+                            message: format!("Moved workspace to output {direction}"),
+                        },
+                        Err(e) => crate::test_ipc::TestResponse::Error {
+                            message: e.to_string(),
+                        },
+                    }
+                }
+
+                crate::test_ipc::TestCommand::MoveWorkspaceToNamedOutput { name } => {
+                    let target = crate::config::OutputTarget::Name(name.clone());
+                    match state.move_workspace_to_output(target) {
+                        Ok(()) => crate::test_ipc::TestResponse::Success {
+                            message: format!("Moved workspace to output {name}"),
+                        },
+                        Err(e) => crate::test_ipc::TestResponse::Error {
+                            message: e.to_string(),
+                        },
+                    }
+                }
+
+                crate::test_ipc::TestCommand::SetGaps { scope, amount } => {
+                    if amount < 0 {
+                        crate::test_ipc::TestResponse::Error {
+                            message: format!("Gaps can't be negative: {amount}"),
+                        }
+                    } else {
+                        state.set_gaps(
+                            scope.to_config_scope(),
+                            crate::config::GapAmount::Set(amount),
+                        );
+                        crate::test_ipc::TestResponse::Success {
+                            message: format!("Set {scope:?} gaps to {amount}"),
+                        }
+                    }
+                }
+
+                crate::test_ipc::TestCommand::AddOutput {
+                    name,
+                    x,
+                    y,
+                    width,
+                    height,
+                } => {
+                    let output = Output::new(
+                        name.clone(),
+                        PhysicalProperties {
+                            size: (width, height).into(),
+                            subpixel: Subpixel::Unknown,
+                            make: "Test".to_string(),
+                            model: "Hotplug".to_string(),
+                        },
+                    );
+                    let mode = Mode {
+                        size: (width, height).into(),
+                        refresh: 60_000,
+                    };
+                    output.change_current_state(Some(mode), None, None, None);
+                    output.set_preferred(mode);
+                    output.create_global::<StilchState<TestBackendData>>(&state.display_handle);
+                    state.space_mut().map_output(&output, (x, y));
+
+                    let region = Rectangle::new(Point::from((x, y)), (width, height).into());
+                    let vo_id = state
+                        .virtual_output_manager
+                        .create_from_physical(output, region);
+                    state.initialize_virtual_output(vo_id);
+
+                    if let Ok(mut ascii) = connection.ascii_backend.lock() {
+                        ascii.update_total_size(x + width, y + height);
+                    }
 
-                    // Use the compositor's move_workspace_to_output method
-                    state.move_workspace_to_output(dir);
                     crate::test_ipc::TestResponse::Success {
-                        message: format!("Moved workspace to output {direction}"),
+                        message: format!("Added output {name} as virtual output {vo_id}"),
+                    }
+                }
+
+                crate::test_ipc::TestCommand::RemoveOutput { name } => {
+                    let output = state
+                        .space()
+                        .outputs()
+                        .find(|output| output.name() == name)
+                        .cloned();
+                    match output {
+                        Some(output) => {
+                            state.protocols.gamma_control_state.output_removed(&output);
+                            state.remove_physical_output(&output);
+                            crate::test_ipc::TestResponse::Success {
+                                message: format!("Removed output {name}"),
+                            }
+                        }
+                        None => crate::test_ipc::TestResponse::Error {
+                            message: format!("No output named {name}"),
+                        },
+                    }
+                }
+
+                crate::test_ipc::TestCommand::GetGamma { output } => {
+                    crate::test_ipc::TestResponse::Gamma {
+                        ramps: state.backend_data.gamma.get(&output).cloned(),
+                    }
+                }
+
+                crate::test_ipc::TestCommand::GetRenderCount => {
+                    crate::test_ipc::TestResponse::Renders {
+                        count: state.backend_data.renders,
+                    }
+                }
+
+                crate::test_ipc::TestCommand::Drag {
+                    button,
+                    from_x,
+                    from_y,
+                    to_x,
+                    to_y,
+                    modifiers,
+                } => {
+                    use smithay::{
+                        backend::input::{ButtonState, KeyState},
+                        input::keyboard::{FilterResult, Keycode},
+                    };
+
+                    // xkb keycodes of the left-hand modifier keys
+                    let keycodes: Option<Vec<Keycode>> = modifiers
+                        .iter()
+                        .map(|name| match name.as_str() {
+                            "Super" => Some(Keycode::new(133)),
+                            "Alt" => Some(Keycode::new(64)),
+                            "Ctrl" => Some(Keycode::new(37)),
+                            "Shift" => Some(Keycode::new(50)),
+                            _ => None,
+                        })
+                        .collect();
+                    let button_code = match button {
+                        crate::test_ipc::MouseButton::Left => 0x110,
+                        crate::test_ipc::MouseButton::Right => 0x111,
+                        crate::test_ipc::MouseButton::Middle => 0x112,
+                    };
+                    match (keycodes, state.seat().get_keyboard()) {
+                        (None, _) => crate::test_ipc::TestResponse::Error {
+                            message: format!("Unknown modifier in {modifiers:?}"),
+                        },
+                        (_, None) => crate::test_ipc::TestResponse::Error {
+                            message: "Seat has no keyboard".to_string(),
+                        },
+                        (Some(keycodes), Some(keyboard)) => {
+                            let press_keys = |state: &mut StilchState<_>, key_state| {
+                                for &keycode in &keycodes {
+                                    let serial = smithay::utils::SERIAL_COUNTER.next_serial();
+                                    keyboard.input::<(), _>(
+                                        state,
+                                        keycode,
+                                        key_state,
+                                        serial,
+                                        0,
+                                        |_, _, _| FilterResult::Forward,
+                                    );
+                                }
+                            };
+
+                            press_keys(state, KeyState::Pressed);
+                            test_pointer_motion(state, (from_x, from_y));
+                            state.pointer_button(button_code, ButtonState::Pressed, 0);
+                            test_pointer_motion(state, (to_x, to_y));
+                            state.pointer_button(button_code, ButtonState::Released, 1);
+                            press_keys(state, KeyState::Released);
+
+                            crate::test_ipc::TestResponse::Success {
+                                message: format!(
+                                    "Dragged from ({from_x}, {from_y}) to ({to_x}, {to_y})"
+                                ),
+                            }
+                        }
                     }
                 }
 
@@ -875,6 +1236,15 @@ impl TestIpcHandler {
                     }
                 }
 
+                crate::test_ipc::TestCommand::Exec { command } => {
+                    // Spawn it the same way an `exec` keybinding does
+                    use crate::keybindings::KeyAction;
+                    state.handle_key_action(KeyAction::Run(command.clone()));
+                    crate::test_ipc::TestResponse::Success {
+                        message: format!("Started {command}"),
+                    }
+                }
+
                 crate::test_ipc::TestCommand::GetCursorPosition => {
                     // Get current cursor position
                     let location = state.pointer().current_location();
@@ -926,6 +1296,45 @@ pub struct TestModeConfig {
     pub logical_height: i32,
     /// Additional outputs to create (for multi-output testing)
     pub additional_outputs: Vec<Rectangle<i32, Logical>>,
+    /// Start XWayland, for testing X11 clients
+    pub xwayland: bool,
+}
+
+/// Move the pointer to `location` the way real pointer motion does, so
+/// grabs and pointer focus follow
+fn test_pointer_motion<BackendData: BackendTrait + 'static>(
+    state: &mut StilchState<BackendData>,
+    location: (i32, i32),
+) {
+    use smithay::{input::pointer::MotionEvent, utils::SERIAL_COUNTER};
+
+    let location = (location.0 as f64, location.1 as f64).into();
+    let under = state.pointer_target_at(location);
+    let pointer = state.pointer().clone();
+    pointer.motion(
+        state,
+        under,
+        &MotionEvent {
+            location,
+            serial: SERIAL_COUNTER.next_serial(),
+            time: 0,
+        },
+    );
+    pointer.frame(state);
+}
+
+/// Repaint every output once if anything asked for a render during this
+/// loop iteration, the way the udev backend coalesces requests
+fn render_pending(state: &mut StilchState<TestBackendData>) {
+    if !std::mem::take(&mut state.backend_data.render_requested) {
+        return;
+    }
+    state.backend_data.renders += 1;
+    let now = state.clock.now();
+    let outputs: Vec<_> = state.space().outputs().cloned().collect();
+    for output in outputs {
+        state.post_repaint(&output, now, None, &Default::default());
+    }
 }
 
 impl Default for TestModeConfig {
@@ -936,6 +1345,7 @@ impl Default for TestModeConfig {
             logical_width: 3840,
             logical_height: 2160,
             additional_outputs: Vec::new(),
+            xwayland: false,
         }
     }
 }
@@ -999,12 +1409,20 @@ pub fn run_test_mode(config: TestModeConfig) {
         ascii_backend: ascii_backend.clone(),
         renderer,
         seat_name: "test-seat".to_string(),
+        gamma: Default::default(),
+        render_requested: false,
+        renders: 0,
     };
 
     // Initialize compositor state
     // IMPORTANT: Pass true to listen on Wayland socket so real apps can connect!
     let mut state = StilchState::init(display, event_loop.handle(), backend_data, true);
 
+    #[cfg(feature = "xwayland")]
+    if config.xwayland {
+        state.start_xwayland();
+    }
+
     // CRITICAL: Update SHM formats - pixman supports standard formats
     // This tells clients which buffer formats are supported
     use smithay::reexports::wayland_server::protocol::wl_shm::Format;
@@ -1297,6 +1715,7 @@ pub fn run_test_mode(config: TestModeConfig) {
 
     let next_id_for_listener = next_conn_id.clone();
     let connections_for_listener = active_connections.clone();
+    let connections_for_sync = active_connections.clone();
     let handle_for_listener = event_loop.handle();
 
     // Add listener source for accepting new connections
@@ -1373,9 +1792,16 @@ pub fn run_test_mode(config: TestModeConfig) {
             break;
         }
 
+        state.refresh_output_management();
+        render_pending(&mut state);
+
         // Flush any pending client events
         let _ = state.display_handle.flush_clients();
 
+        if let Ok(mut connections) = connections_for_sync.lock() {
+            TestIpcHandler::complete_pending(&mut connections, &mut state);
+        }
+
         // Check if we should exit
         if !state.running.load(std::sync::atomic::Ordering::SeqCst) {
             info!("Shutting down test mode compositor...");
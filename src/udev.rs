@@ -13,16 +13,16 @@ use std::{
 };
 
 use crate::{
+    config::CursorMode,
     drawing::*,
     render::*,
-    shell::WindowElement,
+    shell::{FullscreenSurface, WindowElement},
     state::{take_presentation_feedback, update_primary_scanout_output, Backend, StilchState},
 };
 use crate::{
     shell::WindowRenderElement,
     state::{DndIcon, SurfaceDmabufFeedback},
 };
-#[cfg(feature = "renderer_sync")]
 use smithay::backend::drm::compositor::PrimaryPlaneElement;
 #[cfg(feature = "egl")]
 use smithay::backend::renderer::ImportEgl;
@@ -48,7 +48,7 @@ use smithay::{
         libinput::{LibinputInputBackend, LibinputSessionInterface},
         renderer::{
             damage::Error as OutputDamageTrackerError,
-            element::{memory::MemoryRenderBuffer, AsRenderElements, RenderElementStates},
+            element::{memory::MemoryRenderBuffer, AsRenderElements, Kind, RenderElementStates},
             gles::GlesRenderer,
             multigpu::{gbm::GbmGlesBackend, GpuManager, MultiRenderer},
             DebugFlags, ImportDma, ImportMemWl,
@@ -73,10 +73,10 @@ use smithay::{
     reexports::{
         calloop::{
             timer::{TimeoutAction, Timer},
-            EventLoop, RegistrationToken,
+            EventLoop, LoopHandle, RegistrationToken,
         },
         drm::{
-            control::{connector, crtc, Device, ModeTypeFlags},
+            control::{connector, crtc, Device, Mode as DrmMode, ModeTypeFlags},
             Device as _,
         },
         input::{DeviceCapability, Libinput},
@@ -204,6 +204,7 @@ delegate_dmabuf!(StilchState<UdevData>);
 impl Backend for UdevData {
     const HAS_RELATIVE_MOTION: bool = true;
     const HAS_GESTURES: bool = true;
+    const OWNS_SESSION: bool = true;
 
     fn seat_name(&self) -> String {
         self.session.seat()
@@ -245,6 +246,148 @@ impl Backend for UdevData {
         // Only schedule if not already scheduled
         !self.render_idle_scheduled.load(Ordering::Acquire)
     }
+
+    fn gamma_size(&self, output: &Output) -> Option<u32> {
+        let id = output.user_data().get::<UdevOutputId>()?;
+        let device = self.backends.get(&id.device_id)?;
+        let crtc = device.drm_output_manager.device().get_crtc(id.crtc).ok()?;
+        Some(crtc.gamma_length()).filter(|size| *size > 0)
+    }
+
+    fn set_gamma(&mut self, output: &Output, ramps: Option<&[u16]>) -> bool {
+        let Some(size) = self.gamma_size(output) else {
+            return false;
+        };
+        let Some(id) = output.user_data().get::<UdevOutputId>() else {
+            return false;
+        };
+        let Some(device) = self.backends.get(&id.device_id) else {
+            return false;
+        };
+
+        let size = size as usize;
+        let ramps = match ramps {
+            // Ramps longer than the CRTC's LUT are clamped to it
+            Some(ramps) if ramps.len() >= size * 3 => ramps[..size * 3].to_vec(),
+            Some(_) => return false,
+            None => {
+                // Linear ramp, the default for every channel
+                let channel: Vec<u16> = (0..size)
+                    .map(|i| (i * u16::MAX as usize / (size - 1).max(1)) as u16)
+                    .collect();
+                channel.repeat(3)
+            }
+        };
+        let (red, rest) = ramps.split_at(size);
+        let (green, blue) = rest.split_at(size);
+
+        match device
+            .drm_output_manager
+            .device()
+            .set_gamma(id.crtc, red, green, blue)
+        {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("Failed to set gamma on {}: {err}", output.name());
+                false
+            }
+        }
+    }
+
+    fn set_cursor_mode(&mut self, mode: CursorMode) -> bool {
+        self.pointer_element.set_kind(match mode {
+            CursorMode::Hardware => Kind::Cursor,
+            CursorMode::Software => Kind::Unspecified,
+        });
+        true
+    }
+
+    fn set_output_enabled(&mut self, output: &Output, enabled: bool) -> bool {
+        let Some(id) = output.user_data().get::<UdevOutputId>() else {
+            return false;
+        };
+        let Some(surface) = self
+            .backends
+            .get_mut(&id.device_id)
+            .and_then(|device| device.surfaces.get_mut(&id.crtc))
+        else {
+            return false;
+        };
+        if enabled {
+            // The next frame commits the mode again
+            surface.drm_output.reset_buffers();
+            self.outputs_needing_render
+                .insert((id.device_id, id.crtc), ());
+            return true;
+        }
+
+        // Frames stop once the output is unmapped; turn the CRTC off too
+        match surface
+            .drm_output
+            .with_compositor(|compositor| compositor.clear())
+        {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("Failed to turn off {}: {err:?}", output.name());
+                false
+            }
+        }
+    }
+
+    fn set_output_mode(&mut self, output: &Output, mode: WlMode) -> bool {
+        let Some(id) = output.user_data().get::<UdevOutputId>() else {
+            return false;
+        };
+        let Some(device) = self.backends.get_mut(&id.device_id) else {
+            return false;
+        };
+        let render_node = device.render_node.unwrap_or(self.primary_gpu);
+        let Some(surface) = device.surfaces.get_mut(&id.crtc) else {
+            return false;
+        };
+        let Some(drm_mode) = surface
+            .modes
+            .iter()
+            .copied()
+            .find(|drm_mode| WlMode::from(*drm_mode) == mode)
+        else {
+            warn!("Output {} has no mode {:?}", output.name(), mode);
+            return false;
+        };
+
+        let mut renderer = match self.gpus.single_renderer(&render_node) {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                warn!("Failed to get renderer for mode change: {err:?}");
+                return false;
+            }
+        };
+        match surface.drm_output.use_mode::<_, OutputRenderElements<
+            UdevRenderer<'_>,
+            WindowRenderElement<UdevRenderer<'_>>,
+        >>(
+            drm_mode, &mut renderer, &DrmOutputRenderElements::default()
+        ) {
+            Ok(()) => {
+                output.change_current_state(Some(mode), None, None, None);
+                true
+            }
+            Err(err) => {
+                warn!("Failed to set mode {:?} on {}: {err}", mode, output.name());
+                false
+            }
+        }
+    }
+}
+
+/// The render node for a DRM device path, which may name either its render
+/// node (`/dev/dri/renderD129`) or its card node (`/dev/dri/card1`)
+fn render_node_for_path(path: &str) -> Result<DrmNode, Box<dyn std::error::Error>> {
+    let node = DrmNode::from_path(path)?;
+    Ok(node
+        .node_with_type(NodeType::Render)
+        .and_then(|render| render.ok())
+        .unwrap_or(node))
 }
 
 pub fn run_udev(enable_test_ipc: bool) -> Result<(), Box<dyn std::error::Error>> {
@@ -266,8 +409,24 @@ pub fn run_udev(enable_test_ipc: bool) -> Result<(), Box<dyn std::error::Error>>
     /*
      * Initialize the compositor
      */
-    let primary_gpu = if let Ok(var) = std::env::var("ANVIL_DRM_DEVICE") {
-        DrmNode::from_path(var).map_err(|e| format!("Invalid DRM device path: {e}"))?
+    let config = crate::state::load_config();
+
+    // STILCH_RENDER_NODE wins over `render_node` in the config, for trying
+    // another GPU without editing it
+    let requested_node = std::env::var("STILCH_RENDER_NODE")
+        .or_else(|_| std::env::var("ANVIL_DRM_DEVICE"))
+        .ok()
+        .or_else(|| config.render_node.clone());
+    let requested_gpu = requested_node.and_then(|path| match render_node_for_path(&path) {
+        Ok(node) => Some(node),
+        Err(err) => {
+            error!("Ignoring render node {path}: {err}");
+            None
+        }
+    });
+
+    let primary_gpu = if let Some(node) = requested_gpu {
+        node
     } else {
         primary_gpu(session.seat())
             .ok()
@@ -287,7 +446,15 @@ pub fn run_udev(enable_test_ipc: bool) -> Result<(), Box<dyn std::error::Error>>
             })
             .ok_or_else(|| "No GPU device found")?
     };
-    info!("Using {} as primary gpu.", primary_gpu);
+    info!(
+        "Using {} as primary gpu for compositing and client buffers{}",
+        primary_gpu,
+        if requested_gpu.is_some() {
+            " (requested)"
+        } else {
+            ""
+        }
+    );
 
     let gpus = GpuManager::new(GbmGlesBackend::with_context_priority(ContextPriority::High))
         .map_err(|e| format!("Failed to initialize GPU manager: {e}"))?;
@@ -308,7 +475,7 @@ pub fn run_udev(enable_test_ipc: bool) -> Result<(), Box<dyn std::error::Error>>
         outputs_needing_render: HashMap::new(),
         render_idle_scheduled: Arc::new(AtomicBool::new(false)),
     };
-    let mut state = StilchState::init(display, event_loop.handle(), data, true);
+    let mut state = StilchState::init_with_config(display, event_loop.handle(), data, true, config);
 
     /*
      * Initialize the udev backend
@@ -455,6 +622,11 @@ pub fn run_udev(enable_test_ipc: bool) -> Result<(), Box<dyn std::error::Error>>
                 );
             }
         }
+    } else {
+        warn!(
+            "Primary gpu {} is not a DRM device on seat {}",
+            primary_gpu, state.seat_name
+        );
     }
 
     let primary_device_id = primary_device.map(|(device_id, _)| device_id);
@@ -661,6 +833,9 @@ pub fn run_udev(enable_test_ipc: bool) -> Result<(), Box<dyn std::error::Error>>
         } else {
             state.space_mut().refresh();
             state.popups_mut().cleanup();
+            state.refresh_output_management();
+            #[cfg(feature = "xwayland")]
+            state.refresh_xwayland_scale();
             display_handle.flush_clients().unwrap();
 
             // Process any pending renders
@@ -793,7 +968,11 @@ struct SurfaceData {
         Option<OutputPresentationFeedback>,
         DrmDeviceFd,
     >,
+    /// Modes supported by the connector, used for runtime mode switches
+    modes: Vec<DrmMode>,
     disable_direct_scanout: bool,
+    /// Whether the last frame put a client buffer straight on the primary plane
+    direct_scanout: bool,
     #[cfg(feature = "debug")]
     fps: fps_ticker::Fps,
     #[cfg(feature = "debug")]
@@ -801,6 +980,10 @@ struct SurfaceData {
     dmabuf_feedback: Option<SurfaceDmabufFeedback>,
     last_presentation_time: Option<Time<Monotonic>>,
     vblank_throttle_timer: Option<RegistrationToken>,
+    /// When the last frame was drawn, to hold to `max_render_fps`
+    last_render: Option<Instant>,
+    /// Pending delayed render, which later requests join
+    render_timer: Option<RegistrationToken>,
 }
 
 impl Drop for SurfaceData {
@@ -935,12 +1118,9 @@ impl StilchState<UdevData> {
     pub fn schedule_render(&mut self) {
         self.backend_data.request_render();
 
-        // Schedule idle callback if not already scheduled
-        if !self
-            .backend_data
-            .render_idle_scheduled
-            .load(Ordering::Acquire)
-        {
+        // Schedule idle callback if not already scheduled, so any number of
+        // requests before it runs lead to one render per output
+        if self.backend_data.should_schedule_render() {
             self.backend_data
                 .render_idle_scheduled
                 .store(true, Ordering::Release);
@@ -964,11 +1144,56 @@ impl StilchState<UdevData> {
 
         tracing::trace!("Processing {} pending renders", outputs_to_render.len());
 
+        let min_interval = self.config.min_render_interval();
         for ((node, crtc), _) in outputs_to_render {
+            // Outputs drawn too recently for max_render_fps wait for their turn
+            let last_render = self
+                .backend_data
+                .backends
+                .get(&node)
+                .and_then(|device| device.surfaces.get(&crtc))
+                .and_then(|surface| surface.last_render);
+            if let Some(delay) = render_delay(min_interval, last_render, Instant::now()) {
+                self.schedule_render_after(node, crtc, delay);
+                continue;
+            }
             self.render_surface(node, crtc, self.clock.now());
         }
     }
 
+    /// Render an output after `delay`. Requests made while one is pending
+    /// join it instead of adding another.
+    fn schedule_render_after(&mut self, node: DrmNode, crtc: crtc::Handle, delay: Duration) {
+        let Some(surface) = self
+            .backend_data
+            .backends
+            .get_mut(&node)
+            .and_then(|device| device.surfaces.get_mut(&crtc))
+        else {
+            return;
+        };
+        insert_debounced(
+            &self.handle,
+            &mut surface.render_timer,
+            delay,
+            move |state: &mut StilchState<UdevData>| {
+                if let Some(surface) = state
+                    .backend_data
+                    .backends
+                    .get_mut(&node)
+                    .and_then(|device| device.surfaces.get_mut(&crtc))
+                {
+                    surface.render_timer = None;
+                }
+                state
+                    .backend_data
+                    .outputs_needing_render
+                    .insert((node, crtc), ());
+                state.schedule_render();
+            },
+        );
+    }
+
     fn device_added(&mut self, node: DrmNode, path: &Path) -> Result<(), DeviceAddError> {
         // Try to open the device
         let fd = self
@@ -1033,6 +1258,15 @@ impl StilchState<UdevData> {
                 warn!(?err, "failed to initialize gpu");
             })
             .ok();
+        match render_node {
+            Some(render_node) => {
+                info!("DRM device {node}: scanout here, rendering on {render_node}")
+            }
+            None => info!(
+                "DRM device {node}: scanout here, rendering on primary gpu {} and copied over",
+                self.backend_data.primary_gpu
+            ),
+        }
 
         let allocator = render_node
             .is_some()
@@ -1292,6 +1526,9 @@ impl StilchState<UdevData> {
             // Use the pre-calculated position
             let position = position_coords.into();
 
+            for mode in connector.modes() {
+                output.add_mode(WlMode::from(*mode));
+            }
             output.set_preferred(wl_mode);
 
             // Determine scale from config or auto-detect
@@ -1434,7 +1671,9 @@ impl StilchState<UdevData> {
                 output: output.clone(),
                 global: Some(global),
                 drm_output,
+                modes: connector.modes().to_vec(),
                 disable_direct_scanout,
+                direct_scanout: false,
                 #[cfg(feature = "debug")]
                 fps: fps_ticker::Fps::default(),
                 #[cfg(feature = "debug")]
@@ -1442,6 +1681,8 @@ impl StilchState<UdevData> {
                 dmabuf_feedback,
                 last_presentation_time: None,
                 vblank_throttle_timer: None,
+                last_render: None,
+                render_timer: None,
             };
 
             device.surfaces.insert(crtc, surface);
@@ -1724,26 +1965,11 @@ impl StilchState<UdevData> {
 
         // Now we can use self mutably
         if let Some(output) = maybe_output {
-            // Remove from physical layout manager if present
-            if let Some(ref mut physical_layout) = self.physical_layout {
-                let output_name = output.name();
-                info!("Removing display '{}' from physical layout", output_name);
-                physical_layout.remove_display(&output_name);
-            }
-
-            // Remove any virtual outputs associated with this physical output
-            let removed_virtual = self.virtual_output_manager.remove_physical_output(&output);
-            for vo_id in removed_virtual {
-                info!(
-                    "Removed virtual output {:?} due to physical output disconnection",
-                    vo_id
-                );
-                // Clean up workspace associations if needed
-                // The workspace manager should handle this automatically when the virtual output is gone
-            }
-
-            self.space_mut().unmap_output(&output);
-            self.space_mut().refresh();
+            self.protocols.gamma_control_state.output_removed(&output);
+            self.protocols
+                .output_management_state
+                .output_removed(&output);
+            self.remove_physical_output(&output);
         }
 
         let device = match self.backend_data.backends.get_mut(&node) {
@@ -2054,7 +2280,12 @@ impl StilchState<UdevData> {
 
         // Extract values before device borrow
         let pointer_location = self.pointer().current_location();
-        let show_window_preview = self.show_window_preview;
+        let cursor_hidden = self.cursor_hidden();
+        let overview = self.overview_frame(&output);
+        let debug_overlay = self.debug_overlay_frame(&output);
+        let status_bars = self.status_bar_frames(&output);
+        let selection = self.selection_frame(&output);
+        let closing = self.closing_window_frames(&output);
         let dnd_icon = self.dnd_icon().cloned();
 
         // Collect tab bar data before mutable borrows
@@ -2149,18 +2380,31 @@ impl StilchState<UdevData> {
             pointer_location,
             &pointer_image,
             pointer_element,
+            cursor_hidden,
             &dnd_icon,
             cursor_status,
             cursor_hotspot,
-            show_window_preview,
+            overview.as_ref(),
+            debug_overlay.as_ref(),
+            &status_bars,
+            selection.as_ref(),
+            &closing,
             &tab_bar_data,
             text_cache,
         );
         let reschedule = match result {
             Ok((has_rendered, states)) => {
+                if has_rendered {
+                    surface.last_render = Some(start);
+                }
                 let dmabuf_feedback = surface.dmabuf_feedback.clone();
                 self.post_repaint(&output, frame_target, dmabuf_feedback, &states);
-                !has_rendered
+                if has_rendered {
+                    self.record_frame_time(start.elapsed());
+                }
+                // Nothing to retry without damage, the next commit or input
+                // schedules a render again
+                false
             }
             Err(err) => {
                 warn!("Error during rendering: {:#?}", err);
@@ -2223,10 +2467,15 @@ fn render_surface<'a>(
     pointer_location: Point<f64, Logical>,
     pointer_image: &MemoryRenderBuffer,
     pointer_element: &mut PointerElement,
+    cursor_hidden: bool,
     dnd_icon: &Option<DndIcon>,
     cursor_status: &mut CursorImageStatus,
     named_cursor_hotspot: (i32, i32),
-    show_window_preview: bool,
+    overview: Option<&crate::overview::OverviewFrame>,
+    debug_overlay: Option<&crate::debug_overlay::DebugOverlayFrame>,
+    status_bars: &[crate::status_bar::StatusBarFrame],
+    selection: Option<&crate::selection_outline::SelectionFrame>,
+    closing: &[crate::animation::ClosingFrame],
     tab_bar_data: &[crate::render::TabBarData],
     text_cache: &mut crate::tab_bar::TabTextCache,
 ) -> Result<(bool, RenderElementStates), SwapBuffersError> {
@@ -2281,16 +2530,19 @@ fn render_surface<'a>(
             pointer_element.set_status(cursor_status.clone());
         }
 
-        custom_elements.extend(
-            pointer_element.render_elements(
-                renderer,
-                (cursor_pos - cursor_hotspot.to_f64())
-                    .to_physical(scale)
-                    .to_i32_round(),
-                scale,
-                1.0,
-            ),
-        );
+        // Hidden by typing or idling, the client's image stays set
+        if !cursor_hidden {
+            custom_elements.extend(
+                pointer_element.render_elements(
+                    renderer,
+                    (cursor_pos - cursor_hotspot.to_f64())
+                        .to_physical(scale)
+                        .to_i32_round(),
+                    scale,
+                    1.0,
+                ),
+            );
+        }
 
         // draw the dnd icon if applicable
         {
@@ -2318,30 +2570,72 @@ fn render_surface<'a>(
         custom_elements.push(CustomRenderElements::Fps(element.clone()));
     }
 
+    if let Some(frame) = debug_overlay {
+        custom_elements.extend(crate::render::debug_overlay_element(renderer, frame, scale));
+    }
+
+    // A window fullscreened on the whole output, with nothing drawn over it
+    // but the cursor, may have its buffer scanned out directly instead of
+    // composited. The DRM compositor falls back to compositing by itself
+    // when the buffer's format or modifier doesn't suit the plane.
+    let scanout_candidate = overview.is_none()
+        && output
+            .user_data()
+            .get::<FullscreenSurface>()
+            .and_then(|fullscreen| fullscreen.get())
+            .is_some();
+
+    // Bars would cover the window and force compositing
+    if !scanout_candidate {
+        custom_elements.extend(crate::render::status_bar_elements(
+            renderer,
+            status_bars,
+            scale,
+        ));
+    }
+
+    if let Some(frame) = selection {
+        custom_elements.extend(crate::render::selection_elements(frame, scale));
+    }
+
+    custom_elements.extend(crate::render::closing_window_elements(
+        renderer, closing, scale,
+    ));
+
     let (elements, clear_color) = output_elements(
         output,
         space,
         custom_elements,
         renderer,
-        show_window_preview,
+        overview,
         tab_bar_data,
         text_cache,
     );
 
     let frame_mode = if surface.disable_direct_scanout {
         FrameFlags::empty()
+    } else if scanout_candidate {
+        FrameFlags::DEFAULT | FrameFlags::ALLOW_PRIMARY_PLANE_SCANOUT
     } else {
         FrameFlags::DEFAULT
     };
-    let (rendered, states) = surface
+    let (rendered, states, direct_scanout) = surface
         .drm_output
         .render_frame(renderer, &elements, clear_color, frame_mode)
         .map(|render_frame_result| {
+            let direct_scanout = matches!(
+                render_frame_result.primary_element,
+                PrimaryPlaneElement::Element(_)
+            );
             #[cfg(feature = "renderer_sync")]
             if let PrimaryPlaneElement::Swapchain(element) = render_frame_result.primary_element {
                 element.sync.wait();
             }
-            (!render_frame_result.is_empty, render_frame_result.states)
+            (
+                !render_frame_result.is_empty,
+                render_frame_result.states,
+                direct_scanout,
+            )
         })
         .map_err(|err| match err {
             smithay::backend::drm::compositor::RenderFrameError::PrepareFrame(err) => {
@@ -2353,6 +2647,18 @@ fn render_surface<'a>(
             _ => unreachable!(),
         })?;
 
+    if direct_scanout != surface.direct_scanout {
+        surface.direct_scanout = direct_scanout;
+        if direct_scanout {
+            info!(
+                "{}: scanning out the fullscreen window directly",
+                output.name()
+            );
+        } else {
+            info!("{}: compositing again", output.name());
+        }
+    }
+
     update_primary_scanout_output(space, output, dnd_icon, cursor_status, &states);
 
     if rendered {
@@ -2368,3 +2674,101 @@ fn render_surface<'a>(
 
     Ok((rendered, states))
 }
+
+/// How much longer an output last drawn at `last_render` has to wait before
+/// drawing again, when `max_render_fps` sets `min_interval`
+fn render_delay(
+    min_interval: Option<Duration>,
+    last_render: Option<Instant>,
+    now: Instant,
+) -> Option<Duration> {
+    let (interval, last_render) = min_interval.zip(last_render)?;
+    interval
+        .checked_sub(now.saturating_duration_since(last_render))
+        .filter(|delay| !delay.is_zero())
+}
+
+/// Run `callback` after `delay`, unless a timer is already pending in `slot`,
+/// which the request then joins. `callback` has to clear `slot`.
+fn insert_debounced<D: 'static>(
+    handle: &LoopHandle<'static, D>,
+    slot: &mut Option<RegistrationToken>,
+    delay: Duration,
+    mut callback: impl FnMut(&mut D) + 'static,
+) {
+    if slot.is_some() {
+        return;
+    }
+    *slot = handle
+        .insert_source(Timer::from_duration(delay), move |_, _, data| {
+            callback(data);
+            TimeoutAction::Drop
+        })
+        .map_err(|e| error!("Failed to register render timer: {e}"))
+        .ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_delay_holds_to_max_render_fps() {
+        let interval = Some(Duration::from_millis(20));
+        let now = Instant::now();
+        let drawn = |ago| Some(now - Duration::from_millis(ago));
+
+        // Without a limit, or before the first frame, there is no wait
+        assert_eq!(render_delay(None, drawn(1), now), None);
+        assert_eq!(render_delay(interval, None, now), None);
+
+        assert_eq!(
+            render_delay(interval, drawn(5), now),
+            Some(Duration::from_millis(15))
+        );
+        assert_eq!(render_delay(interval, drawn(20), now), None);
+        assert_eq!(render_delay(interval, drawn(50), now), None);
+    }
+
+    #[derive(Default)]
+    struct Pending {
+        timer: Option<RegistrationToken>,
+        renders: u32,
+    }
+
+    fn request_render(handle: &LoopHandle<'static, Pending>, pending: &mut Pending) {
+        insert_debounced(
+            handle,
+            &mut pending.timer,
+            Duration::from_millis(5),
+            |pending: &mut Pending| {
+                pending.timer = None;
+                pending.renders += 1;
+            },
+        );
+    }
+
+    #[test]
+    fn test_render_requests_join_the_pending_timer() {
+        let mut event_loop = EventLoop::<Pending>::try_new().unwrap();
+        let handle = event_loop.handle();
+        let mut pending = Pending::default();
+
+        for _ in 0..3 {
+            request_render(&handle, &mut pending);
+        }
+        for _ in 0..100 {
+            if pending.timer.is_none() {
+                break;
+            }
+            event_loop
+                .dispatch(Some(Duration::from_millis(10)), &mut pending)
+                .unwrap();
+        }
+        assert_eq!(pending.renders, 1);
+
+        // Once it fired, the next request waits on a timer of its own
+        request_render(&handle, &mut pending);
+        assert!(pending.timer.is_some());
+    }
+}
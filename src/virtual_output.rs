@@ -31,7 +31,7 @@ use smithay::{
     output::Output,
     utils::{Logical, Point, Rectangle, Size},
 };
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::num::NonZeroU32;
 use tracing::info;
 
@@ -101,6 +101,8 @@ pub struct VirtualOutput {
     logical_region: Rectangle<i32, Logical>,
     /// Current state of this virtual output
     state: VirtualOutputState,
+    /// Workspace shown before the current one
+    previous_workspace: Option<WorkspaceId>,
 }
 
 #[derive(Debug)]
@@ -109,6 +111,8 @@ pub struct VirtualOutputManager {
     next_id: u32,
     /// Mapping from physical output to virtual outputs it contains
     physical_to_virtual: HashMap<Output, Vec<VirtualOutputId>>,
+    /// Name of the output configured as primary
+    primary_output: Option<String>,
 }
 
 impl VirtualOutputManager {
@@ -117,9 +121,15 @@ impl VirtualOutputManager {
             virtual_outputs: HashMap::new(),
             next_id: 1, // Start at 1 for NonZeroU32
             physical_to_virtual: HashMap::new(),
+            primary_output: None,
         }
     }
 
+    /// Set the name of the output to treat as primary while it is connected
+    pub fn set_primary_output(&mut self, name: Option<String>) {
+        self.primary_output = name;
+    }
+
     /// Create a virtual output that represents the entire physical output
     pub fn create_from_physical(
         &mut self,
@@ -152,6 +162,7 @@ impl VirtualOutputManager {
             physical_outputs: physical_outputs.clone(),
             logical_region,
             state: VirtualOutputState::Empty,
+            previous_workspace: None,
         };
 
         self.virtual_outputs.insert(id, virtual_output);
@@ -250,6 +261,7 @@ impl VirtualOutputManager {
                 physical_outputs: vec![physical.clone()],
                 logical_region: region,
                 state: VirtualOutputState::Empty,
+                previous_workspace: None,
             };
 
             self.virtual_outputs.insert(id, virtual_output);
@@ -308,6 +320,7 @@ impl VirtualOutputManager {
             physical_outputs: physical_outputs.clone(),
             logical_region,
             state: VirtualOutputState::Empty,
+            previous_workspace: None,
         };
 
         self.virtual_outputs.insert(id, virtual_output);
@@ -330,6 +343,32 @@ impl VirtualOutputManager {
         self.virtual_outputs.get_mut(&id)
     }
 
+    /// Where workspaces and layer surfaces without an output of their own
+    /// go: the configured primary output, or the oldest virtual output still
+    /// around while that one is missing
+    pub fn primary_virtual_output(&self) -> Option<VirtualOutputId> {
+        self.primary_output
+            .as_deref()
+            .and_then(|name| self.find_by_output_name(name))
+            .or_else(|| self.virtual_outputs.keys().min().copied())
+    }
+
+    /// Find a virtual output by its own name or the name of a physical
+    /// output backing it, like `DP-1`
+    pub fn find_by_output_name(&self, name: &str) -> Option<VirtualOutputId> {
+        self.virtual_outputs
+            .values()
+            .filter(|vo| {
+                vo.name == name
+                    || vo
+                        .physical_outputs
+                        .iter()
+                        .any(|output| output.name() == name)
+            })
+            .map(|vo| vo.id)
+            .min()
+    }
+
     /// List all virtual output IDs
     pub fn list_virtual_outputs(&self) -> Vec<VirtualOutputId> {
         self.virtual_outputs.keys().copied().collect()
@@ -374,6 +413,52 @@ impl VirtualOutputManager {
         removed
     }
 
+    /// Follow physical outputs that were moved, resized, rescaled or rotated
+    ///
+    /// Each virtual output on them is mapped proportionally from the bounding
+    /// box of its physical outputs' old geometries onto that of their new
+    /// ones, so splits keep their relative layout and merged outputs stretch
+    /// with their members. `old_geometries` holds the outputs that changed;
+    /// the others are taken from `new_geometries`.
+    pub fn relocate_physical_outputs(
+        &mut self,
+        old_geometries: &HashMap<Output, Rectangle<i32, Logical>>,
+        new_geometries: &HashMap<Output, Rectangle<i32, Logical>>,
+    ) {
+        let affected: BTreeSet<VirtualOutputId> = old_geometries
+            .keys()
+            .flat_map(|physical| self.virtual_outputs_for_physical(physical))
+            .collect();
+
+        for id in affected {
+            let Some(virtual_output) = self.virtual_outputs.get_mut(&id) else {
+                continue;
+            };
+            let old_bounds = bounding_box(&virtual_output.physical_outputs, |physical| {
+                old_geometries
+                    .get(physical)
+                    .or_else(|| new_geometries.get(physical))
+                    .copied()
+            });
+            let new_bounds = bounding_box(&virtual_output.physical_outputs, |physical| {
+                new_geometries.get(physical).copied()
+            });
+            let (Some(old_bounds), Some(new_bounds)) = (old_bounds, new_bounds) else {
+                continue;
+            };
+            if old_bounds == new_bounds || old_bounds.is_empty() {
+                continue;
+            }
+
+            let region = virtual_output.logical_region;
+            virtual_output.logical_region = map_region(region, old_bounds, new_bounds);
+            info!(
+                "Virtual output '{}' moved from {:?} to {:?}",
+                virtual_output.name, region, virtual_output.logical_region
+            );
+        }
+    }
+
     /// Get virtual outputs that contain a logical point
     pub fn virtual_outputs_at_point(&self, point: Point<i32, Logical>) -> Vec<VirtualOutputId> {
         self.virtual_outputs
@@ -411,6 +496,11 @@ impl VirtualOutputManager {
     ) {
         if let Some(virtual_output) = self.virtual_outputs.get_mut(&virtual_output_id) {
             let workspace_id = WorkspaceId::new(workspace_idx as u8);
+            if let Some(current) = virtual_output.active_workspace_id() {
+                if current != workspace_id {
+                    virtual_output.previous_workspace = Some(current);
+                }
+            }
             virtual_output.state = VirtualOutputState::ShowingWorkspace(workspace_id);
         }
     }
@@ -461,6 +551,11 @@ impl VirtualOutput {
         }
     }
 
+    /// Workspace this output showed before the current one, for back-and-forth
+    pub fn previous_workspace_id(&self) -> Option<WorkspaceId> {
+        self.previous_workspace
+    }
+
     pub fn is_empty(&self) -> bool {
         matches!(self.state, VirtualOutputState::Empty)
     }
@@ -469,3 +564,83 @@ impl VirtualOutput {
         // This is now handled in VirtualOutputManager when switching workspaces
     }
 }
+
+/// Smallest rectangle around the geometries of `outputs`, or None if none
+/// of them has one
+fn bounding_box(
+    outputs: &[Output],
+    geometry: impl Fn(&Output) -> Option<Rectangle<i32, Logical>>,
+) -> Option<Rectangle<i32, Logical>> {
+    outputs
+        .iter()
+        .filter_map(geometry)
+        .reduce(|bounds, rect| bounds.merge(rect))
+}
+
+/// Map `region` proportionally from `old_bounds` onto `new_bounds`
+fn map_region(
+    region: Rectangle<i32, Logical>,
+    old_bounds: Rectangle<i32, Logical>,
+    new_bounds: Rectangle<i32, Logical>,
+) -> Rectangle<i32, Logical> {
+    let scale_x = new_bounds.size.w as f64 / old_bounds.size.w as f64;
+    let scale_y = new_bounds.size.h as f64 / old_bounds.size.h as f64;
+    let map_x =
+        |x: i32| new_bounds.loc.x + ((x - old_bounds.loc.x) as f64 * scale_x).round() as i32;
+    let map_y =
+        |y: i32| new_bounds.loc.y + ((y - old_bounds.loc.y) as f64 * scale_y).round() as i32;
+
+    let x1 = map_x(region.loc.x);
+    let y1 = map_y(region.loc.y);
+    let x2 = map_x(region.loc.x + region.size.w);
+    let y2 = map_y(region.loc.y + region.size.h);
+    Rectangle::new(Point::from((x1, y1)), Size::from((x2 - x1, y2 - y1)))
+}
+
+/// The part of a virtual output's `region` left for windows once layer-shell
+/// panels take their exclusive zones. `outputs` holds the geometry and the
+/// non-exclusive zone of every physical output the virtual output is shown
+/// on, both in global coordinates.
+///
+/// The result has to stay a rectangle, so a panel only shrinks the region
+/// from an edge that the part of the region on the panel's output shares
+/// with the whole region. On merged outputs a panel on one monitor therefore
+/// insets that edge across all of them, and a panel along the seam between
+/// two monitors reserves nothing.
+pub fn usable_area(
+    region: Rectangle<i32, Logical>,
+    outputs: &[(Rectangle<i32, Logical>, Rectangle<i32, Logical>)],
+) -> Rectangle<i32, Logical> {
+    let mut left = region.loc.x;
+    let mut top = region.loc.y;
+    let mut right = region.loc.x + region.size.w;
+    let mut bottom = region.loc.y + region.size.h;
+
+    for &(geometry, non_exclusive) in outputs {
+        let Some(part) = region.intersection(geometry) else {
+            continue;
+        };
+        let Some(usable) = part.intersection(non_exclusive) else {
+            continue;
+        };
+
+        if part.loc.x == region.loc.x {
+            left = left.max(usable.loc.x);
+        }
+        if part.loc.y == region.loc.y {
+            top = top.max(usable.loc.y);
+        }
+        if part.loc.x + part.size.w == region.loc.x + region.size.w {
+            right = right.min(usable.loc.x + usable.size.w);
+        }
+        if part.loc.y + part.size.h == region.loc.y + region.size.h {
+            bottom = bottom.min(usable.loc.y + usable.size.h);
+        }
+    }
+
+    if right <= left || bottom <= top {
+        // Panels would leave nothing, ignore them rather than tile into nothing
+        return region;
+    }
+    Rectangle::new((left, top).into(), (right - left, bottom - top).into())
+}
@@ -0,0 +1,72 @@
+//! What config criteria see of a window
+//!
+//! `for_window`, `assign`, `no_focus` and saved layout placeholders all
+//! match windows through [`WindowCriteria`], so a Wayland app_id or an X11
+//! class is read the same way wherever a rule looks at it.
+
+use crate::shell::WindowElement;
+
+/// Properties of a window that criteria match against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowCriteria {
+    /// Wayland app_id
+    pub app_id: Option<String>,
+    /// Window title
+    pub title: String,
+    /// X11 WM_CLASS class
+    pub class: Option<String>,
+    /// X11 WM_CLASS instance
+    pub instance: Option<String>,
+    /// X11 WM_WINDOW_ROLE. Smithay's X11 surfaces don't read it, so this
+    /// stays unset for now
+    pub window_role: Option<String>,
+    /// X11 _NET_WM_WINDOW_TYPE, named like i3 does (`normal`, `dialog`, ...)
+    pub window_type: Option<&'static str>,
+}
+
+impl WindowCriteria {
+    /// Read the properties of a window
+    pub fn from_element(window: &WindowElement) -> Self {
+        #[cfg(feature = "xwayland")]
+        if let Some(surface) = window.0.x11_surface() {
+            return Self {
+                app_id: None,
+                title: surface.title(),
+                class: Some(surface.class()),
+                instance: Some(surface.instance()),
+                window_role: None,
+                window_type: surface.window_type().map(window_type_name),
+            };
+        }
+        Self {
+            app_id: window.app_id(),
+            title: window.title(),
+            ..Default::default()
+        }
+    }
+
+    /// What identifies the application: the app_id, or the class of X11
+    /// windows
+    pub fn app(&self) -> Option<&str> {
+        self.app_id.as_deref().or(self.class.as_deref())
+    }
+}
+
+/// i3's name for an X11 window type, as used by `[window_type=...]`
+#[cfg(feature = "xwayland")]
+fn window_type_name(window_type: smithay::xwayland::xwm::WmWindowType) -> &'static str {
+    use smithay::xwayland::xwm::WmWindowType;
+
+    match window_type {
+        WmWindowType::Normal => "normal",
+        WmWindowType::Dialog => "dialog",
+        WmWindowType::Utility => "utility",
+        WmWindowType::Toolbar => "toolbar",
+        WmWindowType::Splash => "splash",
+        WmWindowType::Menu => "menu",
+        WmWindowType::DropdownMenu => "dropdown_menu",
+        WmWindowType::PopupMenu => "popup_menu",
+        WmWindowType::Tooltip => "tooltip",
+        WmWindowType::Notification => "notification",
+    }
+}
@@ -9,7 +9,7 @@
 use smithay::{
     desktop::{PopupManager, Space},
     output::Output,
-    utils::{Logical, Point, Rectangle},
+    utils::{Logical, Point, Rectangle, Size},
 };
 use std::time::Instant;
 use tracing::{debug, info, warn};
@@ -201,11 +201,18 @@ impl WindowManager {
             }
 
             let window_element = &managed_window.element;
+            // The border and the header bar of a server-side decorated
+            // window take their space out of the rectangle
+            let decoration = window_element.decoration_size();
+            let content_size = Size::from((
+                (size.size.w - decoration.w).max(1),
+                (size.size.h - decoration.h).max(1),
+            ));
             // Handle resize through the window element
             if let Some(toplevel) = window_element.0.toplevel() {
                 // XDG windows handle resize through configure events
                 toplevel.with_pending_state(|state| {
-                    state.size = Some(size.size);
+                    state.size = Some(content_size);
                 });
                 toplevel.send_pending_configure();
             }
@@ -213,7 +220,8 @@ impl WindowManager {
             {
                 if let Some(surface) = window_element.0.x11_surface() {
                     // X11 windows can be resized directly
-                    let _ = surface.configure(size);
+                    let offset = window_element.content_offset();
+                    let _ = surface.configure(Rectangle::new(size.loc + offset, content_size));
                 }
             }
             debug!("Resized window {} to {:?}", window_id, size);
@@ -337,6 +345,23 @@ impl WindowManager {
         }
     }
 
+    /// Record where a floating window was dragged or resized to, so later
+    /// relayouts keep it there
+    pub fn set_floating_geometry(
+        &mut self,
+        window_id: WindowId,
+        geometry: Rectangle<i32, Logical>,
+    ) {
+        if let Some(managed_window) = self.registry.get_mut(window_id) {
+            if let WindowLayout::Floating {
+                geometry: ref mut floating,
+            } = managed_window.layout
+            {
+                *floating = geometry;
+            }
+        }
+    }
+
     /// Clear moved flag for a window
     pub fn clear_moved(&mut self, window_id: WindowId) {
         self.moved_windows.remove(&window_id);
@@ -4,11 +4,13 @@
 //! WindowElement with tracking information.
 
 mod consistency;
+mod criteria;
 mod id;
 mod manager;
 mod registry;
 
 pub use consistency::check_consistency;
+pub use criteria::WindowCriteria;
 pub use id::{ContainerId, WindowId};
 pub use manager::WindowManager;
 pub use registry::WindowRegistry;
@@ -106,6 +108,14 @@ pub struct ManagedWindow {
     pub workspace: WorkspaceId,
     /// Current layout state of the window
     pub layout: WindowLayout,
+    /// When the window asked for attention, until it gets focus
+    pub urgent_since: Option<std::time::Instant>,
+    /// focus_follows_mouse forced for this window by a `for_window` rule
+    pub focus_follows_mouse: Option<bool>,
+    /// Title last announced on the event bus, to notice changes
+    pub last_title: String,
+    /// App id last announced on the event bus, to notice changes
+    pub last_app_id: Option<String>,
 }
 
 impl ManagedWindow {
@@ -113,6 +123,8 @@ impl ManagedWindow {
     pub fn new(element: WindowElement, workspace: WorkspaceId) -> Self {
         // New windows start without a container assignment
         // The workspace will assign them to a container
+        let last_title = element.title();
+        let last_app_id = element.app_id();
         Self {
             id: WindowId::next(),
             element,
@@ -121,6 +133,10 @@ impl ManagedWindow {
                 container: ContainerId::next(), // Temporary - will be replaced by workspace
                 geometry: Rectangle::default(),
             },
+            urgent_since: None,
+            focus_follows_mouse: None,
+            last_title,
+            last_app_id,
         }
     }
 
@@ -134,6 +150,11 @@ impl ManagedWindow {
         self.element.app_id()
     }
 
+    /// Properties config criteria match this window by
+    pub fn criteria(&self) -> WindowCriteria {
+        WindowCriteria::from_element(&self.element)
+    }
+
     /// Check if window is currently fullscreen in any mode
     pub fn is_fullscreen(&self) -> bool {
         matches!(self.layout, WindowLayout::Fullscreen { .. })
@@ -144,6 +165,11 @@ impl ManagedWindow {
         matches!(self.layout, WindowLayout::Tiled { .. })
     }
 
+    /// Check if window wants attention
+    pub fn is_urgent(&self) -> bool {
+        self.urgent_since.is_some()
+    }
+
     /// Check if window is floating
     pub fn is_floating(&self) -> bool {
         matches!(self.layout, WindowLayout::Floating { .. })
@@ -26,7 +26,7 @@ use smithay::{
     },
     delegate_dmabuf,
     input::{
-        keyboard::LedState,
+        keyboard::{LedState, ModifiersState},
         pointer::{CursorImageAttributes, CursorImageStatus},
     },
     output::{Mode, Output, PhysicalProperties, Subpixel},
@@ -34,9 +34,9 @@ use smithay::{
         calloop::EventLoop,
         wayland_protocols::wp::presentation_time::server::wp_presentation_feedback,
         wayland_server::{protocol::wl_surface, Display},
-        winit::platform::pump_events::PumpStatus,
+        winit::{dpi::PhysicalSize, platform::pump_events::PumpStatus},
     },
-    utils::{IsAlive, Point, Rectangle, Scale, Size, Transform},
+    utils::{IsAlive, Physical, Point, Rectangle, Scale, Size, Transform},
     wayland::{
         compositor,
         dmabuf::{
@@ -59,10 +59,47 @@ pub struct WinitData {
     dmabuf_state: (DmabufState, DmabufGlobal, Option<DmabufFeedback>),
     full_redraw: u8,
     render_needed: bool,
+    /// When the last frame was drawn, so commits in between share the next one
+    last_render: Option<std::time::Instant>,
+    /// Modifier standing in for the logo key, which the host compositor keeps
+    nested_modifier: Option<NestedModifier>,
     #[cfg(feature = "debug")]
     pub fps: fps_ticker::Fps,
 }
 
+/// `--nested-mod`: bindings on the logo key fire with this modifier instead
+#[derive(Debug, Clone)]
+pub struct NestedModifier {
+    /// As given on the command line, for the window title
+    pub name: String,
+    pub modifiers: ModifiersState,
+}
+
+impl NestedModifier {
+    /// Parse a modifier name such as `Alt` or `Ctrl+Alt`
+    pub fn parse(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            name: name.to_string(),
+            modifiers: crate::config::parser::parse_modifiers(name)?,
+        })
+    }
+}
+
+impl WinitData {
+    /// Show in the window title whether the nested compositor has the
+    /// keyboard, and which modifier its bindings use
+    fn update_title(&self, focused: bool) {
+        let mut title = String::from("stilch");
+        if let Some(nested) = &self.nested_modifier {
+            title.push_str(&format!(" ({} as mod)", nested.name));
+        }
+        if focused {
+            title.push_str(" - keyboard captured");
+        }
+        self.backend.window().set_title(&title);
+    }
+}
+
 impl DmabufHandler for StilchState<WinitData> {
     fn dmabuf_state(&mut self) -> &mut DmabufState {
         &mut self.backend_data.dmabuf_state.0
@@ -102,14 +139,28 @@ impl Backend for WinitData {
     fn request_render(&mut self) {
         self.render_needed = true;
     }
+    fn binding_modifiers(&self, modifiers: ModifiersState) -> ModifiersState {
+        match &self.nested_modifier {
+            Some(nested) => crate::keybindings::remap_logo_modifier(modifiers, nested.modifiers),
+            None => modifiers,
+        }
+    }
     fn should_schedule_render(&self) -> bool {
         // For winit, we always allow scheduling renders since we don't have the same
         // idle callback mechanism as udev
         true
     }
+    fn resize_output(&mut self, _output: &Output, size: Size<i32, Physical>) -> bool {
+        // The output follows the window through the resize event
+        let size = PhysicalSize::new(size.w.max(1) as u32, size.h.max(1) as u32);
+        let _ = self.backend.window().request_inner_size(size);
+        true
+    }
 }
 
-pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_winit(
+    nested_modifier: Option<NestedModifier>,
+) -> Result<(), Box<dyn std::error::Error>> {
     let mut event_loop = EventLoop::try_new()?;
     let display = Display::new()?;
     let mut display_handle = display.handle();
@@ -233,10 +284,13 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
             dmabuf_state,
             full_redraw: 0,
             render_needed: true, // Initial render needed
+            last_render: None,
+            nested_modifier,
             #[cfg(feature = "debug")]
             fps: fps_ticker::Fps::default(),
         }
     };
+    data.update_title(false);
     let mut state = StilchState::init(display, event_loop.handle(), data, true);
     state
         .protocols
@@ -453,6 +507,7 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
                 // Update tiling area for new output size
                 state.update_tiling_area_from_output();
             }
+            WinitEvent::Focus(focused) => state.backend_data.update_title(focused),
             WinitEvent::Input(event) => state.process_input_event_windowed(event, OUTPUT_NAME),
             _ => (),
         });
@@ -462,9 +517,20 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
             break;
         }
 
-        // Only render if needed
-        if state.backend_data.render_needed {
+        // Only render if needed, and at most once per refresh or
+        // max_render_fps interval however often clients commit
+        let render_interval = output
+            .current_mode()
+            .map(|mode| Duration::from_secs_f64(1_000f64 / mode.refresh as f64))
+            .unwrap_or_default()
+            .max(state.config.min_render_interval().unwrap_or_default());
+        let render_due = state
+            .backend_data
+            .last_render
+            .is_none_or(|last| last.elapsed() >= render_interval);
+        if state.backend_data.render_needed && render_due {
             state.backend_data.render_needed = false; // Clear the flag
+            state.backend_data.last_render = Some(std::time::Instant::now());
 
             // drawing logic
             {
@@ -485,8 +551,9 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
                 if reset {
                     state.input_manager.cursor_status = CursorImageStatus::default_named();
                 }
-                let cursor_visible =
-                    !matches!(state.cursor_status(), CursorImageStatus::Surface(_));
+                let cursor_hidden = state.cursor_hidden();
+                let cursor_visible = !cursor_hidden
+                    && !matches!(state.cursor_status(), CursorImageStatus::Surface(_));
 
                 pointer_element.set_status(state.cursor_status().clone());
 
@@ -509,7 +576,11 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
                 fps_element.update_fps(fps);
 
                 // Extract values we need before mutable borrows
-                let show_window_preview = state.show_window_preview;
+                let overview = state.overview_frame(&output);
+                let debug_overlay = state.debug_overlay_frame(&output);
+                let status_bars = state.status_bar_frames(&output);
+                let selection = state.selection_frame(&output);
+                let closing = state.closing_window_frames(&output);
                 let dnd_icon = state
                     .dnd_icon()
                     .map(|icon| (icon.surface.clone(), icon.offset));
@@ -567,6 +638,7 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     })
                     .unwrap_or_else(|_| std::ptr::null_mut());
+                let render_start = std::time::Instant::now();
                 let render_res = backend.bind().and_then(|(renderer, mut fb)| {
                     #[cfg(feature = "debug")]
                     if let Some(renderdoc) = renderdoc.as_mut() {
@@ -578,16 +650,18 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
 
                     let mut elements = Vec::<CustomRenderElements<GlesRenderer>>::new();
 
-                    elements.extend(
-                        pointer_element.render_elements(
-                            renderer,
-                            (cursor_pos - cursor_hotspot.to_f64())
-                                .to_physical(scale)
-                                .to_i32_round(),
-                            scale,
-                            1.0,
-                        ),
-                    );
+                    if !cursor_hidden {
+                        elements.extend(
+                            pointer_element.render_elements(
+                                renderer,
+                                (cursor_pos - cursor_hotspot.to_f64())
+                                    .to_physical(scale)
+                                    .to_i32_round(),
+                                scale,
+                                1.0,
+                            ),
+                        );
+                    }
 
                     // draw the dnd icon if any
                     if let Some((surface, offset)) = dnd_icon {
@@ -608,6 +682,25 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
                     #[cfg(feature = "debug")]
                     elements.push(CustomRenderElements::Fps(fps_element.clone()));
 
+                    if let Some(frame) = debug_overlay.as_ref() {
+                        elements
+                            .extend(crate::render::debug_overlay_element(renderer, frame, scale));
+                    }
+
+                    elements.extend(crate::render::status_bar_elements(
+                        renderer,
+                        &status_bars,
+                        scale,
+                    ));
+
+                    if let Some(frame) = selection.as_ref() {
+                        elements.extend(crate::render::selection_elements(frame, scale));
+                    }
+
+                    elements.extend(crate::render::closing_window_elements(
+                        renderer, &closing, scale,
+                    ));
+
                     let res = render_output(
                         &output,
                         space,
@@ -616,7 +709,7 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
                         &mut fb,
                         damage_tracker,
                         age,
-                        show_window_preview,
+                        overview.as_ref(),
                         &tab_bar_data,
                         text_cache,
                     )
@@ -678,6 +771,9 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
 
                         // Send frame events so that client start drawing their next frame
                         state.post_repaint(&output, frame_target, None, &states);
+                        if has_rendered {
+                            state.record_frame_time(render_start.elapsed());
+                        }
 
                         // Execute startup commands after first successful render
                         if !state.startup_done.get() {
@@ -718,6 +814,9 @@ pub fn run_winit() -> Result<(), Box<dyn std::error::Error>> {
         } else {
             state.space_mut().refresh();
             state.popups_mut().cleanup();
+            state.refresh_output_management();
+            #[cfg(feature = "xwayland")]
+            state.refresh_xwayland_scale();
             display_handle.flush_clients().unwrap();
         }
 
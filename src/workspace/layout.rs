@@ -1,7 +1,11 @@
 //! Layout tree management for tiling windows
 
+use crate::config::{Criteria, NewWindowPosition};
+use crate::tab_bar::TabBarSize;
 use crate::window::{ContainerId, WindowId};
+use serde::{Deserialize, Serialize};
 use smithay::utils::{Logical, Point, Rectangle, Size};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SplitDirection {
@@ -19,6 +23,71 @@ pub enum LayoutMode {
     Stacked,
 }
 
+/// Physical pixel grid of the output a layout is shown on
+///
+/// At fractional scales most logical coordinates fall between device pixels,
+/// which blurs window edges and leaves 1px seams between tiles. Tile edges are
+/// moved to the nearest logical coordinate that lands on a device pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelGrid {
+    /// Logical location of the output's top-left device pixel
+    pub origin: Point<i32, Logical>,
+    pub scale: f64,
+}
+
+impl PixelGrid {
+    /// How far an edge may move. Fractional scales are multiples of 1/120,
+    /// the common ones (1.25, 1.5, 1.75) repeat every 2 or 4 logical pixels.
+    const MAX_SNAP: i32 = 4;
+
+    fn snap(&self, origin: i32, value: i32) -> i32 {
+        let on_grid = |v: i32| {
+            let physical = (v - origin) as f64 * self.scale;
+            (physical - physical.round()).abs() < 1e-6
+        };
+        (0..=Self::MAX_SNAP)
+            .flat_map(|d| [value - d, value + d])
+            .find(|&v| on_grid(v))
+            .unwrap_or(value)
+    }
+
+    /// Move each edge of `rect` onto the grid. Edges shared by neighbouring
+    /// tiles snap to the same coordinate, so they still meet exactly.
+    pub fn snap_rect(&self, rect: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let x1 = self.snap(self.origin.x, rect.loc.x);
+        let y1 = self.snap(self.origin.y, rect.loc.y);
+        let x2 = self.snap(self.origin.x, rect.loc.x + rect.size.w);
+        let y2 = self.snap(self.origin.y, rect.loc.y + rect.size.h);
+        Rectangle::new((x1, y1).into(), ((x2 - x1).max(1), (y2 - y1).max(1)).into())
+    }
+}
+
+/// Space kept free between the tiled windows and each edge of the workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OuterGaps {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+impl OuterGaps {
+    /// `area` with the gaps taken off its edges
+    pub fn shrink(&self, area: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        Rectangle::new(
+            (area.loc.x + self.left, area.loc.y + self.top).into(),
+            (
+                (area.size.w - self.left - self.right).max(1),
+                (area.size.h - self.top - self.bottom).max(1),
+            )
+                .into(),
+        )
+    }
+}
+
+/// Smallest width or height resizing leaves a window
+pub const MIN_WINDOW_SIZE: i32 = 50;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum TabMoveDirection {
     Left,
@@ -43,6 +112,46 @@ pub enum LayoutNode {
     },
 }
 
+/// A node of the tree by its id, for state kept next to the tree and for
+/// walking it with `focus parent|child|next|prev`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKey {
+    Window(WindowId),
+    Container(ContainerId),
+}
+
+impl LayoutNode {
+    fn key(&self) -> NodeKey {
+        match self {
+            LayoutNode::Window { id, .. } => NodeKey::Window(*id),
+            LayoutNode::Container { id, .. } => NodeKey::Container(*id),
+        }
+    }
+
+    fn geometry(&self) -> Rectangle<i32, Logical> {
+        match self {
+            LayoutNode::Window { geometry, .. } | LayoutNode::Container { geometry, .. } => {
+                *geometry
+            }
+        }
+    }
+}
+
+/// Where two neighbouring children of a split meet, as found under the
+/// pointer to be dragged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitBoundary {
+    container: ContainerId,
+    /// The child before the boundary, `index + 1` being the one after it
+    index: usize,
+    /// Whether the children sit side by side, so the boundary runs
+    /// vertically between them
+    pub side_by_side: bool,
+    /// Length of the child before the boundary when it was found, which
+    /// drags are measured from
+    first_length: i32,
+}
+
 /// Safe container for children that guarantees active_child is always valid
 #[derive(Debug, Clone)]
 pub struct SafeChildren {
@@ -181,6 +290,16 @@ impl SafeChildren {
         true
     }
 
+    /// Insert a child at `index`, clamped to the end (becomes new active)
+    pub fn insert(&mut self, index: usize, child: LayoutNode) {
+        let mut all = self.to_vec();
+        let index = index.min(all.len());
+        all.insert(index, child);
+        if let Some(new_children) = SafeChildren::from_vec(all, index) {
+            *self = new_children;
+        }
+    }
+
     /// Add a child (becomes new active)
     pub fn push(&mut self, child: LayoutNode) {
         // Move current active and all after elements to before, new child becomes active
@@ -256,9 +375,36 @@ impl SafeChildren {
             false
         }
     }
+
+    /// Shift every child one slot forward, the last one wrapping to the
+    /// front, or one slot back. The active child stays the same node.
+    pub fn rotate(&mut self, forward: bool) -> bool {
+        let len = self.len();
+        if len < 2 {
+            return false;
+        }
+
+        let mut all = self.to_vec();
+        let active_idx = self.active_index();
+        let new_active_idx = if forward {
+            all.rotate_right(1);
+            (active_idx + 1) % len
+        } else {
+            all.rotate_left(1);
+            (active_idx + len - 1) % len
+        };
+
+        if let Some(new_children) = SafeChildren::from_vec(all, new_active_idx) {
+            *self = new_children;
+            true
+        } else {
+            false
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ContainerLayout {
     /// Children are arranged horizontally
     Horizontal,
@@ -270,12 +416,60 @@ pub enum ContainerLayout {
     Stacked,
 }
 
+/// A layout tree as written by `save_layout` and read by `append_layout`
+///
+/// Windows are stored as the criteria a window has to match to take their
+/// place once the layout is loaded again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SavedLayoutNode {
+    Window {
+        swallows: Criteria,
+    },
+    Container {
+        layout: ContainerLayout,
+        children: Vec<SavedLayoutNode>,
+    },
+}
+
 /// The layout tree for a workspace
+/// A container taken out of one layout tree to go into another
+#[derive(Debug, Clone)]
+pub struct DetachedContainer {
+    node: LayoutNode,
+    placeholders: HashMap<WindowId, Criteria>,
+}
+
+impl DetachedContainer {
+    /// The windows in the container in tree order, placeholders left out
+    pub fn windows(&self) -> Vec<WindowId> {
+        let mut leaves = Vec::new();
+        LayoutTree::collect_windows_ordered(&Some(self.node.clone()), &mut leaves);
+        leaves.retain(|id| !self.placeholders.contains_key(id));
+        leaves
+    }
+}
+
 #[derive(Debug)]
 pub struct LayoutTree {
     root: Option<LayoutNode>,
     area: Rectangle<i32, Logical>,
     gap: i32,
+    /// Kept free along the edges of `area`
+    outer_gaps: OuterGaps,
+    /// Leave out all gaps while only one window is tiled
+    smart_gaps: bool,
+    /// Space reserved for the bars of tabbed and stacked containers
+    tab_bar: TabBarSize,
+    /// Device pixel grid window edges are snapped to, if any
+    pixel_grid: Option<PixelGrid>,
+    /// Leaves loaded by `append_layout` that no window has taken yet. They
+    /// hold their space in the tree but are left out of every window query.
+    placeholders: HashMap<WindowId, Criteria>,
+    /// Share of resized children in the split holding them, relative to the
+    /// 1.0 of every other child. Kept per container, so a node that moves
+    /// to another split starts out with an even share there.
+    weights: HashMap<(ContainerId, NodeKey), f64>,
 }
 
 impl LayoutTree {
@@ -285,6 +479,12 @@ impl LayoutTree {
             root: None,
             area,
             gap,
+            outer_gaps: OuterGaps::default(),
+            smart_gaps: false,
+            tab_bar: TabBarSize::default(),
+            pixel_grid: None,
+            placeholders: HashMap::new(),
+            weights: HashMap::new(),
         }
     }
 
@@ -293,8 +493,82 @@ impl LayoutTree {
         self.area = area;
     }
 
+    /// Gap between neighbouring windows
+    pub fn gap(&self) -> i32 {
+        self.gap
+    }
+
+    /// Change the gap between neighbouring windows
+    pub fn set_gap(&mut self, gap: i32) {
+        self.gap = gap;
+    }
+
+    /// Change the gaps along the edges of the workspace
+    pub fn set_outer_gaps(&mut self, outer_gaps: OuterGaps) {
+        self.outer_gaps = outer_gaps;
+    }
+
+    /// Turn smart gaps on or off
+    pub fn set_smart_gaps(&mut self, smart_gaps: bool) {
+        self.smart_gaps = smart_gaps;
+    }
+
+    /// Whether smart gaps leave out all gaps, the tree holding a single
+    /// window. Placeholders count, as they hold space of their own.
+    fn gaps_collapsed(&self) -> bool {
+        let mut windows = Vec::new();
+        Self::collect_windows_ordered(&self.root, &mut windows);
+        self.smart_gaps && windows.len() <= 1
+    }
+
+    /// Gap neighbouring windows are laid out with right now
+    pub fn effective_gap(&self) -> i32 {
+        if self.gaps_collapsed() {
+            0
+        } else {
+            self.gap
+        }
+    }
+
+    /// The area tiled windows share, inside the outer gaps
+    pub fn tiling_area(&self) -> Rectangle<i32, Logical> {
+        if self.gaps_collapsed() {
+            self.area
+        } else {
+            self.outer_gaps.shrink(self.area)
+        }
+    }
+
+    /// Space reserved for the bars of tabbed and stacked containers
+    pub fn tab_bar(&self) -> TabBarSize {
+        self.tab_bar
+    }
+
+    /// Change the space reserved for tab and title bars
+    pub fn set_tab_bar(&mut self, tab_bar: TabBarSize) {
+        self.tab_bar = tab_bar;
+    }
+
+    /// Set the device pixel grid of the output this layout is shown on
+    pub fn set_pixel_grid(&mut self, grid: Option<PixelGrid>) {
+        self.pixel_grid = grid;
+    }
+
     /// Add a window to the layout with specific split direction
     pub fn add_window(&mut self, window_id: WindowId, split_direction: SplitDirection) {
+        self.insert_window(window_id, split_direction, NewWindowPosition::End, None);
+    }
+
+    /// Add a window with a specific split direction, at `position` among the
+    /// children of the container it joins. `AfterFocused` places it right
+    /// after the child holding `focused`, or at the end without one.
+    pub fn insert_window(
+        &mut self,
+        window_id: WindowId,
+        split_direction: SplitDirection,
+        position: NewWindowPosition,
+        focused: Option<WindowId>,
+    ) {
         if self.root.is_none() {
             // First window becomes the root
             tracing::debug!(
@@ -314,7 +588,7 @@ impl LayoutTree {
                 split_direction
             );
             if let Some(mut root) = self.root.take() {
-                self.add_to_node(&mut root, window_id, split_direction);
+                Self::add_to_node(&mut root, window_id, split_direction, position, focused);
                 self.root = Some(root);
             } else {
                 tracing::error!("Attempted to add window to non-existent root");
@@ -330,27 +604,463 @@ impl LayoutTree {
         self.calculate_geometries();
     }
 
+    /// Put a window right after `target`, splitting `target` in `direction`
+    /// unless the container it is in already runs that way. Returns false if
+    /// `target` is not in the tree.
+    pub fn insert_beside(
+        &mut self,
+        window_id: WindowId,
+        target: WindowId,
+        direction: SplitDirection,
+    ) -> bool {
+        let Some(root) = &mut self.root else {
+            return false;
+        };
+        let inserted = Self::insert_beside_node(root, window_id, target, direction);
+        if inserted {
+            self.calculate_geometries();
+        }
+        inserted
+    }
+
+    fn insert_beside_node(
+        node: &mut LayoutNode,
+        window_id: WindowId,
+        target: WindowId,
+        direction: SplitDirection,
+    ) -> bool {
+        match node {
+            LayoutNode::Window { id, geometry } => {
+                if *id != target {
+                    return false;
+                }
+                let geometry = *geometry;
+                let mut children = SafeChildren::single(node.clone());
+                children.insert(
+                    1,
+                    LayoutNode::Window {
+                        id: window_id,
+                        geometry,
+                    },
+                );
+                *node = LayoutNode::Container {
+                    id: ContainerId::next(),
+                    layout: match direction {
+                        SplitDirection::Horizontal => ContainerLayout::Horizontal,
+                        SplitDirection::Vertical => ContainerLayout::Vertical,
+                    },
+                    children,
+                    geometry,
+                };
+                true
+            }
+            LayoutNode::Container {
+                layout, children, ..
+            } => {
+                let runs_that_way = matches!(
+                    (*layout, direction),
+                    (ContainerLayout::Horizontal, SplitDirection::Horizontal)
+                        | (ContainerLayout::Vertical, SplitDirection::Vertical)
+                );
+                let index = children.iter().position(
+                    |child| matches!(child, LayoutNode::Window { id, .. } if *id == target),
+                );
+                if let (true, Some(index)) = (runs_that_way, index) {
+                    children.insert(
+                        index + 1,
+                        LayoutNode::Window {
+                            id: window_id,
+                            geometry: Rectangle::default(),
+                        },
+                    );
+                    return true;
+                }
+                children
+                    .iter_mut()
+                    .any(|child| Self::insert_beside_node(child, window_id, target, direction))
+            }
+        }
+    }
+
     /// Remove a window from the layout
     pub fn remove_window(&mut self, window_id: WindowId) {
+        self.placeholders.remove(&window_id);
         let root = self.root.take();
         self.root = self.remove_window_recursive(root, window_id);
+        self.prune_weights();
+    }
+
+    /// Grow the window by `delta` pixels along `direction`'s axis, or shrink
+    /// it for a negative `delta`, like i3's `resize grow|shrink`
+    ///
+    /// The space comes from the child after the one holding the window in
+    /// the nearest split running that way, or the child before it for the
+    /// last one, leaving every other child as it is. Returns whether
+    /// anything changed.
+    pub fn resize_window(
+        &mut self,
+        window_id: WindowId,
+        direction: crate::config::Direction,
+        delta: i32,
+    ) -> bool {
+        let layout = match direction {
+            crate::config::Direction::Left | crate::config::Direction::Right => {
+                ContainerLayout::Horizontal
+            }
+            crate::config::Direction::Up | crate::config::Direction::Down => {
+                ContainerLayout::Vertical
+            }
+        };
+        let gap = self.effective_gap();
+        let Some(root) = &self.root else {
+            return false;
+        };
+        Self::resize_recursive(root, &mut self.weights, window_id, layout, delta, gap)
+            .unwrap_or(false)
+    }
+
+    /// The boundary between two children of a split whose gap, widened by
+    /// `reach` pixels on both sides, holds `point`, the innermost one where
+    /// splits nest. With no reach, only the gap itself counts, so there is
+    /// none where windows meet without one.
+    pub fn split_boundary_at(
+        &self,
+        point: Point<f64, Logical>,
+        reach: i32,
+    ) -> Option<SplitBoundary> {
+        Self::boundary_at_node(self.root.as_ref()?, point, reach)
+    }
+
+    /// The boundary along the edge of `window_id` nearest to `point`, for
+    /// resizing a tiled window by dragging from anywhere inside it. Edges
+    /// on the outside of the tiling area have none, so the next nearest
+    /// edge is taken.
+    pub fn window_edge_boundary(
+        &self,
+        window_id: WindowId,
+        point: Point<f64, Logical>,
+    ) -> Option<SplitBoundary> {
+        let geometry = self.get_window_geometry(window_id)?.to_f64();
+        // Halfway across the gap to the neighbour
+        let half_gap = self.effective_gap() as f64 / 2.0;
+        let left = geometry.loc.x;
+        let right = left + geometry.size.w;
+        let top = geometry.loc.y;
+        let bottom = top + geometry.size.h;
+        let mut edges = [
+            (point.x - left, Point::from((left - half_gap, point.y))),
+            (right - point.x, Point::from((right + half_gap, point.y))),
+            (point.y - top, Point::from((point.x, top - half_gap))),
+            (bottom - point.y, Point::from((point.x, bottom + half_gap))),
+        ];
+        edges.sort_by(|a, b| a.0.total_cmp(&b.0));
+        edges
+            .into_iter()
+            .find_map(|(_, edge)| self.split_boundary_at(edge, 1))
+    }
+
+    /// Move a boundary found by `split_boundary_at` `delta` pixels on from
+    /// where it was found, trading space between the two children meeting
+    /// there only. Returns whether anything changed.
+    pub fn drag_split_boundary(&mut self, boundary: SplitBoundary, delta: i32) -> bool {
+        let Some(LayoutNode::Container { children, .. }) = self
+            .root
+            .as_ref()
+            .and_then(|root| Self::find_container(root, boundary.container))
+        else {
+            return false;
+        };
+        let (Some(first), Some(second)) = (
+            children.get(boundary.index),
+            children.get(boundary.index + 1),
+        ) else {
+            return false;
+        };
+        let length = |node: &LayoutNode| {
+            let geometry = node.geometry();
+            if boundary.side_by_side {
+                geometry.size.w
+            } else {
+                geometry.size.h
+            }
+        };
+        let current = length(first);
+        let pair_length = current + length(second);
+        if pair_length < 2 * MIN_WINDOW_SIZE {
+            return false;
+        }
+        let target =
+            (boundary.first_length + delta).clamp(MIN_WINDOW_SIZE, pair_length - MIN_WINDOW_SIZE);
+        if target == current {
+            return false;
+        }
+
+        let (first, second) = (first.key(), second.key());
+        let pair = Self::weight(&self.weights, boundary.container, first)
+            + Self::weight(&self.weights, boundary.container, second);
+        let first_weight = pair * target as f64 / pair_length as f64;
+        self.weights
+            .insert((boundary.container, first), first_weight);
+        self.weights
+            .insert((boundary.container, second), pair - first_weight);
+        true
+    }
+
+    /// Make two windows trade places in the tree, each taking the other's
+    /// share of its split. Returns whether both were found.
+    pub fn swap_windows(&mut self, first: WindowId, second: WindowId) -> bool {
+        if first == second
+            || !self.contains_window(&self.root, first)
+            || !self.contains_window(&self.root, second)
+        {
+            return false;
+        }
+        let Some(root) = &mut self.root else {
+            return false;
+        };
+        Self::swap_leaves(root, first, second);
+
+        let swapped = |key: NodeKey| match key {
+            NodeKey::Window(id) if id == first => NodeKey::Window(second),
+            NodeKey::Window(id) if id == second => NodeKey::Window(first),
+            key => key,
+        };
+        self.weights = self
+            .weights
+            .drain()
+            .map(|((container, key), weight)| ((container, swapped(key)), weight))
+            .collect();
+        self.calculate_geometries();
+        true
+    }
+
+    /// Share out every split evenly again, or only the one directly
+    /// holding `window_id`
+    pub fn balance(&mut self, window_id: Option<WindowId>) {
+        let Some(window_id) = window_id else {
+            self.weights.clear();
+            return;
+        };
+        let parent = self
+            .root
+            .as_ref()
+            .and_then(|root| Self::find_parent(root, NodeKey::Window(window_id)));
+        if let Some(parent) = parent {
+            self.weights
+                .retain(|(container, _), _| *container != parent);
+        }
+    }
+
+    /// Container directly holding `node`, None for the root
+    pub fn parent_of(&self, node: NodeKey) -> Option<ContainerId> {
+        Self::find_parent(self.root.as_ref()?, node)
+    }
+
+    /// Child of `container` that holds `window`, or else its active child
+    pub fn child_towards(&self, container: ContainerId, window: WindowId) -> Option<NodeKey> {
+        let LayoutNode::Container { children, .. } =
+            Self::find_container(self.root.as_ref()?, container)?
+        else {
+            return None;
+        };
+        let child = children
+            .iter()
+            .find(|child| Self::node_contains_window(child, window))
+            .unwrap_or_else(|| children.active());
+        Some(child.key())
+    }
+
+    /// The child after `node` in its container, or before it, wrapping
+    /// around. None if `node` has no siblings.
+    pub fn sibling_of(&self, node: NodeKey, next: bool) -> Option<NodeKey> {
+        let parent = self.parent_of(node)?;
+        let LayoutNode::Container { children, .. } =
+            Self::find_container(self.root.as_ref()?, parent)?
+        else {
+            return None;
+        };
+        let count = children.len();
+        if count < 2 {
+            return None;
+        }
+        let index = children.iter().position(|child| child.key() == node)?;
+        let index = if next {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        };
+        children.get(index).map(LayoutNode::key)
+    }
+
+    /// Window focus lands on inside `node`, following the active child of
+    /// each container down
+    pub fn focus_leaf(&self, node: NodeKey) -> Option<WindowId> {
+        match node {
+            NodeKey::Window(id) => Some(id),
+            NodeKey::Container(container) => {
+                let LayoutNode::Container { children, .. } =
+                    Self::find_container(self.root.as_ref()?, container)?
+                else {
+                    return None;
+                };
+                self.focus_leaf(children.active().key())
+            }
+        }
+    }
+
+    /// Set the layout of `container` itself, as selected with `focus parent`
+    pub fn set_layout_of(&mut self, container: ContainerId, new_layout: ContainerLayout) {
+        let Some(LayoutNode::Container {
+            layout,
+            children,
+            geometry,
+            ..
+        }) = self
+            .root
+            .as_mut()
+            .and_then(|root| Self::find_container_mut(root, container))
+        else {
+            return;
+        };
+        *layout = new_layout;
+        // Tabs overlap, each taking the whole container
+        if matches!(
+            new_layout,
+            ContainerLayout::Tabbed | ContainerLayout::Stacked
+        ) {
+            for child in children.iter_mut() {
+                Self::reset_node_geometry(child, *geometry);
+            }
+        }
+    }
+
+    /// Flip `container` between a horizontal and a vertical split, or make
+    /// a tabbed or stacked one a split the preferred way
+    pub fn toggle_split_of(&mut self, container: ContainerId, preferred_split: SplitDirection) {
+        if let Some(LayoutNode::Container { layout, .. }) = self
+            .root
+            .as_mut()
+            .and_then(|root| Self::find_container_mut(root, container))
+        {
+            *layout = Self::toggled_split(*layout, preferred_split);
+        }
+    }
+
+    /// Geometry of `container` as last laid out
+    pub fn container_geometry(&self, container: ContainerId) -> Option<Rectangle<i32, Logical>> {
+        Self::find_container(self.root.as_ref()?, container).map(LayoutNode::geometry)
+    }
+
+    /// Move `container`, as selected with `focus parent`, one step the way
+    /// of `direction`
+    ///
+    /// The nearest split running that way where the container, or the
+    /// child holding it, isn't already at that end decides: a child of it
+    /// trades places with its neighbour, one further down leaves its own
+    /// container to sit beside the child holding it, like in i3. Tabbed
+    /// containers run sideways and stacked ones up and down. Returns
+    /// whether anything changed.
+    pub fn move_container(
+        &mut self,
+        container: ContainerId,
+        direction: crate::config::Direction,
+    ) -> bool {
+        use crate::config::Direction;
+
+        let sideways = matches!(direction, Direction::Left | Direction::Right);
+        let forward = matches!(direction, Direction::Right | Direction::Down);
+        let moving = NodeKey::Container(container);
+        let mut child = moving;
+        let (parent, index) = loop {
+            let Some(parent) = self.parent_of(child) else {
+                return false;
+            };
+            let Some(LayoutNode::Container {
+                layout, children, ..
+            }) = self
+                .root
+                .as_ref()
+                .and_then(|root| Self::find_container(root, parent))
+            else {
+                return false;
+            };
+            let runs_that_way = match layout {
+                ContainerLayout::Horizontal | ContainerLayout::Tabbed => sideways,
+                ContainerLayout::Vertical | ContainerLayout::Stacked => !sideways,
+            };
+            let Some(index) = children.iter().position(|node| node.key() == child) else {
+                return false;
+            };
+            let at_end = if forward {
+                index + 1 == children.len()
+            } else {
+                index == 0
+            };
+            if runs_that_way && !at_end {
+                break (parent, index);
+            }
+            child = NodeKey::Container(parent);
+        };
+
+        if child == moving {
+            let neighbour = if forward { index + 1 } else { index - 1 };
+            let Some(LayoutNode::Container { children, .. }) = self
+                .root
+                .as_mut()
+                .and_then(|root| Self::find_container_mut(root, parent))
+            else {
+                return false;
+            };
+            if !children.swap(index, neighbour) {
+                return false;
+            }
+        } else {
+            let Some(node) = self.take_container(container) else {
+                return false;
+            };
+            let Some(LayoutNode::Container { children, .. }) = self
+                .root
+                .as_mut()
+                .and_then(|root| Self::find_container_mut(root, parent))
+            else {
+                return false;
+            };
+            // The child holding it is gone if it held nothing else
+            let index = children
+                .iter()
+                .position(|node| node.key() == child)
+                .map_or(index, |index| if forward { index + 1 } else { index });
+            children.insert(index, node);
+        }
+        self.prune_weights();
+        self.calculate_geometries();
+        true
     }
 
     /// Find the next window to focus after removing a window
     pub fn find_next_focus(&self) -> Option<WindowId> {
         // For tabbed containers, focus the active child, not just the first window
         self.find_active_window(&self.root)
+            .filter(|id| !self.placeholders.contains_key(id))
+            .or_else(|| self.get_windows().first().copied())
     }
 
     /// Calculate geometries for all nodes
     pub fn calculate_geometries(&mut self) {
-        let area = self.area;
-        let gap = self.gap;
+        let area = self.tiling_area();
+        let gap = self.effective_gap();
+        let tab_bar = self.tab_bar;
+        let grid = self.pixel_grid;
         if let Some(root) = &mut self.root {
-            Self::calculate_node_geometry_static(root, area, gap);
+            Self::calculate_node_geometry_static(root, area, gap, tab_bar, grid, &self.weights);
         }
     }
 
+    /// The root of the tree, placeholders included
+    pub fn root(&self) -> Option<&LayoutNode> {
+        self.root.as_ref()
+    }
+
     /// Get the geometry for a specific window
     pub fn get_window_geometry(&self, window_id: WindowId) -> Option<Rectangle<i32, Logical>> {
         self.find_window_geometry(&self.root, window_id)
@@ -360,6 +1070,7 @@ impl LayoutTree {
     pub fn get_all_geometries(&self) -> Vec<(WindowId, Rectangle<i32, Logical>)> {
         let mut geometries = Vec::new();
         self.collect_all_geometries(&self.root, &mut geometries);
+        geometries.retain(|(id, _)| !self.placeholders.contains_key(id));
         geometries
     }
 
@@ -367,6 +1078,7 @@ impl LayoutTree {
     pub fn get_visible_geometries(&self) -> Vec<(WindowId, Rectangle<i32, Logical>)> {
         let mut geometries = Vec::new();
         self.collect_geometries(&self.root, &mut geometries);
+        geometries.retain(|(id, _)| !self.placeholders.contains_key(id));
         geometries
     }
 
@@ -445,10 +1157,22 @@ impl LayoutTree {
         }
     }
 
+    /// Rotate the children of the container holding `window_id` by one slot,
+    /// clockwise moving each to the next slot and the last one to the first.
+    /// The tree keeps its shape; returns false if there is nothing to rotate.
+    pub fn rotate_container(&mut self, window_id: WindowId, clockwise: bool) -> bool {
+        if let Some(root) = &mut self.root {
+            Self::rotate_container_recursive(root, window_id, clockwise)
+        } else {
+            false
+        }
+    }
+
     /// Get all windows in order
     pub fn get_windows(&self) -> Vec<WindowId> {
         let mut windows = Vec::new();
         Self::collect_windows_ordered(&self.root, &mut windows);
+        windows.retain(|id| !self.placeholders.contains_key(id));
         windows
     }
 
@@ -466,16 +1190,154 @@ impl LayoutTree {
     pub fn find_tabbed_containers(&self) -> Vec<(Rectangle<i32, Logical>, Vec<(WindowId, bool)>)> {
         let mut containers = Vec::new();
         Self::find_tabbed_containers_recursive(&self.root, &mut containers);
-        containers
+        self.without_placeholder_tabs(containers)
     }
 
     /// Find all stacked containers and their windows
     pub fn find_stacked_containers(&self) -> Vec<(Rectangle<i32, Logical>, Vec<(WindowId, bool)>)> {
         let mut containers = Vec::new();
         Self::find_stacked_containers_recursive(&self.root, &mut containers);
+        self.without_placeholder_tabs(containers)
+    }
+
+    fn without_placeholder_tabs(
+        &self,
+        mut containers: Vec<(Rectangle<i32, Logical>, Vec<(WindowId, bool)>)>,
+    ) -> Vec<(Rectangle<i32, Logical>, Vec<(WindowId, bool)>)> {
+        for (_, tabs) in &mut containers {
+            tabs.retain(|(id, _)| !self.placeholders.contains_key(id));
+        }
+        containers.retain(|(_, tabs)| !tabs.is_empty());
         containers
     }
 
+    /// Serialize the tree, describing each window by the criteria `criteria`
+    /// returns for it. Windows without criteria are left out.
+    pub fn save(&self, criteria: impl Fn(WindowId) -> Option<Criteria>) -> Option<SavedLayoutNode> {
+        let leaf_criteria =
+            |id: WindowId| self.placeholders.get(&id).cloned().or_else(|| criteria(id));
+        self.root
+            .as_ref()
+            .and_then(|root| Self::save_node(root, &leaf_criteria))
+    }
+
+    /// Add a saved layout next to the existing tree, split in `direction`,
+    /// with a placeholder for every window in it that has criteria. Returns
+    /// the number of placeholders added.
+    pub fn append(&mut self, saved: &SavedLayoutNode, direction: SplitDirection) -> usize {
+        let before = self.placeholders.len();
+        let Some(node) = Self::build_node(saved, &mut self.placeholders) else {
+            return 0;
+        };
+        self.join_root(node, direction);
+        self.placeholders.len() - before
+    }
+
+    /// Take `container` out of the tree as a whole, along with the
+    /// placeholders in it, to be attached to another workspace's tree
+    pub fn detach_container(&mut self, container: ContainerId) -> Option<DetachedContainer> {
+        let node = match self.take_container(container) {
+            Some(node) => node,
+            // Nothing else is in the tree but the containers wrapping it
+            None => {
+                let root = self.root.take()?;
+                let node = Self::find_container(&root, container).cloned();
+                if node.is_none() {
+                    self.root = Some(root);
+                }
+                node?
+            }
+        };
+        let mut leaves = Vec::new();
+        Self::collect_windows_ordered(&Some(node.clone()), &mut leaves);
+        let placeholders = leaves
+            .into_iter()
+            .filter_map(|id| self.placeholders.remove_entry(&id))
+            .collect();
+        self.prune_weights();
+        self.calculate_geometries();
+        Some(DetachedContainer { node, placeholders })
+    }
+
+    /// Add a container detached from another tree next to the existing
+    /// tree, split in `direction`
+    pub fn attach(&mut self, detached: DetachedContainer, direction: SplitDirection) {
+        self.placeholders.extend(detached.placeholders);
+        self.join_root(detached.node, direction);
+    }
+
+    /// Make `node` the root, or a child of the root split in `direction`
+    fn join_root(&mut self, node: LayoutNode, direction: SplitDirection) {
+        self.root = Some(match self.root.take() {
+            None => node,
+            Some(LayoutNode::Container {
+                id,
+                layout,
+                mut children,
+                geometry,
+            }) if layout == Self::split_layout(direction) => {
+                children.push(node);
+                LayoutNode::Container {
+                    id,
+                    layout,
+                    children,
+                    geometry,
+                }
+            }
+            Some(root) => {
+                let mut children = SafeChildren::single(root);
+                children.push(node);
+                LayoutNode::Container {
+                    id: ContainerId::next(),
+                    layout: Self::split_layout(direction),
+                    children,
+                    geometry: self.area,
+                }
+            }
+        });
+        self.calculate_geometries();
+    }
+
+    /// First placeholder, in tree order, whose criteria satisfy `matches`
+    pub fn find_placeholder(&self, matches: impl Fn(&Criteria) -> bool) -> Option<WindowId> {
+        let mut leaves = Vec::new();
+        Self::collect_windows_ordered(&self.root, &mut leaves);
+        leaves.into_iter().find(|id| {
+            self.placeholders
+                .get(id)
+                .is_some_and(|criteria| matches(criteria))
+        })
+    }
+
+    /// Whether `id` is a placeholder waiting for a window
+    pub fn is_placeholder(&self, id: WindowId) -> bool {
+        self.placeholders.contains_key(&id)
+    }
+
+    /// Put `window_id` in the place of a placeholder
+    pub fn swallow(&mut self, placeholder: WindowId, window_id: WindowId) -> bool {
+        if self.placeholders.remove(&placeholder).is_none() {
+            return false;
+        }
+        if let Some(root) = &mut self.root {
+            Self::replace_window_id(root, placeholder, window_id);
+        }
+        let moved: Vec<_> = self
+            .weights
+            .keys()
+            .filter(|(_, key)| *key == NodeKey::Window(placeholder))
+            .copied()
+            .collect();
+        for (container, key) in moved {
+            if let Some(weight) = self.weights.remove(&(container, key)) {
+                self.weights
+                    .insert((container, NodeKey::Window(window_id)), weight);
+            }
+        }
+        self.calculate_geometries();
+        true
+    }
+
     /// Move a window in the given direction
     pub fn move_window(
         &mut self,
@@ -600,11 +1462,31 @@ impl LayoutTree {
 
     // Helper methods
 
+    /// Index a new window takes among `children`
+    fn insert_index(
+        children: &SafeChildren,
+        position: NewWindowPosition,
+        focused: Option<WindowId>,
+    ) -> usize {
+        match position {
+            NewWindowPosition::Beginning => 0,
+            NewWindowPosition::End => children.len(),
+            NewWindowPosition::AfterFocused => focused
+                .and_then(|focused| {
+                    children
+                        .iter()
+                        .position(|child| Self::node_contains_window(child, focused))
+                })
+                .map_or(children.len(), |index| index + 1),
+        }
+    }
+
     fn add_to_node(
-        &mut self,
         node: &mut LayoutNode,
         window_id: WindowId,
         direction: SplitDirection,
+        position: NewWindowPosition,
+        focused: Option<WindowId>,
     ) {
         match node {
             LayoutNode::Window { id, geometry } => {
@@ -621,10 +1503,14 @@ impl LayoutTree {
                     id: old_id,
                     geometry: old_geometry,
                 });
-                new_children.push(LayoutNode::Window {
-                    id: window_id,
-                    geometry: old_geometry,
-                });
+                let index = Self::insert_index(&new_children, position, focused);
+                new_children.insert(
+                    index,
+                    LayoutNode::Window {
+                        id: window_id,
+                        geometry: old_geometry,
+                    },
+                );
 
                 *node = LayoutNode::Container {
                     id: ContainerId::next(),
@@ -642,11 +1528,15 @@ impl LayoutTree {
                     ContainerLayout::Vertical => SplitDirection::Vertical,
                     ContainerLayout::Tabbed | ContainerLayout::Stacked => {
                         // For tabbed/stacked, just add the window
-                        children.push(LayoutNode::Window {
-                            id: window_id,
-                            geometry: Rectangle::default(),
-                        });
-                        // New window becomes active automatically with push()
+                        let index = Self::insert_index(children, position, focused);
+                        children.insert(
+                            index,
+                            LayoutNode::Window {
+                                id: window_id,
+                                geometry: Rectangle::default(),
+                            },
+                        );
+                        // New window becomes active automatically with insert()
                         tracing::info!(
                             "Added window {} to tabbed container, set as active tab",
                             window_id
@@ -657,10 +1547,14 @@ impl LayoutTree {
 
                 if container_direction == direction {
                     // Same direction - add as sibling
-                    children.push(LayoutNode::Window {
-                        id: window_id,
-                        geometry: Rectangle::default(),
-                    });
+                    let index = Self::insert_index(children, position, focused);
+                    children.insert(
+                        index,
+                        LayoutNode::Window {
+                            id: window_id,
+                            geometry: Rectangle::default(),
+                        },
+                    );
                 } else {
                     // Different direction - replace this container with a new split
                     let old_container = node.clone();
@@ -670,10 +1564,14 @@ impl LayoutTree {
                     };
 
                     let mut new_children = SafeChildren::single(old_container);
-                    new_children.push(LayoutNode::Window {
-                        id: window_id,
-                        geometry: Rectangle::default(),
-                    });
+                    let index = Self::insert_index(&new_children, position, focused);
+                    new_children.insert(
+                        index,
+                        LayoutNode::Window {
+                            id: window_id,
+                            geometry: Rectangle::default(),
+                        },
+                    );
 
                     *node = LayoutNode::Container {
                         id: ContainerId::next(),
@@ -874,16 +1772,22 @@ impl LayoutTree {
         node: &mut LayoutNode,
         available: Rectangle<i32, Logical>,
         gap: i32,
+        tab_bar: TabBarSize,
+        grid: Option<PixelGrid>,
+        weights: &HashMap<(ContainerId, NodeKey), f64>,
     ) {
         match node {
             LayoutNode::Window { geometry, .. } => {
-                *geometry = available;
+                *geometry = match grid {
+                    Some(grid) => grid.snap_rect(available),
+                    None => available,
+                };
             }
             LayoutNode::Container {
+                id,
                 layout,
                 children,
                 geometry,
-                ..
             } => {
                 *geometry = available;
 
@@ -891,48 +1795,66 @@ impl LayoutTree {
                     ContainerLayout::Horizontal => {
                         let count = children.len() as i32;
                         let total_gap_space = gap * (count - 1);
-                        let width = (available.size.w - total_gap_space) / count;
+                        let widths = Self::split_lengths(
+                            *id,
+                            children,
+                            available.size.w - total_gap_space,
+                            weights,
+                        );
 
-                        for (i, child) in children.iter_mut().enumerate() {
-                            let x = available.loc.x + (width + gap) * i as i32;
+                        let mut x = available.loc.x;
+                        for (child, width) in children.iter_mut().zip(widths) {
                             let child_rect = Rectangle::new(
                                 (x, available.loc.y).into(),
                                 (width, available.size.h).into(),
                             );
-                            Self::calculate_node_geometry_static(child, child_rect, gap);
+                            Self::calculate_node_geometry_static(
+                                child, child_rect, gap, tab_bar, grid, weights,
+                            );
+                            x += width + gap;
                         }
                     }
                     ContainerLayout::Vertical => {
                         let count = children.len() as i32;
                         let total_gap_space = gap * (count - 1);
-                        let height = (available.size.h - total_gap_space) / count;
+                        let heights = Self::split_lengths(
+                            *id,
+                            children,
+                            available.size.h - total_gap_space,
+                            weights,
+                        );
 
-                        for (i, child) in children.iter_mut().enumerate() {
-                            let y = available.loc.y + (height + gap) * i as i32;
+                        let mut y = available.loc.y;
+                        for (child, height) in children.iter_mut().zip(heights) {
                             let child_rect = Rectangle::new(
                                 (available.loc.x, y).into(),
                                 (available.size.w, height).into(),
                             );
-                            Self::calculate_node_geometry_static(child, child_rect, gap);
+                            Self::calculate_node_geometry_static(
+                                child, child_rect, gap, tab_bar, grid, weights,
+                            );
+                            y += height + gap;
                         }
                     }
                     ContainerLayout::Tabbed => {
                         // For tabbed, reserve space for tab bar at top
-                        let tab_bar_height = crate::tab_bar::TAB_BAR_HEIGHT;
-                        let client_area = Rectangle::new(
-                            (available.loc.x, available.loc.y + tab_bar_height).into(),
-                            (available.size.w, available.size.h - tab_bar_height).into(),
-                        );
+                        let client_area = crate::tab_bar::calculate_client_area(available, tab_bar);
                         // All children get the client area (below tab bar)
                         for child in children.iter_mut() {
-                            Self::calculate_node_geometry_static(child, client_area, gap);
+                            Self::calculate_node_geometry_static(
+                                child,
+                                client_area,
+                                gap,
+                                tab_bar,
+                                grid,
+                                weights,
+                            );
                         }
                     }
                     ContainerLayout::Stacked => {
                         // For stacked, reserve space for title bars - one for each window
                         let num_children = children.len();
-                        let title_bar_height = crate::tab_bar::TAB_BAR_HEIGHT;
-                        let total_title_height = title_bar_height * num_children as i32;
+                        let total_title_height = tab_bar.reserved(num_children as i32);
 
                         // Calculate the client area (below all stacked title bars)
                         let client_area = Rectangle::new(
@@ -942,7 +1864,14 @@ impl LayoutTree {
 
                         // All children get the client area (below title bars)
                         for child in children.iter_mut() {
-                            Self::calculate_node_geometry_static(child, client_area, gap);
+                            Self::calculate_node_geometry_static(
+                                child,
+                                client_area,
+                                gap,
+                                tab_bar,
+                                grid,
+                                weights,
+                            );
                         }
                     }
                 }
@@ -950,6 +1879,271 @@ impl LayoutTree {
         }
     }
 
+    /// Lengths `children` of container `id` get out of `length`, going by
+    /// their weights. Evenly shared splits give every child the same whole
+    /// number of pixels, resized ones are rounded so they fill `length`.
+    fn split_lengths(
+        id: ContainerId,
+        children: &SafeChildren,
+        length: i32,
+        weights: &HashMap<(ContainerId, NodeKey), f64>,
+    ) -> Vec<i32> {
+        let count = children.len() as i32;
+        if !children
+            .iter()
+            .any(|child| weights.contains_key(&(id, child.key())))
+        {
+            return vec![length / count; children.len()];
+        }
+
+        let shares: Vec<f64> = children
+            .iter()
+            .map(|child| Self::weight(weights, id, child.key()))
+            .collect();
+        let total: f64 = shares.iter().sum();
+        let mut lengths = Vec::with_capacity(shares.len());
+        let mut share_before = 0.0;
+        let mut edge_before = 0;
+        for share in shares {
+            share_before += share;
+            let edge = (length as f64 * share_before / total).round() as i32;
+            lengths.push(edge - edge_before);
+            edge_before = edge;
+        }
+        lengths
+    }
+
+    fn weight(
+        weights: &HashMap<(ContainerId, NodeKey), f64>,
+        container: ContainerId,
+        key: NodeKey,
+    ) -> f64 {
+        weights.get(&(container, key)).copied().unwrap_or(1.0)
+    }
+
+    /// Resize in the nearest split running `layout`'s way around the
+    /// window. `None` when the window is not under `node`, `Some(false)`
+    /// while no split of that way has taken the change yet.
+    fn resize_recursive(
+        node: &LayoutNode,
+        weights: &mut HashMap<(ContainerId, NodeKey), f64>,
+        window_id: WindowId,
+        layout: ContainerLayout,
+        delta: i32,
+        gap: i32,
+    ) -> Option<bool> {
+        let LayoutNode::Container {
+            id,
+            layout: node_layout,
+            children,
+            geometry,
+        } = node
+        else {
+            return matches!(node, LayoutNode::Window { id, .. } if *id == window_id)
+                .then_some(false);
+        };
+        let index = children
+            .iter()
+            .position(|child| Self::node_contains_window(child, window_id))?;
+        let child = children.get(index)?;
+        if Self::resize_recursive(child, weights, window_id, layout, delta, gap)? {
+            return Some(true);
+        }
+        if *node_layout != layout || children.len() < 2 {
+            return Some(false);
+        }
+
+        let sibling = if index + 1 < children.len() {
+            index + 1
+        } else {
+            index - 1
+        };
+        let sibling = children.get(sibling)?.key();
+        let child = child.key();
+        let size = match layout {
+            ContainerLayout::Horizontal => geometry.size.w,
+            _ => geometry.size.h,
+        };
+        let length = size - gap * (children.len() as i32 - 1);
+        if length <= 0 {
+            return Some(false);
+        }
+
+        // Weights per pixel of this split
+        let total: f64 = children
+            .iter()
+            .map(|node| Self::weight(weights, *id, node.key()))
+            .sum();
+        let per_pixel = total / length as f64;
+        let child_weight = Self::weight(weights, *id, child);
+        let sibling_weight = Self::weight(weights, *id, sibling);
+        let min = MIN_WINDOW_SIZE as f64 * per_pixel;
+        let pair = child_weight + sibling_weight;
+        if pair < 2.0 * min {
+            return Some(false);
+        }
+        let resized = (child_weight + delta as f64 * per_pixel).clamp(min, pair - min);
+        if (resized - child_weight).abs() < per_pixel / 2.0 {
+            return Some(false);
+        }
+        weights.insert((*id, child), resized);
+        weights.insert((*id, sibling), pair - resized);
+        Some(true)
+    }
+
+    fn boundary_at_node(
+        node: &LayoutNode,
+        point: Point<f64, Logical>,
+        reach: i32,
+    ) -> Option<SplitBoundary> {
+        let LayoutNode::Container {
+            id,
+            layout,
+            children,
+            geometry,
+        } = node
+        else {
+            return None;
+        };
+        if !geometry.to_f64().contains(point) {
+            return None;
+        }
+        let inner = match layout {
+            // Only the shown child of a tabbed or stacked container counts
+            ContainerLayout::Tabbed | ContainerLayout::Stacked => {
+                Self::boundary_at_node(children.active(), point, reach)
+            }
+            ContainerLayout::Horizontal | ContainerLayout::Vertical => children
+                .iter()
+                .find_map(|child| Self::boundary_at_node(child, point, reach)),
+        };
+        if inner.is_some() {
+            return inner;
+        }
+
+        let side_by_side = match layout {
+            ContainerLayout::Horizontal => true,
+            ContainerLayout::Vertical => false,
+            ContainerLayout::Tabbed | ContainerLayout::Stacked => return None,
+        };
+        let along = if side_by_side { point.x } else { point.y };
+        (0..children.len().saturating_sub(1)).find_map(|index| {
+            let first = children.get(index)?.geometry();
+            let second = children.get(index + 1)?.geometry();
+            let (first_length, end, start) = if side_by_side {
+                (first.size.w, first.loc.x + first.size.w, second.loc.x)
+            } else {
+                (first.size.h, first.loc.y + first.size.h, second.loc.y)
+            };
+            // The second child starts on its own first pixel
+            let near = along >= (end - reach) as f64 && along < (start + reach) as f64;
+            near.then_some(SplitBoundary {
+                container: *id,
+                index,
+                side_by_side,
+                first_length,
+            })
+        })
+    }
+
+    fn swap_leaves(node: &mut LayoutNode, first: WindowId, second: WindowId) {
+        match node {
+            LayoutNode::Window { id, .. } if *id == first => *id = second,
+            LayoutNode::Window { id, .. } if *id == second => *id = first,
+            LayoutNode::Window { .. } => {}
+            LayoutNode::Container { children, .. } => {
+                for child in children.iter_mut() {
+                    Self::swap_leaves(child, first, second);
+                }
+            }
+        }
+    }
+
+    fn find_container(node: &LayoutNode, container: ContainerId) -> Option<&LayoutNode> {
+        match node {
+            LayoutNode::Container { id, .. } if *id == container => Some(node),
+            LayoutNode::Container { children, .. } => children
+                .iter()
+                .find_map(|child| Self::find_container(child, container)),
+            LayoutNode::Window { .. } => None,
+        }
+    }
+
+    fn find_container_mut(
+        node: &mut LayoutNode,
+        container: ContainerId,
+    ) -> Option<&mut LayoutNode> {
+        if matches!(node, LayoutNode::Container { id, .. } if *id == container) {
+            return Some(node);
+        }
+        match node {
+            LayoutNode::Container { children, .. } => children
+                .iter_mut()
+                .find_map(|child| Self::find_container_mut(child, container)),
+            LayoutNode::Window { .. } => None,
+        }
+    }
+
+    /// Id of the container directly holding `key`
+    fn find_parent(node: &LayoutNode, key: NodeKey) -> Option<ContainerId> {
+        let LayoutNode::Container { id, children, .. } = node else {
+            return None;
+        };
+        if children.iter().any(|child| child.key() == key) {
+            return Some(*id);
+        }
+        children
+            .iter()
+            .find_map(|child| Self::find_parent(child, key))
+    }
+
+    /// Take `container` out of the tree, along with the containers around it
+    /// that held nothing else
+    fn take_container(&mut self, container: ContainerId) -> Option<LayoutNode> {
+        let mut outer = NodeKey::Container(container);
+        let parent = loop {
+            let parent = self.parent_of(outer)?;
+            let Some(LayoutNode::Container { children, .. }) =
+                Self::find_container(self.root.as_ref()?, parent)
+            else {
+                return None;
+            };
+            if children.len() > 1 {
+                break parent;
+            }
+            outer = NodeKey::Container(parent);
+        };
+        let Some(LayoutNode::Container { children, .. }) =
+            Self::find_container_mut(self.root.as_mut()?, parent)
+        else {
+            return None;
+        };
+        let taken = children.remove(|child| child.key() == outer)?;
+        Self::find_container(&taken, container).cloned()
+    }
+
+    /// Forget the weights of children no longer in the container they were
+    /// resized in
+    fn prune_weights(&mut self) {
+        if self.weights.is_empty() {
+            return;
+        }
+        let mut pairs = HashSet::new();
+        if let Some(root) = &self.root {
+            Self::collect_child_keys(root, &mut pairs);
+        }
+        self.weights.retain(|pair, _| pairs.contains(pair));
+    }
+
+    fn collect_child_keys(node: &LayoutNode, pairs: &mut HashSet<(ContainerId, NodeKey)>) {
+        if let LayoutNode::Container { id, children, .. } = node {
+            for child in children.iter() {
+                pairs.insert((*id, child.key()));
+                Self::collect_child_keys(child, pairs);
+            }
+        }
+    }
+
     fn find_window_geometry(
         &self,
         node: &Option<LayoutNode>,
@@ -1239,17 +2433,7 @@ impl LayoutTree {
                     .any(|child| Self::node_contains_window(child, window_id));
 
                 if contains_window {
-                    *layout = match *layout {
-                        ContainerLayout::Horizontal => ContainerLayout::Vertical,
-                        ContainerLayout::Vertical => ContainerLayout::Horizontal,
-                        ContainerLayout::Tabbed | ContainerLayout::Stacked => {
-                            // Convert tabbed/stacked to split using the preferred direction
-                            match preferred_split {
-                                SplitDirection::Horizontal => ContainerLayout::Horizontal,
-                                SplitDirection::Vertical => ContainerLayout::Vertical,
-                            }
-                        }
-                    };
+                    *layout = Self::toggled_split(*layout, preferred_split);
                 } else {
                     for child in children.iter_mut() {
                         Self::toggle_container_split_recursive(child, window_id, preferred_split);
@@ -1259,6 +2443,20 @@ impl LayoutTree {
         }
     }
 
+    fn toggled_split(layout: ContainerLayout, preferred_split: SplitDirection) -> ContainerLayout {
+        match layout {
+            ContainerLayout::Horizontal => ContainerLayout::Vertical,
+            ContainerLayout::Vertical => ContainerLayout::Horizontal,
+            ContainerLayout::Tabbed | ContainerLayout::Stacked => {
+                // Convert tabbed/stacked to split using the preferred direction
+                match preferred_split {
+                    SplitDirection::Horizontal => ContainerLayout::Horizontal,
+                    SplitDirection::Vertical => ContainerLayout::Vertical,
+                }
+            }
+        }
+    }
+
     fn find_tabbed_containers_recursive(
         node: &Option<LayoutNode>,
         containers: &mut Vec<(Rectangle<i32, Logical>, Vec<(WindowId, bool)>)>,
@@ -1540,6 +2738,27 @@ impl LayoutTree {
         }
     }
 
+    fn rotate_container_recursive(
+        node: &mut LayoutNode,
+        window_id: WindowId,
+        clockwise: bool,
+    ) -> bool {
+        let LayoutNode::Container { children, .. } = node else {
+            return false;
+        };
+
+        let holds_window = children
+            .iter()
+            .any(|child| matches!(child, LayoutNode::Window { id, .. } if *id == window_id));
+        if holds_window {
+            return children.rotate(clockwise);
+        }
+
+        children
+            .iter_mut()
+            .any(|child| Self::rotate_container_recursive(child, window_id, clockwise))
+    }
+
     fn collect_windows_ordered(node: &Option<LayoutNode>, windows: &mut Vec<WindowId>) {
         if let Some(node) = node {
             match node {
@@ -1586,4 +2805,82 @@ impl LayoutTree {
             None => false,
         }
     }
+
+    fn split_layout(direction: SplitDirection) -> ContainerLayout {
+        match direction {
+            SplitDirection::Horizontal => ContainerLayout::Horizontal,
+            SplitDirection::Vertical => ContainerLayout::Vertical,
+        }
+    }
+
+    fn save_node(
+        node: &LayoutNode,
+        criteria: &impl Fn(WindowId) -> Option<Criteria>,
+    ) -> Option<SavedLayoutNode> {
+        match node {
+            LayoutNode::Window { id, .. } => {
+                criteria(*id).map(|swallows| SavedLayoutNode::Window { swallows })
+            }
+            LayoutNode::Container {
+                layout, children, ..
+            } => {
+                let children: Vec<_> = children
+                    .iter()
+                    .filter_map(|child| Self::save_node(child, criteria))
+                    .collect();
+                (!children.is_empty()).then(|| SavedLayoutNode::Container {
+                    layout: *layout,
+                    children,
+                })
+            }
+        }
+    }
+
+    fn build_node(
+        saved: &SavedLayoutNode,
+        placeholders: &mut HashMap<WindowId, Criteria>,
+    ) -> Option<LayoutNode> {
+        match saved {
+            SavedLayoutNode::Window { swallows } => {
+                // Criteria without any property would take in every window
+                if swallows.is_empty() {
+                    return None;
+                }
+                let id = WindowId::next();
+                placeholders.insert(id, swallows.clone());
+                Some(LayoutNode::Window {
+                    id,
+                    geometry: Rectangle::default(),
+                })
+            }
+            SavedLayoutNode::Container { layout, children } => {
+                let children: Vec<_> = children
+                    .iter()
+                    .filter_map(|child| Self::build_node(child, placeholders))
+                    .collect();
+                Some(LayoutNode::Container {
+                    id: ContainerId::next(),
+                    layout: *layout,
+                    children: SafeChildren::from_vec(children, 0)?,
+                    geometry: Rectangle::default(),
+                })
+            }
+        }
+    }
+
+    fn replace_window_id(node: &mut LayoutNode, old: WindowId, new: WindowId) -> bool {
+        match node {
+            LayoutNode::Window { id, .. } => {
+                if *id == old {
+                    *id = new;
+                    true
+                } else {
+                    false
+                }
+            }
+            LayoutNode::Container { children, .. } => children
+                .iter_mut()
+                .any(|child| Self::replace_window_id(child, old, new)),
+        }
+    }
 }
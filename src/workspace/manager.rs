@@ -1,6 +1,7 @@
 //! Workspace manager that owns all workspaces
 
 use super::{Workspace, WorkspaceId};
+use crate::config::NewWindowPosition;
 use crate::virtual_output::VirtualOutputId;
 use crate::window::WindowId;
 use smithay::utils::{Logical, Rectangle};
@@ -10,14 +11,135 @@ use smithay::utils::{Logical, Rectangle};
 pub struct WorkspaceManager {
     /// All workspaces (0-9 by default)
     workspaces: Vec<Workspace>,
+    /// Every workspace id once, in the order bars show them and next/prev
+    /// walk them
+    order: Vec<WorkspaceId>,
+    /// Workspaces that exist even when empty and hidden
+    persistent: Vec<WorkspaceId>,
+    /// Output each workspace is assigned to by name, from the config
+    output_assignments: Vec<(WorkspaceId, String)>,
+    /// Where new tiled windows go, from the config
+    new_window_position: NewWindowPosition,
 }
 
 impl WorkspaceManager {
     /// Create a new workspace manager with 10 workspaces (0-9)
     pub fn new(gap: i32) -> Self {
         let workspaces = (0..10).map(|i| Workspace::new(i, gap)).collect();
+        let order = (0..10).map(WorkspaceId::new).collect();
 
-        Self { workspaces }
+        Self {
+            workspaces,
+            order,
+            persistent: Vec::new(),
+            output_assignments: Vec::new(),
+            new_window_position: NewWindowPosition::default(),
+        }
+    }
+
+    /// Set where new tiled windows go on every workspace
+    pub fn set_new_window_position(&mut self, position: NewWindowPosition) {
+        self.new_window_position = position;
+        for ws in &mut self.workspaces {
+            ws.new_window_position = position;
+        }
+    }
+
+    /// Set which output, by name, each workspace belongs on
+    pub fn set_output_assignments(
+        &mut self,
+        assignments: impl IntoIterator<Item = (WorkspaceId, String)>,
+    ) {
+        self.output_assignments = assignments.into_iter().collect();
+    }
+
+    /// Name of the output a workspace is assigned to, if any
+    pub fn assigned_output(&self, id: WorkspaceId) -> Option<&str> {
+        self.output_assignments
+            .iter()
+            .find(|(workspace, _)| *workspace == id)
+            .map(|(_, output)| output.as_str())
+    }
+
+    /// Set which workspaces persist while empty and hidden
+    pub fn set_persistent(&mut self, persistent: impl IntoIterator<Item = WorkspaceId>) {
+        self.persistent = persistent.into_iter().collect();
+    }
+
+    /// Whether a workspace persists while empty and hidden
+    pub fn is_persistent(&self, id: WorkspaceId) -> bool {
+        self.persistent.contains(&id)
+    }
+
+    /// Whether a workspace exists, in the i3 sense: it is visible, holds
+    /// windows or layout placeholders, or is persistent. Bars only list
+    /// existing workspaces.
+    pub fn exists(&self, id: WorkspaceId) -> bool {
+        self.get(id).is_some_and(|ws| {
+            ws.is_visible()
+                || !ws.is_empty()
+                || ws.layout.root().is_some()
+                || self.is_persistent(id)
+        })
+    }
+
+    /// Reset every workspace that no longer exists, forgetting the output
+    /// it was last on and its split direction, like i3 destroying it
+    fn discard_unused(&mut self) {
+        let unused: Vec<WorkspaceId> = self
+            .workspaces
+            .iter()
+            .map(|ws| ws.id)
+            .filter(|&id| !self.exists(id))
+            .collect();
+        for ws in &mut self.workspaces {
+            // Workspaces never shown anywhere are still as new
+            if unused.contains(&ws.id) && ws.associated_output().is_some() {
+                tracing::debug!("Discarding empty workspace {}", ws.id);
+                *ws = Workspace::new(ws.id.get(), ws.layout.gap());
+                ws.new_window_position = self.new_window_position;
+            }
+        }
+    }
+
+    /// Set the workspace order. Unknown and repeated ids are dropped and
+    /// workspaces left out follow in numeric order.
+    pub fn set_order(&mut self, order: impl IntoIterator<Item = WorkspaceId>) {
+        let mut ordered: Vec<WorkspaceId> = Vec::with_capacity(self.workspaces.len());
+        for id in order {
+            if self.get(id).is_some() && !ordered.contains(&id) {
+                ordered.push(id);
+            }
+        }
+        let mut rest: Vec<WorkspaceId> = self
+            .workspaces
+            .iter()
+            .map(|ws| ws.id)
+            .filter(|id| !ordered.contains(id))
+            .collect();
+        rest.sort_by_key(|id| id.get());
+        ordered.extend(rest);
+        self.order = ordered;
+    }
+
+    /// Workspace ids in display order
+    pub fn order(&self) -> &[WorkspaceId] {
+        &self.order
+    }
+
+    /// All workspaces in display order
+    pub fn ordered_workspaces(&self) -> impl Iterator<Item = &Workspace> {
+        self.order.iter().filter_map(|&id| self.get(id))
+    }
+
+    /// The workspace after `id` in display order, wrapping around
+    pub fn next_in_order(&self, id: WorkspaceId) -> Option<WorkspaceId> {
+        self.step_in_order(id, 1, None)
+    }
+
+    /// The workspace before `id` in display order, wrapping around
+    pub fn previous_in_order(&self, id: WorkspaceId) -> Option<WorkspaceId> {
+        self.step_in_order(id, -1, None)
     }
 
     /// Get a workspace by ID
@@ -35,6 +157,41 @@ impl WorkspaceManager {
         &self.workspaces
     }
 
+    /// The workspace after `id` among those associated with `output`
+    pub fn next_on_output(&self, id: WorkspaceId, output: VirtualOutputId) -> Option<WorkspaceId> {
+        self.step_in_order(id, 1, Some(output))
+    }
+
+    /// The workspace before `id` among those associated with `output`
+    pub fn previous_on_output(
+        &self,
+        id: WorkspaceId,
+        output: VirtualOutputId,
+    ) -> Option<WorkspaceId> {
+        self.step_in_order(id, -1, Some(output))
+    }
+
+    /// Move `step` places from `id` in display order, wrapping around,
+    /// optionally counting only the workspaces associated with `output`
+    fn step_in_order(
+        &self,
+        id: WorkspaceId,
+        step: isize,
+        output: Option<VirtualOutputId>,
+    ) -> Option<WorkspaceId> {
+        let candidates: Vec<WorkspaceId> = self
+            .order
+            .iter()
+            .copied()
+            .filter(|&ws| {
+                output.is_none_or(|output| self.workspace_association(ws) == Some(output))
+            })
+            .collect();
+        let position = candidates.iter().position(|&ws| ws == id)? as isize;
+        let len = candidates.len() as isize;
+        Some(candidates[(position + step).rem_euclid(len) as usize])
+    }
+
     /// Get the workspace currently on a virtual output
     pub fn workspace_on_output(&self, output_id: VirtualOutputId) -> Option<WorkspaceId> {
         self.workspaces
@@ -84,6 +241,9 @@ impl WorkspaceManager {
             workspace.show_on_output(output_id, output_area);
         }
 
+        // The workspace switched away from goes away if it was left empty
+        self.discard_unused();
+
         Ok(())
     }
 
@@ -9,9 +9,11 @@ mod manager;
 pub use layout::LayoutTree;
 pub use manager::WorkspaceManager;
 
+use crate::config::{HideEdgeBorders, NewWindowPosition, SmartBorders};
 use crate::shell::WindowElement;
 use crate::virtual_output::VirtualOutputId;
-use crate::window::WindowId;
+use crate::window::{ContainerId, WindowId};
+use layout::DetachedContainer;
 use smithay::desktop::Space;
 use smithay::utils::{Logical, Rectangle};
 
@@ -65,6 +67,14 @@ impl std::fmt::Display for WorkspaceId {
     }
 }
 
+/// A split command waiting for the window that fills it
+#[derive(Debug, Clone, Copy)]
+struct PendingSplit {
+    /// Window that was focused when the split was made
+    target: WindowId,
+    direction: crate::workspace::layout::SplitDirection,
+}
+
 /// A workspace that manages windows in a tiled layout
 #[derive(Debug)]
 pub struct Workspace {
@@ -78,14 +88,30 @@ pub struct Workspace {
     pub layout: LayoutTree,
     /// Currently focused window
     pub focused_window: Option<WindowId>,
+    /// Container selected with `focus parent`, holding the focused window,
+    /// which layout commands act on until focus moves to a window again
+    pub focused_container: Option<ContainerId>,
+    /// Tiled window that last had focus, for `focus tiling`
+    last_focused_tiled: Option<WindowId>,
+    /// Floating window that last had focus, for `focus floating`
+    last_focused_floating: Option<WindowId>,
     /// Windows in this workspace (maintains order for focus cycling)
     pub windows: Vec<WindowId>,
+    /// Windows floating above the tiled layout (not part of the layout tree)
+    pub floating_windows: Vec<WindowId>,
     /// Currently fullscreen window (if any)
     pub fullscreen_window: Option<WindowId>,
     /// Current area of the workspace (updated when shown on a virtual output)
     pub area: Rectangle<i32, Logical>,
     /// Next split direction for new windows
     pub next_split: crate::workspace::layout::SplitDirection,
+    /// Split made by a split command that no window has filled yet
+    pending_split: Option<PendingSplit>,
+    /// The window that last filled a pending split, and that split, in case
+    /// the window turns out to float
+    filled_split: Option<(WindowId, PendingSplit)>,
+    /// Where new tiled windows go in the container they join
+    pub new_window_position: NewWindowPosition,
 }
 
 impl Workspace {
@@ -110,10 +136,17 @@ impl Workspace {
             location: WorkspaceLocation::Hidden { last_output: None },
             layout: LayoutTree::new(default_area, gap),
             focused_window: None,
+            focused_container: None,
+            last_focused_tiled: None,
+            last_focused_floating: None,
             windows: Vec::new(),
+            floating_windows: Vec::new(),
             fullscreen_window: None,
             area: default_area,
             next_split: crate::workspace::layout::SplitDirection::Horizontal,
+            pending_split: None,
+            filled_split: None,
+            new_window_position: NewWindowPosition::default(),
         }
     }
 
@@ -187,7 +220,7 @@ impl Workspace {
                 self.area
             );
             self.windows.push(window_id);
-            self.layout.add_window(window_id, self.next_split);
+            self.add_to_layout(window_id);
 
             // If this is the first window, focus it
             if self.focused_window.is_none() {
@@ -207,11 +240,15 @@ impl Workspace {
     pub fn remove_window(&mut self, window_id: WindowId) -> bool {
         if let Some(pos) = self.windows.iter().position(|&id| id == window_id) {
             self.windows.remove(pos);
+            self.floating_windows.retain(|&id| id != window_id);
             self.layout.remove_window(window_id);
 
             // Update focus if needed
             if self.focused_window == Some(window_id) {
-                self.focused_window = self.layout.find_next_focus();
+                self.focused_window = self
+                    .layout
+                    .find_next_focus()
+                    .or_else(|| self.floating_windows.last().copied());
             }
 
             // Clear fullscreen if it was this window
@@ -225,9 +262,186 @@ impl Workspace {
         }
     }
 
+    /// Put a tiled window in the place of a placeholder from `append_layout`
+    pub fn swallow_placeholder(&mut self, placeholder: WindowId, window_id: WindowId) -> bool {
+        if !self.layout.is_placeholder(placeholder) || self.is_floating(window_id) {
+            return false;
+        }
+
+        // A window already tiled here gives up its own spot
+        if self.windows.contains(&window_id) {
+            self.layout.remove_window(window_id);
+        } else {
+            self.windows.push(window_id);
+        }
+        self.layout.swallow(placeholder, window_id);
+
+        if self.focused_window.is_none() {
+            self.focused_window = Some(window_id);
+        }
+        true
+    }
+
+    /// Take a tiled container out of this workspace with the windows in it,
+    /// to be added to another one with `add_container`
+    pub fn take_container(&mut self, container: ContainerId) -> Option<DetachedContainer> {
+        let detached = self.layout.detach_container(container)?;
+        let windows = detached.windows();
+        self.windows.retain(|id| !windows.contains(id));
+        if self.focused_container == Some(container) {
+            self.focused_container = None;
+        }
+        if self.focused_window.is_some_and(|id| windows.contains(&id)) {
+            self.focused_window = self
+                .layout
+                .find_next_focus()
+                .or_else(|| self.floating_windows.last().copied());
+        }
+        if self
+            .fullscreen_window
+            .is_some_and(|id| windows.contains(&id))
+        {
+            self.fullscreen_window = None;
+        }
+        Some(detached)
+    }
+
+    /// Tile a container taken from another workspace here, beside what is
+    /// already tiled
+    pub fn add_container(&mut self, detached: DetachedContainer) {
+        let windows = detached.windows();
+        self.layout.attach(detached, self.next_split);
+        for &window_id in &windows {
+            if !self.windows.contains(&window_id) {
+                self.windows.push(window_id);
+            }
+        }
+        if self.focused_window.is_none() {
+            self.focused_window = windows.first().copied();
+        }
+    }
+
+    /// Add a window to this workspace as floating, outside the layout tree
+    pub fn add_floating_window(&mut self, window_id: WindowId) {
+        if !self.windows.contains(&window_id) {
+            self.windows.push(window_id);
+            self.floating_windows.push(window_id);
+
+            if self.focused_window.is_none() {
+                self.focused_window = Some(window_id);
+            }
+        }
+    }
+
+    /// Move a window between the layout tree and the floating layer
+    pub fn set_window_floating(&mut self, window_id: WindowId, floating: bool) -> bool {
+        if !self.windows.contains(&window_id) || self.is_floating(window_id) == floating {
+            return false;
+        }
+
+        if floating {
+            self.layout.remove_window(window_id);
+            self.floating_windows.push(window_id);
+            // A window that floats right away leaves its split to the next one
+            if let Some((_, split)) = self.filled_split.filter(|&(id, _)| id == window_id) {
+                self.pending_split = Some(split);
+                self.filled_split = None;
+            }
+        } else {
+            self.floating_windows.retain(|&id| id != window_id);
+            self.add_to_layout(window_id);
+        }
+        true
+    }
+
+    /// Add a window to the layout tree, placed next to the focused window
+    /// or at either end as configured. The first window after a split
+    /// command goes into that split, wherever focus went in the meantime.
+    fn add_to_layout(&mut self, window_id: WindowId) {
+        // Only the window that just filled a split may hand it back
+        self.filled_split = None;
+        if let Some(split) = self.pending_split.take() {
+            if split.target != window_id
+                && self
+                    .layout
+                    .insert_beside(window_id, split.target, split.direction)
+            {
+                self.filled_split = Some((window_id, split));
+                return;
+            }
+        }
+
+        let focused = self.focused_window.filter(|&id| id != window_id);
+        self.layout.insert_window(
+            window_id,
+            self.next_split,
+            self.new_window_position,
+            focused,
+        );
+    }
+
+    /// Put a window back after fullscreen
+    ///
+    /// A tiled window that is still in the tree becomes the active child of
+    /// its tabbed or stacked container again, even if windows were added to it
+    /// in the meantime. If its spot is gone, it is added to the layout anew.
+    pub fn restore_after_fullscreen(&mut self, window_id: WindowId, floating: bool) {
+        if !self.windows.contains(&window_id) {
+            self.windows.push(window_id);
+        }
+
+        let in_tree = self.layout.get_windows().contains(&window_id);
+        if floating {
+            if in_tree {
+                self.layout.remove_window(window_id);
+            }
+            if !self.floating_windows.contains(&window_id) {
+                self.floating_windows.push(window_id);
+            }
+        } else {
+            self.floating_windows.retain(|&id| id != window_id);
+            if in_tree {
+                self.layout.update_active_child_for_window(window_id);
+            } else {
+                self.add_to_layout(window_id);
+            }
+        }
+        self.focused_window = Some(window_id);
+    }
+
+    /// Check if a window is floating in this workspace
+    pub fn is_floating(&self, window_id: WindowId) -> bool {
+        self.floating_windows.contains(&window_id)
+    }
+
     /// Set the focused window
     pub fn set_focus(&mut self, window_id: Option<WindowId>) {
         self.focused_window = window_id;
+        self.focused_container = None;
+        // Once focus moves on, the split stays with the window that filled it
+        self.filled_split = self.filled_split.filter(|&(id, _)| Some(id) == window_id);
+        if let Some(id) = window_id.filter(|id| self.windows.contains(id)) {
+            if self.is_floating(id) {
+                self.last_focused_floating = Some(id);
+            } else {
+                self.last_focused_tiled = Some(id);
+            }
+        }
+    }
+
+    /// Window `focus floating` or `focus tiling` goes to: the floating or
+    /// tiled window that last had focus, or else the topmost floating
+    /// window or the one the layout would focus next
+    pub fn focus_mode_target(&self, floating: bool) -> Option<WindowId> {
+        if floating {
+            self.last_focused_floating
+                .filter(|&id| self.is_floating(id))
+                .or_else(|| self.floating_windows.last().copied())
+        } else {
+            self.last_focused_tiled
+                .filter(|&id| self.windows.contains(&id) && !self.is_floating(id))
+                .or_else(|| self.layout.find_next_focus())
+        }
     }
 
     /// Set fullscreen window
@@ -411,8 +625,117 @@ impl Workspace {
         }
     }
 
-    /// Set the next split direction for new windows
+    /// Make two tiled windows of this workspace trade places. Returns
+    /// whether the layout changed.
+    pub fn swap_windows(&mut self, first: WindowId, second: WindowId) -> bool {
+        let tiled = |id| self.windows.contains(&id) && !self.is_floating(id);
+        tiled(first) && tiled(second) && self.layout.swap_windows(first, second)
+    }
+
+    /// Grow the focused tiled window by `delta` pixels along `direction`'s
+    /// axis, or shrink it for a negative `delta`, taking the space from its
+    /// neighbour in the split. Returns whether the layout changed.
+    pub fn resize_focused(&mut self, direction: crate::config::Direction, delta: i32) -> bool {
+        match self.focused_window {
+            Some(window_id) if !self.is_floating(window_id) => {
+                self.layout.resize_window(window_id, direction, delta)
+            }
+            _ => false,
+        }
+    }
+
+    /// Set the next split direction for new windows, and split the focused
+    /// window for the next one
     pub fn set_next_split(&mut self, direction: crate::workspace::layout::SplitDirection) {
         self.next_split = direction;
+        self.pending_split = self
+            .focused_window
+            .filter(|&id| !self.is_floating(id))
+            .map(|target| PendingSplit { target, direction });
     }
+
+    /// Which borders of a window should be drawn under `hide_edge_borders`
+    /// and `smart_borders`
+    ///
+    /// Adjacency is taken from the current layout geometry against the
+    /// workspace area, so it follows every relayout. Inner gaps only space
+    /// windows from each other and never make an edge touch the workspace
+    /// boundary. Floating windows always keep their borders and a fullscreen
+    /// window has none.
+    pub fn border_edges(
+        &self,
+        window_id: WindowId,
+        mode: HideEdgeBorders,
+        smart: SmartBorders,
+    ) -> BorderEdges {
+        if self.fullscreen_window == Some(window_id) {
+            return BorderEdges::NONE;
+        }
+        if self.floating_windows.contains(&window_id) {
+            return BorderEdges::ALL;
+        }
+
+        let geometries = self.layout.get_visible_geometries();
+        let Some(geometry) = geometries
+            .iter()
+            .find(|(id, _)| *id == window_id)
+            .map(|(_, geometry)| *geometry)
+        else {
+            return BorderEdges::ALL;
+        };
+
+        let alone = geometries.len() == 1;
+        let smart_hidden = match smart {
+            SmartBorders::Off => false,
+            SmartBorders::On => alone,
+            SmartBorders::NoGaps => self.layout.effective_gap() == 0,
+        };
+        if smart_hidden {
+            return BorderEdges::NONE;
+        }
+
+        let area = self.area;
+        let left = geometry.loc.x <= area.loc.x;
+        let top = geometry.loc.y <= area.loc.y;
+        let right = geometry.loc.x + geometry.size.w >= area.loc.x + area.size.w;
+        let bottom = geometry.loc.y + geometry.size.h >= area.loc.y + area.size.h;
+
+        let (hide_vertical, hide_horizontal) = match mode {
+            HideEdgeBorders::None => (false, false),
+            HideEdgeBorders::Vertical => (true, false),
+            HideEdgeBorders::Horizontal => (false, true),
+            HideEdgeBorders::Both => (true, true),
+            HideEdgeBorders::Smart => (alone, alone),
+        };
+        BorderEdges {
+            left: !(hide_vertical && left),
+            right: !(hide_vertical && right),
+            top: !(hide_horizontal && top),
+            bottom: !(hide_horizontal && bottom),
+        }
+    }
+}
+
+/// Borders of a window that are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderEdges {
+    pub top: bool,
+    pub bottom: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl BorderEdges {
+    pub const ALL: Self = Self {
+        top: true,
+        bottom: true,
+        left: true,
+        right: true,
+    };
+    pub const NONE: Self = Self {
+        top: false,
+        bottom: false,
+        left: false,
+        right: false,
+    };
 }
@@ -356,7 +356,11 @@ pub fn run_x11() -> Result<(), Box<dyn std::error::Error>> {
             #[cfg(feature = "debug")]
             fps_element.update_fps(fps);
 
-            let show_window_preview = state.show_window_preview;
+            let overview = state.overview_frame(&output);
+            let debug_overlay = state.debug_overlay_frame(&output);
+            let status_bars = state.status_bar_frames(&output);
+            let selection = state.selection_frame(&output);
+            let closing = state.closing_window_frames(&output);
 
             // Collect tab bar data before the render closure
             let tab_bar_data = crate::render::collect_tab_bar_data(&state, &output);
@@ -370,7 +374,9 @@ pub fn run_x11() -> Result<(), Box<dyn std::error::Error>> {
             if reset {
                 state.input_manager.cursor_status = CursorImageStatus::default_named();
             }
-            let cursor_visible = !matches!(state.cursor_status(), CursorImageStatus::Surface(_));
+            let cursor_hidden = state.cursor_hidden();
+            let cursor_visible =
+                !cursor_hidden && !matches!(state.cursor_status(), CursorImageStatus::Surface(_));
 
             let scale = Scale::from(output.current_scale().fractional_scale());
             let cursor_hotspot =
@@ -434,16 +440,18 @@ pub fn run_x11() -> Result<(), Box<dyn std::error::Error>> {
                     pointer_element.set_buffer(buffer);
                 }
             }
-            elements.extend(
-                pointer_element.render_elements(
-                    &mut backend_data.renderer,
-                    (cursor_pos - cursor_hotspot.to_f64())
-                        .to_physical(scale)
-                        .to_i32_round(),
-                    scale,
-                    1.0,
-                ),
-            );
+            if !cursor_hidden {
+                elements.extend(
+                    pointer_element.render_elements(
+                        &mut backend_data.renderer,
+                        (cursor_pos - cursor_hotspot.to_f64())
+                            .to_physical(scale)
+                            .to_i32_round(),
+                        scale,
+                        1.0,
+                    ),
+                );
+            }
 
             // draw the dnd icon if any
             if let Some((surface, offset)) = dnd_icon_data {
@@ -464,6 +472,31 @@ pub fn run_x11() -> Result<(), Box<dyn std::error::Error>> {
             #[cfg(feature = "debug")]
             elements.push(CustomRenderElements::Fps(fps_element.clone()));
 
+            if let Some(frame) = debug_overlay.as_ref() {
+                elements.extend(crate::render::debug_overlay_element(
+                    &mut backend_data.renderer,
+                    frame,
+                    scale,
+                ));
+            }
+
+            elements.extend(crate::render::status_bar_elements(
+                &mut backend_data.renderer,
+                &status_bars,
+                scale,
+            ));
+
+            if let Some(frame) = selection.as_ref() {
+                elements.extend(crate::render::selection_elements(frame, scale));
+            }
+
+            elements.extend(crate::render::closing_window_elements(
+                &mut backend_data.renderer,
+                &closing,
+                scale,
+            ));
+
+            let render_start = std::time::Instant::now();
             let render_res = render_output(
                 &output,
                 &state.window_manager.space,
@@ -472,7 +505,7 @@ pub fn run_x11() -> Result<(), Box<dyn std::error::Error>> {
                 &mut fb,
                 &mut backend_data.damage_tracker,
                 age.into(),
-                show_window_preview,
+                overview.as_ref(),
                 &tab_bar_data,
                 &mut state.tab_text_cache,
             );
@@ -489,9 +522,8 @@ pub fn run_x11() -> Result<(), Box<dyn std::error::Error>> {
                     };
 
                     let states = render_output_result.states;
-                    #[cfg(feature = "debug")]
                     let rendered = render_output_result.damage.is_some();
-                    if render_output_result.damage.is_some() {
+                    if rendered {
                         let mut output_presentation_feedback =
                             take_presentation_feedback(&output, state.space(), &states);
                         output_presentation_feedback.presented(
@@ -533,6 +565,9 @@ pub fn run_x11() -> Result<(), Box<dyn std::error::Error>> {
                     }
 
                     state.backend_data.render = !submitted;
+                    if rendered {
+                        state.record_frame_time(render_start.elapsed());
+                    }
 
                     // Send frame events so that client start drawing their next frame
                     state.post_repaint(&output, frame_target, None, &states);
@@ -570,6 +605,9 @@ pub fn run_x11() -> Result<(), Box<dyn std::error::Error>> {
         } else {
             state.space_mut().refresh();
             state.popups_mut().cleanup();
+            state.refresh_output_management();
+            #[cfg(feature = "xwayland")]
+            state.refresh_xwayland_scale();
             display_handle.flush_clients().unwrap();
         }
     }
@@ -0,0 +1,81 @@
+use smithay::utils::{Rectangle, Size};
+use stilch::backend::ascii::{AsciiBackend, AsciiLayout, AsciiNode, AsciiWindow};
+use stilch::window::WindowId;
+use stilch::workspace::layout::{ContainerLayout, LayoutTree, SplitDirection};
+
+fn snapshot(layout: &LayoutTree, focused: Option<WindowId>) -> String {
+    let mut ascii = AsciiBackend::new(80, 24, Size::from((800, 600)));
+    for (id, bounds) in layout.get_visible_geometries() {
+        ascii.update_window(AsciiWindow {
+            id,
+            bounds,
+            focused: Some(id) == focused,
+            floating: false,
+            fullscreen: false,
+            urgent: false,
+            tab_info: None,
+        });
+    }
+    let layouts = [AsciiLayout {
+        workspace: "1".to_string(),
+        gap: layout.gap(),
+        root: AsciiNode::from_layout(layout, focused).expect("layout has windows"),
+    }];
+    ascii.render_with_layouts(&layouts)
+}
+
+#[test]
+fn test_legend_lists_containers_and_focus() {
+    let mut layout = LayoutTree::new(Rectangle::from_size((800, 600).into()), 0);
+    let win1 = WindowId::new(1);
+    let win2 = WindowId::new(2);
+    layout.add_window(win1, SplitDirection::Horizontal);
+    layout.add_window(win2, SplitDirection::Horizontal);
+
+    let output = snapshot(&layout, Some(win2));
+    let (grid, legend) = output.split_once("Layout:\n").expect("legend present");
+
+    // Marker on the top edge of the split container
+    assert!(grid.lines().next().unwrap().starts_with("┌H"), "{output}");
+    assert_eq!(
+        legend,
+        "workspace 1: gap 0\nH [0,0 79x23]\n  #1\n  #2 [F]\n"
+    );
+}
+
+#[test]
+fn test_legend_marks_tabbed_container() {
+    let mut layout = LayoutTree::new(Rectangle::from_size((800, 600).into()), 0);
+    let win1 = WindowId::new(1);
+    let win2 = WindowId::new(2);
+    layout.add_window(win1, SplitDirection::Horizontal);
+    layout.add_window(win2, SplitDirection::Horizontal);
+    layout.set_container_layout(win1, ContainerLayout::Tabbed);
+
+    let output = snapshot(&layout, None);
+    let legend = output.split_once("Layout:\n").unwrap().1;
+    assert!(legend.contains("\nT ["), "{output}");
+    assert!(!legend.contains("[F]"), "No focus without a focused window");
+}
+
+#[test]
+fn test_gaps_leave_blank_cells() {
+    let borders = |gap: i32| {
+        let mut layout = LayoutTree::new(Rectangle::from_size((800, 600).into()), gap);
+        layout.add_window(WindowId::new(1), SplitDirection::Horizontal);
+        layout.add_window(WindowId::new(2), SplitDirection::Horizontal);
+        let output = snapshot(&layout, None);
+        let middle_row = output.lines().nth(12).unwrap().to_string();
+        middle_row
+            .chars()
+            .enumerate()
+            .filter(|(_, ch)| *ch == '│')
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>()
+    };
+
+    // Without a gap the two windows share their border
+    assert_eq!(borders(0), vec![0, 40, 79]);
+    // A 50px gap is five cells at 10px per cell, blank between the windows
+    assert_eq!(borders(50), vec![0, 37, 42, 79]);
+}
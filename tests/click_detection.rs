@@ -0,0 +1,48 @@
+// Tests for counting button presses into double and triple clicks
+
+use stilch::input::click::{Click, ClickTracker};
+
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const THRESHOLD: u32 = 400;
+
+#[test]
+fn test_quick_presses_count_up() {
+    let mut clicks = ClickTracker::default();
+    let at = (100.0, 100.0).into();
+    assert_eq!(clicks.press(BTN_LEFT, 1000, at, THRESHOLD), Click::Single);
+    assert_eq!(clicks.press(BTN_LEFT, 1200, at, THRESHOLD), Click::Double);
+    assert_eq!(clicks.press(BTN_LEFT, 1500, at, THRESHOLD), Click::Triple);
+    // A fourth press starts over
+    assert_eq!(clicks.press(BTN_LEFT, 1600, at, THRESHOLD), Click::Single);
+}
+
+#[test]
+fn test_slow_press_is_single() {
+    let mut clicks = ClickTracker::default();
+    let at = (100.0, 100.0).into();
+    assert_eq!(clicks.press(BTN_LEFT, 1000, at, THRESHOLD), Click::Single);
+    assert_eq!(clicks.press(BTN_LEFT, 1401, at, THRESHOLD), Click::Single);
+    assert_eq!(clicks.press(BTN_LEFT, 1801, at, THRESHOLD), Click::Double);
+}
+
+#[test]
+fn test_other_button_or_place_is_single() {
+    let mut clicks = ClickTracker::default();
+    assert_eq!(
+        clicks.press(BTN_LEFT, 1000, (100.0, 100.0).into(), THRESHOLD),
+        Click::Single
+    );
+    assert_eq!(
+        clicks.press(BTN_RIGHT, 1100, (100.0, 100.0).into(), THRESHOLD),
+        Click::Single
+    );
+    assert_eq!(
+        clicks.press(BTN_RIGHT, 1200, (102.0, 99.0).into(), THRESHOLD),
+        Click::Double
+    );
+    assert_eq!(
+        clicks.press(BTN_RIGHT, 1300, (150.0, 99.0).into(), THRESHOLD),
+        Click::Single
+    );
+}
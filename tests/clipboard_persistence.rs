@@ -0,0 +1,89 @@
+//! Test that a selection outlives its client only when persistence is on
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Set the primary selection from a window, kill it, then open a second
+/// window and return what it pasted within a few seconds
+fn paste_after_source_exits(
+    name: &str,
+    config: &str,
+    text: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new(name);
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--ascii-size", "80x24", "--config", config])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    let mut source = env.start_window_with_args("Source", "blue", &["--set-primary", text])?;
+    client.wait_for_window_count(1, "after starting source window")?;
+    thread::sleep(Duration::from_millis(200));
+    source.kill()?;
+    source.wait()?;
+    client.wait_for_window_count(0, "after killing source window")?;
+
+    let mut sink = env.start_window_with_args("Sink", "green", &["--paste-primary"])?;
+    client.wait_for_window_count(1, "after starting sink window")?;
+
+    let stdout = sink.stdout.take().ok_or("Sink has no stdout")?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut pasted = Vec::new();
+    while let Ok(line) = rx.recv_timeout(Duration::from_secs(2)) {
+        let done = line.starts_with("PRIMARY_SELECTION: ");
+        pasted.push(line);
+        if done {
+            break;
+        }
+    }
+    sink.kill()?;
+    Ok(pasted)
+}
+
+#[test]
+fn test_selection_outlives_its_client() -> Result<(), Box<dyn std::error::Error>> {
+    let text = "kept after exit";
+    let pasted = paste_after_source_exits(
+        "clipboard-persist",
+        "tests/test_configs/clipboard_persist.conf",
+        text,
+    )?;
+
+    let expected = format!("PRIMARY_SELECTION: {text}");
+    assert_eq!(
+        pasted.last(),
+        Some(&expected),
+        "Sink should paste the exited source's selection, got {pasted:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_selection_dies_with_its_client_by_default() -> Result<(), Box<dyn std::error::Error>> {
+    let pasted = paste_after_source_exits(
+        "clipboard-no-persist",
+        "tests/test_configs/no_gaps.conf",
+        "gone after exit",
+    )?;
+
+    assert!(
+        !pasted
+            .iter()
+            .any(|line| line.starts_with("PRIMARY_SELECTION: ")),
+        "Nothing should be pasted once the source exits, got {pasted:?}"
+    );
+    Ok(())
+}
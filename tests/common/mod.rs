@@ -4,9 +4,12 @@ use serde_json::Value;
 use std::fs;
 use std::io::{Read, Write};
 use std::os::unix::net::UnixStream;
-use std::process::{Child, Command};
+use std::process::{Child, Command, Stdio};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// How long the `wait_for_*` helpers wait for the compositor
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Test environment configuration
 pub struct TestEnv {
@@ -32,6 +35,7 @@ impl TestEnv {
         // Clean up old sockets (don't kill processes - let Drop handle that)
         let _ = fs::remove_file(&self.test_socket);
         let _ = fs::remove_file(format!("/tmp/stilch-ipc-{}.sock", self.test_name));
+        let _ = fs::remove_file(format!("/tmp/stilch-ipc-{}.i3.sock", self.test_name));
         let _ = fs::remove_file(format!("/run/user/1000/{}", self.wayland_display));
 
         Ok(())
@@ -141,6 +145,39 @@ impl TestEnv {
             .map_err(|e| e.into())
     }
 
+    /// Start a simple window with extra arguments, capturing its stdout
+    pub fn start_window_with_args(
+        &self,
+        title: &str,
+        color: &str,
+        extra_args: &[&str],
+    ) -> Result<Child, Box<dyn std::error::Error>> {
+        Command::new("target/debug/simple_window")
+            .arg(title)
+            .arg(color)
+            .args(extra_args)
+            .env("WAYLAND_DISPLAY", &self.wayland_display)
+            .env("XDG_RUNTIME_DIR", "/run/user/1000")
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.into())
+    }
+
+    /// Start another test client binary, capturing its stdout
+    pub fn start_client(
+        &self,
+        binary: &str,
+        args: &[&str],
+    ) -> Result<Child, Box<dyn std::error::Error>> {
+        Command::new(format!("target/debug/{binary}"))
+            .args(args)
+            .env("WAYLAND_DISPLAY", &self.wayland_display)
+            .env("XDG_RUNTIME_DIR", "/run/user/1000")
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.into())
+    }
+
     /// Get environment variables for running clients
     pub fn client_env(&self) -> Vec<(&str, &str)> {
         vec![
@@ -200,12 +237,67 @@ impl TestClient {
             .unwrap_or_default())
     }
 
+    /// Get a single window by ID
+    pub fn get_window(&self, id: u64) -> Result<Value, Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({"type": "GetWindow", "id": id}))?;
+        response
+            .get("window")
+            .cloned()
+            .ok_or_else(|| format!("Window {id} not found: {response}").into())
+    }
+
+    /// Wait for the window titled `title` to show up and return it
+    pub fn find_window(&self, title: &str) -> Result<Value, Box<dyn std::error::Error>> {
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        loop {
+            let response =
+                self.send_command(&serde_json::json!({"type": "FindWindow", "title": title}))?;
+            if let Some(window) = response.get("window") {
+                return Ok(window.clone());
+            }
+            if Instant::now() >= deadline {
+                return Err(format!("No window titled {title:?}").into());
+            }
+            self.sync()?;
+        }
+    }
+
     /// Get focused window ID
     pub fn get_focused_window(&self) -> Result<Option<u64>, Box<dyn std::error::Error>> {
         let response = self.send_command(&serde_json::json!({"type": "GetFocusedWindow"}))?;
         Ok(response.get("id").and_then(|id| id.as_u64()))
     }
 
+    /// Create a solid color window inside the compositor, gray without `color`.
+    /// A `title` lets the test find it again with `find_window`. Returns
+    /// the window's id once the client has mapped it.
+    pub fn create_window(
+        &self,
+        title: Option<&str>,
+        color: Option<&str>,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({
+            "type": "CreateWindow",
+            "width": 800,
+            "height": 600,
+            "color": color,
+            "title": title
+        }))?;
+
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .into());
+        }
+
+        response
+            .get("id")
+            .and_then(|id| id.as_u64())
+            .ok_or_else(|| format!("CreateWindow replied without an id: {response}").into())
+    }
+
     /// Focus a window by ID
     pub fn focus_window(&self, id: u64) -> Result<(), Box<dyn std::error::Error>> {
         let response = self.send_command(&serde_json::json!({
@@ -263,6 +355,36 @@ impl TestClient {
         Ok(())
     }
 
+    /// Drag with `button` ("left", "right" or "middle") from one point to
+    /// another, holding `modifiers` ("Super", "Alt", "Ctrl", "Shift")
+    pub fn drag(
+        &self,
+        button: &str,
+        from: (i32, i32),
+        to: (i32, i32),
+        modifiers: &[&str],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({
+            "type": "Drag",
+            "button": button,
+            "from_x": from.0,
+            "from_y": from.1,
+            "to_x": to.0,
+            "to_y": to.1,
+            "modifiers": modifiers
+        }))?;
+
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .into());
+        }
+
+        Ok(())
+    }
+
     /// Get ASCII snapshot
     pub fn get_ascii_snapshot(
         &self,
@@ -288,19 +410,18 @@ impl TestClient {
         expected: usize,
         context: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        for i in 0..50 {
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        loop {
             let windows = self.get_windows()?;
             if windows.len() == expected {
                 println!("✓ {expected} window(s) {context}");
                 return Ok(());
             }
-
-            if i == 49 {
+            if Instant::now() >= deadline {
                 return Err(format!("Failed to get {expected} windows {context}").into());
             }
-            thread::sleep(Duration::from_millis(100));
+            self.sync()?;
         }
-        Ok(())
     }
 
     /// Wait for focus on a specific window
@@ -309,22 +430,45 @@ impl TestClient {
         window_id: u64,
         context: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        for i in 0..50 {
-            if let Some(focused) = self.get_focused_window()? {
-                if focused == window_id {
-                    println!("✓ Window {window_id} focused {context}");
-                    return Ok(());
-                }
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        loop {
+            if self.get_focused_window()? == Some(window_id) {
+                println!("✓ Window {window_id} focused {context}");
+                return Ok(());
             }
-
-            if i == 49 {
+            if Instant::now() >= deadline {
                 return Err(format!("Failed to get focus on window {window_id} {context}").into());
             }
+            self.sync()?;
+        }
+    }
+
+    /// Return once the compositor has dispatched everything sent so far,
+    /// applied the layouts and sent a frame
+    ///
+    /// Clients started in other processes may still not have connected, so
+    /// waits loop on this instead of sleeping a fixed time.
+    pub fn sync(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({"type": "Sync"}))?;
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            // Compositors without Sync get the old polling interval
             thread::sleep(Duration::from_millis(100));
         }
         Ok(())
     }
 
+    /// Number of render passes the compositor ran so far
+    pub fn render_count(&self) -> Result<u64, Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({"type": "GetRenderCount"}))?;
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(format!("GetRenderCount failed: {response}").into());
+        }
+        response
+            .get("count")
+            .and_then(|count| count.as_u64())
+            .ok_or_else(|| format!("No render count in {response}").into())
+    }
+
     /// Get outputs from compositor
     pub fn get_outputs(&self) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
         let response = self.send_command(&serde_json::json!({"type": "GetOutputs"}))?;
@@ -345,6 +489,61 @@ impl TestClient {
             .unwrap_or_default())
     }
 
+    /// Get a workspace's layout scaled down to `width`x`height`
+    pub fn get_workspace_thumbnail(
+        &self,
+        workspace: usize,
+        width: i32,
+        height: i32,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({
+            "type": "GetWorkspaceThumbnail",
+            "workspace": workspace,
+            "width": width,
+            "height": height
+        }))?;
+
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .into());
+        }
+
+        Ok(response)
+    }
+
+    /// Get the effective gaps, border widths and visible workspace areas
+    pub fn get_layout_config(&self) -> Result<Value, Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({"type": "GetLayoutConfig"}))?;
+
+        if response.get("type").and_then(|t| t.as_str()) != Some("LayoutConfig") {
+            return Err(format!("Unexpected response to GetLayoutConfig: {response}").into());
+        }
+
+        Ok(response)
+    }
+
+    /// Run the compositor's state validator, returning the errors it found
+    pub fn get_consistency_errors(&self) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({"type": "GetConsistency"}))?;
+
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .into());
+        }
+
+        Ok(response
+            .get("errors")
+            .and_then(|e| e.as_array())
+            .cloned()
+            .unwrap_or_default())
+    }
+
     /// Move workspace to output in direction
     pub fn move_workspace_to_output(
         &self,
@@ -366,6 +565,93 @@ impl TestClient {
         Ok(())
     }
 
+    /// Move workspace to an output by name
+    pub fn move_workspace_to_named_output(
+        &self,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({
+            "type": "MoveWorkspaceToNamedOutput",
+            "name": name
+        }))?;
+
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .into());
+        }
+
+        Ok(())
+    }
+
+    /// Set a gap on every workspace: inner, outer, top, bottom, left or right
+    pub fn set_gaps(&self, scope: &str, amount: i32) -> Result<(), Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({
+            "type": "SetGaps",
+            "scope": scope,
+            "amount": amount
+        }))?;
+
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .into());
+        }
+
+        Ok(())
+    }
+
+    /// Plug in another output after startup
+    pub fn add_output(
+        &self,
+        name: &str,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({
+            "type": "AddOutput",
+            "name": name,
+            "x": x,
+            "y": y,
+            "width": width,
+            "height": height
+        }))?;
+
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .into());
+        }
+
+        Ok(())
+    }
+
+    /// Unplug an output after startup
+    pub fn remove_output(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let response = self.send_command(&serde_json::json!({
+            "type": "RemoveOutput",
+            "name": name
+        }))?;
+
+        if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+            return Err(response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .into());
+        }
+
+        Ok(())
+    }
+
     /// Switch to workspace by index
     pub fn switch_workspace(&self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
         let response = self.send_command(&serde_json::json!({
@@ -434,6 +720,62 @@ pub fn verify_window_geometry(
     Ok(())
 }
 
+/// Helper to verify window geometry as fractions of its workspace's usable area
+///
+/// `layout_config` is a `GetLayoutConfig` response. Fractions count each cell
+/// with the gap after it, so `(0.5, 0.0, 0.5, 1.0)` is the right half of a
+/// horizontal split whatever the gap, and `(0.0, 0.0, 1.0, 1.0)` the whole area.
+/// Allows a couple of pixels for the layout rounding down uneven splits.
+pub fn verify_window_geometry_relative(
+    window: &Value,
+    layout_config: &Value,
+    fraction_x: f64,
+    fraction_y: f64,
+    fraction_width: f64,
+    fraction_height: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const TOLERANCE: i32 = 2;
+
+    let workspace = window
+        .get("workspace")
+        .and_then(|w| w.as_u64())
+        .ok_or("Window has no workspace")?;
+    let area = layout_config
+        .get("workspaces")
+        .and_then(|w| w.as_array())
+        .and_then(|areas| {
+            areas
+                .iter()
+                .find(|area| area.get("workspace").and_then(|w| w.as_u64()) == Some(workspace))
+        })
+        .ok_or_else(|| format!("Workspace {workspace} is not visible"))?;
+    let field = |name: &str| area.get(name).and_then(|v| v.as_i64()).unwrap_or(0) as i32;
+    let gap = field("gap");
+
+    let expected_x = field("x") + (fraction_x * (field("width") + gap) as f64).round() as i32;
+    let expected_y = field("y") + (fraction_y * (field("height") + gap) as f64).round() as i32;
+    let expected_width = (fraction_width * (field("width") + gap) as f64).round() as i32 - gap;
+    let expected_height = (fraction_height * (field("height") + gap) as f64).round() as i32 - gap;
+
+    let x = window.get("x").and_then(|v| v.as_i64()).unwrap_or(-1) as i32;
+    let y = window.get("y").and_then(|v| v.as_i64()).unwrap_or(-1) as i32;
+    let width = window.get("width").and_then(|v| v.as_i64()).unwrap_or(-1) as i32;
+    let height = window.get("height").and_then(|v| v.as_i64()).unwrap_or(-1) as i32;
+
+    if (x - expected_x).abs() > TOLERANCE
+        || (y - expected_y).abs() > TOLERANCE
+        || (width - expected_width).abs() > TOLERANCE
+        || (height - expected_height).abs() > TOLERANCE
+    {
+        return Err(format!(
+            "Window geometry mismatch: got ({x}, {y}, {width}x{height}), expected about ({expected_x}, {expected_y}, {expected_width}x{expected_height}) in workspace {workspace} with gap {gap}"
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
 /// Helper to run a test with timeout
 pub fn run_with_timeout<F>(
     test_fn: F,
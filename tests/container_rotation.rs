@@ -0,0 +1,56 @@
+// Tests for rotating the windows of a container by one slot
+
+use smithay::utils::Rectangle;
+use stilch::window::WindowId;
+use stilch::workspace::layout::{LayoutTree, SplitDirection};
+
+fn three_windows() -> (LayoutTree, [WindowId; 3]) {
+    let workspace_rect = Rectangle::from_size((900, 600).into());
+    let mut layout = LayoutTree::new(workspace_rect, 0);
+    let windows = [WindowId::new(1), WindowId::new(2), WindowId::new(3)];
+    for window in windows {
+        layout.add_window(window, SplitDirection::Horizontal);
+    }
+    layout.calculate_geometries();
+    (layout, windows)
+}
+
+#[test]
+fn test_rotate_clockwise_shifts_every_window_one_slot() {
+    let (mut layout, [win1, win2, win3]) = three_windows();
+    let slots: Vec<_> = [win1, win2, win3]
+        .iter()
+        .map(|&id| layout.get_window_geometry(id).unwrap())
+        .collect();
+
+    assert!(layout.rotate_container(win2, true));
+    layout.calculate_geometries();
+
+    assert_eq!(layout.get_windows(), vec![win3, win1, win2]);
+    assert_eq!(layout.get_window_geometry(win1), Some(slots[1]));
+    assert_eq!(layout.get_window_geometry(win2), Some(slots[2]));
+    assert_eq!(layout.get_window_geometry(win3), Some(slots[0]));
+}
+
+#[test]
+fn test_rotate_counterclockwise_undoes_clockwise() {
+    let (mut layout, [win1, win2, win3]) = three_windows();
+
+    assert!(layout.rotate_container(win1, false));
+    assert_eq!(layout.get_windows(), vec![win2, win3, win1]);
+
+    assert!(layout.rotate_container(win1, true));
+    assert_eq!(layout.get_windows(), vec![win1, win2, win3]);
+}
+
+#[test]
+fn test_rotate_single_window_does_nothing() {
+    let workspace_rect = Rectangle::from_size((900, 600).into());
+    let mut layout = LayoutTree::new(workspace_rect, 0);
+    let win1 = WindowId::new(1);
+    layout.add_window(win1, SplitDirection::Horizontal);
+
+    assert!(!layout.rotate_container(win1, true));
+    assert!(!layout.rotate_container(WindowId::new(2), true));
+    assert_eq!(layout.get_windows(), vec![win1]);
+}
@@ -0,0 +1,91 @@
+//! Test that dialogs and fixed-size windows open floating
+
+mod common;
+
+use common::{TestClient, TestEnv};
+
+#[test]
+fn test_dialog_with_parent_floats_over_it() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("dialog-floating-parent");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut window = env.start_window_with_args("Editor", "blue", &["--dialog", "Save As"])?;
+    client.wait_for_window_count(2, "after the dialog opened")?;
+    client.sync()?;
+
+    let parent = client.find_window("Editor")?;
+    let dialog = client.find_window("Save As")?;
+    assert_eq!(parent["floating"].as_bool(), Some(false));
+    assert_eq!(dialog["floating"].as_bool(), Some(true));
+
+    // Centered over its parent
+    let center = |w: &serde_json::Value, pos: &str, size: &str| {
+        w[pos].as_i64().unwrap() + w[size].as_i64().unwrap() / 2
+    };
+    assert!((center(&dialog, "x", "width") - center(&parent, "x", "width")).abs() <= 1);
+    assert!((center(&dialog, "y", "height") - center(&parent, "y", "height")).abs() <= 1);
+
+    window.kill()?;
+    Ok(())
+}
+
+#[test]
+fn test_fixed_size_window_floats() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("dialog-floating-fixed-size");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut tiled = env.start_window("Tiled", Some("red"))?;
+    client.wait_for_window_count(1, "after starting the tiled window")?;
+    let mut fixed = env.start_window_with_args("Fixed", "green", &["--fixed-size"])?;
+    client.wait_for_window_count(2, "after starting the fixed-size window")?;
+    client.sync()?;
+
+    let fixed_info = client.find_window("Fixed")?;
+    assert_eq!(fixed_info["floating"].as_bool(), Some(true));
+    assert_eq!(fixed_info["width"].as_i64(), Some(400));
+    assert_eq!(fixed_info["height"].as_i64(), Some(300));
+
+    // The tiled window keeps the whole output
+    let tiled_info = client.find_window("Tiled")?;
+    assert_eq!(tiled_info["floating"].as_bool(), Some(false));
+    assert_eq!(tiled_info["width"].as_i64(), Some(3840));
+
+    fixed.kill()?;
+    tiled.kill()?;
+    Ok(())
+}
+
+#[test]
+fn test_focusing_the_parent_keeps_the_dialog_above() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("dialog-floating-stacking");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut window = env.start_window_with_args("Editor", "blue", &["--dialog", "Save As"])?;
+    client.wait_for_window_count(2, "after the dialog opened")?;
+    client.sync()?;
+
+    let parent_id = client.find_window("Editor")?["id"]
+        .as_u64()
+        .ok_or("window has no id")?;
+    client.focus_window(parent_id)?;
+    client.sync()?;
+
+    let parent = client.find_window("Editor")?;
+    let dialog = client.find_window("Save As")?;
+    assert_eq!(parent["focused"].as_bool(), Some(true));
+    let parent_stacking = parent["stacking"].as_u64().ok_or("parent is not mapped")?;
+    let dialog_stacking = dialog["stacking"].as_u64().ok_or("dialog is not mapped")?;
+    assert!(
+        dialog_stacking > parent_stacking,
+        "Dialog should stay above its parent: {dialog_stacking} <= {parent_stacking}"
+    );
+
+    window.kill()?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! Test dragging tiled windows with the floating modifier held
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+
+/// Start the compositor with two tiled windows side by side, Left and Right
+fn start_with_two_windows(name: &str) -> Result<(TestEnv, TestClient), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new(name);
+    env.cleanup()?;
+    // The config sets $mod to Mod4, which floating_modifier defaults to
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.create_window(Some("Left"), Some("red"))?;
+    client.wait_for_window_count(1, "after creating the left window")?;
+    client.create_window(Some("Right"), Some("blue"))?;
+    client.wait_for_window_count(2, "after creating the right window")?;
+    client.sync()?;
+    Ok((env, client))
+}
+
+fn field(window: &Value, name: &str) -> i32 {
+    window[name].as_i64().unwrap_or(-1) as i32
+}
+
+fn center(window: &Value) -> (i32, i32) {
+    (
+        field(window, "x") + field(window, "width") / 2,
+        field(window, "y") + field(window, "height") / 2,
+    )
+}
+
+#[test]
+fn test_modifier_drag_swaps_tiled_windows() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client) = start_with_two_windows("floating-modifier-swap")?;
+    let left = client.find_window("Left")?;
+    let right = client.find_window("Right")?;
+    assert!(field(&left, "x") < field(&right, "x"));
+
+    client.drag("left", center(&left), center(&right), &["Super"])?;
+    client.sync()?;
+
+    let moved_left = client.find_window("Left")?;
+    let moved_right = client.find_window("Right")?;
+    assert_eq!(field(&moved_left, "x"), field(&right, "x"));
+    assert_eq!(field(&moved_right, "x"), field(&left, "x"));
+    assert_eq!(field(&moved_left, "width"), field(&right, "width"));
+    Ok(())
+}
+
+#[test]
+fn test_modifier_right_drag_resizes_tiled_window() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client) = start_with_two_windows("floating-modifier-resize")?;
+    let left = client.find_window("Left")?;
+    let right = client.find_window("Right")?;
+
+    // Nearest to the edge the two windows share
+    let (_, y) = center(&left);
+    let start = (field(&left, "x") + field(&left, "width") - 50, y);
+    client.drag("right", start, (start.0 + 200, y), &["Super"])?;
+    client.sync()?;
+
+    let resized_left = client.find_window("Left")?;
+    let resized_right = client.find_window("Right")?;
+    assert_eq!(field(&resized_left, "width"), field(&left, "width") + 200);
+    assert_eq!(field(&resized_right, "width"), field(&right, "width") - 200);
+    assert_eq!(field(&resized_right, "x"), field(&right, "x") + 200);
+    Ok(())
+}
+
+#[test]
+fn test_drag_without_modifier_leaves_tiles_alone() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client) = start_with_two_windows("floating-modifier-none-held")?;
+    let left = client.find_window("Left")?;
+    let right = client.find_window("Right")?;
+
+    client.drag("left", center(&left), center(&right), &[])?;
+    client.sync()?;
+
+    assert_eq!(field(&client.find_window("Left")?, "x"), field(&left, "x"));
+    assert_eq!(
+        field(&client.find_window("Right")?, "x"),
+        field(&right, "x")
+    );
+    Ok(())
+}
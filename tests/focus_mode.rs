@@ -0,0 +1,56 @@
+// Tests for picking the window `focus tiling|floating|mode_toggle` goes to
+
+use stilch::window::WindowId;
+use stilch::workspace::Workspace;
+
+#[test]
+fn test_targets_last_focused_of_each_kind() {
+    let [tiled1, tiled2, float1, float2] = [1, 2, 3, 4].map(WindowId::new);
+    let mut workspace = Workspace::new(0, 0);
+    workspace.add_window(tiled1);
+    workspace.add_window(tiled2);
+    workspace.add_floating_window(float1);
+    workspace.add_floating_window(float2);
+
+    workspace.set_focus(Some(tiled2));
+    workspace.set_focus(Some(float1));
+    assert_eq!(workspace.focus_mode_target(false), Some(tiled2));
+    assert_eq!(workspace.focus_mode_target(true), Some(float1));
+
+    workspace.set_focus(Some(tiled1));
+    assert_eq!(workspace.focus_mode_target(false), Some(tiled1));
+    assert_eq!(workspace.focus_mode_target(true), Some(float1));
+}
+
+#[test]
+fn test_falls_back_without_focus_history() {
+    let [tiled, float1, float2] = [1, 2, 3].map(WindowId::new);
+    let mut workspace = Workspace::new(0, 0);
+    assert_eq!(workspace.focus_mode_target(false), None);
+    assert_eq!(workspace.focus_mode_target(true), None);
+
+    workspace.add_window(tiled);
+    workspace.add_floating_window(float1);
+    workspace.add_floating_window(float2);
+
+    // The topmost floating window and the layout's own choice
+    assert_eq!(workspace.focus_mode_target(true), Some(float2));
+    assert_eq!(workspace.focus_mode_target(false), Some(tiled));
+}
+
+#[test]
+fn test_forgets_windows_that_changed_kind_or_left() {
+    let [tiled1, tiled2, float] = [1, 2, 3].map(WindowId::new);
+    let mut workspace = Workspace::new(0, 0);
+    workspace.add_window(tiled1);
+    workspace.add_window(tiled2);
+    workspace.add_floating_window(float);
+
+    workspace.set_focus(Some(tiled2));
+    workspace.set_window_floating(tiled2, true);
+    assert_eq!(workspace.focus_mode_target(false), Some(tiled1));
+
+    workspace.set_focus(Some(float));
+    workspace.remove_window(float);
+    assert_eq!(workspace.focus_mode_target(true), Some(tiled2));
+}
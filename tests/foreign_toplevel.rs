@@ -0,0 +1,145 @@
+//! Test wlr-foreign-toplevel-management handles and requests
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::io::{BufRead, BufReader};
+use std::process::Child;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Lines the client prints, read on another thread
+fn output_lines(child: &mut Child) -> Result<Receiver<String>, Box<dyn std::error::Error>> {
+    let stdout = child.stdout.take().ok_or("client has no stdout")?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(rx)
+}
+
+/// Wait for a line starting with `prefix` and containing `part`
+fn wait_for_line(
+    lines: &Receiver<String>,
+    prefix: &str,
+    part: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut seen = Vec::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(line) = lines.recv_timeout(left) else {
+            break;
+        };
+        if line.starts_with(prefix) && line.contains(part) {
+            return Ok(line);
+        }
+        seen.push(line);
+    }
+    Err(format!("No line like '{prefix}...{part}', got {seen:?}").into())
+}
+
+#[test]
+fn test_handles_follow_window_events() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("foreign-toplevel-events");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut list = env.start_client("toplevel_list", &[])?;
+    let lines = output_lines(&mut list)?;
+
+    // The title changes once the window is activated
+    let mut window = env.start_window_with_args("Before", "blue", &["--retitle", "After"])?;
+    client.wait_for_window_count(1, "after starting the window")?;
+    let line = wait_for_line(&lines, "TOPLEVEL After ", "activated")?;
+    assert!(line.contains("app_id=simple-window"), "{line}");
+
+    window.kill()?;
+    window.wait()?;
+    wait_for_line(&lines, "CLOSED After", "")?;
+
+    list.kill()?;
+    Ok(())
+}
+
+#[test]
+fn test_minimize_hides_in_scratchpad_and_unminimize_restores(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("foreign-toplevel-minimize");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut window = env.start_window("Alpha", Some("blue"))?;
+    client.wait_for_window_count(1, "after starting the window")?;
+    let id = client.find_window("Alpha")?["id"]
+        .as_u64()
+        .ok_or("window has no id")?;
+
+    let mut minimize = env.start_client("toplevel_list", &["--minimize", "Alpha"])?;
+    let lines = output_lines(&mut minimize)?;
+    wait_for_line(&lines, "TOPLEVEL Alpha ", "minimized")?;
+    client.sync()?;
+    assert_eq!(client.get_window(id)?["visible"].as_bool(), Some(false));
+    minimize.kill()?;
+
+    let mut unminimize = env.start_client("toplevel_list", &["--unminimize", "Alpha"])?;
+    let lines = output_lines(&mut unminimize)?;
+    let line = wait_for_line(&lines, "TOPLEVEL Alpha ", "activated")?;
+    assert!(!line.contains("minimized"), "{line}");
+    client.sync()?;
+
+    // Back tiled, where it was
+    let window_info = client.get_window(id)?;
+    assert_eq!(window_info["visible"].as_bool(), Some(true));
+    assert_eq!(window_info["floating"].as_bool(), Some(false));
+    assert_eq!(window_info["width"].as_i64(), Some(3840));
+    assert_eq!(client.get_focused_window()?, Some(id));
+
+    unminimize.kill()?;
+    window.kill()?;
+    Ok(())
+}
+
+#[test]
+fn test_fullscreen_request_uses_the_given_output() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("foreign-toplevel-fullscreen");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/workspace_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+
+    let mut window = env.start_window("Player", Some("blue"))?;
+    client.wait_for_window_count(1, "after starting the window")?;
+    let id = client.find_window("Player")?["id"]
+        .as_u64()
+        .ok_or("window has no id")?;
+
+    let mut list = env.start_client(
+        "toplevel_list",
+        &["--fullscreen", "Player", "--on", "HOTPLUG-1"],
+    )?;
+    let lines = output_lines(&mut list)?;
+    wait_for_line(&lines, "TOPLEVEL Player ", "fullscreen")?;
+    client.sync()?;
+
+    let window_info = client.get_window(id)?;
+    assert_eq!(window_info["fullscreen"].as_bool(), Some(true));
+    assert_eq!(window_info["x"].as_i64(), Some(1920));
+    assert_eq!(window_info["workspace"].as_u64(), Some(5));
+
+    list.kill()?;
+    window.kill()?;
+    Ok(())
+}
@@ -1,6 +1,6 @@
 mod common;
 
-use common::{TestClient, TestEnv};
+use common::{verify_window_geometry, TestClient, TestEnv};
 
 #[test]
 fn test_container_fullscreen() -> Result<(), Box<dyn std::error::Error>> {
@@ -227,6 +227,124 @@ fn test_physical_output_fullscreen() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_physical_output_fullscreen_split_output() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("physical-fullscreen-split");
+    env.cleanup()?;
+
+    // The 3840x2160 output is split into two 1920x1080 virtual outputs
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/virtual_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    let _window = env.start_window("PhysicalFullscreenSplitTest", Some("blue"))?;
+    client.wait_for_window_count(1, "after starting window")?;
+
+    let windows = client.get_windows()?;
+    let before = windows.first().expect("Should find window");
+    let geometry_before: Vec<i64> = ["x", "y", "width", "height"]
+        .iter()
+        .map(|key| before.get(*key).and_then(|v| v.as_i64()).unwrap())
+        .collect();
+    assert!(
+        geometry_before[2] <= 1920 && geometry_before[3] <= 1080,
+        "Window should start inside one virtual output, got {geometry_before:?}"
+    );
+
+    // Fullscreen covers the whole physical output, not the virtual output
+    client.send_simple_command("FullscreenPhysicalOutput")?;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let windows = client.get_windows()?;
+    let w = windows.first().expect("Should find window");
+    assert_eq!(w.get("fullscreen").and_then(|v| v.as_bool()), Some(true));
+    verify_window_geometry(w, 0, 0, 3840, 2160)?;
+
+    // Leaving fullscreen puts the window back exactly where it was
+    client.send_simple_command("FullscreenPhysicalOutput")?;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let windows = client.get_windows()?;
+    let w = windows.first().expect("Should find window");
+    assert_eq!(w.get("fullscreen").and_then(|v| v.as_bool()), Some(false));
+    verify_window_geometry(
+        w,
+        geometry_before[0] as i32,
+        geometry_before[1] as i32,
+        geometry_before[2] as i32,
+        geometry_before[3] as i32,
+    )?;
+
+    env.cleanup()?;
+    Ok(())
+}
+
+#[test]
+fn test_set_fullscreen_is_absolute() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("set-fullscreen");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/virtual_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    let _window = env.start_window("SetFullscreenTest", Some("blue"))?;
+    client.wait_for_window_count(1, "after starting window")?;
+    let id = client.get_windows()?[0]
+        .get("id")
+        .and_then(|v| v.as_u64())
+        .unwrap();
+
+    let set_fullscreen = |enabled: bool, mode: &str| -> Result<(), Box<dyn std::error::Error>> {
+        let response = client.send_command(&serde_json::json!({
+            "type": "SetFullscreen",
+            "id": id,
+            "enabled": enabled,
+            "mode": mode
+        }))?;
+        assert_ne!(
+            response.get("type").and_then(|t| t.as_str()),
+            Some("Error"),
+            "SetFullscreen failed: {response}"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        Ok(())
+    };
+    let fullscreen = || -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(client.get_windows()?[0]
+            .get("fullscreen")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false))
+    };
+
+    // Enabling twice keeps the window fullscreen instead of toggling it back
+    set_fullscreen(true, "virtual_output")?;
+    set_fullscreen(true, "virtual_output")?;
+    assert!(fullscreen()?, "Window should stay fullscreen");
+    verify_window_geometry(&client.get_windows()?[0], 0, 0, 1920, 1080)?;
+
+    // Enabling another mode switches to it
+    set_fullscreen(true, "physical_output")?;
+    assert!(fullscreen()?, "Window should still be fullscreen");
+    verify_window_geometry(&client.get_windows()?[0], 0, 0, 3840, 2160)?;
+
+    // Disabling leaves fullscreen whatever the mode, and twice is harmless
+    set_fullscreen(false, "virtual_output")?;
+    set_fullscreen(false, "virtual_output")?;
+    assert!(!fullscreen()?, "Window should not be fullscreen");
+
+    env.cleanup()?;
+    Ok(())
+}
+
 #[test]
 fn test_fullscreen_with_multiple_windows() -> Result<(), Box<dyn std::error::Error>> {
     let mut env = TestEnv::new("fullscreen-multiple");
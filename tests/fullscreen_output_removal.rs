@@ -0,0 +1,100 @@
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+
+/// Start a window on workspace 5, shown on a hotplugged output right of
+/// the primary one, and fullscreen it there
+fn fullscreen_on_hotplugged_output(
+    env: &TestEnv,
+    client: &TestClient,
+) -> Result<(std::process::Child, u64), Box<dyn std::error::Error>> {
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+    client.switch_workspace(4)?;
+
+    let window = env.start_window("Fullscreen", Some("blue"))?;
+    client.wait_for_window_count(1, "after starting the window")?;
+    let id = client.find_window("Fullscreen")?["id"]
+        .as_u64()
+        .ok_or("window has no id")?;
+
+    client.send_simple_command("FullscreenPhysicalOutput")?;
+    client.sync()?;
+    let fullscreen = client.get_window(id)?;
+    assert_eq!(fullscreen["fullscreen"].as_bool(), Some(true));
+    assert_eq!(fullscreen["x"].as_i64(), Some(1920));
+
+    Ok((window, id))
+}
+
+/// Number of the workspace shown on a virtual output
+fn shown_on(client: &TestClient, output: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    client
+        .get_workspaces()?
+        .into_iter()
+        .find(|ws| ws["visible"].as_bool() == Some(true) && ws["output"].as_str() == Some(output))
+        .map(|ws| ws["id"].clone())
+        .ok_or_else(|| format!("No workspace shown on {output}").into())
+}
+
+#[test]
+fn test_unplugging_the_output_restores_the_fullscreen_window(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("fullscreen-output-removed-restore");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/workspace_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let (mut window, id) = fullscreen_on_hotplugged_output(&env, &client)?;
+
+    client.remove_output("HOTPLUG-1")?;
+    client.sync()?;
+
+    // Back in a tiled spot on the workspace of the remaining output
+    let window_info = client.get_window(id)?;
+    assert_eq!(window_info["fullscreen"].as_bool(), Some(false));
+    assert_eq!(window_info["visible"].as_bool(), Some(true));
+    assert_eq!(window_info["workspace"], shown_on(&client, "output-1")?);
+    let x = window_info["x"].as_i64().unwrap();
+    assert!(
+        x < 1920,
+        "Window should be on the remaining output, x = {x}"
+    );
+    assert_eq!(client.get_focused_window()?, Some(id));
+
+    window.kill()?;
+    Ok(())
+}
+
+#[test]
+fn test_unplugging_the_output_moves_the_fullscreen_window() -> Result<(), Box<dyn std::error::Error>>
+{
+    let mut env = TestEnv::new("fullscreen-output-removed-move");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/fullscreen_output_removed.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let (mut window, id) = fullscreen_on_hotplugged_output(&env, &client)?;
+
+    client.remove_output("HOTPLUG-1")?;
+    client.sync()?;
+
+    // Fullscreen again, covering the remaining output
+    let window_info = client.get_window(id)?;
+    assert_eq!(window_info["fullscreen"].as_bool(), Some(true));
+    assert_eq!(window_info["workspace"], shown_on(&client, "output-1")?);
+    assert_eq!(window_info["x"].as_i64(), Some(0));
+    assert_eq!(window_info["y"].as_i64(), Some(0));
+
+    window.kill()?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Test that a client asking to go fullscreen on a specific output gets it
+//! there, moving to the workspace shown on that output
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_fullscreen_goes_to_the_requested_output() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("fullscreen-requested-output");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/workspace_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+
+    // Started on workspace 1, on the first output
+    let mut window =
+        env.start_window_with_args("Player", "blue", &["--fullscreen-on", "HOTPLUG-1"])?;
+    client.wait_for_window_count(1, "after starting the window")?;
+    let id = client.find_window("Player")?["id"]
+        .as_u64()
+        .ok_or("window has no id")?;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let window_info = loop {
+        let window_info = client.get_window(id)?;
+        if window_info["fullscreen"].as_bool() == Some(true) {
+            break window_info;
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("Window never went fullscreen: {window_info}").into());
+        }
+        client.sync()?;
+    };
+
+    // Covering the requested output, on the workspace assigned to it
+    assert_eq!(window_info["x"].as_i64(), Some(1920));
+    assert_eq!(window_info["y"].as_i64(), Some(0));
+    assert_eq!(window_info["width"].as_i64(), Some(1920));
+    assert_eq!(window_info["workspace"].as_u64(), Some(5));
+    assert_eq!(client.get_focused_window()?, Some(id));
+
+    window.kill()?;
+    Ok(())
+}
+
+#[test]
+fn test_fullscreen_requested_before_mapping() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("fullscreen-requested-before-mapping");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/workspace_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+
+    // Asked for before the initial commit, when the window isn't placed yet
+    let mut window =
+        env.start_window_with_args("Player", "blue", &["--fullscreen-at-start", "HOTPLUG-1"])?;
+    client.wait_for_window_count(1, "after starting the window")?;
+    let id = client.find_window("Player")?["id"]
+        .as_u64()
+        .ok_or("window has no id")?;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let window_info = loop {
+        let window_info = client.get_window(id)?;
+        if window_info["fullscreen"].as_bool() == Some(true) {
+            break window_info;
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("Window never went fullscreen: {window_info}").into());
+        }
+        client.sync()?;
+    };
+
+    assert_eq!(window_info["x"].as_i64(), Some(1920));
+    assert_eq!(window_info["width"].as_i64(), Some(1920));
+    assert_eq!(window_info["workspace"].as_u64(), Some(5));
+
+    window.kill()?;
+    Ok(())
+}
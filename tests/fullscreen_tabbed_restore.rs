@@ -0,0 +1,77 @@
+mod common;
+
+use common::{TestClient, TestEnv};
+
+fn window_by_id(windows: &[serde_json::Value], id: u64) -> &serde_json::Value {
+    windows
+        .iter()
+        .find(|w| w.get("id").and_then(|v| v.as_u64()) == Some(id))
+        .expect("Should find window")
+}
+
+fn geometry(window: &serde_json::Value) -> Vec<i64> {
+    ["x", "y", "width", "height"]
+        .iter()
+        .map(|key| window.get(*key).and_then(|v| v.as_i64()).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_fullscreen_restores_active_tab() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("fullscreen-tabbed-restore");
+    env.cleanup()?;
+
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    let _window1 = env.start_window("TabRestore1", Some("blue"))?;
+    client.wait_for_window_count(1, "after starting window 1")?;
+    let window1_id = client.get_windows()?[0]
+        .get("id")
+        .and_then(|v| v.as_u64())
+        .unwrap();
+
+    let _window2 = env.start_window("TabRestore2", Some("red"))?;
+    client.wait_for_window_count(2, "after starting window 2")?;
+    let window2_id = client
+        .get_windows()?
+        .iter()
+        .filter_map(|w| w.get("id").and_then(|v| v.as_u64()))
+        .find(|&id| id != window1_id)
+        .expect("Should find window 2");
+
+    // Tab both windows and make the first one the active tab
+    client.send_simple_command("LayoutTabbed")?;
+    client.send_simple_command("FocusLeft")?;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert_eq!(client.get_focused_window()?, Some(window1_id));
+
+    client.send_simple_command("FullscreenContainer")?;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    // A window opened meanwhile takes over the active tab
+    let _window3 = env.start_window("TabRestore3", Some("green"))?;
+    client.wait_for_window_count(3, "after starting window 3")?;
+
+    client.focus_window(window1_id)?;
+    client.send_simple_command("FullscreenContainer")?;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    // The window is back as the focused tab of its container, not re-tiled
+    let windows = client.get_windows()?;
+    let window1 = window_by_id(&windows, window1_id);
+    assert_eq!(
+        window1.get("fullscreen").and_then(|v| v.as_bool()),
+        Some(false)
+    );
+    assert_eq!(client.get_focused_window()?, Some(window1_id));
+    assert_eq!(
+        geometry(window1),
+        geometry(window_by_id(&windows, window2_id)),
+        "Window 1 should share its tabbed container with window 2"
+    );
+
+    env.cleanup()?;
+    Ok(())
+}
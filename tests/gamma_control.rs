@@ -0,0 +1,145 @@
+//! Test wlr-gamma-control ramps, their validation and their reset
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::process::Child;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Entries per channel the test backend advertises
+const GAMMA_SIZE: usize = 256;
+
+/// Lines the client prints, read on another thread
+fn output_lines(child: &mut Child) -> Result<Receiver<String>, Box<dyn std::error::Error>> {
+    let stdout = child.stdout.take().ok_or("client has no stdout")?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(rx)
+}
+
+fn next_line(lines: &Receiver<String>) -> Result<String, Box<dyn std::error::Error>> {
+    lines
+        .recv_timeout(Duration::from_secs(5))
+        .map_err(|_| "client printed nothing".into())
+}
+
+/// The ramps set on an output, Null for the default ramp
+fn gamma(client: &TestClient, output: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let response = client.send_command(&serde_json::json!({
+        "type": "GetGamma",
+        "output": output
+    }))?;
+    Ok(response["ramps"].clone())
+}
+
+/// Wait for an output to get its default ramp back
+fn wait_for_default_gamma(
+    client: &TestClient,
+    output: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        client.sync()?;
+        if gamma(client, output)?.is_null() {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    Err(format!("{output} kept its gamma ramps").into())
+}
+
+#[test]
+fn test_ramps_are_set_and_reset_when_the_control_goes() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("gamma-control-set");
+    env.cleanup()?;
+    env.start_compositor(&["--test"])?;
+    let client = TestClient::new(&env.test_socket);
+    assert!(gamma(&client, "ascii")?.is_null());
+
+    let mut control = env.start_client("gamma_control", &["ascii"])?;
+    let lines = output_lines(&mut control)?;
+    assert_eq!(next_line(&lines)?, "SET");
+
+    let ramps = gamma(&client, "ascii")?;
+    let ramps = ramps.as_array().ok_or("no ramps set")?;
+    assert_eq!(ramps.len(), GAMMA_SIZE * 3);
+    // Inverted on every channel
+    for channel in ramps.chunks(GAMMA_SIZE) {
+        assert_eq!(channel[0].as_u64(), Some(u16::MAX as u64));
+        assert_eq!(channel[GAMMA_SIZE - 1].as_u64(), Some(0));
+    }
+
+    // A second client cannot take the output over
+    let mut second = env.start_client("gamma_control", &["ascii"])?;
+    let second_lines = output_lines(&mut second)?;
+    assert_eq!(next_line(&second_lines)?, "FAILED");
+    second.wait()?;
+
+    control.kill()?;
+    control.wait()?;
+    wait_for_default_gamma(&client, "ascii")
+}
+
+#[test]
+fn test_short_ramps_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("gamma-control-short");
+    env.cleanup()?;
+    env.start_compositor(&["--test"])?;
+    let client = TestClient::new(&env.test_socket);
+
+    let mut control = env.start_client("gamma_control", &["ascii", "--short"])?;
+    let lines = output_lines(&mut control)?;
+    assert_eq!(next_line(&lines)?, "ERROR");
+    control.wait()?;
+
+    client.sync()?;
+    assert!(gamma(&client, "ascii")?.is_null());
+    Ok(())
+}
+
+#[test]
+fn test_control_fails_when_its_output_goes() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("gamma-control-output-removed");
+    env.cleanup()?;
+    env.start_compositor(&["--test"])?;
+    let client = TestClient::new(&env.test_socket);
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+
+    let mut control = env.start_client("gamma_control", &["HOTPLUG-1"])?;
+    let lines = output_lines(&mut control)?;
+    assert_eq!(next_line(&lines)?, "SET");
+
+    client.remove_output("HOTPLUG-1")?;
+    assert_eq!(next_line(&lines)?, "FAILED");
+    control.wait()?;
+    Ok(())
+}
+
+#[test]
+fn test_unreadable_ramps_fail_the_control() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("gamma-control-pipe");
+    env.cleanup()?;
+    env.start_compositor(&["--test"])?;
+    let client = TestClient::new(&env.test_socket);
+
+    let mut control = env.start_client("gamma_control", &["ascii", "--pipe"])?;
+    let lines = output_lines(&mut control)?;
+    assert_eq!(next_line(&lines)?, "FAILED");
+    control.wait()?;
+
+    // The compositor is still responsive and the output keeps its default
+    client.sync()?;
+    assert!(gamma(&client, "ascii")?.is_null());
+    Ok(())
+}
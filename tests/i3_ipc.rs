@@ -0,0 +1,239 @@
+//! Test that i3 IPC clients are served on the i3 socket next to the stilch one
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_OUTPUTS: u32 = 3;
+const GET_TREE: u32 = 4;
+const GET_BINDING_MODES: u32 = 8;
+const GET_BINDING_STATE: u32 = 12;
+const EVENT_MODE: u32 = (1 << 31) | 2;
+const EVENT_WINDOW: u32 = (1 << 31) | 3;
+
+fn send(stream: &mut UnixStream, message_type: u32, payload: &str) -> std::io::Result<()> {
+    let mut message = b"i3-ipc".to_vec();
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&message_type.to_ne_bytes());
+    message.extend_from_slice(payload.as_bytes());
+    stream.write_all(&message)
+}
+
+fn receive(
+    stream: &mut UnixStream,
+) -> Result<(u32, serde_json::Value), Box<dyn std::error::Error>> {
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    assert_eq!(&header[..6], b"i3-ipc");
+    let length = u32::from_ne_bytes(header[6..10].try_into()?);
+    let message_type = u32::from_ne_bytes(header[10..14].try_into()?);
+    let mut payload = vec![0u8; length as usize];
+    stream.read_exact(&mut payload)?;
+    Ok((message_type, serde_json::from_slice(&payload)?))
+}
+
+fn query(
+    stream: &mut UnixStream,
+    message_type: u32,
+    payload: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    send(stream, message_type, payload)?;
+    let (reply_type, reply) = receive(stream)?;
+    assert_eq!(reply_type, message_type);
+    Ok(reply)
+}
+
+#[test]
+fn test_i3_clients_get_workspaces_outputs_and_tree() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("i3-ipc");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut i3 = UnixStream::connect("/tmp/stilch-ipc-i3-ipc.i3.sock")?;
+    i3.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let subscribed = query(&mut i3, SUBSCRIBE, r#"["window", "workspace"]"#)?;
+    assert_eq!(subscribed["success"], true);
+
+    let mut _window = env.start_window("I3 Window", Some("blue"))?;
+    client.wait_for_window_count(1, "after starting the window")?;
+    let window_id = client.find_window("I3 Window")?["id"]
+        .as_u64()
+        .ok_or("window has no id")?;
+
+    // The new window is announced with its id
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        if Instant::now() >= deadline {
+            return Err("no window new event".into());
+        }
+        let (event_type, event) = receive(&mut i3)?;
+        if event_type == EVENT_WINDOW && event["change"] == "new" {
+            assert_eq!(event["container"]["id"].as_u64(), Some(window_id));
+            break;
+        }
+    }
+
+    // Queries from here on have events in between, so use a fresh client
+    let mut i3 = UnixStream::connect("/tmp/stilch-ipc-i3-ipc.i3.sock")?;
+    i3.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let workspaces = query(&mut i3, GET_WORKSPACES, "")?;
+    let workspaces = workspaces.as_array().ok_or("workspaces are no list")?;
+    let focused: Vec<_> = workspaces
+        .iter()
+        .filter(|workspace| workspace["focused"] == true)
+        .collect();
+    assert_eq!(focused.len(), 1, "one workspace has focus: {workspaces:?}");
+    assert_eq!(focused[0]["num"], 1);
+    assert_eq!(focused[0]["visible"], true);
+
+    let outputs = query(&mut i3, GET_OUTPUTS, "")?;
+    let outputs = outputs.as_array().ok_or("outputs are no list")?;
+    assert!(!outputs.is_empty());
+    assert!(outputs
+        .iter()
+        .any(|output| output["current_workspace"] == focused[0]["name"]));
+
+    // root > output > content > workspace > window
+    let tree = query(&mut i3, GET_TREE, "")?;
+    assert_eq!(tree["type"], "root");
+    let window = tree["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|output| output["nodes"][0]["nodes"].as_array().into_iter().flatten())
+        .flat_map(|workspace| workspace["nodes"].as_array().into_iter().flatten())
+        .find(|node| node["id"].as_u64() == Some(window_id))
+        .ok_or("window missing from the tree")?;
+    assert_eq!(window["name"], "I3 Window");
+    assert_eq!(window["type"], "con");
+
+    Ok(())
+}
+
+#[test]
+fn test_i3_run_command_reports_every_command() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("i3-ipc-commands");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let mut i3 = UnixStream::connect("/tmp/stilch-ipc-i3-ipc-commands.i3.sock")?;
+    i3.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    // The command that doesn't parse is reported, the others still run
+    let results = query(&mut i3, RUN_COMMAND, "frobnicate; workspace 3")?;
+    let results = results.as_array().ok_or("results are no list")?;
+    assert_eq!(results.len(), 2, "one result per command: {results:?}");
+    assert_eq!(results[0]["success"], false);
+    assert_eq!(results[0]["parse_error"], true);
+    assert_eq!(results[1]["success"], true);
+
+    let workspaces = query(&mut i3, GET_WORKSPACES, "")?;
+    let workspaces = workspaces.as_array().ok_or("workspaces are no list")?;
+    assert!(workspaces
+        .iter()
+        .any(|workspace| workspace["num"] == 3 && workspace["focused"] == true));
+
+    Ok(())
+}
+
+#[test]
+fn test_i3_client_with_oversized_payload_is_dropped() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("i3-ipc-oversized");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let mut i3 = UnixStream::connect("/tmp/stilch-ipc-i3-ipc-oversized.i3.sock")?;
+    i3.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    // Announce 4 GiB without sending it
+    let mut message = b"i3-ipc".to_vec();
+    message.extend_from_slice(&u32::MAX.to_ne_bytes());
+    message.extend_from_slice(&RUN_COMMAND.to_ne_bytes());
+    i3.write_all(&message)?;
+
+    let mut buffer = [0u8; 1];
+    assert_eq!(i3.read(&mut buffer)?, 0, "the connection is closed");
+
+    // Other clients are still served
+    let mut i3 = UnixStream::connect("/tmp/stilch-ipc-i3-ipc-oversized.i3.sock")?;
+    i3.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let workspaces = query(&mut i3, GET_WORKSPACES, "")?;
+    assert!(workspaces.is_array());
+
+    Ok(())
+}
+
+#[test]
+fn test_i3_clients_follow_the_binding_mode() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("i3-ipc-mode");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let socket = "/tmp/stilch-ipc-i3-ipc-mode.i3.sock";
+    let mut events = UnixStream::connect(socket)?;
+    events.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let subscribed = query(&mut events, SUBSCRIBE, r#"["mode"]"#)?;
+    assert_eq!(subscribed["success"], true);
+
+    let mut i3 = UnixStream::connect(socket)?;
+    i3.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let modes = query(&mut i3, GET_BINDING_MODES, "")?;
+    assert!(modes
+        .as_array()
+        .is_some_and(|modes| modes.iter().any(|mode| mode == "resize")));
+    assert_eq!(query(&mut i3, GET_BINDING_STATE, "")?["name"], "default");
+
+    for mode in ["resize", "default"] {
+        let results = query(&mut i3, RUN_COMMAND, &format!("mode \"{mode}\""))?;
+        assert_eq!(results[0]["success"], true);
+        assert_eq!(query(&mut i3, GET_BINDING_STATE, "")?["name"], mode);
+
+        let (event_type, event) = receive(&mut events)?;
+        assert_eq!(event_type, EVENT_MODE);
+        assert_eq!(event["change"], mode);
+    }
+
+    // Modes the config doesn't have are not entered
+    let results = query(&mut i3, RUN_COMMAND, "mode \"nonexistent\"")?;
+    assert_eq!(results[0]["success"], false);
+    assert_eq!(query(&mut i3, GET_BINDING_STATE, "")?["name"], "default");
+
+    Ok(())
+}
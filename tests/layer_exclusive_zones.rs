@@ -0,0 +1,91 @@
+// Tests for how layer-shell exclusive zones shrink virtual outputs that don't
+// line up with a single physical output
+
+use smithay::utils::{Logical, Rectangle};
+use stilch::virtual_output::usable_area;
+
+fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+    Rectangle::new((x, y).into(), (w, h).into())
+}
+
+#[test]
+fn test_top_panel_on_left_monitor_of_merged_output() {
+    // Two 1920x1080 monitors merged into one virtual output, with a 30px top
+    // panel on the left one only
+    let left = rect(0, 0, 1920, 1080);
+    let right = rect(1920, 0, 1920, 1080);
+    let region = rect(0, 0, 3840, 1080);
+
+    let area = usable_area(region, &[(left, rect(0, 30, 1920, 1050)), (right, right)]);
+
+    // The panel insets the top edge across the whole merged output
+    assert_eq!(area, rect(0, 30, 3840, 1050));
+}
+
+#[test]
+fn test_no_panels_leaves_merged_output_untouched() {
+    let left = rect(0, 0, 1920, 1080);
+    let right = rect(1920, 0, 1920, 1080);
+    let region = rect(0, 0, 3840, 1080);
+
+    assert_eq!(usable_area(region, &[(left, left), (right, right)]), region);
+}
+
+#[test]
+fn test_panel_along_seam_of_merged_output_reserves_nothing() {
+    // A 40px panel on the right edge of the left monitor sits in the middle of
+    // the merged output, where no rectangle can avoid it
+    let left = rect(0, 0, 1920, 1080);
+    let right = rect(1920, 0, 1920, 1080);
+    let region = rect(0, 0, 3840, 1080);
+
+    let area = usable_area(region, &[(left, rect(0, 0, 1880, 1080)), (right, right)]);
+
+    assert_eq!(area, region);
+}
+
+#[test]
+fn test_top_panel_on_split_output() {
+    // A 3840x1080 monitor split into two halves with a 30px top panel
+    let monitor = rect(0, 0, 3840, 1080);
+    let non_exclusive = rect(0, 30, 3840, 1050);
+
+    let left_half = usable_area(rect(0, 0, 1920, 1080), &[(monitor, non_exclusive)]);
+    let right_half = usable_area(rect(1920, 0, 1920, 1080), &[(monitor, non_exclusive)]);
+
+    assert_eq!(left_half, rect(0, 30, 1920, 1050));
+    assert_eq!(right_half, rect(1920, 30, 1920, 1050));
+}
+
+#[test]
+fn test_top_panel_misses_bottom_of_split_output() {
+    // The monitor is split into a top and a bottom half; only the top half
+    // touches the panel
+    let monitor = rect(0, 0, 1920, 2160);
+    let non_exclusive = rect(0, 30, 1920, 2130);
+
+    let top_half = usable_area(rect(0, 0, 1920, 1080), &[(monitor, non_exclusive)]);
+    let bottom_half = usable_area(rect(0, 1080, 1920, 1080), &[(monitor, non_exclusive)]);
+
+    assert_eq!(top_half, rect(0, 30, 1920, 1050));
+    assert_eq!(bottom_half, rect(0, 1080, 1920, 1080));
+}
+
+#[test]
+fn test_panels_on_both_monitors_of_vertical_merge() {
+    // Two monitors stacked vertically, a top panel on the upper one and a
+    // bottom panel on the lower one
+    let upper = rect(0, 0, 1920, 1080);
+    let lower = rect(0, 1080, 1920, 1080);
+    let region = rect(0, 0, 1920, 2160);
+
+    let area = usable_area(
+        region,
+        &[
+            (upper, rect(0, 30, 1920, 1050)),
+            (lower, rect(0, 1080, 1920, 1040)),
+        ],
+    );
+
+    assert_eq!(area, rect(0, 30, 1920, 2090));
+}
@@ -0,0 +1,37 @@
+//! Test that popups of layer surfaces are kept on their output
+
+mod common;
+
+use common::TestEnv;
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_layer_popup_slides_onto_the_output() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("layer-popup");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    // A panel in the bottom right corner opens a popup past both edges
+    let mut panel = env.start_client("layer_popup", &[])?;
+    let stdout = panel.stdout.take().ok_or("client has no stdout")?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line.starts_with("POPUP ") && tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let line = rx.recv_timeout(Duration::from_secs(5));
+    panel.kill()?;
+    let line = line.map_err(|_| "The popup was never configured")?;
+
+    // Slid back up and left until it fits on the 3840x2160 output, relative
+    // to the 200x30 panel at 3640,2130
+    assert_eq!(line, "POPUP -100 -170 300 200");
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{verify_window_geometry_relative, TestClient, TestEnv};
+
+#[test]
+fn test_layout_config_reports_gaps_and_borders() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("layout-config");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/with_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let layout_config = client.get_layout_config()?;
+
+    assert_eq!(layout_config["border_width"], 2);
+    let areas = layout_config["workspaces"]
+        .as_array()
+        .ok_or("No workspace areas")?;
+    assert!(!areas.is_empty());
+    assert!(areas.iter().all(|area| area["gap"] == 10));
+    assert!(areas.iter().all(|area| area["width"].as_i64() > Some(0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_relative_geometry_follows_gaps() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("layout-config-relative");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/with_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let layout_config = client.get_layout_config()?;
+
+    client.create_window(Some("only"), None)?;
+    let only = client.find_window("only")?;
+    verify_window_geometry_relative(&only, &layout_config, 0.0, 0.0, 1.0, 1.0)?;
+
+    client.create_window(Some("second"), None)?;
+    client.find_window("second")?;
+    client.sync()?;
+
+    let left = client.find_window("only")?;
+    let right = client.find_window("second")?;
+    verify_window_geometry_relative(&left, &layout_config, 0.0, 0.0, 0.5, 1.0)?;
+    verify_window_geometry_relative(&right, &layout_config, 0.5, 0.0, 0.5, 1.0)?;
+
+    // The left half is not where the right window is
+    assert!(verify_window_geometry_relative(&right, &layout_config, 0.0, 0.0, 0.5, 1.0).is_err());
+
+    Ok(())
+}
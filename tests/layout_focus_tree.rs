@@ -0,0 +1,159 @@
+// Tests for walking the layout tree with focus parent/child/next/prev
+
+use smithay::utils::Rectangle;
+use stilch::config::Direction;
+use stilch::window::WindowId;
+use stilch::workspace::layout::{ContainerLayout, LayoutTree, NodeKey, SplitDirection};
+
+/// Window 1 on the left, windows 2 and 3 stacked vertically on the right
+fn nested() -> (LayoutTree, [WindowId; 3]) {
+    let workspace_rect = Rectangle::from_size((800, 600).into());
+    let mut layout = LayoutTree::new(workspace_rect, 0);
+    let windows = [WindowId::new(1), WindowId::new(2), WindowId::new(3)];
+    layout.add_window(windows[0], SplitDirection::Horizontal);
+    layout.add_window(windows[1], SplitDirection::Horizontal);
+    layout.insert_beside(windows[2], windows[1], SplitDirection::Vertical);
+    layout.calculate_geometries();
+    (layout, windows)
+}
+
+#[test]
+fn test_parent_walks_up_to_the_root() {
+    let (layout, [win1, win2, win3]) = nested();
+
+    let column = layout.parent_of(NodeKey::Window(win2)).unwrap();
+    assert_eq!(layout.parent_of(NodeKey::Window(win3)), Some(column));
+    let root = layout.parent_of(NodeKey::Container(column)).unwrap();
+    assert_eq!(layout.parent_of(NodeKey::Window(win1)), Some(root));
+    assert_eq!(layout.parent_of(NodeKey::Container(root)), None);
+}
+
+#[test]
+fn test_child_goes_back_down_towards_the_window() {
+    let (layout, [_, _, win3]) = nested();
+    let column = layout.parent_of(NodeKey::Window(win3)).unwrap();
+    let root = layout.parent_of(NodeKey::Container(column)).unwrap();
+
+    assert_eq!(
+        layout.child_towards(root, win3),
+        Some(NodeKey::Container(column))
+    );
+    assert_eq!(
+        layout.child_towards(column, win3),
+        Some(NodeKey::Window(win3))
+    );
+}
+
+#[test]
+fn test_siblings_wrap_around_within_their_container() {
+    let (layout, [win1, win2, win3]) = nested();
+    let column = layout.parent_of(NodeKey::Window(win2)).unwrap();
+
+    assert_eq!(
+        layout.sibling_of(NodeKey::Window(win1), true),
+        Some(NodeKey::Container(column))
+    );
+    assert_eq!(
+        layout.sibling_of(NodeKey::Container(column), true),
+        Some(NodeKey::Window(win1))
+    );
+    // Inside the column, not out of it
+    assert_eq!(
+        layout.sibling_of(NodeKey::Window(win2), false),
+        Some(NodeKey::Window(win3))
+    );
+}
+
+#[test]
+fn test_next_steps_through_tabs_in_order() {
+    let workspace_rect = Rectangle::from_size((900, 600).into());
+    let mut layout = LayoutTree::new(workspace_rect, 0);
+    let windows = [WindowId::new(1), WindowId::new(2), WindowId::new(3)];
+    for window in windows {
+        layout.add_window(window, SplitDirection::Horizontal);
+    }
+    let root = layout.parent_of(NodeKey::Window(windows[0])).unwrap();
+    layout.set_layout_of(root, ContainerLayout::Tabbed);
+    layout.calculate_geometries();
+
+    let mut node = NodeKey::Window(windows[0]);
+    let mut visited = Vec::new();
+    for _ in 0..3 {
+        node = layout.sibling_of(node, true).unwrap();
+        visited.push(node);
+    }
+    assert_eq!(
+        visited,
+        vec![
+            NodeKey::Window(windows[1]),
+            NodeKey::Window(windows[2]),
+            NodeKey::Window(windows[0]),
+        ]
+    );
+}
+
+#[test]
+fn test_focus_leaf_follows_the_active_tab() {
+    let (mut layout, [_, win2, win3]) = nested();
+    let column = layout.parent_of(NodeKey::Window(win2)).unwrap();
+
+    layout.set_layout_of(column, ContainerLayout::Tabbed);
+    layout.update_active_child_for_window(win3);
+
+    assert_eq!(layout.focus_leaf(NodeKey::Container(column)), Some(win3));
+    assert_eq!(layout.focus_leaf(NodeKey::Window(win2)), Some(win2));
+}
+
+#[test]
+fn test_layout_of_a_selected_container_changes_as_a_whole() {
+    let (mut layout, [win1, win2, win3]) = nested();
+    let column = layout.parent_of(NodeKey::Window(win2)).unwrap();
+
+    layout.toggle_split_of(column, SplitDirection::Horizontal);
+    layout.calculate_geometries();
+
+    // The column is now split side by side, the outer split untouched
+    let geometry = |id| layout.get_window_geometry(id).unwrap();
+    assert_eq!(geometry(win2).loc.y, geometry(win3).loc.y);
+    assert!(geometry(win2).loc.x < geometry(win3).loc.x);
+    assert_eq!(geometry(win1).size.w, 400);
+}
+
+#[test]
+fn test_selected_container_trades_places_with_its_neighbour() {
+    let (mut layout, [win1, win2, win3]) = nested();
+    let column = layout.parent_of(NodeKey::Window(win2)).unwrap();
+
+    assert!(layout.move_container(column, Direction::Left));
+
+    let geometry = |id| layout.get_window_geometry(id).unwrap();
+    assert_eq!(geometry(win2).loc.x, 0);
+    assert_eq!(geometry(win3).loc.x, 0);
+    assert_eq!(geometry(win1).loc.x, 400);
+    assert_eq!(layout.parent_of(NodeKey::Window(win2)), Some(column));
+    // Already at the left end, and nothing runs up and down around it
+    assert!(!layout.move_container(column, Direction::Left));
+    assert!(!layout.move_container(column, Direction::Down));
+}
+
+#[test]
+fn test_nested_container_moves_out_beside_the_one_holding_it() {
+    let (mut layout, [win1, win2, win3]) = nested();
+    let win4 = WindowId::new(4);
+    // Windows 3 and 4 side by side below window 2
+    layout.insert_beside(win4, win3, SplitDirection::Horizontal);
+    layout.calculate_geometries();
+    let column = layout.parent_of(NodeKey::Window(win2)).unwrap();
+    let row = layout.parent_of(NodeKey::Window(win3)).unwrap();
+    assert_eq!(layout.parent_of(NodeKey::Container(row)), Some(column));
+
+    assert!(layout.move_container(row, Direction::Left));
+
+    let root = layout.parent_of(NodeKey::Window(win1)).unwrap();
+    assert_eq!(layout.parent_of(NodeKey::Container(row)), Some(root));
+    assert_eq!(layout.parent_of(NodeKey::Window(win4)), Some(row));
+    let x = |id| layout.get_window_geometry(id).unwrap().loc.x;
+    assert!(x(win1) < x(win3));
+    assert!(x(win3) < x(win4));
+    assert!(x(win4) < x(win2));
+}
@@ -0,0 +1,96 @@
+// Tests for snapping tiled windows to the device pixel grid at fractional
+// scales, so neighbouring tiles meet without seams or blurry edges
+
+use smithay::utils::{Logical, Rectangle};
+use stilch::window::WindowId;
+use stilch::workspace::layout::{LayoutTree, PixelGrid, SplitDirection};
+
+fn on_grid(value: i32, scale: f64) -> bool {
+    let physical = value as f64 * scale;
+    (physical - physical.round()).abs() < 1e-6
+}
+
+fn tiled(
+    area: Rectangle<i32, Logical>,
+    gap: i32,
+    count: u32,
+    direction: SplitDirection,
+    scale: f64,
+) -> Vec<Rectangle<i32, Logical>> {
+    let mut layout = LayoutTree::new(area, gap);
+    layout.set_pixel_grid(Some(PixelGrid {
+        origin: (0, 0).into(),
+        scale,
+    }));
+    for id in 1..=count {
+        layout.add_window(WindowId::new(id), direction);
+    }
+    layout.calculate_geometries();
+    let mut geometries: Vec<_> = layout
+        .get_all_geometries()
+        .into_iter()
+        .map(|(_, geometry)| geometry)
+        .collect();
+    geometries.sort_by_key(|geometry| (geometry.loc.x, geometry.loc.y));
+    geometries
+}
+
+#[test]
+fn test_three_columns_at_1_5_scale_land_on_device_pixels() {
+    // 427 logical wide columns would put the first edge at 640.5 device
+    // pixels
+    let area = Rectangle::from_size((1281, 720).into());
+    let windows = tiled(area, 0, 3, SplitDirection::Horizontal, 1.5);
+
+    for window in &windows {
+        assert!(
+            on_grid(window.loc.x, 1.5),
+            "{window:?} starts between pixels"
+        );
+        assert!(
+            on_grid(window.loc.x + window.size.w, 1.5),
+            "{window:?} ends between pixels"
+        );
+    }
+    // Neighbouring columns still meet exactly
+    for pair in windows.windows(2) {
+        assert_eq!(pair[0].loc.x + pair[0].size.w, pair[1].loc.x);
+    }
+}
+
+#[test]
+fn test_rows_with_gaps_at_1_25_scale_land_on_device_pixels() {
+    let area = Rectangle::from_size((1536, 864).into());
+    let windows = tiled(area, 5, 3, SplitDirection::Vertical, 1.25);
+
+    for window in &windows {
+        assert!(
+            on_grid(window.loc.y, 1.25),
+            "{window:?} starts between pixels"
+        );
+        assert!(
+            on_grid(window.loc.y + window.size.h, 1.25),
+            "{window:?} ends between pixels"
+        );
+    }
+}
+
+#[test]
+fn test_integer_scale_leaves_geometry_alone() {
+    let area = Rectangle::from_size((1000, 600).into());
+    let snapped = tiled(area, 7, 3, SplitDirection::Horizontal, 1.0);
+
+    let mut layout = LayoutTree::new(area, 7);
+    for id in 1..=3 {
+        layout.add_window(WindowId::new(id), SplitDirection::Horizontal);
+    }
+    layout.calculate_geometries();
+    let mut plain: Vec<_> = layout
+        .get_all_geometries()
+        .into_iter()
+        .map(|(_, geometry)| geometry)
+        .collect();
+    plain.sort_by_key(|geometry| (geometry.loc.x, geometry.loc.y));
+
+    assert_eq!(snapped, plain);
+}
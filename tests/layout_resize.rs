@@ -0,0 +1,217 @@
+// Tests for resizing tiled windows, which only trades space with the
+// neighbouring child of the nearest split running the same way
+
+use smithay::utils::Rectangle;
+use stilch::config::Direction;
+use stilch::window::WindowId;
+use stilch::workspace::layout::{LayoutTree, SplitDirection, MIN_WINDOW_SIZE};
+
+fn three_columns() -> (LayoutTree, [WindowId; 3]) {
+    let workspace_rect = Rectangle::from_size((900, 600).into());
+    let mut layout = LayoutTree::new(workspace_rect, 0);
+    let windows = [WindowId::new(1), WindowId::new(2), WindowId::new(3)];
+    for window in windows {
+        layout.add_window(window, SplitDirection::Horizontal);
+    }
+    layout.calculate_geometries();
+    (layout, windows)
+}
+
+fn widths(layout: &LayoutTree, windows: &[WindowId]) -> Vec<i32> {
+    windows
+        .iter()
+        .map(|&id| layout.get_window_geometry(id).unwrap().size.w)
+        .collect()
+}
+
+#[test]
+fn test_growing_takes_space_from_the_next_sibling_only() {
+    let (mut layout, windows) = three_columns();
+
+    assert!(layout.resize_window(windows[0], Direction::Right, 90));
+    layout.calculate_geometries();
+
+    assert_eq!(widths(&layout, &windows), vec![390, 210, 300]);
+    // Columns still meet and fill the workspace
+    let last = layout.get_window_geometry(windows[2]).unwrap();
+    assert_eq!(last.loc.x + last.size.w, 900);
+}
+
+#[test]
+fn test_last_window_trades_space_with_the_one_before_it() {
+    let (mut layout, windows) = three_columns();
+
+    assert!(layout.resize_window(windows[2], Direction::Left, -60));
+    layout.calculate_geometries();
+
+    assert_eq!(widths(&layout, &windows), vec![300, 360, 240]);
+}
+
+#[test]
+fn test_resizing_across_the_split_resizes_the_enclosing_split() {
+    // Window 3 sits in a vertical split taking the right half
+    let workspace_rect = Rectangle::from_size((800, 600).into());
+    let mut layout = LayoutTree::new(workspace_rect, 0);
+    let [win1, win2, win3] = [WindowId::new(1), WindowId::new(2), WindowId::new(3)];
+    layout.add_window(win1, SplitDirection::Horizontal);
+    layout.add_window(win2, SplitDirection::Horizontal);
+    layout.insert_beside(win3, win2, SplitDirection::Vertical);
+    layout.calculate_geometries();
+
+    assert!(layout.resize_window(win3, Direction::Right, 100));
+    layout.calculate_geometries();
+
+    // The right column grows by taking from the left one, both its
+    // windows widening together
+    assert_eq!(layout.get_window_geometry(win1).unwrap().size.w, 300);
+    assert_eq!(layout.get_window_geometry(win2).unwrap().size.w, 500);
+    assert_eq!(layout.get_window_geometry(win3).unwrap().size.w, 500);
+    assert_eq!(layout.get_window_geometry(win3).unwrap().size.h, 300);
+}
+
+#[test]
+fn test_resizing_without_a_split_that_way_changes_nothing() {
+    let (mut layout, windows) = three_columns();
+
+    assert!(!layout.resize_window(windows[1], Direction::Down, 50));
+    layout.calculate_geometries();
+
+    assert_eq!(widths(&layout, &windows), vec![300, 300, 300]);
+}
+
+#[test]
+fn test_resizing_stops_at_the_minimum_size() {
+    let (mut layout, windows) = three_columns();
+
+    assert!(layout.resize_window(windows[0], Direction::Right, 1000));
+    layout.calculate_geometries();
+    assert_eq!(widths(&layout, &windows), vec![550, MIN_WINDOW_SIZE, 300]);
+
+    // Nothing left to take
+    assert!(!layout.resize_window(windows[0], Direction::Right, 10));
+}
+
+#[test]
+fn test_balance_shares_the_space_evenly_again() {
+    let (mut layout, windows) = three_columns();
+    assert!(layout.resize_window(windows[1], Direction::Right, 120));
+
+    layout.balance(Some(windows[1]));
+    layout.calculate_geometries();
+
+    assert_eq!(widths(&layout, &windows), vec![300, 300, 300]);
+}
+
+#[test]
+fn test_closing_a_neighbour_keeps_the_resized_share() {
+    let (mut layout, windows) = three_columns();
+    assert!(layout.resize_window(windows[0], Direction::Right, 90));
+
+    layout.remove_window(windows[1]);
+    layout.calculate_geometries();
+
+    // Window 1 keeps its share against the untouched window 3
+    assert_eq!(widths(&layout, &[windows[0], windows[2]]), vec![509, 391]);
+}
+
+#[test]
+fn test_split_boundary_is_found_near_where_windows_meet() {
+    let (layout, _) = three_columns();
+
+    let boundary = layout.split_boundary_at((302.0, 200.0).into(), 4).unwrap();
+    assert!(boundary.side_by_side);
+    assert!(layout.split_boundary_at((597.0, 200.0).into(), 4).is_some());
+    // Well inside a window
+    assert!(layout.split_boundary_at((450.0, 200.0).into(), 4).is_none());
+}
+
+#[test]
+fn test_split_boundary_without_reach_is_only_the_gap() {
+    let workspace_rect = Rectangle::from_size((900, 600).into());
+    let mut layout = LayoutTree::new(workspace_rect, 10);
+    let [win1, win2] = [WindowId::new(1), WindowId::new(2)];
+    layout.add_window(win1, SplitDirection::Horizontal);
+    layout.add_window(win2, SplitDirection::Horizontal);
+    layout.calculate_geometries();
+    let first = layout.get_window_geometry(win1).unwrap();
+    let end = first.loc.x + first.size.w;
+    let start = layout.get_window_geometry(win2).unwrap().loc.x;
+    assert!(start - end > 2);
+
+    let boundary = layout
+        .split_boundary_at(((end + start) as f64 / 2.0, 200.0).into(), 0)
+        .unwrap();
+    assert!(boundary.side_by_side);
+    // The windows' own edges are theirs
+    assert!(layout
+        .split_boundary_at(((end - 1) as f64, 200.0).into(), 0)
+        .is_none());
+    assert!(layout
+        .split_boundary_at((start as f64, 200.0).into(), 0)
+        .is_none());
+}
+
+#[test]
+fn test_split_boundary_needs_reach_without_gaps() {
+    let (layout, _) = three_columns();
+
+    assert!(layout.split_boundary_at((300.0, 200.0).into(), 0).is_none());
+    assert!(layout.split_boundary_at((300.0, 200.0).into(), 1).is_some());
+}
+
+#[test]
+fn test_split_boundary_prefers_the_innermost_split() {
+    let workspace_rect = Rectangle::from_size((800, 600).into());
+    let mut layout = LayoutTree::new(workspace_rect, 0);
+    let [win1, win2, win3] = [WindowId::new(1), WindowId::new(2), WindowId::new(3)];
+    layout.add_window(win1, SplitDirection::Horizontal);
+    layout.add_window(win2, SplitDirection::Horizontal);
+    layout.insert_beside(win3, win2, SplitDirection::Vertical);
+    layout.calculate_geometries();
+
+    // Where windows 2 and 3 meet in the right column
+    let boundary = layout.split_boundary_at((600.0, 299.0).into(), 4).unwrap();
+    assert!(!boundary.side_by_side);
+    // The left column's edge is still the outer split
+    let boundary = layout.split_boundary_at((400.0, 100.0).into(), 4).unwrap();
+    assert!(boundary.side_by_side);
+}
+
+#[test]
+fn test_dragging_a_boundary_trades_space_between_its_windows() {
+    let (mut layout, windows) = three_columns();
+    let boundary = layout.split_boundary_at((600.0, 200.0).into(), 4).unwrap();
+
+    // Drags are measured from where the boundary was found
+    assert!(layout.drag_split_boundary(boundary, 40));
+    layout.calculate_geometries();
+    assert!(layout.drag_split_boundary(boundary, 60));
+    layout.calculate_geometries();
+
+    assert_eq!(widths(&layout, &windows), vec![300, 360, 240]);
+    // Nothing further to do at the same spot
+    assert!(!layout.drag_split_boundary(boundary, 60));
+}
+
+#[test]
+fn test_dragging_a_boundary_stops_at_the_minimum_size() {
+    let (mut layout, windows) = three_columns();
+    let boundary = layout.split_boundary_at((300.0, 200.0).into(), 4).unwrap();
+
+    assert!(layout.drag_split_boundary(boundary, -1000));
+    layout.calculate_geometries();
+
+    assert_eq!(widths(&layout, &windows), vec![MIN_WINDOW_SIZE, 550, 300]);
+}
+
+#[test]
+fn test_dragged_share_survives_a_relayout() {
+    let (mut layout, windows) = three_columns();
+    let boundary = layout.split_boundary_at((300.0, 200.0).into(), 4).unwrap();
+    assert!(layout.drag_split_boundary(boundary, 90));
+
+    layout.calculate_geometries();
+    layout.calculate_geometries();
+
+    assert_eq!(widths(&layout, &windows), vec![390, 210, 300]);
+}
@@ -0,0 +1,133 @@
+//! Test moving a container selected with `focus parent`
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+const RUN_COMMAND: u32 = 0;
+
+/// Run `commands` through the i3 socket, failing unless every one succeeded
+fn run(stream: &mut UnixStream, commands: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let results = run_command(stream, commands)?;
+    let failed = results
+        .as_array()
+        .ok_or("results are no list")?
+        .iter()
+        .any(|result| result["success"] != true);
+    if failed {
+        return Err(format!("{commands}: {results}").into());
+    }
+    Ok(())
+}
+
+/// Run `commands` through the i3 socket and return the result of each
+fn run_command(
+    stream: &mut UnixStream,
+    commands: &str,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut message = b"i3-ipc".to_vec();
+    message.extend_from_slice(&(commands.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&RUN_COMMAND.to_ne_bytes());
+    message.extend_from_slice(commands.as_bytes());
+    stream.write_all(&message)?;
+
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    let length = u32::from_ne_bytes(header[6..10].try_into()?);
+    let mut payload = vec![0u8; length as usize];
+    stream.read_exact(&mut payload)?;
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+/// Window Left beside a column of Top above Bottom, with Bottom focused
+fn start_with_a_column(
+    name: &str,
+) -> Result<(TestEnv, TestClient, UnixStream), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new(name);
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut i3 = UnixStream::connect(format!("/tmp/stilch-ipc-{name}.i3.sock"))?;
+    i3.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    client.create_window(Some("Left"), Some("red"))?;
+    client.wait_for_window_count(1, "after creating the left window")?;
+    client.create_window(Some("Top"), Some("green"))?;
+    client.wait_for_window_count(2, "after creating the top window")?;
+    run(&mut i3, "splitv")?;
+    client.create_window(Some("Bottom"), Some("blue"))?;
+    client.wait_for_window_count(3, "after creating the bottom window")?;
+    client.sync()?;
+
+    let top = client.find_window("Top")?;
+    let bottom = client.find_window("Bottom")?;
+    assert_eq!(field(&top, "x"), field(&bottom, "x"), "no column to select");
+    Ok((env, client, i3))
+}
+
+fn field(window: &Value, name: &str) -> i64 {
+    window[name].as_i64().unwrap_or(-1)
+}
+
+#[test]
+fn test_selected_container_moves_as_a_whole() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client, mut i3) = start_with_a_column("move-container")?;
+    let left = client.find_window("Left")?;
+
+    run(&mut i3, "focus parent; move left")?;
+    client.sync()?;
+
+    let top = client.find_window("Top")?;
+    let bottom = client.find_window("Bottom")?;
+    assert_eq!(field(&top, "x"), field(&left, "x"));
+    assert_eq!(field(&bottom, "x"), field(&left, "x"));
+    assert!(field(&top, "y") < field(&bottom, "y"));
+    assert!(field(&client.find_window("Left")?, "x") > field(&top, "x"));
+    Ok(())
+}
+
+#[test]
+fn test_selected_container_moves_to_another_workspace() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client, mut i3) = start_with_a_column("move-container-workspace")?;
+
+    run(&mut i3, "focus parent; move container to workspace 3")?;
+    client.sync()?;
+
+    assert_eq!(client.find_window("Left")?["workspace"].as_u64(), Some(1));
+    for title in ["Top", "Bottom"] {
+        assert_eq!(client.find_window(title)?["workspace"].as_u64(), Some(3));
+    }
+
+    // Still a column, now the only thing tiled there
+    client.switch_workspace(2)?;
+    client.sync()?;
+    let top = client.find_window("Top")?;
+    let bottom = client.find_window("Bottom")?;
+    assert_eq!(field(&top, "x"), field(&bottom, "x"));
+    assert!(field(&top, "y") < field(&bottom, "y"));
+    assert_eq!(field(&top, "width"), 3840);
+    Ok(())
+}
+
+#[test]
+fn test_selected_container_stays_on_its_workspace() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client, mut i3) = start_with_a_column("move-container-scratchpad")?;
+
+    run(&mut i3, "focus parent")?;
+    let results = run_command(&mut i3, "move scratchpad")?;
+    assert_eq!(results[0]["success"], false);
+    client.sync()?;
+
+    // Rather than the focused window leaving the column on its own
+    for title in ["Left", "Top", "Bottom"] {
+        let window = client.find_window(title)?;
+        assert_eq!(window["workspace"].as_u64(), Some(1));
+        assert_eq!(window["visible"], true);
+    }
+    Ok(())
+}
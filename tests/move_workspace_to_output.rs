@@ -398,3 +398,44 @@ fn test_move_workspace_no_output_in_direction() -> Result<(), Box<dyn std::error
 
     Ok(())
 }
+
+#[test]
+fn test_move_workspace_to_named_output() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("move-workspace-named");
+    env.cleanup()?;
+
+    env.start_compositor_multi_output(2, 1920, 1080)?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    let mut window = env.start_window("Named", Some("blue"))?;
+    client.wait_for_window_count(1, "after window")?;
+
+    let workspace_output = |client: &TestClient| -> Result<String, Box<dyn std::error::Error>> {
+        let workspaces = client.get_workspaces()?;
+        let ws1 = workspaces
+            .iter()
+            .find(|ws| ws["id"].as_u64() == Some(1))
+            .ok_or("workspace 1 missing")?;
+        Ok(ws1["output"].as_str().unwrap_or_default().to_string())
+    };
+    assert_eq!(workspace_output(&client)?, "output-1");
+
+    // The second output is named by its physical output
+    client.move_workspace_to_named_output("TEST-2")?;
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(workspace_output(&client)?, "output-2");
+
+    client.move_workspace_to_named_output("ascii")?;
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(workspace_output(&client)?, "output-1");
+
+    // An unknown name is an error and leaves the workspace where it is
+    let result = client.move_workspace_to_named_output("NOPE-1");
+    assert!(result.is_err(), "moving to an unknown output should fail");
+    assert_eq!(workspace_output(&client)?, "output-1");
+
+    window.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+// Tests for the modifier standing in for Super in a nested session
+
+use smithay::input::keyboard::ModifiersState;
+use stilch::config::parser::parse_modifiers;
+use stilch::keybindings::remap_logo_modifier;
+
+fn alt() -> ModifiersState {
+    ModifiersState {
+        alt: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_modifiers() {
+    let modifiers = parse_modifiers("Ctrl+Alt").unwrap();
+    assert!(modifiers.ctrl && modifiers.alt);
+    assert!(!modifiers.logo && !modifiers.shift);
+    assert!(parse_modifiers("Hyper").is_err());
+}
+
+#[test]
+fn test_stand_in_becomes_logo() {
+    let remapped = remap_logo_modifier(alt(), alt());
+    assert!(remapped.logo);
+    assert!(!remapped.alt);
+
+    let shifted = ModifiersState {
+        alt: true,
+        shift: true,
+        ..Default::default()
+    };
+    let remapped = remap_logo_modifier(shifted, alt());
+    assert!(remapped.logo && remapped.shift);
+}
+
+#[test]
+fn test_real_logo_is_left_to_host() {
+    let logo = ModifiersState {
+        logo: true,
+        ..Default::default()
+    };
+    let remapped = remap_logo_modifier(logo, alt());
+    assert!(!remapped.logo);
+}
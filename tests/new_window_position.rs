@@ -0,0 +1,129 @@
+// Tests for where new tiled windows land in the container they join
+
+use smithay::utils::Rectangle;
+use stilch::config::NewWindowPosition;
+use stilch::window::WindowId;
+use stilch::workspace::layout::{ContainerLayout, LayoutTree, SplitDirection};
+use stilch::workspace::Workspace;
+
+fn layout_with(windows: &[WindowId]) -> LayoutTree {
+    let mut layout = LayoutTree::new(Rectangle::from_size((1200, 600).into()), 0);
+    for &window in windows {
+        layout.add_window(window, SplitDirection::Horizontal);
+    }
+    layout
+}
+
+#[test]
+fn test_end_appends_after_all_windows() {
+    let [win1, win2, win3] = [1, 2, 3].map(WindowId::new);
+    let mut layout = layout_with(&[win1, win2]);
+
+    layout.insert_window(
+        win3,
+        SplitDirection::Horizontal,
+        NewWindowPosition::End,
+        Some(win1),
+    );
+    assert_eq!(layout.get_windows(), vec![win1, win2, win3]);
+}
+
+#[test]
+fn test_beginning_puts_window_first() {
+    let [win1, win2, win3] = [1, 2, 3].map(WindowId::new);
+    let mut layout = layout_with(&[win1]);
+
+    // Also when the first window is turned into a container
+    layout.insert_window(
+        win2,
+        SplitDirection::Horizontal,
+        NewWindowPosition::Beginning,
+        Some(win1),
+    );
+    assert_eq!(layout.get_windows(), vec![win2, win1]);
+
+    layout.insert_window(
+        win3,
+        SplitDirection::Horizontal,
+        NewWindowPosition::Beginning,
+        Some(win1),
+    );
+    assert_eq!(layout.get_windows(), vec![win3, win2, win1]);
+}
+
+#[test]
+fn test_after_focused_splits_the_focused_window() {
+    let [win1, win2, win3, win4] = [1, 2, 3, 4].map(WindowId::new);
+    let mut layout = layout_with(&[win1, win2, win3]);
+
+    layout.insert_window(
+        win4,
+        SplitDirection::Horizontal,
+        NewWindowPosition::AfterFocused,
+        Some(win1),
+    );
+    assert_eq!(layout.get_windows(), vec![win1, win4, win2, win3]);
+
+    // Takes the slot next to the focused window's
+    layout.calculate_geometries();
+    let first = layout.get_window_geometry(win1).unwrap();
+    let new = layout.get_window_geometry(win4).unwrap();
+    assert_eq!(new.loc.x, first.loc.x + first.size.w);
+}
+
+#[test]
+fn test_after_focused_without_focus_appends() {
+    let [win1, win2, win3] = [1, 2, 3].map(WindowId::new);
+    let mut layout = layout_with(&[win1, win2]);
+
+    layout.insert_window(
+        win3,
+        SplitDirection::Horizontal,
+        NewWindowPosition::AfterFocused,
+        None,
+    );
+    assert_eq!(layout.get_windows(), vec![win1, win2, win3]);
+}
+
+#[test]
+fn test_after_focused_in_tabbed_container() {
+    let [win1, win2, win3, win4] = [1, 2, 3, 4].map(WindowId::new);
+    let mut layout = layout_with(&[win1, win2, win3]);
+    layout.set_container_layout(win1, ContainerLayout::Tabbed);
+
+    layout.insert_window(
+        win4,
+        SplitDirection::Horizontal,
+        NewWindowPosition::AfterFocused,
+        Some(win2),
+    );
+    assert_eq!(layout.get_windows(), vec![win1, win2, win4, win3]);
+
+    // The new tab is the one shown
+    layout.calculate_geometries();
+    let visible = layout.get_visible_geometries();
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].0, win4);
+}
+
+#[test]
+fn test_pending_split_wraps_the_tree_around_the_new_window() {
+    let [win1, win2, win3] = [1, 2, 3].map(WindowId::new);
+    let mut workspace = Workspace::new(0, 0);
+    workspace.new_window_position = NewWindowPosition::Beginning;
+
+    workspace.add_window(win1);
+    workspace.add_window(win2);
+    assert_eq!(workspace.layout.get_windows(), vec![win2, win1]);
+
+    // With a different split pending, the old tree becomes one child and
+    // the new window still goes first
+    workspace.set_next_split(SplitDirection::Vertical);
+    workspace.add_window(win3);
+    assert_eq!(workspace.layout.get_windows(), vec![win3, win2, win1]);
+
+    workspace.relayout();
+    let top = workspace.layout.get_window_geometry(win3).unwrap();
+    let below = workspace.layout.get_window_geometry(win2).unwrap();
+    assert!(top.loc.y < below.loc.y);
+}
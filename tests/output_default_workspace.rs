@@ -0,0 +1,70 @@
+mod common;
+
+use common::{TestClient, TestEnv};
+
+/// Number of the workspace visible on a virtual output, if any
+fn shown_on(client: &TestClient, output: &str) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    Ok(client
+        .get_workspaces()?
+        .into_iter()
+        .find(|ws| ws["visible"].as_bool() == Some(true) && ws["output"].as_str() == Some(output))
+        .and_then(|ws| ws["id"].as_u64()))
+}
+
+#[test]
+fn test_new_outputs_show_first_free_workspace() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("output-default-workspace");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    assert_eq!(shown_on(&client, "output-1")?, Some(1));
+
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+
+    // The second output gets workspace 2 and the first one keeps workspace 1
+    assert_eq!(shown_on(&client, "output-1")?, Some(1));
+    assert_eq!(shown_on(&client, "output-2")?, Some(2));
+
+    client.add_output("HOTPLUG-2", 3840, 0, 1920, 1080)?;
+    client.sync()?;
+
+    assert_eq!(shown_on(&client, "output-1")?, Some(1));
+    assert_eq!(shown_on(&client, "output-2")?, Some(2));
+    assert_eq!(shown_on(&client, "output-3")?, Some(3));
+
+    Ok(())
+}
+
+#[test]
+fn test_new_output_skips_workspaces_held_by_other_outputs() -> Result<(), Box<dyn std::error::Error>>
+{
+    let mut env = TestEnv::new("output-default-workspace-held");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    // Leave a window on workspace 2, hidden behind workspace 1
+    client.switch_workspace(1)?;
+    let mut window = env.start_window("Held", Some("blue"))?;
+    client.wait_for_window_count(1, "after window on workspace 2")?;
+    client.switch_workspace(0)?;
+    assert_eq!(shown_on(&client, "output-1")?, Some(1));
+
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+
+    // Workspace 2 still belongs to the first output
+    assert_eq!(shown_on(&client, "output-2")?, Some(3));
+
+    client.add_output("HOTPLUG-2", 3840, 0, 1920, 1080)?;
+    client.sync()?;
+
+    assert_eq!(shown_on(&client, "output-3")?, Some(4));
+    assert_eq!(shown_on(&client, "output-1")?, Some(1));
+
+    window.kill()?;
+    Ok(())
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+
+/// Layout settings of a visible workspace
+fn layout_of(client: &TestClient, workspace: u64) -> Result<Value, Box<dyn std::error::Error>> {
+    client.get_layout_config()?["workspaces"]
+        .as_array()
+        .ok_or("No workspace areas")?
+        .iter()
+        .find(|area| area["workspace"].as_u64() == Some(workspace))
+        .cloned()
+        .ok_or_else(|| format!("Workspace {workspace} not visible").into())
+}
+
+#[test]
+fn test_workspace_takes_gaps_of_its_output() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("output-gaps");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/output_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut window = env.start_window("Traveller", Some("blue"))?;
+    client.wait_for_window_count(1, "after window on workspace 1")?;
+
+    let ws1 = layout_of(&client, 1)?;
+    assert_eq!(ws1["gap"], 10);
+    assert_eq!(ws1["border_width"], 2);
+
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+
+    // The new output's own workspace uses its overrides
+    let ws2 = layout_of(&client, 2)?;
+    assert_eq!(ws2["gap"], 30);
+    assert_eq!(ws2["border_width"], 5);
+
+    // Workspace 1 picks them up when moved there
+    client.move_workspace_to_output("right")?;
+    client.sync()?;
+    let ws1 = layout_of(&client, 1)?;
+    assert_eq!(ws1["gap"], 30);
+    assert_eq!(ws1["border_width"], 5);
+
+    // And drops them again on the way back
+    client.move_workspace_to_output("left")?;
+    client.sync()?;
+    let ws1 = layout_of(&client, 1)?;
+    assert_eq!(ws1["gap"], 10);
+    assert_eq!(ws1["border_width"], 2);
+
+    window.kill()?;
+    Ok(())
+}
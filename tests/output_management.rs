@@ -0,0 +1,159 @@
+//! Test applying and testing wlr-output-management configurations
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Run the output_config client to the end and return the lines it printed
+fn output_config(env: &TestEnv, args: &[&str]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut child = env.start_client("output_config", args)?;
+    let stdout = child.stdout.take().ok_or("client has no stdout")?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let lines: Vec<String> = BufReader::new(stdout)
+            .lines()
+            .map_while(Result::ok)
+            .collect();
+        let _ = tx.send(lines);
+    });
+
+    match rx.recv_timeout(Duration::from_secs(5)) {
+        Ok(lines) => {
+            child.wait()?;
+            Ok(lines)
+        }
+        Err(_) => {
+            child.kill()?;
+            Err(format!("output_config {args:?} did not finish").into())
+        }
+    }
+}
+
+/// Start the compositor with a second output, HOTPLUG-1, right of the first
+fn start_with_two_outputs(name: &str) -> Result<(TestEnv, TestClient), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new(name);
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/workspace_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+    assert_eq!(client.get_outputs()?.len(), 2);
+    Ok((env, client))
+}
+
+#[test]
+fn test_heads_are_advertised() -> Result<(), Box<dyn std::error::Error>> {
+    let (env, _client) = start_with_two_outputs("output-management-heads")?;
+
+    let lines = output_config(&env, &[])?;
+    assert!(
+        lines.contains(&"HEAD HOTPLUG-1 enabled=1".to_string()),
+        "{lines:?}"
+    );
+    assert!(
+        lines.contains(&"HEAD ascii enabled=1".to_string()),
+        "{lines:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_testing_a_disable_changes_nothing() -> Result<(), Box<dyn std::error::Error>> {
+    let (env, client) = start_with_two_outputs("output-management-test-disable")?;
+
+    let lines = output_config(&env, &["--test", "--disable", "HOTPLUG-1"])?;
+    assert_eq!(lines.last().map(String::as_str), Some("SUCCEEDED"));
+
+    client.sync()?;
+    assert_eq!(client.get_outputs()?.len(), 2);
+    let lines = output_config(&env, &[])?;
+    assert!(
+        lines.contains(&"HEAD HOTPLUG-1 enabled=1".to_string()),
+        "{lines:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_disable_and_enable_an_output() -> Result<(), Box<dyn std::error::Error>> {
+    let (env, client) = start_with_two_outputs("output-management-disable")?;
+
+    let lines = output_config(&env, &["--disable", "HOTPLUG-1"])?;
+    assert_eq!(lines.last().map(String::as_str), Some("SUCCEEDED"));
+    client.sync()?;
+
+    // Gone from the layout, but still there to be enabled again
+    let outputs = client.get_outputs()?;
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0]["x"].as_i64(), Some(0));
+    let lines = output_config(&env, &[])?;
+    assert!(
+        lines.contains(&"HEAD HOTPLUG-1 enabled=0".to_string()),
+        "{lines:?}"
+    );
+
+    let lines = output_config(&env, &["--enable", "HOTPLUG-1"])?;
+    assert_eq!(lines.last().map(String::as_str), Some("SUCCEEDED"));
+    client.sync()?;
+
+    let outputs = client.get_outputs()?;
+    assert_eq!(outputs.len(), 2);
+    assert!(
+        outputs
+            .iter()
+            .any(|output| output["x"].as_i64() == Some(1920)),
+        "{outputs:?}"
+    );
+    // Its assigned workspace comes back with it
+    let workspace = client
+        .get_workspaces()?
+        .into_iter()
+        .find(|ws| ws["id"].as_u64() == Some(5))
+        .ok_or("no workspace 5")?;
+    assert_eq!(workspace["visible"].as_bool(), Some(true));
+    Ok(())
+}
+
+#[test]
+fn test_disabling_every_output_fails() -> Result<(), Box<dyn std::error::Error>> {
+    let (env, client) = start_with_two_outputs("output-management-disable-all")?;
+
+    let lines = output_config(&env, &["--disable", "HOTPLUG-1", "--disable", "ascii"])?;
+    assert_eq!(lines.last().map(String::as_str), Some("FAILED"));
+    let lines = output_config(
+        &env,
+        &["--test", "--disable", "HOTPLUG-1", "--disable", "ascii"],
+    )?;
+    assert_eq!(lines.last().map(String::as_str), Some("FAILED"));
+
+    client.sync()?;
+    assert_eq!(client.get_outputs()?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_scale_change_resizes_the_virtual_output() -> Result<(), Box<dyn std::error::Error>> {
+    let (env, client) = start_with_two_outputs("output-management-scale")?;
+
+    let lines = output_config(&env, &["--scale", "HOTPLUG-1", "2"])?;
+    assert_eq!(lines.last().map(String::as_str), Some("SUCCEEDED"));
+    client.sync()?;
+
+    let outputs = client.get_outputs()?;
+    let hotplugged = outputs
+        .iter()
+        .find(|output| output["x"].as_i64() == Some(1920))
+        .ok_or("no virtual output on HOTPLUG-1")?;
+    assert_eq!(hotplugged["width"].as_i64(), Some(960));
+    assert_eq!(hotplugged["height"].as_i64(), Some(540));
+    Ok(())
+}
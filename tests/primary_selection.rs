@@ -0,0 +1,79 @@
+//! Test middle-click paste (primary selection) between workspaces
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_primary_selection_across_workspaces() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("primary-selection");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let text = "selected on workspace 1";
+
+    // The source window offers the primary selection as soon as it is focused
+    let mut source = env.start_window_with_args("Source", "blue", &["--set-primary", text])?;
+    client.wait_for_window_count(1, "after starting source window")?;
+    thread::sleep(Duration::from_millis(200));
+
+    client.switch_workspace(1)?;
+
+    let mut sink = env.start_window_with_args("Sink", "green", &["--paste-primary"])?;
+    client.wait_for_window_count(2, "after starting sink window")?;
+
+    let windows = client.get_windows()?;
+    let workspaces: Vec<u64> = windows
+        .iter()
+        .filter_map(|w| w["workspace"].as_u64())
+        .collect();
+    assert_eq!(workspaces.len(), 2);
+    assert_ne!(
+        workspaces[0], workspaces[1],
+        "Source and sink should be on different workspaces"
+    );
+
+    // Collect what the sink pasted
+    let stdout = sink.stdout.take().ok_or("Sink has no stdout")?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let expected = format!("PRIMARY_SELECTION: {text}");
+    let mut pasted = Vec::new();
+    while let Ok(line) = rx.recv_timeout(Duration::from_secs(5)) {
+        let done = line == expected;
+        pasted.push(line);
+        if done {
+            break;
+        }
+    }
+
+    source.kill()?;
+    sink.kill()?;
+
+    assert_eq!(
+        pasted.last(),
+        Some(&expected),
+        "Sink should paste the source's primary selection, got {pasted:?}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Test that a burst of commits leads to few renders and an idle
+//! compositor stops rendering
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::io::{BufRead, BufReader};
+use std::thread;
+use std::time::Duration;
+
+const COMMITS: u32 = 500;
+
+#[test]
+fn test_commit_storm_is_coalesced() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("render-coalescing");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    let mut window =
+        env.start_window_with_args("Storm", "blue", &["--commit-storm", &COMMITS.to_string()])?;
+    let stdout = window.stdout.take().ok_or("Window has no stdout")?;
+    let done = BufReader::new(stdout)
+        .lines()
+        .map_while(Result::ok)
+        .any(|line| line == "COMMIT_STORM_DONE");
+    assert!(done, "Window exited before finishing its commits");
+    client.sync()?;
+
+    let after_storm = client.render_count()?;
+    assert!(
+        after_storm < u64::from(COMMITS) / 10,
+        "{COMMITS} commits led to {after_storm} renders"
+    );
+
+    // Nothing changes on screen, so nothing gets drawn
+    thread::sleep(Duration::from_millis(300));
+    client.sync()?;
+    assert_eq!(client.render_count()?, after_storm);
+
+    window.kill()?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+
+/// Layout area and gap of the only visible workspace
+fn layout(client: &TestClient) -> Result<Value, Box<dyn std::error::Error>> {
+    client.get_layout_config()?["workspaces"]
+        .as_array()
+        .and_then(|areas| areas.first())
+        .cloned()
+        .ok_or_else(|| "No visible workspace".into())
+}
+
+fn geometry(value: &Value) -> (i64, i64, i64, i64) {
+    let field = |name: &str| value[name].as_i64().unwrap_or(-1);
+    (field("x"), field("y"), field("width"), field("height"))
+}
+
+#[test]
+fn test_gaps_change_at_runtime() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("runtime-gaps");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.create_window(Some("first"), None)?;
+    let full = geometry(&layout(&client)?);
+    assert_eq!(geometry(&client.find_window("first")?), full);
+
+    // Outer gaps shrink the area on every edge
+    client.set_gaps("outer", 20)?;
+    client.sync()?;
+    let (x, y, width, height) = full;
+    let inset = (x + 20, y + 20, width - 40, height - 40);
+    assert_eq!(geometry(&layout(&client)?), inset);
+    assert_eq!(geometry(&client.find_window("first")?), inset);
+
+    // An edge of its own overrides the outer gap there
+    client.set_gaps("top", 50)?;
+    client.sync()?;
+    let inset = (x + 20, y + 50, width - 40, height - 70);
+    assert_eq!(geometry(&client.find_window("first")?), inset);
+
+    // Inner gaps separate the windows
+    client.set_gaps("inner", 30)?;
+    client.create_window(Some("second"), None)?;
+    client.find_window("second")?;
+    client.sync()?;
+    assert_eq!(layout(&client)?["gap"], 30);
+    let first = client.find_window("first")?;
+    let second = client.find_window("second")?;
+    assert_eq!(
+        second["x"].as_i64().unwrap_or(-1),
+        first["x"].as_i64().unwrap_or(-1) + first["width"].as_i64().unwrap_or(-1) + 30
+    );
+
+    assert!(client.set_gaps("inner", -5).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_smart_gaps_leave_out_gaps_for_a_lone_window() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("smart-gaps");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/smart_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.create_window(Some("alone"), None)?;
+    client.sync()?;
+
+    // A lone window gets the whole workspace
+    let area = layout(&client)?;
+    assert_eq!(area["gap"], 0);
+    let (x, y, width, height) = geometry(&area);
+    let alone = geometry(&client.find_window("alone")?);
+    assert_eq!(alone, (x, y, width, height));
+
+    // With a second window both gaps are back
+    client.create_window(Some("company"), None)?;
+    client.find_window("company")?;
+    client.sync()?;
+    let area = layout(&client)?;
+    assert_eq!(area["gap"], 10);
+    assert_eq!(geometry(&area), (x + 5, y + 5, width - 10, height - 10));
+    let alone = client.find_window("alone")?;
+    assert_eq!(alone["x"].as_i64(), Some(x + 5));
+    assert_eq!(alone["y"].as_i64(), Some(y + 5));
+
+    Ok(())
+}
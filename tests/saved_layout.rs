@@ -0,0 +1,91 @@
+//! Test that windows take the place of placeholders from `append_layout`
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+const RUN_COMMAND: u32 = 0;
+
+/// Run `commands` through the i3 socket, failing unless every one succeeded
+fn run(stream: &mut UnixStream, commands: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut message = b"i3-ipc".to_vec();
+    message.extend_from_slice(&(commands.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&RUN_COMMAND.to_ne_bytes());
+    message.extend_from_slice(commands.as_bytes());
+    stream.write_all(&message)?;
+
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    let length = u32::from_ne_bytes(header[6..10].try_into()?);
+    let mut payload = vec![0u8; length as usize];
+    stream.read_exact(&mut payload)?;
+    let results: Value = serde_json::from_slice(&payload)?;
+    let failed = results
+        .as_array()
+        .ok_or("results are no list")?
+        .iter()
+        .any(|result| result["success"] != true);
+    if failed {
+        return Err(format!("{commands}: {results}").into());
+    }
+    Ok(())
+}
+
+fn field(window: &Value, name: &str) -> i64 {
+    window[name].as_i64().unwrap_or(-1)
+}
+
+#[test]
+fn test_appended_layout_swallows_matching_windows() -> Result<(), Box<dyn std::error::Error>> {
+    let name = "saved-layout";
+    let mut env = TestEnv::new(name);
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", "tests/test_configs/no_gaps.conf"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let mut i3 = UnixStream::connect(format!("/tmp/stilch-ipc-{name}.i3.sock"))?;
+    i3.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let path = std::env::temp_dir().join("stilch-test-saved-layout.json");
+    let _ = std::fs::remove_file(&path);
+
+    // Two windows side by side on workspace 1
+    client.create_window(Some("Left"), Some("red"))?;
+    client.wait_for_window_count(1, "after creating the left window")?;
+    client.create_window(Some("Right"), Some("green"))?;
+    client.wait_for_window_count(2, "after creating the right window")?;
+    run(&mut i3, &format!("save_layout {}", path.display()))?;
+
+    // Placeholders for both on workspace 2 keep their halves free
+    client.switch_workspace(1)?;
+    run(&mut i3, &format!("append_layout {}", path.display()))?;
+    let _ = std::fs::remove_file(&path);
+    client.create_window(Some("First"), Some("blue"))?;
+    client.wait_for_window_count(3, "after creating the first window")?;
+    client.sync()?;
+
+    let first = client.find_window("First")?;
+    assert_eq!(first["workspace"].as_u64(), Some(2));
+    assert_eq!(field(&first, "x"), 0);
+    assert_eq!(field(&first, "width"), 1920);
+
+    // A matching window opened elsewhere moves into the other placeholder
+    client.switch_workspace(2)?;
+    client.create_window(Some("Second"), Some("yellow"))?;
+    client.wait_for_window_count(4, "after creating the second window")?;
+    client.sync()?;
+
+    let second = client.find_window("Second")?;
+    assert_eq!(second["workspace"].as_u64(), Some(2));
+    client.switch_workspace(1)?;
+    client.sync()?;
+    let second = client.find_window("Second")?;
+    assert_eq!(field(&second, "x"), 1920);
+    assert_eq!(field(&second, "width"), 1920);
+    assert_eq!(field(&client.find_window("First")?, "width"), 1920);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{verify_window_geometry, TestClient, TestEnv};
+use stilch::test_ipc::solid_window::{format_color, parse_color};
+
+#[test]
+fn test_parse_color() {
+    assert_eq!(parse_color("red"), Some([0xff, 0, 0, 0xff]));
+    assert_eq!(parse_color("#12ab34"), Some([0x12, 0xab, 0x34, 0xff]));
+    assert_eq!(parse_color("#12ab3480"), Some([0x12, 0xab, 0x34, 0x80]));
+    assert_eq!(parse_color("12ab34"), None);
+    assert_eq!(parse_color("#12ab3"), None);
+    assert_eq!(parse_color("#12ab3g"), None);
+    assert_eq!(parse_color("purple"), None);
+
+    assert_eq!(format_color([0xff, 0, 0, 0xff]), "#ff0000");
+    assert_eq!(format_color([0x12, 0xab, 0x34, 0x80]), "#12ab3480");
+}
+
+#[test]
+fn test_create_solid_color_windows() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("solid-window");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    // Replied to once mapped, with the id the window is known by
+    let red = client.create_window(None, Some("red"))?;
+    assert_eq!(client.get_window(red)?["title"], "solid-#ff0000");
+    let green = client.create_window(None, Some("#00ff0080"))?;
+    assert_ne!(red, green);
+    client.wait_for_window_count(2, "after creating translucent green window")?;
+
+    assert!(
+        client.create_window(None, Some("purple")).is_err(),
+        "Unknown colors should be rejected"
+    );
+
+    // Colors show up once the clients attach their buffers
+    let mut windows = client.get_windows()?;
+    for _ in 0..50 {
+        if windows.iter().all(|w| w["color"].is_string()) {
+            break;
+        }
+        client.sync()?;
+        windows = client.get_windows()?;
+    }
+
+    // Side by side like any other client, with their colors reported back
+    windows.sort_by_key(|w| w["x"].as_i64());
+    verify_window_geometry(&windows[0], 0, 0, 1920, 2160)?;
+    verify_window_geometry(&windows[1], 1920, 0, 1920, 2160)?;
+    assert_eq!(windows[0]["color"], "#ff0000");
+    assert_eq!(windows[1]["color"], "#00ff0080");
+
+    Ok(())
+}
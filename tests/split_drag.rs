@@ -0,0 +1,111 @@
+//! Test dragging the gap between tiled windows to resize them
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+
+/// Start the compositor with two tiled windows side by side, Left and Right
+fn start_with_two_windows(
+    name: &str,
+    config: &str,
+) -> Result<(TestEnv, TestClient), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new(name);
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", config])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.create_window(Some("Left"), Some("red"))?;
+    client.wait_for_window_count(1, "after creating the left window")?;
+    client.create_window(Some("Right"), Some("blue"))?;
+    client.wait_for_window_count(2, "after creating the right window")?;
+    client.sync()?;
+    Ok((env, client))
+}
+
+fn field(window: &Value, name: &str) -> i32 {
+    window[name].as_i64().unwrap_or(-1) as i32
+}
+
+fn right_edge(window: &Value) -> i32 {
+    field(window, "x") + field(window, "width")
+}
+
+fn middle_y(window: &Value) -> i32 {
+    field(window, "y") + field(window, "height") / 2
+}
+
+#[test]
+fn test_dragging_the_gap_resizes_both_windows() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client) =
+        start_with_two_windows("split-drag-gap", "tests/test_configs/with_gaps.conf")?;
+    let left = client.find_window("Left")?;
+    let right = client.find_window("Right")?;
+    assert!(field(&right, "x") - right_edge(&left) > 2, "no gap to drag");
+
+    let start = (
+        (right_edge(&left) + field(&right, "x")) / 2,
+        middle_y(&left),
+    );
+    client.drag("left", start, (start.0 + 100, start.1), &[])?;
+    client.sync()?;
+
+    let resized_left = client.find_window("Left")?;
+    let resized_right = client.find_window("Right")?;
+    assert_eq!(field(&resized_left, "width"), field(&left, "width") + 100);
+    assert_eq!(field(&resized_right, "width"), field(&right, "width") - 100);
+    assert_eq!(field(&resized_right, "x"), field(&right, "x") + 100);
+    Ok(())
+}
+
+#[test]
+fn test_dragging_next_to_the_gap_leaves_the_windows_alone() -> Result<(), Box<dyn std::error::Error>>
+{
+    let (_env, client) = start_with_two_windows(
+        "split-drag-window-edge",
+        "tests/test_configs/with_gaps.conf",
+    )?;
+    let left = client.find_window("Left")?;
+    let right = client.find_window("Right")?;
+
+    // Inside each window, a couple of pixels from the gap
+    for x in [right_edge(&left) - 2, field(&right, "x") + 1] {
+        client.drag(
+            "left",
+            (x, middle_y(&left)),
+            (x + 100, middle_y(&left)),
+            &[],
+        )?;
+        client.sync()?;
+    }
+
+    assert_eq!(
+        field(&client.find_window("Left")?, "width"),
+        field(&left, "width")
+    );
+    assert_eq!(
+        field(&client.find_window("Right")?, "width"),
+        field(&right, "width")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_windows_without_a_gap_have_no_boundary_to_drag() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client) =
+        start_with_two_windows("split-drag-no-gap", "tests/test_configs/no_gaps.conf")?;
+    let left = client.find_window("Left")?;
+    let right = client.find_window("Right")?;
+    assert_eq!(right_edge(&left), field(&right, "x"));
+
+    // Right where the two windows meet
+    let start = (right_edge(&left), middle_y(&left));
+    client.drag("left", start, (start.0 + 100, start.1), &[])?;
+    client.sync()?;
+
+    assert_eq!(
+        field(&client.find_window("Left")?, "width"),
+        field(&left, "width")
+    );
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// Tests for the window opened right after a split command landing in that
+// split, however focus moved while it was starting
+
+use stilch::window::WindowId;
+use stilch::workspace::layout::SplitDirection;
+use stilch::workspace::Workspace;
+
+fn geometry(
+    workspace: &Workspace,
+    id: WindowId,
+) -> smithay::utils::Rectangle<i32, smithay::utils::Logical> {
+    workspace.layout.get_window_geometry(id).unwrap()
+}
+
+#[test]
+fn test_split_then_spawn_lands_below_split_window() {
+    let [first, second, spawned] = [1, 2, 3].map(WindowId::new);
+    let mut workspace = Workspace::new(0, 0);
+    workspace.add_window(first);
+    workspace.add_window(second);
+
+    workspace.set_focus(Some(first));
+    workspace.set_next_split(SplitDirection::Vertical);
+    // Focus follows the pointer elsewhere before the client maps
+    workspace.set_focus(Some(second));
+    workspace.add_window(spawned);
+
+    let (first, second, spawned) = (
+        geometry(&workspace, first),
+        geometry(&workspace, second),
+        geometry(&workspace, spawned),
+    );
+    assert_eq!(spawned.loc.x, first.loc.x);
+    assert_eq!(spawned.size.w, first.size.w);
+    assert!(spawned.loc.y > first.loc.y);
+    assert_eq!(second.size.h, workspace.area.size.h);
+}
+
+#[test]
+fn test_split_in_same_direction_opens_next_to_split_window() {
+    let [a, b, c, spawned] = [1, 2, 3, 4].map(WindowId::new);
+    let mut workspace = Workspace::new(0, 0);
+    for id in [a, b, c] {
+        workspace.add_window(id);
+    }
+
+    workspace.set_focus(Some(a));
+    workspace.set_next_split(SplitDirection::Horizontal);
+    workspace.set_focus(Some(c));
+    workspace.add_window(spawned);
+
+    assert_eq!(workspace.layout.get_windows(), vec![a, spawned, b, c]);
+}
+
+#[test]
+fn test_only_the_next_window_fills_the_split() {
+    let [a, b, spawned, later] = [1, 2, 3, 4].map(WindowId::new);
+    let mut workspace = Workspace::new(0, 0);
+    workspace.add_window(a);
+    workspace.add_window(b);
+
+    workspace.set_focus(Some(a));
+    workspace.set_next_split(SplitDirection::Horizontal);
+    workspace.add_window(spawned);
+    workspace.set_focus(Some(b));
+    workspace.add_window(later);
+
+    assert_eq!(workspace.layout.get_windows(), vec![a, spawned, b, later]);
+}
+
+#[test]
+fn test_floating_window_leaves_split_to_next_one() {
+    let [a, b, dialog, spawned] = [1, 2, 3, 4].map(WindowId::new);
+    let mut workspace = Workspace::new(0, 0);
+    workspace.add_window(a);
+    workspace.add_window(b);
+
+    workspace.set_focus(Some(a));
+    workspace.set_next_split(SplitDirection::Horizontal);
+    // A dialog maps first and is floated once it's recognized
+    workspace.add_window(dialog);
+    workspace.set_window_floating(dialog, true);
+    workspace.add_window(spawned);
+
+    assert_eq!(workspace.layout.get_windows(), vec![a, spawned, b]);
+}
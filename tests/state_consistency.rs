@@ -0,0 +1,72 @@
+//! Test that the compositor's internal state stays consistent across operations
+
+mod common;
+
+use common::{TestClient, TestEnv};
+
+fn assert_consistent(client: &TestClient, after: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let errors = client.get_consistency_errors()?;
+    assert!(
+        errors.is_empty(),
+        "State should be consistent {after}, got {errors:#?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_state_consistent_after_window_operations() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("state-consistency");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    // The validator is compiled out of release builds of the compositor
+    if let Err(err) = client.get_consistency_errors() {
+        if err.to_string().contains("debug builds") {
+            println!("Skipping: {err}");
+            return Ok(());
+        }
+        return Err(err);
+    }
+    assert_consistent(&client, "at startup")?;
+
+    let mut window1 = env.start_window("First", Some("red"))?;
+    client.wait_for_window_count(1, "after first window")?;
+    let mut window2 = env.start_window("Second", Some("blue"))?;
+    client.wait_for_window_count(2, "after second window")?;
+    let mut window3 = env.start_window("Third", Some("green"))?;
+    client.wait_for_window_count(3, "after third window")?;
+    assert_consistent(&client, "after opening three windows")?;
+
+    let response = client.send_command(&serde_json::json!({
+        "type": "MoveWindowToWorkspace",
+        "window_id": 2,
+        "workspace": 1
+    }))?;
+    assert_eq!(response["type"].as_str(), Some("Success"));
+    assert_consistent(&client, "after moving a window to workspace 2")?;
+
+    client.switch_workspace(1)?;
+    assert_consistent(&client, "after switching to workspace 2")?;
+
+    client.send_command(&serde_json::json!({"type": "KillFocusedWindow"}))?;
+    client.wait_for_window_count(2, "after closing the moved window")?;
+    assert_consistent(&client, "after closing a window")?;
+
+    client.switch_workspace(0)?;
+    assert_consistent(&client, "after switching back")?;
+
+    window1.kill()?;
+    window2.kill()?;
+    window3.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+// Tests for the space tabbed and stacked containers reserve for their bars
+
+use smithay::utils::Rectangle;
+use stilch::tab_bar::{TabBarSize, TabTextCache};
+use stilch::window::WindowId;
+use stilch::workspace::layout::{ContainerLayout, LayoutTree, SplitDirection};
+
+fn visible_geometry(
+    layout: ContainerLayout,
+    size: TabBarSize,
+) -> Rectangle<i32, smithay::utils::Logical> {
+    let mut tree = LayoutTree::new(Rectangle::from_size((800, 600).into()), 0);
+    tree.set_tab_bar(size);
+    for id in 1..=3 {
+        tree.add_window(WindowId::new(id), SplitDirection::Horizontal);
+    }
+    tree.set_container_layout(WindowId::new(1), layout);
+    tree.calculate_geometries();
+
+    let geometries = tree.get_visible_geometries();
+    assert_eq!(geometries.len(), 1);
+    geometries[0].1
+}
+
+#[test]
+fn test_tabbed_container_reserves_bar_and_gap() {
+    let geometry = visible_geometry(ContainerLayout::Tabbed, TabBarSize { height: 24, gap: 4 });
+    assert_eq!(geometry.loc.y, 28);
+    assert_eq!(geometry.size.h, 600 - 28);
+}
+
+#[test]
+fn test_stacked_container_reserves_a_bar_per_window() {
+    let geometry = visible_geometry(ContainerLayout::Stacked, TabBarSize { height: 20, gap: 2 });
+    assert_eq!(geometry.loc.y, 3 * 20 + 2);
+    assert_eq!(geometry.size.h, 600 - 62);
+}
+
+#[test]
+fn test_bar_height_follows_font_size() {
+    let mut cache = TabTextCache::new();
+    let small = cache.bar_height(8.0, 1.0);
+    let large = cache.bar_height(20.0, 1.0);
+    assert!(small > 0);
+    assert!(large > small, "{large} should be taller than {small}");
+    assert_eq!(cache.bar_height(20.0, 1.0), large);
+}
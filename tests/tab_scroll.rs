@@ -0,0 +1,40 @@
+// Tests for turning scroll over tab bars into tab switches
+
+use stilch::input::tab_scroll::{TabScroll, DISTANCE_PER_TAB, V120_PER_TAB};
+
+#[test]
+fn test_one_notch_moves_one_tab() {
+    let mut scroll = TabScroll::default();
+    assert_eq!(scroll.scroll(120.0, V120_PER_TAB, 1000), 1);
+    assert_eq!(scroll.scroll(-120.0, V120_PER_TAB, 1050), -1);
+    assert_eq!(scroll.scroll(240.0, V120_PER_TAB, 1100), 2);
+}
+
+#[test]
+fn test_high_resolution_wheel_adds_up_to_a_notch() {
+    let mut scroll = TabScroll::default();
+    for time in [1000, 1010, 1020] {
+        assert_eq!(scroll.scroll(30.0, V120_PER_TAB, time), 0);
+    }
+    assert_eq!(scroll.scroll(30.0, V120_PER_TAB, 1030), 1);
+    assert_eq!(scroll.scroll(30.0, V120_PER_TAB, 1040), 0);
+}
+
+#[test]
+fn test_pause_or_reversal_starts_over() {
+    let mut scroll = TabScroll::default();
+    assert_eq!(scroll.scroll(10.0, DISTANCE_PER_TAB, 1000), 0);
+    // Long after, the leftover 10 no longer counts
+    assert_eq!(scroll.scroll(10.0, DISTANCE_PER_TAB, 2000), 0);
+    // Reversing drops what was scrolled the other way
+    assert_eq!(scroll.scroll(-10.0, DISTANCE_PER_TAB, 2010), 0);
+    assert_eq!(scroll.scroll(-10.0, DISTANCE_PER_TAB, 2020), -1);
+}
+
+#[test]
+fn test_reset_drops_leftover() {
+    let mut scroll = TabScroll::default();
+    assert_eq!(scroll.scroll(10.0, DISTANCE_PER_TAB, 1000), 0);
+    scroll.reset();
+    assert_eq!(scroll.scroll(10.0, DISTANCE_PER_TAB, 1010), 0);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+
+#[test]
+fn test_sync_answers_in_order() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("test-sync");
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--ascii-size", "80x24"])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let response = client.send_command(&serde_json::json!({"type": "Sync"}))?;
+    assert_eq!(response["type"], "Success", "{response}");
+
+    // Commands sent behind a Sync wait for it and are answered after it
+    let mut stream = UnixStream::connect(&env.test_socket)?;
+    stream.write_all(b"{\"type\":\"Sync\"}\n{\"type\":\"GetWindows\"}\n")?;
+    let mut lines = BufReader::new(stream.try_clone()?).lines();
+    let first: serde_json::Value = serde_json::from_str(&lines.next().ok_or("no reply")??)?;
+    let second: serde_json::Value = serde_json::from_str(&lines.next().ok_or("no reply")??)?;
+    assert_eq!(first["type"], "Success", "{first}");
+    assert_eq!(second["type"], "Windows", "{second}");
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+// Tests for how virtual outputs follow their physical outputs when those are
+// moved or rescaled
+
+use std::collections::HashMap;
+
+use smithay::{
+    output::{Output, PhysicalProperties, Subpixel},
+    utils::{Logical, Rectangle},
+};
+use stilch::virtual_output::{SplitType, VirtualOutputManager};
+
+fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+    Rectangle::new((x, y).into(), (w, h).into())
+}
+
+fn output(name: &str) -> Output {
+    Output::new(
+        name.to_string(),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: "Test".to_string(),
+            model: "Test".to_string(),
+        },
+    )
+}
+
+#[test]
+fn test_merged_output_follows_rescaled_member() {
+    // Two 1920x1080 monitors merged into one virtual output; the right one
+    // goes to scale 2 and shrinks to 960x540 logical pixels
+    let left = output("left");
+    let right = output("right");
+    let mut manager = VirtualOutputManager::new();
+    let id = manager.create_virtual_output(
+        "merged".to_string(),
+        vec![left.clone(), right.clone()],
+        rect(0, 0, 3840, 1080),
+    );
+
+    let old = HashMap::from([(right.clone(), rect(1920, 0, 1920, 1080))]);
+    let new = HashMap::from([
+        (left, rect(0, 0, 1920, 1080)),
+        (right, rect(1920, 0, 960, 540)),
+    ]);
+    manager.relocate_physical_outputs(&old, &new);
+
+    assert_eq!(
+        manager.get(id).unwrap().logical_region(),
+        rect(0, 0, 2880, 1080)
+    );
+}
+
+#[test]
+fn test_split_output_keeps_its_proportions() {
+    let monitor = output("monitor");
+    let mut manager = VirtualOutputManager::new();
+    let ids = manager.split_physical(
+        monitor.clone(),
+        rect(0, 0, 3840, 1080),
+        SplitType::Horizontal,
+        2,
+    );
+    let regions: Vec<_> = ids
+        .iter()
+        .map(|id| manager.get(*id).unwrap().logical_region())
+        .collect();
+
+    // Moved right and rescaled to half its size
+    let old = HashMap::from([(monitor.clone(), rect(0, 0, 3840, 1080))]);
+    let new = HashMap::from([(monitor, rect(100, 0, 1920, 540))]);
+    manager.relocate_physical_outputs(&old, &new);
+
+    for (id, region) in ids.iter().zip(regions) {
+        let moved = manager.get(*id).unwrap().logical_region();
+        assert_eq!(moved.loc.x, 100 + region.loc.x / 2);
+        assert_eq!(moved.size.w, region.size.w / 2);
+        assert_eq!(moved.size.h, 540);
+    }
+}
+
+#[test]
+fn test_unchanged_outputs_stay_put() {
+    let left = output("left");
+    let right = output("right");
+    let mut manager = VirtualOutputManager::new();
+    let left_id = manager.create_from_physical(left.clone(), rect(0, 0, 1920, 1080));
+    let right_id = manager.create_from_physical(right.clone(), rect(1920, 0, 1920, 1080));
+
+    let old = HashMap::from([(right.clone(), rect(1920, 0, 1920, 1080))]);
+    let new = HashMap::from([
+        (left, rect(0, 0, 1920, 1080)),
+        (right, rect(1920, 200, 1920, 1080)),
+    ]);
+    manager.relocate_physical_outputs(&old, &new);
+
+    assert_eq!(
+        manager.get(left_id).unwrap().logical_region(),
+        rect(0, 0, 1920, 1080)
+    );
+    assert_eq!(
+        manager.get(right_id).unwrap().logical_region(),
+        rect(1920, 200, 1920, 1080)
+    );
+}
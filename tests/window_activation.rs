@@ -0,0 +1,93 @@
+//! Test what each focus_on_window_activation mode does with a request
+
+mod common;
+
+use common::{TestClient, TestEnv};
+
+/// Start a compositor with `focus_on_window_activation mode`. Window "A" is
+/// on the hidden workspace 1, "B" and focused "C" on the shown workspace 2.
+fn start(mode: &str) -> Result<(TestEnv, TestClient, [u64; 3]), Box<dyn std::error::Error>> {
+    let config = format!("/tmp/stilch-test-activation-{mode}.conf");
+    std::fs::write(
+        &config,
+        format!(
+            "gaps inner 0\ngaps outer 0\ndefault_border pixel 0\n\
+             focus_on_window_activation {mode}\nset $mod Mod4\n"
+        ),
+    )?;
+
+    let mut env = TestEnv::new(&format!("activation-{mode}"));
+    env.cleanup()?;
+    env.start_compositor(&["--test", "--config", &config])?;
+    let client = TestClient::new(&env.test_socket);
+
+    let a = client.create_window(Some("A"), None)?;
+    client.switch_workspace(1)?;
+    let b = client.create_window(Some("B"), None)?;
+    let c = client.create_window(Some("C"), None)?;
+    client.wait_for_focus(c, "after creating C")?;
+    Ok((env, client, [a, b, c]))
+}
+
+fn request_activation(client: &TestClient, id: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let response =
+        client.send_command(&serde_json::json!({"type": "RequestActivation", "id": id}))?;
+    if response["type"] == "Error" {
+        return Err(format!("RequestActivation failed: {response}").into());
+    }
+    client.sync()
+}
+
+fn is_urgent(client: &TestClient, id: u64) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(client.get_window(id)?["urgent"] == true)
+}
+
+#[test]
+fn test_smart_focuses_shown_windows_and_marks_hidden_ones() -> Result<(), Box<dyn std::error::Error>>
+{
+    let (_env, client, [a, b, _]) = start("smart")?;
+
+    request_activation(&client, b)?;
+    assert_eq!(client.get_focused_window()?, Some(b));
+    assert!(!is_urgent(&client, b)?);
+
+    request_activation(&client, a)?;
+    assert_eq!(client.get_focused_window()?, Some(b));
+    assert!(is_urgent(&client, a)?);
+    Ok(())
+}
+
+#[test]
+fn test_urgent_only_marks_windows() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client, [a, b, c]) = start("urgent")?;
+
+    request_activation(&client, b)?;
+    request_activation(&client, a)?;
+    assert_eq!(client.get_focused_window()?, Some(c));
+    assert!(is_urgent(&client, b)?);
+    assert!(is_urgent(&client, a)?);
+    Ok(())
+}
+
+#[test]
+fn test_focus_switches_to_the_window() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client, [a, _, _]) = start("focus")?;
+
+    request_activation(&client, a)?;
+    assert_eq!(client.get_focused_window()?, Some(a));
+    assert!(!is_urgent(&client, a)?);
+    assert_eq!(client.get_window(a)?["visible"], true);
+    Ok(())
+}
+
+#[test]
+fn test_none_ignores_the_request() -> Result<(), Box<dyn std::error::Error>> {
+    let (_env, client, [a, b, c]) = start("none")?;
+
+    request_activation(&client, b)?;
+    request_activation(&client, a)?;
+    assert_eq!(client.get_focused_window()?, Some(c));
+    assert!(!is_urgent(&client, b)?);
+    assert!(!is_urgent(&client, a)?);
+    Ok(())
+}
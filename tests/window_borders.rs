@@ -0,0 +1,171 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+
+/// Create a solid color window that asks stilch for server-side decorations
+fn create_decorated_window(
+    client: &TestClient,
+    title: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let response = client.send_command(&serde_json::json!({
+        "type": "CreateWindow",
+        "width": 800,
+        "height": 600,
+        "title": title,
+        "server_side_decorations": true
+    }))?;
+    if response.get("type").and_then(|t| t.as_str()) == Some("Error") {
+        return Err(format!("CreateWindow failed: {response}").into());
+    }
+    Ok(())
+}
+
+/// The border rects drawn around a window, `[x, y, width, height]`
+fn borders(window: &Value) -> Vec<[i64; 4]> {
+    window["borders"]
+        .as_array()
+        .map(|rects| {
+            rects
+                .iter()
+                .filter_map(|rect| {
+                    let rect: Vec<i64> =
+                        rect.as_array()?.iter().filter_map(Value::as_i64).collect();
+                    rect.try_into().ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Wait until the window titled `title` has drawn `expected`, which only
+/// happens once the client committed at the size its border leaves it
+fn wait_for_borders(
+    client: &TestClient,
+    title: &str,
+    expected: &[[i64; 4]],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let drawn = borders(&client.find_window(title)?);
+        if drawn == expected {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("{title} drew borders {drawn:?}, expected {expected:?}").into());
+        }
+        client.sync()?;
+    }
+}
+
+#[test]
+fn test_hide_edge_borders_draws_only_inner_edges() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("edge-borders");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/edge_borders.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    // Alone on the output every edge touches the screen edge
+    create_decorated_window(&client, "left")?;
+    client.wait_for_window_count(1, "after creating the first window")?;
+    wait_for_borders(&client, "left", &[])?;
+
+    // Side by side only the edges between them are drawn, and the clients
+    // are configured without the hidden ones
+    create_decorated_window(&client, "right")?;
+    client.wait_for_window_count(2, "after creating the second window")?;
+    wait_for_borders(&client, "left", &[[1916, 0, 4, 2160]])?;
+    wait_for_borders(&client, "right", &[[0, 0, 4, 2160]])?;
+
+    // The border is drawn inside the layout slot, not around it
+    let left = client.find_window("left")?;
+    assert_eq!(
+        (left["x"].as_i64(), left["width"].as_i64()),
+        (Some(0), Some(1920))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_smart_borders_follow_the_window_count() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("smart-borders");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/smart_borders.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let all_edges = [
+        [0, 0, 1920, 4],
+        [0, 2156, 1920, 4],
+        [0, 4, 4, 2152],
+        [1916, 4, 4, 2152],
+    ];
+
+    // A lone window goes without
+    create_decorated_window(&client, "first")?;
+    client.wait_for_window_count(1, "after creating the first window")?;
+    wait_for_borders(&client, "first", &[])?;
+
+    // A second one brings the borders back on both
+    create_decorated_window(&client, "second")?;
+    client.wait_for_window_count(2, "after creating the second window")?;
+    wait_for_borders(&client, "first", &all_edges)?;
+    wait_for_borders(&client, "second", &all_edges)?;
+
+    // And closing it takes them away again
+    let second = client.find_window("second")?["id"]
+        .as_u64()
+        .ok_or("window without an id")?;
+    client.send_command(&serde_json::json!({"type": "DestroyWindow", "id": second}))?;
+    client.wait_for_window_count(1, "after closing the second window")?;
+    wait_for_borders(&client, "first", &[])?;
+
+    Ok(())
+}
+
+#[test]
+fn test_client_side_decorated_windows_get_no_border() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("csd-borders");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/edge_borders.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    create_decorated_window(&client, "server")?;
+    client.create_window(Some("client"), None)?;
+    client.wait_for_window_count(2, "after creating both windows")?;
+
+    // The server-side window borders its inner edge, the one drawing its
+    // own frame is left alone
+    let server = client.find_window("server")?;
+    let expected = if server["x"].as_i64() == Some(0) {
+        [1916, 0, 4, 2160]
+    } else {
+        [0, 0, 4, 2160]
+    };
+    wait_for_borders(&client, "server", &[expected])?;
+    assert!(borders(&client.find_window("client")?).is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! Test that window rules see the app_id and title of real windows
+
+mod common;
+
+use common::{TestClient, TestEnv};
+
+#[test]
+fn test_assign_matches_window_properties() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("window-criteria");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/criteria.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    // Matches the escaped title and the app_id pattern
+    let mut draft = env.start_window("Mail (draft)", Some("blue"))?;
+    client.wait_for_window_count(1, "after starting the draft window")?;
+    // Patterns match the whole title
+    let mut reply = env.start_window("Mail (draft) reply", Some("green"))?;
+    client.wait_for_window_count(2, "after starting the reply window")?;
+    // The parentheses are literal, not a group
+    let mut plain = env.start_window("Mail draft", Some("red"))?;
+    client.wait_for_window_count(3, "after starting the plain window")?;
+
+    let workspace = |title: &str| -> Result<Option<u64>, Box<dyn std::error::Error>> {
+        Ok(client.find_window(title)?["workspace"].as_u64())
+    };
+    assert_eq!(workspace("Mail (draft)")?, Some(3));
+    assert_eq!(workspace("Mail (draft) reply")?, Some(1));
+    assert_eq!(workspace("Mail draft")?, Some(1));
+
+    draft.kill()?;
+    reply.kill()?;
+    plain.kill()?;
+    Ok(())
+}
+
+#[test]
+fn test_no_focus_matches_the_committed_app_id() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("no-focus-app-id");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/criteria.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let main = client.create_window(Some("Main"), None)?;
+    client.wait_for_focus(main, "after creating the main window")?;
+
+    // The rule needs the app_id, which only arrives with the first commit
+    let mut quiet = env.start_window("Quiet", Some("blue"))?;
+    client.wait_for_window_count(2, "after starting the quiet window")?;
+    for _ in 0..5 {
+        client.sync()?;
+    }
+    assert_eq!(client.get_focused_window()?, Some(main));
+    assert_eq!(client.find_window("Quiet")?["focused"], false);
+
+    // Windows no rule matches still take focus
+    let mut loud = env.start_window("Loud", Some("red"))?;
+    client.wait_for_window_count(3, "after starting the loud window")?;
+    let loud_id = client.find_window("Loud")?["id"]
+        .as_u64()
+        .ok_or("window has no id")?;
+    client.wait_for_focus(loud_id, "after starting the loud window")?;
+
+    quiet.kill()?;
+    loud.kill()?;
+    Ok(())
+}
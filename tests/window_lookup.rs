@@ -0,0 +1,42 @@
+mod common;
+
+use common::{verify_window_geometry, TestClient, TestEnv};
+
+#[test]
+fn test_find_and_get_window() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("window-lookup");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    client.create_window(Some("left"), Some("red"))?;
+    let left = client.find_window("left")?;
+    client.create_window(Some("right"), Some("blue"))?;
+    let right = client.find_window("right")?;
+
+    let left_id = left["id"].as_u64().ok_or("Window has no id")?;
+    let right_id = right["id"].as_u64().ok_or("Window has no id")?;
+    assert_ne!(left_id, right_id);
+
+    // GetWindow reflects the relayout that came with the second window
+    let left = client.get_window(left_id)?;
+    assert_eq!(left["title"], "left");
+    verify_window_geometry(&left, 0, 0, 1920, 2160)?;
+    let right = client.get_window(right_id)?;
+    assert_eq!(right["title"], "right");
+    verify_window_geometry(&right, 1920, 0, 1920, 2160)?;
+
+    assert!(client.get_window(right_id + 100).is_err());
+    let response =
+        client.send_command(&serde_json::json!({"type": "FindWindow", "title": "missing"}))?;
+    assert_eq!(response["type"], "Error");
+
+    Ok(())
+}
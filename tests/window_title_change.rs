@@ -0,0 +1,55 @@
+//! Test that title changes are announced on the IPC event stream
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_title_change_reaches_ipc() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("window-title-change");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let ipc = UnixStream::connect("/tmp/stilch-ipc-window-title-change.sock")?;
+    ipc.set_read_timeout(Some(Duration::from_millis(200)))?;
+    let mut ipc = BufReader::new(ipc);
+    // Give the server a moment to subscribe the connection
+    std::thread::sleep(Duration::from_millis(200));
+
+    let mut _window = env.start_window_with_args("Before", "blue", &["--retitle", "After"])?;
+    client.wait_for_window_count(1, "after starting the window")?;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let window_id = loop {
+        if Instant::now() >= deadline {
+            return Err("no title_changed event on the IPC stream".into());
+        }
+        let mut line = String::new();
+        if ipc.read_line(&mut line).unwrap_or(0) == 0 {
+            continue;
+        }
+        let message: serde_json::Value = serde_json::from_str(&line)?;
+        if message["type"] == "title_changed" {
+            assert_eq!(message["title"], "After");
+            break message["window_id"]
+                .as_u64()
+                .ok_or("event has no window_id")?;
+        }
+    };
+
+    let window = client.find_window("After")?;
+    assert_eq!(window["id"].as_u64(), Some(window_id));
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+// Tests for walking workspaces in their configured order
+
+use smithay::utils::Rectangle;
+use stilch::virtual_output::VirtualOutputId;
+use stilch::workspace::{WorkspaceId, WorkspaceManager};
+
+fn ids(numbers: &[u8]) -> Vec<WorkspaceId> {
+    numbers.iter().map(|&n| WorkspaceId::new(n)).collect()
+}
+
+#[test]
+fn test_default_order_is_numeric() {
+    let manager = WorkspaceManager::new(0);
+    assert_eq!(manager.order(), ids(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+    assert_eq!(
+        manager.next_in_order(WorkspaceId::new(0)),
+        Some(WorkspaceId::new(1))
+    );
+}
+
+#[test]
+fn test_set_order_fills_in_missing_workspaces() {
+    let mut manager = WorkspaceManager::new(0);
+    // Unknown and repeated ids are dropped
+    manager.set_order(ids(&[4, 2, 4, 42, 7]));
+    assert_eq!(manager.order(), ids(&[4, 2, 7, 0, 1, 3, 5, 6, 8, 9]));
+
+    let shown: Vec<WorkspaceId> = manager.ordered_workspaces().map(|ws| ws.id).collect();
+    assert_eq!(shown, manager.order());
+}
+
+#[test]
+fn test_next_and_previous_follow_order_and_wrap() {
+    let mut manager = WorkspaceManager::new(0);
+    manager.set_order(ids(&[9, 0, 5]));
+
+    assert_eq!(
+        manager.next_in_order(WorkspaceId::new(9)),
+        Some(WorkspaceId::new(0))
+    );
+    assert_eq!(
+        manager.next_in_order(WorkspaceId::new(0)),
+        Some(WorkspaceId::new(5))
+    );
+    assert_eq!(
+        manager.previous_in_order(WorkspaceId::new(5)),
+        Some(WorkspaceId::new(0))
+    );
+    // The last workspace in the order wraps to the first and back
+    assert_eq!(
+        manager.next_in_order(WorkspaceId::new(8)),
+        Some(WorkspaceId::new(9))
+    );
+    assert_eq!(
+        manager.previous_in_order(WorkspaceId::new(9)),
+        Some(WorkspaceId::new(8))
+    );
+    assert_eq!(manager.next_in_order(WorkspaceId::new(42)), None);
+}
+
+#[test]
+fn test_next_and_previous_on_output_skip_other_outputs() {
+    let mut manager = WorkspaceManager::new(0);
+    let left = VirtualOutputId::new(1);
+    let right = VirtualOutputId::new(2);
+    let area = Rectangle::from_size((1920, 1080).into());
+    // Keep the empty workspaces around after switching away
+    manager.set_persistent(ids(&[0, 1, 2, 3]));
+
+    // Workspaces 1, 2 and 4 have been on the left output, 3 is on the right
+    for (workspace, output) in [(0, left), (1, left), (2, right), (3, left)] {
+        manager
+            .show_workspace_on_output(WorkspaceId::new(workspace), output, area)
+            .unwrap();
+    }
+
+    assert_eq!(
+        manager.next_on_output(WorkspaceId::new(0), left),
+        Some(WorkspaceId::new(1))
+    );
+    assert_eq!(
+        manager.next_on_output(WorkspaceId::new(1), left),
+        Some(WorkspaceId::new(3))
+    );
+    // Wraps within the output's workspaces
+    assert_eq!(
+        manager.next_on_output(WorkspaceId::new(3), left),
+        Some(WorkspaceId::new(0))
+    );
+    assert_eq!(
+        manager.previous_on_output(WorkspaceId::new(0), left),
+        Some(WorkspaceId::new(3))
+    );
+    // A lone workspace cycles to itself
+    assert_eq!(
+        manager.next_on_output(WorkspaceId::new(2), right),
+        Some(WorkspaceId::new(2))
+    );
+    assert_eq!(manager.next_on_output(WorkspaceId::new(2), left), None);
+
+    // The global order still visits every workspace
+    assert_eq!(
+        manager.next_in_order(WorkspaceId::new(1)),
+        Some(WorkspaceId::new(2))
+    );
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+
+fn workspace(client: &TestClient, id: u64) -> Result<Value, Box<dyn std::error::Error>> {
+    client
+        .get_workspaces()?
+        .into_iter()
+        .find(|ws| ws["id"].as_u64() == Some(id))
+        .ok_or_else(|| format!("Workspace {id} not listed").into())
+}
+
+#[test]
+fn test_workspace_follows_assigned_output_when_plugged_in() -> Result<(), Box<dyn std::error::Error>>
+{
+    let mut env = TestEnv::new("workspace-output-assignment");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/workspace_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    assert_eq!(client.get_outputs()?.len(), 1);
+
+    // HOTPLUG-1 isn't there yet, so workspace 5 opens on the primary output
+    client.switch_workspace(4)?;
+    let mut window = env.start_window("Assigned", Some("blue"))?;
+    client.wait_for_window_count(1, "after window on workspace 5")?;
+
+    let ws5 = workspace(&client, 5)?;
+    assert_eq!(ws5["output"].as_str(), Some("output-1"));
+    assert_eq!(ws5["window_count"].as_u64(), Some(1));
+
+    client.add_output("HOTPLUG-1", 1920, 0, 1920, 1080)?;
+    client.sync()?;
+
+    // Now it moves to the output it is assigned to, window and all
+    let ws5 = workspace(&client, 5)?;
+    assert_eq!(ws5["output"].as_str(), Some("output-2"));
+    assert_eq!(ws5["visible"].as_bool(), Some(true));
+    assert_eq!(ws5["window_count"].as_u64(), Some(1));
+
+    // The primary output is left showing another workspace
+    let workspaces = client.get_workspaces()?;
+    assert!(workspaces
+        .iter()
+        .any(|ws| ws["output"].as_str() == Some("output-1")));
+
+    let windows = client.get_windows()?;
+    let x = windows[0]["x"].as_i64().unwrap();
+    assert!(x >= 1920, "Window should be on the new output, x = {x}");
+
+    // Switching to it from the primary output lands on the new output
+    client.switch_workspace(0)?;
+    client.switch_workspace(4)?;
+    assert_eq!(workspace(&client, 5)?["output"].as_str(), Some("output-2"));
+
+    window.kill()?;
+    Ok(())
+}
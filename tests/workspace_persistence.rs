@@ -0,0 +1,71 @@
+// Tests for empty workspaces going away unless they are persistent
+
+use smithay::utils::Rectangle;
+use stilch::virtual_output::VirtualOutputId;
+use stilch::window::WindowId;
+use stilch::workspace::{WorkspaceId, WorkspaceManager};
+
+fn show(manager: &mut WorkspaceManager, workspace: u8, output: VirtualOutputId) {
+    manager
+        .show_workspace_on_output(
+            WorkspaceId::new(workspace),
+            output,
+            Rectangle::from_size((1920, 1080).into()),
+        )
+        .unwrap();
+}
+
+#[test]
+fn test_empty_workspace_is_discarded_when_switched_away() {
+    let mut manager = WorkspaceManager::new(0);
+    let output = VirtualOutputId::new(1);
+
+    show(&mut manager, 0, output);
+    assert!(manager.exists(WorkspaceId::new(0)));
+    assert!(!manager.exists(WorkspaceId::new(1)));
+
+    show(&mut manager, 1, output);
+    assert!(!manager.exists(WorkspaceId::new(0)));
+    // Gone, so it no longer belongs to the output either
+    assert_eq!(manager.workspace_association(WorkspaceId::new(0)), None);
+    assert!(manager.exists(WorkspaceId::new(1)));
+}
+
+#[test]
+fn test_workspace_with_windows_is_kept() {
+    let mut manager = WorkspaceManager::new(0);
+    let output = VirtualOutputId::new(1);
+    let window = WindowId::new(1);
+
+    show(&mut manager, 0, output);
+    manager.add_window_to_workspace(window, WorkspaceId::new(0));
+    show(&mut manager, 1, output);
+
+    assert!(manager.exists(WorkspaceId::new(0)));
+    assert_eq!(
+        manager.workspace_association(WorkspaceId::new(0)),
+        Some(output)
+    );
+
+    // Once its last window goes, it doesn't exist any more
+    manager.remove_window_from_workspace(window, WorkspaceId::new(0));
+    assert!(!manager.exists(WorkspaceId::new(0)));
+}
+
+#[test]
+fn test_persistent_workspace_survives_empty() {
+    let mut manager = WorkspaceManager::new(0);
+    let output = VirtualOutputId::new(1);
+    manager.set_persistent([WorkspaceId::new(0), WorkspaceId::new(4)]);
+
+    // Listed before it was ever shown
+    assert!(manager.exists(WorkspaceId::new(4)));
+
+    show(&mut manager, 0, output);
+    show(&mut manager, 1, output);
+    assert!(manager.exists(WorkspaceId::new(0)));
+    assert_eq!(
+        manager.workspace_association(WorkspaceId::new(0)),
+        Some(output)
+    );
+}
@@ -0,0 +1,120 @@
+// Tests for reading, writing and merging the workspace state file
+
+use stilch::state::workspace_state::{SavedOutput, SavedWorkspace, WorkspaceStateFile};
+
+fn output(name: &str, make: &str, model: &str) -> SavedOutput {
+    SavedOutput {
+        name: name.to_string(),
+        make: make.to_string(),
+        model: model.to_string(),
+    }
+}
+
+fn saved(number: u8, output: SavedOutput, visible: bool) -> SavedWorkspace {
+    SavedWorkspace {
+        number,
+        output,
+        visible,
+    }
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("stilch-workspace-state-{}", std::process::id()))
+        .join(name)
+}
+
+#[test]
+fn test_state_file_round_trips() {
+    let path = temp_path("round-trip.json");
+    let state = WorkspaceStateFile {
+        workspaces: vec![
+            saved(1, output("DP-1", "Dell", "U2720Q"), true),
+            saved(4, output("HDMI-A-1", "", ""), false),
+        ],
+    };
+
+    state.save(&path).unwrap();
+    assert_eq!(WorkspaceStateFile::load(&path), state);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_missing_or_corrupt_state_file_is_empty() {
+    assert_eq!(
+        WorkspaceStateFile::load(&temp_path("missing.json")),
+        WorkspaceStateFile::default()
+    );
+
+    let path = temp_path("corrupt.json");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "{ \"workspaces\": [").unwrap();
+    assert_eq!(
+        WorkspaceStateFile::load(&path),
+        WorkspaceStateFile::default()
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_update_keeps_workspaces_of_disconnected_outputs() {
+    let state = WorkspaceStateFile {
+        workspaces: vec![
+            saved(1, output("DP-1", "", ""), true),
+            saved(2, output("DP-2", "", ""), true),
+            saved(3, output("DP-1", "", ""), false),
+        ],
+    };
+
+    // DP-2 is unplugged and workspace 3 went away on DP-1
+    let updated = state.updated_with(vec![saved(1, output("DP-1", "", ""), true)], |output| {
+        output.name == "DP-1"
+    });
+
+    assert_eq!(
+        updated.workspaces,
+        vec![
+            saved(1, output("DP-1", "", ""), true),
+            saved(2, output("DP-2", "", ""), true),
+        ]
+    );
+}
+
+#[test]
+fn test_live_workspaces_replace_saved_ones() {
+    let state = WorkspaceStateFile {
+        workspaces: vec![saved(2, output("DP-2", "", ""), true)],
+    };
+
+    let updated = state.updated_with(vec![saved(2, output("DP-1", "", ""), false)], |_| false);
+
+    assert_eq!(
+        updated.workspaces,
+        vec![saved(2, output("DP-1", "", ""), false)]
+    );
+}
+
+#[test]
+fn test_outputs_match_by_name_then_model() {
+    let state = WorkspaceStateFile {
+        workspaces: vec![
+            saved(1, output("DP-1", "Dell", "U2720Q"), true),
+            saved(2, output("DP-2", "LG", "27GL850"), true),
+            saved(3, output("DP-3", "LG", "27GL850"), true),
+        ],
+    };
+
+    // The LG monitor came back as DP-4 while DP-3 is still connected
+    let lg = output("DP-4", "LG", "27GL850");
+    let numbers: Vec<u8> = state
+        .for_output(&lg, |name| name == "DP-3" || name == "DP-4")
+        .map(|saved| saved.number)
+        .collect();
+    assert_eq!(numbers, vec![2]);
+
+    // Outputs without make and model only match by name
+    let unknown = output("DP-5", "", "");
+    assert_eq!(state.for_output(&unknown, |_| false).count(), 0);
+}
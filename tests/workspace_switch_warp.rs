@@ -0,0 +1,55 @@
+//! Test that only workspace switches the user asks for move the pointer
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use serde_json::Value;
+
+fn get_cursor_position(client: &TestClient) -> Result<(f64, f64), Box<dyn std::error::Error>> {
+    let response = client.send_command(&serde_json::json!({"type": "GetCursorPosition"}))?;
+    let message = response["message"]
+        .as_str()
+        .ok_or("GetCursorPosition replied without a position")?;
+    let data: Value = serde_json::from_str(message)?;
+    let x = data["data"]["x"]
+        .as_f64()
+        .ok_or("Cursor position has no x")?;
+    let y = data["data"]["y"]
+        .as_f64()
+        .ok_or("Cursor position has no y")?;
+    Ok((x, y))
+}
+
+#[test]
+fn test_hotplug_leaves_the_pointer_alone() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("switch-warp-hotplug");
+    env.cleanup()?;
+    env.start_compositor(&[
+        "--test",
+        "--config",
+        "tests/test_configs/workspace_outputs.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    client.sync()?;
+    let before = get_cursor_position(&client)?;
+
+    // The new output shows workspace 5, assigned to it in the config,
+    // without pulling the pointer over
+    client.add_output("HOTPLUG-1", 3840, 0, 1920, 1080)?;
+    client.sync()?;
+    let workspace = client
+        .get_workspaces()?
+        .into_iter()
+        .find(|ws| ws["name"] == "5")
+        .ok_or("No workspace 5")?;
+    assert_eq!(workspace["visible"], true);
+    assert_eq!(get_cursor_position(&client)?, before);
+
+    // Switching to it from the first output does, to the output it is on
+    client.switch_workspace(4)?;
+    client.sync()?;
+    assert_eq!(get_cursor_position(&client)?, (4800.0, 540.0));
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{TestClient, TestEnv};
+
+#[test]
+fn test_workspace_thumbnail_scales_layout() -> Result<(), Box<dyn std::error::Error>> {
+    let mut env = TestEnv::new("workspace-thumbnail");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+
+    let mut window1 = env.start_window("Left", Some("red"))?;
+    client.wait_for_window_count(1, "after first window")?;
+    let mut window2 = env.start_window("Right", Some("blue"))?;
+    client.wait_for_window_count(2, "after second window")?;
+
+    let thumbnail = client.get_workspace_thumbnail(0, 160, 160)?;
+    println!("Thumbnail: {thumbnail:#}");
+
+    let width = thumbnail["width"].as_i64().unwrap();
+    let height = thumbnail["height"].as_i64().unwrap();
+    assert!(width <= 160 && height <= 160);
+    assert!(
+        width == 160 || height == 160,
+        "Thumbnail should fill the requested size along one axis"
+    );
+
+    let windows = thumbnail["windows"].as_array().unwrap();
+    assert_eq!(
+        windows.len(),
+        2,
+        "Both tiled windows should be in the thumbnail"
+    );
+    for window in windows {
+        let x = window["x"].as_i64().unwrap();
+        let w = window["width"].as_i64().unwrap();
+        assert!(x >= 0 && x + w <= width, "Window should fit the thumbnail");
+    }
+
+    // The two windows split the workspace side by side
+    let left = windows
+        .iter()
+        .find(|w| w["id"].as_u64() == Some(1))
+        .unwrap();
+    let right = windows
+        .iter()
+        .find(|w| w["id"].as_u64() == Some(2))
+        .unwrap();
+    assert!(left["x"].as_i64() < right["x"].as_i64());
+    assert!(right["focused"].as_bool().unwrap());
+    assert!(!left["focused"].as_bool().unwrap());
+
+    // An empty workspace has no windows
+    let empty = client.get_workspace_thumbnail(4, 160, 90)?;
+    assert!(empty["windows"].as_array().unwrap().is_empty());
+
+    window1.kill()?;
+    window2.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Test that X11 windows assigned to a hidden workspace are still laid out
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::time::{Duration, Instant};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, CreateWindowAux, PropMode, WindowClass};
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::COPY_DEPTH_FROM_PARENT;
+
+fn find_in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+#[test]
+fn test_x11_window_assigned_to_hidden_workspace() -> Result<(), Box<dyn std::error::Error>> {
+    if !find_in_path("Xwayland") {
+        println!("Skipping: Xwayland is not installed");
+        return Ok(());
+    }
+
+    let mut env = TestEnv::new("xwayland-assign");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--xwayland",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/criteria.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let display_file = std::env::temp_dir().join("stilch-test-xwayland-assign-display");
+    let _ = std::fs::remove_file(&display_file);
+    client.send_command(&serde_json::json!({
+        "type": "Exec",
+        "command": format!("echo \"$DISPLAY\" > {}", display_file.display()),
+    }))?;
+
+    // XWayland takes a while to come up
+    let deadline = Instant::now() + Duration::from_secs(15);
+    let display = loop {
+        let display = std::fs::read_to_string(&display_file).unwrap_or_default();
+        if display.trim().starts_with(':') {
+            break display.trim().to_string();
+        }
+        if Instant::now() >= deadline {
+            return Err("XWayland never came up".into());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+    let _ = std::fs::remove_file(&display_file);
+
+    let (conn, screen_num) = x11rb::connect(Some(&display))?;
+    let screen = &conn.setup().roots[screen_num];
+
+    // The config assigns this class to workspace 4, which isn't shown
+    let app = conn.generate_id()?;
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        app,
+        screen.root,
+        0,
+        0,
+        300,
+        200,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        0,
+        &CreateWindowAux::new().background_pixel(screen.white_pixel),
+    )?;
+    conn.change_property8(
+        PropMode::REPLACE,
+        app,
+        AtomEnum::WM_CLASS,
+        AtomEnum::STRING,
+        b"stilch-test\0StilchAssigned\0",
+    )?;
+    conn.map_window(app)?;
+    conn.flush()?;
+    client.wait_for_window_count(1, "after mapping the X11 window")?;
+
+    let window = &client.get_windows()?[0];
+    assert_eq!(window["workspace"], 4);
+    assert_eq!(window["visible"], false);
+
+    // It is sized for the workspace it will show on, not left at its
+    // requested 300x200
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let geometry = conn.get_geometry(app)?.reply()?;
+        if (geometry.width, geometry.height) == (3840, 2160) {
+            break;
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "X11 window was never configured, still {}x{}",
+                geometry.width, geometry.height
+            )
+            .into());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    // Showing the workspace keeps that size
+    client.switch_workspace(3)?;
+    client.sync()?;
+    let window = &client.get_windows()?[0];
+    assert_eq!(window["visible"], true);
+    assert_eq!(window["width"], 3840);
+    assert_eq!(window["height"], 2160);
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! Test that programs started by an `exec` binding reach XWayland
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::time::{Duration, Instant};
+
+/// X11-only programs that open a window, the first one installed is used
+const X11_APPS: &[&str] = &["xeyes", "xclock", "xlogo"];
+
+fn find_in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+#[test]
+fn test_exec_binding_starts_x11_app() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = X11_APPS.iter().find(|app| find_in_path(app)) else {
+        println!("Skipping: none of {X11_APPS:?} is installed");
+        return Ok(());
+    };
+    if !find_in_path("Xwayland") {
+        println!("Skipping: Xwayland is not installed");
+        return Ok(());
+    }
+
+    let mut env = TestEnv::new("xwayland-exec");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--xwayland",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let display_file = std::env::temp_dir().join("stilch-test-xwayland-exec-display");
+    let _ = std::fs::remove_file(&display_file);
+
+    // Sent right away, most likely before XWayland is ready, so these are
+    // held back until DISPLAY is known
+    client.send_command(&serde_json::json!({
+        "type": "Exec",
+        "command": format!("echo \"$DISPLAY\" > {}", display_file.display()),
+    }))?;
+    client.send_command(&serde_json::json!({"type": "Exec", "command": app}))?;
+
+    // XWayland takes a while to come up
+    let deadline = Instant::now() + Duration::from_secs(15);
+    while client.get_windows()?.is_empty() {
+        if Instant::now() >= deadline {
+            return Err(format!("{app} never mapped a window").into());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    client.wait_for_window_count(1, &format!("after starting {app}"))?;
+
+    let display = std::fs::read_to_string(&display_file)?;
+    let _ = std::fs::remove_file(&display_file);
+    assert!(
+        display.trim().starts_with(':'),
+        "exec should get DISPLAY, got {display:?}"
+    );
+
+    Ok(())
+}
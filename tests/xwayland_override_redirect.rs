@@ -0,0 +1,129 @@
+//! Test that X11 menus and tooltips (override-redirect windows) stay unmanaged
+
+mod common;
+
+use common::{TestClient, TestEnv};
+use std::time::{Duration, Instant};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{ConnectionExt, CreateWindowAux, WindowClass};
+use x11rb::COPY_DEPTH_FROM_PARENT;
+
+fn find_in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+#[test]
+fn test_override_redirect_window_is_not_managed() -> Result<(), Box<dyn std::error::Error>> {
+    if !find_in_path("Xwayland") {
+        println!("Skipping: Xwayland is not installed");
+        return Ok(());
+    }
+
+    let mut env = TestEnv::new("xwayland-override-redirect");
+    env.cleanup()?;
+
+    env.start_compositor(&[
+        "--test",
+        "--xwayland",
+        "--ascii-size",
+        "80x24",
+        "--config",
+        "tests/test_configs/no_gaps.conf",
+    ])?;
+
+    let client = TestClient::new(&env.test_socket);
+    let display_file = std::env::temp_dir().join("stilch-test-xwayland-or-display");
+    let _ = std::fs::remove_file(&display_file);
+    client.send_command(&serde_json::json!({
+        "type": "Exec",
+        "command": format!("echo \"$DISPLAY\" > {}", display_file.display()),
+    }))?;
+
+    // XWayland takes a while to come up
+    let deadline = Instant::now() + Duration::from_secs(15);
+    let display = loop {
+        let display = std::fs::read_to_string(&display_file).unwrap_or_default();
+        if display.trim().starts_with(':') {
+            break display.trim().to_string();
+        }
+        if Instant::now() >= deadline {
+            return Err("XWayland never came up".into());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+    let _ = std::fs::remove_file(&display_file);
+
+    let (conn, screen_num) = x11rb::connect(Some(&display))?;
+    let screen = &conn.setup().roots[screen_num];
+
+    // The application window
+    let app = conn.generate_id()?;
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        app,
+        screen.root,
+        0,
+        0,
+        300,
+        200,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        0,
+        &CreateWindowAux::new().background_pixel(screen.white_pixel),
+    )?;
+    conn.map_window(app)?;
+    conn.flush()?;
+    client.wait_for_window_count(1, "after mapping the X11 window")?;
+    let app_id = client.get_windows()?[0]["id"]
+        .as_u64()
+        .ok_or("X11 window has no id")?;
+    client.focus_window(app_id)?;
+
+    // A context menu it opens
+    let menu = conn.generate_id()?;
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        menu,
+        screen.root,
+        40,
+        50,
+        120,
+        80,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        0,
+        &CreateWindowAux::new()
+            .override_redirect(1)
+            .background_pixel(screen.black_pixel),
+    )?;
+    conn.map_window(menu)?;
+    conn.sync()?;
+    std::thread::sleep(Duration::from_millis(500));
+    client.sync()?;
+
+    assert_eq!(
+        client.get_windows()?.len(),
+        1,
+        "the menu must not be tiled as a window"
+    );
+
+    // Clicking the menu leaves the keyboard with the application
+    client.click_at(60, 70)?;
+    client.sync()?;
+    assert_eq!(
+        client.get_focused_window()?,
+        Some(app_id),
+        "clicking the menu must not move focus"
+    );
+
+    // Closing the menu doesn't disturb the application window
+    conn.unmap_window(menu)?;
+    conn.sync()?;
+    std::thread::sleep(Duration::from_millis(200));
+    client.sync()?;
+    assert_eq!(client.get_windows()?.len(), 1);
+    assert_eq!(client.get_focused_window()?, Some(app_id));
+
+    Ok(())
+}